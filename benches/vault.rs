@@ -0,0 +1,108 @@
+//! Local benchmarks of the vault's serialization and deposit lookup hot paths
+//!
+//! `cargo bench` measures them with criterion. `cargo bench -- --test` runs
+//! every benchmark once instead, which with the regression thresholds below
+//! makes a quick CI check.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+use std::time::{Duration, Instant};
+use time_locked_vault::{
+    test_utils::{synthetic_vault, SYNTHETIC_VAULT_SIZES},
+    Deposit, Vault,
+};
+
+// Loose per-deposit ceilings, far above what any build should need, so only
+// a regression by an order of magnitude or more fails
+const MAX_DESERIALIZE_PER_DEPOSIT: Duration = Duration::from_micros(50);
+const MAX_SERIALIZE_PER_DEPOSIT: Duration = Duration::from_micros(50);
+const MAX_LINEAR_LOOKUP_PER_DEPOSIT: Duration = Duration::from_micros(1);
+const MAX_BINARY_LOOKUP: Duration = Duration::from_micros(20);
+
+// The lookups the handlers do today: a scan for the deposit's ID
+fn find_deposit_linear(deposits: &[Deposit], id: u64) -> Option<usize> {
+    deposits.iter().position(|deposit| deposit.id == id)
+}
+
+// Deposits are appended in ID order and only ever removed, so their IDs stay
+// sorted and a binary search finds the same index
+fn find_deposit_binary(deposits: &[Deposit], id: u64) -> Option<usize> {
+    deposits.binary_search_by_key(&id, |deposit| deposit.id).ok()
+}
+
+fn bench_serialization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("vault");
+    for &size in SYNTHETIC_VAULT_SIZES {
+        let vault = synthetic_vault(size);
+        let data = vault.try_to_vec().unwrap();
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_with_input(BenchmarkId::new("try_from_slice", size), &data, |b, data| {
+            b.iter(|| Vault::try_from_slice(black_box(data)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("try_to_vec", size), &vault, |b, vault| {
+            b.iter(|| black_box(vault).try_to_vec().unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_deposit");
+    for &size in SYNTHETIC_VAULT_SIZES {
+        let vault = synthetic_vault(size);
+        // The newest deposit, the linear scan's worst case
+        let id = size as u64 - 1;
+        group.bench_with_input(BenchmarkId::new("linear", size), &vault.deposits, |b, deposits| {
+            b.iter(|| find_deposit_linear(black_box(deposits), black_box(id)))
+        });
+        group.bench_with_input(BenchmarkId::new("binary", size), &vault.deposits, |b, deposits| {
+            b.iter(|| find_deposit_binary(black_box(deposits), black_box(id)))
+        });
+    }
+    group.finish();
+}
+
+// Average time of `iterations` runs of `f`
+fn average<T>(iterations: u32, mut f: impl FnMut() -> T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(f());
+    }
+    start.elapsed() / iterations
+}
+
+// Fail if the largest synthetic vault blows through the ceilings above
+fn check_regression_thresholds() {
+    let size = *SYNTHETIC_VAULT_SIZES.last().unwrap();
+    let vault = synthetic_vault(size);
+    let data = vault.try_to_vec().unwrap();
+    let id = size as u64 - 1;
+    assert_eq!(find_deposit_linear(&vault.deposits, id), find_deposit_binary(&vault.deposits, id));
+
+    let deposits = size as u32;
+    let checks = [
+        (
+            "try_from_slice",
+            average(10, || Vault::try_from_slice(&data).unwrap()),
+            MAX_DESERIALIZE_PER_DEPOSIT * deposits,
+        ),
+        ("try_to_vec", average(10, || vault.try_to_vec().unwrap()), MAX_SERIALIZE_PER_DEPOSIT * deposits),
+        (
+            "linear lookup",
+            average(100, || find_deposit_linear(&vault.deposits, id)),
+            MAX_LINEAR_LOOKUP_PER_DEPOSIT * deposits,
+        ),
+        ("binary lookup", average(100, || find_deposit_binary(&vault.deposits, id)), MAX_BINARY_LOOKUP),
+    ];
+    for (name, elapsed, ceiling) in checks {
+        assert!(elapsed <= ceiling, "{} of {} deposits took {:?}, over {:?}", name, size, elapsed, ceiling);
+    }
+}
+
+fn main() {
+    check_regression_thresholds();
+    let mut criterion = Criterion::default().configure_from_args();
+    bench_serialization(&mut criterion);
+    bench_lookup(&mut criterion);
+    criterion.final_summary();
+}
@@ -0,0 +1,169 @@
+//! Anchor compatibility layer for programs calling the vault (enabled with the
+//! `anchor` feature, which implies `no-entrypoint`)
+//!
+//! The vault program itself stays native: this module only lets Anchor
+//! programs hold the vault as `Account<'info, Vault>`, name the program as
+//! `Program<'info, TimeLockedVault>` and call it through `cpi`.
+
+use crate::{Vault, VaultError};
+use anchor_lang::{error::ErrorCode, AccountDeserialize, AccountSerialize, Id, Owner, Result};
+use borsh::BorshDeserialize;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+/// The vault program, for `Program<'info, TimeLockedVault>`
+#[derive(Clone)]
+pub struct TimeLockedVault;
+
+impl Id for TimeLockedVault {
+    fn id() -> Pubkey {
+        crate::id()
+    }
+}
+
+impl Owner for Vault {
+    fn owner() -> Pubkey {
+        crate::id()
+    }
+}
+
+// Vault accounts have no Anchor discriminator: as in the program's own
+// `load_vault`, zero-filled data reads as an uninitialized account
+impl AccountDeserialize for Vault {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if buf.iter().all(|byte| *byte == 0) {
+            return Err(ErrorCode::AccountNotInitialized.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        Vault::deserialize(buf).map_err(|_| anchor_lang::error!(ErrorCode::AccountDidNotDeserialize))
+    }
+}
+
+// Only the vault program writes vault accounts, so Anchor never persists one
+impl AccountSerialize for Vault {}
+
+impl From<VaultError> for anchor_lang::error::Error {
+    fn from(error: VaultError) -> Self {
+        ProgramError::from(error).into()
+    }
+}
+
+/// `CpiContext` wrappers around the instruction builders
+pub mod cpi {
+    use crate::builder;
+    use anchor_lang::{context::CpiContext, Result, ToAccountInfos, ToAccountMetas};
+    use solana_program::{
+        account_info::AccountInfo,
+        instruction::{AccountMeta, Instruction},
+        program::invoke_signed,
+    };
+
+    /// Account structs in the order the vault instructions take them
+    pub mod accounts {
+        use anchor_lang::{prelude::AccountInfo, Accounts};
+        
+        /// Accounts of `Deposit`
+        #[derive(Accounts)]
+        pub struct Deposit<'info> {
+            pub depositor: AccountInfo<'info>,
+            pub vault: AccountInfo<'info>,
+            pub source_token_account: AccountInfo<'info>,
+            pub vault_token_account: AccountInfo<'info>,
+            pub token_program: AccountInfo<'info>,
+            pub system_program: AccountInfo<'info>,
+            pub clock: AccountInfo<'info>,
+        }
+        
+        /// Accounts of `Withdraw`
+        #[derive(Accounts)]
+        pub struct Withdraw<'info> {
+            pub depositor: AccountInfo<'info>,
+            pub vault: AccountInfo<'info>,
+            pub destination_token_account: AccountInfo<'info>,
+            pub vault_token_account: AccountInfo<'info>,
+            pub token_program: AccountInfo<'info>,
+            pub clock: AccountInfo<'info>,
+        }
+    }
+
+    /// Deposit `amount` of the depositor's tokens until `unlock_time`
+    pub fn deposit<'info>(
+        ctx: CpiContext<'_, '_, '_, 'info, accounts::Deposit<'info>>,
+        amount: u64,
+        unlock_time: i64,
+        tag: [u8; 32],
+    ) -> Result<()> {
+        let instruction = builder::deposit(
+            ctx.program.key,
+            ctx.accounts.depositor.key,
+            ctx.accounts.vault.key,
+            ctx.accounts.source_token_account.key,
+            ctx.accounts.vault_token_account.key,
+            amount,
+            unlock_time,
+            tag,
+        );
+        let accounts = &ctx.accounts;
+        let infos = vec![
+            accounts.depositor.clone(),
+            accounts.vault.clone(),
+            accounts.source_token_account.clone(),
+            accounts.vault_token_account.clone(),
+            accounts.token_program.clone(),
+            accounts.system_program.clone(),
+            accounts.clock.clone(),
+        ];
+        invoke(instruction, infos, &ctx)
+    }
+
+    /// Withdraw deposit `deposit_id` to the destination token account
+    pub fn withdraw<'info>(
+        ctx: CpiContext<'_, '_, '_, 'info, accounts::Withdraw<'info>>,
+        deposit_id: u64,
+    ) -> Result<()> {
+        let instruction = builder::withdraw(
+            ctx.program.key,
+            ctx.accounts.depositor.key,
+            ctx.accounts.vault.key,
+            ctx.accounts.destination_token_account.key,
+            ctx.accounts.vault_token_account.key,
+            deposit_id,
+        );
+        let accounts = &ctx.accounts;
+        let infos = vec![
+            accounts.depositor.clone(),
+            accounts.vault.clone(),
+            accounts.destination_token_account.clone(),
+            accounts.vault_token_account.clone(),
+            accounts.token_program.clone(),
+            accounts.clock.clone(),
+        ];
+        invoke(instruction, infos, &ctx)
+    }
+
+    // Invoke `instruction` with the context's remaining accounts appended, for
+    // the optional trailing accounts (a memo, destination creation). Accounts
+    // that signed the caller's instruction keep signing
+    fn invoke<'info, T>(
+        mut instruction: Instruction,
+        mut infos: Vec<AccountInfo<'info>>,
+        ctx: &CpiContext<'_, '_, '_, 'info, T>,
+    ) -> Result<()>
+    where
+        T: ToAccountMetas + ToAccountInfos<'info>,
+    {
+        instruction.accounts.extend(ctx.remaining_accounts.iter().map(|info| AccountMeta {
+            pubkey: *info.key,
+            is_signer: info.is_signer,
+            is_writable: info.is_writable,
+        }));
+        infos.extend(ctx.remaining_accounts.iter().cloned());
+        for (meta, info) in instruction.accounts.iter_mut().zip(&infos) {
+            meta.is_signer |= info.is_signer;
+        }
+        infos.push(ctx.program.clone());
+        invoke_signed(&instruction, &infos, ctx.signer_seeds).map_err(Into::into)
+    }
+}
@@ -0,0 +1,1060 @@
+//! Instruction builders for clients
+
+use crate::{
+    check_deposit_amount, check_unlock_in_future, find_registry_address, find_stats_address, find_vault_address,
+    LockTier, VaultConfig, VaultError, VaultInstruction,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+    sysvar,
+};
+use std::time::Duration;
+
+// Account roles of each instruction, in account order. Optional trailing
+// accounts are listed where their position is fixed.
+
+/// Accounts of `CreateVault`
+pub const CREATE_VAULT_ROLES: &[&str] = &["owner", "vault", "system program", "registry", "payer"];
+
+/// Accounts of `Deposit` and the deposit variants that only change the unlock condition
+pub const DEPOSIT_ROLES: &[&str] = &[
+    "depositor",
+    "vault",
+    "source token account",
+    "vault token account",
+    "token program",
+    "system program",
+    "clock sysvar",
+];
+
+/// Accounts of `CreateVaultAndDeposit`
+pub const CREATE_VAULT_AND_DEPOSIT_ROLES: &[&str] = &[
+    "owner",
+    "payer",
+    "vault",
+    "source token account",
+    "vault token account",
+    "token program",
+    "system program",
+    "clock sysvar",
+];
+
+/// Accounts of `DonateToDeposit`
+pub const DONATE_TO_DEPOSIT_ROLES: &[&str] =
+    &["donor", "vault", "source token account", "vault token account", "token program"];
+
+/// Accounts of `CloseVaultTokenAccount`
+pub const CLOSE_VAULT_TOKEN_ACCOUNT_ROLES: &[&str] =
+    &["owner", "vault", "vault token account", "token program", "rent destination"];
+
+/// Accounts of `DepositFor`
+pub const DEPOSIT_FOR_ROLES: &[&str] = &[
+    "funder",
+    "payer",
+    "depositor",
+    "vault",
+    "source token account",
+    "vault token account",
+    "token program",
+    "system program",
+    "clock sysvar",
+];
+
+/// Accounts of `DepositUiAmount`
+pub const DEPOSIT_UI_AMOUNT_ROLES: &[&str] = &[
+    "token mint",
+    "depositor",
+    "vault",
+    "source token account",
+    "vault token account",
+    "token program",
+    "system program",
+    "clock sysvar",
+];
+
+/// Accounts of `DepositPair`
+pub const DEPOSIT_PAIR_ROLES: &[&str] = &[
+    "depositor",
+    "vault",
+    "first source token account",
+    "first vault token account",
+    "second source token account",
+    "second vault token account",
+    "token program",
+    "system program",
+    "clock sysvar",
+];
+
+/// Accounts of `Withdraw` and `ClaimWithdraw`
+pub const WITHDRAW_ROLES: &[&str] = &[
+    "depositor",
+    "vault",
+    "destination token account",
+    "vault token account",
+    "token program",
+    "clock sysvar",
+];
+
+/// Accounts of `WithdrawOnPrice`
+pub const WITHDRAW_ON_PRICE_ROLES: &[&str] = &[
+    "depositor",
+    "vault",
+    "destination token account",
+    "vault token account",
+    "token program",
+    "clock sysvar",
+    "price feed",
+];
+
+/// Accounts of `WithdrawOnCondition`
+pub const WITHDRAW_ON_CONDITION_ROLES: &[&str] = &[
+    "depositor",
+    "vault",
+    "destination token account",
+    "vault token account",
+    "token program",
+    "clock sysvar",
+    "condition account",
+];
+
+/// Accounts of `WithdrawByTag`, followed by a vault token account and
+/// destination token account pair per mint
+pub const WITHDRAW_BY_TAG_ROLES: &[&str] = &[
+    "depositor or beneficiary",
+    "vault",
+    "token program",
+    "clock sysvar",
+    "protocol config",
+];
+
+/// Accounts of `WithdrawSplit`, followed by one destination token account
+/// per split and optionally the vault owner
+pub const WITHDRAW_SPLIT_ROLES: &[&str] = &[
+    "depositor or beneficiary",
+    "vault",
+    "vault token account",
+    "token program",
+    "clock sysvar",
+    "protocol config",
+];
+
+/// Accounts of `ClaimWithPreimage`
+pub const CLAIM_WITH_PREIMAGE_ROLES: &[&str] = &[
+    "vault",
+    "beneficiary token account",
+    "vault token account",
+    "token program",
+];
+
+/// Accounts of `ReleaseEscrow`
+pub const RELEASE_ESCROW_ROLES: &[&str] = &[
+    "depositor",
+    "counterparty",
+    "vault",
+    "counterparty token account",
+    "vault token account",
+    "token program",
+];
+
+/// Accounts of `CloneVault`
+pub const CLONE_VAULT_ROLES: &[&str] = &["owner", "source vault", "clone vault", "system program"];
+
+/// Accounts of `EarlyWithdraw`
+pub const EARLY_WITHDRAW_ROLES: &[&str] = &[
+    "depositor",
+    "vault",
+    "destination token account",
+    "vault token account",
+    "token program",
+    "clock sysvar",
+    "treasury token account",
+];
+
+/// Accounts of `EmergencyWithdraw` and `EmergencyWithdrawAll`
+pub const EMERGENCY_WITHDRAW_ROLES: &[&str] = &[
+    "emergency authority",
+    "vault",
+    "destination token account",
+    "vault token account",
+    "token program",
+    "depositor",
+];
+
+/// Accounts of `WithdrawWithPermit`
+pub const PERMIT_WITHDRAW_ROLES: &[&str] = &[
+    "vault",
+    "destination token account",
+    "vault token account",
+    "token program",
+    "clock sysvar",
+    "instructions sysvar",
+];
+
+/// Accounts of `CrankWithdraw`
+pub const CRANK_WITHDRAW_ROLES: &[&str] = &[
+    "cranker",
+    "vault",
+    "depositor token account",
+    "vault token account",
+    "depositor",
+    "token mint",
+    "token program",
+    "system program",
+    "associated token program",
+    "clock sysvar",
+    "cranker token account",
+];
+
+/// Accounts of `ResolveFrozenDeposit`
+pub const RESOLVE_FROZEN_ROLES: &[&str] = &[
+    "emergency authority",
+    "vault",
+    "destination token account",
+    "vault token account",
+    "token program",
+];
+
+/// Accounts of the instructions fixing a vault token account
+pub const VAULT_TOKEN_FIX_ROLES: &[&str] = &["owner", "vault", "vault token account", "token program"];
+
+/// Accounts of `SweepSurplus`
+pub const SWEEP_SURPLUS_ROLES: &[&str] =
+    &["owner", "vault", "vault token account", "destination token account", "token program"];
+
+/// Accounts of `SweepLamports`
+pub const SWEEP_LAMPORTS_ROLES: &[&str] = &["owner", "vault", "destination"];
+
+/// Accounts of `GcDeposits`
+pub const GC_DEPOSITS_ROLES: &[&str] = &["caller", "vault", "clock sysvar"];
+
+/// Accounts of `CloseVault`
+pub const CLOSE_VAULT_ROLES: &[&str] = &["owner", "vault", "registry"];
+
+/// Accounts of the owner-only vault settings
+pub const OWNER_ROLES: &[&str] = &["owner", "vault"];
+
+/// Accounts of depositor-only deposit settings that don't read the clock
+pub const DEPOSITOR_ROLES: &[&str] = &["depositor", "vault"];
+
+/// Accounts of depositor-only deposit settings that read the clock
+pub const DEPOSITOR_CLOCK_ROLES: &[&str] = &["depositor", "vault", "clock sysvar"];
+
+/// Accounts of `ProposeRecovery` and `ApproveRecovery`
+pub const GUARDIAN_ROLES: &[&str] = &["guardian", "vault", "clock sysvar"];
+
+/// Accounts of `ExecuteRecovery`
+pub const EXECUTE_RECOVERY_ROLES: &[&str] = &["caller", "vault", "clock sysvar"];
+
+/// Accounts of `CancelWithdrawRequest`
+pub const CANCEL_WITHDRAW_ROLES: &[&str] = &["depositor or emergency authority", "vault"];
+
+/// Accounts of `CancelBeneficiaryChange`
+pub const CANCEL_BENEFICIARY_ROLES: &[&str] = &["beneficiary, owner or depositor", "vault"];
+
+/// Accounts of `FreezeDeposit` and `ThawDeposit`
+pub const FREEZE_ROLES: &[&str] = &["freeze authority", "vault"];
+
+/// Accounts of `InitializeConfig`
+pub const INITIALIZE_CONFIG_ROLES: &[&str] = &["admin", "config", "system program"];
+
+/// Accounts of `UpdateConfig` and `ProposeConfigAdmin`
+pub const CONFIG_ADMIN_ROLES: &[&str] = &["admin", "config"];
+
+/// Accounts of `AcceptConfigAdmin`
+pub const ACCEPT_CONFIG_ADMIN_ROLES: &[&str] = &["proposed admin", "config"];
+
+/// Accounts of `InitializeGlobalState`
+pub const INITIALIZE_GLOBAL_ROLES: &[&str] = &["authority", "global state", "system program"];
+
+/// Accounts of `SetGlobalHalt` and `ProposeGlobalAuthority`
+pub const GLOBAL_AUTHORITY_ROLES: &[&str] = &["authority", "global state"];
+
+/// Accounts of `AcceptGlobalAuthority`
+pub const ACCEPT_GLOBAL_AUTHORITY_ROLES: &[&str] = &["proposed authority", "global state"];
+
+/// Accounts of `WriteSnapshot`
+pub const WRITE_SNAPSHOT_ROLES: &[&str] = &["caller", "vault", "snapshot", "system program"];
+
+/// Accounts of `ReconcileStats`
+pub const RECONCILE_STATS_ROLES: &[&str] = &["caller", "vault", "program stats", "system program"];
+
+/// Accounts of `DepositStakedSol`
+pub const DEPOSIT_STAKED_SOL_ROLES: &[&str] = &[
+    "depositor",
+    "vault",
+    "stake account",
+    "stake authority",
+    "validator vote account",
+    "stake program",
+    "system program",
+    "clock sysvar",
+    "rent sysvar",
+    "stake history sysvar",
+    "stake config",
+];
+
+/// Accounts of `DeactivateStake`
+pub const DEACTIVATE_STAKE_ROLES: &[&str] = &[
+    "depositor or beneficiary",
+    "vault",
+    "stake account",
+    "stake authority",
+    "stake program",
+    "clock sysvar",
+];
+
+/// Accounts of `WithdrawStake`
+pub const WITHDRAW_STAKE_ROLES: &[&str] = &[
+    "depositor or beneficiary",
+    "vault",
+    "stake account",
+    "stake authority",
+    "stake program",
+    "clock sysvar",
+    "stake history sysvar",
+];
+
+/// Accounts of `AnnounceSweepAll`
+pub const ANNOUNCE_SWEEP_ALL_ROLES: &[&str] = &["emergency authority", "owner", "vault"];
+
+/// Accounts of `ExecuteSweepAll`
+pub const EXECUTE_SWEEP_ALL_ROLES: &[&str] = &[
+    "emergency authority",
+    "vault",
+    "vault token account",
+    "emergency destination token account",
+    "token program",
+];
+
+/// Accounts of `ReleaseEarly`
+pub const RELEASE_EARLY_ROLES: &[&str] = &[
+    "owner",
+    "vault",
+    "destination token account",
+    "vault token account",
+    "token program",
+];
+
+/// Accounts of `DistributeRewards`; the last three only start a distribution
+pub const DISTRIBUTE_REWARDS_ROLES: &[&str] = &[
+    "caller",
+    "vault",
+    "donor token account",
+    "vault token account",
+    "token program",
+];
+
+/// Accounts of `ClaimRewards`
+pub const CLAIM_REWARDS_ROLES: &[&str] = &[
+    "depositor",
+    "vault",
+    "destination token account",
+    "vault token account",
+    "token program",
+];
+
+/// Accounts of `ViewDeposit`
+pub const VIEW_DEPOSIT_ROLES: &[&str] = &["vault"];
+
+/// Accounts of `LogVotingPower`
+pub const LOG_VOTING_POWER_ROLES: &[&str] = &["vault", "clock sysvar"];
+
+/// Accounts of `GetVersion`
+pub const GET_VERSION_ROLES: &[&str] = &[];
+
+/// Accounts of `SetInsurance`
+pub const SET_INSURANCE_ROLES: &[&str] = &["owner", "vault", "insurance pool"];
+
+/// Accounts appended to `Withdraw` or `EmergencyWithdraw` to create a missing
+/// destination associated token account
+pub const DESTINATION_CREATION_ROLES: &[&str] = &["rent payer", "mint", "system program", "associated token program"];
+
+/// Account roles of `instruction`
+pub fn account_roles(instruction: &VaultInstruction) -> &'static [&'static str] {
+    use VaultInstruction::*;
+    match instruction {
+        CreateVault { .. } => CREATE_VAULT_ROLES,
+        CreateVaultAndDeposit { .. } => CREATE_VAULT_AND_DEPOSIT_ROLES,
+        DonateToDeposit { .. } => DONATE_TO_DEPOSIT_ROLES,
+        CloseVaultTokenAccount { .. } => CLOSE_VAULT_TOKEN_ACCOUNT_ROLES,
+        Deposit { .. }
+        | DepositWithDuration { .. }
+        | DepositTiered { .. }
+        | DepositUntilSlot { .. }
+        | DepositWithPriceCondition { .. }
+        | DepositWithAccountCondition { .. }
+        | DepositWithHashlock { .. }
+        | DepositWithGuardians { .. }
+        | DepositEscrow { .. }
+        | DepositMany { .. } => DEPOSIT_ROLES,
+        DepositFor { .. } => DEPOSIT_FOR_ROLES,
+        DepositUiAmount { .. } => DEPOSIT_UI_AMOUNT_ROLES,
+        DepositPair { .. } => DEPOSIT_PAIR_ROLES,
+        Withdraw { .. } | ClaimWithdraw { .. } | WithdrawWithMemo { .. } => WITHDRAW_ROLES,
+        WithdrawOnPrice { .. } => WITHDRAW_ON_PRICE_ROLES,
+        WithdrawOnCondition { .. } => WITHDRAW_ON_CONDITION_ROLES,
+        ClaimWithPreimage { .. } => CLAIM_WITH_PREIMAGE_ROLES,
+        ReleaseEscrow { .. } => RELEASE_ESCROW_ROLES,
+        CloneVault { .. } => CLONE_VAULT_ROLES,
+        WithdrawByTag { .. } => WITHDRAW_BY_TAG_ROLES,
+        WithdrawSplit { .. } => WITHDRAW_SPLIT_ROLES,
+        EarlyWithdraw { .. } => EARLY_WITHDRAW_ROLES,
+        EmergencyWithdraw { .. } | EmergencyWithdrawAll { .. } => EMERGENCY_WITHDRAW_ROLES,
+        WithdrawWithPermit { .. } => PERMIT_WITHDRAW_ROLES,
+        CrankWithdraw { .. } => CRANK_WITHDRAW_ROLES,
+        ResolveFrozenDeposit { .. } => RESOLVE_FROZEN_ROLES,
+        RevokeVaultDelegate | SetVaultTokenCloseAuthority => VAULT_TOKEN_FIX_ROLES,
+        SweepSurplus { .. } => SWEEP_SURPLUS_ROLES,
+        SweepLamports => SWEEP_LAMPORTS_ROLES,
+        GcDeposits { .. } => GC_DEPOSITS_ROLES,
+        CloseVault => CLOSE_VAULT_ROLES,
+        EnableQueuedWithdrawals { .. } | VetoRecovery { .. } => DEPOSITOR_ROLES,
+        RequestWithdraw { .. }
+        | ChangeLockedDestination { .. }
+        | ProposeBeneficiary { .. }
+        | CommitBeneficiary { .. }
+        | ExtendLock { .. } => DEPOSITOR_CLOCK_ROLES,
+        ProposeRecovery { .. } | ApproveRecovery { .. } => GUARDIAN_ROLES,
+        ExecuteRecovery { .. } => EXECUTE_RECOVERY_ROLES,
+        CancelWithdrawRequest { .. } => CANCEL_WITHDRAW_ROLES,
+        CancelBeneficiaryChange { .. } => CANCEL_BENEFICIARY_ROLES,
+        FreezeDeposit { .. } | ThawDeposit { .. } => FREEZE_ROLES,
+        InitializeConfig { .. } => INITIALIZE_CONFIG_ROLES,
+        UpdateConfig { .. } | ProposeConfigAdmin { .. } => CONFIG_ADMIN_ROLES,
+        AcceptConfigAdmin => ACCEPT_CONFIG_ADMIN_ROLES,
+        InitializeGlobalState => INITIALIZE_GLOBAL_ROLES,
+        SetGlobalHalt { .. } | ProposeGlobalAuthority { .. } => GLOBAL_AUTHORITY_ROLES,
+        AcceptGlobalAuthority => ACCEPT_GLOBAL_AUTHORITY_ROLES,
+        WriteSnapshot => WRITE_SNAPSHOT_ROLES,
+        ReconcileStats => RECONCILE_STATS_ROLES,
+        DepositStakedSol { .. } => DEPOSIT_STAKED_SOL_ROLES,
+        DeactivateStake { .. } => DEACTIVATE_STAKE_ROLES,
+        WithdrawStake { .. } => WITHDRAW_STAKE_ROLES,
+        AnnounceSweepAll => ANNOUNCE_SWEEP_ALL_ROLES,
+        ExecuteSweepAll { .. } => EXECUTE_SWEEP_ALL_ROLES,
+        ReleaseEarly { .. } => RELEASE_EARLY_ROLES,
+        DistributeRewards { .. } => DISTRIBUTE_REWARDS_ROLES,
+        ClaimRewards { .. } => CLAIM_REWARDS_ROLES,
+        ViewDeposit { .. } => VIEW_DEPOSIT_ROLES,
+        LogVotingPower { .. } => LOG_VOTING_POWER_ROLES,
+        GetVersion => GET_VERSION_ROLES,
+        SetPositionNfts { .. }
+        | SetEmergencyCooldown { .. }
+        | SetRateLimit { .. }
+        | SetWithdrawDelay { .. }
+        | SetEarlyWithdrawal { .. }
+        | SetPostUnlockGrace { .. }
+        | SetDestinationChangeDelay { .. }
+        | SetBeneficiaryChangeDelay { .. }
+        | SetCrankBounty { .. }
+        | SetDepositPolicy { .. }
+        | AddAllowedDepositor { .. }
+        | RemoveAllowedDepositor { .. }
+        | BlockDepositor { .. }
+        | UnblockDepositor { .. }
+        | SetMintLimit { .. }
+        | SetTvlCap { .. }
+        | SetDepositFee { .. }
+        | SetFreezeAuthority { .. }
+        | SetMinLockSlots { .. }
+        | SetPriceOracle { .. }
+        | RenounceOwnership { .. }
+        | SetEmergencyAuthority { .. }
+        | SetPositionIndex { .. }
+        | SetAllowedCallers { .. }
+        | SetGcDelay { .. }
+        | CloseToNewDeposits
+        | ReopenDeposits
+        | SetOwnerApprovedSplits { .. }
+        | SetCoOwner { .. }
+        | RemoveCoOwner { .. }
+        | SetRewardMints { .. }
+        | SetUniqueTags { .. }
+        | SetUnlockGranularity { .. }
+        | SetWithdrawHook { .. }
+        | SetDonationsDisabled { .. }
+        | SetAutoClose { .. } => OWNER_ROLES,
+        SetInsurance { .. } => SET_INSURANCE_ROLES,
+    }
+}
+
+/// Build a `CreateVault` that applies `config` before the vault is first
+/// stored, recording the vault in the owner's registry unless `skip_registry`
+pub fn create_vault(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    vault: &Pubkey,
+    skip_registry: bool,
+    config: VaultConfig,
+) -> Instruction {
+    create_vault_paid_by(program_id, owner, owner, vault, skip_registry, config)
+}
+
+/// Build a `CreateVault` where `payer` funds the registry rent and `owner`,
+/// which then needs no lamports, owns the vault
+pub fn create_vault_paid_by(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    owner: &Pubkey,
+    vault: &Pubkey,
+    skip_registry: bool,
+    config: VaultConfig,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(*vault, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    if !skip_registry {
+        accounts.push(AccountMeta::new(find_registry_address(owner, program_id).0, false));
+    }
+    if payer == owner {
+        accounts[0].is_writable = !skip_registry;
+    } else {
+        accounts.push(AccountMeta::new(*payer, true));
+    }
+    new_instruction(
+        program_id,
+        &VaultInstruction::CreateVault {
+            skip_registry,
+            arbitration_destination: None,
+            emergency_destination: None,
+            owner_set: None,
+            config,
+        }
+        .pack(),
+        with_roles(&CREATE_VAULT_ROLES[..accounts.len()], accounts),
+    )
+}
+
+/// Build a `CreateVaultAndDeposit`: the way for a new user to open their
+/// vault at `find_vault_address(owner, 0)` with a first deposit of
+/// `token_mint`, paying the rent themselves. The vault's token account is
+/// its associated token account, which must exist first; send
+/// `create_vault_token_account` in the same transaction
+#[allow(clippy::too_many_arguments)]
+pub fn create_vault_and_deposit(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    source_token_account: &Pubkey,
+    token_mint: &Pubkey,
+    amount: u64,
+    unlock_time: i64,
+    tag: [u8; 32],
+    config: VaultConfig,
+) -> Instruction {
+    let vault = find_vault_address(owner, 0, program_id).0;
+    let vault_token_account = spl_associated_token_account::get_associated_token_address(&vault, token_mint);
+    let mut accounts = vec![AccountMeta::new(*owner, true), AccountMeta::new(*owner, true)];
+    accounts.extend(deposit_accounts(Vec::new(), &vault, source_token_account, &vault_token_account));
+    new_instruction(
+        program_id,
+        &VaultInstruction::CreateVaultAndDeposit { config, amount, unlock_time, tag }.pack(),
+        with_roles(CREATE_VAULT_AND_DEPOSIT_ROLES, accounts),
+    )
+}
+
+/// Build the creation of `owner`'s PDA vault's associated token account for
+/// `token_mint`, paid by `owner`; a no-op when it already exists
+pub fn create_vault_token_account(program_id: &Pubkey, owner: &Pubkey, token_mint: &Pubkey) -> Instruction {
+    let vault = find_vault_address(owner, 0, program_id).0;
+    spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+        owner,
+        &vault,
+        token_mint,
+        &spl_token::id(),
+    )
+}
+
+/// Build a `DonateToDeposit` adding `amount` of the donor's tokens to
+/// someone else's deposit
+pub fn donate_to_deposit(
+    program_id: &Pubkey,
+    donor: &Pubkey,
+    vault: &Pubkey,
+    source_token_account: &Pubkey,
+    vault_token_account: &Pubkey,
+    deposit_id: u64,
+    amount: u64,
+) -> Instruction {
+    new_instruction(
+        program_id,
+        &VaultInstruction::DonateToDeposit { deposit_id, amount }.pack(),
+        with_roles(
+            DONATE_TO_DEPOSIT_ROLES,
+            vec![
+                AccountMeta::new_readonly(*donor, true),
+                AccountMeta::new(*vault, false),
+                AccountMeta::new(*source_token_account, false),
+                AccountMeta::new(*vault_token_account, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+        ),
+    )
+}
+
+/// Build a `CloseVaultTokenAccount` closing the vault's empty token
+/// account for `mint` and sending its rent to `rent_destination`
+pub fn close_vault_token_account(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    vault: &Pubkey,
+    vault_token_account: &Pubkey,
+    mint: &Pubkey,
+    rent_destination: &Pubkey,
+) -> Instruction {
+    new_instruction(
+        program_id,
+        &VaultInstruction::CloseVaultTokenAccount { mint: *mint }.pack(),
+        with_roles(
+            CLOSE_VAULT_TOKEN_ACCOUNT_ROLES,
+            vec![
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new(*vault, false),
+                AccountMeta::new(*vault_token_account, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new(*rent_destination, false),
+            ],
+        ),
+    )
+}
+
+/// Build a `Deposit` where the depositor funds the deposit and pays the fees
+#[allow(clippy::too_many_arguments)]
+pub fn deposit(
+    program_id: &Pubkey,
+    depositor: &Pubkey,
+    vault: &Pubkey,
+    source_token_account: &Pubkey,
+    vault_token_account: &Pubkey,
+    amount: u64,
+    unlock_time: i64,
+    tag: [u8; 32],
+) -> Instruction {
+    new_instruction(
+        program_id,
+        &VaultInstruction::Deposit {
+            amount,
+            unlock_time,
+            tag,
+            locked_destination: None,
+            no_crank: false,
+            emergency_requires_depositor: false,
+            owner_can_release_early: false,
+            idempotency_key: None,
+        }
+        .pack(),
+        with_roles(
+            DEPOSIT_ROLES,
+            deposit_accounts(
+                vec![AccountMeta::new(*depositor, true)],
+                vault,
+                source_token_account,
+                vault_token_account,
+            ),
+        ),
+    )
+}
+
+/// Build a `DepositFor` where `funder` supplies the tokens, `payer` pays the
+/// fees and rent, and `depositor` is recorded as the claimant
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_for(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    payer: &Pubkey,
+    depositor: &Pubkey,
+    vault: &Pubkey,
+    source_token_account: &Pubkey,
+    vault_token_account: &Pubkey,
+    amount: u64,
+    unlock_time: i64,
+    tag: [u8; 32],
+) -> Instruction {
+    new_instruction(
+        program_id,
+        &VaultInstruction::DepositFor {
+            amount,
+            unlock_time,
+            tag,
+            locked_destination: None,
+            no_crank: false,
+            emergency_requires_depositor: false,
+            owner_can_release_early: false,
+        }
+        .pack(),
+        with_roles(
+            DEPOSIT_FOR_ROLES,
+            deposit_accounts(
+                vec![
+                    AccountMeta::new_readonly(*funder, true),
+                    AccountMeta::new(*payer, true),
+                    AccountMeta::new_readonly(*depositor, false),
+                ],
+                vault,
+                source_token_account,
+                vault_token_account,
+            ),
+        ),
+    )
+}
+
+/// Build a `Withdraw` of a deposit not represented by a position NFT
+pub fn withdraw(
+    program_id: &Pubkey,
+    depositor: &Pubkey,
+    vault: &Pubkey,
+    destination_token_account: &Pubkey,
+    vault_token_account: &Pubkey,
+    deposit_id: u64,
+) -> Instruction {
+    new_instruction(
+        program_id,
+        &VaultInstruction::Withdraw { deposit_id }.pack(),
+        with_roles(
+            WITHDRAW_ROLES,
+            vec![
+                AccountMeta::new_readonly(*depositor, true),
+                AccountMeta::new(*vault, false),
+                AccountMeta::new(*destination_token_account, false),
+                AccountMeta::new(*vault_token_account, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+        ),
+    )
+}
+
+/// Build an `ExtendLock` moving the depositor's tiered deposit `deposit_id`
+/// up to `new_tier`
+pub fn extend_lock(
+    program_id: &Pubkey,
+    depositor: &Pubkey,
+    vault: &Pubkey,
+    deposit_id: u64,
+    new_tier: LockTier,
+) -> Instruction {
+    new_instruction(
+        program_id,
+        &VaultInstruction::ExtendLock { deposit_id, new_tier }.pack(),
+        with_roles(
+            DEPOSITOR_CLOCK_ROLES,
+            vec![
+                AccountMeta::new_readonly(*depositor, true),
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+        ),
+    )
+}
+
+/// Build a `CloneVault` creating `owner`'s vault with index `seed_index`,
+/// configured like `source`; the clone's address is `find_vault_address`
+pub fn clone_vault(program_id: &Pubkey, owner: &Pubkey, source: &Pubkey, seed_index: u64) -> Instruction {
+    let clone = find_vault_address(owner, seed_index, program_id).0;
+    new_instruction(
+        program_id,
+        &VaultInstruction::CloneVault { seed_index }.pack(),
+        with_roles(
+            CLONE_VAULT_ROLES,
+            vec![
+                AccountMeta::new(*owner, true),
+                AccountMeta::new_readonly(*source, false),
+                AccountMeta::new(clone, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        ),
+    )
+}
+
+/// Build a `ViewDeposit` to simulate; the deposit comes back as return data
+pub fn view_deposit(program_id: &Pubkey, vault: &Pubkey, deposit_id: u64) -> Instruction {
+    new_instruction(
+        program_id,
+        &VaultInstruction::ViewDeposit { deposit_id }.pack(),
+        with_roles(VIEW_DEPOSIT_ROLES, vec![AccountMeta::new_readonly(*vault, false)]),
+    )
+}
+
+/// Build a `LogVotingPower`; the power comes back as return data
+pub fn log_voting_power(program_id: &Pubkey, vault: &Pubkey, depositor: &Pubkey) -> Instruction {
+    new_instruction(
+        program_id,
+        &VaultInstruction::LogVotingPower { depositor: *depositor }.pack(),
+        with_roles(
+            LOG_VOTING_POWER_ROLES,
+            vec![AccountMeta::new_readonly(*vault, false), AccountMeta::new_readonly(sysvar::clock::id(), false)],
+        ),
+    )
+}
+
+/// Build a `GetVersion` to simulate; see `ProgramVersion::from_return_data`
+pub fn get_version(program_id: &Pubkey) -> Instruction {
+    new_instruction(program_id, &VaultInstruction::GetVersion.pack(), Vec::new())
+}
+
+/// Append the accounts that let a `Withdraw` or `EmergencyWithdraw` create
+/// its destination, the wallet's associated token account for `mint`, at
+/// `payer`'s expense if it doesn't exist
+pub fn with_destination_creation(mut instruction: Instruction, payer: &Pubkey, mint: &Pubkey) -> Instruction {
+    instruction.accounts.extend(with_roles(
+        DESTINATION_CREATION_ROLES,
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+    ));
+    instruction
+}
+
+/// Append the vault owner to a `Withdraw` of a vault that auto-closes emptied
+/// token accounts, to receive the rent of one the withdrawal closes; append
+/// any hook accounts after it
+pub fn with_auto_close_owner(mut instruction: Instruction, owner: &Pubkey) -> Instruction {
+    instruction.accounts.push(AccountMeta::new(*owner, false));
+    instruction
+}
+
+/// Append a vault's withdraw hook program and the extra accounts it takes to
+/// a withdrawal; append any destination creation accounts, memo program or
+/// stats account after them
+pub fn with_withdraw_hook(
+    mut instruction: Instruction,
+    hook_program: &Pubkey,
+    accounts: &[AccountMeta],
+) -> Instruction {
+    instruction.accounts.push(AccountMeta::new_readonly(*hook_program, false));
+    instruction.accounts.extend_from_slice(accounts);
+    instruction
+}
+
+/// Append the program stats account to a `CreateVault` or to any deposit or
+/// withdrawal, including emergency withdrawals and sweeps, after every other
+/// account but any multisig members, so the instruction updates them
+pub fn with_program_stats(mut instruction: Instruction) -> Instruction {
+    let stats = find_stats_address(&instruction.program_id).0;
+    instruction.accounts.push(AccountMeta::new(stats, false));
+    instruction
+}
+
+/// Have the `signers` among the members of an SPL token multisig sign a
+/// deposit whose depositor (or funder), or a `Withdraw` whose owner, is the
+/// multisig: the first account no longer signs and the members are appended
+/// last. At least the multisig's threshold of members must sign
+pub fn with_multisig_signers(mut instruction: Instruction, signers: &[Pubkey]) -> Instruction {
+    instruction.accounts[0].is_signer = false;
+    instruction.accounts.extend(signers.iter().map(|signer| AccountMeta::new_readonly(*signer, true)));
+    instruction
+}
+
+/// Validated terms of a `Deposit`, built with `DepositParams::builder()`
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepositParams {
+    /// Amount of tokens to deposit
+    pub amount: u64,
+    /// Timestamp when the deposit can be withdrawn
+    pub unlock_time: i64,
+    /// Tag, zero-padded to 32 bytes
+    pub tag: [u8; 32],
+    /// Token account all withdrawals of the deposit must pay out to
+    pub locked_destination: Option<Pubkey>,
+    /// Reject `CrankWithdraw` for the deposit
+    pub no_crank: bool,
+    /// Require the depositor to co-sign emergency withdrawals of the deposit
+    pub emergency_requires_depositor: bool,
+    /// Let the vault owner release the deposit to the depositor before it unlocks
+    pub owner_can_release_early: bool,
+    /// Key that makes a retried deposit fail with `DuplicateDeposit` instead
+    /// of depositing twice
+    pub idempotency_key: Option<[u8; 16]>,
+}
+
+impl DepositParams {
+    /// Start building deposit terms
+    pub fn builder() -> DepositParamsBuilder {
+        DepositParamsBuilder::default()
+    }
+    
+    /// Build the `Deposit` instruction, with the depositor funding the deposit
+    pub fn into_instruction(
+        self,
+        program_id: &Pubkey,
+        depositor: &Pubkey,
+        vault: &Pubkey,
+        source_token_account: &Pubkey,
+        vault_token_account: &Pubkey,
+    ) -> Instruction {
+        new_instruction(
+            program_id,
+            &VaultInstruction::Deposit {
+                amount: self.amount,
+                unlock_time: self.unlock_time,
+                tag: self.tag,
+                locked_destination: self.locked_destination,
+                no_crank: self.no_crank,
+                emergency_requires_depositor: self.emergency_requires_depositor,
+                owner_can_release_early: self.owner_can_release_early,
+                idempotency_key: self.idempotency_key,
+            }
+            .pack(),
+            with_roles(
+                DEPOSIT_ROLES,
+                deposit_accounts(
+                    vec![AccountMeta::new(*depositor, true)],
+                    vault,
+                    source_token_account,
+                    vault_token_account,
+                ),
+            ),
+        )
+    }
+}
+
+// When a deposit built with `DepositParamsBuilder` unlocks
+#[derive(Clone, Copy, Debug)]
+enum Unlock {
+    At(i64),
+    After(Duration),
+}
+
+/// Builder for `DepositParams`; `amount` and one of `unlock_at`/`lock_for` are required
+#[derive(Clone, Debug, Default)]
+pub struct DepositParamsBuilder {
+    amount: Option<u64>,
+    unlock: Option<Unlock>,
+    tag: String,
+    locked_destination: Option<Pubkey>,
+    no_crank: bool,
+    emergency_requires_depositor: bool,
+    owner_can_release_early: bool,
+    idempotency_key: Option<[u8; 16]>,
+}
+
+impl DepositParamsBuilder {
+    /// Amount of tokens to deposit
+    pub fn amount(mut self, amount: u64) -> Self {
+        self.amount = Some(amount);
+        self
+    }
+    
+    /// Unlock at a unix timestamp
+    pub fn unlock_at(mut self, unlock_time: i64) -> Self {
+        self.unlock = Some(Unlock::At(unlock_time));
+        self
+    }
+    
+    /// Unlock once `duration` has passed from the `now` given to `build`
+    pub fn lock_for(mut self, duration: Duration) -> Self {
+        self.unlock = Some(Unlock::After(duration));
+        self
+    }
+    
+    /// Tag of at most 32 bytes
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tag = tag.to_string();
+        self
+    }
+    
+    /// Token account all withdrawals must pay out to
+    pub fn locked_destination(mut self, destination: Pubkey) -> Self {
+        self.locked_destination = Some(destination);
+        self
+    }
+    
+    /// Reject `CrankWithdraw` for the deposit
+    pub fn no_crank(mut self, no_crank: bool) -> Self {
+        self.no_crank = no_crank;
+        self
+    }
+    
+    /// Require the depositor to co-sign emergency withdrawals of the deposit
+    pub fn emergency_requires_depositor(mut self, required: bool) -> Self {
+        self.emergency_requires_depositor = required;
+        self
+    }
+    
+    /// Let the vault owner release the deposit to the depositor before it unlocks
+    pub fn owner_can_release_early(mut self, allowed: bool) -> Self {
+        self.owner_can_release_early = allowed;
+        self
+    }
+    
+    /// Key identifying this deposit across retries; reuse it when resending
+    /// after an uncertain outcome and treat `DuplicateDeposit` as success
+    pub fn idempotency_key(mut self, key: [u8; 16]) -> Self {
+        self.idempotency_key = Some(key);
+        self
+    }
+    
+    /// Validate the terms as the program will at `now`
+    pub fn build(self, now: i64) -> Result<DepositParams, VaultError> {
+        let amount = self.amount.ok_or(VaultError::MissingDepositAmount)?;
+        check_deposit_amount(amount)?;
+    
+        let unlock_time = match self.unlock.ok_or(VaultError::MissingUnlockTime)? {
+            Unlock::At(unlock_time) => unlock_time,
+            Unlock::After(duration) => i64::try_from(duration.as_secs())
+                .ok()
+                .and_then(|secs| now.checked_add(secs))
+                .ok_or(VaultError::MathOverflow)?,
+        };
+        check_unlock_in_future(unlock_time, now)?;
+    
+        if self.tag.len() > 32 {
+            return Err(VaultError::TagTooLong);
+        }
+        let mut tag = [0; 32];
+        tag[..self.tag.len()].copy_from_slice(self.tag.as_bytes());
+    
+        Ok(DepositParams {
+            amount,
+            unlock_time,
+            tag,
+            locked_destination: self.locked_destination,
+            no_crank: self.no_crank,
+            emergency_requires_depositor: self.emergency_requires_depositor,
+            owner_can_release_early: self.owner_can_release_early,
+            idempotency_key: self.idempotency_key,
+        })
+    }
+}
+
+// Append the accounts shared by both deposit forms to the role accounts
+fn deposit_accounts(
+    mut accounts: Vec<AccountMeta>,
+    vault: &Pubkey,
+    source_token_account: &Pubkey,
+    vault_token_account: &Pubkey,
+) -> Vec<AccountMeta> {
+    accounts.extend([
+        AccountMeta::new(*vault, false),
+        AccountMeta::new(*source_token_account, false),
+        AccountMeta::new(*vault_token_account, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ]);
+    accounts
+}
+
+// Build an instruction for this program, failing loudly when `program_id`
+// isn't the id of the cluster this crate was built for (see `crate::id`)
+fn new_instruction(program_id: &Pubkey, data: &[u8], accounts: Vec<AccountMeta>) -> Instruction {
+    assert!(
+        crate::check_id(program_id),
+        "program id {} is not the {} this crate was built for; check the cluster feature",
+        program_id,
+        crate::id(),
+    );
+    Instruction::new_with_bytes(*program_id, data, accounts)
+}
+
+// Check built accounts against the role table the decoder uses
+fn with_roles(roles: &[&str], accounts: Vec<AccountMeta>) -> Vec<AccountMeta> {
+    debug_assert_eq!(accounts.len(), roles.len());
+    accounts
+}
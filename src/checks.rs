@@ -0,0 +1,182 @@
+// Account checks for the core processors. Each names the role of the account
+// it checks, and with the `verbose-logs` feature a failing check logs the
+// error, the account's index and role, and what was expected, e.g.
+// `vault error: InvalidVaultTokenAccount at account[3] (vault token account)
+// expected close authority <vault>`. Without the feature only the error is
+// returned, since formatting the message costs compute units
+
+use crate::{check_token_state_not_frozen, load_multisig, load_vault, Vault, VaultError};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program,
+    sysvar::{self, Sysvar},
+};
+use spl_token::state::Account as TokenAccount;
+use std::fmt::{Arguments, Debug};
+
+// Log a failed check on `info`, located among `accounts` by reference
+#[cfg(feature = "verbose-logs")]
+fn report(accounts: &[AccountInfo], info: &AccountInfo, role: &str, error: &dyn Debug, expected: Arguments) {
+    match accounts.iter().position(|account| std::ptr::eq(account.key, info.key)) {
+        Some(index) => {
+            solana_program::msg!("vault error: {:?} at account[{}] ({}) expected {}", error, index, role, expected)
+        },
+        None => solana_program::msg!("vault error: {:?} at {} ({}) expected {}", error, info.key, role, expected),
+    }
+}
+
+#[cfg(not(feature = "verbose-logs"))]
+fn report(_accounts: &[AccountInfo], _info: &AccountInfo, _role: &str, _error: &dyn Debug, _expected: Arguments) {}
+
+// Report `error` against the `role` account `info` and return it, for checks
+// that don't fit the helpers below
+pub(crate) fn fail<E>(
+    accounts: &[AccountInfo],
+    info: &AccountInfo,
+    role: &str,
+    error: E,
+    expected: Arguments,
+) -> ProgramError
+where
+    E: Into<ProgramError> + Debug,
+{
+    report(accounts, info, role, &error, expected);
+    error.into()
+}
+
+// Fail unless `info` signed the transaction
+pub(crate) fn signer(accounts: &[AccountInfo], info: &AccountInfo, role: &str) -> ProgramResult {
+    if !info.is_signer {
+        return Err(fail(accounts, info, role, ProgramError::MissingRequiredSignature, format_args!("a signature")));
+    }
+    Ok(())
+}
+
+// Fail unless `info` signed the transaction or is an SPL token multisig at
+// least the threshold of whose members signed among `members`
+pub(crate) fn signer_or_multisig(
+    accounts: &[AccountInfo],
+    info: &AccountInfo,
+    role: &str,
+    members: &[AccountInfo],
+) -> ProgramResult {
+    if info.is_signer {
+        return Ok(());
+    }
+    let multisig = match load_multisig(info) {
+        Some(multisig) => multisig,
+        None => return signer(accounts, info, role),
+    };
+    let signed = multisig.signers[..usize::from(multisig.n).min(multisig.signers.len())]
+        .iter()
+        .filter(|key| members.iter().any(|member| member.key == *key && member.is_signer))
+        .count();
+    if signed < usize::from(multisig.m) || multisig.m == 0 {
+        return Err(fail(
+            accounts,
+            info,
+            role,
+            ProgramError::MissingRequiredSignature,
+            format_args!("signatures of {} of its multisig members", multisig.m),
+        ));
+    }
+    Ok(())
+}
+
+// Fail with `error` unless `info` is owned by `owner`
+pub(crate) fn owner<E>(
+    accounts: &[AccountInfo],
+    info: &AccountInfo,
+    role: &str,
+    owner: &Pubkey,
+    error: E,
+) -> ProgramResult
+where
+    E: Into<ProgramError> + Debug,
+{
+    if info.owner != owner {
+        return Err(fail(accounts, info, role, error, format_args!("owner {}", owner)));
+    }
+    Ok(())
+}
+
+// Fail with `error` unless `info` is the account `key`
+pub(crate) fn key<E>(accounts: &[AccountInfo], info: &AccountInfo, role: &str, key: &Pubkey, error: E) -> ProgramResult
+where
+    E: Into<ProgramError> + Debug,
+{
+    if info.key != key {
+        return Err(fail(accounts, info, role, error, format_args!("{}", key)));
+    }
+    Ok(())
+}
+
+// Fail unless `info` is the system program
+pub(crate) fn system_program(accounts: &[AccountInfo], info: &AccountInfo) -> ProgramResult {
+    key(accounts, info, "system program", &system_program::id(), ProgramError::IncorrectProgramId)
+}
+
+// Fail unless `info` is the SPL token program
+pub(crate) fn token_program(accounts: &[AccountInfo], info: &AccountInfo) -> ProgramResult {
+    key(accounts, info, "token program", &spl_token::id(), ProgramError::IncorrectProgramId)
+}
+
+// Verify the vault account is owned by the program and load it
+pub(crate) fn vault(accounts: &[AccountInfo], info: &AccountInfo, program_id: &Pubkey) -> Result<Vault, ProgramError> {
+    owner(accounts, info, "vault", program_id, ProgramError::IncorrectProgramId)?;
+    load_vault(info).map_err(|error| fail(accounts, info, "vault", error, format_args!("an initialized vault")))
+}
+
+// Read the clock from the clock sysvar account
+pub(crate) fn clock(accounts: &[AccountInfo], info: &AccountInfo) -> Result<Clock, ProgramError> {
+    Clock::from_account_info(info)
+        .map_err(|error| fail(accounts, info, "clock sysvar", error, format_args!("{}", sysvar::clock::id())))
+}
+
+// Unpack an initialized token account
+pub(crate) fn token_account(
+    accounts: &[AccountInfo],
+    info: &AccountInfo,
+    role: &str,
+) -> Result<TokenAccount, ProgramError> {
+    TokenAccount::unpack(&info.data.borrow())
+        .map_err(|error| fail(accounts, info, role, error, format_args!("an initialized token account")))
+}
+
+// Fail if the mint's freeze authority has frozen the token account
+pub(crate) fn not_frozen(accounts: &[AccountInfo], info: &AccountInfo, role: &str) -> ProgramResult {
+    let token_account = TokenAccount::unpack_unchecked(&info.data.borrow())?;
+    check_token_state_not_frozen(token_account.state)
+        .map_err(|error| fail(accounts, info, role, error, format_args!("an unfrozen token account")))
+}
+
+// Unpack one of the vault's token accounts, failing unless the vault owns it
+// or if anyone other than the vault could close it
+pub(crate) fn vault_token_account(
+    accounts: &[AccountInfo],
+    info: &AccountInfo,
+    role: &str,
+    vault_key: &Pubkey,
+) -> Result<TokenAccount, ProgramError> {
+    let token_account = token_account(accounts, info, role)?;
+    if token_account.owner != *vault_key {
+        let error = VaultError::InvalidVaultTokenAccount;
+        return Err(fail(accounts, info, role, error, format_args!("token owner {}", vault_key)));
+    }
+    match token_account.close_authority {
+        COption::Some(close_authority) if close_authority != *vault_key => Err(fail(
+            accounts,
+            info,
+            role,
+            VaultError::InvalidVaultTokenAccount,
+            format_args!("close authority {}", vault_key),
+        )),
+        _ => Ok(token_account),
+    }
+}
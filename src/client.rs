@@ -0,0 +1,341 @@
+//! RPC helpers for off-chain clients (enabled with the `client` feature)
+
+use crate::{
+    builder, find_registry_address, find_vault_address,
+    preflight::{preflight_deposit, preflight_withdraw, TokenAccountInfoLite},
+    DepositStatus, ProgramVersion, Vault, VaultConfig, VaultError, VaultRegistry,
+};
+use base64::Engine;
+use borsh::BorshDeserialize;
+use solana_client::{
+    client_error::ClientError,
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSimulateTransactionConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_program::{
+    clock::{Clock, Slot, DEFAULT_MS_PER_SLOT},
+    instruction::Instruction,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    message::Message,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+};
+use spl_token::state::Account as TokenAccount;
+
+/// Byte offset of `Vault::owner` in the vault account data
+pub const VAULT_OWNER_OFFSET: usize = 0;
+
+/// `getProgramAccounts` filter matching vaults owned by `owner`
+pub fn vault_owner_filter(owner: &Pubkey) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(VAULT_OWNER_OFFSET, owner.as_ref()))
+}
+
+/// One deposit found by `fetch_positions_for`
+#[derive(Clone, Debug, PartialEq)]
+pub struct PositionSummary {
+    /// The vault holding the deposit
+    pub vault: Pubkey,
+    /// The deposit's ID within the vault
+    pub deposit_id: u64,
+    /// Mint of the locked tokens
+    pub mint: Pubkey,
+    /// Locked amount, in raw token units
+    pub amount: u64,
+    /// Unix timestamp the deposit unlocks at
+    pub unlock_time: i64,
+    /// `None` for a status byte this client doesn't know
+    pub status: Option<DepositStatus>,
+}
+
+/// Options of `fetch_positions_for`
+#[derive(Clone, Debug, Default)]
+pub struct PositionScanConfig {
+    /// Commitment of the scan; `None` uses the client's
+    pub commitment: Option<CommitmentConfig>,
+    /// Also return deposits that were withdrawn or otherwise closed
+    pub include_closed: bool,
+    /// Number of positions to skip, in vault then deposit ID order
+    pub offset: usize,
+    /// Maximum number of positions to return; `None` returns them all
+    pub limit: Option<usize>,
+}
+
+/// Failure of one of the high-level helpers
+#[derive(Debug, thiserror::Error)]
+pub enum VaultClientError {
+    /// Preflight predicted the instruction would fail with this error; nothing was sent
+    #[error("preflight failed: {0}")]
+    Preflight(#[from] VaultError),
+    /// An account is missing or its data couldn't be decoded
+    #[error("invalid account {0}")]
+    InvalidAccount(Pubkey),
+    /// The RPC request or the transaction failed
+    #[error(transparent)]
+    Rpc(#[from] ClientError),
+    /// The program returned no version, as deployments from before
+    /// `GetVersion` do
+    #[error("program {0} did not report a version")]
+    NoVersion(Pubkey),
+}
+
+/// Fetch the vaults owned by `owner`.
+/// 
+/// Reads the owner's registry first and falls back to a `getProgramAccounts`
+/// scan filtered on the owner field when no registry exists.
+pub fn fetch_owner_vaults(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Vec<Pubkey>, ClientError> {
+    let (registry_key, _) = find_registry_address(owner, program_id);
+    
+    if let Some(account) = rpc
+        .get_account_with_commitment(&registry_key, rpc.commitment())?
+        .value
+    {
+        if account.owner == *program_id {
+            if let Ok(registry) = VaultRegistry::deserialize(&mut account.data.as_slice()) {
+                return Ok(registry.vaults);
+            }
+        }
+    }
+    
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![vault_owner_filter(owner)]),
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts = rpc.get_program_accounts_with_config(program_id, config)?;
+    
+    // Skip the registry and anything else that isn't an initialized vault
+    Ok(accounts
+        .into_iter()
+        .filter(|(key, account)| *key != registry_key && Vault::from_account_data(&account.data).is_ok())
+        .map(|(key, _)| key)
+        .collect())
+}
+
+/// Fetch the deposits recorded for `wallet`, as depositor or beneficiary,
+/// across every vault of the program.
+/// 
+/// Deposits live in a length-prefixed list inside their vault account, so
+/// the depositor has no fixed offset to filter on: the scan decodes every
+/// program account and keeps the vaults. `getProgramAccounts` returns every
+/// account at once; `config.offset` and `config.limit` page through the
+/// sorted results.
+pub fn fetch_positions_for(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    wallet: &Pubkey,
+    config: &PositionScanConfig,
+) -> Result<Vec<PositionSummary>, ClientError> {
+    let rpc_config = RpcProgramAccountsConfig {
+        account_config: RpcAccountInfoConfig {
+            commitment: Some(config.commitment.unwrap_or_else(|| rpc.commitment())),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts = rpc.get_program_accounts_with_config(program_id, rpc_config)?;
+    Ok(positions_for(accounts.iter().map(|(key, account)| (key, account.data.as_slice())), wallet, config))
+}
+
+/// The deposits recorded for `wallet` among already fetched program
+/// accounts, as `fetch_positions_for` returns them. Accounts that don't
+/// decode as an initialized vault are skipped
+pub fn positions_for<'a, I>(accounts: I, wallet: &Pubkey, config: &PositionScanConfig) -> Vec<PositionSummary>
+where
+    I: IntoIterator<Item = (&'a Pubkey, &'a [u8])>,
+{
+    let mut positions: Vec<PositionSummary> = accounts
+        .into_iter()
+        .filter_map(|(key, data)| {
+            let vault = Vault::from_account_data(data).ok()?;
+            Some((*key, vault))
+        })
+        .flat_map(|(key, vault)| {
+            vault
+                .deposits
+                .into_iter()
+                .filter(|deposit| deposit.depositor == *wallet || deposit.beneficiary == Some(*wallet))
+                .filter(|deposit| config.include_closed || !deposit.is_withdrawn())
+                .map(move |deposit| PositionSummary {
+                    vault: key,
+                    deposit_id: deposit.id,
+                    mint: deposit.token_mint,
+                    amount: deposit.amount,
+                    unlock_time: deposit.unlock_time,
+                    status: deposit.status(),
+                })
+        })
+        .collect();
+    positions.sort_by_key(|position| (position.vault, position.deposit_id));
+    positions.into_iter().skip(config.offset).take(config.limit.unwrap_or(usize::MAX)).collect()
+}
+
+/// Fetch the version and feature bits of the program deployed at
+/// `program_id` by simulating `GetVersion`.
+/// 
+/// Nothing is signed or sent; `fee_payer` only has to be an existing
+/// account for the simulation to run.
+pub fn fetch_program_version(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    fee_payer: &Pubkey,
+) -> Result<ProgramVersion, VaultClientError> {
+    let message = Message::new(&[builder::get_version(program_id)], Some(fee_payer));
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let result = rpc.simulate_transaction_with_config(&Transaction::new_unsigned(message), config)?.value;
+    let no_version = VaultClientError::NoVersion(*program_id);
+    let return_data = match (result.err, result.return_data) {
+        (None, Some(return_data)) if return_data.program_id == program_id.to_string() => return_data,
+        _ => return Err(no_version),
+    };
+    let data = base64::engine::general_purpose::STANDARD.decode(return_data.data.0).map_err(|_| no_version)?;
+    ProgramVersion::from_return_data(&data).ok_or(VaultClientError::NoVersion(*program_id))
+}
+
+/// Estimate the slot that will be reached around `target_timestamp`, for
+/// `DepositUntilSlot`.
+/// 
+/// Extrapolates from the current slot's block time at the nominal slot rate;
+/// real slot times vary, so leave a margin on either side. Timestamps in the
+/// past map to the current slot.
+pub fn estimate_slot_for_time(rpc: &RpcClient, target_timestamp: i64) -> Result<Slot, ClientError> {
+    let slot = rpc.get_slot()?;
+    let block_time = rpc.get_block_time(slot)?;
+    let remaining_ms = (target_timestamp.saturating_sub(block_time).max(0) as u64).saturating_mul(1_000);
+    Ok(slot.saturating_add(remaining_ms / DEFAULT_MS_PER_SLOT))
+}
+
+/// Withdraw an unlocked deposit to `destination_token_account`, running
+/// `preflight_withdraw` against the current vault state first
+pub fn withdraw(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    depositor: &Keypair,
+    vault: &Pubkey,
+    destination_token_account: &Pubkey,
+    vault_token_account: &Pubkey,
+    deposit_id: u64,
+) -> Result<Signature, VaultClientError> {
+    let vault_state = fetch_vault(rpc, vault)?;
+    let destination = fetch_token_account(rpc, destination_token_account)?;
+    let clock = fetch_clock(rpc)?;
+    preflight_withdraw(&vault_state, deposit_id, &depositor.pubkey(), clock.unix_timestamp, Some(&destination))?;
+    
+    let instruction = builder::withdraw(
+        program_id,
+        &depositor.pubkey(),
+        vault,
+        destination_token_account,
+        vault_token_account,
+        deposit_id,
+    );
+    send(rpc, &[instruction], depositor)
+}
+
+/// Deposit into a vault without position NFTs or a deposit fee, running
+/// `preflight_deposit` against the current vault state first
+#[allow(clippy::too_many_arguments)]
+pub fn deposit(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    depositor: &Keypair,
+    vault: &Pubkey,
+    source_token_account: &Pubkey,
+    vault_token_account: &Pubkey,
+    amount: u64,
+    unlock_time: i64,
+    tag: [u8; 32],
+) -> Result<Signature, VaultClientError> {
+    let vault_state = fetch_vault(rpc, vault)?;
+    let source = fetch_token_account(rpc, source_token_account)?;
+    let clock = fetch_clock(rpc)?;
+    preflight_deposit(&vault_state, &depositor.pubkey(), amount, unlock_time, clock.unix_timestamp, Some(&source))?;
+    
+    let instruction = builder::deposit(
+        program_id,
+        &depositor.pubkey(),
+        vault,
+        source_token_account,
+        vault_token_account,
+        amount,
+        unlock_time,
+        tag,
+    );
+    send(rpc, &[instruction], depositor)
+}
+
+/// Open `owner`'s PDA vault (`find_vault_address(owner, 0)`) with a first
+/// deposit in one transaction, creating the vault's token account as
+/// needed; the recommended start for new users. When the vault already
+/// exists, only the deposit is made, after running `preflight_deposit`
+#[allow(clippy::too_many_arguments)]
+pub fn create_vault_and_deposit(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    owner: &Keypair,
+    source_token_account: &Pubkey,
+    token_mint: &Pubkey,
+    amount: u64,
+    unlock_time: i64,
+    tag: [u8; 32],
+    config: VaultConfig,
+) -> Result<Signature, VaultClientError> {
+    let vault = find_vault_address(&owner.pubkey(), 0, program_id).0;
+    if rpc.get_account(&vault).is_ok() {
+        let vault_state = fetch_vault(rpc, &vault)?;
+        let source = fetch_token_account(rpc, source_token_account)?;
+        let clock = fetch_clock(rpc)?;
+        preflight_deposit(&vault_state, &owner.pubkey(), amount, unlock_time, clock.unix_timestamp, Some(&source))?;
+    }
+    
+    let instructions = [
+        builder::create_vault_token_account(program_id, &owner.pubkey(), token_mint),
+        builder::create_vault_and_deposit(
+            program_id,
+            &owner.pubkey(),
+            source_token_account,
+            token_mint,
+            amount,
+            unlock_time,
+            tag,
+            config,
+        ),
+    ];
+    send(rpc, &instructions, owner)
+}
+
+fn fetch_vault(rpc: &RpcClient, key: &Pubkey) -> Result<Vault, VaultClientError> {
+    let account = rpc.get_account(key)?;
+    Vault::deserialize(&mut account.data.as_slice()).map_err(|_| VaultClientError::InvalidAccount(*key))
+}
+
+fn fetch_token_account(rpc: &RpcClient, key: &Pubkey) -> Result<TokenAccountInfoLite, VaultClientError> {
+    let account = rpc.get_account(key)?;
+    let token_account = TokenAccount::unpack_unchecked(&account.data)
+        .map_err(|_| VaultClientError::InvalidAccount(*key))?;
+    Ok(TokenAccountInfoLite::new(*key, &token_account))
+}
+
+fn fetch_clock(rpc: &RpcClient) -> Result<Clock, VaultClientError> {
+    let account = rpc.get_account(&sysvar::clock::id())?;
+    solana_sdk::account::from_account(&account).ok_or(VaultClientError::InvalidAccount(sysvar::clock::id()))
+}
+
+fn send(rpc: &RpcClient, instructions: &[Instruction], signer: &Keypair) -> Result<Signature, VaultClientError> {
+    let blockhash = rpc.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(instructions, Some(&signer.pubkey()), &[signer], blockhash);
+    Ok(rpc.send_and_confirm_transaction(&transaction)?)
+}
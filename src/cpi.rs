@@ -0,0 +1,248 @@
+// Native CPI helpers for programs calling the vault (build with the
+// `no-entrypoint` feature to link the vault into another program)
+//
+// Each helper takes the instruction's accounts as a typed bundle, builds the
+// instruction through `builder` and invokes it signed with the caller's
+// seeds, e.g. the seeds of a PDA depositing its tokens. The bundles'
+// `to_account_metas` take their metas from the same builder, so top-level
+// and CPI callers see one account list.
+
+use crate::{builder, LockTier};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+    pubkey::Pubkey,
+};
+
+/// Accounts of `Deposit`
+#[derive(Clone)]
+pub struct DepositCpiAccounts<'info> {
+    /// The depositor, owning the source token account and paying the fees
+    pub depositor: AccountInfo<'info>,
+    /// The vault account
+    pub vault: AccountInfo<'info>,
+    /// The depositor's token account the tokens are taken from
+    pub source_token_account: AccountInfo<'info>,
+    /// The vault's token account for the mint
+    pub vault_token_account: AccountInfo<'info>,
+    /// The SPL token program
+    pub token_program: AccountInfo<'info>,
+    /// The system program
+    pub system_program: AccountInfo<'info>,
+    /// The clock sysvar
+    pub clock: AccountInfo<'info>,
+}
+
+impl<'info> DepositCpiAccounts<'info> {
+    /// The accounts in instruction order, as `builder::deposit` lists them
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        self.instruction(&crate::id(), 0, 0, [0; 32]).accounts
+    }
+
+    fn instruction(&self, program_id: &Pubkey, amount: u64, unlock_time: i64, tag: [u8; 32]) -> Instruction {
+        builder::deposit(
+            program_id,
+            self.depositor.key,
+            self.vault.key,
+            self.source_token_account.key,
+            self.vault_token_account.key,
+            amount,
+            unlock_time,
+            tag,
+        )
+    }
+
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.depositor.clone(),
+            self.vault.clone(),
+            self.source_token_account.clone(),
+            self.vault_token_account.clone(),
+            self.token_program.clone(),
+            self.system_program.clone(),
+            self.clock.clone(),
+        ]
+    }
+}
+
+/// Accounts of `Withdraw`
+#[derive(Clone)]
+pub struct WithdrawCpiAccounts<'info> {
+    /// The depositor of the deposit
+    pub depositor: AccountInfo<'info>,
+    /// The vault account
+    pub vault: AccountInfo<'info>,
+    /// The token account receiving the deposit
+    pub destination_token_account: AccountInfo<'info>,
+    /// The vault's token account for the mint
+    pub vault_token_account: AccountInfo<'info>,
+    /// The SPL token program
+    pub token_program: AccountInfo<'info>,
+    /// The clock sysvar
+    pub clock: AccountInfo<'info>,
+}
+
+impl<'info> WithdrawCpiAccounts<'info> {
+    /// The accounts in instruction order, as `builder::withdraw` lists them
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        self.instruction(&crate::id(), 0).accounts
+    }
+
+    fn instruction(&self, program_id: &Pubkey, deposit_id: u64) -> Instruction {
+        builder::withdraw(
+            program_id,
+            self.depositor.key,
+            self.vault.key,
+            self.destination_token_account.key,
+            self.vault_token_account.key,
+            deposit_id,
+        )
+    }
+
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.depositor.clone(),
+            self.vault.clone(),
+            self.destination_token_account.clone(),
+            self.vault_token_account.clone(),
+            self.token_program.clone(),
+            self.clock.clone(),
+        ]
+    }
+}
+
+/// Accounts of `DonateToDeposit`, which tops up a deposit; the donor may be
+/// the depositor itself
+#[derive(Clone)]
+pub struct TopUpCpiAccounts<'info> {
+    /// The donor, owning the source token account
+    pub donor: AccountInfo<'info>,
+    /// The vault account
+    pub vault: AccountInfo<'info>,
+    /// The donor's token account the tokens are taken from
+    pub source_token_account: AccountInfo<'info>,
+    /// The vault's token account for the deposit's mint
+    pub vault_token_account: AccountInfo<'info>,
+    /// The SPL token program
+    pub token_program: AccountInfo<'info>,
+}
+
+impl<'info> TopUpCpiAccounts<'info> {
+    /// The accounts in instruction order, as `builder::donate_to_deposit`
+    /// lists them
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        self.instruction(&crate::id(), 0, 0).accounts
+    }
+
+    fn instruction(&self, program_id: &Pubkey, deposit_id: u64, amount: u64) -> Instruction {
+        builder::donate_to_deposit(
+            program_id,
+            self.donor.key,
+            self.vault.key,
+            self.source_token_account.key,
+            self.vault_token_account.key,
+            deposit_id,
+            amount,
+        )
+    }
+
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.donor.clone(),
+            self.vault.clone(),
+            self.source_token_account.clone(),
+            self.vault_token_account.clone(),
+            self.token_program.clone(),
+        ]
+    }
+}
+
+/// Accounts of `ExtendLock`
+#[derive(Clone)]
+pub struct ExtendLockCpiAccounts<'info> {
+    /// The depositor of the tiered deposit
+    pub depositor: AccountInfo<'info>,
+    /// The vault account
+    pub vault: AccountInfo<'info>,
+    /// The clock sysvar
+    pub clock: AccountInfo<'info>,
+}
+
+impl<'info> ExtendLockCpiAccounts<'info> {
+    /// The accounts in instruction order, as `builder::extend_lock` lists them
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        self.instruction(&crate::id(), 0, LockTier::OneMonth).accounts
+    }
+
+    fn instruction(&self, program_id: &Pubkey, deposit_id: u64, new_tier: LockTier) -> Instruction {
+        builder::extend_lock(program_id, self.depositor.key, self.vault.key, deposit_id, new_tier)
+    }
+
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![self.depositor.clone(), self.vault.clone(), self.clock.clone()]
+    }
+}
+
+/// Deposit `amount` of the depositor's tokens until `unlock_time`
+pub fn deposit(
+    program_id: &Pubkey,
+    accounts: DepositCpiAccounts,
+    signers_seeds: &[&[&[u8]]],
+    amount: u64,
+    unlock_time: i64,
+    tag: [u8; 32],
+) -> ProgramResult {
+    let instruction = accounts.instruction(program_id, amount, unlock_time, tag);
+    invoke_with_signers(instruction, &accounts.to_account_infos(), signers_seeds)
+}
+
+/// Withdraw deposit `deposit_id` to the destination token account
+pub fn withdraw(
+    program_id: &Pubkey,
+    accounts: WithdrawCpiAccounts,
+    signers_seeds: &[&[&[u8]]],
+    deposit_id: u64,
+) -> ProgramResult {
+    let instruction = accounts.instruction(program_id, deposit_id);
+    invoke_with_signers(instruction, &accounts.to_account_infos(), signers_seeds)
+}
+
+/// Add `amount` of the donor's tokens to deposit `deposit_id`
+pub fn top_up(
+    program_id: &Pubkey,
+    accounts: TopUpCpiAccounts,
+    signers_seeds: &[&[&[u8]]],
+    deposit_id: u64,
+    amount: u64,
+) -> ProgramResult {
+    let instruction = accounts.instruction(program_id, deposit_id, amount);
+    invoke_with_signers(instruction, &accounts.to_account_infos(), signers_seeds)
+}
+
+/// Move tiered deposit `deposit_id` up to `new_tier`
+pub fn extend_lock(
+    program_id: &Pubkey,
+    accounts: ExtendLockCpiAccounts,
+    signers_seeds: &[&[&[u8]]],
+    deposit_id: u64,
+    new_tier: LockTier,
+) -> ProgramResult {
+    let instruction = accounts.instruction(program_id, deposit_id, new_tier);
+    invoke_with_signers(instruction, &accounts.to_account_infos(), signers_seeds)
+}
+
+// Invoke `instruction` over `infos`, in the same order as its accounts.
+// Accounts that signed the caller's instruction keep signing, e.g. a vault
+// keypair signing the payout from its token account
+fn invoke_with_signers(
+    mut instruction: Instruction,
+    infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    for (meta, info) in instruction.accounts.iter_mut().zip(infos) {
+        meta.is_signer |= info.is_signer;
+    }
+    invoke_signed(&instruction, infos, signers_seeds)
+}
@@ -0,0 +1,386 @@
+//! Instruction decoding for explorers and wallets
+
+use crate::{
+    builder::account_roles, AuthorityOnRenounce, DepositPolicy, ResolutionDestination, Vault, VaultConfig,
+    VaultError, VaultInstruction,
+};
+use solana_program::pubkey::Pubkey;
+use std::fmt;
+
+/// A parsed instruction with its accounts labelled by role
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedInstruction {
+    /// The parsed instruction
+    pub instruction: VaultInstruction,
+    /// Accounts paired with their role; accounts past the role table are "extra"
+    pub accounts: Vec<(&'static str, Pubkey)>,
+}
+
+/// Parse instruction data and name its accounts by position
+pub fn decode_instruction(data: &[u8], accounts: &[Pubkey]) -> Result<DecodedInstruction, VaultError> {
+    let instruction = VaultInstruction::unpack(data)?;
+    let roles = account_roles(&instruction);
+    let accounts = accounts
+        .iter()
+        .enumerate()
+        .map(|(index, key)| (roles.get(index).copied().unwrap_or("extra"), *key))
+        .collect();
+    Ok(DecodedInstruction { instruction, accounts })
+}
+
+impl DecodedInstruction {
+    /// The account with the given role, if present
+    pub fn account(&self, role: &str) -> Option<&Pubkey> {
+        self.accounts.iter().find(|(name, _)| *name == role).map(|(_, key)| key)
+    }
+}
+
+impl fmt::Display for DecodedInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use VaultInstruction::*;
+        match &self.instruction {
+            CreateVault { skip_registry, emergency_destination, owner_set, config, .. } => {
+                write!(f, "Create vault")?;
+                if *config != VaultConfig::default() {
+                    write!(f, " with initial settings")?;
+                }
+                if let Some(owner_set) = owner_set {
+                    write!(f, " owned by {} of {} members", owner_set.threshold, owner_set.members.len())?;
+                }
+                if let Some(emergency_destination) = emergency_destination {
+                    write!(f, " with emergency withdrawals to {}", emergency_destination)?;
+                }
+                if *skip_registry {
+                    write!(f, " (not registered)")?;
+                }
+                Ok(())
+            },
+            Deposit { amount, unlock_time, tag, .. } | DepositFor { amount, unlock_time, tag, .. } => {
+                write!(f, "Deposit {} tokens until {}{}", amount, format_date(*unlock_time), format_tag(tag))
+            },
+            DepositWithDuration { amount, lock_duration_secs, tag } => {
+                write!(f, "Deposit {} tokens for {}s{}", amount, lock_duration_secs, format_tag(tag))
+            },
+            DepositUiAmount { ui_amount, decimals_exponent, unlock_time, tag } => write!(
+                f,
+                "Deposit {} whole tokens ({} decimals) until {}{}",
+                ui_amount,
+                decimals_exponent,
+                format_date(*unlock_time),
+                format_tag(tag),
+            ),
+            DepositPair { amount_a, amount_b, unlock_time, tag } => write!(
+                f,
+                "Deposit a pair of {} and {} tokens until {}{}",
+                amount_a,
+                amount_b,
+                format_date(*unlock_time),
+                format_tag(tag),
+            ),
+            DepositMany { items } => {
+                let total: u128 = items.iter().map(|(amount, _, _)| *amount as u128).sum();
+                write!(f, "Deposit {} tokens in a batch of {} deposits", total, items.len())
+            },
+            DepositTiered { amount, tier, tag } => {
+                write!(f, "Deposit {} tokens for {:?}{}", amount, tier, format_tag(tag))
+            },
+            ExtendLock { deposit_id, new_tier } => {
+                write!(f, "Extend deposit #{} to {:?}", deposit_id, new_tier)
+            },
+            DepositUntilSlot { amount, unlock_slot, tag } => {
+                write!(f, "Deposit {} tokens until slot {}{}", amount, unlock_slot, format_tag(tag))
+            },
+            DepositWithPriceCondition { amount, unlock_time, tag, price_condition } => write!(
+                f,
+                "Deposit {} tokens until {} or price {}e{} on {}{}",
+                amount,
+                format_date(*unlock_time),
+                price_condition.threshold,
+                price_condition.expo,
+                price_condition.feed,
+                format_tag(tag),
+            ),
+            DepositWithAccountCondition { amount, unlock_time, tag, account_condition } => write!(
+                f,
+                "Deposit {} tokens until {} or {} bytes at offset {} of {} match{}",
+                amount,
+                format_date(*unlock_time),
+                account_condition.expected.len(),
+                account_condition.offset,
+                account_condition.account,
+                format_tag(tag),
+            ),
+            DepositWithHashlock { amount, unlock_time, tag, beneficiary, .. } => write!(
+                f,
+                "Deposit {} tokens claimable by {} with the secret, refundable after {}{}",
+                amount,
+                beneficiary,
+                format_date(*unlock_time),
+                format_tag(tag),
+            ),
+            ClaimWithPreimage { deposit_id, .. } => write!(f, "Claim deposit #{} with its secret", deposit_id),
+            DepositEscrow { amount, unlock_time, tag, counterparty } => write!(
+                f,
+                "Deposit {} tokens in escrow for {}, refundable after {}{}",
+                amount,
+                counterparty,
+                format_date(*unlock_time),
+                format_tag(tag),
+            ),
+            ReleaseEscrow { deposit_id } => write!(f, "Release escrow deposit #{} to its counterparty", deposit_id),
+            DepositWithGuardians { amount, unlock_time, tag, guardians, guardian_threshold } => write!(
+                f,
+                "Deposit {} tokens until {} recoverable by {} of {} guardians{}",
+                amount,
+                format_date(*unlock_time),
+                guardian_threshold,
+                guardians.len(),
+                format_tag(tag),
+            ),
+            ProposeRecovery { deposit_id, new_depositor } => {
+                write!(f, "Propose recovering deposit #{} to {}", deposit_id, new_depositor)
+            },
+            ApproveRecovery { deposit_id } => write!(f, "Approve recovery of deposit #{}", deposit_id),
+            ExecuteRecovery { deposit_id } => write!(f, "Execute recovery of deposit #{}", deposit_id),
+            VetoRecovery { deposit_id } => write!(f, "Veto recovery of deposit #{}", deposit_id),
+            Withdraw { deposit_id } => write!(f, "Withdraw deposit #{}", deposit_id),
+            WithdrawWithMemo { deposit_id, memo } => {
+                write!(f, "Withdraw deposit #{} with memo {:?}", deposit_id, String::from_utf8_lossy(memo))
+            },
+            WithdrawOnPrice { deposit_id } => write!(f, "Withdraw deposit #{} on price", deposit_id),
+            WithdrawOnCondition { deposit_id } => {
+                write!(f, "Withdraw deposit #{} on account condition", deposit_id)
+            },
+            WithdrawByTag { tag } => write!(f, "Withdraw matured deposits{}", format_tag(tag)),
+            DepositStakedSol { amount, unlock_time, validator_vote, tag } => write!(
+                f,
+                "Stake {} lamports with {} until {}{}",
+                amount,
+                validator_vote,
+                format_date(*unlock_time),
+                format_tag(tag),
+            ),
+            DeactivateStake { deposit_id } => write!(f, "Deactivate the stake of deposit #{}", deposit_id),
+            WithdrawStake { deposit_id } => write!(f, "Withdraw staked deposit #{}", deposit_id),
+            WithdrawSplit { deposit_id, splits } => {
+                write!(f, "Withdraw deposit #{} split {} ways", deposit_id, splits.len())
+            },
+            EmergencyWithdraw { deposit_id } => write!(f, "Emergency withdraw deposit #{}", deposit_id),
+            EmergencyWithdrawAll { depositor, mint } => {
+                write!(f, "Emergency withdraw every deposit of {} by {}", mint, depositor)
+            },
+            AnnounceSweepAll => write!(f, "Announce sweeping the whole vault"),
+            ReconcileStats => write!(f, "Fold the vault into the program stats"),
+            ExecuteSweepAll { mint } => write!(f, "Sweep the vault's entire balance of {}", mint),
+            SetCoOwner { co_owner, roles } => write!(f, "Grant co-owner {} roles {:#06b}", co_owner, roles),
+            RemoveCoOwner { co_owner } => write!(f, "Remove co-owner {}", co_owner),
+            ReleaseEarly { deposit_id } => write!(f, "Release deposit #{} early to its depositor", deposit_id),
+            SetRewardMints { reward_mint, weight_mint } => {
+                write!(f, "Reward deposits of {} in {}", weight_mint, reward_mint)
+            },
+            DistributeRewards { total: 0, .. } => write!(f, "Continue the reward distribution"),
+            DistributeRewards { mint, total } => write!(f, "Distribute {} reward tokens of {}", total, mint),
+            ClaimRewards { deposit_id } => write!(f, "Claim the rewards of deposit #{}", deposit_id),
+            ViewDeposit { deposit_id } => write!(f, "View deposit #{}", deposit_id),
+            LogVotingPower { depositor } => write!(f, "Log the voting power of {}", depositor),
+            GetVersion => write!(f, "Get the program version"),
+            CloseVault => write!(f, "Close vault"),
+            SetPositionNfts { enabled, .. } => write!(f, "{} position NFTs", enable(*enabled)),
+            WithdrawWithPermit { deposit_id, expiry } => {
+                write!(f, "Withdraw deposit #{} with permit expiring {}", deposit_id, format_date(*expiry))
+            },
+            SetEmergencyCooldown { cooldown } => write!(f, "Set emergency cooldown to {}s", cooldown),
+            SetRateLimit { rate_limit: Some(limit) } => {
+                write!(f, "Rate limit withdrawals to {} per {}s", limit.amount, limit.window_secs)
+            },
+            SetRateLimit { rate_limit: None } => write!(f, "Disable withdrawal rate limit"),
+            SetWithdrawDelay { delay } => write!(f, "Set withdraw delay to {}s", delay),
+            SetMinLockSlots { min_slots } => write!(f, "Set minimum slot lock to {} slots", min_slots),
+            SetPriceOracle { price_oracle: Some(config) } => {
+                write!(f, "Enable price unlocks with oracle program {}", config.program)
+            },
+            SetPriceOracle { price_oracle: None } => write!(f, "Disable price unlocks"),
+            RenounceOwnership { emergency_authority: Some(AuthorityOnRenounce::Clear) } => {
+                write!(f, "Renounce vault ownership and clear the emergency authority")
+            },
+            RenounceOwnership { .. } => write!(f, "Renounce vault ownership"),
+            EnableQueuedWithdrawals { deposit_id } => {
+                write!(f, "Enable queued withdrawals for deposit #{}", deposit_id)
+            },
+            RequestWithdraw { deposit_id } => write!(f, "Request withdrawal of deposit #{}", deposit_id),
+            ClaimWithdraw { deposit_id } => write!(f, "Claim withdrawal of deposit #{}", deposit_id),
+            CancelWithdrawRequest { deposit_id } => write!(f, "Cancel withdrawal request for deposit #{}", deposit_id),
+            SetEarlyWithdrawal { max_penalty_bps, treasury: Some(treasury) } => {
+                write!(f, "Allow early withdrawals with up to {} bps penalty to {}", max_penalty_bps, treasury)
+            },
+            SetEarlyWithdrawal { treasury: None, .. } => write!(f, "Disable early withdrawals"),
+            EarlyWithdraw { deposit_id } => write!(f, "Early withdraw deposit #{}", deposit_id),
+            SetPostUnlockGrace { grace } => write!(f, "Set post-unlock grace to {}s", grace),
+            SetDestinationChangeDelay { delay } => write!(f, "Set destination change delay to {}s", delay),
+            ChangeLockedDestination { deposit_id, new_destination } => {
+                write!(f, "Change locked destination of deposit #{} to {}", deposit_id, new_destination)
+            },
+            SetBeneficiaryChangeDelay { delay } => write!(f, "Set beneficiary change delay to {}s", delay),
+            ProposeBeneficiary { deposit_id, new_beneficiary } => {
+                write!(f, "Propose {} as beneficiary of deposit #{}", new_beneficiary, deposit_id)
+            },
+            CommitBeneficiary { deposit_id } => write!(f, "Commit beneficiary of deposit #{}", deposit_id),
+            CancelBeneficiaryChange { deposit_id } => {
+                write!(f, "Cancel beneficiary change of deposit #{}", deposit_id)
+            },
+            CrankWithdraw { deposit_id } => write!(f, "Crank withdrawal of deposit #{}", deposit_id),
+            SetCrankBounty { bounty_bps } => write!(f, "Set crank bounty to {} bps", bounty_bps),
+            SetDepositPolicy { policy: DepositPolicy::Open } => write!(f, "Open deposits to anyone"),
+            SetDepositPolicy { policy: DepositPolicy::Allowlist } => write!(f, "Restrict deposits to the allowlist"),
+            AddAllowedDepositor { depositor } => write!(f, "Allow depositor {}", depositor),
+            RemoveAllowedDepositor { depositor } => write!(f, "Remove depositor {} from the allowlist", depositor),
+            BlockDepositor { depositor } => write!(f, "Block depositor {}", depositor),
+            UnblockDepositor { depositor } => write!(f, "Unblock depositor {}", depositor),
+            SetMintLimit { mint, limit: Some(limit) } => write!(f, "Limit mint {} to {} tokens", mint, limit),
+            SetMintLimit { mint, limit: None } => write!(f, "Lift the limit on mint {}", mint),
+            SetTvlCap { cap: Some(cap) } => write!(f, "Cap vault TVL at {} tokens", cap),
+            SetTvlCap { cap: None } => write!(f, "Lift the vault TVL cap"),
+            SetDepositFee { lamports, destination } => {
+                write!(f, "Set deposit fee to {} lamports paid to {}", lamports, destination)
+            },
+            InitializeConfig { protocol_fee_bps, fee_destination } => write!(
+                f,
+                "Initialize protocol config with a {} bps fee to {}",
+                protocol_fee_bps, fee_destination,
+            ),
+            UpdateConfig { protocol_fee_bps, fee_destination } => {
+                write!(f, "Set protocol fee to {} bps paid to {}", protocol_fee_bps, fee_destination)
+            },
+            ProposeConfigAdmin { new_admin: Some(admin) } => write!(f, "Propose {} as config admin", admin),
+            ProposeConfigAdmin { new_admin: None } => write!(f, "Withdraw the config admin proposal"),
+            AcceptConfigAdmin => write!(f, "Accept config admin"),
+            SetFreezeAuthority { authority: Some(authority) } => write!(f, "Set freeze authority to {}", authority),
+            SetFreezeAuthority { authority: None } => write!(f, "Clear freeze authority"),
+            SetEmergencyAuthority { authority: Some(authority) } => {
+                write!(f, "Set emergency authority to {}", authority)
+            },
+            SetEmergencyAuthority { authority: None } => write!(f, "Clear emergency authority"),
+            SetInsurance { insurance_bps: 0, .. } => write!(f, "Disable deposit insurance"),
+            SetInsurance { insurance_bps, insurance_pool } => {
+                write!(f, "Pay {} bps of each deposit into insurance pool {}", insurance_bps, insurance_pool)
+            },
+            SetPositionIndex { enabled: true } => write!(f, "Enable position indexes"),
+            SetPositionIndex { enabled: false } => write!(f, "Disable position indexes"),
+            SetAllowedCallers { allowed_callers: None, .. } => write!(f, "Allow deposits from any caller"),
+            SetAllowedCallers { allowed_callers: Some(callers), reject_top_level } => {
+                let callers: Vec<String> = callers.iter().map(|caller| caller.to_string()).collect();
+                write!(f, "Allow CPI deposits only from [{}]", callers.join(", "))?;
+                if *reject_top_level {
+                    write!(f, ", rejecting direct deposits")?;
+                }
+                Ok(())
+            },
+            FreezeDeposit { deposit_id } => write!(f, "Freeze deposit #{}", deposit_id),
+            ThawDeposit { deposit_id } => write!(f, "Thaw deposit #{}", deposit_id),
+            ResolveFrozenDeposit { deposit_id, destination_kind } => {
+                let destination = match destination_kind {
+                    ResolutionDestination::ReturnToDepositor => "the depositor",
+                    ResolutionDestination::Arbitration => "arbitration",
+                };
+                write!(f, "Resolve frozen deposit #{} to {}", deposit_id, destination)
+            },
+            RevokeVaultDelegate => write!(f, "Revoke vault token account delegate"),
+            SweepSurplus { mint } => write!(f, "Sweep surplus tokens of mint {}", mint),
+            SweepLamports => write!(f, "Sweep vault lamports above the rent-exempt minimum"),
+            SetGcDelay { gc_delay: Some(gc_delay) } => {
+                write!(f, "Allow removing deposit records {} seconds after withdrawal", gc_delay)
+            },
+            SetGcDelay { gc_delay: None } => write!(f, "Keep every deposit record"),
+            GcDeposits { max } => write!(f, "Remove up to {} withdrawn deposit records", max),
+            CloseToNewDeposits => write!(f, "Close the vault to new deposits"),
+            ReopenDeposits => write!(f, "Reopen the vault to new deposits"),
+            InitializeGlobalState => write!(f, "Initialize the global state"),
+            SetGlobalHalt { halted: true } => write!(f, "Halt deposits program-wide"),
+            SetGlobalHalt { halted: false } => write!(f, "Resume deposits program-wide"),
+            ProposeGlobalAuthority { new_authority: Some(authority) } => {
+                write!(f, "Propose {} as global authority", authority)
+            },
+            ProposeGlobalAuthority { new_authority: None } => write!(f, "Withdraw the global authority proposal"),
+            AcceptGlobalAuthority => write!(f, "Accept global authority"),
+            WriteSnapshot => write!(f, "Write the vault snapshot"),
+            SetOwnerApprovedSplits { enabled } => {
+                write!(f, "{} owner-approved split withdrawals", enable(*enabled))
+            },
+            SetUniqueTags { enabled } => write!(f, "{} unique deposit tags", enable(*enabled)),
+            SetUnlockGranularity { granularity: 0 } => write!(f, "Stop rounding unlock times"),
+            SetUnlockGranularity { granularity } => write!(f, "Round unlock times up to {}s", granularity),
+            SetVaultTokenCloseAuthority => write!(f, "Clear vault token account close authority"),
+            SetWithdrawHook { hook_program: Some(hook_program) } => {
+                write!(f, "Call {} after each withdrawal", hook_program)
+            },
+            SetWithdrawHook { hook_program: None } => write!(f, "Stop calling a withdraw hook"),
+            CloneVault { seed_index } => write!(f, "Clone a vault's configuration into vault #{}", seed_index),
+            CreateVaultAndDeposit { amount, unlock_time, tag, .. } => write!(
+                f,
+                "Create vault and deposit {} tokens until {}{}",
+                amount,
+                format_date(*unlock_time),
+                format_tag(tag),
+            ),
+            DonateToDeposit { deposit_id, amount } => write!(f, "Donate {} tokens to deposit {}", amount, deposit_id),
+            SetDonationsDisabled { disabled } => write!(f, "{} donations", enable(!*disabled)),
+            CloseVaultTokenAccount { mint } => write!(f, "Close the vault's token account for {}", mint),
+            SetAutoClose { enabled } => write!(f, "{} auto-closing emptied token accounts", enable(*enabled)),
+        }
+    }
+}
+
+impl fmt::Display for Vault {
+    /// One-line summary of the vault's open deposits and lifetime totals
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let open = self.deposits.iter().filter(|deposit| !deposit.is_withdrawn()).count();
+        let intake = if self.accepting_deposits() { "" } else { ", closed to new deposits" };
+        write!(
+            f,
+            "Vault owned by {}: {} open deposits, {} tokens locked{}; lifetime {} deposits, {} deposited, \
+             {} withdrawn, {} emergency withdrawals",
+            self.owner,
+            open,
+            self.total_locked,
+            intake,
+            self.lifetime_deposits_created,
+            self.lifetime_amount_deposited,
+            self.lifetime_amount_withdrawn,
+            self.lifetime_emergency_withdrawals,
+        )
+    }
+}
+
+fn enable(enabled: bool) -> &'static str {
+    if enabled {
+        "Enable"
+    } else {
+        "Disable"
+    }
+}
+
+// Render a tag as ` (tag: text)`, or nothing for an empty tag
+fn format_tag(tag: &[u8; 32]) -> String {
+    let text = String::from_utf8_lossy(tag);
+    let text = text.trim_end_matches('\0');
+    if text.is_empty() {
+        String::new()
+    } else {
+        format!(" (tag: {})", text)
+    }
+}
+
+// Render a unix timestamp as a UTC `YYYY-MM-DD` date
+fn format_date(timestamp: i64) -> String {
+    // Civil-from-days conversion (proleptic Gregorian calendar)
+    let days = timestamp.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -0,0 +1,195 @@
+//! Machine-readable byte layouts of the program accounts (enabled with the `layout` feature)
+//!
+//! Only the leading fixed-size fields are given offsets; everything after the
+//! first variable-length field is described by the Borsh encoding rules.
+
+use serde_json::{json, Value};
+use std::mem::size_of;
+
+/// A fixed-offset field: name, Borsh type and size in bytes
+pub type FieldSpec = (&'static str, &'static str, usize);
+
+const PUBKEY_LEN: usize = size_of::<solana_program::pubkey::Pubkey>();
+
+/// Fixed header of `Vault`, in serialization order. `deposits_len` is the
+/// little-endian u32 length prefix of the deposits vector.
+pub const VAULT_HEADER: &[FieldSpec] = &[
+    ("owner", "pubkey", PUBKEY_LEN),
+    ("deposit_count", "u64", 8),
+    ("deposits_len", "u32", 4),
+];
+
+/// Fixed prefix of each `Deposit`, in serialization order
+pub const DEPOSIT_HEADER: &[FieldSpec] = &[
+    ("id", "u64", 8),
+    ("depositor", "pubkey", PUBKEY_LEN),
+    ("token_mint", "pubkey", PUBKEY_LEN),
+    ("amount", "u64", 8),
+    ("unlock_time", "i64", 8),
+    ("status", "u8", 1),
+    ("tag", "[u8; 32]", 32),
+    ("created_at", "i64", 8),
+];
+
+/// Fields of `Deposit` after the fixed prefix, in serialization order
+const DEPOSIT_TAIL: &[(&str, &str)] = &[
+    ("position_mint", "option<pubkey>"),
+    ("permit_nonce", "u64"),
+    ("queued_withdrawals", "bool"),
+    ("withdraw_requested_at", "option<i64>"),
+    ("locked_destination", "option<pubkey>"),
+    ("pending_destination", "option<(pubkey, i64)>"),
+    ("beneficiary", "option<pubkey>"),
+    ("pending_beneficiary", "option<(pubkey, i64)>"),
+    ("no_crank", "bool"),
+    ("frozen", "bool"),
+    ("resolution", "option<u8 enum>"),
+    ("last_action_slot", "u64"),
+    ("linked_deposit", "option<u64>"),
+    ("lock_tier", "option<u8>"),
+    ("unlock_slot", "option<u64>"),
+    ("price_condition", "option<PriceCondition>"),
+    ("account_condition", "option<AccountCondition>"),
+    ("hashlock", "option<[u8; 32]>"),
+    ("guardians", "vec<pubkey>"),
+    ("guardian_threshold", "u8"),
+    ("pending_recovery", "option<PendingRecovery>"),
+    ("emergency_requires_depositor", "bool"),
+    ("emergency_paid_to", "option<pubkey>"),
+    ("premium_paid", "u64"),
+    ("withdrawn_at", "option<i64>"),
+    ("stake_account", "option<pubkey>"),
+    ("owner_can_release_early", "bool"),
+    ("pending_rewards", "u64"),
+    ("escrow_counterparty", "option<pubkey>"),
+    ("donations_received", "u64"),
+];
+
+/// Fields of `Vault` after the deposits vector, in serialization order
+const VAULT_TAIL: &[(&str, &str)] = &[
+    ("reentrancy_guard", "bool"),
+    ("emergency_authority", "option<pubkey>"),
+    ("position_nfts", "bool"),
+    ("position_nft_uri", "string"),
+    ("emergency_cooldown", "i64"),
+    ("last_emergency_withdraw", "i64"),
+    ("rate_limit", "option<RateLimit>"),
+    ("withdrawal_windows", "vec<WithdrawalWindow>"),
+    ("withdraw_delay", "i64"),
+    ("max_penalty_bps", "u16"),
+    ("treasury", "option<pubkey>"),
+    ("post_unlock_grace", "i64"),
+    ("destination_change_delay", "i64"),
+    ("beneficiary_change_delay", "i64"),
+    ("crank_bounty_bps", "u16"),
+    ("deposit_policy", "u8 enum"),
+    ("allowed_depositors", "vec<pubkey>"),
+    ("blocked_depositors", "vec<pubkey>"),
+    ("mints", "vec<MintEntry>"),
+    ("tvl_cap", "option<u64>"),
+    ("total_locked", "u64"),
+    ("deposit_fee_lamports", "u64"),
+    ("deposit_fee_destination", "pubkey"),
+    ("freeze_authority", "option<pubkey>"),
+    ("arbitration_destination", "option<pubkey>"),
+    ("min_lock_slots", "u64"),
+    ("price_oracle", "option<PriceOracleConfig>"),
+    ("tag_totals", "vec<TagTotal>"),
+    ("other_tags_total", "u64"),
+    ("renounced", "bool"),
+    ("emergency_destination", "option<pubkey>"),
+    ("insurance_bps", "u16"),
+    ("insurance_pool", "pubkey"),
+    ("insurance_premiums", "u64"),
+    ("position_index", "bool"),
+    ("allowed_callers", "option<vec<pubkey>>"),
+    ("reject_top_level", "bool"),
+    ("gc_delay", "option<i64>"),
+    ("lifetime_deposits_created", "u64"),
+    ("lifetime_amount_deposited", "u128"),
+    ("lifetime_amount_withdrawn", "u128"),
+    ("lifetime_emergency_withdrawals", "u64"),
+    ("idempotency_keys", "vec<IdempotencyKey>"),
+    ("idempotency_cursor", "u8"),
+    ("deposits_closed", "bool"),
+    ("owner_approved_splits", "bool"),
+    ("sweep_announced_at", "option<i64>"),
+    ("owner_set", "option<OwnerSet>"),
+    ("co_owners", "vec<(pubkey, u8)>"),
+    ("reward_mint", "option<pubkey>"),
+    ("reward_weight_mint", "option<pubkey>"),
+    ("reward_carry", "u64"),
+    ("reward_distribution", "option<RewardDistribution>"),
+    ("unique_tags", "bool"),
+    ("unlock_granularity", "i64"),
+    ("emergency_evacuation", "option<EmergencyEvacuation>"),
+    ("stats_reconciled", "bool"),
+    ("hook_program", "option<pubkey>"),
+    ("donations_disabled", "bool"),
+    ("auto_close", "bool"),
+    ("is_initialized", "bool"),
+];
+
+/// Offset of `name` within `fields`, if it is one of them
+pub fn field_offset(fields: &[FieldSpec], name: &str) -> Option<usize> {
+    let mut offset = 0;
+    for (field, _, size) in fields {
+        if *field == name {
+            return Some(offset);
+        }
+        offset += size;
+    }
+    None
+}
+
+fn fixed_fields(fields: &[FieldSpec]) -> Value {
+    let mut offset = 0;
+    let fields: Vec<Value> = fields
+        .iter()
+        .map(|(name, ty, size)| {
+            let field = json!({ "name": name, "type": ty, "offset": offset, "size": size });
+            offset += size;
+            field
+        })
+        .collect();
+    Value::Array(fields)
+}
+
+fn fixed_size(fields: &[FieldSpec]) -> usize {
+    fields.iter().map(|(_, _, size)| size).sum()
+}
+
+fn variable_fields(fields: &[(&str, &str)]) -> Value {
+    Value::Array(fields.iter().map(|(name, ty)| json!({ "name": name, "type": ty })).collect())
+}
+
+/// Describe the `Vault` and `Deposit` account layouts as JSON
+pub fn account_layouts() -> Value {
+    json!({
+        "encoding": {
+            "integers": "little-endian",
+            "bool": "1 byte, 0 or 1",
+            "pubkey": "32 bytes",
+            "option<T>": "1-byte tag (0 = None, 1 = Some) followed by T when Some",
+            "vec<T>": "u32 length followed by that many T",
+            "string": "u32 byte length followed by UTF-8 bytes",
+            "u8 enum": "1-byte variant index",
+        },
+        "Vault": {
+            "fixedHeader": fixed_fields(VAULT_HEADER),
+            "fixedHeaderSize": fixed_size(VAULT_HEADER),
+            "deposits": {
+                "offset": fixed_size(VAULT_HEADER),
+                "count": "deposits_len",
+                "element": "Deposit",
+                "note": "Deposits are variable-length; walk them in order to find later fields",
+            },
+            "afterDeposits": variable_fields(VAULT_TAIL),
+        },
+        "Deposit": {
+            "fixedHeader": fixed_fields(DEPOSIT_HEADER),
+            "fixedHeaderSize": fixed_size(DEPOSIT_HEADER),
+            "afterFixedHeader": variable_fields(DEPOSIT_TAIL),
+        },
+    })
+}
@@ -2,22 +2,144 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
-    entrypoint,
     entrypoint::ProgramResult,
+    hash::hash,
+    instruction::{get_stack_height, AccountMeta, Instruction, TRANSACTION_LEVEL_STACK_HEIGHT},
+    log::sol_log_data,
     msg,
     program_error::ProgramError,
     pubkey::Pubkey,
-    sysvar::Sysvar,
-    program::{invoke, invoke_signed},
+    sysvar::{
+        instructions::{load_current_index_checked, load_instruction_at_checked},
+        Sysvar,
+    },
+    program::{invoke, invoke_signed, set_return_data},
+    ed25519_program,
+    program_option::COption,
+    program_pack::Pack,
+    rent::Rent,
+    stake::{self, instruction as stake_instruction, state::{Authorized, Lockup, StakeState}},
+    system_instruction,
+    system_program,
+    vote,
+};
+use spl_token::{
+    instruction::AuthorityType,
+    state::{Account as TokenAccount, AccountState, Mint, Multisig},
 };
-use spl_token::state::Account as TokenAccount;
 use std::convert::TryFrom;
 
+// Log a message. Messages with arguments are formatted only with the
+// `verbose-logs` feature; without it only static messages are logged and
+// clients rely on the `vault_event!` data instead
+macro_rules! vault_log {
+    ($message:literal) => {
+        msg!($message)
+    };
+    ($format:literal, $($arg:expr),+ $(,)?) => {{
+        #[cfg(feature = "verbose-logs")]
+        msg!($format, $($arg),+);
+        #[cfg(not(feature = "verbose-logs"))]
+        let _ = ($(&$arg,)+);
+    }};
+}
+
+// Emit a structured event with `sol_log_data`: the event name followed by
+// its fields as raw bytes (little-endian integers, pubkeys)
+macro_rules! vault_event {
+    ($name:literal $(, $field:expr)* $(,)?) => {
+        sol_log_data(&[&$name[..] $(, AsRef::<[u8]>::as_ref(&$field))*])
+    };
+}
+
+pub mod builder;
+mod checks;
+pub mod cpi;
+pub mod decode;
+pub mod oracle;
+pub mod preflight;
+#[cfg(feature = "time-display")]
+pub mod time_display;
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(feature = "layout")]
+pub mod layout;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "anchor")]
+pub mod anchor;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+
 // Program entrypoint
-entrypoint!(process_instruction);
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+// Program ID, chosen per cluster with the `mainnet`, `devnet` (the default)
+// and `localnet` features, or read from `VAULT_PROGRAM_ID` at build time with
+// `custom-program-id` for forks deploying under their own id
+
+/// Program id of the mainnet deployment
+pub const MAINNET_PROGRAM_ID: Pubkey = solana_program::pubkey!("3ZwVpaHexF49RCJS89UGV4EV8F53BjMEr8gP7W83sVax");
+
+/// Program id of the devnet deployment
+pub const DEVNET_PROGRAM_ID: Pubkey = solana_program::pubkey!("F6MGs86NnKbKEwsJgMpmMD757NPjZap4yKZYXKwWK4HD");
+
+/// Program id for local validators and tests; its keypair is
+/// `deploy/localnet-keypair.json`
+pub const LOCALNET_PROGRAM_ID: Pubkey = solana_program::pubkey!("GkJndT4E9uN8Zf8Q87nLevXZqnmDkQXCasxjkxGJFGsg");
+
+#[cfg(any(
+    all(feature = "mainnet", feature = "devnet"),
+    all(feature = "mainnet", feature = "localnet"),
+    all(feature = "devnet", feature = "localnet"),
+    all(feature = "custom-program-id", any(feature = "mainnet", feature = "devnet", feature = "localnet")),
+))]
+compile_error!("enable at most one of the `mainnet`, `devnet`, `localnet` and `custom-program-id` features");
+
+#[cfg(feature = "mainnet")]
+solana_program::declare_id!(MAINNET_PROGRAM_ID);
+
+#[cfg(feature = "localnet")]
+solana_program::declare_id!(LOCALNET_PROGRAM_ID);
 
-// Program ID
-solana_program::declare_id!("TimeLockedVault");
+#[cfg(feature = "custom-program-id")]
+solana_program::declare_id!(parse_program_id(env!("VAULT_PROGRAM_ID")));
+
+#[cfg(not(any(feature = "mainnet", feature = "localnet", feature = "custom-program-id")))]
+solana_program::declare_id!(DEVNET_PROGRAM_ID);
+
+/// Decode a base58 program id in a const context; with `custom-program-id`
+/// a malformed `VAULT_PROGRAM_ID` fails the build
+pub const fn parse_program_id(id: &str) -> Pubkey {
+    const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    let input = id.as_bytes();
+    let mut bytes = [0u8; 32];
+    let mut i = 0;
+    while i < input.len() {
+        let mut digit = 0;
+        while digit < ALPHABET.len() && ALPHABET[digit] != input[i] {
+            digit += 1;
+        }
+        if digit == ALPHABET.len() {
+            panic!("program id is not base58");
+        }
+        // bytes = bytes * 58 + digit, big-endian
+        let mut carry = digit as u32;
+        let mut j = bytes.len();
+        while j > 0 {
+            j -= 1;
+            carry += bytes[j] as u32 * 58;
+            bytes[j] = carry as u8;
+            carry >>= 8;
+        }
+        if carry != 0 {
+            panic!("program id is longer than 32 bytes");
+        }
+        i += 1;
+    }
+    Pubkey::new_from_array(bytes)
+}
 
 // Error codes
 #[derive(Debug, thiserror::Error)]
@@ -54,6 +176,333 @@ pub enum VaultError {
     
     #[error("Math overflow")]
     MathOverflow,
+    
+    #[error("Owner registry is full")]
+    RegistryFull,
+    
+    #[error("Only the vault owner can perform this action")]
+    NotVaultOwner,
+    
+    #[error("Vault still holds active deposits")]
+    VaultNotEmpty,
+    
+    #[error("Invalid position NFT account")]
+    InvalidPositionAccount,
+    
+    #[error("Withdrawal permit signature is missing or invalid")]
+    InvalidPermit,
+    
+    #[error("Withdrawal permit has expired")]
+    PermitExpired,
+    
+    #[error("Emergency withdrawal cooldown has not elapsed")]
+    EmergencyCooldownActive,
+    
+    #[error("Withdrawal exceeds the rate limit for the current window")]
+    RateLimited,
+    
+    #[error("Deposit uses queued withdrawals: request then claim")]
+    WithdrawalQueueRequired,
+    
+    #[error("No withdrawal request is pending for this deposit")]
+    NoPendingWithdrawal,
+    
+    #[error("A withdrawal request is already pending for this deposit")]
+    WithdrawalAlreadyRequested,
+    
+    #[error("Withdrawal delay has not elapsed since the request")]
+    WithdrawalDelayActive,
+    
+    #[error("Early withdrawals are not enabled for this vault")]
+    EarlyWithdrawalDisabled,
+    
+    #[error("Treasury account does not match the vault's treasury")]
+    InvalidTreasuryAccount,
+    
+    #[error("Deposit is in its post-unlock grace period")]
+    GracePeriodActive,
+    
+    #[error("Destination does not match the deposit's locked destination")]
+    InvalidDestinationAccount,
+    
+    #[error("Timelock on this change has not elapsed")]
+    TimelockNotElapsed,
+    
+    #[error("No beneficiary change is pending for this deposit")]
+    NoPendingBeneficiaryChange,
+    
+    #[error("Deposit opted out of cranked withdrawals")]
+    CrankDisabled,
+    
+    #[error("Invalid crank bounty account")]
+    InvalidBountyAccount,
+    
+    #[error("Depositor is not on the vault's allowlist")]
+    DepositorNotAllowed,
+    
+    #[error("Depositor allowlist is full")]
+    AllowlistFull,
+    
+    #[error("Depositor is blocked from this vault")]
+    DepositorBlocked,
+    
+    #[error("Depositor blocklist is full")]
+    BlocklistFull,
+    
+    #[error("Deposit would exceed the vault's limit for this mint")]
+    MintLimitExceeded,
+    
+    #[error("Vault tracks too many mints")]
+    TooManyMints,
+    
+    #[error("Deposit would exceed the vault's TVL cap")]
+    VaultCapReached,
+    
+    #[error("Only the protocol config admin can perform this action")]
+    NotConfigAdmin,
+    
+    #[error("No admin change is pending for the protocol config")]
+    NoPendingAdminChange,
+    
+    #[error("Fee destination does not match the protocol config")]
+    InvalidFeeDestination,
+    
+    #[error("Deposit is frozen")]
+    DepositFrozen,
+    
+    #[error("Deposit is not frozen")]
+    DepositNotFrozen,
+    
+    #[error("Vault has no arbitration destination")]
+    ArbitrationNotConfigured,
+    
+    #[error("Vault token accounts must not have a delegate")]
+    DelegatedAccountNotAllowed,
+    
+    #[error("Vault token account is not the vault's or has a foreign close authority")]
+    InvalidVaultTokenAccount,
+    
+    #[error("Token account is frozen")]
+    TokenAccountFrozen,
+    
+    #[error("Decimals do not match the token mint")]
+    DecimalsMismatch,
+    
+    #[error("Deposit cannot be withdrawn or cancelled in the slot it was made")]
+    SameSlotAction,
+    
+    #[error("Vault account is not initialized")]
+    UninitializedVault,
+    
+    #[error("Vault account data is corrupt")]
+    CorruptVaultData,
+    
+    #[error("Token account mint does not match the deposit")]
+    TokenMintMismatch,
+    
+    #[error("Deposit amount was not set")]
+    MissingDepositAmount,
+    
+    #[error("Deposit unlock time was not set")]
+    MissingUnlockTime,
+    
+    #[error("Deposit tag is longer than 32 bytes")]
+    TagTooLong,
+    
+    #[error("Lock can only be extended to a higher tier")]
+    InvalidLockTier,
+    
+    #[error("Price-conditioned unlocks are not enabled for this vault")]
+    PriceOracleDisabled,
+    
+    #[error("Invalid price feed account")]
+    InvalidPriceFeed,
+    
+    #[error("Oracle price is stale")]
+    StalePrice,
+    
+    #[error("Oracle price confidence interval is too wide")]
+    PriceConfidenceTooWide,
+    
+    #[error("Oracle price is below the unlock threshold")]
+    PriceThresholdNotMet,
+    
+    #[error("Invalid account condition or condition account")]
+    InvalidAccountCondition,
+    
+    #[error("Condition account does not hold the expected bytes")]
+    AccountConditionNotMet,
+    
+    #[error("Condition offset is past the end of the account data")]
+    ConditionOutOfRange,
+    
+    #[error("Preimage does not match the deposit's hashlock")]
+    InvalidPreimage,
+    
+    #[error("Invalid recovery guardians or threshold")]
+    InvalidGuardians,
+    
+    #[error("Signer is not a guardian of this deposit")]
+    NotGuardian,
+    
+    #[error("A recovery is already pending for this deposit")]
+    RecoveryAlreadyPending,
+    
+    #[error("No recovery is pending for this deposit")]
+    NoPendingRecovery,
+    
+    #[error("Guardian has already approved this recovery")]
+    DuplicateApproval,
+    
+    #[error("Recovery has not reached its approval threshold")]
+    RecoveryNotApproved,
+    
+    #[error("The vault owner renounced ownership")]
+    OwnershipRenounced,
+    
+    #[error("Renouncing must choose whether to keep the emergency authority")]
+    EmergencyAuthorityChoiceRequired,
+    
+    #[error("Invalid insurance pool account")]
+    InvalidInsurancePool,
+    
+    #[error("Position index is full")]
+    PositionIndexFull,
+    
+    #[error("Position index doesn't match the vault's deposits")]
+    PositionIndexMismatch,
+    
+    #[error("The calling program is not allowed to use this vault")]
+    CallerNotAllowed,
+    
+    #[error("The vault token account holds no tokens beyond its deposits")]
+    NoSurplus,
+    
+    #[error("The vault doesn't allow garbage collection of deposit records")]
+    GcDisabled,
+    
+    #[error("A deposit with this idempotency key already succeeded")]
+    DuplicateDeposit,
+    
+    #[error("The vault is closed to new deposits")]
+    DepositsClosed,
+    
+    #[error("Deposits are halted program-wide")]
+    DepositsHalted,
+    
+    #[error("Only the global authority can perform this action")]
+    NotGlobalAuthority,
+    
+    #[error("No authority change is pending for the global state")]
+    NoPendingAuthorityChange,
+    
+    #[error("The vault holds too many deposit records to summarize")]
+    VaultTooLargeToScan,
+    
+    #[error("Withdrawal splits must be non-zero and sum to the deposit amount")]
+    InvalidSplits,
+    
+    #[error("No split destination belongs to the depositor")]
+    SplitNeedsDepositorDestination,
+    
+    #[error("Memo must be 1 to 128 bytes of UTF-8")]
+    InvalidMemo,
+    
+    #[error("The last account is not the SPL Memo program")]
+    InvalidMemoProgram,
+    
+    #[error("Staked deposits are paid out with DeactivateStake and WithdrawStake")]
+    StakedDeposit,
+    
+    #[error("Deposit is not a staked deposit")]
+    NotStakedDeposit,
+    
+    #[error("Invalid stake account")]
+    InvalidStakeAccount,
+    
+    #[error("No sweep of the vault has been announced")]
+    SweepNotAnnounced,
+    
+    #[error("A sweep of the vault is already announced")]
+    SweepAlreadyAnnounced,
+    
+    #[error("The sweep announcement delay has not elapsed")]
+    SweepDelayNotElapsed,
+    
+    #[error("The vault has no emergency destination to sweep to")]
+    NoEmergencyDestination,
+    
+    #[error("Owner set needs 1 to 11 distinct members and a threshold between 1 and their count")]
+    InvalidOwnerSet,
+    
+    #[error("Not enough members of the owner set signed")]
+    OwnerThresholdNotMet,
+    
+    #[error("The co-owner lacks the role this instruction needs")]
+    MissingCoOwnerRole,
+    
+    #[error("The vault already has the maximum number of co-owners")]
+    TooManyCoOwners,
+    
+    #[error("Co-owners need known, non-empty role bits and cannot be the vault owner")]
+    InvalidCoOwner,
+    
+    #[error("The depositor did not allow the vault owner to release this deposit early")]
+    EarlyReleaseNotAllowed,
+    
+    #[error("The vault has no reward mints set")]
+    RewardsNotConfigured,
+    
+    #[error("A reward distribution is already in progress")]
+    RewardDistributionInProgress,
+    
+    #[error("No reward distribution is in progress")]
+    NoRewardDistribution,
+    
+    #[error("No active deposit of the weight mint to distribute rewards to")]
+    NoRewardWeight,
+    
+    #[error("The deposit has no pending rewards")]
+    NoPendingRewards,
+    
+    #[error("Rewards are still owed or being distributed")]
+    RewardsOutstanding,
+    
+    #[error("The delegate's approved amount does not cover the deposit")]
+    InsufficientDelegation,
+    
+    #[error("A delegate can only deposit for the source account's owner, on terms that pay only the owner")]
+    DelegateTermsNotAllowed,
+    
+    #[error("Invalid vault configuration")]
+    InvalidVaultConfig,
+    
+    #[error("The depositor already has an active deposit with this tag")]
+    DuplicateTag,
+    
+    #[error("Unlock granularity out of range")]
+    InvalidUnlockGranularity,
+    
+    #[error("Another depositor or mint is being evacuated")]
+    EvacuationInProgress,
+    
+    #[error("The vault is already counted in the program stats")]
+    StatsAlreadyReconciled,
+    
+    #[error("Deposit is not an escrow with this counterparty")]
+    NotEscrowDeposit,
+    
+    #[error("The vault's withdraw hook program is missing")]
+    WithdrawHookMissing,
+    
+    #[error("Too many accounts for the withdraw hook")]
+    TooManyHookAccounts,
+    
+    #[error("The vault doesn't accept donations")]
+    DonationsDisabled,
+    
+    #[error("The mint still has open deposits or tokens in the vault")]
+    MintStillActive,
 }
 
 impl From<VaultError> for ProgramError {
@@ -68,21 +517,52 @@ pub enum VaultInstruction {
     /// Create a new vault
     /// 
     /// Accounts expected:
-    /// 0. `[signer]` The vault creator/owner
+    /// 0. `[signer]` The vault owner (writable when it pays the registry rent)
     /// 1. `[writable]` The vault account to be created
-    /// 2. `[]` System program
-    CreateVault,
+    /// 2. `[]` System program (creates the registry)
+    /// 3. `[writable]` The owner's registry account (omitted when `skip_registry` is set)
+    /// 4. `[signer, writable]` Optional payer of the registry rent in place of
+    ///    the owner; it gains no authority over the vault
+    CreateVault {
+        /// Don't record the vault in the owner's registry (saves the registry rent)
+        skip_registry: bool,
+        /// Escrow token account frozen deposits can be sent to; fixed for the vault's lifetime
+        arbitration_destination: Option<Pubkey>,
+        /// Wallet whose associated token accounts receive every emergency
+        /// withdrawal instead of the depositor; fixed for the vault's lifetime
+        emergency_destination: Option<Pubkey>,
+        /// M-of-N owner set taking the place of the creating wallet in
+        /// owner-gated instructions; fixed for the vault's lifetime
+        owner_set: Option<OwnerSet>,
+        /// Settings applied before the vault is first stored; all-`None`
+        /// leaves every setting at its default
+        config: VaultConfig,
+    },
     
     /// Deposit tokens into the vault
     /// 
     /// Accounts expected:
-    /// 0. `[signer]` The depositor
+    /// 0. `[signer]` The depositor (a program signs for its PDA via `invoke_signed`)
     /// 1. `[writable]` The vault account
     /// 2. `[writable]` The token account to transfer from (owned by depositor)
     /// 3. `[writable]` The token account to transfer to (vault's token account)
     /// 4. `[]` The token program
-    /// 5. `[]` The system program
+    /// 5. `[]` The system program (pays the deposit fee and creates position NFT accounts)
     /// 6. `[]` The clock sysvar
+    /// 
+    /// When the vault issues position NFTs (the depositor must also be writable to fund them):
+    /// 7. `[writable]` The position mint (PDA of `["position", vault, deposit_id]`)
+    /// 8. `[writable]` The depositor's associated token account for the position mint
+    /// 9. `[writable]` The position's Metaplex metadata account
+    /// 10. `[]` The token metadata program
+    /// 11. `[]` The associated token account program
+    /// 
+    /// When the vault charges a deposit fee (the payer must also be writable):
+    /// 12. `[writable]` The vault's fee destination (index 7 without position NFTs)
+    /// 
+    /// When the vault charges an insurance premium:
+    /// 13. `[writable]` The vault's insurance pool token account, after any of
+    ///     the accounts above
     Deposit {
         /// Amount of tokens to deposit
         amount: u64,
@@ -90,6 +570,19 @@ pub enum VaultInstruction {
         unlock_time: i64,
         /// Optional tag for the deposit (e.g., "Vacation", "Rent")
         tag: [u8; 32],
+        /// Token account all withdrawals of this deposit must pay out to
+        locked_destination: Option<Pubkey>,
+        /// Reject `CrankWithdraw` for this deposit
+        no_crank: bool,
+        /// Require the depositor to co-sign every `EmergencyWithdraw` of this deposit
+        emergency_requires_depositor: bool,
+        /// Opaque client key; a deposit whose key the depositor already used
+        /// in one of the vault's last `MAX_IDEMPOTENCY_KEYS` keyed deposits
+        /// fails with `DuplicateDeposit`
+        idempotency_key: Option<[u8; 16]>,
+        /// Let the vault owner pay the deposit out to the depositor before it
+        /// unlocks with `ReleaseEarly`
+        owner_can_release_early: bool,
     },
     
     /// Withdraw tokens from the vault
@@ -101,6 +594,23 @@ pub enum VaultInstruction {
     /// 3. `[writable]` The token account to transfer from (vault's token account)
     /// 4. `[]` The token program
     /// 5. `[]` The clock sysvar
+    /// 
+    /// For deposits represented by a position NFT (the signer is the NFT holder):
+    /// 6. `[writable]` The signer's token account holding the position NFT
+    /// 7. `[writable]` The position mint
+    /// 
+    /// Optionally, following those (no protocol fee is charged without them):
+    /// 8. `[]` The protocol config PDA (index 6 without a position NFT)
+    /// 9. `[writable]` The config's fee destination token account, when the fee is non-zero
+    /// 
+    /// Optionally, last before any hook accounts, when the vault auto-closes
+    /// emptied token accounts:
+    /// 10. `[writable]` The vault owner, receiving the rent of a token account
+    ///     the withdrawal closes; without it the account stays open
+    /// 
+    /// When the vault has a withdraw hook, following those:
+    /// 11. `[]` The hook program
+    /// 12. `[]` Extra accounts for the hook, at most `MAX_HOOK_ACCOUNTS`
     Withdraw {
         /// Unique identifier for the deposit
         deposit_id: u64,
@@ -111,90 +621,10899 @@ pub enum VaultInstruction {
     /// Accounts expected:
     /// 0. `[signer]` The emergency authority (multisig or DAO)
     /// 1. `[writable]` The vault account
-    /// 2. `[writable]` The token account to transfer to (owned by depositor)
+    /// 2. `[writable]` The token account to transfer to (owned by depositor, or
+    ///    the emergency destination's associated token account when the vault has one)
     /// 3. `[writable]` The token account to transfer from (vault's token account)
     /// 4. `[]` The token program
-    /// 5. `[]` The depositor account
+    /// 5. `[]` The depositor account, `[signer]` for deposits made with
+    ///    `emergency_requires_depositor`
     EmergencyWithdraw {
         /// Unique identifier for the deposit
         deposit_id: u64,
     },
-}
-
-// Vault account data structure
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
-pub struct Vault {
-    /// The owner of the vault
-    pub owner: Pubkey,
-    /// The number of deposits made to this vault
-    pub deposit_count: u64,
-    /// The deposits in this vault
-    pub deposits: Vec<Deposit>,
-    /// Reentrancy guard
-    pub reentrancy_guard: bool,
-    /// Emergency authority (multisig or DAO)
-    pub emergency_authority: Option<Pubkey>,
-}
-
-// Deposit data structure
-#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
-pub struct Deposit {
-    /// Unique identifier for the deposit
-    pub id: u64,
-    /// The depositor's public key
-    pub depositor: Pubkey,
-    /// The token mint address
-    pub token_mint: Pubkey,
-    /// Amount of tokens deposited
-    pub amount: u64,
-    /// Timestamp when the deposit can be withdrawn
-    pub unlock_time: i64,
-    /// Whether the deposit has been withdrawn
-    pub withdrawn: bool,
-    /// Optional tag for the deposit
-    pub tag: [u8; 32],
-    /// Creation timestamp
-    pub created_at: i64,
-}
-
-// Process program instruction
-pub fn process_instruction(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    instruction_data: &[u8],
-) -> ProgramResult {
-    // Verify the instruction data is valid
-    if instruction_data.is_empty() {
-        return Err(VaultError::InvalidInstructionData.into());
+    
+    /// Close a vault whose deposits have all been withdrawn
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The vault owner (receives the reclaimed rent)
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` (Optional) The owner's registry account, pruned if supplied
+    CloseVault,
+    
+    /// Enable or disable position NFTs for new deposits
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetPositionNfts {
+        /// Whether new deposits mint a position NFT
+        enabled: bool,
+        /// Metadata URI used for the position NFTs
+        uri: String,
+    },
+    
+    /// Withdraw an unlocked deposit on behalf of its depositor, authorized by an
+    /// ed25519 signature over `permit_message` instead of a transaction signature
+    /// 
+    /// The instruction immediately preceding this one must be an ed25519 program
+    /// instruction verifying that signature. Anyone may submit and pay for it.
+    /// 
+    /// Accounts expected:
+    /// 0. `[writable]` The vault account
+    /// 1. `[writable]` The destination token account (owned by the depositor)
+    /// 2. `[writable]` The vault's token account
+    /// 3. `[]` The token program
+    /// 4. `[]` The clock sysvar
+    /// 5. `[]` The instructions sysvar
+    /// 
+    /// Following those, the optional protocol fee accounts, vault owner and
+    /// withdraw hook accounts of `Withdraw`, from index 6; the withdrawal pays
+    /// out the same way
+    WithdrawWithPermit {
+        /// ID of the deposit to withdraw
+        deposit_id: u64,
+        /// Unix timestamp after which the permit is no longer valid
+        expiry: i64,
+    },
+    
+    /// Set the minimum time between two emergency withdrawals (zero disables it)
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetEmergencyCooldown {
+        /// Cooldown in seconds
+        cooldown: i64,
+    },
+    
+    /// Set or clear the per-withdrawer rate limit
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetRateLimit {
+        /// The new limit, or `None` to disable rate limiting
+        rate_limit: Option<RateLimit>,
+    },
+    
+    /// Set the delay between requesting and claiming a queued withdrawal
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetWithdrawDelay {
+        /// Delay in seconds
+        delay: i64,
+    },
+    
+    /// Opt a deposit into queued withdrawals; this cannot be undone
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor
+    /// 1. `[writable]` The vault account
+    EnableQueuedWithdrawals {
+        /// ID of the deposit
+        deposit_id: u64,
+    },
+    
+    /// Announce a withdrawal of a queued deposit
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor
+    /// 1. `[writable]` The vault account
+    /// 2. `[]` The clock sysvar
+    RequestWithdraw {
+        /// ID of the deposit
+        deposit_id: u64,
+    },
+    
+    /// Execute a requested withdrawal once the withdraw delay has elapsed
+    /// and the deposit is unlocked
+    /// 
+    /// Accounts expected: same as `Withdraw`
+    ClaimWithdraw {
+        /// ID of the deposit
+        deposit_id: u64,
+    },
+    
+    /// Abort a pending withdrawal request
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor or the emergency authority
+    /// 1. `[writable]` The vault account
+    CancelWithdrawRequest {
+        /// ID of the deposit
+        deposit_id: u64,
+    },
+    
+    /// Configure early withdrawals: the penalty starts at `max_penalty_bps` when
+    /// a deposit is made and decays linearly to zero at its unlock time
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetEarlyWithdrawal {
+        /// Penalty in basis points for a withdrawal right after depositing
+        max_penalty_bps: u16,
+        /// Token account receiving penalties, or `None` to disable early withdrawals
+        treasury: Option<Pubkey>,
+    },
+    
+    /// Withdraw a deposit before its unlock time, paying the decayed penalty
+    /// to the treasury
+    /// 
+    /// Accounts expected:
+    /// 0-5. Same as `Withdraw`
+    /// 6. `[writable]` The vault's treasury token account
+    EarlyWithdraw {
+        /// ID of the deposit to withdraw
+        deposit_id: u64,
+    },
+    
+    /// Set how long after unlock only the depositor may act on a deposit
+    /// (zero disables the grace period)
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetPostUnlockGrace {
+        /// Grace period in seconds
+        grace: i64,
+    },
+    
+    /// Set the timelock on changes to locked destinations
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetDestinationChangeDelay {
+        /// Delay in seconds
+        delay: i64,
+    },
+    
+    /// Change a deposit's locked destination in two steps: the first call
+    /// proposes `new_destination`, and repeating it once the vault's
+    /// destination change delay has passed applies it. Proposing the current
+    /// destination cancels a pending change.
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor
+    /// 1. `[writable]` The vault account
+    /// 2. `[]` The clock sysvar
+    ChangeLockedDestination {
+        /// ID of the deposit
+        deposit_id: u64,
+        /// The new destination token account
+        new_destination: Pubkey,
+    },
+    
+    /// Set the timelock on beneficiary changes
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetBeneficiaryChangeDelay {
+        /// Delay in seconds
+        delay: i64,
+    },
+    
+    /// Propose a new beneficiary for a deposit, replacing any pending proposal
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor
+    /// 1. `[writable]` The vault account
+    /// 2. `[]` The clock sysvar
+    ProposeBeneficiary {
+        /// ID of the deposit
+        deposit_id: u64,
+        /// The proposed beneficiary
+        new_beneficiary: Pubkey,
+    },
+    
+    /// Make the pending beneficiary current once the vault's beneficiary
+    /// change delay has passed since the proposal
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor
+    /// 1. `[writable]` The vault account
+    /// 2. `[]` The clock sysvar
+    CommitBeneficiary {
+        /// ID of the deposit
+        deposit_id: u64,
+    },
+    
+    /// Veto a pending beneficiary change
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The current beneficiary, the vault owner or the depositor
+    /// 1. `[writable]` The vault account
+    CancelBeneficiaryChange {
+        /// ID of the deposit
+        deposit_id: u64,
+    },
+    
+    /// Deposit tokens on behalf of another wallet, with the tokens and the
+    /// fees/rent supplied by separate accounts (e.g. a sponsoring backend)
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The funder (owns the source token account)
+    /// 1. `[signer, writable]` The payer of any rent
+    /// 2. `[]` The depositor: the recorded claimant, who need not sign
+    ///    3-7. Same as accounts 1-6 of `Deposit`
+    ///    8-12. Same as the position NFT accounts 7-11 of `Deposit`, minted to
+    ///    the depositor and funded by the payer
+    /// 13. `[writable]` The vault's fee destination, when the vault charges a
+    ///     deposit fee (index 9 without position NFTs)
+    DepositFor {
+        /// Amount of tokens to deposit
+        amount: u64,
+        /// Timestamp when the deposit can be withdrawn
+        unlock_time: i64,
+        /// Optional tag for the deposit
+        tag: [u8; 32],
+        /// Token account all withdrawals of this deposit must pay out to
+        locked_destination: Option<Pubkey>,
+        /// Reject `CrankWithdraw` for this deposit
+        no_crank: bool,
+        /// Require the depositor to co-sign every `EmergencyWithdraw` of this deposit
+        emergency_requires_depositor: bool,
+        /// Let the vault owner pay the deposit out to the depositor before it
+        /// unlocks with `ReleaseEarly`
+        owner_can_release_early: bool,
+    },
+    
+    /// Permissionlessly pay a matured deposit out to the depositor's
+    /// associated token account, creating it if needed
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The cranker (funds the associated token account if created)
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The depositor's associated token account for the deposit's mint
+    /// 3. `[writable]` The vault's token account
+    /// 4. `[]` The depositor
+    /// 5. `[]` The deposit's token mint
+    /// 6. `[]` The token program
+    /// 7. `[]` The system program (creates a missing associated token account)
+    /// 8. `[]` The associated token account program
+    /// 9. `[]` The clock sysvar
+    /// 10. `[writable]` The cranker's token account for the deposit's mint,
+    ///     receiving the crank bounty (only read when the vault pays one)
+    CrankWithdraw {
+        /// ID of the deposit to withdraw
+        deposit_id: u64,
+    },
+    
+    /// Set the share of a cranked deposit paid to the cranker
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetCrankBounty {
+        /// Bounty in basis points, at most `MAX_CRANK_BOUNTY_BPS`
+        bounty_bps: u16,
+    },
+    
+    /// Deposit tokens locked for a duration counted from when the transaction
+    /// executes; the resulting unlock time is set as the return data (i64 LE)
+    /// 
+    /// Accounts expected: same as `Deposit`
+    DepositWithDuration {
+        /// Amount of tokens to deposit
+        amount: u64,
+        /// Seconds from now until the deposit can be withdrawn
+        lock_duration_secs: i64,
+        /// Optional tag for the deposit
+        tag: [u8; 32],
+    },
+    
+    /// Set who new deposits may be attributed to
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetDepositPolicy {
+        /// The new policy
+        policy: DepositPolicy,
+    },
+    
+    /// Add a wallet to the depositor allowlist
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    AddAllowedDepositor {
+        /// The wallet to allow
+        depositor: Pubkey,
+    },
+    
+    /// Remove a wallet from the depositor allowlist; its existing deposits are
+    /// unaffected
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    RemoveAllowedDepositor {
+        /// The wallet to remove
+        depositor: Pubkey,
+    },
+    
+    /// Block a wallet from making or funding new deposits; its existing
+    /// deposits can still be withdrawn
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    BlockDepositor {
+        /// The wallet to block
+        depositor: Pubkey,
+    },
+    
+    /// Lift a block on a wallet
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    UnblockDepositor {
+        /// The wallet to unblock
+        depositor: Pubkey,
+    },
+    
+    /// Cap the amount of a mint outstanding in the vault; lowering it below the
+    /// current outstanding amount only blocks new deposits
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetMintLimit {
+        /// The mint to limit
+        mint: Pubkey,
+        /// Maximum outstanding amount, or `None` to lift the limit
+        limit: Option<u64>,
+    },
+    
+    /// Cap the total amount locked in the vault across all mints; lowering it
+    /// below the current total only blocks new deposits
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetTvlCap {
+        /// Maximum total locked, in raw token units, or `None` to lift the cap
+        cap: Option<u64>,
+    },
+    
+    /// Set the flat lamport fee charged to the payer of each deposit
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetDepositFee {
+        /// Fee in lamports (zero disables it)
+        lamports: u64,
+        /// Account receiving the fees
+        destination: Pubkey,
+    },
+    
+    /// Create the program-wide protocol config; the signer becomes its admin
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The admin, paying the config rent
+    /// 1. `[writable]` The config PDA (`["config"]`)
+    /// 2. `[]` The system program
+    InitializeConfig {
+        /// Share of each withdrawal skimmed as a protocol fee, in basis points
+        protocol_fee_bps: u16,
+        /// Token account receiving protocol fees (one mint per config)
+        fee_destination: Pubkey,
+    },
+    
+    /// Update the protocol fee
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The config admin
+    /// 1. `[writable]` The config PDA
+    UpdateConfig {
+        /// Share of each withdrawal skimmed as a protocol fee, in basis points
+        protocol_fee_bps: u16,
+        /// Token account receiving protocol fees
+        fee_destination: Pubkey,
+    },
+    
+    /// Propose a new config admin, who must accept before taking over
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The config admin
+    /// 1. `[writable]` The config PDA
+    ProposeConfigAdmin {
+        /// The proposed admin, or `None` to withdraw a proposal
+        new_admin: Option<Pubkey>,
+    },
+    
+    /// Accept a pending config admin proposal
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The proposed admin
+    /// 1. `[writable]` The config PDA
+    AcceptConfigAdmin,
+    
+    /// Set the authority allowed to freeze deposits (the emergency authority
+    /// when unset)
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetFreezeAuthority {
+        /// The new freeze authority, or `None` to fall back to the emergency authority
+        authority: Option<Pubkey>,
+    },
+    
+    /// Freeze a deposit, blocking every withdrawal and change until it is thawed
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The freeze authority
+    /// 1. `[writable]` The vault account
+    FreezeDeposit {
+        /// ID of the deposit to freeze
+        deposit_id: u64,
+    },
+    
+    /// Thaw a frozen deposit
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The freeze authority
+    /// 1. `[writable]` The vault account
+    ThawDeposit {
+        /// ID of the deposit to thaw
+        deposit_id: u64,
+    },
+    
+    /// Settle a frozen deposit by paying it back to the depositor or to the
+    /// vault's arbitration destination
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The emergency authority
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The token account to transfer to (the depositor's, or the arbitration destination)
+    /// 3. `[writable]` The token account to transfer from (vault's token account)
+    /// 4. `[]` The token program
+    ResolveFrozenDeposit {
+        /// ID of the frozen deposit
+        deposit_id: u64,
+        /// Where the deposit is paid
+        destination_kind: ResolutionDestination,
+    },
+    
+    /// Revoke any delegate approved on one of the vault's token accounts
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[]` The vault account
+    /// 2. `[writable]` The vault's token account
+    /// 3. `[]` The token program
+    RevokeVaultDelegate,
+    
+    /// Clear the close authority of one of the vault's token accounts. SPL
+    /// Token only lets the current close authority change it, so this fixes
+    /// accounts where the vault holds it; accounts with a foreign close
+    /// authority must be replaced
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[]` The vault account
+    /// 2. `[writable]` The vault's token account
+    /// 3. `[]` The token program
+    SetVaultTokenCloseAuthority,
+    
+    /// Like `Deposit`, but with the amount in whole tokens, converted on-chain
+    /// using the mint's decimals
+    /// 
+    /// Accounts expected:
+    /// 0. `[]` The token mint of the source token account
+    /// 1-7. Same as accounts 0-6 of `Deposit`, followed by any of its optional accounts
+    DepositUiAmount {
+        /// Amount in whole tokens
+        ui_amount: u64,
+        /// Decimals of the mint, as the client understood them
+        decimals_exponent: u8,
+        /// Timestamp when the deposit can be withdrawn
+        unlock_time: i64,
+        /// Optional tag for the deposit (e.g., "Vacation", "Rent")
+        tag: [u8; 32],
+    },
+    
+    /// Deposit two mints together as two linked deposits sharing an unlock
+    /// time and tag; either both are recorded or neither is. Not available
+    /// in vaults issuing position NFTs.
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The token account to transfer the first mint from
+    /// 3. `[writable]` The vault's token account for the first mint
+    /// 4. `[writable]` The token account to transfer the second mint from
+    /// 5. `[writable]` The vault's token account for the second mint
+    /// 6. `[]` The token program
+    /// 7. `[]` The system program
+    /// 8. `[]` The clock sysvar
+    /// 9. `[writable]` The vault's fee destination, when the vault charges a
+    ///    deposit fee (charged once per pair)
+    DepositPair {
+        /// Amount of the first mint
+        amount_a: u64,
+        /// Amount of the second mint
+        amount_b: u64,
+        /// Timestamp when both deposits can be withdrawn
+        unlock_time: i64,
+        /// Optional tag for both deposits
+        tag: [u8; 32],
+    },
+    
+    /// Deposit tokens locked for one of the canonical `LockTier` durations,
+    /// counted from when the transaction executes; the resulting unlock time
+    /// is set as the return data (i64 LE)
+    /// 
+    /// Accounts expected: same as `Deposit`
+    DepositTiered {
+        /// Amount of tokens to deposit
+        amount: u64,
+        /// Lock duration tier
+        tier: LockTier,
+        /// Optional tag for the deposit
+        tag: [u8; 32],
+    },
+    
+    /// Move a tiered deposit to a strictly higher tier, relocking it for the
+    /// new tier's duration from now (never unlocking it earlier)
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor
+    /// 1. `[writable]` The vault account
+    /// 2. `[]` The clock sysvar
+    ExtendLock {
+        /// ID of the tiered deposit
+        deposit_id: u64,
+        /// The new, higher tier
+        new_tier: LockTier,
+    },
+    
+    /// Deposit tokens locked until a slot height rather than a timestamp; the
+    /// slot must be at least the vault's `min_lock_slots` past the current one
+    /// 
+    /// Accounts expected: same as `Deposit`
+    DepositUntilSlot {
+        /// Amount of tokens to deposit
+        amount: u64,
+        /// Slot at which the tokens unlock
+        unlock_slot: u64,
+        /// Optional tag for the deposit
+        tag: [u8; 32],
+    },
+    
+    /// Set the minimum number of slots a `DepositUntilSlot` must lock for
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetMinLockSlots {
+        /// Minimum lock in slots
+        min_slots: u64,
+    },
+    
+    /// Deposit tokens that unlock at `unlock_time`, or earlier through
+    /// `WithdrawOnPrice` once the oracle price reaches the condition's
+    /// threshold; the vault must have a price oracle configured
+    /// 
+    /// Accounts expected: same as `Deposit`
+    DepositWithPriceCondition {
+        /// Amount of tokens to deposit
+        amount: u64,
+        /// Backstop unlock time
+        unlock_time: i64,
+        /// Optional tag for the deposit
+        tag: [u8; 32],
+        /// Price that releases the deposit early
+        price_condition: PriceCondition,
+    },
+    
+    /// Withdraw a price-conditioned deposit before its unlock time because
+    /// the oracle price has reached its threshold
+    /// 
+    /// Accounts expected: same as `Withdraw`, with the price account after
+    /// the clock sysvar
+    WithdrawOnPrice {
+        /// ID of the deposit to withdraw
+        deposit_id: u64,
+    },
+    
+    /// Configure or disable price-conditioned unlocks
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetPriceOracle {
+        /// The new oracle settings, or `None` to disable price unlocks
+        price_oracle: Option<PriceOracleConfig>,
+    },
+    
+    /// Deposit tokens that unlock at `unlock_time`, or earlier through
+    /// `WithdrawOnCondition` once another account holds the expected bytes.
+    /// 
+    /// The condition trusts whoever can write the referenced account: only the
+    /// key is pinned, not the owner, so it should be an account its owning
+    /// program can't close and recreate (such as a governance proposal PDA)
+    /// and the bytes should only be reachable in the intended state.
+    /// 
+    /// Accounts expected: same as `Deposit`
+    DepositWithAccountCondition {
+        /// Amount of tokens to deposit
+        amount: u64,
+        /// Backstop unlock time
+        unlock_time: i64,
+        /// Optional tag for the deposit
+        tag: [u8; 32],
+        /// Account state that releases the deposit early
+        account_condition: AccountCondition,
+    },
+    
+    /// Withdraw an account-conditioned deposit before its unlock time because
+    /// the referenced account holds the expected bytes
+    /// 
+    /// Accounts expected: same as `Withdraw`, with the condition account after
+    /// the clock sysvar
+    WithdrawOnCondition {
+        /// ID of the deposit to withdraw
+        deposit_id: u64,
+    },
+    
+    /// Deposit tokens as a hash time-locked contract: the beneficiary can claim
+    /// them at any time by revealing the preimage of `hashlock`, otherwise the
+    /// depositor withdraws them after `unlock_time`. Not available in vaults
+    /// issuing position NFTs.
+    /// 
+    /// Accounts expected: same as `Deposit`
+    DepositWithHashlock {
+        /// Amount of tokens to deposit
+        amount: u64,
+        /// Time after which the depositor can take the tokens back
+        unlock_time: i64,
+        /// Optional tag for the deposit
+        tag: [u8; 32],
+        /// SHA-256 of the secret
+        hashlock: [u8; 32],
+        /// Wallet paid when the secret is revealed
+        beneficiary: Pubkey,
+    },
+    
+    /// Pay a hashlocked deposit to its beneficiary by revealing the secret;
+    /// anyone holding the secret may submit it
+    /// 
+    /// Accounts expected:
+    /// 0. `[writable]` The vault account
+    /// 1. `[writable]` The beneficiary's token account
+    /// 2. `[writable]` The vault's token account
+    /// 3. `[]` The token program
+    ClaimWithPreimage {
+        /// ID of the hashlocked deposit
+        deposit_id: u64,
+        /// The secret whose SHA-256 is the deposit's hashlock
+        preimage: [u8; 32],
+    },
+    
+    /// Deposit tokens with guardians that can move the deposit to a new
+    /// depositor key if the current one is lost
+    /// 
+    /// Accounts expected: same as `Deposit`
+    DepositWithGuardians {
+        /// Amount of tokens to deposit
+        amount: u64,
+        /// Timestamp when tokens can be withdrawn
+        unlock_time: i64,
+        /// Optional tag for the deposit
+        tag: [u8; 32],
+        /// Distinct guardian wallets, at most `MAX_GUARDIANS`, excluding the depositor
+        guardians: Vec<Pubkey>,
+        /// Approvals needed for a recovery, between 1 and the number of guardians
+        guardian_threshold: u8,
+    },
+    
+    /// Propose moving a deposit to a new depositor key; counts as the
+    /// proposing guardian's approval
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` A guardian of the deposit
+    /// 1. `[writable]` The vault account
+    /// 2. `[]` The clock sysvar
+    ProposeRecovery {
+        /// ID of the deposit
+        deposit_id: u64,
+        /// Wallet that becomes the depositor
+        new_depositor: Pubkey,
+    },
+    
+    /// Approve the pending recovery of a deposit
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` A guardian of the deposit
+    /// 1. `[writable]` The vault account
+    /// 2. `[]` The clock sysvar
+    ApproveRecovery {
+        /// ID of the deposit
+        deposit_id: u64,
+    },
+    
+    /// Make the new depositor of an approved recovery the deposit's depositor
+    /// once `RECOVERY_DELAY` has passed since the threshold was reached; also
+    /// clears the locked destination, which belonged to the lost key
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` Any wallet
+    /// 1. `[writable]` The vault account
+    /// 2. `[]` The clock sysvar
+    ExecuteRecovery {
+        /// ID of the deposit
+        deposit_id: u64,
+    },
+    
+    /// Cancel the pending recovery of a deposit
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor
+    /// 1. `[writable]` The vault account
+    VetoRecovery {
+        /// ID of the deposit
+        deposit_id: u64,
+    },
+    
+    /// Withdraw every matured deposit of the signer carrying `tag`, with one
+    /// transfer per mint. Deposits whose mint has no account pair supplied, or
+    /// whose locked destination isn't the supplied one, are skipped and their
+    /// IDs returned as little-endian `u64`s in the return data
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor or beneficiary
+    /// 1. `[writable]` The vault account
+    /// 2. `[]` The token program
+    /// 3. `[]` The clock sysvar
+    /// 4. `[]` The protocol config account
+    /// 5. `[writable]` The protocol fee destination, only when a protocol fee applies
+    ///    6.. `[writable]` Pairs of the vault token account and the destination
+    ///    token account, one pair per mint
+    WithdrawByTag {
+        /// Tag the deposits must carry exactly
+        tag: [u8; 32],
+    },
+    
+    /// Irreversibly give up ownership: every owner-gated instruction fails
+    /// afterwards, while depositor flows are unaffected
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    RenounceOwnership {
+        /// What happens to the emergency authority; required when one is set
+        emergency_authority: Option<AuthorityOnRenounce>,
+    },
+    
+    /// Set the authority allowed to make emergency withdrawals and resolve
+    /// frozen deposits. Any pubkey is accepted, including a program-derived
+    /// address: a governance program acts as the authority by passing its PDA
+    /// as a signer through `invoke_signed`
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetEmergencyAuthority {
+        /// The new emergency authority, or `None` to remove it
+        authority: Option<Pubkey>,
+    },
+    
+    /// Route a share of every deposit into an insurance pool token account.
+    /// Deposits then need the pool as their last account and must be in the
+    /// pool's mint
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    /// 2. `[]` The insurance pool token account (omitted when `insurance_bps` is zero)
+    SetInsurance {
+        /// Premium taken from each deposit, in basis points; zero disables insurance
+        insurance_bps: u16,
+        /// Token account receiving the premiums
+        insurance_pool: Pubkey,
+    },
+    
+    /// Keep a position index PDA (`["positions", vault, depositor]`) listing
+    /// each depositor's open deposits. Only possible while the vault has no
+    /// open deposits, so every index starts out complete
+    /// 
+    /// Once enabled, instructions that open or close a deposit take the
+    /// affected depositor's index right after their fixed accounts, ahead of
+    /// any position NFT, fee or other optional accounts; the first deposit
+    /// creates it at the payer's expense. `DepositPair` takes it the same
+    /// way, `WithdrawByTag` takes the signer's index and only visits the
+    /// deposits it lists, and `ExecuteRecovery` takes the old and new
+    /// depositors' indexes followed by the system program
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetPositionIndex {
+        /// Whether deposits are tracked in position indexes
+        enabled: bool,
+    },
+    
+    /// Lock several deposits of one mint from the same source account with a
+    /// single token transfer; either every item is recorded or none is. The
+    /// deposits get consecutive IDs, returned as the first and last ID (two
+    /// little-endian u64s). Not available in vaults issuing position NFTs or
+    /// charging an insurance premium.
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The token account to transfer from (owned by depositor)
+    /// 3. `[writable]` The token account to transfer to (vault's token account)
+    /// 4. `[]` The token program
+    /// 5. `[]` The system program
+    /// 6. `[]` The clock sysvar
+    /// 7. `[writable]` The vault's fee destination, when the vault charges a
+    ///    deposit fee (charged once per batch)
+    DepositMany {
+        /// Amount, unlock timestamp and tag of each deposit, at most
+        /// `MAX_BATCH_DEPOSITS` of them
+        items: Vec<(u64, i64, [u8; 32])>,
+    },
+    
+    /// Restrict which programs may deposit into the vault through CPI, e.g.
+    /// to a router enforcing off-chain checks. While a list is set, every
+    /// deposit instruction takes the instructions sysvar right after the
+    /// depositor's position index (if any), ahead of the other optional
+    /// accounts, and fails with `CallerNotAllowed` when invoked through CPI
+    /// from a transaction whose instruction targets a program outside the
+    /// list. Deposits made directly by wallets stay allowed unless
+    /// `reject_top_level` is set. Allowed CPI deposits emit a `caller` event.
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetAllowedCallers {
+        /// Programs allowed to deposit through CPI, at most
+        /// `MAX_ALLOWED_CALLERS`; `None` lifts the restriction
+        allowed_callers: Option<Vec<Pubkey>>,
+        /// Whether deposits made directly, not through CPI, are rejected
+        reject_top_level: bool,
+    },
+    
+    /// Move tokens that reached one of the vault's token accounts outside a
+    /// deposit (airdrops, direct transfers, rebates) to a token account of
+    /// the owner's choosing. Only the account's balance beyond the amount
+    /// held for the mint's active deposits is swept; fails with `NoSurplus`
+    /// when there is none.
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[]` The vault account
+    /// 2. `[writable]` The vault's token account for `mint`
+    /// 3. `[writable]` The token account receiving the surplus
+    /// 4. `[]` The token program
+    SweepSurplus {
+        /// Mint of the tokens to sweep
+        mint: Pubkey,
+    },
+    
+    /// Move the vault account's lamports above its rent-exempt minimum
+    /// (overfunded creation, accidental transfers) to a destination account;
+    /// a no-op when there are none
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The account receiving the lamports (not the vault)
+    SweepLamports,
+    
+    /// Set how long withdrawn deposit records are kept before `GcDeposits`
+    /// may remove them
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetGcDelay {
+        /// Delay in seconds, or `None` to keep every record
+        gc_delay: Option<i64>,
+    },
+    
+    /// Remove up to `max` deposit records withdrawn at least the vault's
+    /// `gc_delay` ago, freeing their space for new deposits. Anyone may call
+    /// it; the caller earns `GC_BOUNTY_LAMPORTS` per removed record, paid from
+    /// the vault account's lamports above its rent-exempt minimum as far as
+    /// they go. Succeeds without changes when no record qualifies.
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The caller (receives the bounty)
+    /// 1. `[writable]` The vault account
+    /// 2. `[]` The clock sysvar
+    GcDeposits {
+        /// Maximum number of records to remove
+        max: u8,
+    },
+    
+    /// Stop accepting new deposits while leaving every withdrawal path open,
+    /// to wind the vault down
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    CloseToNewDeposits,
+    
+    /// Accept new deposits again after `CloseToNewDeposits`
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    ReopenDeposits,
+    
+    /// Create the program-wide global state; the signer becomes its authority
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The authority, paying the global state rent
+    /// 1. `[writable]` The global state PDA (`["global"]`)
+    /// 2. `[]` The system program
+    InitializeGlobalState,
+    
+    /// Turn the program-wide deposit kill switch on or off; withdrawals are
+    /// never halted
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The global authority
+    /// 1. `[writable]` The global state PDA
+    SetGlobalHalt {
+        /// Whether deposit instructions should be refused
+        halted: bool,
+    },
+    
+    /// Propose a new global authority, who must accept before taking over
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The global authority
+    /// 1. `[writable]` The global state PDA
+    ProposeGlobalAuthority {
+        /// The proposed authority, or `None` to withdraw a proposal
+        new_authority: Option<Pubkey>,
+    },
+    
+    /// Accept a pending global authority proposal
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The proposed authority
+    /// 1. `[writable]` The global state PDA
+    AcceptGlobalAuthority,
+    
+    /// Summarize the vault into its snapshot PDA, creating it on first use;
+    /// anyone may refresh it
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The caller, paying the snapshot rent
+    /// 1. `[]` The vault account
+    /// 2. `[writable]` The snapshot PDA (`["snapshot", vault]`)
+    /// 3. `[]` The system program
+    WriteSnapshot,
+    
+    /// Withdraw an unlocked deposit, paying it out across several token
+    /// accounts in one instruction
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor or beneficiary
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The vault token account
+    /// 3. `[]` The token program
+    /// 4. `[]` The clock sysvar
+    /// 5. `[writable]` The depositor's position index, only when the vault keeps them
+    /// 6. `[]` The protocol config account
+    /// 7. `[writable]` The protocol fee destination, only when a protocol fee applies
+    ///    8.. `[writable]` One destination token account per split, in order
+    /// 
+    /// Then, optionally, `[signer]` the vault owner, approving a payout to no
+    /// account of the depositor's where the vault allows it
+    WithdrawSplit {
+        /// ID of the deposit to withdraw
+        deposit_id: u64,
+        /// Amount paid to each destination, at most `MAX_WITHDRAW_SPLITS`,
+        /// summing to the deposit amount
+        splits: Vec<u64>,
+    },
+    
+    /// Let a vault owner co-signature approve split withdrawals that pay no
+    /// account of the depositor's
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetOwnerApprovedSplits {
+        /// Whether the owner may approve such splits
+        enabled: bool,
+    },
+    
+    /// `Withdraw`, followed by an SPL Memo the vault program emits after the
+    /// transfer, so the memo shares the payout's transaction
+    /// 
+    /// Accounts expected: those of `Withdraw`, then
+    /// last. `[]` The SPL Memo program
+    WithdrawWithMemo {
+        /// ID of the deposit to withdraw
+        deposit_id: u64,
+        /// Memo text, at most `MAX_MEMO_LEN` bytes of UTF-8
+        memo: Vec<u8>,
+    },
+    
+    /// Lock native SOL in a new stake account delegated to a validator. The
+    /// vault's stake authority is the account's staker and withdrawer, so the
+    /// stake only leaves through `DeactivateStake` and `WithdrawStake`
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The depositor, funding the stake and its rent
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The stake account (`find_stake_account_address` of the next deposit ID)
+    /// 3. `[]` The vault's stake authority (`["stake_authority", vault]`)
+    /// 4. `[]` The validator's vote account
+    /// 5. `[]` The stake program
+    /// 6. `[]` The system program
+    /// 7. `[]` The clock sysvar
+    /// 8. `[]` The rent sysvar
+    /// 9. `[]` The stake history sysvar
+    /// 10. `[]` The stake config account
+    /// 11. `[writable]` The depositor's position index, only when the vault keeps them
+    /// 12. `[writable]` The deposit fee destination, only when the vault charges a deposit fee
+    DepositStakedSol {
+        /// Lamports to stake, on top of the stake account's rent
+        amount: u64,
+        /// Timestamp after which the stake may be deactivated
+        unlock_time: i64,
+        /// Vote account of the validator to delegate to
+        validator_vote: Pubkey,
+        /// Optional tag for the deposit
+        tag: [u8; 32],
+    },
+    
+    /// Start the cooldown of an unlocked staked deposit; its lamports can be
+    /// withdrawn once the stake is fully inactive, an epoch boundary or more later
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor or beneficiary
+    /// 1. `[]` The vault account
+    /// 2. `[writable]` The stake account
+    /// 3. `[]` The vault's stake authority
+    /// 4. `[]` The stake program
+    /// 5. `[]` The clock sysvar
+    DeactivateStake {
+        /// ID of the staked deposit
+        deposit_id: u64,
+    },
+    
+    /// Withdraw a deactivated staked deposit: every lamport of the stake
+    /// account, rewards and rent included, goes to the withdrawer
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The depositor or beneficiary, receiving the lamports
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The stake account
+    /// 3. `[]` The vault's stake authority
+    /// 4. `[]` The stake program
+    /// 5. `[]` The clock sysvar
+    /// 6. `[]` The stake history sysvar
+    /// 7. `[writable]` The depositor's position index, only when the vault keeps them
+    WithdrawStake {
+        /// ID of the staked deposit
+        deposit_id: u64,
+    },
+    
+    /// Publicly announce that the whole vault will be evacuated to its
+    /// emergency destination; `ExecuteSweepAll` works `SWEEP_ALL_DELAY` later.
+    /// The vault stops taking deposits, and depositors keep every withdrawal
+    /// path in the meantime
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The emergency authority
+    /// 1. `[signer]` The vault owner
+    /// 2. `[writable]` The vault account
+    AnnounceSweepAll,
+    
+    /// Once the announced delay has passed, move the vault's entire balance
+    /// of a mint to the emergency destination and mark every deposit of the
+    /// mint still open `Swept`
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The emergency authority
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The vault token account of the mint
+    /// 3. `[writable]` The emergency destination's associated token account of the mint
+    /// 4. `[]` The token program
+    ExecuteSweepAll {
+        /// Mint to sweep
+        mint: Pubkey,
+    },
+    
+    /// Grant a co-owner scoped owner powers (`CAN_*` role bits), replacing
+    /// any roles it already held
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetCoOwner {
+        /// Wallet of the co-owner
+        co_owner: Pubkey,
+        /// Bitmask of `CAN_*` roles
+        roles: u8,
+    },
+    
+    /// Revoke every role of a co-owner
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    RemoveCoOwner {
+        /// Wallet of the co-owner
+        co_owner: Pubkey,
+    },
+    
+    /// Pay a deposit made with `owner_can_release_early` out to its depositor
+    /// before it unlocks, marking it `ReleasedEarly`
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The depositor's token account of the deposit's mint (the
+    ///    locked destination, if the deposit has one)
+    /// 3. `[writable]` The vault token account
+    /// 4. `[]` The token program
+    /// 5. `[writable]` The depositor's position index, when the vault keeps them
+    ReleaseEarly {
+        /// Unique identifier for the deposit
+        deposit_id: u64,
+    },
+    
+    /// Choose the mint rewards are paid in and the mint whose active deposits
+    /// share them, by locked amount; fails while rewards are owed
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetRewardMints {
+        /// Mint rewards are paid in
+        reward_mint: Pubkey,
+        /// Mint of the deposits that earn rewards
+        weight_mint: Pubkey,
+    },
+    
+    /// Donate `total` reward tokens to the active deposits of the weight mint,
+    /// pro rata by amount, or with `total` zero continue the distribution in
+    /// progress. Each call allocates to at most `MAX_REWARD_DEPOSITS_PER_CALL`
+    /// deposits; rounding dust is carried into the next distribution
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The caller (the donor when starting a distribution)
+    /// 1. `[writable]` The vault account
+    /// 
+    /// When starting a distribution:
+    /// 2. `[writable]` The donor's token account of the reward mint
+    /// 3. `[writable]` The vault token account of the reward mint
+    /// 4. `[]` The token program
+    DistributeRewards {
+        /// Reward mint, which must be the vault's
+        mint: Pubkey,
+        /// Reward tokens to donate; zero continues the current distribution
+        total: u64,
+    },
+    
+    /// Pay a deposit's pending rewards out to its depositor, whether or not
+    /// the deposit itself has been withdrawn
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor or beneficiary
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The token account of the reward mint to pay to
+    /// 3. `[writable]` The vault token account of the reward mint
+    /// 4. `[]` The token program
+    ClaimRewards {
+        /// Unique identifier for the deposit
+        deposit_id: u64,
+    },
+    
+    /// Describe a deposit without changing anything: logs a summary line and
+    /// sets the Borsh-encoded `Deposit` as return data, for explorers that
+    /// simulate the instruction instead of decoding the vault account
+    /// 
+    /// Accounts expected:
+    /// 0. `[]` The vault account
+    ViewDeposit {
+        /// Unique identifier for the deposit
+        deposit_id: u64,
+    },
+    
+    /// Report the program version and its `FEATURE_*` bits: logs them and
+    /// sets `ProgramVersion::to_return_data` as return data, for clients
+    /// that simulate it to detect what a deployment supports
+    /// 
+    /// No accounts expected
+    GetVersion,
+    
+    /// Require each depositor's active deposits to carry distinct tags, so
+    /// tags can serve as identifiers; untagged deposits are exempt
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetUniqueTags {
+        /// Whether a depositor may not reuse a tag until its deposit closes
+        enabled: bool,
+    },
+    
+    /// Round the unlock time of new deposits up to a multiple of the
+    /// granularity, e.g. 86400 for 00:00 UTC day boundaries (zero disables
+    /// rounding)
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetUnlockGranularity {
+        /// Granularity in seconds, at most `MAX_UNLOCK_GRANULARITY`
+        granularity: i64,
+    },
+    
+    /// Emergency withdraw every open deposit of one depositor and mint in a
+    /// single transfer, visiting at most `MAX_EVACUATION_DEPOSITS_PER_CALL`
+    /// deposit records per call; repeat until the returned
+    /// `EmergencyWithdrawAllResult` is finished
+    /// 
+    /// Accounts expected: same as `EmergencyWithdraw`
+    EmergencyWithdrawAll {
+        /// The depositor whose deposits are withdrawn
+        depositor: Pubkey,
+        /// The mint of the deposits withdrawn
+        mint: Pubkey,
+    },
+    
+    /// Fold a vault's lifetime counters and outstanding amounts into the
+    /// program stats, once per vault; anyone may call it
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The caller, paying the stats rent on first use
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The program stats PDA (`["stats"]`)
+    /// 3. `[]` The system program
+    ReconcileStats,
+    
+    /// Compute a depositor's vote-escrow voting power in a vault without
+    /// changing anything: logs it and sets it as return data (a little-endian
+    /// u128), so a governance program can read it through CPI in the same
+    /// transaction as a vote
+    /// 
+    /// Accounts expected:
+    /// 0. `[]` The vault account
+    /// 1. `[]` The clock sysvar
+    LogVotingPower {
+        /// The depositor whose deposits confer the power
+        depositor: Pubkey,
+    },
+    
+    /// Deposit tokens in escrow for a deal with `counterparty`: both parties
+    /// can release them to the counterparty at any time with
+    /// `ReleaseEscrow`, otherwise the depositor withdraws them after
+    /// `unlock_time`. Emergency withdrawals need the depositor's signature.
+    /// Not available in vaults issuing position NFTs.
+    /// 
+    /// Accounts expected: same as `Deposit`
+    DepositEscrow {
+        /// Amount of tokens to deposit
+        amount: u64,
+        /// Deadline after which the depositor can take the tokens back
+        unlock_time: i64,
+        /// Optional tag for the deposit
+        tag: [u8; 32],
+        /// Wallet paid when both parties release the escrow
+        counterparty: Pubkey,
+    },
+    
+    /// Pay an escrow deposit to its counterparty, with the agreement of both
+    /// parties
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The depositor
+    /// 1. `[signer]` The counterparty
+    /// 2. `[writable]` The vault account
+    /// 3. `[writable]` The counterparty's token account
+    /// 4. `[writable]` The vault's token account
+    /// 5. `[]` The token program
+    ReleaseEscrow {
+        /// ID of the escrow deposit
+        deposit_id: u64,
+    },
+    
+    /// Set or clear the program every withdrawal but emergency ones calls
+    /// after each payout; see `WithdrawHookData` for the call
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetWithdrawHook {
+        /// The hook program, or `None` to stop calling one
+        hook_program: Option<Pubkey>,
+    },
+    
+    /// Create a vault at the signer's `find_vault_address` PDA with the
+    /// configuration of an existing vault (see `Vault::clone_config`); the
+    /// signer owns the clone whoever owns the source
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The clone's owner (writable when it pays the rent)
+    /// 1. `[]` The source vault
+    /// 2. `[writable]` The clone's PDA, as large as the source account
+    /// 3. `[]` System program
+    /// 4. `[signer, writable]` Optional payer of the rent in place of the owner
+    CloneVault {
+        /// Index distinguishing the owner's cloned vaults
+        seed_index: u64,
+    },
+    
+    /// Create the owner's vault at `find_vault_address(owner, 0)` and make
+    /// its first deposit in one instruction, so a failed deposit leaves no
+    /// empty vault behind. When the owner's vault already exists, as for a
+    /// retried transaction, only the deposit is made and `config` is ignored
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The owner, who is also the depositor
+    /// 1. `[signer, writable]` The payer of the vault's rent (may be the owner)
+    /// 2. `[writable]` The vault PDA
+    /// 3. `[writable]` The token account to transfer from (owned by the owner)
+    /// 4. `[writable]` The vault's token account (owned by the vault PDA)
+    /// 5. `[]` The token program
+    /// 6. `[]` The system program
+    /// 7. `[]` The clock sysvar
+    /// 
+    /// Followed by the optional accounts of a `Deposit` into the vault
+    CreateVaultAndDeposit {
+        /// Settings the vault is created with
+        config: VaultConfig,
+        /// Amount of tokens to deposit
+        amount: u64,
+        /// Unix timestamp when the deposit unlocks
+        unlock_time: i64,
+        /// Caller-defined label
+        tag: [u8; 32],
+    },
+    
+    /// Add tokens to someone else's active deposit. The deposit keeps its
+    /// depositor, unlock time and claim rights; only its amount grows
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The donor
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The donor's token account, in the deposit's mint
+    /// 3. `[writable]` The vault's token account
+    /// 4. `[]` The token program
+    DonateToDeposit {
+        /// ID of the deposit to add to
+        deposit_id: u64,
+        /// Amount of tokens to donate
+        amount: u64,
+    },
+    
+    /// Stop or resume accepting `DonateToDeposit`, e.g. to keep out
+    /// unsolicited dust
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetDonationsDisabled {
+        /// Whether donations are rejected
+        disabled: bool,
+    },
+    
+    /// Close the vault's empty token account for a mint no open deposit
+    /// holds, sending its rent where the owner names, and drop the mint's
+    /// entry unless it carries a mint limit
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    /// 2. `[writable]` The vault's token account for the mint
+    /// 3. `[]` The token program
+    /// 4. `[writable]` The rent destination, e.g. the owner or the fee payer
+    CloseVaultTokenAccount {
+        /// Mint of the token account to close
+        mint: Pubkey,
+    },
+    
+    /// Have `Withdraw` close the vault's token account once the last open
+    /// deposit of its mint is paid out and it is empty, crediting the rent
+    /// to the vault owner when the withdrawal passes the owner's account
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The vault owner
+    /// 1. `[writable]` The vault account
+    SetAutoClose {
+        /// Whether withdrawals close emptied token accounts
+        enabled: bool,
+    },
+}
+
+// Instruction wire format
+//
+// Every instruction starts with its tag. Tags match the variant order of
+// `VaultInstruction`, so unversioned instructions encode exactly as the
+// legacy Borsh enum did. Instructions expected to grow set
+// `VERSIONED_TAG_FLAG` on their tag and carry a version byte before their
+// fields; the unflagged legacy form is still accepted during the deprecation
+// window.
+
+/// Wire tag of `CreateVault`
+pub const TAG_CREATE_VAULT: u8 = 0;
+
+/// Wire tag of `Deposit`
+pub const TAG_DEPOSIT: u8 = 1;
+
+/// Wire tag of `Withdraw`
+pub const TAG_WITHDRAW: u8 = 2;
+
+/// Wire tag of `EmergencyWithdraw`
+pub const TAG_EMERGENCY_WITHDRAW: u8 = 3;
+
+/// Wire tag of `CloseVault`
+pub const TAG_CLOSE_VAULT: u8 = 4;
+
+/// Wire tag of `SetPositionNfts`
+pub const TAG_SET_POSITION_NFTS: u8 = 5;
+
+/// Wire tag of `WithdrawWithPermit`
+pub const TAG_WITHDRAW_WITH_PERMIT: u8 = 6;
+
+/// Wire tag of `SetEmergencyCooldown`
+pub const TAG_SET_EMERGENCY_COOLDOWN: u8 = 7;
+
+/// Wire tag of `SetRateLimit`
+pub const TAG_SET_RATE_LIMIT: u8 = 8;
+
+/// Wire tag of `SetWithdrawDelay`
+pub const TAG_SET_WITHDRAW_DELAY: u8 = 9;
+
+/// Wire tag of `EnableQueuedWithdrawals`
+pub const TAG_ENABLE_QUEUED_WITHDRAWALS: u8 = 10;
+
+/// Wire tag of `RequestWithdraw`
+pub const TAG_REQUEST_WITHDRAW: u8 = 11;
+
+/// Wire tag of `ClaimWithdraw`
+pub const TAG_CLAIM_WITHDRAW: u8 = 12;
+
+/// Wire tag of `CancelWithdrawRequest`
+pub const TAG_CANCEL_WITHDRAW_REQUEST: u8 = 13;
+
+/// Wire tag of `SetEarlyWithdrawal`
+pub const TAG_SET_EARLY_WITHDRAWAL: u8 = 14;
+
+/// Wire tag of `EarlyWithdraw`
+pub const TAG_EARLY_WITHDRAW: u8 = 15;
+
+/// Wire tag of `SetPostUnlockGrace`
+pub const TAG_SET_POST_UNLOCK_GRACE: u8 = 16;
+
+/// Wire tag of `SetDestinationChangeDelay`
+pub const TAG_SET_DESTINATION_CHANGE_DELAY: u8 = 17;
+
+/// Wire tag of `ChangeLockedDestination`
+pub const TAG_CHANGE_LOCKED_DESTINATION: u8 = 18;
+
+/// Wire tag of `SetBeneficiaryChangeDelay`
+pub const TAG_SET_BENEFICIARY_CHANGE_DELAY: u8 = 19;
+
+/// Wire tag of `ProposeBeneficiary`
+pub const TAG_PROPOSE_BENEFICIARY: u8 = 20;
+
+/// Wire tag of `CommitBeneficiary`
+pub const TAG_COMMIT_BENEFICIARY: u8 = 21;
+
+/// Wire tag of `CancelBeneficiaryChange`
+pub const TAG_CANCEL_BENEFICIARY_CHANGE: u8 = 22;
+
+/// Wire tag of `DepositFor`
+pub const TAG_DEPOSIT_FOR: u8 = 23;
+
+/// Wire tag of `CrankWithdraw`
+pub const TAG_CRANK_WITHDRAW: u8 = 24;
+
+/// Wire tag of `SetCrankBounty`
+pub const TAG_SET_CRANK_BOUNTY: u8 = 25;
+
+/// Wire tag of `DepositWithDuration`
+pub const TAG_DEPOSIT_WITH_DURATION: u8 = 26;
+
+/// Wire tag of `SetDepositPolicy`
+pub const TAG_SET_DEPOSIT_POLICY: u8 = 27;
+
+/// Wire tag of `AddAllowedDepositor`
+pub const TAG_ADD_ALLOWED_DEPOSITOR: u8 = 28;
+
+/// Wire tag of `RemoveAllowedDepositor`
+pub const TAG_REMOVE_ALLOWED_DEPOSITOR: u8 = 29;
+
+/// Wire tag of `BlockDepositor`
+pub const TAG_BLOCK_DEPOSITOR: u8 = 30;
+
+/// Wire tag of `UnblockDepositor`
+pub const TAG_UNBLOCK_DEPOSITOR: u8 = 31;
+
+/// Wire tag of `SetMintLimit`
+pub const TAG_SET_MINT_LIMIT: u8 = 32;
+
+/// Wire tag of `SetTvlCap`
+pub const TAG_SET_TVL_CAP: u8 = 33;
+
+/// Wire tag of `SetDepositFee`
+pub const TAG_SET_DEPOSIT_FEE: u8 = 34;
+
+/// Wire tag of `InitializeConfig`
+pub const TAG_INITIALIZE_CONFIG: u8 = 35;
+
+/// Wire tag of `UpdateConfig`
+pub const TAG_UPDATE_CONFIG: u8 = 36;
+
+/// Wire tag of `ProposeConfigAdmin`
+pub const TAG_PROPOSE_CONFIG_ADMIN: u8 = 37;
+
+/// Wire tag of `AcceptConfigAdmin`
+pub const TAG_ACCEPT_CONFIG_ADMIN: u8 = 38;
+
+/// Wire tag of `SetFreezeAuthority`
+pub const TAG_SET_FREEZE_AUTHORITY: u8 = 39;
+
+/// Wire tag of `FreezeDeposit`
+pub const TAG_FREEZE_DEPOSIT: u8 = 40;
+
+/// Wire tag of `ThawDeposit`
+pub const TAG_THAW_DEPOSIT: u8 = 41;
+
+/// Wire tag of `ResolveFrozenDeposit`
+pub const TAG_RESOLVE_FROZEN_DEPOSIT: u8 = 42;
+
+/// Wire tag of `RevokeVaultDelegate`
+pub const TAG_REVOKE_VAULT_DELEGATE: u8 = 43;
+
+/// Wire tag of `SetVaultTokenCloseAuthority`
+pub const TAG_SET_VAULT_TOKEN_CLOSE_AUTHORITY: u8 = 44;
+
+/// Wire tag of `DepositUiAmount`
+pub const TAG_DEPOSIT_UI_AMOUNT: u8 = 45;
+
+/// Wire tag of `DepositPair`
+pub const TAG_DEPOSIT_PAIR: u8 = 46;
+
+/// Wire tag of `DepositTiered`
+pub const TAG_DEPOSIT_TIERED: u8 = 47;
+
+/// Wire tag of `ExtendLock`
+pub const TAG_EXTEND_LOCK: u8 = 48;
+
+/// Wire tag of `DepositUntilSlot`
+pub const TAG_DEPOSIT_UNTIL_SLOT: u8 = 49;
+
+/// Wire tag of `SetMinLockSlots`
+pub const TAG_SET_MIN_LOCK_SLOTS: u8 = 50;
+
+/// Wire tag of `DepositWithPriceCondition`
+pub const TAG_DEPOSIT_WITH_PRICE_CONDITION: u8 = 51;
+
+/// Wire tag of `WithdrawOnPrice`
+pub const TAG_WITHDRAW_ON_PRICE: u8 = 52;
+
+/// Wire tag of `SetPriceOracle`
+pub const TAG_SET_PRICE_ORACLE: u8 = 53;
+
+/// Wire tag of `DepositWithAccountCondition`
+pub const TAG_DEPOSIT_WITH_ACCOUNT_CONDITION: u8 = 54;
+
+/// Wire tag of `WithdrawOnCondition`
+pub const TAG_WITHDRAW_ON_CONDITION: u8 = 55;
+
+/// Wire tag of `DepositWithHashlock`
+pub const TAG_DEPOSIT_WITH_HASHLOCK: u8 = 56;
+
+/// Wire tag of `ClaimWithPreimage`
+pub const TAG_CLAIM_WITH_PREIMAGE: u8 = 57;
+
+/// Wire tag of `DepositWithGuardians`
+pub const TAG_DEPOSIT_WITH_GUARDIANS: u8 = 58;
+
+/// Wire tag of `ProposeRecovery`
+pub const TAG_PROPOSE_RECOVERY: u8 = 59;
+
+/// Wire tag of `ApproveRecovery`
+pub const TAG_APPROVE_RECOVERY: u8 = 60;
+
+/// Wire tag of `ExecuteRecovery`
+pub const TAG_EXECUTE_RECOVERY: u8 = 61;
+
+/// Wire tag of `VetoRecovery`
+pub const TAG_VETO_RECOVERY: u8 = 62;
+/// Wire tag of `WithdrawByTag`
+pub const TAG_WITHDRAW_BY_TAG: u8 = 63;
+/// Wire tag of `RenounceOwnership`
+pub const TAG_RENOUNCE_OWNERSHIP: u8 = 64;
+/// Wire tag of `SetEmergencyAuthority`
+pub const TAG_SET_EMERGENCY_AUTHORITY: u8 = 65;
+/// Wire tag of `SetInsurance`
+pub const TAG_SET_INSURANCE: u8 = 66;
+/// Wire tag of `SetPositionIndex`
+pub const TAG_SET_POSITION_INDEX: u8 = 67;
+/// Wire tag of `DepositMany`
+pub const TAG_DEPOSIT_MANY: u8 = 68;
+/// Wire tag of `SetAllowedCallers`
+pub const TAG_SET_ALLOWED_CALLERS: u8 = 69;
+/// Wire tag of `SweepSurplus`
+pub const TAG_SWEEP_SURPLUS: u8 = 70;
+/// Wire tag of `SweepLamports`
+pub const TAG_SWEEP_LAMPORTS: u8 = 71;
+/// Wire tag of `SetGcDelay`
+pub const TAG_SET_GC_DELAY: u8 = 72;
+/// Wire tag of `GcDeposits`
+pub const TAG_GC_DEPOSITS: u8 = 73;
+/// Wire tag of `CloseToNewDeposits`
+pub const TAG_CLOSE_TO_NEW_DEPOSITS: u8 = 74;
+/// Wire tag of `ReopenDeposits`
+pub const TAG_REOPEN_DEPOSITS: u8 = 75;
+/// Wire tag of `InitializeGlobalState`
+pub const TAG_INITIALIZE_GLOBAL_STATE: u8 = 76;
+/// Wire tag of `SetGlobalHalt`
+pub const TAG_SET_GLOBAL_HALT: u8 = 77;
+/// Wire tag of `ProposeGlobalAuthority`
+pub const TAG_PROPOSE_GLOBAL_AUTHORITY: u8 = 78;
+/// Wire tag of `AcceptGlobalAuthority`
+pub const TAG_ACCEPT_GLOBAL_AUTHORITY: u8 = 79;
+/// Wire tag of `WriteSnapshot`
+pub const TAG_WRITE_SNAPSHOT: u8 = 80;
+/// Wire tag of `WithdrawSplit`
+pub const TAG_WITHDRAW_SPLIT: u8 = 81;
+/// Wire tag of `SetOwnerApprovedSplits`
+pub const TAG_SET_OWNER_APPROVED_SPLITS: u8 = 82;
+/// Wire tag of `WithdrawWithMemo`
+pub const TAG_WITHDRAW_WITH_MEMO: u8 = 83;
+/// Wire tag of `DepositStakedSol`
+pub const TAG_DEPOSIT_STAKED_SOL: u8 = 84;
+/// Wire tag of `DeactivateStake`
+pub const TAG_DEACTIVATE_STAKE: u8 = 85;
+/// Wire tag of `WithdrawStake`
+pub const TAG_WITHDRAW_STAKE: u8 = 86;
+/// Wire tag of `AnnounceSweepAll`
+pub const TAG_ANNOUNCE_SWEEP_ALL: u8 = 87;
+/// Wire tag of `ExecuteSweepAll`
+pub const TAG_EXECUTE_SWEEP_ALL: u8 = 88;
+/// Wire tag of `SetCoOwner`
+pub const TAG_SET_CO_OWNER: u8 = 89;
+/// Wire tag of `RemoveCoOwner`
+pub const TAG_REMOVE_CO_OWNER: u8 = 90;
+/// Wire tag of `ReleaseEarly`
+pub const TAG_RELEASE_EARLY: u8 = 91;
+/// Wire tag of `SetRewardMints`
+pub const TAG_SET_REWARD_MINTS: u8 = 92;
+/// Wire tag of `DistributeRewards`
+pub const TAG_DISTRIBUTE_REWARDS: u8 = 93;
+/// Wire tag of `ClaimRewards`
+pub const TAG_CLAIM_REWARDS: u8 = 94;
+
+/// Wire tag of `ViewDeposit`
+pub const TAG_VIEW_DEPOSIT: u8 = 95;
+
+/// Wire tag of `GetVersion`
+pub const TAG_GET_VERSION: u8 = 96;
+
+/// Wire tag of `SetUniqueTags`
+pub const TAG_SET_UNIQUE_TAGS: u8 = 97;
+
+/// Wire tag of `SetUnlockGranularity`
+pub const TAG_SET_UNLOCK_GRANULARITY: u8 = 98;
+
+/// Wire tag of `EmergencyWithdrawAll`
+pub const TAG_EMERGENCY_WITHDRAW_ALL: u8 = 99;
+
+/// Wire tag of `ReconcileStats`
+pub const TAG_RECONCILE_STATS: u8 = 100;
+
+/// Wire tag of `LogVotingPower`
+pub const TAG_LOG_VOTING_POWER: u8 = 101;
+
+/// Wire tag of `DepositEscrow`
+pub const TAG_DEPOSIT_ESCROW: u8 = 102;
+
+/// Wire tag of `ReleaseEscrow`
+pub const TAG_RELEASE_ESCROW: u8 = 103;
+
+/// Wire tag of `SetWithdrawHook`
+pub const TAG_SET_WITHDRAW_HOOK: u8 = 104;
+
+/// Wire tag of `CloneVault`
+pub const TAG_CLONE_VAULT: u8 = 105;
+
+/// Wire tag of `CreateVaultAndDeposit`
+pub const TAG_CREATE_VAULT_AND_DEPOSIT: u8 = 106;
+
+/// Wire tag of `DonateToDeposit`
+pub const TAG_DONATE_TO_DEPOSIT: u8 = 107;
+
+/// Wire tag of `SetDonationsDisabled`
+pub const TAG_SET_DONATIONS_DISABLED: u8 = 108;
+
+/// Wire tag of `CloseVaultTokenAccount`
+pub const TAG_CLOSE_VAULT_TOKEN_ACCOUNT: u8 = 109;
+
+/// Wire tag of `SetAutoClose`
+pub const TAG_SET_AUTO_CLOSE: u8 = 110;
+
+/// Set on the tag of versioned instructions
+pub const VERSIONED_TAG_FLAG: u8 = 0x80;
+
+/// Current version of the versioned instructions, bumped whenever one of
+/// them gains a field
+pub const INSTRUCTION_VERSION: u8 = 7;
+
+// Versions that appended a field to a versioned instruction. Older versions
+// still decode, with the fields appended since left at their defaults.
+// Untagged payloads predate versioning and may end after any field
+const VERSION_UNTAGGED: u8 = 0;
+const VERSION_TAGGED: u8 = 1;
+const VERSION_EMERGENCY_REQUIRES_DEPOSITOR: u8 = 2;
+const VERSION_EMERGENCY_DESTINATION: u8 = 3;
+const VERSION_IDEMPOTENCY_KEY: u8 = 4;
+const VERSION_OWNER_SET: u8 = 5;
+const VERSION_OWNER_CAN_RELEASE_EARLY: u8 = 6;
+const VERSION_VAULT_CONFIG: u8 = 7;
+
+/// Tags of the instructions encoded with a version byte
+pub const VERSIONED_TAGS: &[u8] = &[
+    TAG_CREATE_VAULT,
+    TAG_DEPOSIT,
+    TAG_DEPOSIT_FOR,
+    TAG_DEPOSIT_WITH_DURATION,
+    TAG_DEPOSIT_UI_AMOUNT,
+    TAG_DEPOSIT_PAIR,
+    TAG_DEPOSIT_TIERED,
+    TAG_DEPOSIT_UNTIL_SLOT,
+    TAG_DEPOSIT_WITH_PRICE_CONDITION,
+    TAG_DEPOSIT_WITH_ACCOUNT_CONDITION,
+    TAG_DEPOSIT_WITH_HASHLOCK,
+    TAG_DEPOSIT_WITH_GUARDIANS,
+    TAG_DEPOSIT_MANY,
+];
+
+impl VaultInstruction {
+    /// The instruction's wire tag
+    pub fn tag(&self) -> u8 {
+        match self {
+            VaultInstruction::CreateVault { .. } => TAG_CREATE_VAULT,
+            VaultInstruction::Deposit { .. } => TAG_DEPOSIT,
+            VaultInstruction::Withdraw { .. } => TAG_WITHDRAW,
+            VaultInstruction::EmergencyWithdraw { .. } => TAG_EMERGENCY_WITHDRAW,
+            VaultInstruction::CloseVault => TAG_CLOSE_VAULT,
+            VaultInstruction::SetPositionNfts { .. } => TAG_SET_POSITION_NFTS,
+            VaultInstruction::WithdrawWithPermit { .. } => TAG_WITHDRAW_WITH_PERMIT,
+            VaultInstruction::SetEmergencyCooldown { .. } => TAG_SET_EMERGENCY_COOLDOWN,
+            VaultInstruction::SetRateLimit { .. } => TAG_SET_RATE_LIMIT,
+            VaultInstruction::SetWithdrawDelay { .. } => TAG_SET_WITHDRAW_DELAY,
+            VaultInstruction::EnableQueuedWithdrawals { .. } => TAG_ENABLE_QUEUED_WITHDRAWALS,
+            VaultInstruction::RequestWithdraw { .. } => TAG_REQUEST_WITHDRAW,
+            VaultInstruction::ClaimWithdraw { .. } => TAG_CLAIM_WITHDRAW,
+            VaultInstruction::CancelWithdrawRequest { .. } => TAG_CANCEL_WITHDRAW_REQUEST,
+            VaultInstruction::SetEarlyWithdrawal { .. } => TAG_SET_EARLY_WITHDRAWAL,
+            VaultInstruction::EarlyWithdraw { .. } => TAG_EARLY_WITHDRAW,
+            VaultInstruction::SetPostUnlockGrace { .. } => TAG_SET_POST_UNLOCK_GRACE,
+            VaultInstruction::SetDestinationChangeDelay { .. } => TAG_SET_DESTINATION_CHANGE_DELAY,
+            VaultInstruction::ChangeLockedDestination { .. } => TAG_CHANGE_LOCKED_DESTINATION,
+            VaultInstruction::SetBeneficiaryChangeDelay { .. } => TAG_SET_BENEFICIARY_CHANGE_DELAY,
+            VaultInstruction::ProposeBeneficiary { .. } => TAG_PROPOSE_BENEFICIARY,
+            VaultInstruction::CommitBeneficiary { .. } => TAG_COMMIT_BENEFICIARY,
+            VaultInstruction::CancelBeneficiaryChange { .. } => TAG_CANCEL_BENEFICIARY_CHANGE,
+            VaultInstruction::DepositFor { .. } => TAG_DEPOSIT_FOR,
+            VaultInstruction::CrankWithdraw { .. } => TAG_CRANK_WITHDRAW,
+            VaultInstruction::SetCrankBounty { .. } => TAG_SET_CRANK_BOUNTY,
+            VaultInstruction::DepositWithDuration { .. } => TAG_DEPOSIT_WITH_DURATION,
+            VaultInstruction::SetDepositPolicy { .. } => TAG_SET_DEPOSIT_POLICY,
+            VaultInstruction::AddAllowedDepositor { .. } => TAG_ADD_ALLOWED_DEPOSITOR,
+            VaultInstruction::RemoveAllowedDepositor { .. } => TAG_REMOVE_ALLOWED_DEPOSITOR,
+            VaultInstruction::BlockDepositor { .. } => TAG_BLOCK_DEPOSITOR,
+            VaultInstruction::UnblockDepositor { .. } => TAG_UNBLOCK_DEPOSITOR,
+            VaultInstruction::SetMintLimit { .. } => TAG_SET_MINT_LIMIT,
+            VaultInstruction::SetTvlCap { .. } => TAG_SET_TVL_CAP,
+            VaultInstruction::SetDepositFee { .. } => TAG_SET_DEPOSIT_FEE,
+            VaultInstruction::InitializeConfig { .. } => TAG_INITIALIZE_CONFIG,
+            VaultInstruction::UpdateConfig { .. } => TAG_UPDATE_CONFIG,
+            VaultInstruction::ProposeConfigAdmin { .. } => TAG_PROPOSE_CONFIG_ADMIN,
+            VaultInstruction::AcceptConfigAdmin => TAG_ACCEPT_CONFIG_ADMIN,
+            VaultInstruction::SetFreezeAuthority { .. } => TAG_SET_FREEZE_AUTHORITY,
+            VaultInstruction::FreezeDeposit { .. } => TAG_FREEZE_DEPOSIT,
+            VaultInstruction::ThawDeposit { .. } => TAG_THAW_DEPOSIT,
+            VaultInstruction::ResolveFrozenDeposit { .. } => TAG_RESOLVE_FROZEN_DEPOSIT,
+            VaultInstruction::RevokeVaultDelegate => TAG_REVOKE_VAULT_DELEGATE,
+            VaultInstruction::SetVaultTokenCloseAuthority => TAG_SET_VAULT_TOKEN_CLOSE_AUTHORITY,
+            VaultInstruction::DepositUiAmount { .. } => TAG_DEPOSIT_UI_AMOUNT,
+            VaultInstruction::DepositPair { .. } => TAG_DEPOSIT_PAIR,
+            VaultInstruction::DepositTiered { .. } => TAG_DEPOSIT_TIERED,
+            VaultInstruction::ExtendLock { .. } => TAG_EXTEND_LOCK,
+            VaultInstruction::DepositUntilSlot { .. } => TAG_DEPOSIT_UNTIL_SLOT,
+            VaultInstruction::SetMinLockSlots { .. } => TAG_SET_MIN_LOCK_SLOTS,
+            VaultInstruction::DepositWithPriceCondition { .. } => TAG_DEPOSIT_WITH_PRICE_CONDITION,
+            VaultInstruction::WithdrawOnPrice { .. } => TAG_WITHDRAW_ON_PRICE,
+            VaultInstruction::SetPriceOracle { .. } => TAG_SET_PRICE_ORACLE,
+            VaultInstruction::DepositWithAccountCondition { .. } => TAG_DEPOSIT_WITH_ACCOUNT_CONDITION,
+            VaultInstruction::WithdrawOnCondition { .. } => TAG_WITHDRAW_ON_CONDITION,
+            VaultInstruction::DepositWithHashlock { .. } => TAG_DEPOSIT_WITH_HASHLOCK,
+            VaultInstruction::ClaimWithPreimage { .. } => TAG_CLAIM_WITH_PREIMAGE,
+            VaultInstruction::DepositWithGuardians { .. } => TAG_DEPOSIT_WITH_GUARDIANS,
+            VaultInstruction::ProposeRecovery { .. } => TAG_PROPOSE_RECOVERY,
+            VaultInstruction::ApproveRecovery { .. } => TAG_APPROVE_RECOVERY,
+            VaultInstruction::ExecuteRecovery { .. } => TAG_EXECUTE_RECOVERY,
+            VaultInstruction::VetoRecovery { .. } => TAG_VETO_RECOVERY,
+            VaultInstruction::WithdrawByTag { .. } => TAG_WITHDRAW_BY_TAG,
+            VaultInstruction::RenounceOwnership { .. } => TAG_RENOUNCE_OWNERSHIP,
+            VaultInstruction::SetEmergencyAuthority { .. } => TAG_SET_EMERGENCY_AUTHORITY,
+            VaultInstruction::SetInsurance { .. } => TAG_SET_INSURANCE,
+            VaultInstruction::SetPositionIndex { .. } => TAG_SET_POSITION_INDEX,
+            VaultInstruction::DepositMany { .. } => TAG_DEPOSIT_MANY,
+            VaultInstruction::SetAllowedCallers { .. } => TAG_SET_ALLOWED_CALLERS,
+            VaultInstruction::SweepSurplus { .. } => TAG_SWEEP_SURPLUS,
+            VaultInstruction::SweepLamports => TAG_SWEEP_LAMPORTS,
+            VaultInstruction::SetGcDelay { .. } => TAG_SET_GC_DELAY,
+            VaultInstruction::GcDeposits { .. } => TAG_GC_DEPOSITS,
+            VaultInstruction::CloseToNewDeposits => TAG_CLOSE_TO_NEW_DEPOSITS,
+            VaultInstruction::ReopenDeposits => TAG_REOPEN_DEPOSITS,
+            VaultInstruction::InitializeGlobalState => TAG_INITIALIZE_GLOBAL_STATE,
+            VaultInstruction::SetGlobalHalt { .. } => TAG_SET_GLOBAL_HALT,
+            VaultInstruction::ProposeGlobalAuthority { .. } => TAG_PROPOSE_GLOBAL_AUTHORITY,
+            VaultInstruction::AcceptGlobalAuthority => TAG_ACCEPT_GLOBAL_AUTHORITY,
+            VaultInstruction::WriteSnapshot => TAG_WRITE_SNAPSHOT,
+            VaultInstruction::WithdrawSplit { .. } => TAG_WITHDRAW_SPLIT,
+            VaultInstruction::SetOwnerApprovedSplits { .. } => TAG_SET_OWNER_APPROVED_SPLITS,
+            VaultInstruction::WithdrawWithMemo { .. } => TAG_WITHDRAW_WITH_MEMO,
+            VaultInstruction::DepositStakedSol { .. } => TAG_DEPOSIT_STAKED_SOL,
+            VaultInstruction::DeactivateStake { .. } => TAG_DEACTIVATE_STAKE,
+            VaultInstruction::WithdrawStake { .. } => TAG_WITHDRAW_STAKE,
+            VaultInstruction::AnnounceSweepAll => TAG_ANNOUNCE_SWEEP_ALL,
+            VaultInstruction::ExecuteSweepAll { .. } => TAG_EXECUTE_SWEEP_ALL,
+            VaultInstruction::SetCoOwner { .. } => TAG_SET_CO_OWNER,
+            VaultInstruction::RemoveCoOwner { .. } => TAG_REMOVE_CO_OWNER,
+            VaultInstruction::ReleaseEarly { .. } => TAG_RELEASE_EARLY,
+            VaultInstruction::SetRewardMints { .. } => TAG_SET_REWARD_MINTS,
+            VaultInstruction::DistributeRewards { .. } => TAG_DISTRIBUTE_REWARDS,
+            VaultInstruction::ClaimRewards { .. } => TAG_CLAIM_REWARDS,
+            VaultInstruction::ViewDeposit { .. } => TAG_VIEW_DEPOSIT,
+            VaultInstruction::GetVersion => TAG_GET_VERSION,
+            VaultInstruction::SetUniqueTags { .. } => TAG_SET_UNIQUE_TAGS,
+            VaultInstruction::SetUnlockGranularity { .. } => TAG_SET_UNLOCK_GRANULARITY,
+            VaultInstruction::EmergencyWithdrawAll { .. } => TAG_EMERGENCY_WITHDRAW_ALL,
+            VaultInstruction::ReconcileStats => TAG_RECONCILE_STATS,
+            VaultInstruction::LogVotingPower { .. } => TAG_LOG_VOTING_POWER,
+            VaultInstruction::DepositEscrow { .. } => TAG_DEPOSIT_ESCROW,
+            VaultInstruction::ReleaseEscrow { .. } => TAG_RELEASE_ESCROW,
+            VaultInstruction::SetWithdrawHook { .. } => TAG_SET_WITHDRAW_HOOK,
+            VaultInstruction::CloneVault { .. } => TAG_CLONE_VAULT,
+            VaultInstruction::CreateVaultAndDeposit { .. } => TAG_CREATE_VAULT_AND_DEPOSIT,
+            VaultInstruction::DonateToDeposit { .. } => TAG_DONATE_TO_DEPOSIT,
+            VaultInstruction::SetDonationsDisabled { .. } => TAG_SET_DONATIONS_DISABLED,
+            VaultInstruction::CloseVaultTokenAccount { .. } => TAG_CLOSE_VAULT_TOKEN_ACCOUNT,
+            VaultInstruction::SetAutoClose { .. } => TAG_SET_AUTO_CLOSE,
+        }
+    }
+    
+    /// Encode in the tagged wire format
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = self.try_to_vec().expect("instructions always serialize");
+        if VERSIONED_TAGS.contains(&data[0]) {
+            data[0] |= VERSIONED_TAG_FLAG;
+            data.insert(1, INSTRUCTION_VERSION);
+        }
+        data
+    }
+    
+    /// Decode the tagged wire format or the legacy Borsh enum encoding
+    pub fn unpack(data: &[u8]) -> Result<Self, VaultError> {
+        let (&tag, rest) = data.split_first().ok_or(VaultError::InvalidInstructionData)?;
+        if tag & VERSIONED_TAG_FLAG == 0 {
+            return Self::try_from_slice(data).or_else(|_| Self::unpack_versioned(tag, VERSION_UNTAGGED, rest));
+        }
+    
+        let tag = tag & !VERSIONED_TAG_FLAG;
+        let (&version, fields) = rest.split_first().ok_or(VaultError::InvalidInstructionData)?;
+        if !VERSIONED_TAGS.contains(&tag) || version == VERSION_UNTAGGED || version > INSTRUCTION_VERSION {
+            return Err(VaultError::InvalidInstructionData);
+        }
+        Self::unpack_versioned(tag, version, fields)
+    }
+    
+    // Decode the fields of a versioned instruction as encoded in `version`
+    fn unpack_versioned(tag: u8, version: u8, fields: &[u8]) -> Result<Self, VaultError> {
+        let buf = &mut &*fields;
+        let instruction = match tag {
+            // The original zero-argument `CreateVault` never used the registry
+            TAG_CREATE_VAULT if version == VERSION_UNTAGGED && fields.is_empty() => VaultInstruction::CreateVault {
+                skip_registry: true,
+                arbitration_destination: None,
+                emergency_destination: None,
+                owner_set: None,
+                config: VaultConfig::default(),
+            },
+            TAG_CREATE_VAULT => VaultInstruction::CreateVault {
+                skip_registry: read_appended_field(buf, version, VERSION_TAGGED)?,
+                arbitration_destination: read_appended_field(buf, version, VERSION_TAGGED)?,
+                emergency_destination: read_appended_field(buf, version, VERSION_EMERGENCY_DESTINATION)?,
+                owner_set: read_appended_field(buf, version, VERSION_OWNER_SET)?,
+                config: read_appended_field(buf, version, VERSION_VAULT_CONFIG)?,
+            },
+            TAG_DEPOSIT => VaultInstruction::Deposit {
+                amount: read_field(buf)?,
+                unlock_time: read_field(buf)?,
+                tag: read_field(buf)?,
+                locked_destination: read_appended_field(buf, version, VERSION_TAGGED)?,
+                no_crank: read_appended_field(buf, version, VERSION_TAGGED)?,
+                emergency_requires_depositor: read_appended_field(buf, version, VERSION_EMERGENCY_REQUIRES_DEPOSITOR)?,
+                idempotency_key: read_appended_field(buf, version, VERSION_IDEMPOTENCY_KEY)?,
+                owner_can_release_early: read_appended_field(buf, version, VERSION_OWNER_CAN_RELEASE_EARLY)?,
+            },
+            TAG_DEPOSIT_FOR => VaultInstruction::DepositFor {
+                amount: read_field(buf)?,
+                unlock_time: read_field(buf)?,
+                tag: read_field(buf)?,
+                locked_destination: read_appended_field(buf, version, VERSION_TAGGED)?,
+                no_crank: read_appended_field(buf, version, VERSION_TAGGED)?,
+                emergency_requires_depositor: read_appended_field(buf, version, VERSION_EMERGENCY_REQUIRES_DEPOSITOR)?,
+                owner_can_release_early: read_appended_field(buf, version, VERSION_OWNER_CAN_RELEASE_EARLY)?,
+            },
+            _ => {
+                let mut legacy = Vec::with_capacity(1 + fields.len());
+                legacy.push(tag);
+                legacy.extend_from_slice(fields);
+                return Self::try_from_slice(&legacy).map_err(|_| VaultError::InvalidInstructionData);
+            },
+        };
+        if !buf.is_empty() {
+            return Err(VaultError::InvalidInstructionData);
+        }
+        Ok(instruction)
+    }
+}
+
+// Read the next field of an instruction
+fn read_field<T: BorshDeserialize>(buf: &mut &[u8]) -> Result<T, VaultError> {
+    T::deserialize(buf).map_err(|_| VaultError::InvalidInstructionData)
+}
+
+// Read a field appended in version `since`, or its default when decoding
+// an older version or an untagged payload that ends before it
+fn read_appended_field<T>(buf: &mut &[u8], version: u8, since: u8) -> Result<T, VaultError>
+where
+    T: BorshDeserialize + Default,
+{
+    let absent = match version {
+        VERSION_UNTAGGED => buf.is_empty(),
+        _ => version < since,
+    };
+    if absent {
+        return Ok(T::default());
+    }
+    read_field(buf)
+}
+
+// Vault account data structure
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default)]
+pub struct Vault {
+    /// The owner of the vault
+    pub owner: Pubkey,
+    /// The number of deposits made to this vault
+    pub deposit_count: u64,
+    /// The deposits in this vault
+    pub deposits: Vec<Deposit>,
+    /// Reentrancy guard
+    pub reentrancy_guard: bool,
+    /// Emergency authority (multisig or DAO)
+    pub emergency_authority: Option<Pubkey>,
+    /// Whether deposits are represented by transferable position NFTs
+    pub position_nfts: bool,
+    /// Metadata URI of the position NFTs
+    pub position_nft_uri: String,
+    /// Minimum seconds between emergency withdrawals across the whole vault
+    pub emergency_cooldown: i64,
+    /// Timestamp of the last emergency withdrawal (zero if none)
+    pub last_emergency_withdraw: i64,
+    /// Maximum amount each withdrawer may take out per window
+    pub rate_limit: Option<RateLimit>,
+    /// Open rate limit windows, one per withdrawer
+    pub withdrawal_windows: Vec<WithdrawalWindow>,
+    /// Seconds between requesting and claiming a queued withdrawal
+    pub withdraw_delay: i64,
+    /// Early withdrawal penalty in basis points at deposit time
+    pub max_penalty_bps: u16,
+    /// Token account receiving early withdrawal penalties (early withdrawals
+    /// are disabled while unset)
+    pub treasury: Option<Pubkey>,
+    /// Seconds after unlock during which only the depositor may act on a deposit
+    pub post_unlock_grace: i64,
+    /// Seconds a locked destination change must wait before it can be applied
+    pub destination_change_delay: i64,
+    /// Seconds a beneficiary change must wait before it can be committed
+    pub beneficiary_change_delay: i64,
+    /// Share of a cranked deposit paid to the cranker, in basis points
+    pub crank_bounty_bps: u16,
+    /// Who new deposits may be attributed to
+    pub deposit_policy: DepositPolicy,
+    /// Wallets allowed to deposit under `DepositPolicy::Allowlist`
+    pub allowed_depositors: Vec<Pubkey>,
+    /// Wallets blocked from new deposits, kept sorted for binary search
+    pub blocked_depositors: Vec<Pubkey>,
+    /// Per-mint outstanding amounts and limits
+    pub mints: Vec<MintEntry>,
+    /// Maximum total locked across all mints, in raw token units
+    pub tvl_cap: Option<u64>,
+    /// Total locked across all mints, in raw token units
+    pub total_locked: u64,
+    /// Flat lamport fee charged per deposit
+    pub deposit_fee_lamports: u64,
+    /// Account receiving deposit fees
+    pub deposit_fee_destination: Pubkey,
+    /// Authority allowed to freeze deposits, falling back to the emergency authority
+    pub freeze_authority: Option<Pubkey>,
+    /// Escrow token account frozen deposits can be sent to, set at creation
+    pub arbitration_destination: Option<Pubkey>,
+    /// Minimum number of slots between a `DepositUntilSlot` and its unlock slot
+    pub min_lock_slots: u64,
+    /// Oracle settings for price-conditioned unlocks, `None` to disable them
+    pub price_oracle: Option<PriceOracleConfig>,
+    /// Amount locked under each tracked tag, at most `MAX_TRACKED_TAGS` entries
+    pub tag_totals: Vec<TagTotal>,
+    /// Amount locked under tags that didn't fit in `tag_totals`
+    pub other_tags_total: u64,
+    /// Set once the owner renounced ownership; owner-gated instructions fail
+    pub renounced: bool,
+    /// Wallet whose associated token accounts receive emergency withdrawals,
+    /// set at creation; `None` pays the depositor
+    pub emergency_destination: Option<Pubkey>,
+    /// Share of each deposit paid into the insurance pool, in basis points
+    pub insurance_bps: u16,
+    /// Token account receiving insurance premiums
+    pub insurance_pool: Pubkey,
+    /// Total premiums paid into the insurance pool, in raw token units
+    pub insurance_premiums: u64,
+    /// Whether open deposits are tracked in per-depositor position indexes
+    pub position_index: bool,
+    /// Programs allowed to deposit through CPI; `None` allows any caller
+    pub allowed_callers: Option<Vec<Pubkey>>,
+    /// Whether deposits not made through an allowed caller are rejected
+    pub reject_top_level: bool,
+    /// Seconds after withdrawal before `GcDeposits` may remove a deposit
+    /// record; `None` keeps every record
+    pub gc_delay: Option<i64>,
+    /// Deposits ever created; never decreases, even as records are removed
+    pub lifetime_deposits_created: u64,
+    /// Total amount ever deposited, net of insurance premiums
+    pub lifetime_amount_deposited: u128,
+    /// Total amount of deposits ever closed by any withdrawal path
+    pub lifetime_amount_withdrawn: u128,
+    /// Emergency withdrawals ever made
+    pub lifetime_emergency_withdrawals: u64,
+    /// Keys of the last `MAX_IDEMPOTENCY_KEYS` keyed deposits, a ring buffer
+    /// of at most `IDEMPOTENCY_KEYS_SPACE` bytes
+    pub idempotency_keys: Vec<IdempotencyKey>,
+    /// Slot of `idempotency_keys` the next key overwrites once it is full
+    pub idempotency_cursor: u8,
+    /// Whether `CloseToNewDeposits` stopped new deposits; stored inverted so
+    /// vaults accept deposits by default (see `accepting_deposits`)
+    pub deposits_closed: bool,
+    /// Whether the vault owner's co-signature lets `WithdrawSplit` pay only
+    /// accounts the depositor doesn't own
+    pub owner_approved_splits: bool,
+    /// When `AnnounceSweepAll` announced the vault's evacuation, if it did
+    pub sweep_announced_at: Option<i64>,
+    /// M-of-N owner set; when present, owner-gated instructions need its
+    /// threshold of member signatures and `owner` only records the creator
+    pub owner_set: Option<OwnerSet>,
+    /// Co-owners and their `CAN_*` role bitmasks, at most `MAX_CO_OWNERS`
+    pub co_owners: Vec<(Pubkey, u8)>,
+    /// Mint `DistributeRewards` pays rewards in
+    pub reward_mint: Option<Pubkey>,
+    /// Mint of the deposits that share rewards by amount
+    pub reward_weight_mint: Option<Pubkey>,
+    /// Rounding dust of past distributions, added to the next one
+    pub reward_carry: u64,
+    /// Distribution still being allocated across transactions
+    pub reward_distribution: Option<RewardDistribution>,
+    /// Whether a depositor's active deposits must carry distinct tags
+    pub unique_tags: bool,
+    /// Seconds new unlock times are rounded up to a multiple of (zero if off)
+    pub unlock_granularity: i64,
+    /// `EmergencyWithdrawAll` still visiting the deposits
+    pub emergency_evacuation: Option<EmergencyEvacuation>,
+    /// Whether the program stats count this vault, from its creation or a
+    /// `ReconcileStats`; only counted vaults update them incrementally
+    pub stats_reconciled: bool,
+    /// Program `Withdraw` calls after each payout, if any
+    pub hook_program: Option<Pubkey>,
+    /// Whether `DonateToDeposit` is rejected
+    pub donations_disabled: bool,
+    /// Whether `Withdraw` closes a mint's token account once it pays out the
+    /// mint's last open deposit and leaves it empty
+    pub auto_close: bool,
+    /// Set when the vault is created; vaults from before the flag read it as
+    /// false, so only zero-filled data counts as uninitialized
+    pub is_initialized: bool,
+}
+
+impl Vault {
+    /// Decode a vault account's data, telling accounts that were never
+    /// initialized (empty or zero-filled) apart from undecodable ones
+    pub fn from_account_data(data: &[u8]) -> Result<Vault, VaultError> {
+        if data.iter().all(|byte| *byte == 0) {
+            return Err(VaultError::UninitializedVault);
+        }
+        Vault::deserialize(&mut &data[..]).map_err(|_| VaultError::CorruptVaultData)
+    }
+    
+    /// Whether the vault takes new deposits; withdrawals never depend on it
+    pub fn accepting_deposits(&self) -> bool {
+        !self.deposits_closed
+    }
+    
+    /// Amount locked under `tag`, or `None` when the tag isn't tracked: either
+    /// nothing is locked under it or it is counted in `other_tags_total`
+    pub fn total_for_tag(&self, tag: &[u8; 32]) -> Option<u64> {
+        self.tag_totals.iter().find(|entry| entry.tag == *tag).map(|entry| entry.total)
+    }
+    
+    /// Active deposits in unlock order, ties broken by ID. Computed from
+    /// `deposits` on each call instead of being stored, so it can't drift as
+    /// deposits are made and withdrawn; slot-locked deposits have no unlock
+    /// time and are left out
+    pub fn unlock_order(&self) -> Vec<&Deposit> {
+        let mut order: Vec<&Deposit> = self.deposits.iter()
+            .filter(|d| !d.is_withdrawn() && d.unlock_slot.is_none())
+            .collect();
+        order.sort_unstable_by_key(|d| (d.unlock_time, d.id));
+        order
+    }
+    
+    /// ID and unlock time of the active deposit that unlocks next after
+    /// `now`, the lowest ID among deposits sharing that unlock time
+    pub fn next_unlock(&self, now: i64) -> Option<(u64, i64)> {
+        self.deposits.iter()
+            .filter(|d| !d.is_withdrawn() && d.unlock_slot.is_none() && d.unlock_time > now)
+            .min_by_key(|d| (d.unlock_time, d.id))
+            .map(|d| (d.id, d.unlock_time))
+    }
+    
+    /// Active deposits unlocking in `[start, end)`, in unlock order
+    pub fn unlocking_between(&self, start: i64, end: i64) -> Vec<&Deposit> {
+        let mut order = self.unlock_order();
+        order.retain(|d| d.unlock_time >= start && d.unlock_time < end);
+        order
+    }
+    
+    /// Total vote-escrow voting power of `depositor`'s deposits at `now`,
+    /// summed across every mint the vault holds; see `Deposit::voting_power`
+    pub fn total_voting_power(&self, depositor: &Pubkey, now: i64, max_lock: i64) -> u128 {
+        self.deposits.iter()
+            .filter(|d| d.depositor == *depositor)
+            .fold(0u128, |total, d| total.saturating_add(d.voting_power(now, max_lock)))
+    }
+    
+    /// A new vault owned by `owner` with this vault's configuration: its
+    /// policies, limits, fees, delays, authorities and destinations, with the
+    /// mint limits but none of the outstanding amounts. Deposits, counters,
+    /// totals, windows and the reentrancy, pause, renouncement, sweep,
+    /// evacuation and reward distribution state start fresh, and the owner
+    /// set isn't copied since the new owner alone controls the clone
+    pub fn clone_config(&self, owner: Pubkey) -> Vault {
+        Vault {
+            is_initialized: true,
+            owner,
+            emergency_authority: self.emergency_authority,
+            position_nfts: self.position_nfts,
+            position_nft_uri: self.position_nft_uri.clone(),
+            emergency_cooldown: self.emergency_cooldown,
+            rate_limit: self.rate_limit,
+            withdraw_delay: self.withdraw_delay,
+            max_penalty_bps: self.max_penalty_bps,
+            treasury: self.treasury,
+            post_unlock_grace: self.post_unlock_grace,
+            destination_change_delay: self.destination_change_delay,
+            beneficiary_change_delay: self.beneficiary_change_delay,
+            crank_bounty_bps: self.crank_bounty_bps,
+            deposit_policy: self.deposit_policy,
+            allowed_depositors: self.allowed_depositors.clone(),
+            blocked_depositors: self.blocked_depositors.clone(),
+            mints: self.mints.iter()
+                .filter(|entry| entry.max_outstanding.is_some())
+                .map(|entry| MintEntry {
+                    mint: entry.mint,
+                    max_outstanding: entry.max_outstanding,
+                    ..MintEntry::default()
+                })
+                .collect(),
+            tvl_cap: self.tvl_cap,
+            deposit_fee_lamports: self.deposit_fee_lamports,
+            deposit_fee_destination: self.deposit_fee_destination,
+            freeze_authority: self.freeze_authority,
+            arbitration_destination: self.arbitration_destination,
+            min_lock_slots: self.min_lock_slots,
+            price_oracle: self.price_oracle,
+            emergency_destination: self.emergency_destination,
+            insurance_bps: self.insurance_bps,
+            insurance_pool: self.insurance_pool,
+            position_index: self.position_index,
+            allowed_callers: self.allowed_callers.clone(),
+            reject_top_level: self.reject_top_level,
+            gc_delay: self.gc_delay,
+            owner_approved_splits: self.owner_approved_splits,
+            co_owners: self.co_owners.clone(),
+            reward_mint: self.reward_mint,
+            reward_weight_mint: self.reward_weight_mint,
+            unique_tags: self.unique_tags,
+            unlock_granularity: self.unlock_granularity,
+            hook_program: self.hook_program,
+            donations_disabled: self.donations_disabled,
+            auto_close: self.auto_close,
+            ..Vault::default()
+        }
+    }
+}
+
+// Per-mint accounting entry
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct MintEntry {
+    /// The token mint
+    pub mint: Pubkey,
+    /// Amount of this mint held for active deposits
+    pub outstanding: u64,
+    /// Maximum outstanding amount accepted, if limited
+    pub max_outstanding: Option<u64>,
+    /// Total amount of this mint deposited while the entry was tracked; the
+    /// entry is dropped once nothing is outstanding and no limit is set
+    pub lifetime_deposited: u128,
+}
+
+// A deposit idempotency key and the depositor that used it
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct IdempotencyKey {
+    /// Depositor of the keyed deposit; keys are only compared within a depositor
+    pub depositor: Pubkey,
+    /// The client-supplied key
+    pub key: [u8; 16],
+}
+
+// Members sharing a vault's ownership and how many of them must sign
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct OwnerSet {
+    /// Distinct member wallets, at most `MAX_OWNER_SET_MEMBERS`
+    pub members: Vec<Pubkey>,
+    /// Number of members that must sign an owner-gated instruction
+    pub threshold: u8,
+}
+
+// Settings a vault can be created with, each of which an owner instruction
+// can also change later; `None` keeps the current value
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct VaultConfig {
+    /// Authority allowed to make emergency withdrawals (see `SetEmergencyAuthority`)
+    pub emergency_authority: Option<Pubkey>,
+    /// Minimum slots a slot-locked deposit must lock for (see `SetMinLockSlots`)
+    pub min_lock_slots: Option<u64>,
+    /// Who new deposits may be attributed to (see `SetDepositPolicy`)
+    pub deposit_policy: Option<DepositPolicy>,
+    /// Maximum total locked across all mints (see `SetTvlCap`)
+    pub tvl_cap: Option<u64>,
+    /// Flat lamport fee charged per deposit (see `SetDepositFee`)
+    pub deposit_fee_lamports: Option<u64>,
+    /// Account receiving deposit fees
+    pub deposit_fee_destination: Option<Pubkey>,
+}
+
+impl VaultConfig {
+    /// Fail for settings no vault should have: the default pubkey as
+    /// emergency authority, a zero TVL cap (close the vault to new deposits
+    /// instead) or a deposit fee without a destination
+    pub fn validate(&self) -> Result<(), VaultError> {
+        if self.emergency_authority == Some(Pubkey::default()) || self.tvl_cap == Some(0) {
+            return Err(VaultError::InvalidVaultConfig);
+        }
+        if self.deposit_fee_lamports.unwrap_or(0) > 0
+            && self.deposit_fee_destination.unwrap_or_default() == Pubkey::default()
+        {
+            return Err(VaultError::InvalidFeeDestination);
+        }
+        Ok(())
+    }
+    
+    // Write the settings that are present into the vault
+    fn apply(&self, vault: &mut Vault) {
+        if let Some(authority) = self.emergency_authority {
+            vault.emergency_authority = Some(authority);
+        }
+        if let Some(min_lock_slots) = self.min_lock_slots {
+            vault.min_lock_slots = min_lock_slots;
+        }
+        if let Some(policy) = self.deposit_policy {
+            vault.deposit_policy = policy;
+        }
+        if let Some(cap) = self.tvl_cap {
+            vault.tvl_cap = Some(cap);
+        }
+        if let Some(lamports) = self.deposit_fee_lamports {
+            vault.deposit_fee_lamports = lamports;
+        }
+        if let Some(destination) = self.deposit_fee_destination {
+            vault.deposit_fee_destination = destination;
+        }
+    }
+}
+
+// Version and features reported by `GetVersion`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProgramVersion {
+    /// Major, minor and patch version
+    pub version: (u8, u8, u8),
+    /// `FEATURE_*` bits; bits unknown to this client are kept as they are
+    pub features: u64,
+}
+
+impl ProgramVersion {
+    /// The version and features of this build
+    pub const CURRENT: ProgramVersion = ProgramVersion { version: PROGRAM_VERSION, features: SUPPORTED_FEATURES };
+    
+    /// Encode as `GetVersion` return data: the three version bytes, then the
+    /// feature bits as a little-endian u64
+    pub fn to_return_data(&self) -> Vec<u8> {
+        let (major, minor, patch) = self.version;
+        let mut data = vec![major, minor, patch];
+        data.extend_from_slice(&self.features.to_le_bytes());
+        data
+    }
+    
+    /// Decode `GetVersion` return data, ignoring anything a later version
+    /// appends after the feature bits
+    pub fn from_return_data(data: &[u8]) -> Option<Self> {
+        let features = u64::from_le_bytes(data.get(3..11)?.try_into().ok()?);
+        Some(ProgramVersion { version: (data[0], data[1], data[2]), features })
+    }
+    
+    /// Whether every bit of `feature` is supported
+    pub fn supports(&self, feature: u64) -> bool {
+        self.features & feature == feature
+    }
+}
+
+// Reward distribution allocated in chunks over several `DistributeRewards`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct RewardDistribution {
+    /// Reward tokens to allocate, including the carry of earlier distributions
+    pub total: u64,
+    /// Combined amount of the active weight mint deposits when it started
+    pub total_weight: u128,
+    /// Reward tokens allocated so far
+    pub distributed: u64,
+    /// Lowest deposit ID not yet visited
+    pub next_id: u64,
+    /// Deposits with this ID or above were made after it started and share nothing
+    pub end_id: u64,
+}
+
+// Emergency withdrawal of one depositor's deposits of a mint, carried out in
+// chunks over several `EmergencyWithdrawAll`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct EmergencyEvacuation {
+    /// Depositor whose deposits are withdrawn
+    pub depositor: Pubkey,
+    /// Mint of the deposits withdrawn
+    pub mint: Pubkey,
+    /// Lowest deposit ID not yet visited
+    pub next_id: u64,
+    /// Deposits with this ID or above were made after it started and are left alone
+    pub end_id: u64,
+}
+
+/// Return data of `EmergencyWithdrawAll` (Borsh)
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct EmergencyWithdrawAllResult {
+    /// IDs of the deposits this call withdrew
+    pub withdrawn: Vec<u64>,
+    /// IDs of the depositor's open deposits of the mint this call left alone,
+    /// since `EmergencyWithdraw` would refuse them too
+    pub skipped: Vec<u64>,
+    /// Whether every deposit has been visited
+    pub finished: bool,
+}
+
+// Per-tag accounting entry
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct TagTotal {
+    /// The deposit tag
+    pub tag: [u8; 32],
+    /// Amount locked under this tag by active deposits
+    pub total: u64,
+}
+
+// Deposit policy
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum DepositPolicy {
+    /// Anyone may deposit
+    #[default]
+    Open,
+    /// Only wallets in `allowed_depositors` may be recorded as depositors
+    Allowlist,
+}
+
+// Destination of a resolved frozen deposit
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ResolutionDestination {
+    /// Paid back to the depositor
+    ReturnToDepositor,
+    /// Paid to the vault's arbitration destination
+    Arbitration,
+}
+
+// Fate of the emergency authority when ownership is renounced
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AuthorityOnRenounce {
+    /// The emergency authority keeps its powers
+    Keep,
+    /// The emergency authority is removed
+    Clear,
+}
+
+// Canonical lock durations, ordered from shortest to longest
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LockTier {
+    /// Locked for `LOCK_ONE_MONTH_SECS`
+    OneMonth,
+    /// Locked for `LOCK_THREE_MONTHS_SECS`
+    ThreeMonths,
+    /// Locked for `LOCK_SIX_MONTHS_SECS`
+    SixMonths,
+    /// Locked for `LOCK_ONE_YEAR_SECS`
+    OneYear,
+    /// Locked for `LOCK_FOUR_YEARS_SECS`
+    FourYears,
+}
+
+/// Lock duration of `LockTier::OneMonth` (30 days)
+pub const LOCK_ONE_MONTH_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Lock duration of `LockTier::ThreeMonths` (90 days)
+pub const LOCK_THREE_MONTHS_SECS: i64 = 3 * LOCK_ONE_MONTH_SECS;
+
+/// Lock duration of `LockTier::SixMonths` (180 days)
+pub const LOCK_SIX_MONTHS_SECS: i64 = 6 * LOCK_ONE_MONTH_SECS;
+
+/// Lock duration of `LockTier::OneYear` (365 days)
+pub const LOCK_ONE_YEAR_SECS: i64 = 365 * 24 * 60 * 60;
+
+/// Lock duration of `LockTier::FourYears` (4 × 365 days)
+pub const LOCK_FOUR_YEARS_SECS: i64 = 4 * LOCK_ONE_YEAR_SECS;
+
+impl LockTier {
+    /// Seconds a deposit on this tier stays locked
+    pub const fn duration_secs(self) -> i64 {
+        match self {
+            LockTier::OneMonth => LOCK_ONE_MONTH_SECS,
+            LockTier::ThreeMonths => LOCK_THREE_MONTHS_SECS,
+            LockTier::SixMonths => LOCK_SIX_MONTHS_SECS,
+            LockTier::OneYear => LOCK_ONE_YEAR_SECS,
+            LockTier::FourYears => LOCK_FOUR_YEARS_SECS,
+        }
+    }
+    
+    /// The tier stored as `Deposit::lock_tier`
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(LockTier::OneMonth),
+            1 => Some(LockTier::ThreeMonths),
+            2 => Some(LockTier::SixMonths),
+            3 => Some(LockTier::OneYear),
+            4 => Some(LockTier::FourYears),
+            _ => None,
+        }
+    }
+}
+
+// Price at or above which a deposit unlocks early
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PriceCondition {
+    /// Pyth price account to read
+    pub feed: Pubkey,
+    /// Threshold price, scaled by 10^expo
+    pub threshold: i64,
+    /// Exponent of `threshold`
+    pub expo: i32,
+}
+
+// Bytes another account must hold for a deposit to unlock early, compared
+// like a `getProgramAccounts` memcmp filter
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct AccountCondition {
+    /// Account to read
+    pub account: Pubkey,
+    /// Byte offset into the account data
+    pub offset: u32,
+    /// Bytes expected at `offset`, at most `MAX_CONDITION_BYTES`
+    pub expected: Vec<u8>,
+}
+
+/// Maximum length of `AccountCondition::expected`
+pub const MAX_CONDITION_BYTES: usize = 64;
+
+// Oracle settings for price-conditioned unlocks
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PriceOracleConfig {
+    /// Program that must own the price accounts
+    pub program: Pubkey,
+    /// Maximum slots between a price's publication and its use
+    pub max_age_slots: u64,
+    /// Maximum confidence interval relative to the price, in basis points
+    pub max_conf_bps: u16,
+}
+
+// Rate limit configuration
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    /// Maximum amount withdrawn per window
+    pub amount: u64,
+    /// Length of a window in seconds
+    pub window_secs: i64,
+}
+
+// Amount withdrawn by one withdrawer in the current window
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct WithdrawalWindow {
+    /// The withdrawer this window tracks
+    pub withdrawer: Pubkey,
+    /// When the window opened
+    pub window_start: i64,
+    /// Amount withdrawn since the window opened
+    pub withdrawn: u64,
+}
+
+// Lifecycle of a deposit; every status but `Active` is terminal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DepositStatus {
+    /// Locked or matured, still holding funds
+    Active,
+    /// Paid out by a regular withdrawal after unlocking (or a hashlock claim)
+    Withdrawn,
+    /// Paid out by the emergency authority
+    EmergencyWithdrawn,
+    /// Withdrawn by the depositor before unlocking, with a penalty
+    Cancelled,
+    /// A frozen deposit settled by the emergency authority
+    Swept,
+    /// Paid out to the depositor before unlocking by the vault owner
+    ReleasedEarly,
+}
+
+impl DepositStatus {
+    /// The status stored as `Deposit::status`
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(DepositStatus::Active),
+            1 => Some(DepositStatus::Withdrawn),
+            2 => Some(DepositStatus::EmergencyWithdrawn),
+            3 => Some(DepositStatus::Cancelled),
+            4 => Some(DepositStatus::Swept),
+            5 => Some(DepositStatus::ReleasedEarly),
+            _ => None,
+        }
+    }
+}
+
+// Deposit data structure
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default)]
+pub struct Deposit {
+    /// Unique identifier for the deposit
+    pub id: u64,
+    /// The depositor's public key (informational once a position NFT exists)
+    pub depositor: Pubkey,
+    /// The token mint address
+    pub token_mint: Pubkey,
+    /// Amount of tokens deposited
+    pub amount: u64,
+    /// Timestamp when the deposit can be withdrawn
+    pub unlock_time: i64,
+    /// `DepositStatus` of the deposit, as its `u8` value; written where a
+    /// `withdrawn: bool` used to be, so older records read as `Active` or
+    /// `Withdrawn`
+    pub status: u8,
+    /// Optional tag for the deposit
+    pub tag: [u8; 32],
+    /// Creation timestamp
+    pub created_at: i64,
+    /// Mint of the position NFT whose holder may withdraw, if any
+    pub position_mint: Option<Pubkey>,
+    /// Nonce of the next withdrawal permit, bumped when one is used
+    pub permit_nonce: u64,
+    /// Whether withdrawals must be requested and claimed after a delay
+    pub queued_withdrawals: bool,
+    /// When the pending withdrawal was requested, if any
+    pub withdraw_requested_at: Option<i64>,
+    /// Token account withdrawals must pay out to, if fixed at deposit time
+    pub locked_destination: Option<Pubkey>,
+    /// Proposed locked destination and when it was proposed
+    pub pending_destination: Option<(Pubkey, i64)>,
+    /// Who may withdraw the deposit besides the depositor
+    pub beneficiary: Option<Pubkey>,
+    /// Proposed beneficiary and when it was proposed
+    pub pending_beneficiary: Option<(Pubkey, i64)>,
+    /// Whether `CrankWithdraw` is rejected for this deposit
+    pub no_crank: bool,
+    /// Whether the deposit is frozen pending a dispute
+    pub frozen: bool,
+    /// How a frozen deposit was settled; such deposits are marked `Swept`
+    pub resolution: Option<ResolutionDestination>,
+    /// Slot the deposit was made in (zero for deposits made before it was tracked)
+    pub last_action_slot: u64,
+    /// The other deposit of a pair made by `DepositPair`
+    pub linked_deposit: Option<u64>,
+    /// `LockTier` of deposits made by `DepositTiered`, as its `u8` value
+    pub lock_tier: Option<u8>,
+    /// Slot a `DepositUntilSlot` deposit unlocks at; `unlock_time` is then the
+    /// deposit time and the slot alone keeps it locked
+    pub unlock_slot: Option<u64>,
+    /// Price that releases the deposit before `unlock_time` via `WithdrawOnPrice`
+    pub price_condition: Option<PriceCondition>,
+    /// Account state that releases the deposit before `unlock_time` via `WithdrawOnCondition`
+    pub account_condition: Option<AccountCondition>,
+    /// SHA-256 of the secret that releases the deposit to its beneficiary via
+    /// `ClaimWithPreimage`; after `unlock_time` only the depositor can withdraw
+    pub hashlock: Option<[u8; 32]>,
+    /// Wallets that can recover the deposit for a new depositor key, at most
+    /// `MAX_GUARDIANS`
+    pub guardians: Vec<Pubkey>,
+    /// Number of distinct guardians that must approve a recovery
+    pub guardian_threshold: u8,
+    /// Recovery proposed by a guardian and not yet executed or vetoed
+    pub pending_recovery: Option<PendingRecovery>,
+    /// Whether emergency withdrawals also need the depositor's signature; set
+    /// at deposit time and never changed
+    pub emergency_requires_depositor: bool,
+    /// Token account an emergency withdrawal of the deposit paid out to
+    pub emergency_paid_to: Option<Pubkey>,
+    /// Insurance premium taken at deposit time; `amount` is net of it
+    pub premium_paid: u64,
+    /// When the deposit was withdrawn (`None` for deposits withdrawn before
+    /// it was tracked, which `GcDeposits` never removes)
+    pub withdrawn_at: Option<i64>,
+    /// Stake account holding a `DepositStakedSol` deposit. Such deposits
+    /// record the stake program as their `token_mint` and count lamports
+    pub stake_account: Option<Pubkey>,
+    /// Whether the vault owner may release the deposit to the depositor
+    /// before it unlocks; set at deposit time and never changed
+    pub owner_can_release_early: bool,
+    /// Rewards allocated to the deposit and not yet claimed, in units of the
+    /// vault's reward mint
+    pub pending_rewards: u64,
+    /// Counterparty of a `DepositEscrow` deposit, paid by `ReleaseEscrow`;
+    /// after `unlock_time` only the depositor can withdraw
+    pub escrow_counterparty: Option<Pubkey>,
+    /// Total that third parties added with `DonateToDeposit`, included in `amount`
+    pub donations_received: u64,
+}
+
+impl Deposit {
+    /// The deposit's status, or `None` for a value this program never writes
+    pub fn status(&self) -> Option<DepositStatus> {
+        DepositStatus::from_u8(self.status)
+    }
+    
+    /// Whether the deposit has left the `Active` status and holds no funds
+    pub fn is_withdrawn(&self) -> bool {
+        self.status != DepositStatus::Active as u8
+    }
+    
+    /// Vote-escrow voting power at `now`: `amount * remaining / max_lock`,
+    /// where the remaining lock time is clamped to `0..=max_lock`, so a
+    /// deposit locked for `max_lock` or longer has full power and it decays
+    /// linearly to zero at the unlock time. Deposits that hold no funds any
+    /// more, slot-locked deposits (whose unlock time is their deposit time)
+    /// and a non-positive `max_lock` confer none
+    pub fn voting_power(&self, now: i64, max_lock: i64) -> u128 {
+        if self.is_withdrawn() || max_lock <= 0 {
+            return 0;
+        }
+        let remaining = self.unlock_time.saturating_sub(now).clamp(0, max_lock);
+        self.amount as u128 * remaining as u128 / max_lock as u128
+    }
+}
+
+// A guardian-proposed change of depositor
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct PendingRecovery {
+    /// Wallet that becomes the depositor
+    pub new_depositor: Pubkey,
+    /// Guardians that approved, starting with the proposer
+    pub approvals: Vec<Pubkey>,
+    /// When the approvals reached the threshold, starting the veto delay
+    pub approved_at: Option<i64>,
+}
+
+/// Maximum number of members of a vault's owner set, as for SPL Token multisigs
+pub const MAX_OWNER_SET_MEMBERS: usize = 11;
+
+/// Maximum number of recovery guardians per deposit
+pub const MAX_GUARDIANS: usize = 5;
+
+/// Seconds between a recovery reaching its threshold and its execution, during
+/// which the depositor can veto it
+pub const RECOVERY_DELAY: i64 = 7 * 24 * 60 * 60;
+
+/// Seconds between `AnnounceSweepAll` and the first `ExecuteSweepAll`; fixed
+/// so no configuration can shorten the warning depositors get
+pub const SWEEP_ALL_DELAY: i64 = 72 * 60 * 60;
+
+/// Basis points in 100%
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Effective early withdrawal penalty at `now`: `max_penalty_bps` scaled by the
+/// share of the lock period still remaining, reaching zero at `unlock_time`
+/// 
+/// The schedule depends only on `created_at` and `unlock_time`, so changes to a
+/// deposit's amount don't move it.
+pub fn early_withdrawal_penalty_bps(max_penalty_bps: u16, created_at: i64, unlock_time: i64, now: i64) -> u16 {
+    if now >= unlock_time {
+        return 0;
+    }
+    let total_lock_time = (unlock_time as i128 - created_at as i128).max(1) as u128;
+    let remaining_time = (unlock_time as i128 - now as i128).min(total_lock_time as i128) as u128;
+    (max_penalty_bps as u128 * remaining_time / total_lock_time) as u16
+}
+
+/// Maximum number of wallets on a vault's depositor allowlist (32 bytes each
+/// of vault space)
+pub const MAX_ALLOWED_DEPOSITORS: usize = 64;
+
+/// Maximum number of programs on a vault's caller allowlist
+pub const MAX_ALLOWED_CALLERS: usize = 8;
+
+/// Maximum number of wallets on a vault's depositor blocklist
+pub const MAX_BLOCKED_DEPOSITORS: usize = 64;
+
+/// Maximum length of a `WithdrawWithMemo` memo, in bytes
+pub const MAX_MEMO_LEN: usize = 128;
+
+/// Maximum number of destinations of a `WithdrawSplit`
+pub const MAX_WITHDRAW_SPLITS: usize = 5;
+
+/// Maximum number of co-owners of a vault
+pub const MAX_CO_OWNERS: usize = 8;
+
+/// Role required by instructions only the vault owner may send
+pub const OWNER_ONLY: u8 = 0;
+
+/// Co-owner role: close the vault to new deposits and reopen it
+pub const CAN_PAUSE: u8 = 1 << 0;
+
+/// Co-owner role: edit the depositor allowlist and blocklist and the caller
+/// allowlist
+pub const CAN_EDIT_WHITELIST: u8 = 1 << 1;
+
+/// Co-owner role: set the rate limit, per-mint limits and TVL cap
+pub const CAN_SET_LIMITS: u8 = 1 << 2;
+
+/// Co-owner role: sweep surplus tokens and lamports
+pub const CAN_SWEEP_SURPLUS: u8 = 1 << 3;
+
+/// Maximum number of deposit records one `DistributeRewards` visits
+pub const MAX_REWARD_DEPOSITS_PER_CALL: usize = 64;
+
+/// Every co-owner role bit
+pub const ALL_CO_OWNER_ROLES: u8 = CAN_PAUSE | CAN_EDIT_WHITELIST | CAN_SET_LIMITS | CAN_SWEEP_SURPLUS;
+
+/// Maximum `SetUnlockGranularity` granularity: 365 days, in seconds
+pub const MAX_UNLOCK_GRANULARITY: i64 = 365 * 24 * 60 * 60;
+
+/// Lock time conferring full voting power in `LogVotingPower`
+pub const VOTING_POWER_MAX_LOCK: i64 = LOCK_FOUR_YEARS_SECS;
+
+/// Maximum number of deposit records one `EmergencyWithdrawAll` visits
+pub const MAX_EVACUATION_DEPOSITS_PER_CALL: usize = 16;
+
+/// Version of the program, taken from Cargo.toml at build time
+pub const PROGRAM_VERSION: (u8, u8, u8) = (
+    parse_version_part(env!("CARGO_PKG_VERSION_MAJOR")),
+    parse_version_part(env!("CARGO_PKG_VERSION_MINOR")),
+    parse_version_part(env!("CARGO_PKG_VERSION_PATCH")),
+);
+
+// Feature bits reported by `GetVersion`. Append-only: a bit keeps its
+// meaning forever, and new features take the next free bit
+
+/// Built with the `pyth` feature: price unlocks read Pyth price accounts
+pub const FEATURE_PYTH: u64 = 1 << 0;
+
+/// Built with the `verbose-logs` feature
+pub const FEATURE_VERBOSE_LOGS: u64 = 1 << 1;
+
+/// `CreateVault` takes a `VaultConfig` and an optional rent payer
+pub const FEATURE_VAULT_CONFIG: u64 = 1 << 2;
+
+/// `DepositFor` accepts a delegate approved on the source token account
+pub const FEATURE_DELEGATED_DEPOSITS: u64 = 1 << 3;
+
+/// `ViewDeposit` is available
+pub const FEATURE_VIEW_DEPOSIT: u64 = 1 << 4;
+
+/// `SetUniqueTags` is available
+pub const FEATURE_UNIQUE_TAGS: u64 = 1 << 5;
+
+/// `SetUnlockGranularity` is available
+pub const FEATURE_UNLOCK_GRANULARITY: u64 = 1 << 6;
+
+/// `EmergencyWithdrawAll` is available
+pub const FEATURE_EMERGENCY_WITHDRAW_ALL: u64 = 1 << 7;
+
+/// `ProgramStats` and `ReconcileStats` are available
+pub const FEATURE_PROGRAM_STATS: u64 = 1 << 8;
+
+/// `LogVotingPower` is available
+pub const FEATURE_VOTING_POWER: u64 = 1 << 9;
+
+/// `DepositEscrow` and `ReleaseEscrow` are available
+pub const FEATURE_ESCROW: u64 = 1 << 10;
+
+/// `SetWithdrawHook` is available and withdrawals call the hook
+pub const FEATURE_WITHDRAW_HOOK: u64 = 1 << 11;
+
+/// `CloneVault` is available
+pub const FEATURE_CLONE_VAULT: u64 = 1 << 12;
+
+/// `CreateVaultAndDeposit` is available
+pub const FEATURE_CREATE_VAULT_AND_DEPOSIT: u64 = 1 << 13;
+
+/// `DonateToDeposit` and `SetDonationsDisabled` are available
+pub const FEATURE_DONATIONS: u64 = 1 << 14;
+
+/// `CloseVaultTokenAccount` and `SetAutoClose` are available
+pub const FEATURE_CLOSE_VAULT_TOKEN_ACCOUNT: u64 = 1 << 15;
+
+/// Feature bits this build supports
+pub const SUPPORTED_FEATURES: u64 = FEATURE_VAULT_CONFIG
+    | FEATURE_DELEGATED_DEPOSITS
+    | FEATURE_VIEW_DEPOSIT
+    | FEATURE_UNIQUE_TAGS
+    | FEATURE_UNLOCK_GRANULARITY
+    | FEATURE_EMERGENCY_WITHDRAW_ALL
+    | FEATURE_PROGRAM_STATS
+    | FEATURE_VOTING_POWER
+    | FEATURE_ESCROW
+    | FEATURE_WITHDRAW_HOOK
+    | FEATURE_CLONE_VAULT
+    | FEATURE_CREATE_VAULT_AND_DEPOSIT
+    | FEATURE_DONATIONS
+    | FEATURE_CLOSE_VAULT_TOKEN_ACCOUNT
+    | if cfg!(feature = "pyth") { FEATURE_PYTH } else { 0 }
+    | if cfg!(feature = "verbose-logs") { FEATURE_VERBOSE_LOGS } else { 0 };
+
+// Parse one numeric component of the crate version
+const fn parse_version_part(part: &str) -> u8 {
+    let bytes = part.as_bytes();
+    let mut value: u8 = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        value = value * 10 + (bytes[i] - b'0');
+        i += 1;
+    }
+    value
+}
+
+/// Maximum number of mints a vault tracks
+pub const MAX_VAULT_MINTS: usize = 16;
+
+/// Maximum number of tags with their own entry in `Vault::tag_totals`
+pub const MAX_TRACKED_TAGS: usize = 16;
+
+/// Number of recent deposit idempotency keys a vault remembers
+pub const MAX_IDEMPOTENCY_KEYS: usize = 32;
+
+/// Vault space taken by a full `Vault::idempotency_keys`: the vector length
+/// prefix and 48 bytes per key (on top of the one-byte cursor)
+pub const IDEMPOTENCY_KEYS_SPACE: usize = 4 + MAX_IDEMPOTENCY_KEYS * (32 + 16);
+
+/// Ceiling on the crank bounty, in basis points
+pub const MAX_CRANK_BOUNTY_BPS: u16 = 100;
+
+/// Ceiling on the insurance premium, in basis points
+pub const MAX_INSURANCE_BPS: u16 = 1_000;
+
+/// Lamports paid to the caller of `GcDeposits` per removed deposit record
+pub const GC_BOUNTY_LAMPORTS: u64 = 5_000;
+
+/// Maximum number of deposits made by one `DepositMany`
+pub const MAX_BATCH_DEPOSITS: usize = 16;
+
+/// Raw token amount for `ui_amount` whole tokens of a mint with `decimals`,
+/// or `None` if it doesn't fit in a u64
+pub fn ui_amount_to_raw(ui_amount: u64, decimals: u8) -> Option<u64> {
+    let scale = 10u128.checked_pow(decimals as u32)?;
+    let raw = (ui_amount as u128).checked_mul(scale)?;
+    u64::try_from(raw).ok()
+}
+
+/// Bounty paid to the cranker out of `amount`, rounded down in the depositor's favor
+pub fn crank_bounty(amount: u64, bounty_bps: u16) -> u64 {
+    (amount as u128 * bounty_bps as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+/// Start of the signature offsets within ed25519 program instruction data
+const ED25519_OFFSETS_START: usize = 2;
+
+/// Size of one ed25519 signature offsets entry
+const ED25519_OFFSETS_LEN: usize = 14;
+
+/// Message a depositor signs to authorize `WithdrawWithPermit`
+pub fn permit_message(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    deposit_id: u64,
+    destination: &Pubkey,
+    expiry: i64,
+    nonce: u64,
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(32 * 3 + 8 * 3);
+    message.extend_from_slice(program_id.as_ref());
+    message.extend_from_slice(vault.as_ref());
+    message.extend_from_slice(&deposit_id.to_le_bytes());
+    message.extend_from_slice(destination.as_ref());
+    message.extend_from_slice(&expiry.to_le_bytes());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message
+}
+
+/// Seed prefix of the position NFT mint PDA
+pub const POSITION_MINT_SEED: &[u8] = b"position";
+
+/// Symbol of the position NFTs
+pub const POSITION_NFT_SYMBOL: &str = "VLOCK";
+
+/// Delay after the unlock time before the original depositor may claim a
+/// deposit whose position NFT was burned outside the vault
+pub const POSITION_BURN_FALLBACK_DELAY: i64 = 30 * 24 * 60 * 60;
+
+/// Maximum length Metaplex accepts for a token name
+const METADATA_NAME_LIMIT: usize = 32;
+
+/// Maximum length Metaplex accepts for a metadata URI
+const METADATA_URI_LIMIT: usize = 200;
+
+/// Derive the position NFT mint for a deposit
+pub fn find_position_mint_address(vault: &Pubkey, deposit_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[POSITION_MINT_SEED, vault.as_ref(), &deposit_id.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// Name of a position NFT, e.g. "Locked 1,000 until 2026-01-01"
+pub fn position_nft_name(amount: u64, unlock_time: i64) -> String {
+    let mut name = format!("Locked {} until {}", group_thousands(amount), format_date(unlock_time));
+    name.truncate(METADATA_NAME_LIMIT);
+    name
+}
+
+// Format an integer with comma thousands separators
+fn group_thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+// Format a unix timestamp as a UTC calendar date (YYYY-MM-DD)
+fn format_date(timestamp: i64) -> String {
+    // Civil-from-days conversion (Howard Hinnant's algorithm)
+    let z = timestamp.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Seed prefix of the per-owner registry PDA
+pub const REGISTRY_SEED: &[u8] = b"registry";
+
+/// Maximum number of vaults tracked by a single owner registry
+pub const MAX_REGISTRY_VAULTS: usize = 32;
+
+// Per-owner registry of vault addresses
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct VaultRegistry {
+    /// The owner whose vaults are listed
+    pub owner: Pubkey,
+    /// The vaults created by the owner
+    pub vaults: Vec<Pubkey>,
+}
+
+impl VaultRegistry {
+    /// Account size of a registry holding `MAX_REGISTRY_VAULTS` entries
+    pub const LEN: usize = 32 + 4 + 32 * MAX_REGISTRY_VAULTS;
+    
+    /// Record a vault, failing once the registry is at capacity
+    pub fn add(&mut self, vault: Pubkey) -> Result<(), VaultError> {
+        if self.vaults.len() >= MAX_REGISTRY_VAULTS {
+            return Err(VaultError::RegistryFull);
+        }
+        self.vaults.push(vault);
+        Ok(())
+    }
+    
+    /// Remove a vault, returning whether it was present
+    pub fn remove(&mut self, vault: &Pubkey) -> bool {
+        let len = self.vaults.len();
+        self.vaults.retain(|v| v != vault);
+        self.vaults.len() != len
+    }
+}
+
+/// Seed prefix of the vault PDAs `CloneVault` and `CreateVaultAndDeposit` create
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// Space `CreateVaultAndDeposit` allocates for a vault: the most a program
+/// can allocate for an account in one call
+pub const PDA_VAULT_SPACE: usize = 10_240;
+
+/// Derive the PDA of an owner's vault with index `seed_index`;
+/// `CreateVaultAndDeposit` uses index 0
+pub fn find_vault_address(owner: &Pubkey, seed_index: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, owner.as_ref(), &seed_index.to_le_bytes()], program_id)
+}
+
+/// Derive the registry PDA for an owner
+pub fn find_registry_address(owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REGISTRY_SEED, owner.as_ref()], program_id)
+}
+
+/// Seed prefix of the per-depositor position index PDA
+pub const POSITION_INDEX_SEED: &[u8] = b"positions";
+
+/// Maximum number of open deposits tracked by a single position index
+pub const MAX_INDEXED_POSITIONS: usize = 64;
+
+// Per-depositor index of open deposit IDs in one vault
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, PartialEq)]
+pub struct PositionIndex {
+    /// The vault holding the deposits
+    pub vault: Pubkey,
+    /// The depositor whose deposits are listed
+    pub depositor: Pubkey,
+    /// IDs of the depositor's open deposits, ascending
+    pub deposit_ids: Vec<u64>,
+}
+
+impl PositionIndex {
+    /// Account size of an index holding `MAX_INDEXED_POSITIONS` entries
+    pub const LEN: usize = 32 + 32 + 4 + 8 * MAX_INDEXED_POSITIONS;
+    
+    /// Record an opened deposit, failing if it is already listed or the
+    /// index is at capacity
+    pub fn insert(&mut self, deposit_id: u64) -> Result<(), VaultError> {
+        let at = match self.deposit_ids.binary_search(&deposit_id) {
+            Ok(_) => return Err(VaultError::PositionIndexMismatch),
+            Err(at) => at,
+        };
+        if self.deposit_ids.len() >= MAX_INDEXED_POSITIONS {
+            return Err(VaultError::PositionIndexFull);
+        }
+        self.deposit_ids.insert(at, deposit_id);
+        Ok(())
+    }
+    
+    /// Remove a closed deposit, failing if it isn't listed
+    pub fn remove(&mut self, deposit_id: u64) -> Result<(), VaultError> {
+        let at = self.deposit_ids.binary_search(&deposit_id)
+            .map_err(|_| VaultError::PositionIndexMismatch)?;
+        self.deposit_ids.remove(at);
+        Ok(())
+    }
+}
+
+/// Derive the position index PDA of a depositor in a vault
+pub fn find_position_index_address(vault: &Pubkey, depositor: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[POSITION_INDEX_SEED, vault.as_ref(), depositor.as_ref()], program_id)
+}
+
+/// Seed prefix of the per-vault snapshot PDA
+pub const SNAPSHOT_SEED: &[u8] = b"snapshot";
+
+/// Maximum number of deposit records `WriteSnapshot` scans for the aggregates
+/// the vault keeps no counter for
+pub const MAX_SNAPSHOT_SCAN: usize = 2048;
+
+// Aggregates of one vault as of `slot`, refreshed by `WriteSnapshot`; an
+// eventually-consistent cache that readers fetch instead of the vault
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, PartialEq)]
+pub struct VaultSnapshot {
+    /// The summarized vault
+    pub vault: Pubkey,
+    /// Slot the snapshot was computed in
+    pub slot: u64,
+    /// Timestamp the snapshot was computed at
+    pub unix_timestamp: i64,
+    /// Number of deposits still holding funds
+    pub active_deposits: u64,
+    /// Earliest unlock time of an active time-locked deposit
+    pub earliest_unlock: Option<i64>,
+    /// Latest unlock time of an active time-locked deposit
+    pub latest_unlock: Option<i64>,
+    /// Total locked across all mints, in raw token units
+    pub total_locked: u64,
+    /// Amount outstanding per tracked mint, copied from `Vault::mints`
+    pub outstanding: Vec<(Pubkey, u64)>,
+    /// Deposits ever created
+    pub lifetime_deposits_created: u64,
+    /// Total amount ever deposited
+    pub lifetime_amount_deposited: u128,
+    /// Total amount ever withdrawn
+    pub lifetime_amount_withdrawn: u128,
+    /// Emergency withdrawals ever made
+    pub lifetime_emergency_withdrawals: u64,
+}
+
+impl VaultSnapshot {
+    /// Account size of a snapshot listing `MAX_VAULT_MINTS` mints
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 9 + 9 + 8 + 4 + 40 * MAX_VAULT_MINTS + 8 + 16 + 16 + 8;
+}
+
+/// Derive the snapshot PDA of a vault
+pub fn find_snapshot_address(vault: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SNAPSHOT_SEED, vault.as_ref()], program_id)
+}
+
+/// Seed prefix of the per-vault PDA that stakes and withdraws staked deposits
+pub const STAKE_AUTHORITY_SEED: &[u8] = b"stake_authority";
+
+/// Derive the stake authority PDA of a vault
+pub fn find_stake_authority_address(vault: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAKE_AUTHORITY_SEED, vault.as_ref()], program_id)
+}
+
+/// Address of the stake account of a staked deposit, created with a seed
+/// off the vault's stake authority
+pub fn find_stake_account_address(vault: &Pubkey, deposit_id: u64, program_id: &Pubkey) -> Pubkey {
+    let authority = find_stake_authority_address(vault, program_id).0;
+    Pubkey::create_with_seed(&authority, &stake_account_seed(deposit_id), &stake::program::id())
+        .expect("the seed is at most 28 bytes")
+}
+
+// Seed of a staked deposit's stake account
+fn stake_account_seed(deposit_id: u64) -> String {
+    format!("deposit-{}", deposit_id)
+}
+
+/// Verify `index` lists exactly its depositor's open deposits in `vault`
+pub fn verify_position_index(vault: &Vault, index: &PositionIndex) -> Result<(), VaultError> {
+    let mut open: Vec<u64> = vault.deposits.iter()
+        .filter(|d| d.depositor == index.depositor && !d.is_withdrawn())
+        .map(|d| d.id)
+        .collect();
+    open.sort_unstable();
+    if open != index.deposit_ids {
+        return Err(VaultError::PositionIndexMismatch);
+    }
+    Ok(())
+}
+
+/// Seed of the program-wide protocol config PDA
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Ceiling on the protocol fee, in basis points
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 500;
+
+// Program-wide protocol economics
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, PartialEq)]
+pub struct ProtocolConfig {
+    /// The account allowed to change the config
+    pub admin: Pubkey,
+    /// Share of each withdrawal skimmed as a protocol fee, in basis points
+    pub protocol_fee_bps: u16,
+    /// Token account receiving protocol fees
+    pub fee_destination: Pubkey,
+    /// Admin proposed by `ProposeConfigAdmin`, awaiting acceptance
+    pub pending_admin: Option<Pubkey>,
+}
+
+impl ProtocolConfig {
+    /// Account size of the config
+    pub const LEN: usize = 32 + 2 + 32 + 1 + 32;
+}
+
+/// Derive the protocol config PDA
+pub fn find_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
+
+/// Seed of the program-wide global state PDA
+pub const GLOBAL_SEED: &[u8] = b"global";
+
+// Program-wide kill switch for new deposits
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, PartialEq)]
+pub struct GlobalState {
+    /// The account allowed to halt deposits
+    pub authority: Pubkey,
+    /// Whether deposit instructions are refused across every vault
+    pub deposits_halted: bool,
+    /// Authority proposed by `ProposeGlobalAuthority`, awaiting acceptance
+    pub pending_authority: Option<Pubkey>,
+}
+
+impl GlobalState {
+    /// Account size of the global state
+    pub const LEN: usize = 32 + 1 + 1 + 32;
+}
+
+/// Derive the global state PDA
+pub fn find_global_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GLOBAL_SEED], program_id)
+}
+
+/// Seed of the program-wide stats PDA
+pub const STATS_SEED: &[u8] = b"stats";
+
+/// Maximum number of mints the program stats track value locked for
+pub const MAX_STATS_MINTS: usize = 16;
+
+// Program-wide aggregates for dashboards, updated incrementally by
+// `CreateVault`, the deposit instructions and `Withdraw` when they are given
+// the stats account last, and by `ReconcileStats`. The counters are
+// best-effort: instructions that omit the account, or close deposits through
+// another withdrawal path, aren't counted
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, PartialEq)]
+pub struct ProgramStats {
+    /// Vaults counted, at creation or by `ReconcileStats`
+    pub vaults_counted: u64,
+    /// Deposits created in counted vaults
+    pub deposits_created: u64,
+    /// Total amount deposited into counted vaults
+    pub amount_deposited: u128,
+    /// Total amount withdrawn from counted vaults
+    pub amount_withdrawn: u128,
+    /// Value locked per mint for the first `MAX_STATS_MINTS` mints seen
+    pub mint_locked: Vec<(Pubkey, u128)>,
+    /// Value locked in mints that didn't fit in `mint_locked`
+    pub other_mints_locked: u128,
+    /// Slot of the last update
+    pub last_updated_slot: u64,
+}
+
+impl ProgramStats {
+    /// Account size of the stats with `MAX_STATS_MINTS` mints tracked
+    pub const LEN: usize = 8 + 8 + 16 + 16 + 4 + 48 * MAX_STATS_MINTS + 16 + 8;
+    
+    /// Count `amount` more locked in `mint`
+    pub fn add_locked(&mut self, mint: &Pubkey, amount: u128) {
+        let tracked_mints = self.mint_locked.len();
+        match self.mint_locked.iter_mut().find(|(tracked, _)| tracked == mint) {
+            Some((_, locked)) => *locked = locked.saturating_add(amount),
+            None if tracked_mints < MAX_STATS_MINTS => self.mint_locked.push((*mint, amount)),
+            None => self.other_mints_locked = self.other_mints_locked.saturating_add(amount),
+        }
+    }
+    
+    /// Count `amount` of `mint` as no longer locked
+    pub fn remove_locked(&mut self, mint: &Pubkey, amount: u128) {
+        match self.mint_locked.iter_mut().find(|(tracked, _)| tracked == mint) {
+            Some((_, locked)) => *locked = locked.saturating_sub(amount),
+            None => self.other_mints_locked = self.other_mints_locked.saturating_sub(amount),
+        }
+    }
+    
+    /// Value locked in `mint`, if it is tracked
+    pub fn locked(&self, mint: &Pubkey) -> Option<u128> {
+        self.mint_locked.iter().find(|(tracked, _)| tracked == mint).map(|(_, locked)| *locked)
+    }
+}
+
+/// Derive the program stats PDA
+pub fn find_stats_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STATS_SEED], program_id)
+}
+
+/// Leading bytes of the instruction a withdrawal sends a vault's hook program,
+/// Anchor's discriminator for an `on_withdraw` handler
+pub const WITHDRAW_HOOK_DISCRIMINATOR: [u8; 8] = [213, 204, 169, 71, 153, 100, 136, 205];
+
+/// Maximum number of extra accounts passed to a withdraw hook
+pub const MAX_HOOK_ACCOUNTS: usize = 8;
+
+/// Data of the instruction a vault's hook program receives after each deposit
+/// a withdrawal pays out: `WITHDRAW_HOOK_DISCRIMINATOR`, then these fields in
+/// Borsh. The accounts are the vault (read-only) followed by the extra
+/// accounts the withdrawer passed. If the hook fails, the whole withdrawal
+/// fails; the vault is stored with its reentrancy guard set during the call,
+/// so the hook can't reenter it.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct WithdrawHookData {
+    /// The vault paid out of
+    pub vault: Pubkey,
+    /// ID of the withdrawn deposit
+    pub deposit_id: u64,
+    /// The deposit's depositor
+    pub depositor: Pubkey,
+    /// The deposit's token mint
+    pub mint: Pubkey,
+    /// The deposit's amount, before any protocol fee
+    pub amount: u64,
+}
+
+impl WithdrawHookData {
+    /// Hook instruction data: the discriminator followed by the fields
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = WITHDRAW_HOOK_DISCRIMINATOR.to_vec();
+        data.extend(self.try_to_vec().expect("hook data always serializes"));
+        data
+    }
+    
+    /// Parse hook instruction data, for hook programs
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        match data.strip_prefix(&WITHDRAW_HOOK_DISCRIMINATOR[..]) {
+            Some(fields) => Self::try_from_slice(fields).map_err(|_| ProgramError::InvalidInstructionData),
+            None => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+
+/// Protocol fee skimmed from `amount`, rounded down in the depositor's favor
+pub fn protocol_fee(amount: u64, protocol_fee_bps: u16) -> u64 {
+    let fee_bps = protocol_fee_bps.min(MAX_PROTOCOL_FEE_BPS);
+    (amount as u128 * fee_bps as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+// Process program instruction
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    // Decode the instruction data
+    let instruction = VaultInstruction::unpack(instruction_data)?;
+    
+    match instruction {
+        VaultInstruction::CreateVault {
+            skip_registry,
+            arbitration_destination,
+            emergency_destination,
+            owner_set,
+            config,
+        } => {
+            process_create_vault(
+                program_id,
+                accounts,
+                skip_registry,
+                arbitration_destination,
+                emergency_destination,
+                owner_set,
+                config,
+            )
+        },
+        VaultInstruction::Deposit {
+            amount,
+            unlock_time,
+            tag,
+            locked_destination,
+            no_crank,
+            emergency_requires_depositor,
+            idempotency_key,
+            owner_can_release_early,
+        } => {
+            let unlock = DepositUnlock::At(unlock_time);
+            let terms = DepositTerms {
+                amount,
+                unlock,
+                tag,
+                locked_destination,
+                no_crank,
+                guardians: Vec::new(),
+                guardian_threshold: 0,
+                emergency_requires_depositor,
+                owner_can_release_early,
+                idempotency_key,
+            };
+            process_deposit(program_id, accounts, terms, false)
+        },
+        VaultInstruction::Withdraw { deposit_id } => {
+            process_withdraw(program_id, accounts, deposit_id, WithdrawKind::Direct, None)
+        },
+        VaultInstruction::EmergencyWithdraw { deposit_id } => {
+            process_emergency_withdraw(program_id, accounts, deposit_id)
+        },
+        VaultInstruction::CloseVault => process_close_vault(program_id, accounts),
+        VaultInstruction::SetPositionNfts { enabled, uri } => {
+            process_set_position_nfts(program_id, accounts, enabled, uri)
+        },
+        VaultInstruction::WithdrawWithPermit { deposit_id, expiry } => {
+            process_withdraw_with_permit(program_id, accounts, deposit_id, expiry)
+        },
+        VaultInstruction::SetEmergencyCooldown { cooldown } => {
+            process_set_emergency_cooldown(program_id, accounts, cooldown)
+        },
+        VaultInstruction::SetRateLimit { rate_limit } => {
+            process_set_rate_limit(program_id, accounts, rate_limit)
+        },
+        VaultInstruction::SetWithdrawDelay { delay } => {
+            process_set_withdraw_delay(program_id, accounts, delay)
+        },
+        VaultInstruction::EnableQueuedWithdrawals { deposit_id } => {
+            process_enable_queued_withdrawals(program_id, accounts, deposit_id)
+        },
+        VaultInstruction::RequestWithdraw { deposit_id } => {
+            process_request_withdraw(program_id, accounts, deposit_id)
+        },
+        VaultInstruction::ClaimWithdraw { deposit_id } => {
+            process_withdraw(program_id, accounts, deposit_id, WithdrawKind::Claim, None)
+        },
+        VaultInstruction::CancelWithdrawRequest { deposit_id } => {
+            process_cancel_withdraw_request(program_id, accounts, deposit_id)
+        },
+        VaultInstruction::SetEarlyWithdrawal { max_penalty_bps, treasury } => {
+            process_set_early_withdrawal(program_id, accounts, max_penalty_bps, treasury)
+        },
+        VaultInstruction::EarlyWithdraw { deposit_id } => {
+            process_early_withdraw(program_id, accounts, deposit_id)
+        },
+        VaultInstruction::SetPostUnlockGrace { grace } => {
+            process_set_post_unlock_grace(program_id, accounts, grace)
+        },
+        VaultInstruction::SetDestinationChangeDelay { delay } => {
+            process_set_destination_change_delay(program_id, accounts, delay)
+        },
+        VaultInstruction::ChangeLockedDestination { deposit_id, new_destination } => {
+            process_change_locked_destination(program_id, accounts, deposit_id, new_destination)
+        },
+        VaultInstruction::SetBeneficiaryChangeDelay { delay } => {
+            process_set_beneficiary_change_delay(program_id, accounts, delay)
+        },
+        VaultInstruction::ProposeBeneficiary { deposit_id, new_beneficiary } => {
+            process_propose_beneficiary(program_id, accounts, deposit_id, new_beneficiary)
+        },
+        VaultInstruction::CommitBeneficiary { deposit_id } => {
+            process_commit_beneficiary(program_id, accounts, deposit_id)
+        },
+        VaultInstruction::CancelBeneficiaryChange { deposit_id } => {
+            process_cancel_beneficiary_change(program_id, accounts, deposit_id)
+        },
+        VaultInstruction::DepositFor {
+            amount,
+            unlock_time,
+            tag,
+            locked_destination,
+            no_crank,
+            emergency_requires_depositor,
+            owner_can_release_early,
+        } => {
+            let unlock = DepositUnlock::At(unlock_time);
+            let terms = DepositTerms {
+                amount,
+                unlock,
+                tag,
+                locked_destination,
+                no_crank,
+                guardians: Vec::new(),
+                guardian_threshold: 0,
+                emergency_requires_depositor,
+                owner_can_release_early,
+                idempotency_key: None,
+            };
+            process_deposit(program_id, accounts, terms, true)
+        },
+        VaultInstruction::CrankWithdraw { deposit_id } => {
+            process_crank_withdraw(program_id, accounts, deposit_id)
+        },
+        VaultInstruction::SetCrankBounty { bounty_bps } => {
+            process_set_crank_bounty(program_id, accounts, bounty_bps)
+        },
+        VaultInstruction::SetDepositPolicy { policy } => {
+            process_set_deposit_policy(program_id, accounts, policy)
+        },
+        VaultInstruction::AddAllowedDepositor { depositor } => {
+            process_add_allowed_depositor(program_id, accounts, depositor)
+        },
+        VaultInstruction::RemoveAllowedDepositor { depositor } => {
+            process_remove_allowed_depositor(program_id, accounts, depositor)
+        },
+        VaultInstruction::BlockDepositor { depositor } => {
+            process_block_depositor(program_id, accounts, depositor)
+        },
+        VaultInstruction::UnblockDepositor { depositor } => {
+            process_unblock_depositor(program_id, accounts, depositor)
+        },
+        VaultInstruction::SetMintLimit { mint, limit } => {
+            process_set_mint_limit(program_id, accounts, mint, limit)
+        },
+        VaultInstruction::SetTvlCap { cap } => {
+            process_set_tvl_cap(program_id, accounts, cap)
+        },
+        VaultInstruction::SetDepositFee { lamports, destination } => {
+            process_set_deposit_fee(program_id, accounts, lamports, destination)
+        },
+        VaultInstruction::InitializeConfig { protocol_fee_bps, fee_destination } => {
+            process_initialize_config(program_id, accounts, protocol_fee_bps, fee_destination)
+        },
+        VaultInstruction::UpdateConfig { protocol_fee_bps, fee_destination } => {
+            process_update_config(program_id, accounts, protocol_fee_bps, fee_destination)
+        },
+        VaultInstruction::ProposeConfigAdmin { new_admin } => {
+            process_propose_config_admin(program_id, accounts, new_admin)
+        },
+        VaultInstruction::AcceptConfigAdmin => {
+            process_accept_config_admin(program_id, accounts)
+        },
+        VaultInstruction::SetFreezeAuthority { authority } => {
+            process_set_freeze_authority(program_id, accounts, authority)
+        },
+        VaultInstruction::FreezeDeposit { deposit_id } => {
+            process_set_deposit_frozen(program_id, accounts, deposit_id, true)
+        },
+        VaultInstruction::ThawDeposit { deposit_id } => {
+            process_set_deposit_frozen(program_id, accounts, deposit_id, false)
+        },
+        VaultInstruction::ResolveFrozenDeposit { deposit_id, destination_kind } => {
+            process_resolve_frozen_deposit(program_id, accounts, deposit_id, destination_kind)
+        },
+        VaultInstruction::RevokeVaultDelegate => {
+            process_revoke_vault_delegate(program_id, accounts)
+        },
+        VaultInstruction::SetVaultTokenCloseAuthority => {
+            process_set_vault_token_close_authority(program_id, accounts)
+        },
+        VaultInstruction::DepositUiAmount { ui_amount, decimals_exponent, unlock_time, tag } => {
+            process_deposit_ui_amount(program_id, accounts, ui_amount, decimals_exponent, unlock_time, tag)
+        },
+        VaultInstruction::DepositPair { amount_a, amount_b, unlock_time, tag } => {
+            process_deposit_pair(program_id, accounts, [amount_a, amount_b], unlock_time, tag)
+        },
+        VaultInstruction::DepositMany { items } => process_deposit_many(program_id, accounts, items),
+        VaultInstruction::DepositTiered { amount, tier, tag } => {
+            let terms = DepositTerms {
+                amount,
+                unlock: DepositUnlock::Tier(tier),
+                tag,
+                locked_destination: None,
+                no_crank: false,
+                guardians: Vec::new(),
+                guardian_threshold: 0,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            };
+            process_deposit(program_id, accounts, terms, false)
+        },
+        VaultInstruction::ExtendLock { deposit_id, new_tier } => {
+            process_extend_lock(program_id, accounts, deposit_id, new_tier)
+        },
+        VaultInstruction::DepositUntilSlot { amount, unlock_slot, tag } => {
+            let terms = DepositTerms {
+                amount,
+                unlock: DepositUnlock::Slot(unlock_slot),
+                tag,
+                locked_destination: None,
+                no_crank: false,
+                guardians: Vec::new(),
+                guardian_threshold: 0,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            };
+            process_deposit(program_id, accounts, terms, false)
+        },
+        VaultInstruction::SetMinLockSlots { min_slots } => {
+            process_set_min_lock_slots(program_id, accounts, min_slots)
+        },
+        VaultInstruction::DepositWithPriceCondition { amount, unlock_time, tag, price_condition } => {
+            let terms = DepositTerms {
+                amount,
+                unlock: DepositUnlock::AtOrOnPrice(unlock_time, price_condition),
+                tag,
+                locked_destination: None,
+                no_crank: false,
+                guardians: Vec::new(),
+                guardian_threshold: 0,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            };
+            process_deposit(program_id, accounts, terms, false)
+        },
+        VaultInstruction::WithdrawOnPrice { deposit_id } => {
+            process_withdraw(program_id, accounts, deposit_id, WithdrawKind::OnPrice, None)
+        },
+        VaultInstruction::SetPriceOracle { price_oracle } => {
+            process_set_price_oracle(program_id, accounts, price_oracle)
+        },
+        VaultInstruction::DepositWithAccountCondition { amount, unlock_time, tag, account_condition } => {
+            let terms = DepositTerms {
+                amount,
+                unlock: DepositUnlock::AtOrOnAccount(unlock_time, account_condition),
+                tag,
+                locked_destination: None,
+                no_crank: false,
+                guardians: Vec::new(),
+                guardian_threshold: 0,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            };
+            process_deposit(program_id, accounts, terms, false)
+        },
+        VaultInstruction::WithdrawOnCondition { deposit_id } => {
+            process_withdraw(program_id, accounts, deposit_id, WithdrawKind::OnCondition, None)
+        },
+        VaultInstruction::DepositWithHashlock { amount, unlock_time, tag, hashlock, beneficiary } => {
+            let terms = DepositTerms {
+                amount,
+                unlock: DepositUnlock::AtOrOnPreimage(unlock_time, hashlock, beneficiary),
+                tag,
+                locked_destination: None,
+                no_crank: false,
+                guardians: Vec::new(),
+                guardian_threshold: 0,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            };
+            process_deposit(program_id, accounts, terms, false)
+        },
+        VaultInstruction::ClaimWithPreimage { deposit_id, preimage } => {
+            process_claim_with_preimage(program_id, accounts, deposit_id, preimage)
+        },
+        VaultInstruction::DepositWithGuardians { amount, unlock_time, tag, guardians, guardian_threshold } => {
+            let terms = DepositTerms {
+                amount,
+                unlock: DepositUnlock::At(unlock_time),
+                tag,
+                locked_destination: None,
+                no_crank: false,
+                guardians,
+                guardian_threshold,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            };
+            process_deposit(program_id, accounts, terms, false)
+        },
+        VaultInstruction::ProposeRecovery { deposit_id, new_depositor } => {
+            process_propose_recovery(program_id, accounts, deposit_id, new_depositor)
+        },
+        VaultInstruction::ApproveRecovery { deposit_id } => {
+            process_approve_recovery(program_id, accounts, deposit_id)
+        },
+        VaultInstruction::ExecuteRecovery { deposit_id } => {
+            process_execute_recovery(program_id, accounts, deposit_id)
+        },
+        VaultInstruction::VetoRecovery { deposit_id } => {
+            process_veto_recovery(program_id, accounts, deposit_id)
+        },
+        VaultInstruction::WithdrawByTag { tag } => {
+            process_withdraw_by_tag(program_id, accounts, tag)
+        },
+        VaultInstruction::RenounceOwnership { emergency_authority } => {
+            process_renounce_ownership(program_id, accounts, emergency_authority)
+        },
+        VaultInstruction::SetEmergencyAuthority { authority } => {
+            process_set_emergency_authority(program_id, accounts, authority)
+        },
+        VaultInstruction::SetInsurance { insurance_bps, insurance_pool } => {
+            process_set_insurance(program_id, accounts, insurance_bps, insurance_pool)
+        },
+        VaultInstruction::SetPositionIndex { enabled } => {
+            process_set_position_index(program_id, accounts, enabled)
+        },
+        VaultInstruction::SetAllowedCallers { allowed_callers, reject_top_level } => {
+            process_set_allowed_callers(program_id, accounts, allowed_callers, reject_top_level)
+        },
+        VaultInstruction::SweepSurplus { mint } => process_sweep_surplus(program_id, accounts, mint),
+        VaultInstruction::SweepLamports => process_sweep_lamports(program_id, accounts),
+        VaultInstruction::SetGcDelay { gc_delay } => process_set_gc_delay(program_id, accounts, gc_delay),
+        VaultInstruction::GcDeposits { max } => process_gc_deposits(program_id, accounts, max),
+        VaultInstruction::CloseToNewDeposits => process_set_accepting_deposits(program_id, accounts, false),
+        VaultInstruction::ReopenDeposits => process_set_accepting_deposits(program_id, accounts, true),
+        VaultInstruction::InitializeGlobalState => process_initialize_global_state(program_id, accounts),
+        VaultInstruction::SetGlobalHalt { halted } => process_set_global_halt(program_id, accounts, halted),
+        VaultInstruction::ProposeGlobalAuthority { new_authority } => {
+            process_propose_global_authority(program_id, accounts, new_authority)
+        },
+        VaultInstruction::AcceptGlobalAuthority => process_accept_global_authority(program_id, accounts),
+        VaultInstruction::WriteSnapshot => process_write_snapshot(program_id, accounts),
+        VaultInstruction::WithdrawSplit { deposit_id, splits } => {
+            process_withdraw_split(program_id, accounts, deposit_id, &splits)
+        },
+        VaultInstruction::SetOwnerApprovedSplits { enabled } => {
+            process_set_owner_approved_splits(program_id, accounts, enabled)
+        },
+        VaultInstruction::WithdrawWithMemo { deposit_id, memo } => {
+            process_withdraw(program_id, accounts, deposit_id, WithdrawKind::Direct, Some(memo.as_slice()))
+        },
+        VaultInstruction::DepositStakedSol { amount, unlock_time, validator_vote, tag } => {
+            process_deposit_staked_sol(program_id, accounts, amount, unlock_time, validator_vote, tag)
+        },
+        VaultInstruction::DeactivateStake { deposit_id } => {
+            process_deactivate_stake(program_id, accounts, deposit_id)
+        },
+        VaultInstruction::WithdrawStake { deposit_id } => {
+            process_withdraw_stake(program_id, accounts, deposit_id)
+        },
+        VaultInstruction::AnnounceSweepAll => process_announce_sweep_all(program_id, accounts),
+        VaultInstruction::ExecuteSweepAll { mint } => process_execute_sweep_all(program_id, accounts, mint),
+        VaultInstruction::SetCoOwner { co_owner, roles } => process_set_co_owner(program_id, accounts, co_owner, roles),
+        VaultInstruction::RemoveCoOwner { co_owner } => process_remove_co_owner(program_id, accounts, co_owner),
+        VaultInstruction::ReleaseEarly { deposit_id } => process_release_early(program_id, accounts, deposit_id),
+        VaultInstruction::SetRewardMints { reward_mint, weight_mint } => {
+            process_set_reward_mints(program_id, accounts, reward_mint, weight_mint)
+        },
+        VaultInstruction::DistributeRewards { mint, total } => {
+            process_distribute_rewards(program_id, accounts, mint, total)
+        },
+        VaultInstruction::ClaimRewards { deposit_id } => process_claim_rewards(program_id, accounts, deposit_id),
+        VaultInstruction::ViewDeposit { deposit_id } => process_view_deposit(program_id, accounts, deposit_id),
+        VaultInstruction::GetVersion => process_get_version(),
+        VaultInstruction::SetUniqueTags { enabled } => process_set_unique_tags(program_id, accounts, enabled),
+        VaultInstruction::SetUnlockGranularity { granularity } => {
+            process_set_unlock_granularity(program_id, accounts, granularity)
+        },
+        VaultInstruction::EmergencyWithdrawAll { depositor, mint } => {
+            process_emergency_withdraw_all(program_id, accounts, depositor, mint)
+        },
+        VaultInstruction::ReconcileStats => process_reconcile_stats(program_id, accounts),
+        VaultInstruction::LogVotingPower { depositor } => process_log_voting_power(program_id, accounts, &depositor),
+        VaultInstruction::DepositEscrow { amount, unlock_time, tag, counterparty } => {
+            let terms = DepositTerms {
+                amount,
+                unlock: DepositUnlock::AtOrOnRelease(unlock_time, counterparty),
+                tag,
+                locked_destination: None,
+                no_crank: false,
+                guardians: Vec::new(),
+                guardian_threshold: 0,
+                // Nobody moves escrowed funds without a party's consent
+                emergency_requires_depositor: true,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            };
+            process_deposit(program_id, accounts, terms, false)
+        },
+        VaultInstruction::ReleaseEscrow { deposit_id } => process_release_escrow(program_id, accounts, deposit_id),
+        VaultInstruction::SetWithdrawHook { hook_program } => {
+            process_set_withdraw_hook(program_id, accounts, hook_program)
+        },
+        VaultInstruction::CloneVault { seed_index } => process_clone_vault(program_id, accounts, seed_index),
+        VaultInstruction::CreateVaultAndDeposit { config, amount, unlock_time, tag } => {
+            process_create_vault_and_deposit(program_id, accounts, config, amount, unlock_time, tag)
+        },
+        VaultInstruction::DonateToDeposit { deposit_id, amount } => {
+            process_donate_to_deposit(program_id, accounts, deposit_id, amount)
+        },
+        VaultInstruction::SetDonationsDisabled { disabled } => {
+            process_set_donations_disabled(program_id, accounts, disabled)
+        },
+        VaultInstruction::CloseVaultTokenAccount { mint } => {
+            process_close_vault_token_account(program_id, accounts, &mint)
+        },
+        VaultInstruction::SetAutoClose { enabled } => process_set_auto_close(program_id, accounts, enabled),
+        VaultInstruction::DepositWithDuration { amount, lock_duration_secs, tag } => {
+            let terms = DepositTerms {
+                amount,
+                unlock: DepositUnlock::After(lock_duration_secs),
+                tag,
+                locked_destination: None,
+                no_crank: false,
+                guardians: Vec::new(),
+                guardian_threshold: 0,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            };
+            process_deposit(program_id, accounts, terms, false)
+        },
+    }
+}
+
+// Load the vault stored in a program-owned account, telling accounts that were
+// never initialized apart from ones holding undecodable data
+fn load_vault(vault_account_info: &AccountInfo) -> Result<Vault, ProgramError> {
+    Ok(Vault::from_account_data(&vault_account_info.data.borrow())?)
+}
+
+// Fail unless `signer` owns the vault and hasn't renounced ownership. For
+// vaults owned by an owner set, `signer` must be a member and at least the
+// threshold of distinct members must have signed among `accounts`
+fn check_vault_owner(vault: &Vault, signer: &Pubkey, accounts: &[AccountInfo]) -> Result<(), VaultError> {
+    match &vault.owner_set {
+        Some(owner_set) => {
+            if !owner_set.members.contains(signer) {
+                return Err(VaultError::NotVaultOwner);
+            }
+            let mut signers: Vec<&Pubkey> = accounts.iter()
+                .filter(|info| info.is_signer && owner_set.members.contains(info.key))
+                .map(|info| info.key)
+                .collect();
+            signers.sort();
+            signers.dedup();
+            if signers.len() < owner_set.threshold as usize {
+                return Err(VaultError::OwnerThresholdNotMet);
+            }
+        },
+        None => {
+            if vault.owner != *signer {
+                return Err(VaultError::NotVaultOwner);
+            }
+        },
+    }
+    if vault.renounced {
+        return Err(VaultError::OwnershipRenounced);
+    }
+    Ok(())
+}
+
+// Fail unless the signer in the owner position (the first account) is the
+// owner, or a co-owner holding every bit of `role`. `OWNER_ONLY` admits no
+// co-owner
+fn require_role(vault: &Vault, accounts: &[AccountInfo], role: u8) -> Result<(), VaultError> {
+    let signer = accounts.first().ok_or(VaultError::NotVaultOwner)?.key;
+    let co_owner_roles = vault.co_owners.iter().find(|(co_owner, _)| co_owner == signer).map(|(_, roles)| *roles);
+    match co_owner_roles {
+        Some(roles) if role != OWNER_ONLY && roles & role == role => {
+            if vault.renounced {
+                return Err(VaultError::OwnershipRenounced);
+            }
+            Ok(())
+        },
+        Some(_) => Err(VaultError::MissingCoOwnerRole),
+        None => check_vault_owner(vault, signer, accounts),
+    }
+}
+
+// Load the vault for an owner-signed configuration change, apply `update` and store the result
+fn process_owner_update<F>(program_id: &Pubkey, accounts: &[AccountInfo], role: u8, update: F) -> ProgramResult
+where
+    F: FnOnce(&mut Vault) -> ProgramResult,
+{
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    
+    // Verify the owner signed the transaction
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Verify the signer owns the vault, or holds the role, and still holds its powers
+    require_role(&vault, accounts, role)?;
+    
+    update(&mut vault)?;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Process set position NFTs instruction
+fn process_set_position_nfts(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    enabled: bool,
+    uri: String,
+) -> ProgramResult {
+    if uri.len() > METADATA_URI_LIMIT {
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.position_nfts = enabled;
+        vault.position_nft_uri = uri;
+        Ok(())
+    })?;
+    
+    vault_log!("Position NFTs {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+// Process set emergency cooldown instruction
+fn process_set_emergency_cooldown(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    cooldown: i64,
+) -> ProgramResult {
+    if cooldown < 0 {
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.emergency_cooldown = cooldown;
+        Ok(())
+    })?;
+    
+    vault_log!("Emergency cooldown set to {} seconds", cooldown);
+    Ok(())
+}
+
+// Process set rate limit instruction
+fn process_set_rate_limit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    rate_limit: Option<RateLimit>,
+) -> ProgramResult {
+    if let Some(limit) = rate_limit {
+        if limit.amount == 0 || limit.window_secs <= 0 {
+            return Err(VaultError::InvalidInstructionData.into());
+        }
+    }
+    
+    process_owner_update(program_id, accounts, CAN_SET_LIMITS, |vault| {
+        vault.rate_limit = rate_limit;
+        if rate_limit.is_none() {
+            vault.withdrawal_windows.clear();
+        }
+        Ok(())
+    })?;
+    
+    match rate_limit {
+        Some(limit) => vault_log!("Rate limit set to {} per {} seconds", limit.amount, limit.window_secs),
+        None => vault_log!("Rate limit disabled"),
+    }
+    Ok(())
+}
+
+// Process set withdraw delay instruction
+fn process_set_withdraw_delay(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    delay: i64,
+) -> ProgramResult {
+    if delay < 0 {
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.withdraw_delay = delay;
+        Ok(())
+    })?;
+    
+    vault_log!("Withdraw delay set to {} seconds", delay);
+    Ok(())
+}
+
+// Process set gc delay instruction
+fn process_set_gc_delay(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    gc_delay: Option<i64>,
+) -> ProgramResult {
+    if gc_delay.is_some_and(|delay| delay < 0) {
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.gc_delay = gc_delay;
+        Ok(())
+    })?;
+    
+    vault_log!("Deposit record gc delay set to {:?} seconds", gc_delay);
+    Ok(())
+}
+
+// Process close to new deposits and reopen deposits instructions
+fn process_set_accepting_deposits(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    accepting: bool,
+) -> ProgramResult {
+    process_owner_update(program_id, accounts, CAN_PAUSE, |vault| {
+        vault.deposits_closed = !accepting;
+        Ok(())
+    })?;
+    
+    vault_event!(b"deposits_open", [accepting as u8]);
+    if accepting {
+        vault_log!("Vault reopened to new deposits");
+    } else {
+        vault_log!("Vault closed to new deposits; withdrawals continue");
+    }
+    Ok(())
+}
+
+// Process set owner approved splits instruction
+fn process_set_owner_approved_splits(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    enabled: bool,
+) -> ProgramResult {
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.owner_approved_splits = enabled;
+        Ok(())
+    })?;
+    
+    vault_log!("Owner-approved split withdrawals {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+// Process set unique tags instruction
+fn process_set_unique_tags(program_id: &Pubkey, accounts: &[AccountInfo], enabled: bool) -> ProgramResult {
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.unique_tags = enabled;
+        Ok(())
+    })?;
+    
+    vault_log!("Unique deposit tags {}", if enabled { "required" } else { "not required" });
+    Ok(())
+}
+
+// Process set auto close instruction
+fn process_set_auto_close(program_id: &Pubkey, accounts: &[AccountInfo], enabled: bool) -> ProgramResult {
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.auto_close = enabled;
+        Ok(())
+    })?;
+    
+    vault_log!("Auto-closing emptied token accounts {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+// Process set donations disabled instruction
+fn process_set_donations_disabled(program_id: &Pubkey, accounts: &[AccountInfo], disabled: bool) -> ProgramResult {
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.donations_disabled = disabled;
+        Ok(())
+    })?;
+    
+    vault_log!("Donations {}", if disabled { "disabled" } else { "enabled" });
+    Ok(())
+}
+
+// Process set unlock granularity instruction
+fn process_set_unlock_granularity(program_id: &Pubkey, accounts: &[AccountInfo], granularity: i64) -> ProgramResult {
+    if !(0..=MAX_UNLOCK_GRANULARITY).contains(&granularity) {
+        return Err(VaultError::InvalidUnlockGranularity.into());
+    }
+    
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.unlock_granularity = granularity;
+        Ok(())
+    })?;
+    
+    vault_log!("Unlock granularity set to {} seconds", granularity);
+    Ok(())
+}
+
+// Process set withdraw hook instruction
+fn process_set_withdraw_hook(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    hook_program: Option<Pubkey>,
+) -> ProgramResult {
+    // The vault calling itself would always trip its reentrancy guard
+    if hook_program == Some(*program_id) {
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.hook_program = hook_program;
+        Ok(())
+    })?;
+    
+    match hook_program {
+        Some(hook_program) => vault_log!("Withdraw hook set to {}", hook_program),
+        None => vault_log!("Withdraw hook cleared"),
+    }
+    Ok(())
+}
+
+// Process set min lock slots instruction
+fn process_set_min_lock_slots(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    min_slots: u64,
+) -> ProgramResult {
+    VaultConfig { min_lock_slots: Some(min_slots), ..VaultConfig::default() }.validate()?;
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.min_lock_slots = min_slots;
+        Ok(())
+    })?;
+    
+    vault_log!("Minimum slot lock set to {} slots", min_slots);
+    Ok(())
+}
+
+// Process set price oracle instruction
+fn process_set_price_oracle(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    price_oracle: Option<PriceOracleConfig>,
+) -> ProgramResult {
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.price_oracle = price_oracle;
+        Ok(())
+    })?;
+    
+    match price_oracle {
+        Some(config) => vault_log!("Price unlocks enabled with oracle program {}", config.program),
+        None => vault_log!("Price unlocks disabled"),
+    }
+    Ok(())
+}
+
+// Process set early withdrawal instruction
+fn process_set_early_withdrawal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_penalty_bps: u16,
+    treasury: Option<Pubkey>,
+) -> ProgramResult {
+    if max_penalty_bps as u64 > BPS_DENOMINATOR {
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.max_penalty_bps = max_penalty_bps;
+        vault.treasury = treasury;
+        Ok(())
+    })?;
+    
+    match treasury {
+        Some(_) => vault_log!("Early withdrawals enabled with a {} bps maximum penalty", max_penalty_bps),
+        None => vault_log!("Early withdrawals disabled"),
+    }
+    Ok(())
+}
+
+// Process set post-unlock grace instruction
+fn process_set_post_unlock_grace(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    grace: i64,
+) -> ProgramResult {
+    if grace < 0 {
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.post_unlock_grace = grace;
+        Ok(())
+    })?;
+    
+    vault_log!("Post-unlock grace set to {} seconds", grace);
+    Ok(())
+}
+
+// Process set destination change delay instruction
+fn process_set_destination_change_delay(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    delay: i64,
+) -> ProgramResult {
+    if delay < 0 {
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.destination_change_delay = delay;
+        Ok(())
+    })?;
+    
+    vault_log!("Destination change delay set to {} seconds", delay);
+    Ok(())
+}
+
+// Process set beneficiary change delay instruction
+fn process_set_beneficiary_change_delay(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    delay: i64,
+) -> ProgramResult {
+    if delay < 0 {
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.beneficiary_change_delay = delay;
+        Ok(())
+    })?;
+    
+    vault_log!("Beneficiary change delay set to {} seconds", delay);
+    Ok(())
+}
+
+// Process set crank bounty instruction
+fn process_set_crank_bounty(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    bounty_bps: u16,
+) -> ProgramResult {
+    if bounty_bps > MAX_CRANK_BOUNTY_BPS {
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.crank_bounty_bps = bounty_bps;
+        Ok(())
+    })?;
+    
+    vault_log!("Crank bounty set to {} bps", bounty_bps);
+    Ok(())
+}
+
+// Process set deposit policy instruction
+fn process_set_deposit_policy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    policy: DepositPolicy,
+) -> ProgramResult {
+    VaultConfig { deposit_policy: Some(policy), ..VaultConfig::default() }.validate()?;
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.deposit_policy = policy;
+        Ok(())
+    })?;
+    
+    vault_log!("Deposit policy set to {:?}", policy);
+    Ok(())
+}
+
+// Process add allowed depositor instruction
+fn process_add_allowed_depositor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    depositor: Pubkey,
+) -> ProgramResult {
+    process_owner_update(program_id, accounts, CAN_EDIT_WHITELIST, |vault| {
+        if vault.allowed_depositors.contains(&depositor) {
+            return Ok(());
+        }
+        if vault.allowed_depositors.len() >= MAX_ALLOWED_DEPOSITORS {
+            return Err(VaultError::AllowlistFull.into());
+        }
+        vault.allowed_depositors.push(depositor);
+        Ok(())
+    })?;
+    
+    vault_log!("Depositor {} allowed", depositor);
+    Ok(())
+}
+
+// Process remove allowed depositor instruction
+fn process_remove_allowed_depositor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    depositor: Pubkey,
+) -> ProgramResult {
+    process_owner_update(program_id, accounts, CAN_EDIT_WHITELIST, |vault| {
+        vault.allowed_depositors.retain(|allowed| *allowed != depositor);
+        Ok(())
+    })?;
+    
+    vault_log!("Depositor {} removed from the allowlist", depositor);
+    Ok(())
+}
+
+// Process block depositor instruction
+fn process_block_depositor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    depositor: Pubkey,
+) -> ProgramResult {
+    process_owner_update(program_id, accounts, CAN_EDIT_WHITELIST, |vault| {
+        if let Err(index) = vault.blocked_depositors.binary_search(&depositor) {
+            if vault.blocked_depositors.len() >= MAX_BLOCKED_DEPOSITORS {
+                return Err(VaultError::BlocklistFull.into());
+            }
+            vault.blocked_depositors.insert(index, depositor);
+        }
+        Ok(())
+    })?;
+    
+    vault_log!("Depositor {} blocked", depositor);
+    Ok(())
+}
+
+// Process unblock depositor instruction
+fn process_unblock_depositor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    depositor: Pubkey,
+) -> ProgramResult {
+    process_owner_update(program_id, accounts, CAN_EDIT_WHITELIST, |vault| {
+        if let Ok(index) = vault.blocked_depositors.binary_search(&depositor) {
+            vault.blocked_depositors.remove(index);
+        }
+        Ok(())
+    })?;
+    
+    vault_log!("Depositor {} unblocked", depositor);
+    Ok(())
+}
+
+// Process set mint limit instruction
+fn process_set_mint_limit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint: Pubkey,
+    limit: Option<u64>,
+) -> ProgramResult {
+    process_owner_update(program_id, accounts, CAN_SET_LIMITS, |vault| {
+        mint_entry(vault, &mint)?.max_outstanding = limit;
+        prune_mint_entries(vault);
+        Ok(())
+    })?;
+    
+    match limit {
+        Some(limit) => vault_log!("Limit for mint {} set to {}", mint, limit),
+        None => vault_log!("Limit for mint {} lifted", mint),
+    }
+    Ok(())
+}
+
+// Process set TVL cap instruction
+fn process_set_tvl_cap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    cap: Option<u64>,
+) -> ProgramResult {
+    VaultConfig { tvl_cap: cap, ..VaultConfig::default() }.validate()?;
+    process_owner_update(program_id, accounts, CAN_SET_LIMITS, |vault| {
+        vault.tvl_cap = cap;
+        Ok(())
+    })?;
+    
+    match cap {
+        Some(cap) => vault_log!("TVL cap set to {}", cap),
+        None => vault_log!("TVL cap lifted"),
+    }
+    Ok(())
+}
+
+// Process set deposit fee instruction
+fn process_set_deposit_fee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    lamports: u64,
+    destination: Pubkey,
+) -> ProgramResult {
+    VaultConfig {
+        deposit_fee_lamports: Some(lamports),
+        deposit_fee_destination: Some(destination),
+        ..VaultConfig::default()
+    }
+    .validate()?;
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.deposit_fee_lamports = lamports;
+        vault.deposit_fee_destination = destination;
+        Ok(())
+    })?;
+    
+    vault_log!("Deposit fee set to {} lamports", lamports);
+    Ok(())
+}
+
+// Process set insurance instruction
+fn process_set_insurance(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    insurance_bps: u16,
+    insurance_pool: Pubkey,
+) -> ProgramResult {
+    if insurance_bps > MAX_INSURANCE_BPS {
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    // Verify the pool is a token account
+    if insurance_bps > 0 {
+        let pool_info = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if *pool_info.key != insurance_pool || *pool_info.owner != spl_token::id() {
+            return Err(VaultError::InvalidInsurancePool.into());
+        }
+        TokenAccount::unpack(&pool_info.data.borrow())?;
+    }
+    
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.insurance_bps = insurance_bps;
+        vault.insurance_pool = insurance_pool;
+        Ok(())
+    })?;
+    
+    vault_log!("Insurance premium set to {} bps", insurance_bps);
+    Ok(())
+}
+
+// Process set position index instruction
+fn process_set_position_index(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    enabled: bool,
+) -> ProgramResult {
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        if vault.deposits.iter().any(|d| !d.is_withdrawn()) {
+            return Err(VaultError::VaultNotEmpty.into());
+        }
+        vault.position_index = enabled;
+        Ok(())
+    })?;
+    
+    vault_log!("Position indexes {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+// Process set allowed callers instruction
+fn process_set_allowed_callers(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    allowed_callers: Option<Vec<Pubkey>>,
+    reject_top_level: bool,
+) -> ProgramResult {
+    match &allowed_callers {
+        Some(callers) if callers.len() > MAX_ALLOWED_CALLERS => {
+            return Err(VaultError::InvalidInstructionData.into());
+        },
+        None if reject_top_level => return Err(VaultError::InvalidInstructionData.into()),
+        _ => {},
+    }
+    
+    process_owner_update(program_id, accounts, CAN_EDIT_WHITELIST, |vault| {
+        vault.allowed_callers = allowed_callers;
+        vault.reject_top_level = reject_top_level;
+        Ok(())
+    })?;
+    
+    vault_log!("Allowed callers updated");
+    Ok(())
+}
+
+// Process set emergency authority instruction; the authority is never
+// checked to be on the curve so program-derived addresses work
+fn process_set_emergency_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    authority: Option<Pubkey>,
+) -> ProgramResult {
+    VaultConfig { emergency_authority: authority, ..VaultConfig::default() }.validate()?;
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.emergency_authority = authority;
+        Ok(())
+    })?;
+    
+    match authority {
+        Some(authority) => vault_log!("Emergency authority set to {}", authority),
+        None => vault_log!("Emergency authority cleared"),
+    }
+    Ok(())
+}
+
+// Process set freeze authority instruction
+fn process_set_freeze_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    authority: Option<Pubkey>,
+) -> ProgramResult {
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.freeze_authority = authority;
+        Ok(())
+    })?;
+    
+    match authority {
+        Some(authority) => vault_log!("Freeze authority set to {}", authority),
+        None => vault_log!("Freeze authority cleared"),
+    }
+    Ok(())
+}
+
+// Process freeze deposit and thaw deposit instructions
+fn process_set_deposit_frozen(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+    frozen: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let authority_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    
+    // Verify the authority signed the transaction
+    if !authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Verify the signer is the freeze authority
+    if vault.freeze_authority.or(vault.emergency_authority) != Some(*authority_info.key) {
+        return Err(VaultError::UnauthorizedWithdrawal.into());
+    }
+    
+    // Find the deposit; freezing only flips the flag and never moves funds
+    let deposit = vault.deposits.iter_mut().find(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    if deposit.is_withdrawn() {
+        return Err(VaultError::AlreadyWithdrawn.into());
+    }
+    deposit.frozen = frozen;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    let action = if frozen { "frozen" } else { "thawed" };
+    vault_log!("Deposit {} {} by {}", deposit_id, action, authority_info.key);
+    Ok(())
+}
+
+// Process initialize config instruction
+fn process_initialize_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    protocol_fee_bps: u16,
+    fee_destination: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let admin_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    
+    // Verify the admin signed the transaction
+    if !admin_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the system program account
+    if *system_program_info.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    if protocol_fee_bps > MAX_PROTOCOL_FEE_BPS {
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    // Verify the config address and that it doesn't exist yet
+    let (config_key, bump) = find_config_address(program_id);
+    if config_key != *config_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if config_info.owner == program_id {
+        return Err(VaultError::AccountAlreadyInUse.into());
+    }
+    
+    // Create the config, funded by the admin
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin_info.key,
+            config_info.key,
+            rent.minimum_balance(ProtocolConfig::LEN),
+            ProtocolConfig::LEN as u64,
+            program_id,
+        ),
+        &[
+            admin_info.clone(),
+            config_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[CONFIG_SEED, &[bump]]],
+    )?;
+    
+    let config = ProtocolConfig {
+        admin: *admin_info.key,
+        protocol_fee_bps,
+        fee_destination,
+        pending_admin: None,
+    };
+    config.serialize(&mut &mut config_info.data.borrow_mut()[..])?;
+    
+    vault_log!("Protocol config initialized by {} with a {} bps fee", admin_info.key, protocol_fee_bps);
+    Ok(())
+}
+
+// Load the protocol config for a change signed by `expected_signer(config)`,
+// apply `update` and store the result
+fn process_config_update<S, F>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    expected_signer: S,
+    update: F,
+) -> ProgramResult
+where
+    S: FnOnce(&ProtocolConfig) -> Result<Pubkey, ProgramError>,
+    F: FnOnce(&mut ProtocolConfig),
+{
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let signer_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    
+    // Verify the signer signed the transaction
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the config account is the program's config
+    if config_info.owner != program_id || *config_info.key != find_config_address(program_id).0 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    
+    // Load the config and verify the signer may change it
+    let mut config = ProtocolConfig::deserialize(&mut &config_info.data.borrow()[..])?;
+    if expected_signer(&config)? != *signer_info.key {
+        return Err(VaultError::NotConfigAdmin.into());
+    }
+    
+    update(&mut config);
+    
+    // Serialize and store the updated config
+    config.serialize(&mut &mut config_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Process update config instruction
+fn process_update_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    protocol_fee_bps: u16,
+    fee_destination: Pubkey,
+) -> ProgramResult {
+    if protocol_fee_bps > MAX_PROTOCOL_FEE_BPS {
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    process_config_update(program_id, accounts, |config| Ok(config.admin), |config| {
+        config.protocol_fee_bps = protocol_fee_bps;
+        config.fee_destination = fee_destination;
+    })?;
+    
+    vault_log!("Protocol fee set to {} bps", protocol_fee_bps);
+    Ok(())
+}
+
+// Process propose config admin instruction
+fn process_propose_config_admin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_admin: Option<Pubkey>,
+) -> ProgramResult {
+    process_config_update(program_id, accounts, |config| Ok(config.admin), |config| {
+        config.pending_admin = new_admin;
+    })?;
+    
+    match new_admin {
+        Some(new_admin) => vault_log!("Config admin change to {} proposed", new_admin),
+        None => vault_log!("Config admin proposal withdrawn"),
+    }
+    Ok(())
+}
+
+// Process accept config admin instruction
+fn process_accept_config_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let pending_admin = |config: &ProtocolConfig| {
+        config.pending_admin.ok_or_else(|| VaultError::NoPendingAdminChange.into())
+    };
+    process_config_update(program_id, accounts, pending_admin, |config| {
+        config.admin = config.pending_admin.take().unwrap_or(config.admin);
+    })?;
+    
+    vault_log!("Config admin change accepted");
+    Ok(())
+}
+
+// Process initialize global state instruction
+fn process_initialize_global_state(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let authority_info = next_account_info(account_info_iter)?;
+    let global_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    
+    // Verify the authority signed the transaction
+    if !authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the system program account
+    if *system_program_info.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify the global state address and that it doesn't exist yet
+    let (global_key, bump) = find_global_address(program_id);
+    if global_key != *global_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if global_info.owner == program_id {
+        return Err(VaultError::AccountAlreadyInUse.into());
+    }
+    
+    // Create the global state, funded by the authority
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            authority_info.key,
+            global_info.key,
+            rent.minimum_balance(GlobalState::LEN),
+            GlobalState::LEN as u64,
+            program_id,
+        ),
+        &[
+            authority_info.clone(),
+            global_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[GLOBAL_SEED, &[bump]]],
+    )?;
+    
+    let global = GlobalState {
+        authority: *authority_info.key,
+        deposits_halted: false,
+        pending_authority: None,
+    };
+    global.serialize(&mut &mut global_info.data.borrow_mut()[..])?;
+    
+    vault_log!("Global state initialized by {}", authority_info.key);
+    Ok(())
+}
+
+// Load the global state for a change signed by `expected_signer(global)`,
+// apply `update` and store the result
+fn process_global_update<S, F>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    expected_signer: S,
+    update: F,
+) -> ProgramResult
+where
+    S: FnOnce(&GlobalState) -> Result<Pubkey, ProgramError>,
+    F: FnOnce(&mut GlobalState),
+{
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let signer_info = next_account_info(account_info_iter)?;
+    let global_info = next_account_info(account_info_iter)?;
+    
+    // Verify the signer signed the transaction
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the global account is the program's global state
+    if global_info.owner != program_id || *global_info.key != find_global_address(program_id).0 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    
+    // Load the global state and verify the signer may change it
+    let mut global = GlobalState::deserialize(&mut &global_info.data.borrow()[..])?;
+    if expected_signer(&global)? != *signer_info.key {
+        return Err(VaultError::NotGlobalAuthority.into());
+    }
+    
+    update(&mut global);
+    
+    // Serialize and store the updated global state
+    global.serialize(&mut &mut global_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Process set global halt instruction
+fn process_set_global_halt(program_id: &Pubkey, accounts: &[AccountInfo], halted: bool) -> ProgramResult {
+    process_global_update(program_id, accounts, |global| Ok(global.authority), |global| {
+        global.deposits_halted = halted;
+    })?;
+    
+    vault_event!(b"global_halt", [halted as u8]);
+    if halted {
+        vault_log!("Deposits halted program-wide; withdrawals continue");
+    } else {
+        vault_log!("Deposits resumed program-wide");
+    }
+    Ok(())
+}
+
+// Process propose global authority instruction
+fn process_propose_global_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_authority: Option<Pubkey>,
+) -> ProgramResult {
+    process_global_update(program_id, accounts, |global| Ok(global.authority), |global| {
+        global.pending_authority = new_authority;
+    })?;
+    
+    match new_authority {
+        Some(new_authority) => vault_log!("Global authority change to {} proposed", new_authority),
+        None => vault_log!("Global authority proposal withdrawn"),
+    }
+    Ok(())
+}
+
+// Process accept global authority instruction
+fn process_accept_global_authority(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let pending_authority = |global: &GlobalState| {
+        global.pending_authority.ok_or_else(|| VaultError::NoPendingAuthorityChange.into())
+    };
+    process_global_update(program_id, accounts, pending_authority, |global| {
+        global.authority = global.pending_authority.take().unwrap_or(global.authority);
+    })?;
+    
+    vault_log!("Global authority change accepted");
+    Ok(())
+}
+
+// Fail if the global authority halted deposits. Deposit instructions take the
+// global state as their last account; until the flag day described in the
+// README, clients may omit it and deposits proceed as if un-halted
+fn check_global_halt(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let global_info = match accounts.last() {
+        Some(global_info) if *global_info.key == find_global_address(program_id).0 => global_info,
+        _ => return Ok(()),
+    };
+    if global_info.owner != program_id {
+        return Ok(());
+    }
+    
+    let global = GlobalState::deserialize(&mut &global_info.data.borrow()[..])?;
+    if global.deposits_halted {
+        return Err(VaultError::DepositsHalted.into());
+    }
+    Ok(())
+}
+
+// Set the program stats account aside when it is the last account, so the
+// optional accounts before it are read as usual. Only existing stats are
+// recognized; `ReconcileStats` creates them
+fn split_program_stats<'a, 'b>(
+    program_id: &Pubkey,
+    accounts: &'b [AccountInfo<'a>],
+) -> (&'b [AccountInfo<'a>], Option<&'b AccountInfo<'a>>) {
+    match accounts.split_last() {
+        Some((stats_info, rest))
+            if stats_info.owner == program_id
+                && stats_info.data_len() == ProgramStats::LEN
+                && *stats_info.key == find_stats_address(program_id).0 =>
+        {
+            (rest, Some(stats_info))
+        },
+        _ => (accounts, None),
+    }
+}
+
+// Load `info` as an initialized SPL token multisig, if it is one
+pub(crate) fn load_multisig(info: &AccountInfo) -> Option<Multisig> {
+    if *info.owner != spl_token::id() || info.data_len() != Multisig::LEN {
+        return None;
+    }
+    Multisig::unpack(&info.data.borrow()).ok()
+}
+
+// Set aside the members signing for the first account when it is an SPL
+// token multisig: the trailing accounts that are among its signers, passed
+// after every other account
+fn split_multisig_signers<'a, 'b>(accounts: &'b [AccountInfo<'a>]) -> (&'b [AccountInfo<'a>], &'b [AccountInfo<'a>]) {
+    let multisig = match accounts.first().and_then(load_multisig) {
+        Some(multisig) => multisig,
+        None => return (accounts, &[]),
+    };
+    let members = &multisig.signers[..usize::from(multisig.n).min(multisig.signers.len())];
+    let count = accounts[1..].iter().rev().take_while(|info| members.contains(info.key)).count();
+    accounts.split_at(accounts.len() - count)
+}
+
+// Keys of the multisig members that signed, for a token instruction whose
+// authority is the multisig
+fn signing_members<'b>(multisig_signers: &'b [AccountInfo]) -> Vec<&'b Pubkey> {
+    multisig_signers.iter().filter(|info| info.is_signer).map(|info| info.key).collect()
+}
+
+// The accounts of a token instruction followed by the signing multisig members
+fn with_signing_members<'a>(
+    accounts: &[AccountInfo<'a>],
+    multisig_signers: &[AccountInfo<'a>],
+) -> Vec<AccountInfo<'a>> {
+    accounts.iter().chain(multisig_signers.iter().filter(|info| info.is_signer)).cloned().collect()
+}
+
+// Apply `update` to the program stats, if supplied, for a vault they count;
+// activity in other vaults is left to `ReconcileStats`
+fn update_program_stats<F>(stats_info: Option<&AccountInfo>, vault: &Vault, slot: u64, update: F) -> ProgramResult
+where
+    F: FnOnce(&mut ProgramStats),
+{
+    let stats_info = match stats_info {
+        Some(stats_info) if vault.stats_reconciled => stats_info,
+        _ => return Ok(()),
+    };
+    let mut stats = ProgramStats::deserialize(&mut &stats_info.data.borrow()[..])?;
+    update(&mut stats);
+    stats.last_updated_slot = slot;
+    stats.serialize(&mut &mut stats_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Read the protocol fee rate and fee destination from the optional trailing
+// config accounts; no fee applies when they are omitted or the config doesn't
+// exist yet
+fn load_protocol_fee<'a, 'b, I>(
+    program_id: &Pubkey,
+    account_info_iter: &mut I,
+) -> Result<Option<(u16, &'b AccountInfo<'a>)>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    let config_info = match next_account_info(account_info_iter) {
+        Ok(config_info) => config_info,
+        Err(_) => return Ok(None),
+    };
+    if *config_info.key != find_config_address(program_id).0 {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if config_info.owner != program_id {
+        return Ok(None);
+    }
+    
+    let config = ProtocolConfig::deserialize(&mut &config_info.data.borrow()[..])?;
+    if config.protocol_fee_bps == 0 {
+        return Ok(None);
+    }
+    
+    let fee_destination_info = next_account_info(account_info_iter)?;
+    if *fee_destination_info.key != config.fee_destination {
+        return Err(VaultError::InvalidFeeDestination.into());
+    }
+    Ok(Some((config.protocol_fee_bps, fee_destination_info)))
+}
+
+// Process create vault instruction
+fn process_create_vault(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    skip_registry: bool,
+    arbitration_destination: Option<Pubkey>,
+    emergency_destination: Option<Pubkey>,
+    owner_set: Option<OwnerSet>,
+    config: VaultConfig,
+) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let registry_info = if skip_registry {
+        None
+    } else {
+        Some(next_account_info(account_info_iter)?)
+    };
+    let payer_info = account_info_iter.next().unwrap_or(owner_info);
+    
+    // Verify the owner and the payer signed the transaction
+    checks::signer(accounts, owner_info, "owner")?;
+    checks::signer(accounts, payer_info, "payer")?;
+    
+    // Verify the vault account is owned by the program
+    checks::owner(accounts, vault_account_info, "vault", program_id, ProgramError::IncorrectProgramId)?;
+    
+    // Verify the system program account
+    checks::system_program(accounts, system_program_info)?;
+    
+    // Check if the vault account is already initialized (pre-allocated accounts are zero-filled)
+    if vault_account_info.data.borrow().iter().any(|byte| *byte != 0) {
+        return Err(checks::fail(
+            accounts,
+            vault_account_info,
+            "vault",
+            VaultError::AccountAlreadyInUse,
+            format_args!("an uninitialized account"),
+        ));
+    }
+    
+    // Verify the owner set, if any
+    if let Some(owner_set) = &owner_set {
+        check_owner_set(owner_set)?;
+    }
+    
+    // Verify the initial configuration
+    config.validate()?;
+    
+    // Initialize the vault
+    let mut vault = Vault {
+        is_initialized: true,
+        owner: *owner_info.key,
+        deposit_count: 0,
+        deposits: Vec::new(),
+        reentrancy_guard: false,
+        emergency_authority: None,
+        arbitration_destination,
+        emergency_destination,
+        owner_set,
+        ..Vault::default()
+    };
+    config.apply(&mut vault);
+    
+    // Count the new vault in the program stats, if supplied; from here on its
+    // deposits and withdrawals update them
+    if stats_info.is_some() {
+        vault.stats_reconciled = true;
+        update_program_stats(stats_info, &vault, Clock::get()?.slot, |stats| {
+            stats.vaults_counted = stats.vaults_counted.saturating_add(1);
+        })?;
+    }
+    
+    // Serialize and store the vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    vault_event!(b"vault_created", vault_account_info.key, owner_info.key);
+    
+    // Record the vault in the owner's registry
+    if let Some(registry_info) = registry_info {
+        append_to_registry(
+            program_id,
+            owner_info,
+            payer_info,
+            registry_info,
+            system_program_info,
+            vault_account_info.key,
+        )?;
+    }
+    
+    vault_log!("Vault created successfully");
+    if let Some(emergency_destination) = emergency_destination {
+        vault_log!("Emergency withdrawals pay out to {}", emergency_destination);
+    }
+    Ok(())
+}
+
+// Process create vault and deposit instruction
+fn process_create_vault_and_deposit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: VaultConfig,
+    amount: u64,
+    unlock_time: i64,
+    tag: [u8; 32],
+) -> ProgramResult {
+    let (_, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts; the rest are the deposit's
+    let owner_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let deposit_accounts = account_info_iter.as_slice();
+    let system_program_info = deposit_accounts.get(3).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    
+    // Verify the owner and the payer signed the transaction
+    checks::signer(accounts, owner_info, "owner")?;
+    checks::signer(accounts, payer_info, "payer")?;
+    
+    // Verify the system program account
+    checks::system_program(accounts, system_program_info)?;
+    
+    // Verify the configuration, even when the vault exists, so a retry fails
+    // the way the first attempt would have
+    config.validate()?;
+    
+    // Verify the vault is the owner's PDA
+    let (vault_key, bump) = find_vault_address(owner_info.key, 0, program_id);
+    if *vault_account_info.key != vault_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    
+    if vault_account_info.owner == program_id {
+        // The vault already exists: deposit into it if the signer owns it
+        let vault = checks::vault(accounts, vault_account_info, program_id)?;
+        if vault.owner != *owner_info.key {
+            return Err(checks::fail(
+                accounts,
+                vault_account_info,
+                "vault",
+                VaultError::AccountAlreadyInUse,
+                format_args!("a vault owned by {}", owner_info.key),
+            ));
+        }
+    } else {
+        // Create the vault, funded by the payer
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                vault_account_info.key,
+                Rent::get()?.minimum_balance(PDA_VAULT_SPACE),
+                PDA_VAULT_SPACE as u64,
+                program_id,
+            ),
+            &[payer_info.clone(), vault_account_info.clone(), system_program_info.clone()],
+            &[&[VAULT_SEED, owner_info.key.as_ref(), &0u64.to_le_bytes(), &[bump]]],
+        )?;
+        
+        // Initialize the vault
+        let mut vault = Vault { is_initialized: true, owner: *owner_info.key, ..Vault::default() };
+        config.apply(&mut vault);
+        
+        // Count the new vault in the program stats, if supplied, as `CreateVault` does
+        if stats_info.is_some() {
+            vault.stats_reconciled = true;
+            update_program_stats(stats_info, &vault, Clock::get()?.slot, |stats| {
+                stats.vaults_counted = stats.vaults_counted.saturating_add(1);
+            })?;
+        }
+        
+        // Serialize and store the vault data
+        vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+        vault_event!(b"vault_created", vault_account_info.key, owner_info.key);
+    }
+    
+    // Make the first deposit with the accounts of a `Deposit` by the owner
+    let deposit_accounts: Vec<AccountInfo> = [owner_info.clone(), vault_account_info.clone()]
+        .into_iter()
+        .chain(deposit_accounts.iter().cloned())
+        .collect();
+    let terms = DepositTerms {
+        amount,
+        unlock: DepositUnlock::At(unlock_time),
+        tag,
+        locked_destination: None,
+        no_crank: false,
+        guardians: Vec::new(),
+        guardian_threshold: 0,
+        emergency_requires_depositor: false,
+        owner_can_release_early: false,
+        idempotency_key: None,
+    };
+    process_deposit(program_id, &deposit_accounts, terms, false)
+}
+
+// Process donate to deposit instruction
+fn process_donate_to_deposit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+    amount: u64,
+) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let donor_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let destination_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the donor signed the transaction
+    checks::signer(accounts, donor_info, "donor")?;
+    
+    // Verify the vault account is owned by the program and load it
+    let mut vault = checks::vault(accounts, vault_account_info, program_id)?;
+    
+    // Verify the source token account can still send
+    checks::not_frozen(accounts, source_token_account_info, "source token account")?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Verify the vault takes donations and deposits aren't closed or halted
+    if vault.donations_disabled {
+        return Err(VaultError::DonationsDisabled.into());
+    }
+    check_deposits_open(&vault)?;
+    check_global_halt(program_id, accounts)?;
+    
+    // Verify the amount is valid
+    check_deposit_amount(amount)?;
+    
+    // Find the deposit and verify it is still active and not frozen
+    let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    let deposit = &vault.deposits[deposit_index];
+    check_token_deposit(deposit)?;
+    check_deposit_open(deposit)?;
+    let (depositor, mint, tag) = (deposit.depositor, deposit.token_mint, deposit.tag);
+    
+    // Verify neither the depositor nor the donor is kept out of the vault
+    check_depositor_admitted(&vault, &depositor, donor_info.key)?;
+    
+    // Verify the donor's tokens are in the deposit's mint and suffice
+    let source_token_account = checks::token_account(accounts, source_token_account_info, "source token account")?;
+    check_payout_mint(&vault.deposits[deposit_index], &source_token_account.mint).map_err(|error| {
+        let role = "source token account";
+        checks::fail(accounts, source_token_account_info, role, error, format_args!("mint {}", mint))
+    })?;
+    check_source_balance(source_token_account.amount, amount).map_err(|error| {
+        let role = "source token account";
+        checks::fail(accounts, source_token_account_info, role, error, format_args!("a balance of {}", amount))
+    })?;
+    let role = "vault token account";
+    checks::vault_token_account(accounts, destination_token_account_info, role, vault_account_info.key)?;
+    
+    // Count the donation against the vault and mint limits before any tokens
+    // move, then credit it to the deposit
+    add_tag_total(&mut vault, &tag, amount)?;
+    reserve_capacity(&mut vault, &mint, amount)?;
+    let deposit = &mut vault.deposits[deposit_index];
+    deposit.amount = deposit.amount.checked_add(amount)
+        .ok_or(VaultError::MathOverflow)?;
+    deposit.donations_received = deposit.donations_received.checked_add(amount)
+        .ok_or(VaultError::MathOverflow)?;
+    update_program_stats(stats_info, &vault, Clock::get()?.slot, |stats| {
+        stats.amount_deposited = stats.amount_deposited.saturating_add(amount as u128);
+        stats.add_locked(&mint, amount as u128);
+    })?;
+    
+    // Transfer tokens from the donor to the vault
+    invoke(
+        &spl_token::instruction::transfer(
+            token_program_info.key,
+            source_token_account_info.key,
+            destination_token_account_info.key,
+            donor_info.key,
+            &[],
+            amount,
+        )?,
+        &[
+            source_token_account_info.clone(),
+            destination_token_account_info.clone(),
+            donor_info.clone(),
+            token_program_info.clone(),
+        ],
+    )?;
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(b"donation_received", deposit_id.to_le_bytes(), donor_info.key, depositor, amount.to_le_bytes());
+    vault_log!("Donation of {} tokens to deposit {} from {}", amount, deposit_id, donor_info.key);
+    Ok(())
+}
+
+// Process clone vault instruction
+fn process_clone_vault(program_id: &Pubkey, accounts: &[AccountInfo], seed_index: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner_info = next_account_info(account_info_iter)?;
+    let source_vault_info = next_account_info(account_info_iter)?;
+    let clone_vault_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let payer_info = account_info_iter.next().unwrap_or(owner_info);
+    
+    // Verify the owner and the payer signed the transaction
+    checks::signer(accounts, owner_info, "owner")?;
+    checks::signer(accounts, payer_info, "payer")?;
+    
+    // Verify the system program account
+    checks::system_program(accounts, system_program_info)?;
+    
+    // Load the source vault; paused and renounced vaults can be cloned, as
+    // only their configuration is copied
+    let source = checks::vault(accounts, source_vault_info, program_id)?;
+    
+    // Verify the clone is the owner's PDA for the index and doesn't exist yet
+    let (clone_key, bump) = find_vault_address(owner_info.key, seed_index, program_id);
+    if *clone_vault_info.key != clone_key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if clone_vault_info.owner == program_id {
+        return Err(checks::fail(
+            accounts,
+            clone_vault_info,
+            "clone vault",
+            VaultError::AccountAlreadyInUse,
+            format_args!("an account that doesn't exist yet"),
+        ));
+    }
+    
+    // Create the clone as large as the source account, funded by the payer
+    let space = source_vault_info.data_len();
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            clone_vault_info.key,
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            program_id,
+        ),
+        &[payer_info.clone(), clone_vault_info.clone(), system_program_info.clone()],
+        &[&[VAULT_SEED, owner_info.key.as_ref(), &seed_index.to_le_bytes(), &[bump]]],
+    )?;
+    
+    // Serialize and store the clone's data
+    let vault = source.clone_config(*owner_info.key);
+    vault.serialize(&mut &mut clone_vault_info.data.borrow_mut()[..])?;
+    
+    vault_event!(b"vault_created", clone_vault_info.key, owner_info.key, source_vault_info.key);
+    vault_log!("Vault cloned from {}", source_vault_info.key);
+    Ok(())
+}
+
+// Append a vault to the owner's registry, creating the registry on first use
+// with rent from `payer_info`
+fn append_to_registry<'a>(
+    program_id: &Pubkey,
+    owner_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    registry_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    vault_key: &Pubkey,
+) -> ProgramResult {
+    let (registry_key, bump) = find_registry_address(owner_info.key, program_id);
+    if registry_key != *registry_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    
+    let mut registry = if registry_info.owner != program_id {
+        // Lazily create the registry, funded by the payer
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                registry_info.key,
+                rent.minimum_balance(VaultRegistry::LEN),
+                VaultRegistry::LEN as u64,
+                program_id,
+            ),
+            &[
+                payer_info.clone(),
+                registry_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[REGISTRY_SEED, owner_info.key.as_ref(), &[bump]]],
+        )?;
+        VaultRegistry {
+            owner: *owner_info.key,
+            vaults: Vec::new(),
+        }
+    } else {
+        VaultRegistry::deserialize(&mut &registry_info.data.borrow()[..])?
+    };
+    
+    registry.add(*vault_key)?;
+    registry.serialize(&mut &mut registry_info.data.borrow_mut()[..])?;
+    
+    vault_log!("Vault registered ({} of {})", registry.vaults.len(), MAX_REGISTRY_VAULTS);
+    Ok(())
+}
+
+// Read the affected depositor's position index account when the vault keeps them
+fn next_position_index<'a, 'b, I>(
+    vault: &Vault,
+    account_info_iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !vault.position_index {
+        return Ok(None);
+    }
+    next_account_info(account_info_iter).map(Some)
+}
+
+// When the vault restricts its callers, read the instructions sysvar and
+// fail unless the instruction is a top-level call the vault accepts or a CPI
+// from a transaction-level program on the allowlist
+fn check_caller<'a: 'b, 'b, I>(
+    program_id: &Pubkey,
+    vault: &Vault,
+    account_info_iter: &mut I,
+) -> ProgramResult
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    let allowed_callers = match &vault.allowed_callers {
+        Some(allowed_callers) => allowed_callers,
+        None => return Ok(()),
+    };
+    let instructions_sysvar_info = next_account_info(account_info_iter)?;
+    let current_index = load_current_index_checked(instructions_sysvar_info)?;
+    let top_level = load_instruction_at_checked(current_index as usize, instructions_sysvar_info)?;
+    
+    // The vault never invokes itself, so a transaction-level instruction for
+    // another program means it was reached through CPI
+    if get_stack_height() <= TRANSACTION_LEVEL_STACK_HEIGHT && top_level.program_id == *program_id {
+        if vault.reject_top_level {
+            return Err(VaultError::CallerNotAllowed.into());
+        }
+        return Ok(());
+    }
+    if top_level.program_id == *program_id || !allowed_callers.contains(&top_level.program_id) {
+        vault_log!("Caller {} is not allowed", top_level.program_id);
+        return Err(VaultError::CallerNotAllowed.into());
+    }
+    vault_event!(b"caller", top_level.program_id);
+    Ok(())
+}
+
+// Load an existing position index, verifying its address
+fn load_position_index(
+    program_id: &Pubkey,
+    vault_key: &Pubkey,
+    depositor: &Pubkey,
+    index_info: &AccountInfo,
+) -> Result<PositionIndex, ProgramError> {
+    if find_position_index_address(vault_key, depositor, program_id).0 != *index_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if index_info.owner != program_id {
+        return Err(VaultError::PositionIndexMismatch.into());
+    }
+    Ok(PositionIndex::deserialize(&mut &index_info.data.borrow()[..])?)
+}
+
+// Record opened deposits in the depositor's position index, lazily creating
+// the index funded by the payer
+fn add_to_position_index<'a>(
+    program_id: &Pubkey,
+    vault_key: &Pubkey,
+    depositor: &Pubkey,
+    index_info: Option<&AccountInfo<'a>>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    deposit_ids: &[u64],
+) -> ProgramResult {
+    let index_info = match index_info {
+        Some(index_info) => index_info,
+        None => return Ok(()),
+    };
+    
+    let mut index = if index_info.owner != program_id {
+        let (index_key, bump) = find_position_index_address(vault_key, depositor, program_id);
+        if index_key != *index_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if *system_program_info.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                index_info.key,
+                rent.minimum_balance(PositionIndex::LEN),
+                PositionIndex::LEN as u64,
+                program_id,
+            ),
+            &[
+                payer_info.clone(),
+                index_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[POSITION_INDEX_SEED, vault_key.as_ref(), depositor.as_ref(), &[bump]]],
+        )?;
+        PositionIndex {
+            vault: *vault_key,
+            depositor: *depositor,
+            deposit_ids: Vec::new(),
+        }
+    } else {
+        load_position_index(program_id, vault_key, depositor, index_info)?
+    };
+    
+    for deposit_id in deposit_ids {
+        index.insert(*deposit_id)?;
+    }
+    index.serialize(&mut &mut index_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Remove closed deposits from the depositor's position index
+fn remove_from_position_index(
+    program_id: &Pubkey,
+    vault_key: &Pubkey,
+    depositor: &Pubkey,
+    index_info: Option<&AccountInfo>,
+    deposit_ids: &[u64],
+) -> ProgramResult {
+    let index_info = match index_info {
+        Some(index_info) => index_info,
+        None => return Ok(()),
+    };
+    
+    let mut index = load_position_index(program_id, vault_key, depositor, index_info)?;
+    for deposit_id in deposit_ids {
+        index.remove(*deposit_id)?;
+    }
+    index.serialize(&mut &mut index_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Process close vault instruction
+fn process_close_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    
+    // Verify the owner signed the transaction
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Load the vault
+    let vault = load_vault(vault_account_info)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Verify the signer owns the vault and still holds its powers
+    require_role(&vault, accounts, OWNER_ONLY)?;
+    
+    // Verify every deposit has been withdrawn
+    if vault.deposits.iter().any(|d| !d.is_withdrawn() || d.pending_rewards > 0) {
+        return Err(VaultError::VaultNotEmpty.into());
+    }
+    
+    // Return the rent to the owner and wipe the account
+    let vault_lamports = vault_account_info.lamports();
+    **owner_info.lamports.borrow_mut() = owner_info
+        .lamports()
+        .checked_add(vault_lamports)
+        .ok_or(VaultError::MathOverflow)?;
+    **vault_account_info.lamports.borrow_mut() = 0;
+    vault_account_info.data.borrow_mut().fill(0);
+    
+    // Prune the vault from the owner's registry, if supplied
+    if let Ok(registry_info) = next_account_info(account_info_iter) {
+        let (registry_key, _) = find_registry_address(owner_info.key, program_id);
+        if registry_key != *registry_info.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if registry_info.owner == program_id {
+            let mut registry = VaultRegistry::deserialize(&mut &registry_info.data.borrow()[..])?;
+            if registry.remove(vault_account_info.key) {
+                registry.serialize(&mut &mut registry_info.data.borrow_mut()[..])?;
+            }
+        }
+    }
+    
+    vault_log!("Vault closed successfully");
+    Ok(())
+}
+
+// Process close vault token account instruction
+fn process_close_vault_token_account(program_id: &Pubkey, accounts: &[AccountInfo], mint: &Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let rent_destination_info = next_account_info(account_info_iter)?;
+    
+    // Verify the owner signed the transaction
+    checks::signer(accounts, owner_info, "owner")?;
+    
+    // Verify the vault account is owned by the program and load it
+    let mut vault = checks::vault(accounts, vault_account_info, program_id)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Verify the signer owns the vault and still holds its powers
+    require_role(&vault, accounts, OWNER_ONLY)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify no open deposit holds the mint and the token account is the
+    // vault's, in the mint, and empty
+    check_mint_inactive(&vault, mint)?;
+    let role = "vault token account";
+    let token_account = checks::vault_token_account(accounts, token_account_info, role, vault_account_info.key)?;
+    if token_account.mint != *mint {
+        let error = VaultError::TokenMintMismatch;
+        return Err(checks::fail(accounts, token_account_info, role, error, format_args!("mint {}", mint)));
+    }
+    if token_account.amount != 0 {
+        let error = VaultError::MintStillActive;
+        return Err(checks::fail(accounts, token_account_info, role, error, format_args!("a balance of 0")));
+    }
+    
+    // Close the token account, sending its rent to the named destination, and forget the mint
+    close_vault_token_account(vault_account_info, token_account_info, rent_destination_info, token_program_info)?;
+    remove_mint_entry(&mut vault, mint);
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(b"token_account_closed", token_account_info.key, mint);
+    vault_log!("Vault token account for {} closed", mint);
+    Ok(())
+}
+
+// When a new deposit unlocks
+enum DepositUnlock {
+    /// At an absolute timestamp
+    At(i64),
+    /// After a number of seconds from the deposit
+    After(i64),
+    /// After a lock tier's duration from the deposit
+    Tier(LockTier),
+    /// At a slot height
+    Slot(u64),
+    /// At an absolute timestamp, or earlier once a price is reached
+    AtOrOnPrice(i64, PriceCondition),
+    /// At an absolute timestamp, or earlier once an account holds the expected bytes
+    AtOrOnAccount(i64, AccountCondition),
+    /// At an absolute timestamp, or earlier for the beneficiary given the
+    /// preimage of the hashlock
+    AtOrOnPreimage(i64, [u8; 32], Pubkey),
+    /// At an absolute timestamp, or earlier for the counterparty when both
+    /// parties release the escrow
+    AtOrOnRelease(i64, Pubkey),
+}
+
+// Process deposit UI amount instruction
+fn process_deposit_ui_amount(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    ui_amount: u64,
+    decimals_exponent: u8,
+    unlock_time: i64,
+    tag: [u8; 32],
+) -> ProgramResult {
+    let (mint_info, deposit_accounts) = accounts.split_first()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    
+    // Verify the mint is the source token account's mint and the client used its decimals
+    if *mint_info.owner != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let source_token_account_info = deposit_accounts.get(2)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let source_token_account = TokenAccount::unpack(&source_token_account_info.data.borrow())?;
+    if source_token_account.mint != *mint_info.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mint = Mint::unpack(&mint_info.data.borrow())?;
+    if mint.decimals != decimals_exponent {
+        return Err(VaultError::DecimalsMismatch.into());
+    }
+    
+    let amount = ui_amount_to_raw(ui_amount, mint.decimals)
+        .ok_or(VaultError::MathOverflow)?;
+    let terms = DepositTerms {
+        amount,
+        unlock: DepositUnlock::At(unlock_time),
+        tag,
+        locked_destination: None,
+        no_crank: false,
+        guardians: Vec::new(),
+        guardian_threshold: 0,
+        emergency_requires_depositor: false,
+        owner_can_release_early: false,
+        idempotency_key: None,
+    };
+    process_deposit(program_id, deposit_accounts, terms, false)
+}
+
+// Terms of a new deposit shared by the deposit instructions
+struct DepositTerms {
+    amount: u64,
+    unlock: DepositUnlock,
+    tag: [u8; 32],
+    locked_destination: Option<Pubkey>,
+    no_crank: bool,
+    guardians: Vec<Pubkey>,
+    guardian_threshold: u8,
+    emergency_requires_depositor: bool,
+    owner_can_release_early: bool,
+    idempotency_key: Option<[u8; 16]>,
+}
+
+// Process deposit instruction; with `split_roles` the funder, payer and
+// depositor are separate accounts, otherwise the depositor fills all three
+fn process_deposit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    terms: DepositTerms,
+    split_roles: bool,
+) -> ProgramResult {
+    let DepositTerms {
+        amount,
+        unlock,
+        tag,
+        locked_destination,
+        no_crank,
+        guardians,
+        guardian_threshold,
+        emergency_requires_depositor,
+        owner_can_release_early,
+        idempotency_key,
+    } = terms;
+    let (accounts, multisig_signers) = split_multisig_signers(accounts);
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let (funder_info, payer_info, depositor_info) = if split_roles {
+        (
+            next_account_info(account_info_iter)?,
+            next_account_info(account_info_iter)?,
+            next_account_info(account_info_iter)?,
+        )
+    } else {
+        let depositor_info = next_account_info(account_info_iter)?;
+        (depositor_info, depositor_info, depositor_info)
+    };
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let destination_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the funder and payer signed the transaction; a funder that is
+    // an SPL token multisig signs through its members
+    let (funder_role, payer_role) = if split_roles { ("funder", "payer") } else { ("depositor", "depositor") };
+    checks::signer_or_multisig(accounts, funder_info, funder_role, multisig_signers)?;
+    if payer_info.key != funder_info.key {
+        checks::signer(accounts, payer_info, payer_role)?;
+    }
+    
+    // Verify the vault account is owned by the program and load it
+    let mut vault = checks::vault(accounts, vault_account_info, program_id)?;
+    
+    // Verify the system program account
+    checks::system_program(accounts, system_program_info)?;
+    
+    // Verify the source token account can still send
+    checks::not_frozen(accounts, source_token_account_info, "source token account")?;
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Verify the calling program, if the vault restricts its callers
+    check_caller(program_id, &vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Verify the vault still takes deposits and they aren't halted, the
+    // recorded depositor may deposit into it and neither the depositor nor
+    // the funder is blocked
+    check_deposits_open(&vault)?;
+    check_global_halt(program_id, accounts)?;
+    check_depositor_admitted(&vault, depositor_info.key, funder_info.key)?;
+    
+    // Reject a retried deposit whose key the depositor already used
+    if let Some(key) = idempotency_key {
+        record_idempotency_key(&mut vault, depositor_info.key, key)?;
+    }
+    
+    // Verify the amount is valid
+    check_deposit_amount(amount)?;
+    
+    // Verify the recovery guardians, if any
+    check_guardians(&guardians, guardian_threshold, depositor_info.key)?;
+    
+    // Resolve the unlock time and verify it is in the future; slot-locked
+    // deposits unlock on the slot alone and record the deposit time
+    let clock = checks::clock(accounts, clock_sysvar_info)?;
+    let unlock_time = match unlock {
+        DepositUnlock::At(unlock_time) => unlock_time,
+        DepositUnlock::After(lock_duration_secs) => {
+            let unlock_time = clock.unix_timestamp.checked_add(lock_duration_secs)
+                .ok_or(VaultError::MathOverflow)?;
+            set_return_data(&unlock_time.to_le_bytes());
+            unlock_time
+        },
+        DepositUnlock::Tier(tier) => {
+            let unlock_time = clock.unix_timestamp.checked_add(tier.duration_secs())
+                .ok_or(VaultError::MathOverflow)?;
+            set_return_data(&unlock_time.to_le_bytes());
+            unlock_time
+        },
+        DepositUnlock::Slot(_) => clock.unix_timestamp,
+        DepositUnlock::AtOrOnPrice(unlock_time, _) => {
+            if vault.price_oracle.is_none() {
+                return Err(VaultError::PriceOracleDisabled.into());
+            }
+            unlock_time
+        },
+        DepositUnlock::AtOrOnAccount(unlock_time, ref condition) => {
+            if condition.expected.is_empty() || condition.expected.len() > MAX_CONDITION_BYTES {
+                return Err(VaultError::InvalidAccountCondition.into());
+            }
+            unlock_time
+        },
+        DepositUnlock::AtOrOnPreimage(unlock_time, _, _) => {
+            // A position NFT would let its holder, not the beneficiary, claim
+            if vault.position_nfts {
+                vault_log!("Hashlocked deposits are not available in vaults issuing position NFTs");
+                return Err(VaultError::InvalidInstructionData.into());
+            }
+            unlock_time
+        },
+        DepositUnlock::AtOrOnRelease(unlock_time, counterparty) => {
+            // A position NFT would let its holder, not the depositor, consent
+            if vault.position_nfts {
+                vault_log!("Escrow deposits are not available in vaults issuing position NFTs");
+                return Err(VaultError::InvalidInstructionData.into());
+            }
+            if counterparty == *depositor_info.key {
+                vault_log!("An escrow's counterparty must differ from its depositor");
+                return Err(VaultError::InvalidInstructionData.into());
+            }
+            unlock_time
+        },
+    };
+    let unlock_slot = match unlock {
+        DepositUnlock::Slot(unlock_slot) => {
+            check_unlock_slot_in_future(unlock_slot, clock.slot, vault.min_lock_slots)?;
+            Some(unlock_slot)
+        },
+        _ => {
+            check_unlock_in_future(unlock_time, clock.unix_timestamp)?;
+            None
+        },
+    };
+    
+    // Round the unlock time up to the vault's granularity and return the
+    // rounded time, replacing any unrounded one returned above
+    let unlock_time = if unlock_slot.is_none() && vault.unlock_granularity > 0 {
+        let unlock_time = round_unlock_time(unlock_time, vault.unlock_granularity, clock.unix_timestamp)?;
+        set_return_data(&unlock_time.to_le_bytes());
+        unlock_time
+    } else {
+        unlock_time
+    };
+    
+    // Verify the source token account belongs to the funder, or the funder is
+    // its approved delegate depositing for the owner, and has sufficient funds
+    let source_token_account = checks::token_account(accounts, source_token_account_info, "source token account")?;
+    if source_token_account.owner != *funder_info.key {
+        check_delegated_source(&source_token_account, funder_info.key, amount).map_err(|error| {
+            let role = "source token account";
+            checks::fail(accounts, source_token_account_info, role, error, format_args!("delegate {}", funder_info.key))
+        })?;
+        // A delegate may only lock the owner's tokens for the owner
+        let owner = source_token_account.owner;
+        let owner_destination = locked_destination.is_none_or(|destination| {
+            destination == *source_token_account_info.key
+                || destination == spl_associated_token_account::get_associated_token_address(
+                    &owner,
+                    &source_token_account.mint,
+                )
+        });
+        // An escrow would commit the owner to a deal it never signed
+        let owner_beneficiary = match unlock {
+            DepositUnlock::AtOrOnPreimage(_, _, beneficiary) => beneficiary == owner,
+            DepositUnlock::AtOrOnRelease(..) => false,
+            _ => true,
+        };
+        if *depositor_info.key != owner || !owner_destination || !owner_beneficiary || !guardians.is_empty() {
+            return Err(VaultError::DelegateTermsNotAllowed.into());
+        }
+    } else if source_token_account.delegate.is_some() {
+        vault_log!("Warning: source token account has an active delegate");
+    }
+    check_source_balance(source_token_account.amount, amount).map_err(|error| {
+        let role = "source token account";
+        checks::fail(accounts, source_token_account_info, role, error, format_args!("a balance of {}", amount))
+    })?;
+    
+    // Verify nobody else can move tokens out of, or close, the vault's token account
+    let vault_token_account = checks::vault_token_account(
+        accounts,
+        destination_token_account_info,
+        "vault token account",
+        vault_account_info.key,
+    )?;
+    if vault_token_account.delegate.is_some() {
+        return Err(checks::fail(
+            accounts,
+            destination_token_account_info,
+            "vault token account",
+            VaultError::DelegatedAccountNotAllowed,
+            format_args!("no delegate"),
+        ));
+    }
+    
+    // Get the position NFT accounts
+    let position_accounts = if vault.position_nfts {
+        Some(PositionNftAccounts {
+            payer: payer_info,
+            depositor: depositor_info,
+            mint: next_account_info(account_info_iter)?,
+            token_account: next_account_info(account_info_iter)?,
+            metadata: next_account_info(account_info_iter)?,
+            token_program: token_program_info,
+            system_program: system_program_info,
+            token_metadata_program: next_account_info(account_info_iter)?,
+            associated_token_program: next_account_info(account_info_iter)?,
+        })
+    } else {
+        None
+    };
+    
+    // Verify the payer can cover the deposit fee, if any
+    let fee_destination_info = load_deposit_fee(&vault, payer_info, account_info_iter)?;
+    
+    // Split the insurance premium off the deposit, if the vault charges one
+    let pool_info = load_insurance_pool(&vault, &source_token_account.mint, account_info_iter)?;
+    let premium = insurance_premium(amount, vault.insurance_bps)?;
+    let gross_amount = amount;
+    let amount = gross_amount - premium;
+    check_deposit_amount(amount)?;
+    
+    // Create a new deposit
+    let deposit_id = vault.deposit_count;
+    let deposit = Deposit {
+        id: deposit_id,
+        depositor: *depositor_info.key,
+        token_mint: source_token_account.mint,
+        amount,
+        premium_paid: premium,
+        unlock_time,
+        status: DepositStatus::Active as u8,
+        tag,
+        created_at: clock.unix_timestamp,
+        position_mint: position_accounts.as_ref().map(|accounts| *accounts.mint.key),
+        locked_destination,
+        no_crank,
+        emergency_requires_depositor,
+        owner_can_release_early,
+        last_action_slot: clock.slot,
+        lock_tier: match unlock {
+            DepositUnlock::Tier(tier) => Some(tier as u8),
+            _ => None,
+        },
+        unlock_slot,
+        price_condition: match unlock {
+            DepositUnlock::AtOrOnPrice(_, condition) => Some(condition),
+            _ => None,
+        },
+        beneficiary: match unlock {
+            DepositUnlock::AtOrOnPreimage(_, _, beneficiary) => Some(beneficiary),
+            _ => None,
+        },
+        hashlock: match unlock {
+            DepositUnlock::AtOrOnPreimage(_, hashlock, _) => Some(hashlock),
+            _ => None,
+        },
+        escrow_counterparty: match unlock {
+            DepositUnlock::AtOrOnRelease(_, counterparty) => Some(counterparty),
+            _ => None,
+        },
+        guardians,
+        guardian_threshold,
+        // Moves the condition out of `unlock`, so it comes last
+        account_condition: match unlock {
+            DepositUnlock::AtOrOnAccount(_, condition) => Some(condition),
+            _ => None,
+        },
+        ..Deposit::default()
+    };
+    
+    // Count the deposit against the vault and mint limits before any tokens move
+    check_unique_tag(&vault, &deposit.depositor, &deposit.tag)?;
+    reserve_deposit_capacity(&mut vault, &deposit.token_mint, amount)?;
+    add_tag_total(&mut vault, &deposit.tag, amount)?;
+    update_program_stats(stats_info, &vault, clock.slot, |stats| {
+        stats.deposits_created = stats.deposits_created.saturating_add(1);
+        stats.amount_deposited = stats.amount_deposited.saturating_add(amount as u128);
+        stats.add_locked(&deposit.token_mint, amount as u128);
+    })?;
+    
+    // Charge the deposit fee now that every check has passed
+    charge_deposit_fee(&vault, payer_info, fee_destination_info, system_program_info)?;
+    
+    // Pay the premium from the funder into the insurance pool
+    if let Some(pool_info) = pool_info {
+        vault.insurance_premiums = vault.insurance_premiums.checked_add(premium)
+            .ok_or(VaultError::MathOverflow)?;
+        invoke(
+            &spl_token::instruction::transfer(
+                token_program_info.key,
+                source_token_account_info.key,
+                pool_info.key,
+                funder_info.key,
+                &signing_members(multisig_signers),
+                premium,
+            )?,
+            &with_signing_members(
+                &[
+                    source_token_account_info.clone(),
+                    pool_info.clone(),
+                    funder_info.clone(),
+                    token_program_info.clone(),
+                ],
+                multisig_signers,
+            ),
+        )?;
+    }
+    
+    // Add the deposit to the vault
+    vault.deposits.push(deposit);
+    vault.deposit_count = vault.deposit_count.checked_add(1)
+        .ok_or(VaultError::MathOverflow)?;
+    
+    // Record the deposit in the depositor's position index
+    add_to_position_index(
+        program_id,
+        vault_account_info.key,
+        depositor_info.key,
+        position_index_info,
+        payer_info,
+        system_program_info,
+        &[deposit_id],
+    )?;
+    
+    // Transfer tokens from the funder to the vault
+    let transfer_instruction = spl_token::instruction::transfer(
+        token_program_info.key,
+        source_token_account_info.key,
+        destination_token_account_info.key,
+        funder_info.key,
+        &signing_members(multisig_signers),
+        amount,
+    )?;
+    
+    invoke(
+        &transfer_instruction,
+        &with_signing_members(
+            &[
+                source_token_account_info.clone(),
+                destination_token_account_info.clone(),
+                funder_info.clone(),
+                token_program_info.clone(),
+            ],
+            multisig_signers,
+        ),
+    )?;
+    
+    // Mint the position NFT to the depositor
+    if let Some(position_accounts) = &position_accounts {
+        mint_position_nft(
+            program_id,
+            vault_account_info.key,
+            deposit_id,
+            position_nft_name(amount, unlock_time),
+            vault.position_nft_uri.clone(),
+            position_accounts,
+        )?;
+    }
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(b"deposit", deposit_id.to_le_bytes(), amount.to_le_bytes(), unlock_time.to_le_bytes());
+    vault_log!("Deposit successful: {} tokens locked until timestamp {}", amount, unlock_time);
+    if premium > 0 {
+        vault_log!("Insurance premium: {} of {} tokens", premium, gross_amount);
+    }
+    Ok(())
+}
+
+// Process deposit pair instruction
+fn process_deposit_pair(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amounts: [u64; 2],
+    unlock_time: i64,
+    tag: [u8; 32],
+) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let depositor_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let legs = [
+        (next_account_info(account_info_iter)?, next_account_info(account_info_iter)?),
+        (next_account_info(account_info_iter)?, next_account_info(account_info_iter)?),
+    ];
+    let token_program_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the depositor signed the transaction
+    if !depositor_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify the system program account
+    if *system_program_info.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Verify the calling program, if the vault restricts its callers
+    check_caller(program_id, &vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Position NFTs are minted one per deposit instruction
+    if vault.position_nfts {
+        vault_log!("Paired deposits are not available in vaults issuing position NFTs");
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    // The insurance pool holds a single mint, so one leg couldn't pay its premium
+    if vault.insurance_bps > 0 {
+        vault_log!("Paired deposits are not available in vaults charging an insurance premium");
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    // Verify the vault still takes deposits, they aren't halted and the
+    // depositor may deposit into it
+    check_deposits_open(&vault)?;
+    check_global_halt(program_id, accounts)?;
+    check_depositor_admitted(&vault, depositor_info.key, depositor_info.key)?;
+    
+    // Verify the unlock time is in the future and round it to the vault's granularity
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    check_unlock_in_future(unlock_time, clock.unix_timestamp)?;
+    let unlock_time = round_unlock_time(unlock_time, vault.unlock_granularity, clock.unix_timestamp)?;
+    
+    // Verify both legs and reserve their capacity before any tokens move
+    let first_id = vault.deposit_count;
+    let second_id = first_id.checked_add(1).ok_or(VaultError::MathOverflow)?;
+    let mut deposits = Vec::with_capacity(2);
+    for (index, ((source_info, vault_token_info), amount)) in legs.iter().zip(amounts).enumerate() {
+        check_deposit_amount(amount)?;
+        checks::not_frozen(accounts, source_info, "source token account")?;
+    
+        let source_token_account = TokenAccount::unpack(&source_info.data.borrow())?;
+        if source_token_account.owner != *depositor_info.key {
+            return Err(ProgramError::IllegalOwner);
+        }
+        check_source_balance(source_token_account.amount, amount)?;
+    
+        let vault_token_account = TokenAccount::unpack(&vault_token_info.data.borrow())?;
+        if vault_token_account.delegate.is_some() {
+            return Err(VaultError::DelegatedAccountNotAllowed.into());
+        }
+        check_vault_close_authority(vault_account_info.key, &vault_token_account)?;
+    
+        check_unique_tag(&vault, depositor_info.key, &tag)?;
+        reserve_deposit_capacity(&mut vault, &source_token_account.mint, amount)?;
+        add_tag_total(&mut vault, &tag, amount)?;
+        update_program_stats(stats_info, &vault, clock.slot, |stats| {
+            stats.deposits_created = stats.deposits_created.saturating_add(1);
+            stats.amount_deposited = stats.amount_deposited.saturating_add(amount as u128);
+            stats.add_locked(&source_token_account.mint, amount as u128);
+        })?;
+    
+        let (id, linked_id) = if index == 0 { (first_id, second_id) } else { (second_id, first_id) };
+        deposits.push(Deposit {
+            id,
+            depositor: *depositor_info.key,
+            token_mint: source_token_account.mint,
+            amount,
+            unlock_time,
+            tag,
+            created_at: clock.unix_timestamp,
+            last_action_slot: clock.slot,
+            linked_deposit: Some(linked_id),
+            ..Deposit::default()
+        });
+    }
+    
+    // Verify the depositor can cover the deposit fee, if any
+    let fee_destination_info = load_deposit_fee(&vault, depositor_info, account_info_iter)?;
+    
+    // Record both deposits
+    vault.deposits.extend(deposits);
+    vault.deposit_count = second_id.checked_add(1).ok_or(VaultError::MathOverflow)?;
+    add_to_position_index(
+        program_id,
+        vault_account_info.key,
+        depositor_info.key,
+        position_index_info,
+        depositor_info,
+        system_program_info,
+        &[first_id, second_id],
+    )?;
+    
+    // Every check has passed: charge the fee and move both legs
+    charge_deposit_fee(&vault, depositor_info, fee_destination_info, system_program_info)?;
+    for ((source_info, vault_token_info), amount) in legs.iter().zip(amounts) {
+        invoke(
+            &spl_token::instruction::transfer(
+                token_program_info.key,
+                source_info.key,
+                vault_token_info.key,
+                depositor_info.key,
+                &[],
+                amount,
+            )?,
+            &[
+                (*source_info).clone(),
+                (*vault_token_info).clone(),
+                depositor_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+    }
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    for deposit in &vault.deposits[vault.deposits.len() - 2..] {
+        vault_event!(b"deposit", deposit.id.to_le_bytes(), deposit.amount.to_le_bytes(), unlock_time.to_le_bytes());
+    }
+    vault_log!(
+        "Paired deposit successful: deposits {} and {} locked until timestamp {}",
+        first_id,
+        second_id,
+        unlock_time
+    );
+    Ok(())
+}
+
+// Process deposit many instruction
+fn process_deposit_many(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    items: Vec<(u64, i64, [u8; 32])>,
+) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let depositor_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let destination_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the depositor signed the transaction
+    if !depositor_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify the system program account
+    if *system_program_info.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify the batch size
+    if items.is_empty() || items.len() > MAX_BATCH_DEPOSITS {
+        vault_log!("A batch holds between 1 and {} deposits", MAX_BATCH_DEPOSITS);
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    // Verify the source token account can still send
+    checks::not_frozen(accounts, source_token_account_info, "source token account")?;
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Verify the calling program, if the vault restricts its callers
+    check_caller(program_id, &vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Position NFTs are minted one per deposit instruction
+    if vault.position_nfts {
+        vault_log!("Batched deposits are not available in vaults issuing position NFTs");
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    // The premium would have to be split across the items
+    if vault.insurance_bps > 0 {
+        vault_log!("Batched deposits are not available in vaults charging an insurance premium");
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    // Verify the vault still takes deposits, they aren't halted and the
+    // depositor may deposit into it
+    check_deposits_open(&vault)?;
+    check_global_halt(program_id, accounts)?;
+    check_depositor_admitted(&vault, depositor_info.key, depositor_info.key)?;
+    
+    // Verify the source token account belongs to the depositor and covers the whole batch
+    let source_token_account = TokenAccount::unpack(&source_token_account_info.data.borrow())?;
+    if source_token_account.owner != *depositor_info.key {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let total = items.iter()
+        .try_fold(0u64, |total, (amount, _, _)| total.checked_add(*amount))
+        .ok_or(VaultError::MathOverflow)?;
+    check_source_balance(source_token_account.amount, total)?;
+    
+    // Verify nobody else can move tokens out of, or close, the vault's token account
+    let vault_token_account = TokenAccount::unpack(&destination_token_account_info.data.borrow())?;
+    if vault_token_account.delegate.is_some() {
+        return Err(VaultError::DelegatedAccountNotAllowed.into());
+    }
+    check_vault_close_authority(vault_account_info.key, &vault_token_account)?;
+    
+    // Verify the depositor can cover the deposit fee, if any
+    let fee_destination_info = load_deposit_fee(&vault, depositor_info, account_info_iter)?;
+    
+    // Verify every item, reserve its capacity and record it before any tokens move
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    let first_id = vault.deposit_count;
+    let mut deposit_ids = Vec::with_capacity(items.len());
+    for (amount, unlock_time, tag) in items {
+        check_deposit_amount(amount)?;
+        check_unlock_in_future(unlock_time, clock.unix_timestamp)?;
+        let unlock_time = round_unlock_time(unlock_time, vault.unlock_granularity, clock.unix_timestamp)?;
+        check_unique_tag(&vault, depositor_info.key, &tag)?;
+        reserve_deposit_capacity(&mut vault, &source_token_account.mint, amount)?;
+        add_tag_total(&mut vault, &tag, amount)?;
+        update_program_stats(stats_info, &vault, clock.slot, |stats| {
+            stats.deposits_created = stats.deposits_created.saturating_add(1);
+            stats.amount_deposited = stats.amount_deposited.saturating_add(amount as u128);
+            stats.add_locked(&source_token_account.mint, amount as u128);
+        })?;
+    
+        let id = vault.deposit_count;
+        vault.deposits.push(Deposit {
+            id,
+            depositor: *depositor_info.key,
+            token_mint: source_token_account.mint,
+            amount,
+            unlock_time,
+            tag,
+            created_at: clock.unix_timestamp,
+            last_action_slot: clock.slot,
+            ..Deposit::default()
+        });
+        vault.deposit_count = id.checked_add(1).ok_or(VaultError::MathOverflow)?;
+        deposit_ids.push(id);
+    }
+    let last_id = vault.deposit_count - 1;
+    add_to_position_index(
+        program_id,
+        vault_account_info.key,
+        depositor_info.key,
+        position_index_info,
+        depositor_info,
+        system_program_info,
+        &deposit_ids,
+    )?;
+    
+    // Every check has passed: charge the fee and move the whole batch at once
+    charge_deposit_fee(&vault, depositor_info, fee_destination_info, system_program_info)?;
+    invoke(
+        &spl_token::instruction::transfer(
+            token_program_info.key,
+            source_token_account_info.key,
+            destination_token_account_info.key,
+            depositor_info.key,
+            &[],
+            total,
+        )?,
+        &[
+            source_token_account_info.clone(),
+            destination_token_account_info.clone(),
+            depositor_info.clone(),
+            token_program_info.clone(),
+        ],
+    )?;
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    // Report the assigned ID range
+    let mut id_range = first_id.to_le_bytes().to_vec();
+    id_range.extend_from_slice(&last_id.to_le_bytes());
+    set_return_data(&id_range);
+    
+    for deposit in &vault.deposits[vault.deposits.len() - deposit_ids.len()..] {
+        vault_event!(
+            b"deposit",
+            deposit.id.to_le_bytes(),
+            deposit.amount.to_le_bytes(),
+            deposit.unlock_time.to_le_bytes(),
+        );
+    }
+    vault_log!("Batch deposit successful: {} tokens in deposits {} to {}", total, first_id, last_id);
+    Ok(())
+}
+
+// Read the fee destination account when the vault charges a deposit fee and
+// verify the payer can cover it
+fn load_deposit_fee<'a, 'b, I>(
+    vault: &Vault,
+    payer_info: &AccountInfo<'a>,
+    account_info_iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if vault.deposit_fee_lamports == 0 {
+        return Ok(None);
+    }
+    let fee_destination_info = next_account_info(account_info_iter)?;
+    if *fee_destination_info.key != vault.deposit_fee_destination {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if *payer_info.owner != system_program::id() {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if payer_info.lamports() < vault.deposit_fee_lamports {
+        return Err(VaultError::InsufficientFunds.into());
+    }
+    Ok(Some(fee_destination_info))
+}
+
+// Read the insurance pool account when the vault charges a premium and
+// verify it is the configured pool and holds the deposit's mint
+fn load_insurance_pool<'a, 'b, I>(
+    vault: &Vault,
+    mint: &Pubkey,
+    account_info_iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if vault.insurance_bps == 0 {
+        return Ok(None);
+    }
+    let pool_info = next_account_info(account_info_iter)?;
+    if *pool_info.key != vault.insurance_pool || *pool_info.owner != spl_token::id() {
+        return Err(VaultError::InvalidInsurancePool.into());
+    }
+    let pool = TokenAccount::unpack(&pool_info.data.borrow())?;
+    if pool.mint != *mint {
+        return Err(VaultError::TokenMintMismatch.into());
+    }
+    Ok(Some(pool_info))
+}
+
+// Insurance premium on a deposit of `amount`, rounded up in the pool's favor
+pub(crate) fn insurance_premium(amount: u64, insurance_bps: u16) -> Result<u64, VaultError> {
+    let premium = (amount as u128 * insurance_bps as u128).div_ceil(10_000);
+    u64::try_from(premium).map_err(|_| VaultError::MathOverflow)
+}
+
+// Transfer the deposit fee from the payer, if one is due
+fn charge_deposit_fee<'a>(
+    vault: &Vault,
+    payer_info: &AccountInfo<'a>,
+    fee_destination_info: Option<&AccountInfo<'a>>,
+    system_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    if let Some(fee_destination_info) = fee_destination_info {
+        invoke(
+            &system_instruction::transfer(payer_info.key, fee_destination_info.key, vault.deposit_fee_lamports),
+            &[
+                payer_info.clone(),
+                fee_destination_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+// Accounts used to mint a position NFT during a deposit
+struct PositionNftAccounts<'a, 'b> {
+    payer: &'b AccountInfo<'a>,
+    depositor: &'b AccountInfo<'a>,
+    mint: &'b AccountInfo<'a>,
+    token_account: &'b AccountInfo<'a>,
+    metadata: &'b AccountInfo<'a>,
+    token_program: &'b AccountInfo<'a>,
+    system_program: &'b AccountInfo<'a>,
+    token_metadata_program: &'b AccountInfo<'a>,
+    associated_token_program: &'b AccountInfo<'a>,
+}
+
+// Create a supply-1 position NFT with Metaplex metadata, held by the depositor
+fn mint_position_nft(
+    program_id: &Pubkey,
+    vault_key: &Pubkey,
+    deposit_id: u64,
+    name: String,
+    uri: String,
+    accounts: &PositionNftAccounts,
+) -> ProgramResult {
+    let (mint_key, bump) = find_position_mint_address(vault_key, deposit_id, program_id);
+    if mint_key != *accounts.mint.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if *accounts.token_metadata_program.key != mpl_token_metadata::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    let deposit_id_bytes = deposit_id.to_le_bytes();
+    let bump_seed = [bump];
+    let mint_seeds: &[&[u8]] = &[POSITION_MINT_SEED, vault_key.as_ref(), &deposit_id_bytes, &bump_seed];
+    
+    // Create and initialize the mint; the mint PDA is its own authority
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            accounts.payer.key,
+            accounts.mint.key,
+            rent.minimum_balance(Mint::LEN),
+            Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        &[
+            accounts.payer.clone(),
+            accounts.mint.clone(),
+            accounts.system_program.clone(),
+        ],
+        &[mint_seeds],
+    )?;
+    invoke(
+        &spl_token::instruction::initialize_mint2(
+            &spl_token::id(),
+            accounts.mint.key,
+            accounts.mint.key,
+            None,
+            0,
+        )?,
+        &[accounts.mint.clone(), accounts.token_program.clone()],
+    )?;
+    
+    // Mint the single token to the depositor's associated token account
+    invoke(
+        &spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            accounts.payer.key,
+            accounts.depositor.key,
+            accounts.mint.key,
+            &spl_token::id(),
+        ),
+        &[
+            accounts.payer.clone(),
+            accounts.token_account.clone(),
+            accounts.depositor.clone(),
+            accounts.mint.clone(),
+            accounts.system_program.clone(),
+            accounts.token_program.clone(),
+            accounts.associated_token_program.clone(),
+        ],
+    )?;
+    invoke_signed(
+        &spl_token::instruction::mint_to(
+            &spl_token::id(),
+            accounts.mint.key,
+            accounts.token_account.key,
+            accounts.mint.key,
+            &[],
+            1,
+        )?,
+        &[
+            accounts.mint.clone(),
+            accounts.token_account.clone(),
+            accounts.token_program.clone(),
+        ],
+        &[mint_seeds],
+    )?;
+    
+    // Attach the metadata
+    invoke_signed(
+        &mpl_token_metadata::instruction::create_metadata_accounts_v3(
+            mpl_token_metadata::ID,
+            *accounts.metadata.key,
+            *accounts.mint.key,
+            *accounts.mint.key,
+            *accounts.payer.key,
+            *accounts.mint.key,
+            name,
+            POSITION_NFT_SYMBOL.to_string(),
+            uri,
+            None,
+            0,
+            true,
+            false,
+            None,
+            None,
+            None,
+        ),
+        &[
+            accounts.metadata.clone(),
+            accounts.mint.clone(),
+            accounts.payer.clone(),
+            accounts.system_program.clone(),
+            accounts.token_metadata_program.clone(),
+        ],
+        &[mint_seeds],
+    )?;
+    
+    // Fix the supply at one
+    invoke_signed(
+        &spl_token::instruction::set_authority(
+            &spl_token::id(),
+            accounts.mint.key,
+            None,
+            AuthorityType::MintTokens,
+            accounts.mint.key,
+            &[],
+        )?,
+        &[accounts.mint.clone(), accounts.token_program.clone()],
+        &[mint_seeds],
+    )?;
+    
+    vault_log!("Position NFT {} minted for deposit {}", accounts.mint.key, deposit_id);
+    Ok(())
+}
+
+// Load the vault for a change to one deposit signed by `signer_info`, apply
+// `update` (which also sees the vault's configuration) and store the result;
+// `update` authorizes the signer itself
+fn process_deposit_update<F>(
+    program_id: &Pubkey,
+    vault_account_info: &AccountInfo,
+    signer_info: &AccountInfo,
+    deposit_id: u64,
+    update: F,
+) -> ProgramResult
+where
+    F: FnOnce(&mut Deposit, &Vault) -> ProgramResult,
+{
+    // Verify the signer signed the transaction
+    if !signer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Find the deposit
+    let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    let mut deposit = vault.deposits[deposit_index].clone();
+    
+    // Verify the deposit has not been withdrawn
+    if deposit.is_withdrawn() {
+        return Err(VaultError::AlreadyWithdrawn.into());
+    }
+    
+    // Verify the deposit is not frozen
+    if deposit.frozen {
+        return Err(VaultError::DepositFrozen.into());
+    }
+    
+    update(&mut deposit, &vault)?;
+    vault.deposits[deposit_index] = deposit;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Process enable queued withdrawals instruction
+fn process_enable_queued_withdrawals(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let depositor_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    
+    process_deposit_update(program_id, vault_account_info, depositor_info, deposit_id, |deposit, _| {
+        // Tokenized positions are withdrawn by the NFT holder, not the depositor
+        if deposit.depositor != *depositor_info.key || deposit.position_mint.is_some() {
+            return Err(VaultError::UnauthorizedWithdrawal.into());
+        }
+        deposit.queued_withdrawals = true;
+        Ok(())
+    })?;
+    
+    vault_log!("Queued withdrawals enabled for deposit {}", deposit_id);
+    Ok(())
+}
+
+// Process request withdraw instruction
+fn process_request_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let depositor_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    
+    process_deposit_update(program_id, vault_account_info, depositor_info, deposit_id, |deposit, _| {
+        if deposit.depositor != *depositor_info.key {
+            return Err(VaultError::UnauthorizedWithdrawal.into());
+        }
+        if !deposit.queued_withdrawals {
+            return Err(VaultError::InvalidInstructionData.into());
+        }
+        if deposit.withdraw_requested_at.is_some() {
+            return Err(VaultError::WithdrawalAlreadyRequested.into());
+        }
+        deposit.withdraw_requested_at = Some(clock.unix_timestamp);
+        Ok(())
+    })?;
+    
+    vault_log!("Withdrawal requested for deposit {}", deposit_id);
+    Ok(())
+}
+
+// Process cancel withdraw request instruction
+fn process_cancel_withdraw_request(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let signer_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    
+    process_deposit_update(program_id, vault_account_info, signer_info, deposit_id, |deposit, vault| {
+        // Either the depositor or the emergency authority may cancel
+        if deposit.depositor != *signer_info.key && vault.emergency_authority != Some(*signer_info.key) {
+            return Err(VaultError::UnauthorizedWithdrawal.into());
+        }
+        check_not_same_slot(deposit, Clock::get()?.slot)?;
+        if deposit.withdraw_requested_at.take().is_none() {
+            return Err(VaultError::NoPendingWithdrawal.into());
+        }
+        Ok(())
+    })?;
+    
+    vault_log!("Withdrawal request cancelled for deposit {}", deposit_id);
+    Ok(())
+}
+
+// Process change locked destination instruction
+fn process_change_locked_destination(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+    new_destination: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let depositor_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    
+    process_deposit_update(program_id, vault_account_info, depositor_info, deposit_id, |deposit, vault| {
+        if deposit.depositor != *depositor_info.key {
+            return Err(VaultError::UnauthorizedWithdrawal.into());
+        }
+        let current = deposit.locked_destination.ok_or(VaultError::InvalidInstructionData)?;
+    
+        if new_destination == current {
+            deposit.pending_destination = None;
+            vault_log!("Locked destination change cancelled for deposit {}", deposit_id);
+            return Ok(());
+        }
+    
+        match deposit.pending_destination {
+            Some((pending, proposed_at)) if pending == new_destination => {
+                let applies_at = proposed_at.checked_add(vault.destination_change_delay)
+                    .ok_or(VaultError::MathOverflow)?;
+                if applies_at > clock.unix_timestamp {
+                    return Err(VaultError::TimelockNotElapsed.into());
+                }
+                deposit.locked_destination = Some(new_destination);
+                deposit.pending_destination = None;
+                vault_log!("Locked destination of deposit {} changed to {}", deposit_id, new_destination);
+            },
+            _ => {
+                deposit.pending_destination = Some((new_destination, clock.unix_timestamp));
+                vault_log!("Locked destination change to {} proposed for deposit {}", new_destination, deposit_id);
+            },
+        }
+        Ok(())
+    })
+}
+
+// Process propose beneficiary instruction
+fn process_propose_beneficiary(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+    new_beneficiary: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let depositor_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    
+    process_deposit_update(program_id, vault_account_info, depositor_info, deposit_id, |deposit, _| {
+        // Hashlocked and escrow deposits only pay the parties fixed at deposit time
+        let fixed_parties = deposit.hashlock.is_some() || deposit.escrow_counterparty.is_some();
+        if deposit.depositor != *depositor_info.key || fixed_parties {
+            return Err(VaultError::UnauthorizedWithdrawal.into());
+        }
+        deposit.pending_beneficiary = Some((new_beneficiary, clock.unix_timestamp));
+        Ok(())
+    })?;
+    
+    vault_log!("Beneficiary {} proposed for deposit {}", new_beneficiary, deposit_id);
+    Ok(())
+}
+
+// Process commit beneficiary instruction
+fn process_commit_beneficiary(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let depositor_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    
+    process_deposit_update(program_id, vault_account_info, depositor_info, deposit_id, |deposit, vault| {
+        if deposit.depositor != *depositor_info.key {
+            return Err(VaultError::UnauthorizedWithdrawal.into());
+        }
+        let (new_beneficiary, proposed_at) = deposit.pending_beneficiary
+            .ok_or(VaultError::NoPendingBeneficiaryChange)?;
+        let commits_at = proposed_at.checked_add(vault.beneficiary_change_delay)
+            .ok_or(VaultError::MathOverflow)?;
+        if commits_at > clock.unix_timestamp {
+            return Err(VaultError::TimelockNotElapsed.into());
+        }
+        deposit.beneficiary = Some(new_beneficiary);
+        deposit.pending_beneficiary = None;
+        vault_log!("Beneficiary of deposit {} changed to {}", deposit_id, new_beneficiary);
+        Ok(())
+    })
+}
+
+// Process cancel beneficiary change instruction
+fn process_cancel_beneficiary_change(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let signer_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    
+    process_deposit_update(program_id, vault_account_info, signer_info, deposit_id, |deposit, vault| {
+        // The current beneficiary and the vault owner can veto; the depositor
+        // can withdraw their own proposal
+        let signer = *signer_info.key;
+        if deposit.beneficiary != Some(signer) && deposit.depositor != signer {
+            check_vault_owner(vault, &signer, accounts).map_err(|error| match error {
+                VaultError::NotVaultOwner => VaultError::UnauthorizedWithdrawal,
+                error => error,
+            })?;
+        }
+        if deposit.pending_beneficiary.take().is_none() {
+            return Err(VaultError::NoPendingBeneficiaryChange.into());
+        }
+        Ok(())
+    })?;
+    
+    vault_log!("Beneficiary change cancelled for deposit {}", deposit_id);
+    Ok(())
+}
+
+// Process extend lock instruction
+fn process_extend_lock(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+    new_tier: LockTier,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let depositor_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    
+    process_deposit_update(program_id, vault_account_info, depositor_info, deposit_id, |deposit, vault| {
+        if deposit.depositor != *depositor_info.key {
+            return Err(VaultError::UnauthorizedWithdrawal.into());
+        }
+        // Only tiered deposits can move, and only to a longer tier
+        let current_tier = deposit.lock_tier.and_then(LockTier::from_u8)
+            .ok_or(VaultError::InvalidLockTier)?;
+        if new_tier <= current_tier {
+            return Err(VaultError::InvalidLockTier.into());
+        }
+        // The new unlock time lands on the vault's granularity like a new deposit's
+        let relocked_until = clock.unix_timestamp.checked_add(new_tier.duration_secs())
+            .ok_or(VaultError::MathOverflow)?;
+        let relocked_until = round_unlock_time(relocked_until, vault.unlock_granularity, clock.unix_timestamp)?;
+        deposit.unlock_time = deposit.unlock_time.max(relocked_until);
+        deposit.lock_tier = Some(new_tier as u8);
+        Ok(())
+    })?;
+    
+    vault_log!("Deposit {} extended to lock tier {:?}", deposit_id, new_tier);
+    Ok(())
+}
+
+// How a withdrawal was requested
+#[derive(Clone, Copy, PartialEq)]
+enum WithdrawKind {
+    /// `Withdraw` of an unlocked deposit
+    Direct,
+    /// `ClaimWithdraw`, the claim step of a queued withdrawal
+    Claim,
+    /// `WithdrawOnPrice`, releasing a price-conditioned deposit early
+    OnPrice,
+    /// `WithdrawOnCondition`, releasing an account-conditioned deposit early
+    OnCondition,
+}
+
+// Process propose recovery instruction
+fn process_propose_recovery(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+    new_depositor: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let guardian_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    
+    process_deposit_update(program_id, vault_account_info, guardian_info, deposit_id, |deposit, _| {
+        check_guardian(deposit, guardian_info.key)?;
+        if deposit.pending_recovery.is_some() {
+            return Err(VaultError::RecoveryAlreadyPending.into());
+        }
+        let mut recovery = PendingRecovery {
+            new_depositor,
+            approvals: vec![*guardian_info.key],
+            approved_at: None,
+        };
+        if recovery.approvals.len() >= deposit.guardian_threshold as usize {
+            recovery.approved_at = Some(clock.unix_timestamp);
+        }
+        deposit.pending_recovery = Some(recovery);
+        Ok(())
+    })?;
+    
+    vault_log!("Recovery of deposit {} to {} proposed", deposit_id, new_depositor);
+    Ok(())
+}
+
+// Process approve recovery instruction
+fn process_approve_recovery(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let guardian_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    
+    process_deposit_update(program_id, vault_account_info, guardian_info, deposit_id, |deposit, _| {
+        check_guardian(deposit, guardian_info.key)?;
+        let threshold = deposit.guardian_threshold as usize;
+        let recovery = deposit.pending_recovery.as_mut().ok_or(VaultError::NoPendingRecovery)?;
+        if recovery.approvals.contains(guardian_info.key) {
+            return Err(VaultError::DuplicateApproval.into());
+        }
+        recovery.approvals.push(*guardian_info.key);
+        if recovery.approved_at.is_none() && recovery.approvals.len() >= threshold {
+            recovery.approved_at = Some(clock.unix_timestamp);
+        }
+        Ok(())
+    })?;
+    
+    vault_log!("Recovery of deposit {} approved by {}", deposit_id, guardian_info.key);
+    Ok(())
+}
+
+// Process execute recovery instruction
+fn process_execute_recovery(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let caller_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    
+    process_deposit_update(program_id, vault_account_info, caller_info, deposit_id, |deposit, vault| {
+        let recovery = deposit.pending_recovery.as_ref().ok_or(VaultError::NoPendingRecovery)?;
+        let approved_at = recovery.approved_at.ok_or(VaultError::RecoveryNotApproved)?;
+        let executes_at = approved_at.checked_add(RECOVERY_DELAY)
+            .ok_or(VaultError::MathOverflow)?;
+        if executes_at > clock.unix_timestamp {
+            return Err(VaultError::TimelockNotElapsed.into());
+        }
+    
+        // Move the deposit between the two depositors' position indexes,
+        // creating the new depositor's at the caller's expense
+        if vault.position_index {
+            let old_index_info = next_account_info(account_info_iter)?;
+            let new_index_info = next_account_info(account_info_iter)?;
+            let system_program_info = next_account_info(account_info_iter)?;
+            remove_from_position_index(
+                program_id,
+                vault_account_info.key,
+                &deposit.depositor,
+                Some(old_index_info),
+                &[deposit_id],
+            )?;
+            add_to_position_index(
+                program_id,
+                vault_account_info.key,
+                &recovery.new_depositor,
+                Some(new_index_info),
+                caller_info,
+                system_program_info,
+                &[deposit_id],
+            )?;
+        }
+        deposit.depositor = recovery.new_depositor;
+        deposit.locked_destination = None;
+        deposit.pending_destination = None;
+        deposit.pending_recovery = None;
+        vault_log!("Deposit {} recovered to {}", deposit_id, deposit.depositor);
+        Ok(())
+    })
+}
+
+// Process veto recovery instruction
+fn process_veto_recovery(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let depositor_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    
+    process_deposit_update(program_id, vault_account_info, depositor_info, deposit_id, |deposit, _| {
+        if deposit.depositor != *depositor_info.key {
+            return Err(VaultError::UnauthorizedWithdrawal.into());
+        }
+        if deposit.pending_recovery.take().is_none() {
+            return Err(VaultError::NoPendingRecovery.into());
+        }
+        Ok(())
+    })?;
+    
+    vault_log!("Recovery of deposit {} vetoed", deposit_id);
+    Ok(())
+}
+
+// Process withdraw instruction
+fn process_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+    kind: WithdrawKind,
+    memo: Option<&[u8]>,
+) -> ProgramResult {
+    // Set the multisig members, the stats account and then the memo program
+    // aside, so the optional accounts before them are read as usual
+    let (accounts, multisig_signers) = split_multisig_signers(accounts);
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let (accounts, memo) = match memo {
+        Some(memo) => {
+            let (memo_program_info, accounts) = accounts.split_last().ok_or(ProgramError::NotEnoughAccountKeys)?;
+            check_memo(memo, memo_program_info)?;
+            (accounts, Some((memo, memo_program_info)))
+        },
+        None => (accounts, None),
+    };
+    let (accounts, destination_creation) = split_destination_creation(accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let destination_token_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let condition_info = match kind {
+        WithdrawKind::OnPrice | WithdrawKind::OnCondition => Some(next_account_info(account_info_iter)?),
+        _ => None,
+    };
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the owner signed the transaction, or the threshold of its
+    // members did for a multisig depositor
+    checks::signer_or_multisig(accounts, owner_info, "owner", multisig_signers)?;
+    
+    // Verify the vault account is owned by the program and load it
+    let mut vault = checks::vault(accounts, vault_account_info, program_id)?;
+    
+    // Set the withdraw hook accounts aside, so the optional accounts before
+    // them are read as usual
+    let (optional_accounts, hook) = split_withdraw_hook(&vault, account_info_iter.as_slice())?;
+    let (optional_accounts, rent_destination_info) = split_vault_owner(&vault, optional_accounts);
+    let account_info_iter = &mut optional_accounts.iter();
+    
+    // Verify neither token account is frozen by the mint's freeze authority;
+    // a destination that is about to be created can't be
+    let create_destination = destination_creation.is_some() && destination_token_account_info.data_is_empty();
+    if !create_destination {
+        checks::not_frozen(accounts, destination_token_account_info, "destination token account")?;
+    }
+    checks::not_frozen(accounts, source_token_account_info, "vault token account")?;
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Find the deposit
+    let withdraw_delay = vault.withdraw_delay;
+    let price_oracle = vault.price_oracle;
+    let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    let deposit = &mut vault.deposits[deposit_index];
+    check_token_deposit(deposit)?;
+    let clock = checks::clock(accounts, clock_sysvar_info)?;
+    
+    // Verify the owner is entitled to the deposit: the position NFT holder for
+    // tokenized positions, otherwise the depositor or the current beneficiary
+    let position_to_burn = match deposit.position_mint {
+        Some(position_mint) => {
+            let position_token_account_info = next_account_info(account_info_iter)?;
+            let position_mint_info = next_account_info(account_info_iter)?;
+            let (role, error) = ("position mint", VaultError::InvalidPositionAccount);
+            checks::key(accounts, position_mint_info, role, &position_mint, error)?;
+            checks::owner(accounts, position_mint_info, role, &spl_token::id(), VaultError::InvalidPositionAccount)?;
+    
+            let mint = Mint::unpack(&position_mint_info.data.borrow())?;
+            if mint.supply == 0 {
+                // The NFT was burned outside the vault: the original depositor
+                // may claim once the fallback delay has passed
+                if deposit.depositor != *owner_info.key {
+                    return Err(VaultError::UnauthorizedWithdrawal.into());
+                }
+                let fallback_time = deposit.unlock_time
+                    .checked_add(POSITION_BURN_FALLBACK_DELAY)
+                    .ok_or(VaultError::MathOverflow)?;
+                if fallback_time > clock.unix_timestamp {
+                    return Err(VaultError::UnlockTimeNotReached.into());
+                }
+                None
+            } else {
+                let (role, error) = ("position token account", VaultError::InvalidPositionAccount);
+                checks::owner(accounts, position_token_account_info, role, &spl_token::id(), error)?;
+                let holding = checks::token_account(accounts, position_token_account_info, role)?;
+                if holding.mint != position_mint || holding.owner != *owner_info.key || holding.amount != 1 {
+                    return Err(checks::fail(
+                        accounts,
+                        position_token_account_info,
+                        role,
+                        VaultError::UnauthorizedWithdrawal,
+                        format_args!("1 position token of mint {} held by {}", position_mint, owner_info.key),
+                    ));
+                }
+                Some((position_token_account_info, position_mint_info))
+            }
+        },
+        None => {
+            check_withdrawer(deposit, owner_info.key)?;
+            None
+        },
+    };
+    
+    // Get the protocol fee accounts, if supplied
+    let protocol_fee_accounts = load_protocol_fee(program_id, account_info_iter)?;
+    
+    // Verify the deposit has not been withdrawn and is not frozen
+    check_deposit_open(deposit)?;
+    
+    // Verify the deposit wasn't made in this slot
+    check_not_same_slot(deposit, clock.slot)?;
+    
+    // Verify the unlock time and slot have been reached, or that the oracle
+    // price or the referenced account releases a conditioned deposit early
+    match (kind, condition_info) {
+        (WithdrawKind::OnPrice, Some(price_info)) => {
+            let condition = deposit.price_condition.ok_or(VaultError::InvalidPriceFeed)?;
+            let config = price_oracle.ok_or(VaultError::PriceOracleDisabled)?;
+            checks::key(accounts, price_info, "price feed", &condition.feed, VaultError::InvalidPriceFeed)?;
+            checks::owner(accounts, price_info, "price feed", &config.program, VaultError::InvalidPriceFeed)?;
+            let price = oracle::load_price(price_info)?;
+            oracle::check_price_condition(&condition, &config, &price, clock.slot)?;
+        },
+        (WithdrawKind::OnCondition, Some(account_info)) => {
+            let condition = deposit.account_condition.as_ref().ok_or(VaultError::InvalidAccountCondition)?;
+            let role = "condition account";
+            checks::key(accounts, account_info, role, &condition.account, VaultError::InvalidAccountCondition)?;
+            check_account_condition(condition, &account_info.try_borrow_data()?)?;
+        },
+        _ => {
+            check_unlocked(deposit, clock.unix_timestamp)?;
+            check_unlock_slot_reached(deposit, clock.slot)?;
+        },
+    }
+    
+    // Queued deposits are only released by a claim once the delay has
+    // elapsed since the request
+    let claim = kind == WithdrawKind::Claim;
+    check_withdraw_queue(deposit, claim, withdraw_delay, clock.unix_timestamp)?;
+    deposit.withdraw_requested_at = None;
+    
+    // Verify the payout goes to the locked destination, if any
+    check_locked_destination(deposit, destination_token_account_info.key).map_err(|error| {
+        let role = "destination token account";
+        checks::fail(accounts, destination_token_account_info, role, error, format_args!("the locked destination"))
+    })?;
+    
+    // Verify the destination is the owner's associated token account when the
+    // creation accounts are passed, and create it if missing; the new account
+    // holds the deposit's mint by construction
+    if let Some(creation) = &destination_creation {
+        create_missing_destination(
+            accounts,
+            creation,
+            destination_token_account_info,
+            owner_info,
+            &deposit.token_mint,
+            token_program_info,
+        )?;
+    }
+    
+    // Verify the destination holds the deposit's mint
+    if !create_destination {
+        let destination = TokenAccount::unpack_unchecked(&destination_token_account_info.data.borrow())?;
+        check_payout_mint(deposit, &destination.mint).map_err(|error| {
+            let (role, mint) = ("destination token account", deposit.token_mint);
+            checks::fail(accounts, destination_token_account_info, role, error, format_args!("mint {}", mint))
+        })?;
+    }
+    
+    // Burn the position NFT so it can't be redeemed again
+    if let Some((position_token_account_info, position_mint_info)) = position_to_burn {
+        invoke(
+            &spl_token::instruction::burn(
+                token_program_info.key,
+                position_token_account_info.key,
+                position_mint_info.key,
+                owner_info.key,
+                &signing_members(multisig_signers),
+                1,
+            )?,
+            &with_signing_members(
+                &[
+                    position_token_account_info.clone(),
+                    position_mint_info.clone(),
+                    owner_info.clone(),
+                    token_program_info.clone(),
+                ],
+                multisig_signers,
+            ),
+        )?;
+    }
+    
+    // Pay the deposit out, counted against the owner's rate limit window
+    let amount = deposit.amount;
+    let payout = WithdrawalPayout {
+        vault_account_info,
+        destination_token_account_info,
+        source_token_account_info,
+        token_program_info,
+        stats_info,
+        position_index_info,
+        protocol_fee_accounts,
+        memo,
+        hook,
+        rent_destination_info,
+    };
+    let fee = pay_out_withdrawal(program_id, accounts, &mut vault, deposit_index, owner_info.key, &clock, payout)?;
+    
+    vault_log!("Withdrawal successful: {} tokens from deposit {} ({} protocol fee)", amount - fee, deposit_id, fee);
+    Ok(())
+}
+
+// Accounts paying a withdrawn deposit out in full to one destination
+struct WithdrawalPayout<'a, 'b> {
+    vault_account_info: &'b AccountInfo<'a>,
+    destination_token_account_info: &'b AccountInfo<'a>,
+    source_token_account_info: &'b AccountInfo<'a>,
+    token_program_info: &'b AccountInfo<'a>,
+    stats_info: Option<&'b AccountInfo<'a>>,
+    position_index_info: Option<&'b AccountInfo<'a>>,
+    protocol_fee_accounts: Option<(u16, &'b AccountInfo<'a>)>,
+    memo: Option<(&'b [u8], &'b AccountInfo<'a>)>,
+    hook: Option<WithdrawHook<'a, 'b>>,
+    rent_destination_info: Option<&'b AccountInfo<'a>>,
+}
+
+// Mark an authorized deposit withdrawn, settle the vault's books and pay it
+// out less the protocol fee, then call the withdraw hook and close the emptied
+// token account if the vault asks for it. Stores the vault, releasing its
+// reentrancy guard, and returns the fee
+fn pay_out_withdrawal<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    vault: &mut Vault,
+    deposit_index: usize,
+    withdrawer: &Pubkey,
+    clock: &Clock,
+    payout: WithdrawalPayout<'a, '_>,
+) -> Result<u64, ProgramError> {
+    let WithdrawalPayout {
+        vault_account_info,
+        destination_token_account_info,
+        source_token_account_info,
+        token_program_info,
+        stats_info,
+        position_index_info,
+        protocol_fee_accounts,
+        memo,
+        hook,
+        rent_destination_info,
+    } = payout;
+    
+    // Mark the deposit as withdrawn
+    let deposit = &mut vault.deposits[deposit_index];
+    deposit.status = DepositStatus::Withdrawn as u8;
+    deposit.withdrawn_at = Some(clock.unix_timestamp);
+    let (deposit_id, amount, mint, depositor) = (deposit.id, deposit.amount, deposit.token_mint, deposit.depositor);
+    
+    // Count the withdrawal against the withdrawer's rate limit window
+    debit_rate_limit(vault, withdrawer, amount, clock.unix_timestamp)?;
+    
+    // Free the deposit's share of the vault and mint limits
+    release_deposit_capacity(vault, deposit_index)?;
+    update_program_stats(stats_info, vault, clock.slot, |stats| {
+        stats.amount_withdrawn = stats.amount_withdrawn.saturating_add(amount as u128);
+        stats.remove_locked(&mint, amount as u128);
+    })?;
+    
+    // Drop the deposit from its depositor's position index
+    remove_from_position_index(program_id, vault_account_info.key, &depositor, position_index_info, &[deposit_id])?;
+    
+    // Verify the vault's token account can't be closed by a third party
+    checks::vault_token_account(accounts, source_token_account_info, "vault token account", vault_account_info.key)?;
+    
+    // Skim the protocol fee before paying the withdrawer
+    let fee = match protocol_fee_accounts {
+        Some((protocol_fee_bps, fee_destination_info)) => {
+            let fee = protocol_fee(amount, protocol_fee_bps);
+            if fee > 0 {
+                transfer_from_vault(
+                    vault_account_info,
+                    source_token_account_info,
+                    fee_destination_info,
+                    token_program_info,
+                    fee,
+                )?;
+            }
+            fee
+        },
+        None => 0,
+    };
+    
+    // Transfer tokens from the vault to the withdrawer
+    transfer_from_vault(
+        vault_account_info,
+        source_token_account_info,
+        destination_token_account_info,
+        token_program_info,
+        amount - fee,
+    )?;
+    
+    // Emit the memo after the transfer it describes
+    if let Some((memo, memo_program_info)) = memo {
+        invoke(&spl_memo::build_memo(memo, &[]), std::slice::from_ref(memo_program_info))?;
+    }
+    
+    // Call the withdraw hook once the tokens have moved
+    call_withdraw_hook(vault, vault_account_info, hook.as_ref(), &[deposit_id])?;
+    
+    // Close the token account once the mint's last open deposit has been paid
+    // out and nothing is left in it, if the vault asks for it and the owner's
+    // account was passed to take the rent
+    if let Some(rent_destination_info) = rent_destination_info.filter(|_| vault.auto_close) {
+        if check_mint_inactive(vault, &mint).is_ok()
+            && TokenAccount::unpack(&source_token_account_info.data.borrow())?.amount == 0
+        {
+            let token_account_info = source_token_account_info;
+            close_vault_token_account(
+                vault_account_info,
+                token_account_info,
+                rent_destination_info,
+                token_program_info,
+            )?;
+            remove_mint_entry(vault, &mint);
+            vault_event!(b"token_account_closed", source_token_account_info.key, mint);
+        }
+    }
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(
+        b"withdraw",
+        deposit_id.to_le_bytes(),
+        amount.to_le_bytes(),
+        clock.unix_timestamp.to_le_bytes(),
+        [DepositStatus::Withdrawn as u8],
+    );
+    Ok(fee)
+}
+
+// Process withdraw split instruction
+fn process_withdraw_split(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+    splits: &[u64],
+) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the owner signed the transaction
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify the number of splits and that none is empty
+    if splits.is_empty() || splits.len() > MAX_WITHDRAW_SPLITS || splits.contains(&0) {
+        return Err(VaultError::InvalidSplits.into());
+    }
+    
+    checks::not_frozen(accounts, source_token_account_info, "vault token account")?;
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Set the withdraw hook accounts aside, so the optional accounts before
+    // them are read as usual
+    let (optional_accounts, hook) = split_withdraw_hook(&vault, account_info_iter.as_slice())?;
+    let account_info_iter = &mut optional_accounts.iter();
+    
+    // Get the depositor's position index, if the vault keeps them, and the
+    // protocol fee accounts
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    let protocol_fee_accounts = load_protocol_fee(program_id, account_info_iter)?;
+    
+    // Get one destination per split, then the optional vault owner co-signer
+    let destination_infos = splits.iter()
+        .map(|_| next_account_info(account_info_iter))
+        .collect::<Result<Vec<_>, _>>()?;
+    let owner_approved = match next_account_info(account_info_iter) {
+        Ok(cosigner_info) => {
+            if !cosigner_info.is_signer {
+                return Err(VaultError::NotVaultOwner.into());
+            }
+            check_vault_owner(&vault, cosigner_info.key, accounts)?;
+            true
+        },
+        Err(_) => false,
+    };
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Find the deposit
+    let withdraw_delay = vault.withdraw_delay;
+    let owner_approved_splits = vault.owner_approved_splits;
+    let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    let deposit = &mut vault.deposits[deposit_index];
+    check_token_deposit(deposit)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    
+    // Tokenized positions are paid to the NFT holder by `Withdraw` alone
+    if deposit.position_mint.is_some() {
+        vault_log!("Split withdrawals are not available for tokenized positions");
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    // Verify the owner may withdraw the deposit and that it has matured
+    check_withdrawer(deposit, owner_info.key)?;
+    check_deposit_open(deposit)?;
+    check_not_same_slot(deposit, clock.slot)?;
+    check_unlocked(deposit, clock.unix_timestamp)?;
+    check_unlock_slot_reached(deposit, clock.slot)?;
+    check_withdraw_queue(deposit, false, withdraw_delay, clock.unix_timestamp)?;
+    deposit.withdraw_requested_at = None;
+    
+    // Verify the splits add up to exactly the deposit
+    let total = splits.iter().try_fold(0u64, |total, split| total.checked_add(*split));
+    if total != Some(deposit.amount) {
+        return Err(VaultError::InvalidSplits.into());
+    }
+    
+    // Verify every destination, and that one of them is the depositor's
+    // unless the vault lets its owner approve the payout
+    let mut pays_depositor = false;
+    for destination_info in &destination_infos {
+        checks::not_frozen(accounts, destination_info, "destination token account")?;
+        check_locked_destination(deposit, destination_info.key)?;
+        let destination = TokenAccount::unpack(&destination_info.data.borrow())?;
+        check_payout_mint(deposit, &destination.mint)?;
+        pays_depositor |= destination.owner == deposit.depositor;
+    }
+    if !(pays_depositor || (owner_approved_splits && owner_approved)) {
+        return Err(VaultError::SplitNeedsDepositorDestination.into());
+    }
+    
+    // Mark the deposit as withdrawn
+    deposit.status = DepositStatus::Withdrawn as u8;
+    deposit.withdrawn_at = Some(clock.unix_timestamp);
+    let amount = deposit.amount;
+    
+    // Count the withdrawal against the owner's rate limit window
+    debit_rate_limit(&mut vault, owner_info.key, amount, clock.unix_timestamp)?;
+    
+    // Free the deposit's share of the vault and mint limits
+    release_deposit_capacity(&mut vault, deposit_index)?;
+    update_stats_for_withdrawal(stats_info, &vault, deposit_index, clock.slot)?;
+    
+    // Drop the deposit from its depositor's position index
+    remove_from_position_index(
+        program_id,
+        vault_account_info.key,
+        &vault.deposits[deposit_index].depositor,
+        position_index_info,
+        &[deposit_id],
+    )?;
+    
+    // Verify the vault's token account can't be closed by a third party
+    let vault_token_account = TokenAccount::unpack(&source_token_account_info.data.borrow())?;
+    check_vault_close_authority(vault_account_info.key, &vault_token_account)?;
+    
+    // Skim the protocol fee off each split, paid out in one transfer
+    let fees: Vec<u64> = match protocol_fee_accounts {
+        Some((protocol_fee_bps, _)) => splits.iter().map(|split| protocol_fee(*split, protocol_fee_bps)).collect(),
+        None => vec![0; splits.len()],
+    };
+    let fee: u64 = fees.iter().sum();
+    if let Some((_, fee_destination_info)) = protocol_fee_accounts {
+        if fee > 0 {
+            transfer_from_vault(
+                vault_account_info,
+                source_token_account_info,
+                fee_destination_info,
+                token_program_info,
+                fee,
+            )?;
+        }
+    }
+    
+    // Pay each destination its split
+    for ((destination_info, split), split_fee) in destination_infos.iter().zip(splits).zip(&fees) {
+        transfer_from_vault(
+            vault_account_info,
+            source_token_account_info,
+            destination_info,
+            token_program_info,
+            split - split_fee,
+        )?;
+    }
+    
+    // Call the withdraw hook once the tokens have moved
+    call_withdraw_hook(&vault, vault_account_info, hook.as_ref(), &[deposit_id])?;
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(
+        b"withdraw",
+        deposit_id.to_le_bytes(),
+        amount.to_le_bytes(),
+        clock.unix_timestamp.to_le_bytes(),
+        [DepositStatus::Withdrawn as u8],
+    );
+    vault_log!(
+        "Withdrawal successful: {} tokens from deposit {} split {} ways ({} protocol fee)",
+        amount - fee,
+        deposit_id,
+        splits.len(),
+        fee
+    );
+    Ok(())
+}
+
+// Fail unless the owner set has 1 to `MAX_OWNER_SET_MEMBERS` distinct members
+// and a threshold between 1 and their count
+fn check_owner_set(owner_set: &OwnerSet) -> Result<(), VaultError> {
+    let members = owner_set.members.len();
+    let mut distinct = owner_set.members.clone();
+    distinct.sort();
+    distinct.dedup();
+    if members > MAX_OWNER_SET_MEMBERS
+        || distinct.len() != members
+        || owner_set.threshold == 0
+        || owner_set.threshold as usize > members
+    {
+        return Err(VaultError::InvalidOwnerSet);
+    }
+    Ok(())
+}
+
+// Fail unless `memo` is non-empty UTF-8 of at most `MAX_MEMO_LEN` bytes and
+// `memo_program_info` is the SPL Memo program
+fn check_memo(memo: &[u8], memo_program_info: &AccountInfo) -> ProgramResult {
+    if memo.is_empty() || memo.len() > MAX_MEMO_LEN || std::str::from_utf8(memo).is_err() {
+        return Err(VaultError::InvalidMemo.into());
+    }
+    if *memo_program_info.key != spl_memo::id() {
+        return Err(VaultError::InvalidMemoProgram.into());
+    }
+    Ok(())
+}
+
+// Accounts to create a missing destination associated token account during a
+// withdrawal, passed as the last four accounts (before the memo program, if
+// any): the rent payer, the deposit's mint, the system program and the
+// associated token program
+struct DestinationCreation<'a, 'b> {
+    payer_info: &'b AccountInfo<'a>,
+    mint_info: &'b AccountInfo<'a>,
+    system_program_info: &'b AccountInfo<'a>,
+    associated_token_program_info: &'b AccountInfo<'a>,
+}
+
+// A vault's withdraw hook program and the extra accounts passed to it
+struct WithdrawHook<'a, 'b> {
+    program_info: &'b AccountInfo<'a>,
+    extra_accounts: &'b [AccountInfo<'a>],
+}
+
+// Set the withdraw hook accounts aside when the vault has a hook: the hook
+// program, which must be passed, and every account after it
+fn split_withdraw_hook<'a, 'b>(
+    vault: &Vault,
+    accounts: &'b [AccountInfo<'a>],
+) -> Result<(&'b [AccountInfo<'a>], Option<WithdrawHook<'a, 'b>>), ProgramError> {
+    let hook_program = match vault.hook_program {
+        Some(hook_program) => hook_program,
+        None => return Ok((accounts, None)),
+    };
+    let index = accounts.iter().position(|info| *info.key == hook_program)
+        .ok_or(VaultError::WithdrawHookMissing)?;
+    let (rest, hook_accounts) = accounts.split_at(index);
+    let (program_info, extra_accounts) = hook_accounts.split_first().ok_or(VaultError::WithdrawHookMissing)?;
+    if extra_accounts.len() > MAX_HOOK_ACCOUNTS {
+        return Err(VaultError::TooManyHookAccounts.into());
+    }
+    Ok((rest, Some(WithdrawHook { program_info, extra_accounts })))
+}
+
+// Set the vault owner's account aside when it ends `accounts`, for a
+// withdrawal that may auto-close the vault token account to credit the rent to
+fn split_vault_owner<'a, 'b>(
+    vault: &Vault,
+    accounts: &'b [AccountInfo<'a>],
+) -> (&'b [AccountInfo<'a>], Option<&'b AccountInfo<'a>>) {
+    match accounts.split_last() {
+        Some((owner_info, rest)) if *owner_info.key == vault.owner => (rest, Some(owner_info)),
+        _ => (accounts, None),
+    }
+}
+
+// Call the withdraw hook with the vault and the extra accounts. The vault is
+// stored with its reentrancy guard still set first, so any instruction the
+// hook sends back into the vault fails, and a failing hook fails the caller
+fn invoke_withdraw_hook<'a>(
+    vault: &Vault,
+    vault_account_info: &AccountInfo<'a>,
+    hook: &WithdrawHook<'a, '_>,
+    data: &WithdrawHookData,
+) -> ProgramResult {
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    let mut account_metas = vec![AccountMeta::new_readonly(*vault_account_info.key, false)];
+    account_metas.extend(hook.extra_accounts.iter().map(|info| AccountMeta {
+        pubkey: *info.key,
+        is_signer: info.is_signer,
+        is_writable: info.is_writable,
+    }));
+    let instruction = Instruction {
+        program_id: *hook.program_info.key,
+        accounts: account_metas,
+        data: data.pack(),
+    };
+    let mut account_infos = vec![vault_account_info.clone()];
+    account_infos.extend(hook.extra_accounts.iter().cloned());
+    account_infos.push(hook.program_info.clone());
+    invoke(&instruction, &account_infos)
+}
+
+// Call the vault's withdraw hook, if it has one, once for each of the
+// deposits just paid out, with the deposit's amount before any fee or penalty
+fn call_withdraw_hook<'a>(
+    vault: &Vault,
+    vault_account_info: &AccountInfo<'a>,
+    hook: Option<&WithdrawHook<'a, '_>>,
+    deposit_ids: &[u64],
+) -> ProgramResult {
+    let hook = match hook {
+        Some(hook) => hook,
+        None => return Ok(()),
+    };
+    for deposit_id in deposit_ids {
+        let deposit = vault.deposits.iter().find(|deposit| deposit.id == *deposit_id)
+            .ok_or(VaultError::DepositNotFound)?;
+        let data = WithdrawHookData {
+            vault: *vault_account_info.key,
+            deposit_id: deposit.id,
+            depositor: deposit.depositor,
+            mint: deposit.token_mint,
+            amount: deposit.amount,
+        };
+        invoke_withdraw_hook(vault, vault_account_info, hook, &data)?;
+    }
+    Ok(())
+}
+
+// Set the destination creation accounts aside when the account list ends with
+// the associated token program, so the optional accounts before them are read
+// as usual
+fn split_destination_creation<'a, 'b>(
+    accounts: &'b [AccountInfo<'a>],
+) -> (&'b [AccountInfo<'a>], Option<DestinationCreation<'a, 'b>>) {
+    match accounts {
+        [rest @ .., payer_info, mint_info, system_program_info, associated_token_program_info]
+            if *associated_token_program_info.key == spl_associated_token_account::id() =>
+        {
+            let creation = DestinationCreation {
+                payer_info,
+                mint_info,
+                system_program_info,
+                associated_token_program_info,
+            };
+            (rest, Some(creation))
+        },
+        _ => (accounts, None),
+    }
+}
+
+// Verify the destination is `wallet_info`'s associated token account for
+// `token_mint` and create it at the payer's expense if it doesn't exist
+fn create_missing_destination<'a>(
+    accounts: &[AccountInfo<'a>],
+    creation: &DestinationCreation<'a, '_>,
+    destination_token_account_info: &AccountInfo<'a>,
+    wallet_info: &AccountInfo<'a>,
+    token_mint: &Pubkey,
+    token_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    checks::key(accounts, creation.mint_info, "mint", token_mint, VaultError::InvalidDestinationAccount)?;
+    let expected_destination = spl_associated_token_account::get_associated_token_address(wallet_info.key, token_mint);
+    let (role, error) = ("destination token account", VaultError::InvalidDestinationAccount);
+    checks::key(accounts, destination_token_account_info, role, &expected_destination, error)?;
+    checks::system_program(accounts, creation.system_program_info)?;
+    checks::signer(accounts, creation.payer_info, "rent payer")?;
+    if !destination_token_account_info.data_is_empty() {
+        return Ok(());
+    }
+    invoke(
+        &spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            creation.payer_info.key,
+            wallet_info.key,
+            token_mint,
+            token_program_info.key,
+        ),
+        &[
+            creation.payer_info.clone(),
+            destination_token_account_info.clone(),
+            wallet_info.clone(),
+            creation.mint_info.clone(),
+            creation.system_program_info.clone(),
+            token_program_info.clone(),
+            creation.associated_token_program_info.clone(),
+        ],
+    )
+}
+
+// Process deposit staked SOL instruction
+fn process_deposit_staked_sol(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    unlock_time: i64,
+    validator_vote: Pubkey,
+    tag: [u8; 32],
+) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let depositor_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let stake_account_info = next_account_info(account_info_iter)?;
+    let stake_authority_info = next_account_info(account_info_iter)?;
+    let vote_account_info = next_account_info(account_info_iter)?;
+    let stake_program_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+    let stake_history_info = next_account_info(account_info_iter)?;
+    let stake_config_info = next_account_info(account_info_iter)?;
+    
+    // Verify the depositor signed the transaction
+    if !depositor_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify the system and stake program accounts
+    if *system_program_info.key != system_program::id() || *stake_program_info.key != stake::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify the vote account is the named validator's
+    if *vote_account_info.key != validator_vote || *vote_account_info.owner != vote::program::id() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Position NFTs and insurance premiums are paid in tokens
+    if vault.position_nfts || vault.insurance_bps > 0 {
+        vault_log!("Staked deposits are not available in vaults with position NFTs or insurance premiums");
+        return Err(VaultError::InvalidInstructionData.into());
+    }
+    
+    // Verify the vault still takes deposits, they aren't halted and the
+    // depositor may deposit into it
+    check_deposits_open(&vault)?;
+    check_global_halt(program_id, accounts)?;
+    check_depositor_admitted(&vault, depositor_info.key, depositor_info.key)?;
+    
+    // Verify the amount and that the unlock time is in the future, and round
+    // it to the vault's granularity
+    check_deposit_amount(amount)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    check_unlock_in_future(unlock_time, clock.unix_timestamp)?;
+    let unlock_time = round_unlock_time(unlock_time, vault.unlock_granularity, clock.unix_timestamp)?;
+    
+    // Verify the stake authority and the stake account the deposit ID derives
+    let deposit_id = vault.deposit_count;
+    let seed = stake_account_seed(deposit_id);
+    let (authority_key, authority_bump) = find_stake_authority_address(vault_account_info.key, program_id);
+    let stake_account_key = Pubkey::create_with_seed(&authority_key, &seed, &stake::program::id())
+        .map_err(|_| VaultError::InvalidStakeAccount)?;
+    if *stake_authority_info.key != authority_key || *stake_account_info.key != stake_account_key {
+        return Err(VaultError::InvalidStakeAccount.into());
+    }
+    
+    // Count the deposit, in lamports, against the vault limits
+    let deposit = Deposit {
+        id: deposit_id,
+        depositor: *depositor_info.key,
+        token_mint: stake::program::id(),
+        amount,
+        unlock_time,
+        tag,
+        created_at: clock.unix_timestamp,
+        last_action_slot: clock.slot,
+        stake_account: Some(stake_account_key),
+        ..Deposit::default()
+    };
+    check_unique_tag(&vault, &deposit.depositor, &tag)?;
+    reserve_deposit_capacity(&mut vault, &deposit.token_mint, amount)?;
+    add_tag_total(&mut vault, &tag, amount)?;
+    update_program_stats(stats_info, &vault, clock.slot, |stats| {
+        stats.deposits_created = stats.deposits_created.saturating_add(1);
+        stats.amount_deposited = stats.amount_deposited.saturating_add(amount as u128);
+        stats.add_locked(&deposit.token_mint, amount as u128);
+    })?;
+    
+    // Charge the deposit fee, if any
+    let fee_destination_info = load_deposit_fee(&vault, depositor_info, account_info_iter)?;
+    charge_deposit_fee(&vault, depositor_info, fee_destination_info, system_program_info)?;
+    
+    // Add the deposit to the vault and the depositor's position index
+    vault.deposits.push(deposit);
+    vault.deposit_count = vault.deposit_count.checked_add(1)
+        .ok_or(VaultError::MathOverflow)?;
+    add_to_position_index(
+        program_id,
+        vault_account_info.key,
+        depositor_info.key,
+        position_index_info,
+        depositor_info,
+        system_program_info,
+        &[deposit_id],
+    )?;
+    
+    // Create the stake account with the stake authority as its base, so the
+    // depositor funds it without holding its keypair
+    let space = StakeState::size_of();
+    let lamports = Rent::get()?.minimum_balance(space).checked_add(amount)
+        .ok_or(VaultError::MathOverflow)?;
+    let bump = [authority_bump];
+    let authority_seeds: &[&[u8]] = &[STAKE_AUTHORITY_SEED, vault_account_info.key.as_ref(), &bump];
+    invoke_signed(
+        &system_instruction::create_account_with_seed(
+            depositor_info.key,
+            stake_account_info.key,
+            &authority_key,
+            &seed,
+            lamports,
+            space as u64,
+            &stake::program::id(),
+        ),
+        &[
+            depositor_info.clone(),
+            stake_account_info.clone(),
+            stake_authority_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[authority_seeds],
+    )?;
+    
+    // Make the stake authority both staker and withdrawer, then delegate
+    let authorized = Authorized { staker: authority_key, withdrawer: authority_key };
+    invoke(
+        &stake_instruction::initialize(stake_account_info.key, &authorized, &Lockup::default()),
+        &[stake_account_info.clone(), rent_sysvar_info.clone(), stake_program_info.clone()],
+    )?;
+    invoke_signed(
+        &stake_instruction::delegate_stake(stake_account_info.key, &authority_key, &validator_vote),
+        &[
+            stake_account_info.clone(),
+            vote_account_info.clone(),
+            clock_sysvar_info.clone(),
+            stake_history_info.clone(),
+            stake_config_info.clone(),
+            stake_authority_info.clone(),
+            stake_program_info.clone(),
+        ],
+        &[authority_seeds],
+    )?;
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(
+        b"stake_deposit",
+        deposit_id.to_le_bytes(),
+        amount.to_le_bytes(),
+        unlock_time.to_le_bytes(),
+        validator_vote.to_bytes(),
+    );
+    vault_log!("Staked {} lamports with {} until timestamp {}", amount, validator_vote, unlock_time);
+    Ok(())
+}
+
+// Verify the stake authority and stake account of a staked deposit and
+// return the authority's bump
+fn check_stake_accounts(
+    program_id: &Pubkey,
+    vault_key: &Pubkey,
+    deposit: &Deposit,
+    stake_account_info: &AccountInfo,
+    stake_authority_info: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    let stake_account = deposit.stake_account.ok_or(VaultError::NotStakedDeposit)?;
+    let (authority_key, bump) = find_stake_authority_address(vault_key, program_id);
+    if *stake_account_info.key != stake_account || *stake_authority_info.key != authority_key {
+        return Err(VaultError::InvalidStakeAccount.into());
+    }
+    Ok(bump)
+}
+
+// Process deactivate stake instruction
+fn process_deactivate_stake(program_id: &Pubkey, accounts: &[AccountInfo], deposit_id: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let withdrawer_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let stake_account_info = next_account_info(account_info_iter)?;
+    let stake_authority_info = next_account_info(account_info_iter)?;
+    let stake_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    
+    // Verify the withdrawer signed the transaction
+    if !withdrawer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account and the stake program
+    if vault_account_info.owner != program_id || *stake_program_info.key != stake::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify the deposit is open, unlocked and the signer's to withdraw
+    let vault = load_vault(vault_account_info)?;
+    let deposit = vault.deposits.iter().find(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    let bump = check_stake_accounts(
+        program_id,
+        vault_account_info.key,
+        deposit,
+        stake_account_info,
+        stake_authority_info,
+    )?;
+    check_withdrawer(deposit, withdrawer_info.key)?;
+    check_deposit_open(deposit)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    check_unlocked(deposit, clock.unix_timestamp)?;
+    
+    // Start the cooldown; the stake stays active until the epoch ends
+    invoke_signed(
+        &stake_instruction::deactivate_stake(stake_account_info.key, stake_authority_info.key),
+        &[
+            stake_account_info.clone(),
+            clock_sysvar_info.clone(),
+            stake_authority_info.clone(),
+            stake_program_info.clone(),
+        ],
+        &[&[STAKE_AUTHORITY_SEED, vault_account_info.key.as_ref(), &[bump]]],
+    )?;
+    
+    vault_event!(b"stake_deactivated", deposit_id.to_le_bytes(), clock.epoch.to_le_bytes());
+    vault_log!("Deactivating the stake of deposit {} in epoch {}", deposit_id, clock.epoch);
+    Ok(())
+}
+
+// Process withdraw stake instruction
+fn process_withdraw_stake(program_id: &Pubkey, accounts: &[AccountInfo], deposit_id: u64) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let withdrawer_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let stake_account_info = next_account_info(account_info_iter)?;
+    let stake_authority_info = next_account_info(account_info_iter)?;
+    let stake_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let stake_history_info = next_account_info(account_info_iter)?;
+    
+    // Verify the withdrawer signed the transaction
+    if !withdrawer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account and the stake program
+    if vault_account_info.owner != program_id || *stake_program_info.key != stake::program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Verify the deposit is open, unlocked and the signer's to withdraw
+    let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    let deposit = &mut vault.deposits[deposit_index];
+    let bump = check_stake_accounts(
+        program_id,
+        vault_account_info.key,
+        deposit,
+        stake_account_info,
+        stake_authority_info,
+    )?;
+    check_withdrawer(deposit, withdrawer_info.key)?;
+    check_deposit_open(deposit)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    check_unlocked(deposit, clock.unix_timestamp)?;
+    
+    // Mark the deposit as withdrawn
+    deposit.status = DepositStatus::Withdrawn as u8;
+    deposit.withdrawn_at = Some(clock.unix_timestamp);
+    let (amount, depositor) = (deposit.amount, deposit.depositor);
+    
+    // Count the withdrawal against the withdrawer's rate limit window and
+    // free the deposit's share of the vault limits
+    debit_rate_limit(&mut vault, withdrawer_info.key, amount, clock.unix_timestamp)?;
+    release_deposit_capacity(&mut vault, deposit_index)?;
+    update_stats_for_withdrawal(stats_info, &vault, deposit_index, clock.slot)?;
+    remove_from_position_index(program_id, vault_account_info.key, &depositor, position_index_info, &[deposit_id])?;
+    
+    // Withdraw every lamport; the stake program fails this until the stake
+    // has cooled down, and rewards belong to the depositor like the principal
+    let lamports = stake_account_info.lamports();
+    invoke_signed(
+        &stake_instruction::withdraw(
+            stake_account_info.key,
+            stake_authority_info.key,
+            withdrawer_info.key,
+            lamports,
+            None,
+        ),
+        &[
+            stake_account_info.clone(),
+            withdrawer_info.clone(),
+            clock_sysvar_info.clone(),
+            stake_history_info.clone(),
+            stake_authority_info.clone(),
+            stake_program_info.clone(),
+        ],
+        &[&[STAKE_AUTHORITY_SEED, vault_account_info.key.as_ref(), &[bump]]],
+    )?;
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(
+        b"withdraw",
+        deposit_id.to_le_bytes(),
+        amount.to_le_bytes(),
+        clock.unix_timestamp.to_le_bytes(),
+        [DepositStatus::Withdrawn as u8],
+    );
+    vault_log!("Withdrew {} lamports of staked deposit {} ({} staked)", lamports, deposit_id, amount);
+    Ok(())
+}
+
+// Process renounce ownership instruction
+fn process_renounce_ownership(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    emergency_authority: Option<AuthorityOnRenounce>,
+) -> ProgramResult {
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        match (vault.emergency_authority, emergency_authority) {
+            (Some(_), None) => return Err(VaultError::EmergencyAuthorityChoiceRequired.into()),
+            (_, Some(AuthorityOnRenounce::Clear)) => vault.emergency_authority = None,
+            _ => {},
+        }
+        vault.renounced = true;
+        Ok(())
+    })?;
+    
+    vault_log!("Vault ownership renounced");
+    Ok(())
+}
+
+// Process withdraw by tag instruction
+fn process_withdraw_by_tag(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    tag: [u8; 32],
+) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the owner signed the transaction
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Set the withdraw hook accounts aside, so the optional accounts before
+    // them are read as usual
+    let (optional_accounts, hook) = split_withdraw_hook(&vault, account_info_iter.as_slice())?;
+    let account_info_iter = &mut optional_accounts.iter();
+    
+    // Get the owner's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    let protocol_fee_accounts = load_protocol_fee(program_id, account_info_iter)?;
+    
+    // Collect the (mint, vault token account, destination) pairs
+    let mut payouts = Vec::new();
+    while let Ok(source_token_account_info) = next_account_info(account_info_iter) {
+        let destination_token_account_info = next_account_info(account_info_iter)?;
+        checks::not_frozen(accounts, destination_token_account_info, "destination token account")?;
+        checks::not_frozen(accounts, source_token_account_info, "vault token account")?;
+    
+        let vault_token_account = TokenAccount::unpack(&source_token_account_info.data.borrow())?;
+        check_vault_close_authority(vault_account_info.key, &vault_token_account)?;
+        let destination = TokenAccount::unpack_unchecked(&destination_token_account_info.data.borrow())?;
+        if destination.mint != vault_token_account.mint {
+            return Err(VaultError::TokenMintMismatch.into());
+        }
+        payouts.push((vault_token_account.mint, source_token_account_info, destination_token_account_info, 0u64));
+    }
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    let withdraw_delay = vault.withdraw_delay;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    
+    // Only visit the deposits listed in the owner's position index, if any
+    let candidates = match position_index_info {
+        Some(index_info) => {
+            let index = load_position_index(program_id, vault_account_info.key, owner_info.key, index_info)?;
+            index.deposit_ids.iter()
+                .map(|id| vault.deposits.binary_search_by_key(id, |d| d.id))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| VaultError::PositionIndexMismatch)?
+        },
+        None => (0..vault.deposits.len()).collect(),
+    };
+    
+    // Mark every matured deposit of the owner with the tag as withdrawn,
+    // adding it to its mint's payout; tokenized and queued deposits keep
+    // their own withdrawal paths
+    let mut withdrawn = Vec::new();
+    let mut skipped = Vec::new();
+    for deposit_index in candidates {
+        let deposit = &vault.deposits[deposit_index];
+        if deposit.tag != tag
+            || deposit.position_mint.is_some()
+            || check_token_deposit(deposit).is_err()
+            || check_withdrawer(deposit, owner_info.key).is_err()
+            || check_deposit_open(deposit).is_err()
+            || check_not_same_slot(deposit, clock.slot).is_err()
+            || check_unlocked(deposit, clock.unix_timestamp).is_err()
+            || check_unlock_slot_reached(deposit, clock.slot).is_err()
+            || check_withdraw_queue(deposit, false, withdraw_delay, clock.unix_timestamp).is_err()
+        {
+            continue;
+        }
+    
+        let payout = payouts.iter_mut().find(|(mint, _, destination_info, _)| {
+            *mint == deposit.token_mint && check_locked_destination(deposit, destination_info.key).is_ok()
+        });
+        let payout = match payout {
+            Some(payout) => payout,
+            None => {
+                skipped.push(deposit.id);
+                continue;
+            },
+        };
+        payout.3 = payout.3.checked_add(deposit.amount)
+            .ok_or(VaultError::MathOverflow)?;
+    
+        vault_event!(
+            b"withdraw",
+            deposit.id.to_le_bytes(),
+            deposit.amount.to_le_bytes(),
+            clock.unix_timestamp.to_le_bytes(),
+            [DepositStatus::Withdrawn as u8],
+        );
+        withdrawn.push(deposit.id);
+        vault.deposits[deposit_index].status = DepositStatus::Withdrawn as u8;
+        vault.deposits[deposit_index].withdrawn_at = Some(clock.unix_timestamp);
+        release_deposit_capacity(&mut vault, deposit_index)?;
+        update_stats_for_withdrawal(stats_info, &vault, deposit_index, clock.slot)?;
+    }
+    
+    // Drop the withdrawn deposits from the owner's position index
+    remove_from_position_index(program_id, vault_account_info.key, owner_info.key, position_index_info, &withdrawn)?;
+    
+    // Pay each mint out in one transfer, counted against the owner's rate
+    // limit window and less the protocol fee
+    for (mint, source_token_account_info, destination_token_account_info, amount) in &payouts {
+        if *amount == 0 {
+            continue;
+        }
+        debit_rate_limit(&mut vault, owner_info.key, *amount, clock.unix_timestamp)?;
+    
+        let fee = match protocol_fee_accounts {
+            Some((protocol_fee_bps, fee_destination_info)) => {
+                let fee = protocol_fee(*amount, protocol_fee_bps);
+                if fee > 0 {
+                    transfer_from_vault(
+                        vault_account_info,
+                        source_token_account_info,
+                        fee_destination_info,
+                        token_program_info,
+                        fee,
+                    )?;
+                }
+                fee
+            },
+            None => 0,
+        };
+        transfer_from_vault(
+            vault_account_info,
+            source_token_account_info,
+            destination_token_account_info,
+            token_program_info,
+            amount - fee,
+        )?;
+        vault_log!("Withdrew {} tokens of mint {} ({} protocol fee)", amount - fee, mint, fee);
+    }
+    
+    // Call the withdraw hook once the tokens have moved
+    call_withdraw_hook(&vault, vault_account_info, hook.as_ref(), &withdrawn)?;
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    // Report deposits left behind for lack of accounts
+    let skipped_data: Vec<u8> = skipped.iter().flat_map(|id| id.to_le_bytes()).collect();
+    set_return_data(&skipped_data);
+    if !skipped.is_empty() {
+        vault_log!("Skipped deposits without a matching token account pair: {:?}", skipped);
+    }
+    
+    if withdrawn.is_empty() {
+        vault_log!("No matured deposits matched the tag");
+    } else {
+        vault_log!("Withdrew {} deposits by tag", withdrawn.len());
+    }
+    Ok(())
+}
+
+// Record `amount` against the withdrawer's current rate limit window, rolling
+// the window over once it has expired
+pub(crate) fn debit_rate_limit(
+    vault: &mut Vault,
+    withdrawer: &Pubkey,
+    amount: u64,
+    now: i64,
+) -> Result<(), VaultError> {
+    let limit = match vault.rate_limit {
+        Some(limit) => limit,
+        None => return Ok(()),
+    };
+    
+    // Drop expired windows so the map only holds recent withdrawers
+    vault.withdrawal_windows
+        .retain(|w| now < w.window_start.saturating_add(limit.window_secs));
+    
+    let index = match vault.withdrawal_windows.iter().position(|w| w.withdrawer == *withdrawer) {
+        Some(index) => index,
+        None => {
+            vault.withdrawal_windows.push(WithdrawalWindow {
+                withdrawer: *withdrawer,
+                window_start: now,
+                withdrawn: 0,
+            });
+            vault.withdrawal_windows.len() - 1
+        },
+    };
+    let window = &mut vault.withdrawal_windows[index];
+    
+    let withdrawn = window.withdrawn.checked_add(amount)
+        .ok_or(VaultError::MathOverflow)?;
+    if withdrawn > limit.amount {
+        return Err(VaultError::RateLimited);
+    }
+    window.withdrawn = withdrawn;
+    Ok(())
+}
+
+// Fail if the deposit was made in the current slot, so deposits can't be
+// withdrawn atomically in the same transaction; slot zero marks deposits
+// recorded before the slot was tracked
+fn check_not_same_slot(deposit: &Deposit, slot: u64) -> ProgramResult {
+    if deposit.last_action_slot != 0 && deposit.last_action_slot == slot {
+        return Err(VaultError::SameSlotAction.into());
+    }
+    Ok(())
+}
+
+// Fail if a deposit with a locked destination would pay out anywhere else
+pub(crate) fn check_locked_destination(deposit: &Deposit, destination: &Pubkey) -> Result<(), VaultError> {
+    match deposit.locked_destination {
+        Some(locked) if locked != *destination => Err(VaultError::InvalidDestinationAccount),
+        _ => Ok(()),
+    }
+}
+
+// Fail unless `withdrawer` is the depositor or the current beneficiary of a
+// deposit that isn't represented by a position NFT
+pub(crate) fn check_withdrawer(deposit: &Deposit, withdrawer: &Pubkey) -> Result<(), VaultError> {
+    // The beneficiary of a hashlocked deposit is only paid through the preimage
+    let beneficiary = if deposit.hashlock.is_some() { None } else { deposit.beneficiary };
+    if deposit.depositor != *withdrawer && beneficiary != Some(*withdrawer) {
+        return Err(VaultError::UnauthorizedWithdrawal);
+    }
+    Ok(())
+}
+
+// Fail if the deposit was already paid out or is frozen
+pub(crate) fn check_deposit_open(deposit: &Deposit) -> Result<(), VaultError> {
+    if deposit.is_withdrawn() {
+        return Err(VaultError::AlreadyWithdrawn);
+    }
+    if deposit.frozen {
+        return Err(VaultError::DepositFrozen);
+    }
+    Ok(())
+}
+
+// Fail if the deposit's unlock time is still ahead
+pub(crate) fn check_unlocked(deposit: &Deposit, now: i64) -> Result<(), VaultError> {
+    if deposit.unlock_time > now {
+        return Err(VaultError::UnlockTimeNotReached);
+    }
+    Ok(())
+}
+
+// Fail unless `data` holds the condition's expected bytes at its offset; data
+// too short for the comparison (such as a closed account) fails without
+// matching
+pub(crate) fn check_account_condition(condition: &AccountCondition, data: &[u8]) -> Result<(), VaultError> {
+    let start = condition.offset as usize;
+    let actual = start
+        .checked_add(condition.expected.len())
+        .and_then(|end| data.get(start..end))
+        .ok_or(VaultError::ConditionOutOfRange)?;
+    if actual != condition.expected.as_slice() {
+        return Err(VaultError::AccountConditionNotMet);
+    }
+    Ok(())
+}
+
+// Fail unless a deposit's guardians are distinct, exclude the depositor, fit
+// in `MAX_GUARDIANS` and can reach a threshold of at least one
+fn check_guardians(guardians: &[Pubkey], threshold: u8, depositor: &Pubkey) -> Result<(), VaultError> {
+    if guardians.is_empty() {
+        return if threshold == 0 { Ok(()) } else { Err(VaultError::InvalidGuardians) };
+    }
+    let distinct = guardians.iter().enumerate().all(|(i, guardian)| !guardians[..i].contains(guardian));
+    if guardians.len() > MAX_GUARDIANS
+        || !distinct
+        || guardians.contains(depositor)
+        || threshold == 0
+        || threshold as usize > guardians.len()
+    {
+        return Err(VaultError::InvalidGuardians);
+    }
+    Ok(())
+}
+
+// Fail unless `signer` is one of the deposit's guardians
+fn check_guardian(deposit: &Deposit, signer: &Pubkey) -> Result<(), VaultError> {
+    if !deposit.guardians.contains(signer) {
+        return Err(VaultError::NotGuardian);
+    }
+    Ok(())
+}
+
+// Fail if a slot-locked deposit's unlock slot hasn't been reached
+pub(crate) fn check_unlock_slot_reached(deposit: &Deposit, slot: u64) -> Result<(), VaultError> {
+    if deposit.unlock_slot.is_some_and(|unlock_slot| unlock_slot > slot) {
+        return Err(VaultError::UnlockTimeNotReached);
+    }
+    Ok(())
+}
+
+// Fail unless a queued deposit is claimed after its withdraw delay, or an
+// unqueued deposit is withdrawn directly
+pub(crate) fn check_withdraw_queue(
+    deposit: &Deposit,
+    claim: bool,
+    withdraw_delay: i64,
+    now: i64,
+) -> Result<(), VaultError> {
+    if !deposit.queued_withdrawals {
+        return if claim { Err(VaultError::NoPendingWithdrawal) } else { Ok(()) };
+    }
+    if !claim {
+        return Err(VaultError::WithdrawalQueueRequired);
+    }
+    let requested_at = deposit.withdraw_requested_at
+        .ok_or(VaultError::NoPendingWithdrawal)?;
+    let claimable_at = requested_at.checked_add(withdraw_delay)
+        .ok_or(VaultError::MathOverflow)?;
+    if claimable_at > now {
+        return Err(VaultError::WithdrawalDelayActive);
+    }
+    Ok(())
+}
+
+// Fail if the recorded depositor isn't allowed into the vault, or if either
+// the depositor or the funder is blocked
+pub(crate) fn check_depositor_admitted(vault: &Vault, depositor: &Pubkey, funder: &Pubkey) -> Result<(), VaultError> {
+    if vault.deposit_policy == DepositPolicy::Allowlist && !vault.allowed_depositors.contains(depositor) {
+        return Err(VaultError::DepositorNotAllowed);
+    }
+    if vault.blocked_depositors.binary_search(depositor).is_ok()
+        || vault.blocked_depositors.binary_search(funder).is_ok()
+    {
+        return Err(VaultError::DepositorBlocked);
+    }
+    Ok(())
+}
+
+// Fail if the owner closed the vault to new deposits
+pub(crate) fn check_deposits_open(vault: &Vault) -> Result<(), VaultError> {
+    if !vault.accepting_deposits() {
+        return Err(VaultError::DepositsClosed);
+    }
+    Ok(())
+}
+
+// Fail if `depositor` already used `key` in one of the vault's recent keyed
+// deposits, otherwise remember it, overwriting the oldest key once the ring
+// buffer is full
+fn record_idempotency_key(vault: &mut Vault, depositor: &Pubkey, key: [u8; 16]) -> Result<(), VaultError> {
+    let entry = IdempotencyKey { depositor: *depositor, key };
+    if vault.idempotency_keys.contains(&entry) {
+        return Err(VaultError::DuplicateDeposit);
+    }
+    if vault.idempotency_keys.len() < MAX_IDEMPOTENCY_KEYS {
+        vault.idempotency_keys.push(entry);
+    } else {
+        let slot = vault.idempotency_cursor as usize % MAX_IDEMPOTENCY_KEYS;
+        vault.idempotency_keys[slot] = entry;
+        vault.idempotency_cursor = ((slot + 1) % MAX_IDEMPOTENCY_KEYS) as u8;
+    }
+    Ok(())
+}
+
+// Fail if a deposit moves no tokens
+pub(crate) fn check_deposit_amount(amount: u64) -> Result<(), VaultError> {
+    if amount == 0 {
+        return Err(VaultError::InvalidAmount);
+    }
+    Ok(())
+}
+
+// Fail if a new deposit would already be unlocked
+pub(crate) fn check_unlock_in_future(unlock_time: i64, now: i64) -> Result<(), VaultError> {
+    if unlock_time <= now {
+        return Err(VaultError::InvalidUnlockTime);
+    }
+    Ok(())
+}
+
+// Fail if a new slot-locked deposit wouldn't stay locked for at least
+// `min_lock_slots` (and at least one slot)
+pub(crate) fn check_unlock_slot_in_future(unlock_slot: u64, slot: u64, min_lock_slots: u64) -> Result<(), VaultError> {
+    if unlock_slot <= slot || unlock_slot - slot < min_lock_slots {
+        return Err(VaultError::InvalidUnlockTime);
+    }
+    Ok(())
+}
+
+// Fail if the source token account can't cover the deposit
+pub(crate) fn check_source_balance(balance: u64, amount: u64) -> Result<(), VaultError> {
+    if balance < amount {
+        return Err(VaultError::InsufficientFunds);
+    }
+    Ok(())
+}
+
+// Fail unless `delegate` is approved on the source token account for at
+// least `amount`
+pub(crate) fn check_delegated_source(
+    source: &TokenAccount,
+    delegate: &Pubkey,
+    amount: u64,
+) -> Result<(), ProgramError> {
+    if source.delegate != COption::Some(*delegate) {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if source.delegated_amount < amount {
+        return Err(VaultError::InsufficientDelegation.into());
+    }
+    Ok(())
+}
+
+// Fail for staked deposits, whose lamports sit in a stake account rather
+// than in a vault token account
+pub(crate) fn check_token_deposit(deposit: &Deposit) -> Result<(), VaultError> {
+    if deposit.stake_account.is_some() {
+        return Err(VaultError::StakedDeposit);
+    }
+    Ok(())
+}
+
+// Fail if a payout token account holds a different mint than the deposit
+pub(crate) fn check_payout_mint(deposit: &Deposit, mint: &Pubkey) -> Result<(), VaultError> {
+    if deposit.token_mint != *mint {
+        return Err(VaultError::TokenMintMismatch);
+    }
+    Ok(())
+}
+
+// Fail if a token account's state is frozen
+pub(crate) fn check_token_state_not_frozen(state: AccountState) -> Result<(), VaultError> {
+    if state == AccountState::Frozen {
+        return Err(VaultError::TokenAccountFrozen);
+    }
+    Ok(())
+}
+
+// Fail if a third party acts on a matured deposit before the post-unlock grace
+// period has passed; locked deposits are unaffected
+fn check_post_unlock_grace(post_unlock_grace: i64, deposit: &Deposit, now: i64) -> ProgramResult {
+    if post_unlock_grace > 0
+        && now >= deposit.unlock_time
+        && now <= deposit.unlock_time.saturating_add(post_unlock_grace)
+    {
+        return Err(VaultError::GracePeriodActive.into());
+    }
+    Ok(())
+}
+
+// Find or start the accounting entry for `mint`
+fn mint_entry<'v>(vault: &'v mut Vault, mint: &Pubkey) -> Result<&'v mut MintEntry, VaultError> {
+    let index = match vault.mints.iter().position(|entry| entry.mint == *mint) {
+        Some(index) => index,
+        None => {
+            if vault.mints.len() >= MAX_VAULT_MINTS {
+                return Err(VaultError::TooManyMints);
+            }
+            vault.mints.push(MintEntry {
+                mint: *mint,
+                ..MintEntry::default()
+            });
+            vault.mints.len() - 1
+        },
+    };
+    Ok(&mut vault.mints[index])
+}
+
+// Fail with `MintStillActive` while an open deposit or the mint's entry
+// holds tokens of `mint`
+fn check_mint_inactive(vault: &Vault, mint: &Pubkey) -> Result<(), VaultError> {
+    let outstanding = vault.mints.iter().find(|entry| entry.mint == *mint).map_or(0, |entry| entry.outstanding);
+    if outstanding > 0 || vault.deposits.iter().any(|deposit| deposit.token_mint == *mint && !deposit.is_withdrawn()) {
+        return Err(VaultError::MintStillActive);
+    }
+    Ok(())
+}
+
+// Drop a closed mint's entry; a mint limit the owner set is kept
+fn remove_mint_entry(vault: &mut Vault, mint: &Pubkey) {
+    vault.mints.retain(|entry| entry.mint != *mint || entry.max_outstanding.is_some());
+}
+
+// Drop entries with nothing outstanding and no limit
+fn prune_mint_entries(vault: &mut Vault) {
+    vault.mints.retain(|entry| entry.outstanding > 0 || entry.max_outstanding.is_some());
+}
+
+// Add a new deposit of `amount` to the vault's and its mint's outstanding and lifetime
+// totals, enforcing the TVL cap and the mint limit
+pub(crate) fn reserve_deposit_capacity(vault: &mut Vault, mint: &Pubkey, amount: u64) -> Result<(), VaultError> {
+    reserve_capacity(vault, mint, amount)?;
+    vault.lifetime_deposits_created = vault.lifetime_deposits_created.checked_add(1)
+        .ok_or(VaultError::MathOverflow)?;
+    Ok(())
+}
+
+// Add `amount` to the vault's and its mint's outstanding and lifetime totals,
+// enforcing the TVL cap and the mint limit, without counting a new deposit
+fn reserve_capacity(vault: &mut Vault, mint: &Pubkey, amount: u64) -> Result<(), VaultError> {
+    let total_locked = vault.total_locked.checked_add(amount)
+        .ok_or(VaultError::MathOverflow)?;
+    if let Some(cap) = vault.tvl_cap {
+        if total_locked > cap {
+            return Err(VaultError::VaultCapReached);
+        }
+    }
+    
+    let entry = mint_entry(vault, mint)?;
+    let outstanding = entry.outstanding.checked_add(amount)
+        .ok_or(VaultError::MathOverflow)?;
+    if let Some(limit) = entry.max_outstanding {
+        if outstanding > limit {
+            return Err(VaultError::MintLimitExceeded);
+        }
+    }
+    entry.outstanding = outstanding;
+    entry.lifetime_deposited = entry.lifetime_deposited.checked_add(amount as u128)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.total_locked = total_locked;
+    vault.lifetime_amount_deposited = vault.lifetime_amount_deposited.checked_add(amount as u128)
+        .ok_or(VaultError::MathOverflow)?;
+    Ok(())
+}
+
+/// Round `unlock_time` up to the next multiple of `granularity` seconds,
+/// moving on to the following multiple if that isn't after `now`; a
+/// granularity of zero leaves the time as is
+pub fn round_unlock_time(unlock_time: i64, granularity: i64, now: i64) -> Result<i64, VaultError> {
+    if granularity <= 0 {
+        return Ok(unlock_time);
+    }
+    let round_up = |time: i64| match time.rem_euclid(granularity) {
+        0 => Some(time),
+        remainder => time.checked_add(granularity - remainder),
+    };
+    let mut rounded = round_up(unlock_time).ok_or(VaultError::MathOverflow)?;
+    if rounded <= now {
+        rounded = round_up(now.checked_add(1).ok_or(VaultError::MathOverflow)?).ok_or(VaultError::MathOverflow)?;
+    }
+    Ok(rounded)
+}
+
+// Fail while the vault-wide cooldown since the last emergency withdrawal
+// runs, otherwise count a new one at `now`
+fn record_emergency_withdrawal(vault: &mut Vault, now: i64) -> Result<(), VaultError> {
+    if vault.emergency_cooldown > 0
+        && vault.last_emergency_withdraw != 0
+        && now < vault.last_emergency_withdraw.saturating_add(vault.emergency_cooldown)
+    {
+        return Err(VaultError::EmergencyCooldownActive);
+    }
+    vault.last_emergency_withdraw = now;
+    vault.lifetime_emergency_withdrawals = vault.lifetime_emergency_withdrawals.checked_add(1)
+        .ok_or(VaultError::MathOverflow)?;
+    Ok(())
+}
+
+// With `unique_tags`, fail if `depositor` already has an active deposit
+// tagged `tag`. A tag without a `tag_totals` entry has no active deposit
+// unless the other bucket holds some, so most new tags skip the scan
+fn check_unique_tag(vault: &Vault, depositor: &Pubkey, tag: &[u8; 32]) -> Result<(), VaultError> {
+    if !vault.unique_tags || *tag == [0; 32] {
+        return Ok(());
+    }
+    if vault.total_for_tag(tag).is_none() && vault.other_tags_total == 0 {
+        return Ok(());
+    }
+    let duplicate = vault.deposits.iter()
+        .any(|deposit| deposit.tag == *tag && deposit.depositor == *depositor && !deposit.is_withdrawn());
+    if duplicate {
+        return Err(VaultError::DuplicateTag);
+    }
+    Ok(())
+}
+
+// Add a new deposit of `amount` to its tag's total. Untagged deposits aren't
+// counted; once `MAX_TRACKED_TAGS` tags are tracked, new tags go to the other
+// bucket until an entry frees up
+fn add_tag_total(vault: &mut Vault, tag: &[u8; 32], amount: u64) -> Result<(), VaultError> {
+    if *tag == [0; 32] {
+        return Ok(());
+    }
+    
+    if let Some(entry) = vault.tag_totals.iter_mut().find(|entry| entry.tag == *tag) {
+        entry.total = entry.total.checked_add(amount)
+            .ok_or(VaultError::MathOverflow)?;
+        return Ok(());
+    }
+    
+    if vault.tag_totals.len() >= MAX_TRACKED_TAGS {
+        vault.other_tags_total = vault.other_tags_total.checked_add(amount)
+            .ok_or(VaultError::MathOverflow)?;
+        return Ok(());
+    }
+    
+    // Move active deposits the tag left in the other bucket into its new
+    // entry, so a tag is always counted in exactly one place
+    let moved = vault.deposits.iter()
+        .filter(|deposit| deposit.tag == *tag && !deposit.is_withdrawn())
+        .try_fold(0u64, |sum, deposit| sum.checked_add(deposit.amount))
+        .ok_or(VaultError::MathOverflow)?;
+    let total = moved.checked_add(amount)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.other_tags_total = vault.other_tags_total.saturating_sub(moved);
+    vault.tag_totals.push(TagTotal { tag: *tag, total });
+    Ok(())
+}
+
+// Release the full amount of a withdrawn deposit from the vault's, its
+// mint's and its tag's outstanding totals and count it as withdrawn
+fn release_deposit_capacity(vault: &mut Vault, deposit_index: usize) -> Result<(), VaultError> {
+    let deposit = &vault.deposits[deposit_index];
+    let (mint, amount, tag) = (deposit.token_mint, deposit.amount, deposit.tag);
+    vault.total_locked = vault.total_locked.saturating_sub(amount);
+    vault.lifetime_amount_withdrawn = vault.lifetime_amount_withdrawn.checked_add(amount as u128)
+        .ok_or(VaultError::MathOverflow)?;
+    if let Some(entry) = vault.mints.iter_mut().find(|entry| entry.mint == mint) {
+        // Deposits made before per-mint accounting aren't counted
+        entry.outstanding = entry.outstanding.saturating_sub(amount);
+    }
+    prune_mint_entries(vault);
+    
+    // Deposits made before per-tag accounting aren't counted either
+    if tag != [0; 32] {
+        match vault.tag_totals.iter().position(|entry| entry.tag == tag) {
+            Some(index) => {
+                let entry = &mut vault.tag_totals[index];
+                entry.total = entry.total.saturating_sub(amount);
+                if entry.total == 0 {
+                    vault.tag_totals.remove(index);
+                }
+            },
+            None => vault.other_tags_total = vault.other_tags_total.saturating_sub(amount),
+        }
+    }
+    Ok(())
+}
+
+// Count the full amount of a withdrawn deposit as paid out in the program
+// stats, if supplied, the way `release_deposit_capacity` counts it in the vault
+fn update_stats_for_withdrawal(
+    stats_info: Option<&AccountInfo>,
+    vault: &Vault,
+    deposit_index: usize,
+    slot: u64,
+) -> ProgramResult {
+    let deposit = &vault.deposits[deposit_index];
+    let (mint, amount) = (deposit.token_mint, deposit.amount as u128);
+    update_program_stats(stats_info, vault, slot, |stats| {
+        stats.amount_withdrawn = stats.amount_withdrawn.saturating_add(amount);
+        stats.remove_locked(&mint, amount);
+    })
+}
+
+// Process revoke vault delegate instruction
+fn process_revoke_vault_delegate(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    process_vault_token_fix(program_id, accounts, |token_program, vault_token_account, vault| {
+        spl_token::instruction::revoke(token_program, vault_token_account, vault, &[])
+    })?;
+    
+    vault_log!("Delegate revoked on vault token account {}", accounts[2].key);
+    Ok(())
+}
+
+// Process set vault token close authority instruction
+fn process_set_vault_token_close_authority(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    process_vault_token_fix(program_id, accounts, |token_program, vault_token_account, vault| {
+        spl_token::instruction::set_authority(
+            token_program,
+            vault_token_account,
+            None,
+            AuthorityType::CloseAccount,
+            vault,
+            &[],
+        )
+    })?;
+    
+    vault_log!("Close authority cleared on vault token account {}", accounts[2].key);
+    Ok(())
+}
+
+// Verify the owner signed for a vault token account remediation and issue the
+// token instruction built by `build(token_program, vault_token_account, vault)`,
+// signing as the vault
+fn process_vault_token_fix<F>(program_id: &Pubkey, accounts: &[AccountInfo], build: F) -> ProgramResult
+where
+    F: FnOnce(&Pubkey, &Pubkey, &Pubkey) -> Result<Instruction, ProgramError>,
+{
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let vault_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the owner signed the transaction
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify the signer owns the vault and still holds its powers
+    let vault = load_vault(vault_account_info)?;
+    require_role(&vault, accounts, OWNER_ONLY)?;
+    
+    // Verify the token account belongs to the vault
+    let vault_token_account = TokenAccount::unpack(&vault_token_account_info.data.borrow())?;
+    if vault_token_account.owner != *vault_account_info.key {
+        return Err(ProgramError::IllegalOwner);
+    }
+    
+    invoke_signed(
+        &build(token_program_info.key, vault_token_account_info.key, vault_account_info.key)?,
+        &[
+            vault_token_account_info.clone(),
+            vault_account_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&[&vault_account_info.key.to_bytes(), &[0]]],
+    )
+}
+
+// Process sweep surplus instruction
+fn process_sweep_surplus(program_id: &Pubkey, accounts: &[AccountInfo], mint: Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let vault_token_account_info = next_account_info(account_info_iter)?;
+    let destination_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the owner signed the transaction
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify the signer owns the vault, or holds the role, and still holds its powers
+    let vault = load_vault(vault_account_info)?;
+    require_role(&vault, accounts, CAN_SWEEP_SURPLUS)?;
+    
+    // Verify the token account belongs to the vault and holds the mint
+    let vault_token_account = TokenAccount::unpack(&vault_token_account_info.data.borrow())?;
+    if vault_token_account.owner != *vault_account_info.key {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if vault_token_account.mint != mint {
+        return Err(VaultError::TokenMintMismatch.into());
+    }
+    
+    let surplus = vault_surplus(&vault, &mint, vault_token_account.amount)?;
+    transfer_from_vault(
+        vault_account_info,
+        vault_token_account_info,
+        destination_token_account_info,
+        token_program_info,
+        surplus,
+    )?;
+    
+    vault_event!(b"sweep", mint, surplus.to_le_bytes());
+    vault_log!("Swept {} surplus tokens of mint {}", surplus, mint);
+    Ok(())
+}
+
+// Process sweep lamports instruction
+fn process_sweep_lamports(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    
+    // Verify the owner signed the transaction
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify the signer owns the vault, or holds the role, and still holds its powers
+    let vault = load_vault(vault_account_info)?;
+    require_role(&vault, accounts, CAN_SWEEP_SURPLUS)?;
+    
+    // Sending the lamports back to the vault would only hide them again
+    if destination_info.key == vault_account_info.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    // Keep the vault rent exempt
+    let rent_minimum = Rent::get()?.minimum_balance(vault_account_info.data_len());
+    let excess = vault_account_info.lamports().saturating_sub(rent_minimum);
+    if excess == 0 {
+        vault_log!("No excess lamports to sweep");
+        return Ok(());
+    }
+    
+    **destination_info.lamports.borrow_mut() = destination_info
+        .lamports()
+        .checked_add(excess)
+        .ok_or(VaultError::MathOverflow)?;
+    **vault_account_info.lamports.borrow_mut() -= excess;
+    
+    vault_log!("Swept {} excess lamports to {}", excess, destination_info.key);
+    Ok(())
+}
+
+// Process gc deposits instruction
+fn process_gc_deposits(program_id: &Pubkey, accounts: &[AccountInfo], max: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let caller_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    
+    // Verify the caller signed the transaction
+    if !caller_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    let gc_delay = vault.gc_delay.ok_or(VaultError::GcDisabled)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    
+    // Remove records withdrawn long enough ago; open deposits, records
+    // without a withdrawal time and records with unclaimed rewards are always kept
+    let mut removed = 0usize;
+    vault.deposits.retain(|deposit| {
+        let collectable = deposit.is_withdrawn()
+            && deposit.pending_rewards == 0
+            && deposit.withdrawn_at.is_some_and(|at| clock.unix_timestamp >= at.saturating_add(gc_delay));
+        if collectable && removed < max as usize {
+            removed += 1;
+            return false;
+        }
+        true
+    });
+    if removed == 0 {
+        vault_log!("No deposit records to collect");
+        return Ok(());
+    }
+    
+    // Pay the bounty out of the vault's lamports above its rent-exempt minimum
+    let rent_minimum = Rent::get()?.minimum_balance(vault_account_info.data_len());
+    let bounty = gc_bounty(removed, vault_account_info.lamports().saturating_sub(rent_minimum));
+    **caller_info.lamports.borrow_mut() = caller_info
+        .lamports()
+        .checked_add(bounty)
+        .ok_or(VaultError::MathOverflow)?;
+    **vault_account_info.lamports.borrow_mut() -= bounty;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(b"gc", (removed as u64).to_le_bytes(), bounty.to_le_bytes());
+    vault_log!("Collected {} deposit records for a {} lamport bounty", removed, bounty);
+    Ok(())
+}
+
+// Process write snapshot instruction
+fn process_write_snapshot(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let caller_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let snapshot_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    
+    // Verify the caller signed the transaction
+    if !caller_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify the snapshot address
+    let (snapshot_key, bump) = find_snapshot_address(vault_account_info.key, program_id);
+    if snapshot_key != *snapshot_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    
+    // Load the vault and summarize it
+    let vault = load_vault(vault_account_info)?;
+    let clock = Clock::get()?;
+    let snapshot = snapshot_vault(&vault, vault_account_info.key, &clock)?;
+    
+    // Create the snapshot on first use, funded by the caller
+    if snapshot_info.owner != program_id {
+        if *system_program_info.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                caller_info.key,
+                snapshot_info.key,
+                rent.minimum_balance(VaultSnapshot::LEN),
+                VaultSnapshot::LEN as u64,
+                program_id,
+            ),
+            &[
+                caller_info.clone(),
+                snapshot_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[SNAPSHOT_SEED, vault_account_info.key.as_ref(), &[bump]]],
+        )?;
+    }
+    
+    // Overwrite the previous snapshot
+    snapshot.serialize(&mut &mut snapshot_info.data.borrow_mut()[..])?;
+    
+    vault_log!(
+        "Snapshot of {} written at slot {}: {} active deposits",
+        vault_account_info.key,
+        clock.slot,
+        snapshot.active_deposits
+    );
+    Ok(())
+}
+
+// Aggregate the vault: counters are copied as they are, and what the vault
+// keeps no counter for comes from a scan of at most `MAX_SNAPSHOT_SCAN`
+// deposit records. Slot-locked deposits have no unlock time and are left out
+// of the unlock range
+fn snapshot_vault(vault: &Vault, vault_key: &Pubkey, clock: &Clock) -> Result<VaultSnapshot, VaultError> {
+    if vault.deposits.len() > MAX_SNAPSHOT_SCAN {
+        return Err(VaultError::VaultTooLargeToScan);
+    }
+    
+    let mut snapshot = VaultSnapshot {
+        vault: *vault_key,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+        total_locked: vault.total_locked,
+        outstanding: vault.mints.iter().map(|entry| (entry.mint, entry.outstanding)).collect(),
+        lifetime_deposits_created: vault.lifetime_deposits_created,
+        lifetime_amount_deposited: vault.lifetime_amount_deposited,
+        lifetime_amount_withdrawn: vault.lifetime_amount_withdrawn,
+        lifetime_emergency_withdrawals: vault.lifetime_emergency_withdrawals,
+        ..VaultSnapshot::default()
+    };
+    snapshot.active_deposits = vault.deposits.iter().filter(|deposit| !deposit.is_withdrawn()).count() as u64;
+    let unlock_order = vault.unlock_order();
+    snapshot.earliest_unlock = unlock_order.first().map(|deposit| deposit.unlock_time);
+    snapshot.latest_unlock = unlock_order.last().map(|deposit| deposit.unlock_time);
+    Ok(snapshot)
+}
+
+// Process reconcile stats instruction
+fn process_reconcile_stats(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let caller_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let stats_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    
+    // Verify the caller signed the transaction
+    checks::signer(accounts, caller_info, "caller")?;
+    
+    // Verify the vault account is owned by the program and load it
+    let mut vault = checks::vault(accounts, vault_account_info, program_id)?;
+    
+    // A vault is folded in exactly once; afterwards its activity updates the
+    // stats directly
+    if vault.stats_reconciled {
+        return Err(VaultError::StatsAlreadyReconciled.into());
+    }
+    
+    // Verify the stats address
+    let (stats_key, bump) = find_stats_address(program_id);
+    if stats_key != *stats_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    
+    // Create the stats on first use, funded by the caller
+    let mut stats = if stats_info.owner != program_id {
+        checks::system_program(accounts, system_program_info)?;
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                caller_info.key,
+                stats_info.key,
+                rent.minimum_balance(ProgramStats::LEN),
+                ProgramStats::LEN as u64,
+                program_id,
+            ),
+            &[
+                caller_info.clone(),
+                stats_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&[STATS_SEED, &[bump]]],
+        )?;
+        ProgramStats::default()
+    } else {
+        ProgramStats::deserialize(&mut &stats_info.data.borrow()[..])?
+    };
+    
+    // Fold in the vault's lifetime counters and what it still holds per mint
+    stats.vaults_counted = stats.vaults_counted.saturating_add(1);
+    stats.deposits_created = stats.deposits_created.saturating_add(vault.lifetime_deposits_created);
+    stats.amount_deposited = stats.amount_deposited.saturating_add(vault.lifetime_amount_deposited);
+    stats.amount_withdrawn = stats.amount_withdrawn.saturating_add(vault.lifetime_amount_withdrawn);
+    for entry in &vault.mints {
+        stats.add_locked(&entry.mint, entry.outstanding as u128);
+    }
+    stats.last_updated_slot = Clock::get()?.slot;
+    vault.stats_reconciled = true;
+    
+    // Serialize and store the stats and the vault
+    stats.serialize(&mut &mut stats_info.data.borrow_mut()[..])?;
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(b"stats_reconciled", vault_account_info.key);
+    vault_log!("Vault {} folded into the program stats", vault_account_info.key);
+    Ok(())
+}
+
+// Process log voting power instruction
+fn process_log_voting_power(program_id: &Pubkey, accounts: &[AccountInfo], depositor: &Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    
+    // Verify the vault account is owned by the program and load it
+    let vault = checks::vault(accounts, vault_account_info, program_id)?;
+    let clock = checks::clock(accounts, clock_sysvar_info)?;
+    
+    let power = vault.total_voting_power(depositor, clock.unix_timestamp, VOTING_POWER_MAX_LOCK);
+    set_return_data(&power.to_le_bytes());
+    vault_event!(b"voting_power", depositor, power.to_le_bytes());
+    vault_log!("Voting power of {}: {}", depositor, power);
+    Ok(())
+}
+
+/// Bounty for removing `removed` deposit records when `available` lamports
+/// sit above the vault's rent-exempt minimum
+pub fn gc_bounty(removed: usize, available: u64) -> u64 {
+    (removed as u64).saturating_mul(GC_BOUNTY_LAMPORTS).min(available)
+}
+
+// Balance of a vault token account of `mint` beyond what the vault holds for
+// the mint's active deposits. The per-mint counter misses deposits made before
+// per-mint accounting, so the larger of it and a scan of the deposits counts
+// as held; any other vault token accounts of the mint only add to the real
+// surplus
+fn vault_surplus(vault: &Vault, mint: &Pubkey, balance: u64) -> Result<u64, VaultError> {
+    let counted = vault.mints.iter()
+        .find(|entry| entry.mint == *mint)
+        .map_or(0, |entry| entry.outstanding as u128);
+    let scanned: u128 = vault.deposits.iter()
+        .filter(|deposit| !deposit.is_withdrawn() && deposit.token_mint == *mint)
+        .map(|deposit| deposit.amount as u128)
+        .sum();
+    let held = counted.max(scanned) + rewards_held(vault, mint);
+    
+    let balance = balance as u128;
+    if balance < held {
+        vault_log!("Vault token account holds {} but deposits need {}", balance, held);
+        return Err(VaultError::NoSurplus);
+    }
+    match u64::try_from(balance - held) {
+        Ok(0) => Err(VaultError::NoSurplus),
+        Ok(surplus) => Ok(surplus),
+        Err(_) => Err(VaultError::MathOverflow),
+    }
+}
+
+// Fail with a clear error if the mint's freeze authority froze a token account
+// the instruction is about to move tokens through; other account problems are
+// left to the token program
+// Reward tokens of `mint` the vault owes: pending rewards, the carry and the
+// unallocated part of a distribution in progress
+fn rewards_held(vault: &Vault, mint: &Pubkey) -> u128 {
+    if vault.reward_mint != Some(*mint) {
+        return 0;
+    }
+    let pending: u128 = vault.deposits.iter().map(|deposit| deposit.pending_rewards as u128).sum();
+    let unallocated = vault.reward_distribution.as_ref()
+        .map_or(0, |distribution| distribution.total - distribution.distributed);
+    pending + vault.reward_carry as u128 + unallocated as u128
+}
+
+// Share of `total` earned by `weight` out of `total_weight`, rounded down
+pub(crate) fn reward_share(total: u64, weight: u64, total_weight: u128) -> u64 {
+    (total as u128 * weight as u128 / total_weight) as u64
+}
+
+// Fail if anyone other than the vault could close one of its token accounts
+fn check_vault_close_authority(vault_key: &Pubkey, vault_token_account: &TokenAccount) -> ProgramResult {
+    match vault_token_account.close_authority {
+        COption::Some(close_authority) if close_authority != *vault_key => {
+            Err(VaultError::InvalidVaultTokenAccount.into())
+        },
+        _ => Ok(()),
+    }
+}
+
+// Process claim with preimage instruction
+fn process_claim_with_preimage(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+    preimage: [u8; 32],
+) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let destination_token_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify neither token account is frozen by the mint's freeze authority
+    checks::not_frozen(accounts, destination_token_account_info, "destination token account")?;
+    checks::not_frozen(accounts, source_token_account_info, "vault token account")?;
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Set the withdraw hook accounts aside, so the optional accounts before
+    // them are read as usual
+    let (optional_accounts, hook) = split_withdraw_hook(&vault, account_info_iter.as_slice())?;
+    let account_info_iter = &mut optional_accounts.iter();
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Find the deposit
+    let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    let deposit = &mut vault.deposits[deposit_index];
+    check_token_deposit(deposit)?;
+    
+    // Verify the deposit has not been withdrawn (claimed or refunded) and is not frozen
+    check_deposit_open(deposit)?;
+    
+    // Verify the preimage hashes to the hashlock
+    let hashlock = deposit.hashlock.ok_or(VaultError::InvalidPreimage)?;
+    if !hashes_equal(&hash(&preimage).to_bytes(), &hashlock) {
+        return Err(VaultError::InvalidPreimage.into());
+    }
+    
+    // Verify the payout goes to the beneficiary in the deposit's mint
+    let destination = TokenAccount::unpack(&destination_token_account_info.data.borrow())?;
+    if deposit.beneficiary != Some(destination.owner) {
+        return Err(VaultError::InvalidDestinationAccount.into());
+    }
+    check_payout_mint(deposit, &destination.mint)?;
+    
+    // Mark the deposit as withdrawn
+    deposit.status = DepositStatus::Withdrawn as u8;
+    let withdrawn_at = Clock::get()?.unix_timestamp;
+    deposit.withdrawn_at = Some(withdrawn_at);
+    let amount = deposit.amount;
+    
+    // Free the deposit's share of the vault and mint limits
+    release_deposit_capacity(&mut vault, deposit_index)?;
+    update_stats_for_withdrawal(stats_info, &vault, deposit_index, Clock::get()?.slot)?;
+    
+    // Drop the deposit from its depositor's position index
+    remove_from_position_index(
+        program_id,
+        vault_account_info.key,
+        &vault.deposits[deposit_index].depositor,
+        position_index_info,
+        &[deposit_id],
+    )?;
+    
+    // Transfer tokens from the vault to the beneficiary
+    transfer_from_vault(
+        vault_account_info,
+        source_token_account_info,
+        destination_token_account_info,
+        token_program_info,
+        amount,
+    )?;
+    
+    // Call the withdraw hook once the tokens have moved
+    call_withdraw_hook(&vault, vault_account_info, hook.as_ref(), &[deposit_id])?;
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(
+        b"withdraw",
+        deposit_id.to_le_bytes(),
+        amount.to_le_bytes(),
+        withdrawn_at.to_le_bytes(),
+        [DepositStatus::Withdrawn as u8],
+    );
+    vault_log!("Hashlocked deposit {} claimed: {} tokens", deposit_id, amount);
+    Ok(())
+}
+
+// Process release escrow instruction
+fn process_release_escrow(program_id: &Pubkey, accounts: &[AccountInfo], deposit_id: u64) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let depositor_info = next_account_info(account_info_iter)?;
+    let counterparty_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let destination_token_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify both parties signed the transaction
+    checks::signer(accounts, depositor_info, "depositor")?;
+    checks::signer(accounts, counterparty_info, "counterparty")?;
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify neither token account is frozen by the mint's freeze authority
+    checks::not_frozen(accounts, destination_token_account_info, "destination token account")?;
+    checks::not_frozen(accounts, source_token_account_info, "vault token account")?;
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Set the withdraw hook accounts aside, so the optional accounts before
+    // them are read as usual
+    let (optional_accounts, hook) = split_withdraw_hook(&vault, account_info_iter.as_slice())?;
+    let account_info_iter = &mut optional_accounts.iter();
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Find the deposit
+    let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    let deposit = &mut vault.deposits[deposit_index];
+    check_token_deposit(deposit)?;
+    
+    // Verify the signers are the escrow's two parties
+    if deposit.escrow_counterparty != Some(*counterparty_info.key) {
+        return Err(VaultError::NotEscrowDeposit.into());
+    }
+    if deposit.depositor != *depositor_info.key {
+        return Err(VaultError::UnauthorizedWithdrawal.into());
+    }
+    
+    // Verify the deposit has not been withdrawn (released or refunded) and is not frozen
+    check_deposit_open(deposit)?;
+    
+    // Verify the payout goes to the counterparty in the deposit's mint
+    let destination = TokenAccount::unpack(&destination_token_account_info.data.borrow())?;
+    if destination.owner != *counterparty_info.key {
+        return Err(VaultError::InvalidDestinationAccount.into());
+    }
+    check_payout_mint(deposit, &destination.mint)?;
+    
+    // Mark the deposit as withdrawn
+    deposit.status = DepositStatus::Withdrawn as u8;
+    let withdrawn_at = Clock::get()?.unix_timestamp;
+    deposit.withdrawn_at = Some(withdrawn_at);
+    let amount = deposit.amount;
+    
+    // Free the deposit's share of the vault and mint limits
+    release_deposit_capacity(&mut vault, deposit_index)?;
+    update_stats_for_withdrawal(stats_info, &vault, deposit_index, Clock::get()?.slot)?;
+    
+    // Drop the deposit from its depositor's position index
+    remove_from_position_index(
+        program_id,
+        vault_account_info.key,
+        depositor_info.key,
+        position_index_info,
+        &[deposit_id],
+    )?;
+    
+    // Transfer tokens from the vault to the counterparty
+    transfer_from_vault(
+        vault_account_info,
+        source_token_account_info,
+        destination_token_account_info,
+        token_program_info,
+        amount,
+    )?;
+    
+    // Call the withdraw hook once the tokens have moved
+    call_withdraw_hook(&vault, vault_account_info, hook.as_ref(), &[deposit_id])?;
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(
+        b"withdraw",
+        deposit_id.to_le_bytes(),
+        amount.to_le_bytes(),
+        withdrawn_at.to_le_bytes(),
+        [DepositStatus::Withdrawn as u8],
+    );
+    vault_log!("Escrow deposit {} released to {}: {} tokens", deposit_id, counterparty_info.key, amount);
+    Ok(())
+}
+
+// Compare two hashes without exiting at the first differing byte, so a wrong
+// preimage takes as long however much of its hash matches
+fn hashes_equal(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+// Transfer tokens out of the vault's token account, signing as the vault
+fn transfer_from_vault<'a>(
+    vault_account_info: &AccountInfo<'a>,
+    source_token_account_info: &AccountInfo<'a>,
+    destination_token_account_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    let transfer_instruction = spl_token::instruction::transfer(
+        token_program_info.key,
+        source_token_account_info.key,
+        destination_token_account_info.key,
+        vault_account_info.key,
+        &[],
+        amount,
+    )?;
+    
+    invoke_signed(
+        &transfer_instruction,
+        &[
+            source_token_account_info.clone(),
+            destination_token_account_info.clone(),
+            vault_account_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&[&vault_account_info.key.to_bytes(), &[0]]],
+    )
+}
+
+// Close one of the vault's token accounts, sending its rent to `destination_info`
+fn close_vault_token_account<'a>(
+    vault_account_info: &AccountInfo<'a>,
+    token_account_info: &AccountInfo<'a>,
+    destination_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let close_instruction = spl_token::instruction::close_account(
+        token_program_info.key,
+        token_account_info.key,
+        destination_info.key,
+        vault_account_info.key,
+        &[],
+    )?;
+    
+    invoke_signed(
+        &close_instruction,
+        &[
+            token_account_info.clone(),
+            destination_info.clone(),
+            vault_account_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&[&vault_account_info.key.to_bytes(), &[0]]],
+    )
+}
+
+// Process early withdraw instruction
+fn process_early_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let owner_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let destination_token_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let treasury_token_account_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the owner signed the transaction
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Set the withdraw hook accounts aside, so the optional accounts before
+    // them are read as usual
+    let (optional_accounts, hook) = split_withdraw_hook(&vault, account_info_iter.as_slice())?;
+    let account_info_iter = &mut optional_accounts.iter();
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Verify early withdrawals are enabled and penalties go to the treasury
+    let treasury = vault.treasury.ok_or(VaultError::EarlyWithdrawalDisabled)?;
+    if *treasury_token_account_info.key != treasury {
+        return Err(VaultError::InvalidTreasuryAccount.into());
+    }
+    let max_penalty_bps = vault.max_penalty_bps;
+    
+    // Find the deposit
+    let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    let deposit = &mut vault.deposits[deposit_index];
+    check_token_deposit(deposit)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    
+    // Verify the owner is the depositor; tokenized positions and queued
+    // deposits have their own withdrawal paths, hashlocked deposits stay
+    // claimable by their beneficiary until the unlock time and escrow
+    // deposits only leave early with both parties' consent
+    if deposit.depositor != *owner_info.key
+        || deposit.position_mint.is_some()
+        || deposit.hashlock.is_some()
+        || deposit.escrow_counterparty.is_some()
+    {
+        return Err(VaultError::UnauthorizedWithdrawal.into());
+    }
+    if deposit.queued_withdrawals {
+        return Err(VaultError::WithdrawalQueueRequired.into());
+    }
+    
+    // Verify the deposit has not been withdrawn
+    if deposit.is_withdrawn() {
+        return Err(VaultError::AlreadyWithdrawn.into());
+    }
+    
+    // Verify the deposit is not frozen
+    if deposit.frozen {
+        return Err(VaultError::DepositFrozen.into());
+    }
+    
+    // Verify the deposit wasn't made in this slot
+    check_not_same_slot(deposit, clock.slot)?;
+    
+    // Verify the payout goes to the locked destination, if any
+    check_locked_destination(deposit, destination_token_account_info.key)?;
+    
+    // Compute the penalty from the decayed schedule; slot-locked deposits have
+    // no time schedule and pay the full penalty until their slot
+    let penalty_bps = if check_unlock_slot_reached(deposit, clock.slot).is_err() {
+        max_penalty_bps
+    } else {
+        early_withdrawal_penalty_bps(
+            max_penalty_bps,
+            deposit.created_at,
+            deposit.unlock_time,
+            clock.unix_timestamp,
+        )
+    };
+    let penalty = (deposit.amount as u128 * penalty_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+    let amount = deposit.amount.checked_sub(penalty)
+        .ok_or(VaultError::MathOverflow)?;
+    
+    // Mark the deposit as withdrawn
+    deposit.status = DepositStatus::Cancelled as u8;
+    deposit.withdrawn_at = Some(clock.unix_timestamp);
+    
+    // Count the withdrawal against the owner's rate limit window
+    debit_rate_limit(&mut vault, owner_info.key, amount, clock.unix_timestamp)?;
+    
+    // Free the deposit's share of the vault and mint limits
+    release_deposit_capacity(&mut vault, deposit_index)?;
+    update_stats_for_withdrawal(stats_info, &vault, deposit_index, clock.slot)?;
+    
+    // Drop the deposit from its depositor's position index
+    remove_from_position_index(
+        program_id,
+        vault_account_info.key,
+        &vault.deposits[deposit_index].depositor,
+        position_index_info,
+        &[deposit_id],
+    )?;
+    
+    // Transfer the penalty to the treasury and the rest to the owner
+    if penalty > 0 {
+        transfer_from_vault(
+            vault_account_info,
+            source_token_account_info,
+            treasury_token_account_info,
+            token_program_info,
+            penalty,
+        )?;
+    }
+    transfer_from_vault(
+        vault_account_info,
+        source_token_account_info,
+        destination_token_account_info,
+        token_program_info,
+        amount,
+    )?;
+    
+    // Call the withdraw hook once the tokens have moved
+    call_withdraw_hook(&vault, vault_account_info, hook.as_ref(), &[deposit_id])?;
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(
+        b"withdraw",
+        deposit_id.to_le_bytes(),
+        amount.to_le_bytes(),
+        clock.unix_timestamp.to_le_bytes(),
+        [DepositStatus::Cancelled as u8],
+    );
+    vault_log!("Early withdrawal: {} tokens from deposit {} ({} tokens penalty)", amount, deposit_id, penalty);
+    Ok(())
+}
+
+// Process crank withdraw instruction
+fn process_crank_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let cranker_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let destination_token_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let depositor_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let associated_token_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the cranker signed the transaction (it funds the token account)
+    if !cranker_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Verify the system program account
+    if *system_program_info.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Set the withdraw hook accounts aside, so the optional accounts before
+    // them are read as usual
+    let (optional_accounts, hook) = split_withdraw_hook(&vault, account_info_iter.as_slice())?;
+    let account_info_iter = &mut optional_accounts.iter();
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Find the deposit
+    let post_unlock_grace = vault.post_unlock_grace;
+    let crank_bounty_bps = vault.crank_bounty_bps.min(MAX_CRANK_BOUNTY_BPS);
+    let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    let deposit = &mut vault.deposits[deposit_index];
+    check_token_deposit(deposit)?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    
+    // Verify the deposit accepts cranking: not opted out, not tokenized (the
+    // holder may not be the depositor) and not behind a withdrawal queue
+    if deposit.no_crank {
+        return Err(VaultError::CrankDisabled.into());
+    }
+    if deposit.position_mint.is_some() {
+        return Err(VaultError::UnauthorizedWithdrawal.into());
+    }
+    if deposit.queued_withdrawals {
+        return Err(VaultError::WithdrawalQueueRequired.into());
+    }
+    
+    // Verify the deposit has not been withdrawn
+    if deposit.is_withdrawn() {
+        return Err(VaultError::AlreadyWithdrawn.into());
+    }
+    
+    // Verify the deposit is not frozen
+    if deposit.frozen {
+        return Err(VaultError::DepositFrozen.into());
+    }
+    
+    // Verify the deposit wasn't made in this slot
+    check_not_same_slot(deposit, clock.slot)?;
+    
+    // Verify the unlock time and slot have been reached and the grace period is over
+    if deposit.unlock_time > clock.unix_timestamp {
+        return Err(VaultError::UnlockTimeNotReached.into());
+    }
+    check_unlock_slot_reached(deposit, clock.slot)?;
+    check_post_unlock_grace(post_unlock_grace, deposit, clock.unix_timestamp)?;
+    
+    // Verify the destination is the depositor's associated token account
+    if *depositor_info.key != deposit.depositor || *mint_info.key != deposit.token_mint {
+        return Err(VaultError::InvalidDestinationAccount.into());
+    }
+    let expected_destination = spl_associated_token_account::get_associated_token_address(
+        &deposit.depositor,
+        &deposit.token_mint,
+    );
+    if *destination_token_account_info.key != expected_destination {
+        return Err(VaultError::InvalidDestinationAccount.into());
+    }
+    check_locked_destination(deposit, destination_token_account_info.key)?;
+    
+    // Split off the cranker's bounty; only a single bounty account is read
+    let bounty = crank_bounty(deposit.amount, crank_bounty_bps);
+    let bounty_account_info = if bounty > 0 {
+        let bounty_account_info = next_account_info(account_info_iter)?;
+        if *bounty_account_info.owner != spl_token::id() {
+            return Err(VaultError::InvalidBountyAccount.into());
+        }
+        let bounty_account = TokenAccount::unpack(&bounty_account_info.data.borrow())?;
+        if bounty_account.mint != deposit.token_mint || bounty_account.owner != *cranker_info.key {
+            return Err(VaultError::InvalidBountyAccount.into());
+        }
+        Some(bounty_account_info)
+    } else {
+        None
+    };
+    
+    // Mark the deposit as withdrawn
+    deposit.status = DepositStatus::Withdrawn as u8;
+    deposit.withdrawn_at = Some(clock.unix_timestamp);
+    let total = deposit.amount;
+    let amount = total - bounty;
+    let depositor = deposit.depositor;
+    
+    // Count the withdrawal against the depositor's rate limit window
+    debit_rate_limit(&mut vault, &depositor, total, clock.unix_timestamp)?;
+    
+    // Free the deposit's share of the vault and mint limits
+    release_deposit_capacity(&mut vault, deposit_index)?;
+    update_stats_for_withdrawal(stats_info, &vault, deposit_index, clock.slot)?;
+    
+    // Drop the deposit from its depositor's position index
+    remove_from_position_index(
+        program_id,
+        vault_account_info.key,
+        &vault.deposits[deposit_index].depositor,
+        position_index_info,
+        &[deposit_id],
+    )?;
+    
+    // Create the depositor's associated token account at the cranker's expense
+    if destination_token_account_info.data_is_empty() {
+        invoke(
+            &spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                cranker_info.key,
+                depositor_info.key,
+                mint_info.key,
+                token_program_info.key,
+            ),
+            &[
+                cranker_info.clone(),
+                destination_token_account_info.clone(),
+                depositor_info.clone(),
+                mint_info.clone(),
+                system_program_info.clone(),
+                token_program_info.clone(),
+                associated_token_program_info.clone(),
+            ],
+        )?;
+    }
+    
+    // Transfer the bounty to the cranker and the rest to the depositor
+    if let Some(bounty_account_info) = bounty_account_info {
+        transfer_from_vault(
+            vault_account_info,
+            source_token_account_info,
+            bounty_account_info,
+            token_program_info,
+            bounty,
+        )?;
+    }
+    transfer_from_vault(
+        vault_account_info,
+        source_token_account_info,
+        destination_token_account_info,
+        token_program_info,
+        amount,
+    )?;
+    
+    // Call the withdraw hook once the tokens have moved
+    call_withdraw_hook(&vault, vault_account_info, hook.as_ref(), &[deposit_id])?;
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(
+        b"withdraw",
+        deposit_id.to_le_bytes(),
+        amount.to_le_bytes(),
+        clock.unix_timestamp.to_le_bytes(),
+        [DepositStatus::Withdrawn as u8],
+    );
+    vault_log!(
+        "Crank withdrawal: {} tokens from deposit {} to {} ({} tokens bounty)",
+        amount,
+        deposit_id,
+        depositor,
+        bounty
+    );
+    Ok(())
+}
+
+// Process withdraw with permit instruction
+fn process_withdraw_with_permit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+    expiry: i64,
+) -> ProgramResult {
+    // Set the stats account aside, so the optional accounts before it are
+    // read as usual
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let destination_token_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    let instructions_sysvar_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the vault account is owned by the program and load it
+    let mut vault = checks::vault(accounts, vault_account_info, program_id)?;
+    
+    // Set the withdraw hook accounts aside, so the optional accounts before
+    // them are read as usual
+    let (optional_accounts, hook) = split_withdraw_hook(&vault, account_info_iter.as_slice())?;
+    let (optional_accounts, rent_destination_info) = split_vault_owner(&vault, optional_accounts);
+    let account_info_iter = &mut optional_accounts.iter();
+    
+    // Verify neither token account is frozen by the mint's freeze authority
+    checks::not_frozen(accounts, destination_token_account_info, "destination token account")?;
+    checks::not_frozen(accounts, source_token_account_info, "vault token account")?;
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Find the deposit
+    let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    let deposit = &mut vault.deposits[deposit_index];
+    check_token_deposit(deposit)?;
+    let clock = checks::clock(accounts, clock_sysvar_info)?;
+    
+    // Tokenized positions belong to the NFT holder, not the depositor
+    if deposit.position_mint.is_some() {
+        return Err(VaultError::UnauthorizedWithdrawal.into());
+    }
+    
+    // Queued deposits must go through request and claim
+    if deposit.queued_withdrawals {
+        return Err(VaultError::WithdrawalQueueRequired.into());
+    }
+    
+    // Get the protocol fee accounts, if supplied
+    let protocol_fee_accounts = load_protocol_fee(program_id, account_info_iter)?;
+    
+    // Verify the deposit has not been withdrawn and is not frozen
+    check_deposit_open(deposit)?;
+    
+    // Verify the deposit wasn't made in this slot
+    check_not_same_slot(deposit, clock.slot)?;
+    
+    // Verify the unlock time and slot have been reached and the permit is still valid
+    check_unlocked(deposit, clock.unix_timestamp)?;
+    check_unlock_slot_reached(deposit, clock.slot)?;
+    if clock.unix_timestamp > expiry {
+        return Err(VaultError::PermitExpired.into());
+    }
+    
+    // Verify the funds flow to the locked destination, if any, and to an
+    // account of the deposit's mint owned by the depositor
+    let role = "destination token account";
+    check_locked_destination(deposit, destination_token_account_info.key).map_err(|error| {
+        checks::fail(accounts, destination_token_account_info, role, error, format_args!("the locked destination"))
+    })?;
+    checks::owner(accounts, destination_token_account_info, role, &spl_token::id(), ProgramError::IncorrectProgramId)?;
+    let destination = checks::token_account(accounts, destination_token_account_info, role)?;
+    if destination.owner != deposit.depositor {
+        return Err(checks::fail(
+            accounts,
+            destination_token_account_info,
+            role,
+            VaultError::UnauthorizedWithdrawal,
+            format_args!("token owner {}", deposit.depositor),
+        ));
+    }
+    check_payout_mint(deposit, &destination.mint).map_err(|error| {
+        let mint = deposit.token_mint;
+        checks::fail(accounts, destination_token_account_info, role, error, format_args!("mint {}", mint))
+    })?;
+    
+    // Verify the depositor signed this exact withdrawal
+    let message = permit_message(
+        program_id,
+        vault_account_info.key,
+        deposit_id,
+        destination_token_account_info.key,
+        expiry,
+        deposit.permit_nonce,
+    );
+    verify_ed25519_permit(instructions_sysvar_info, &deposit.depositor, &message)?;
+    
+    // Consume the permit
+    deposit.permit_nonce = deposit.permit_nonce.checked_add(1)
+        .ok_or(VaultError::MathOverflow)?;
+    
+    // Pay the deposit out like a `Withdraw`, counted against the depositor's
+    // rate limit window
+    let (amount, depositor) = (deposit.amount, deposit.depositor);
+    let payout = WithdrawalPayout {
+        vault_account_info,
+        destination_token_account_info,
+        source_token_account_info,
+        token_program_info,
+        stats_info,
+        position_index_info,
+        protocol_fee_accounts,
+        memo: None,
+        hook,
+        rent_destination_info,
+    };
+    let fee = pay_out_withdrawal(program_id, accounts, &mut vault, deposit_index, &depositor, &clock, payout)?;
+    
+    vault_log!(
+        "Permit withdrawal successful: {} tokens from deposit {} ({} protocol fee)",
+        amount - fee,
+        deposit_id,
+        fee
+    );
+    Ok(())
+}
+
+// Verify that the instruction preceding the current one is an ed25519 program
+// check of `message` signed by `signer`
+fn verify_ed25519_permit(
+    instructions_sysvar_info: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> ProgramResult {
+    let current_index = load_current_index_checked(instructions_sysvar_info)?;
+    if current_index == 0 {
+        return Err(VaultError::InvalidPermit.into());
+    }
+    let ed25519_instruction = load_instruction_at_checked(
+        current_index as usize - 1,
+        instructions_sysvar_info,
+    )?;
+    if ed25519_instruction.program_id != ed25519_program::id() {
+        return Err(VaultError::InvalidPermit.into());
+    }
+    
+    // Layout: signature count, padding, then one offsets struct of seven u16s
+    let data = &ed25519_instruction.data;
+    if data.len() < ED25519_OFFSETS_START + ED25519_OFFSETS_LEN || data[0] != 1 {
+        return Err(VaultError::InvalidPermit.into());
+    }
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let signature_instruction_index = read_u16(ED25519_OFFSETS_START + 2);
+    let public_key_offset = read_u16(ED25519_OFFSETS_START + 4) as usize;
+    let public_key_instruction_index = read_u16(ED25519_OFFSETS_START + 6);
+    let message_offset = read_u16(ED25519_OFFSETS_START + 8) as usize;
+    let message_size = read_u16(ED25519_OFFSETS_START + 10) as usize;
+    let message_instruction_index = read_u16(ED25519_OFFSETS_START + 12);
+    
+    // Everything the precompile verified must live in its own instruction data
+    if signature_instruction_index != u16::MAX
+        || public_key_instruction_index != u16::MAX
+        || message_instruction_index != u16::MAX
+    {
+        return Err(VaultError::InvalidPermit.into());
+    }
+    
+    let public_key = data.get(public_key_offset..public_key_offset + 32)
+        .ok_or(VaultError::InvalidPermit)?;
+    let signed_message = data.get(message_offset..message_offset + message_size)
+        .ok_or(VaultError::InvalidPermit)?;
+    if public_key != signer.as_ref() || signed_message != message {
+        return Err(VaultError::InvalidPermit.into());
+    }
+    
+    Ok(())
+}
+
+// Process emergency withdraw instruction
+fn process_emergency_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let (accounts, destination_creation) = split_destination_creation(accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let emergency_authority_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let destination_token_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let depositor_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the emergency authority signed the transaction
+    checks::signer(accounts, emergency_authority_info, "emergency authority")?;
+    
+    // Verify the vault account is owned by the program and load it
+    let mut vault = checks::vault(accounts, vault_account_info, program_id)?;
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Verify the emergency authority is authorized
+    if vault.emergency_authority != Some(*emergency_authority_info.key) {
+        return Err(checks::fail(
+            accounts,
+            emergency_authority_info,
+            "emergency authority",
+            VaultError::UnauthorizedWithdrawal,
+            format_args!("{:?}", vault.emergency_authority),
+        ));
+    }
+    
+    // Enforce the vault-wide cooldown between emergency withdrawals
+    let now = Clock::get()?.unix_timestamp;
+    record_emergency_withdrawal(&mut vault, now)?;
+    let post_unlock_grace = vault.post_unlock_grace;
+    let emergency_destination = vault.emergency_destination;
+    
+    // Find the deposit
+    let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    let deposit = &mut vault.deposits[deposit_index];
+    check_token_deposit(deposit)?;
+    
+    // Verify the deposit has not been withdrawn
+    if deposit.is_withdrawn() {
+        return Err(VaultError::AlreadyWithdrawn.into());
+    }
+    
+    // Verify the deposit is not frozen
+    if deposit.frozen {
+        return Err(VaultError::DepositFrozen.into());
+    }
+    
+    // Leave matured deposits to their depositor during the grace period
+    check_post_unlock_grace(post_unlock_grace, deposit, now)?;
+    
+    // Verify the payout goes to the vault's emergency destination when one is
+    // configured, otherwise to the deposit's locked destination, if any
+    match emergency_destination {
+        Some(wallet) => {
+            let expected_destination = spl_associated_token_account::get_associated_token_address(
+                &wallet,
+                &deposit.token_mint,
+            );
+            let (role, error) = ("destination token account", VaultError::InvalidDestinationAccount);
+            checks::key(accounts, destination_token_account_info, role, &expected_destination, error)?;
+        },
+        None => check_locked_destination(deposit, destination_token_account_info.key).map_err(|error| {
+            let role = "destination token account";
+            checks::fail(accounts, destination_token_account_info, role, error, format_args!("the locked destination"))
+        })?,
+    }
+    deposit.emergency_paid_to = Some(*destination_token_account_info.key);
+    
+    // Verify the depositor account matches the deposit's depositor
+    checks::key(accounts, depositor_info, "depositor", &deposit.depositor, VaultError::UnauthorizedWithdrawal)?;
+    
+    // Deposits opted into dual control also need the depositor's signature
+    if deposit.emergency_requires_depositor && !depositor_info.is_signer {
+        return Err(checks::fail(
+            accounts,
+            depositor_info,
+            "depositor",
+            VaultError::UnauthorizedWithdrawal,
+            format_args!("a signature"),
+        ));
+    }
+    
+    // Mark the deposit as withdrawn
+    deposit.status = DepositStatus::EmergencyWithdrawn as u8;
+    deposit.withdrawn_at = Some(now);
+    let amount = deposit.amount;
+    
+    // Free the deposit's share of the vault and mint limits
+    release_deposit_capacity(&mut vault, deposit_index)?;
+    update_stats_for_withdrawal(stats_info, &vault, deposit_index, Clock::get()?.slot)?;
+    
+    // Drop the deposit from its depositor's position index
+    remove_from_position_index(
+        program_id,
+        vault_account_info.key,
+        &vault.deposits[deposit_index].depositor,
+        position_index_info,
+        &[deposit_id],
+    )?;
+    
+    // Create the depositor's associated token account if the creation accounts
+    // are passed and it is missing; the emergency destination wallet isn't
+    // among the accounts, so its token accounts must already exist
+    if let Some(creation) = &destination_creation {
+        if emergency_destination.is_some() {
+            return Err(checks::fail(
+                accounts,
+                creation.associated_token_program_info,
+                "associated token program",
+                VaultError::InvalidDestinationAccount,
+                format_args!("no destination creation with an emergency destination"),
+            ));
+        }
+        create_missing_destination(
+            accounts,
+            creation,
+            destination_token_account_info,
+            depositor_info,
+            &vault.deposits[deposit_index].token_mint,
+            token_program_info,
+        )?;
+    }
+    
+    // Transfer tokens from the vault to the depositor
+    transfer_from_vault(
+        vault_account_info,
+        source_token_account_info,
+        destination_token_account_info,
+        token_program_info,
+        amount,
+    )?;
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(
+        b"emergency",
+        deposit_id.to_le_bytes(),
+        amount.to_le_bytes(),
+        now.to_le_bytes(),
+        [DepositStatus::EmergencyWithdrawn as u8],
+    );
+    vault_log!("Emergency withdrawal successful: {} tokens from deposit {}", amount, deposit_id);
+    Ok(())
+}
+
+// Process emergency withdraw all instruction
+fn process_emergency_withdraw_all(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    depositor: Pubkey,
+    mint: Pubkey,
+) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let emergency_authority_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let destination_token_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let depositor_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the emergency authority signed the transaction
+    checks::signer(accounts, emergency_authority_info, "emergency authority")?;
+    
+    // Verify the vault account is owned by the program and load it
+    let mut vault = checks::vault(accounts, vault_account_info, program_id)?;
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Verify the emergency authority is authorized
+    if vault.emergency_authority != Some(*emergency_authority_info.key) {
+        return Err(checks::fail(
+            accounts,
+            emergency_authority_info,
+            "emergency authority",
+            VaultError::UnauthorizedWithdrawal,
+            format_args!("{:?}", vault.emergency_authority),
+        ));
+    }
+    
+    // Verify the depositor account matches the depositor being evacuated
+    checks::key(accounts, depositor_info, "depositor", &depositor, VaultError::UnauthorizedWithdrawal)?;
+    
+    // Verify the payout goes to the vault's emergency destination when one is
+    // configured, otherwise to a token account of the mint; locked
+    // destinations are checked per deposit below
+    let emergency_destination = vault.emergency_destination;
+    match emergency_destination {
+        Some(wallet) => {
+            let expected_destination = spl_associated_token_account::get_associated_token_address(&wallet, &mint);
+            let (role, error) = ("destination token account", VaultError::InvalidDestinationAccount);
+            checks::key(accounts, destination_token_account_info, role, &expected_destination, error)?;
+        },
+        None => {
+            let role = "destination token account";
+            let destination = checks::token_account(accounts, destination_token_account_info, role)?;
+            if destination.mint != mint {
+                return Err(checks::fail(
+                    accounts,
+                    destination_token_account_info,
+                    role,
+                    VaultError::TokenMintMismatch,
+                    format_args!("mint {}", mint),
+                ));
+            }
+        },
+    }
+    
+    // Verify the vault token account holds the mint and only the vault can close it
+    let role = "vault token account";
+    let vault_key = vault_account_info.key;
+    let vault_token_account = checks::vault_token_account(accounts, source_token_account_info, role, vault_key)?;
+    if vault_token_account.mint != mint {
+        let error = VaultError::TokenMintMismatch;
+        return Err(checks::fail(accounts, source_token_account_info, role, error, format_args!("mint {}", mint)));
+    }
+    
+    // Start an evacuation, which counts as one emergency withdrawal against the
+    // cooldown, or continue the one in progress for the same depositor and mint
+    let now = Clock::get()?.unix_timestamp;
+    let mut evacuation = match vault.emergency_evacuation.take() {
+        Some(evacuation) if evacuation.depositor == depositor && evacuation.mint == mint => evacuation,
+        Some(_) => return Err(VaultError::EvacuationInProgress.into()),
+        None => {
+            record_emergency_withdrawal(&mut vault, now)?;
+            EmergencyEvacuation { depositor, mint, next_id: 0, end_id: vault.deposit_count }
+        },
+    };
+    
+    // Withdraw the next chunk of the depositor's open deposits of the mint, in
+    // ID order, skipping those the single-deposit path would refuse
+    let post_unlock_grace = vault.post_unlock_grace;
+    let destination_key = *destination_token_account_info.key;
+    let mut result = EmergencyWithdrawAllResult::default();
+    let mut withdrawn = Vec::new();
+    let mut total: u64 = 0;
+    let mut visited = 0;
+    for (deposit_index, deposit) in vault.deposits.iter_mut().enumerate() {
+        if deposit.id < evacuation.next_id || deposit.id >= evacuation.end_id {
+            continue;
+        }
+        if visited == MAX_EVACUATION_DEPOSITS_PER_CALL {
+            break;
+        }
+        visited += 1;
+        evacuation.next_id = deposit.id + 1;
+        
+        if deposit.depositor != depositor || deposit.token_mint != mint || deposit.is_withdrawn() {
+            continue;
+        }
+        let allowed = check_token_deposit(deposit).is_ok()
+            && !deposit.frozen
+            && check_post_unlock_grace(post_unlock_grace, deposit, now).is_ok()
+            && (emergency_destination.is_some() || check_locked_destination(deposit, &destination_key).is_ok())
+            && (!deposit.emergency_requires_depositor || depositor_info.is_signer);
+        if !allowed {
+            result.skipped.push(deposit.id);
+            continue;
+        }
+        
+        // Mark the deposit as withdrawn
+        deposit.status = DepositStatus::EmergencyWithdrawn as u8;
+        deposit.withdrawn_at = Some(now);
+        deposit.emergency_paid_to = Some(destination_key);
+        total = total.checked_add(deposit.amount).ok_or(VaultError::MathOverflow)?;
+        result.withdrawn.push(deposit.id);
+        withdrawn.push((deposit_index, deposit.id, deposit.amount));
+    }
+    
+    // Free the deposits' share of the vault and mint limits
+    for &(deposit_index, _, _) in &withdrawn {
+        release_deposit_capacity(&mut vault, deposit_index)?;
+        update_stats_for_withdrawal(stats_info, &vault, deposit_index, Clock::get()?.slot)?;
+    }
+    
+    // Drop the deposits from the depositor's position index
+    remove_from_position_index(
+        program_id,
+        vault_account_info.key,
+        &depositor,
+        position_index_info,
+        &result.withdrawn,
+    )?;
+    
+    // Finish once every deposit has been visited
+    result.finished = !vault.deposits.iter()
+        .any(|deposit| deposit.id >= evacuation.next_id && deposit.id < evacuation.end_id);
+    if !result.finished {
+        vault.emergency_evacuation = Some(evacuation);
+    }
+    
+    // Transfer the withdrawn deposits in one go
+    if total > 0 {
+        transfer_from_vault(
+            vault_account_info,
+            source_token_account_info,
+            destination_token_account_info,
+            token_program_info,
+            total,
+        )?;
+    }
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    for (_, deposit_id, amount) in withdrawn {
+        vault_event!(
+            b"emergency",
+            deposit_id.to_le_bytes(),
+            amount.to_le_bytes(),
+            now.to_le_bytes(),
+            [DepositStatus::EmergencyWithdrawn as u8],
+        );
+    }
+    for deposit_id in &result.skipped {
+        vault_event!(b"emergency_skipped", deposit_id.to_le_bytes());
+    }
+    set_return_data(&result.try_to_vec()?);
+    vault_log!(
+        "Emergency withdrawal of {} tokens from {} deposits of {}, {} skipped",
+        total,
+        result.withdrawn.len(),
+        depositor,
+        result.skipped.len(),
+    );
+    Ok(())
+}
+
+// Process resolve frozen deposit instruction
+fn process_resolve_frozen_deposit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_id: u64,
+    destination_kind: ResolutionDestination,
+) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let emergency_authority_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    let destination_token_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the emergency authority signed the transaction
+    if !emergency_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Set reentrancy guard
+    vault.reentrancy_guard = true;
+    
+    // Verify the emergency authority is authorized
+    if vault.emergency_authority != Some(*emergency_authority_info.key) {
+        return Err(VaultError::UnauthorizedWithdrawal.into());
     }
+    let arbitration_destination = vault.arbitration_destination;
     
-    let instruction = VaultInstruction::try_from_slice(instruction_data)
-        .map_err(|_| VaultError::InvalidInstructionData)?;
+    // Find the deposit
+    let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    let deposit = &mut vault.deposits[deposit_index];
+    check_token_deposit(deposit)?;
     
-    match instruction {
-        VaultInstruction::CreateVault => process_create_vault(program_id, accounts),
-        VaultInstruction::Deposit { amount, unlock_time, tag } => {
-            process_deposit(program_id, accounts, amount, unlock_time, tag)
-        },
-        VaultInstruction::Withdraw { deposit_id } => {
-            process_withdraw(program_id, accounts, deposit_id)
+    // Verify the deposit has not been withdrawn
+    if deposit.is_withdrawn() {
+        return Err(VaultError::AlreadyWithdrawn.into());
+    }
+    
+    // Only frozen deposits can be resolved
+    if !deposit.frozen {
+        return Err(VaultError::DepositNotFrozen.into());
+    }
+    
+    // Verify the destination matches the chosen resolution
+    match destination_kind {
+        ResolutionDestination::ReturnToDepositor => {
+            check_locked_destination(deposit, destination_token_account_info.key)?;
+            let destination = TokenAccount::unpack(&destination_token_account_info.data.borrow())?;
+            if destination.owner != deposit.depositor {
+                return Err(VaultError::InvalidDestinationAccount.into());
+            }
         },
-        VaultInstruction::EmergencyWithdraw { deposit_id } => {
-            process_emergency_withdraw(program_id, accounts, deposit_id)
+        ResolutionDestination::Arbitration => {
+            let arbitration_destination = arbitration_destination
+                .ok_or(VaultError::ArbitrationNotConfigured)?;
+            if *destination_token_account_info.key != arbitration_destination {
+                return Err(VaultError::InvalidDestinationAccount.into());
+            }
         },
     }
+    
+    // Mark the deposit as resolved; it no longer holds funds
+    deposit.resolution = Some(destination_kind);
+    deposit.status = DepositStatus::Swept as u8;
+    let withdrawn_at = Clock::get()?.unix_timestamp;
+    deposit.withdrawn_at = Some(withdrawn_at);
+    let amount = deposit.amount;
+    
+    // Free the deposit's share of the vault and mint limits
+    release_deposit_capacity(&mut vault, deposit_index)?;
+    update_stats_for_withdrawal(stats_info, &vault, deposit_index, Clock::get()?.slot)?;
+    
+    // Drop the deposit from its depositor's position index
+    remove_from_position_index(
+        program_id,
+        vault_account_info.key,
+        &vault.deposits[deposit_index].depositor,
+        position_index_info,
+        &[deposit_id],
+    )?;
+    
+    // Transfer tokens from the vault to the chosen destination
+    transfer_from_vault(
+        vault_account_info,
+        source_token_account_info,
+        destination_token_account_info,
+        token_program_info,
+        amount,
+    )?;
+    
+    // Reset reentrancy guard
+    vault.reentrancy_guard = false;
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(
+        b"resolve",
+        deposit_id.to_le_bytes(),
+        amount.to_le_bytes(),
+        withdrawn_at.to_le_bytes(),
+        [DepositStatus::Swept as u8],
+    );
+    vault_log!("Frozen deposit {} resolved: {} tokens sent via {:?}", deposit_id, amount, destination_kind);
+    Ok(())
 }
 
-// Process create vault instruction
-fn process_create_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+// Process announce sweep all instruction
+fn process_announce_sweep_all(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     
     // Get accounts
+    let emergency_authority_info = next_account_info(account_info_iter)?;
     let owner_info = next_account_info(account_info_iter)?;
     let vault_account_info = next_account_info(account_info_iter)?;
     
-    // Verify the owner signed the transaction
-    if !owner_info.is_signer {
+    // Verify the emergency authority and the owner both signed the transaction
+    if !emergency_authority_info.is_signer || !owner_info.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
     
@@ -203,48 +11522,49 @@ fn process_create_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> Progra
         return Err(ProgramError::IncorrectProgramId);
     }
     
-    // Check if the vault account is already initialized
-    if !vault_account_info.data.borrow().is_empty() {
-        return Err(VaultError::AccountAlreadyInUse.into());
+    // Verify both signers hold their powers over the vault
+    let mut vault = load_vault(vault_account_info)?;
+    if vault.emergency_authority != Some(*emergency_authority_info.key) {
+        return Err(VaultError::UnauthorizedWithdrawal.into());
     }
+    check_vault_owner(&vault, owner_info.key, accounts)?;
     
-    // Initialize the vault
-    let vault = Vault {
-        owner: *owner_info.key,
-        deposit_count: 0,
-        deposits: Vec::new(),
-        reentrancy_guard: false,
-        emergency_authority: None,
-    };
+    // A sweep needs somewhere to go, and is announced once
+    let emergency_destination = vault.emergency_destination.ok_or(VaultError::NoEmergencyDestination)?;
+    if vault.sweep_announced_at.is_some() {
+        return Err(VaultError::SweepAlreadyAnnounced.into());
+    }
     
-    // Serialize and store the vault data
-    vault.serialize(&mut *vault_account_info.data.borrow_mut())?;
+    // Record the announcement and stop taking deposits that would be swept
+    let now = Clock::get()?.unix_timestamp;
+    vault.sweep_announced_at = Some(now);
+    vault.deposits_closed = true;
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
     
-    msg!("Vault created successfully");
+    let executable_at = now.saturating_add(SWEEP_ALL_DELAY);
+    vault_event!(b"sweep_all_announced", now.to_le_bytes(), executable_at.to_le_bytes(), emergency_destination);
+    vault_log!("WARNING: this vault will be swept to its emergency destination; withdraw matured deposits now");
+    vault_log!("Sweep to {} executable from timestamp {}", emergency_destination, executable_at);
     Ok(())
 }
 
-// Process deposit instruction
-fn process_deposit(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    amount: u64,
-    unlock_time: i64,
-    tag: [u8; 32],
-) -> ProgramResult {
+// Process execute sweep all instruction
+fn process_execute_sweep_all(program_id: &Pubkey, accounts: &[AccountInfo], mint: Pubkey) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
     let account_info_iter = &mut accounts.iter();
     
     // Get accounts
-    let depositor_info = next_account_info(account_info_iter)?;
+    let emergency_authority_info = next_account_info(account_info_iter)?;
     let vault_account_info = next_account_info(account_info_iter)?;
-    let source_token_account_info = next_account_info(account_info_iter)?;
+    let vault_token_account_info = next_account_info(account_info_iter)?;
     let destination_token_account_info = next_account_info(account_info_iter)?;
     let token_program_info = next_account_info(account_info_iter)?;
-    let system_program_info = next_account_info(account_info_iter)?;
-    let clock_sysvar_info = next_account_info(account_info_iter)?;
     
-    // Verify the depositor signed the transaction
-    if !depositor_info.is_signer {
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the emergency authority signed the transaction
+    if !emergency_authority_info.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
     
@@ -254,7 +11574,7 @@ fn process_deposit(
     }
     
     // Load the vault
-    let mut vault = Vault::try_from_slice(&vault_account_info.data.borrow())?;
+    let mut vault = load_vault(vault_account_info)?;
     
     // Check reentrancy guard
     if vault.reentrancy_guard {
@@ -264,76 +11584,116 @@ fn process_deposit(
     // Set reentrancy guard
     vault.reentrancy_guard = true;
     
-    // Verify the amount is valid
-    if amount == 0 {
-        return Err(VaultError::InvalidAmount.into());
+    // Verify the emergency authority is authorized
+    if vault.emergency_authority != Some(*emergency_authority_info.key) {
+        return Err(VaultError::UnauthorizedWithdrawal.into());
     }
     
-    // Verify the unlock time is in the future
-    let clock = Clock::from_account_info(clock_sysvar_info)?;
-    if unlock_time <= clock.unix_timestamp {
-        return Err(VaultError::InvalidUnlockTime.into());
+    // Verify the sweep was announced at least `SWEEP_ALL_DELAY` ago
+    let now = Clock::get()?.unix_timestamp;
+    let announced_at = vault.sweep_announced_at.ok_or(VaultError::SweepNotAnnounced)?;
+    if now < announced_at.saturating_add(SWEEP_ALL_DELAY) {
+        return Err(VaultError::SweepDelayNotElapsed.into());
     }
     
-    // Verify the source token account has sufficient funds
-    let source_token_account = TokenAccount::unpack(&source_token_account_info.data.borrow())?;
-    if source_token_account.amount < amount {
-        return Err(VaultError::InsufficientFunds.into());
+    // Verify the payout goes to the emergency destination's token account
+    let emergency_destination = vault.emergency_destination.ok_or(VaultError::NoEmergencyDestination)?;
+    let expected_destination =
+        spl_associated_token_account::get_associated_token_address(&emergency_destination, &mint);
+    if *destination_token_account_info.key != expected_destination {
+        return Err(VaultError::InvalidDestinationAccount.into());
     }
     
-    // Create a new deposit
-    let deposit = Deposit {
-        id: vault.deposit_count,
-        depositor: *depositor_info.key,
-        token_mint: source_token_account.mint,
-        amount,
-        unlock_time,
-        withdrawn: false,
-        tag,
-        created_at: clock.unix_timestamp,
-    };
+    // Verify the token account belongs to the vault and holds the mint
+    let vault_token_account = TokenAccount::unpack(&vault_token_account_info.data.borrow())?;
+    if vault_token_account.owner != *vault_account_info.key {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if vault_token_account.mint != mint {
+        return Err(VaultError::TokenMintMismatch.into());
+    }
     
-    // Add the deposit to the vault
-    vault.deposits.push(deposit);
-    vault.deposit_count = vault.deposit_count.checked_add(1)
-        .ok_or(VaultError::MathOverflow)?;
+    // Mark every deposit of the mint still open as swept; deposits withdrawn
+    // during the announcement window are already settled, and those still in
+    // their post-unlock grace period stay open for their depositors
+    let post_unlock_grace = vault.post_unlock_grace;
+    let mut swept = 0u64;
+    let mut held: u64 = 0;
+    for deposit_index in 0..vault.deposits.len() {
+        let deposit = &mut vault.deposits[deposit_index];
+        if deposit.token_mint != mint || deposit.is_withdrawn() {
+            continue;
+        }
+        if check_post_unlock_grace(post_unlock_grace, deposit, now).is_err() {
+            held = held.checked_add(deposit.amount).ok_or(VaultError::MathOverflow)?;
+            continue;
+        }
+        deposit.status = DepositStatus::Swept as u8;
+        deposit.withdrawn_at = Some(now);
+        release_deposit_capacity(&mut vault, deposit_index)?;
+        update_stats_for_withdrawal(stats_info, &vault, deposit_index, Clock::get()?.slot)?;
+        swept = swept.checked_add(1).ok_or(VaultError::MathOverflow)?;
+    }
     
-    // Transfer tokens from the depositor to the vault
-    let transfer_instruction = spl_token::instruction::transfer(
-        token_program_info.key,
-        source_token_account_info.key,
-        destination_token_account_info.key,
-        depositor_info.key,
-        &[],
+    // Move whatever the vault still holds of the mint beyond the deposits
+    // left open
+    let amount = vault_token_account.amount.saturating_sub(held);
+    transfer_from_vault(
+        vault_account_info,
+        vault_token_account_info,
+        destination_token_account_info,
+        token_program_info,
         amount,
     )?;
     
-    invoke(
-        &transfer_instruction,
-        &[
-            source_token_account_info.clone(),
-            destination_token_account_info.clone(),
-            depositor_info.clone(),
-            token_program_info.clone(),
-        ],
-    )?;
-    
     // Reset reentrancy guard
     vault.reentrancy_guard = false;
     
     // Serialize and store the updated vault data
-    vault.serialize(&mut *vault_account_info.data.borrow_mut())?;
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
     
-    msg!("Deposit successful: {} tokens locked until timestamp {}", amount, unlock_time);
+    vault_event!(b"sweep_all", mint, amount.to_le_bytes(), swept.to_le_bytes(), now.to_le_bytes());
+    vault_log!("Swept {} tokens of mint {} and {} open deposits to {}", amount, mint, swept, emergency_destination);
     Ok(())
 }
 
-// Process withdraw instruction
-fn process_withdraw(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    deposit_id: u64,
-) -> ProgramResult {
+// Process set co-owner instruction
+fn process_set_co_owner(program_id: &Pubkey, accounts: &[AccountInfo], co_owner: Pubkey, roles: u8) -> ProgramResult {
+    if roles == 0 || roles & !ALL_CO_OWNER_ROLES != 0 {
+        return Err(VaultError::InvalidCoOwner.into());
+    }
+    
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        if co_owner == vault.owner {
+            return Err(VaultError::InvalidCoOwner.into());
+        }
+        let co_owner_count = vault.co_owners.len();
+        match vault.co_owners.iter_mut().find(|(key, _)| *key == co_owner) {
+            Some(entry) => entry.1 = roles,
+            None if co_owner_count >= MAX_CO_OWNERS => return Err(VaultError::TooManyCoOwners.into()),
+            None => vault.co_owners.push((co_owner, roles)),
+        }
+        Ok(())
+    })?;
+    
+    vault_log!("Co-owner {} holds roles {:#06b}", co_owner, roles);
+    Ok(())
+}
+
+// Process remove co-owner instruction
+fn process_remove_co_owner(program_id: &Pubkey, accounts: &[AccountInfo], co_owner: Pubkey) -> ProgramResult {
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        vault.co_owners.retain(|(key, _)| *key != co_owner);
+        Ok(())
+    })?;
+    
+    vault_log!("Co-owner {} removed", co_owner);
+    Ok(())
+}
+
+// Process release early instruction
+fn process_release_early(program_id: &Pubkey, accounts: &[AccountInfo], deposit_id: u64) -> ProgramResult {
+    let (accounts, stats_info) = split_program_stats(program_id, accounts);
     let account_info_iter = &mut accounts.iter();
     
     // Get accounts
@@ -342,7 +11702,9 @@ fn process_withdraw(
     let destination_token_account_info = next_account_info(account_info_iter)?;
     let source_token_account_info = next_account_info(account_info_iter)?;
     let token_program_info = next_account_info(account_info_iter)?;
-    let clock_sysvar_info = next_account_info(account_info_iter)?;
+    
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
     
     // Verify the owner signed the transaction
     if !owner_info.is_signer {
@@ -355,7 +11717,15 @@ fn process_withdraw(
     }
     
     // Load the vault
-    let mut vault = Vault::try_from_slice(&vault_account_info.data.borrow())?;
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Set the withdraw hook accounts aside, so the optional accounts before
+    // them are read as usual
+    let (optional_accounts, hook) = split_withdraw_hook(&vault, account_info_iter.as_slice())?;
+    let account_info_iter = &mut optional_accounts.iter();
+    
+    // Get the depositor's position index, if the vault keeps them
+    let position_index_info = next_position_index(&vault, account_info_iter)?;
     
     // Check reentrancy guard
     if vault.reentrancy_guard {
@@ -365,79 +11735,265 @@ fn process_withdraw(
     // Set reentrancy guard
     vault.reentrancy_guard = true;
     
+    // Verify the signer owns the vault and still holds its powers
+    require_role(&vault, accounts, OWNER_ONLY)?;
+    
     // Find the deposit
     let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
         .ok_or(VaultError::DepositNotFound)?;
     let deposit = &mut vault.deposits[deposit_index];
+    check_token_deposit(deposit)?;
+    check_deposit_open(deposit)?;
     
-    // Verify the owner is the depositor
-    if deposit.depositor != *owner_info.key {
+    // Verify the depositor consented when depositing; a position NFT holder
+    // may no longer be the depositor, so tokenized deposits never qualify
+    if !deposit.owner_can_release_early {
+        return Err(VaultError::EarlyReleaseNotAllowed.into());
+    }
+    if deposit.position_mint.is_some() {
         return Err(VaultError::UnauthorizedWithdrawal.into());
     }
     
-    // Verify the deposit has not been withdrawn
-    if deposit.withdrawn {
-        return Err(VaultError::AlreadyWithdrawn.into());
+    // Verify the payout goes to the depositor: the locked destination if the
+    // deposit has one, otherwise a token account the depositor owns
+    if *destination_token_account_info.owner != spl_token::id() {
+        return Err(VaultError::InvalidDestinationAccount.into());
     }
-    
-    // Verify the unlock time has been reached
-    let clock = Clock::from_account_info(clock_sysvar_info)?;
-    if deposit.unlock_time > clock.unix_timestamp {
-        return Err(VaultError::UnlockTimeNotReached.into());
+    let destination = TokenAccount::unpack(&destination_token_account_info.data.borrow())?;
+    match deposit.locked_destination {
+        Some(_) => check_locked_destination(deposit, destination_token_account_info.key)?,
+        None if destination.owner != deposit.depositor => return Err(VaultError::InvalidDestinationAccount.into()),
+        None => {},
     }
+    check_payout_mint(deposit, &destination.mint)?;
     
-    // Mark the deposit as withdrawn
-    deposit.withdrawn = true;
+    // Mark the deposit as released
+    let now = Clock::get()?.unix_timestamp;
+    deposit.status = DepositStatus::ReleasedEarly as u8;
+    deposit.withdrawn_at = Some(now);
+    let amount = deposit.amount;
+    let depositor = deposit.depositor;
     
-    // Transfer tokens from the vault to the owner
-    let transfer_instruction = spl_token::instruction::transfer(
-        token_program_info.key,
-        source_token_account_info.key,
-        destination_token_account_info.key,
-        &vault_account_info.key,
-        &[],
-        deposit.amount,
-    )?;
+    // Count the release against the depositor's rate limit window
+    debit_rate_limit(&mut vault, &depositor, amount, now)?;
     
-    invoke_signed(
-        &transfer_instruction,
-        &[
-            source_token_account_info.clone(),
-            destination_token_account_info.clone(),
-            vault_account_info.clone(),
-            token_program_info.clone(),
-        ],
-        &[&[&vault_account_info.key.to_bytes(), &[0]]],
+    // Free the deposit's share of the vault and mint limits
+    release_deposit_capacity(&mut vault, deposit_index)?;
+    update_stats_for_withdrawal(stats_info, &vault, deposit_index, Clock::get()?.slot)?;
+    
+    // Drop the deposit from its depositor's position index
+    remove_from_position_index(program_id, vault_account_info.key, &depositor, position_index_info, &[deposit_id])?;
+    
+    // Transfer tokens from the vault to the depositor
+    transfer_from_vault(
+        vault_account_info,
+        source_token_account_info,
+        destination_token_account_info,
+        token_program_info,
+        amount,
     )?;
     
+    // Call the withdraw hook once the tokens have moved
+    call_withdraw_hook(&vault, vault_account_info, hook.as_ref(), &[deposit_id])?;
+    
     // Reset reentrancy guard
     vault.reentrancy_guard = false;
     
     // Serialize and store the updated vault data
-    vault.serialize(&mut *vault_account_info.data.borrow_mut())?;
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
     
-    msg!("Withdrawal successful: {} tokens from deposit {}", deposit.amount, deposit_id);
+    vault_event!(
+        b"withdraw",
+        deposit_id.to_le_bytes(),
+        amount.to_le_bytes(),
+        now.to_le_bytes(),
+        [DepositStatus::ReleasedEarly as u8],
+    );
+    vault_log!("Deposit {} released early: {} tokens to {}", deposit_id, amount, depositor);
     Ok(())
 }
 
-// Process emergency withdraw instruction
-fn process_emergency_withdraw(
+// Process set reward mints instruction
+fn process_set_reward_mints(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    deposit_id: u64,
+    reward_mint: Pubkey,
+    weight_mint: Pubkey,
+) -> ProgramResult {
+    process_owner_update(program_id, accounts, OWNER_ONLY, |vault| {
+        if vault.reward_distribution.is_some() {
+            return Err(VaultError::RewardDistributionInProgress.into());
+        }
+        // Pending rewards are counted in the current reward mint, whose
+        // leftover dust becomes surplus once it changes
+        if vault.reward_mint != Some(reward_mint) {
+            if vault.deposits.iter().any(|deposit| deposit.pending_rewards > 0) {
+                return Err(VaultError::RewardsOutstanding.into());
+            }
+            vault.reward_carry = 0;
+        }
+        vault.reward_mint = Some(reward_mint);
+        vault.reward_weight_mint = Some(weight_mint);
+        Ok(())
+    })?;
+    
+    vault_log!("Rewards in {} go to deposits of {}", reward_mint, weight_mint);
+    Ok(())
+}
+
+// Process distribute rewards instruction
+fn process_distribute_rewards(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint: Pubkey,
+    total: u64,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     
     // Get accounts
-    let emergency_authority_info = next_account_info(account_info_iter)?;
+    let caller_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    
+    // Verify the caller signed the transaction
+    if !caller_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Load the vault
+    let mut vault = load_vault(vault_account_info)?;
+    
+    // Check reentrancy guard
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected.into());
+    }
+    
+    // Verify the reward mint
+    let (reward_mint, weight_mint) = match (vault.reward_mint, vault.reward_weight_mint) {
+        (Some(reward_mint), Some(weight_mint)) => (reward_mint, weight_mint),
+        _ => return Err(VaultError::RewardsNotConfigured.into()),
+    };
+    if mint != reward_mint {
+        return Err(VaultError::TokenMintMismatch.into());
+    }
+    
+    // Start a distribution: weigh the active deposits and take the donation
+    if total > 0 {
+        let source_token_account_info = next_account_info(account_info_iter)?;
+        let vault_token_account_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        
+        // Verify the token program account
+        checks::token_program(accounts, token_program_info)?;
+        
+        if vault.reward_distribution.is_some() {
+            return Err(VaultError::RewardDistributionInProgress.into());
+        }
+        let total_weight: u128 = vault.deposits.iter()
+            .filter(|deposit| !deposit.is_withdrawn() && deposit.token_mint == weight_mint)
+            .map(|deposit| deposit.amount as u128)
+            .sum();
+        if total_weight == 0 {
+            return Err(VaultError::NoRewardWeight.into());
+        }
+        
+        // Verify the token account belongs to the vault and holds the reward mint
+        let vault_token_account = TokenAccount::unpack(&vault_token_account_info.data.borrow())?;
+        if vault_token_account.owner != *vault_account_info.key {
+            return Err(ProgramError::IllegalOwner);
+        }
+        if vault_token_account.mint != reward_mint {
+            return Err(VaultError::TokenMintMismatch.into());
+        }
+        
+        invoke(
+            &spl_token::instruction::transfer(
+                token_program_info.key,
+                source_token_account_info.key,
+                vault_token_account_info.key,
+                caller_info.key,
+                &[],
+                total,
+            )?,
+            &[
+                source_token_account_info.clone(),
+                vault_token_account_info.clone(),
+                caller_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+        
+        vault.reward_distribution = Some(RewardDistribution {
+            total: total.checked_add(vault.reward_carry).ok_or(VaultError::MathOverflow)?,
+            total_weight,
+            distributed: 0,
+            next_id: 0,
+            end_id: vault.deposit_count,
+        });
+        vault.reward_carry = 0;
+        vault_event!(b"rewards_donated", reward_mint, total.to_le_bytes(), caller_info.key);
+    }
+    
+    // Allocate to the next chunk of deposits, in ID order
+    let mut distribution = vault.reward_distribution.take().ok_or(VaultError::NoRewardDistribution)?;
+    let mut visited = 0;
+    for deposit in vault.deposits.iter_mut() {
+        if deposit.id < distribution.next_id || deposit.id >= distribution.end_id {
+            continue;
+        }
+        if visited == MAX_REWARD_DEPOSITS_PER_CALL {
+            break;
+        }
+        visited += 1;
+        distribution.next_id = deposit.id + 1;
+        
+        // Deposits withdrawn since the distribution started forfeit their share to the carry
+        if deposit.is_withdrawn() || deposit.token_mint != weight_mint {
+            continue;
+        }
+        let share = reward_share(distribution.total, deposit.amount, distribution.total_weight);
+        deposit.pending_rewards = deposit.pending_rewards.checked_add(share).ok_or(VaultError::MathOverflow)?;
+        distribution.distributed += share;
+    }
+    
+    // Finish once every deposit has been visited, carrying the dust over
+    let finished = !vault.deposits.iter()
+        .any(|deposit| deposit.id >= distribution.next_id && deposit.id < distribution.end_id);
+    if finished {
+        let dust = distribution.total - distribution.distributed;
+        vault.reward_carry = vault.reward_carry.checked_add(dust).ok_or(VaultError::MathOverflow)?;
+        vault_event!(b"rewards_distributed", reward_mint, distribution.distributed.to_le_bytes(), dust.to_le_bytes());
+        vault_log!("Distributed {} reward tokens, carrying {} over", distribution.distributed, dust);
+    } else {
+        vault_log!("Reward distribution continues from deposit {}", distribution.next_id);
+        vault.reward_distribution = Some(distribution);
+    }
+    
+    // Serialize and store the updated vault data
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Process claim rewards instruction
+fn process_claim_rewards(program_id: &Pubkey, accounts: &[AccountInfo], deposit_id: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let claimant_info = next_account_info(account_info_iter)?;
     let vault_account_info = next_account_info(account_info_iter)?;
     let destination_token_account_info = next_account_info(account_info_iter)?;
     let source_token_account_info = next_account_info(account_info_iter)?;
     let token_program_info = next_account_info(account_info_iter)?;
-    let depositor_info = next_account_info(account_info_iter)?;
     
-    // Verify the emergency authority signed the transaction
-    if !emergency_authority_info.is_signer {
+    // Verify the token program account
+    checks::token_program(accounts, token_program_info)?;
+    
+    // Verify the claimant signed the transaction
+    if !claimant_info.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
     
@@ -447,7 +12003,7 @@ fn process_emergency_withdraw(
     }
     
     // Load the vault
-    let mut vault = Vault::try_from_slice(&vault_account_info.data.borrow())?;
+    let mut vault = load_vault(vault_account_info)?;
     
     // Check reentrancy guard
     if vault.reentrancy_guard {
@@ -456,57 +12012,99 @@ fn process_emergency_withdraw(
     
     // Set reentrancy guard
     vault.reentrancy_guard = true;
+    let reward_mint = vault.reward_mint.ok_or(VaultError::RewardsNotConfigured)?;
     
-    // Verify the emergency authority is authorized
-    if vault.emergency_authority.is_none() || vault.emergency_authority.unwrap() != *emergency_authority_info.key {
+    // Find the deposit and verify the claimant may withdraw it
+    let deposit = vault.deposits.iter_mut().find(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    if deposit.position_mint.is_some() {
         return Err(VaultError::UnauthorizedWithdrawal.into());
     }
+    check_withdrawer(deposit, claimant_info.key)?;
     
-    // Find the deposit
-    let deposit_index = vault.deposits.iter().position(|d| d.id == deposit_id)
-        .ok_or(VaultError::DepositNotFound)?;
-    let deposit = &mut vault.deposits[deposit_index];
-    
-    // Verify the deposit has not been withdrawn
-    if deposit.withdrawn {
-        return Err(VaultError::AlreadyWithdrawn.into());
+    // Verify the destination holds the reward mint
+    let destination = TokenAccount::unpack_unchecked(&destination_token_account_info.data.borrow())?;
+    if destination.mint != reward_mint {
+        return Err(VaultError::TokenMintMismatch.into());
     }
     
-    // Verify the depositor account matches the deposit's depositor
-    if deposit.depositor != *depositor_info.key {
-        return Err(VaultError::UnauthorizedWithdrawal.into());
+    // Take the pending rewards
+    let amount = std::mem::take(&mut deposit.pending_rewards);
+    if amount == 0 {
+        return Err(VaultError::NoPendingRewards.into());
     }
     
-    // Mark the deposit as withdrawn
-    deposit.withdrawn = true;
-    
-    // Transfer tokens from the vault to the depositor
-    let transfer_instruction = spl_token::instruction::transfer(
-        token_program_info.key,
-        source_token_account_info.key,
-        destination_token_account_info.key,
-        &vault_account_info.key,
-        &[],
-        deposit.amount,
-    )?;
-    
-    invoke_signed(
-        &transfer_instruction,
-        &[
-            source_token_account_info.clone(),
-            destination_token_account_info.clone(),
-            vault_account_info.clone(),
-            token_program_info.clone(),
-        ],
-        &[&[&vault_account_info.key.to_bytes(), &[0]]],
+    // Transfer the rewards from the vault to the claimant
+    transfer_from_vault(
+        vault_account_info,
+        source_token_account_info,
+        destination_token_account_info,
+        token_program_info,
+        amount,
     )?;
     
     // Reset reentrancy guard
     vault.reentrancy_guard = false;
     
     // Serialize and store the updated vault data
-    vault.serialize(&mut *vault_account_info.data.borrow_mut())?;
+    vault.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    
+    vault_event!(b"rewards_claimed", deposit_id.to_le_bytes(), amount.to_le_bytes());
+    vault_log!("Claimed {} reward tokens for deposit {}", amount, deposit_id);
+    Ok(())
+}
+
+// Process view deposit instruction; logs unconditionally since the log line
+// is the point of the instruction
+fn process_view_deposit(program_id: &Pubkey, accounts: &[AccountInfo], deposit_id: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    // Get accounts
+    let vault_account_info = next_account_info(account_info_iter)?;
+    
+    // Verify the vault account is owned by the program
+    if vault_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Load the vault and find the deposit
+    let vault = load_vault(vault_account_info)?;
+    let deposit = vault.deposits.iter().find(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
     
-    msg!("Emergency withdrawal successful: {} tokens from deposit {}", deposit.amount, deposit_id);
+    let status = match deposit.status() {
+        Some(status) => format!("{:?}", status),
+        None => format!("unknown ({})", deposit.status),
+    };
+    match deposit.unlock_slot {
+        Some(unlock_slot) => msg!(
+            "Deposit {}: {} of mint {} by {}, {}, unlocks at slot {}",
+            deposit.id,
+            deposit.amount,
+            deposit.token_mint,
+            deposit.depositor,
+            status,
+            unlock_slot,
+        ),
+        None => msg!(
+            "Deposit {}: {} of mint {} by {}, {}, unlocks at {}",
+            deposit.id,
+            deposit.amount,
+            deposit.token_mint,
+            deposit.depositor,
+            status,
+            deposit.unlock_time,
+        ),
+    }
+    set_return_data(&deposit.try_to_vec()?);
+    Ok(())
+}
+
+// Process get version instruction
+fn process_get_version() -> ProgramResult {
+    let current = ProgramVersion::CURRENT;
+    let (major, minor, patch) = current.version;
+    msg!("Time-locked vault v{}.{}.{}, features {:#x}", major, minor, patch, current.features);
+    set_return_data(&current.to_return_data());
     Ok(())
 }
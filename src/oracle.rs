@@ -0,0 +1,78 @@
+//! Price reads for price-conditioned unlocks. Pyth price accounts are parsed
+//! with the `pyth` feature; without it every price read fails.
+
+use crate::{PriceCondition, PriceOracleConfig, VaultError, BPS_DENOMINATOR};
+use solana_program::account_info::AccountInfo;
+
+/// An aggregate price as published by the oracle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OraclePrice {
+    /// Price, scaled by 10^expo
+    pub price: i64,
+    /// Confidence interval, scaled by 10^expo
+    pub conf: u64,
+    /// Exponent of `price` and `conf`
+    pub expo: i32,
+    /// Slot the price was published in
+    pub publish_slot: u64,
+}
+
+// Read the aggregate price of a Pyth price account; prices that aren't
+// currently trading count as stale
+#[cfg(feature = "pyth")]
+pub(crate) fn load_price(price_info: &AccountInfo) -> Result<OraclePrice, VaultError> {
+    use pyth_sdk_solana::state::{load_price_account, PriceStatus};
+    
+    let data = price_info.try_borrow_data().map_err(|_| VaultError::InvalidPriceFeed)?;
+    let account = load_price_account(&data).map_err(|_| VaultError::InvalidPriceFeed)?;
+    if account.agg.status != PriceStatus::Trading {
+        return Err(VaultError::StalePrice);
+    }
+    Ok(OraclePrice {
+        price: account.agg.price,
+        conf: account.agg.conf,
+        expo: account.expo,
+        publish_slot: account.agg.pub_slot,
+    })
+}
+
+#[cfg(not(feature = "pyth"))]
+pub(crate) fn load_price(_price_info: &AccountInfo) -> Result<OraclePrice, VaultError> {
+    Err(VaultError::InvalidPriceFeed)
+}
+
+// Fail unless `price` is fresh and tight enough under `config` and at or
+// above the condition's threshold
+pub(crate) fn check_price_condition(
+    condition: &PriceCondition,
+    config: &PriceOracleConfig,
+    price: &OraclePrice,
+    slot: u64,
+) -> Result<(), VaultError> {
+    if price.publish_slot > slot || slot - price.publish_slot > config.max_age_slots {
+        return Err(VaultError::StalePrice);
+    }
+    let max_conf = price.price.unsigned_abs() as u128 * config.max_conf_bps as u128;
+    if price.conf as u128 * BPS_DENOMINATOR as u128 > max_conf {
+        return Err(VaultError::PriceConfidenceTooWide);
+    }
+    
+    // Compare both values at the smaller exponent
+    let (price_value, threshold) = if price.expo >= condition.expo {
+        (rescale(price.price, price.expo - condition.expo)?, condition.threshold as i128)
+    } else {
+        (price.price as i128, rescale(condition.threshold, condition.expo - price.expo)?)
+    };
+    if price_value < threshold {
+        return Err(VaultError::PriceThresholdNotMet);
+    }
+    Ok(())
+}
+
+// `value` × 10^`shift`
+fn rescale(value: i64, shift: i32) -> Result<i128, VaultError> {
+    10i128
+        .checked_pow(shift as u32)
+        .and_then(|factor| factor.checked_mul(value as i128))
+        .ok_or(VaultError::MathOverflow)
+}
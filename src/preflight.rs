@@ -0,0 +1,101 @@
+//! Off-chain checks predicting whether an instruction would fail, run with the
+//! same predicates as the on-chain handlers
+
+use crate::{
+    check_deposit_amount, check_deposit_open, check_depositor_admitted, check_deposits_open,
+    check_locked_destination, check_payout_mint, check_source_balance, check_token_deposit,
+    check_token_state_not_frozen, check_unlock_in_future, check_unlocked, check_withdraw_queue, check_withdrawer,
+    debit_rate_limit, reserve_deposit_capacity, Vault, VaultError,
+};
+use solana_program::pubkey::Pubkey;
+use spl_token::state::{Account as TokenAccount, AccountState};
+
+/// The parts of a token account the preflight checks read
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TokenAccountInfoLite {
+    /// Address of the token account
+    pub address: Pubkey,
+    /// Mint of the tokens it holds
+    pub mint: Pubkey,
+    /// Wallet owning the tokens
+    pub owner: Pubkey,
+    /// Balance in raw token units
+    pub amount: u64,
+    /// Whether the account is initialized or frozen
+    pub state: AccountState,
+}
+
+impl TokenAccountInfoLite {
+    /// Summarize an unpacked token account
+    pub fn new(address: Pubkey, account: &TokenAccount) -> Self {
+        Self {
+            address,
+            mint: account.mint,
+            owner: account.owner,
+            amount: account.amount,
+            state: account.state,
+        }
+    }
+}
+
+/// Predict the outcome of a `Withdraw` of `deposit_id` signed by `signer` at
+/// `now`. The destination checks are skipped when it is `None`, and the holder
+/// of a position NFT can't be verified here, so tokenized deposits only get
+/// the remaining checks. Same-slot protection and the unlock slot of
+/// slot-locked deposits depend on the slot the transaction lands in and aren't
+/// predicted.
+pub fn preflight_withdraw(
+    vault: &Vault,
+    deposit_id: u64,
+    signer: &Pubkey,
+    now: i64,
+    destination: Option<&TokenAccountInfoLite>,
+) -> Result<(), VaultError> {
+    if let Some(destination) = destination {
+        check_token_state_not_frozen(destination.state)?;
+    }
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected);
+    }
+    let deposit = vault.deposits.iter().find(|d| d.id == deposit_id)
+        .ok_or(VaultError::DepositNotFound)?;
+    check_token_deposit(deposit)?;
+    if deposit.position_mint.is_none() {
+        check_withdrawer(deposit, signer)?;
+    }
+    check_deposit_open(deposit)?;
+    check_unlocked(deposit, now)?;
+    check_withdraw_queue(deposit, false, vault.withdraw_delay, now)?;
+    if let Some(destination) = destination {
+        check_locked_destination(deposit, &destination.address)?;
+        check_payout_mint(deposit, &destination.mint)?;
+    }
+    debit_rate_limit(&mut vault.clone(), signer, deposit.amount, now)
+}
+
+/// Predict the outcome of a `Deposit` by `depositor` at `now`. The source
+/// checks are skipped when it is `None`.
+pub fn preflight_deposit(
+    vault: &Vault,
+    depositor: &Pubkey,
+    amount: u64,
+    unlock_time: i64,
+    now: i64,
+    source: Option<&TokenAccountInfoLite>,
+) -> Result<(), VaultError> {
+    if let Some(source) = source {
+        check_token_state_not_frozen(source.state)?;
+    }
+    if vault.reentrancy_guard {
+        return Err(VaultError::ReentrancyDetected);
+    }
+    check_deposits_open(vault)?;
+    check_depositor_admitted(vault, depositor, depositor)?;
+    check_deposit_amount(amount)?;
+    check_unlock_in_future(unlock_time, now)?;
+    if let Some(source) = source {
+        check_source_balance(source.amount, amount)?;
+        reserve_deposit_capacity(&mut vault.clone(), &source.mint, amount)?;
+    }
+    Ok(())
+}
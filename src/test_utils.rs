@@ -0,0 +1,55 @@
+//! Synthetic vaults for benchmarks and tests (enabled with the `test-utils` feature)
+//!
+//! The data is deterministic: the same size always builds the same vault, so
+//! benchmark runs compare like with like.
+
+use crate::{Deposit, DepositStatus, Vault};
+use solana_program::pubkey::Pubkey;
+
+/// Vault sizes the benchmarks sweep, in deposits
+pub const SYNTHETIC_VAULT_SIZES: &[usize] = &[10, 100, 1_000, 5_000];
+
+// Number of distinct depositors and mints the synthetic deposits cycle through
+const SYNTHETIC_DEPOSITORS: u64 = 64;
+const SYNTHETIC_MINTS: u64 = 4;
+
+/// A deterministic pubkey for seed `seed`
+pub fn synthetic_pubkey(seed: u64) -> Pubkey {
+    let mut bytes = [0u8; 32];
+    bytes[..8].copy_from_slice(&seed.to_le_bytes());
+    bytes[31] = 1;
+    Pubkey::new_from_array(bytes)
+}
+
+/// Deposit `id` of a synthetic vault: depositors, mints, tags and unlock times
+/// cycle, and every tenth deposit has been withdrawn
+pub fn synthetic_deposit(id: u64) -> Deposit {
+    let mut tag = [0u8; 32];
+    tag[0] = (id % 8) as u8;
+    let status = if id % 10 == 9 { DepositStatus::Withdrawn } else { DepositStatus::Active };
+    Deposit {
+        id,
+        depositor: synthetic_pubkey(id % SYNTHETIC_DEPOSITORS),
+        token_mint: synthetic_pubkey(SYNTHETIC_DEPOSITORS + id % SYNTHETIC_MINTS),
+        amount: 1_000 + id,
+        unlock_time: 1_700_000_000 + (id as i64 * 7_919) % 31_536_000,
+        status: status as u8,
+        tag,
+        created_at: 1_690_000_000 + id as i64,
+        ..Deposit::default()
+    }
+}
+
+/// An initialized vault holding `deposits` synthetic deposits with IDs
+/// `0..deposits`, in ID order as the program appends them
+pub fn synthetic_vault(deposits: usize) -> Vault {
+    let deposits: Vec<Deposit> = (0..deposits as u64).map(synthetic_deposit).collect();
+    Vault {
+        is_initialized: true,
+        owner: synthetic_pubkey(u64::MAX),
+        deposit_count: deposits.len() as u64,
+        total_locked: deposits.iter().filter(|deposit| !deposit.is_withdrawn()).map(|deposit| deposit.amount).sum(),
+        deposits,
+        ..Vault::default()
+    }
+}
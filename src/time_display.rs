@@ -0,0 +1,164 @@
+//! Timestamp formatting and parsing for clients (enabled with the `time-display` feature)
+
+use crate::{Deposit, DepositStatus};
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use std::fmt;
+
+/// Why an unlock time argument couldn't be parsed
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum ParseUnlockTimeError {
+    /// Not a unix timestamp, an RFC 3339 date or a `+<n><unit>` offset
+    #[error("unrecognized unlock time {0:?}")]
+    Unrecognized(String),
+    /// The relative offset uses a unit other than s, m, h, d or w
+    #[error("unknown time unit {0:?}")]
+    UnknownUnit(char),
+    /// The resulting timestamp doesn't fit in an i64
+    #[error("unlock time out of range")]
+    OutOfRange,
+}
+
+/// Parse an unlock time given as a unix timestamp, an RFC 3339 date
+/// (`2025-01-01T00:00:00Z`) or an offset from `now` such as `+30d`
+/// (units: s, m, h, d, w)
+pub fn parse_unlock_time(input: &str, now: i64) -> Result<i64, ParseUnlockTimeError> {
+    let input = input.trim();
+    if let Some(offset) = input.strip_prefix('+') {
+        let unit = offset.chars().last().ok_or_else(|| ParseUnlockTimeError::Unrecognized(input.to_string()))?;
+        let seconds_per_unit: i64 = match unit {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            unit if unit.is_ascii_digit() => return Err(ParseUnlockTimeError::Unrecognized(input.to_string())),
+            unit => return Err(ParseUnlockTimeError::UnknownUnit(unit)),
+        };
+        let count: i64 = offset[..offset.len() - 1]
+            .parse()
+            .map_err(|_| ParseUnlockTimeError::Unrecognized(input.to_string()))?;
+        return count
+            .checked_mul(seconds_per_unit)
+            .and_then(|seconds| now.checked_add(seconds))
+            .ok_or(ParseUnlockTimeError::OutOfRange);
+    }
+    if let Ok(timestamp) = input.parse::<i64>() {
+        return Ok(timestamp);
+    }
+    DateTime::parse_from_rfc3339(input)
+        .map(|datetime| datetime.timestamp())
+        .map_err(|_| ParseUnlockTimeError::Unrecognized(input.to_string()))
+}
+
+/// Format a unix timestamp as ISO 8601 in UTC, or as `@<timestamp>` when it
+/// is outside the representable date range
+pub fn format_timestamp(timestamp: i64) -> String {
+    match datetime_utc(timestamp) {
+        Some(datetime) => datetime.to_rfc3339_opts(SecondsFormat::Secs, true),
+        None => format!("@{}", timestamp),
+    }
+}
+
+fn datetime_utc(timestamp: i64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(timestamp, 0).single()
+}
+
+impl Deposit {
+    /// The unlock time in UTC, if it is within the representable date range
+    pub fn unlock_datetime_utc(&self) -> Option<DateTime<Utc>> {
+        datetime_utc(self.unlock_time)
+    }
+    
+    /// The creation time in UTC, if it is within the representable date range
+    pub fn created_datetime_utc(&self) -> Option<DateTime<Utc>> {
+        datetime_utc(self.created_at)
+    }
+    
+    /// The withdrawal time in UTC, if the deposit was withdrawn and the time
+    /// is within the representable date range
+    pub fn withdrawn_datetime_utc(&self) -> Option<DateTime<Utc>> {
+        self.withdrawn_at.and_then(datetime_utc)
+    }
+    
+    /// Render the deposit with its maturity judged at `now`
+    pub fn display_at(&self, now: i64) -> DepositDisplay<'_> {
+        DepositDisplay { deposit: self, now }
+    }
+}
+
+/// A deposit rendered with its maturity at a given time
+pub struct DepositDisplay<'a> {
+    deposit: &'a Deposit,
+    now: i64,
+}
+
+// How a deposit that is no longer active ended, for display
+fn terminal_status(deposit: &Deposit) -> Option<&'static str> {
+    match deposit.status() {
+        Some(DepositStatus::Active) => None,
+        Some(DepositStatus::Withdrawn) => Some("withdrawn"),
+        Some(DepositStatus::EmergencyWithdrawn) => Some("emergency withdrawn"),
+        Some(DepositStatus::Cancelled) => Some("cancelled"),
+        Some(DepositStatus::Swept) => Some("swept"),
+        Some(DepositStatus::ReleasedEarly) => Some("released early"),
+        None => Some("unknown status"),
+    }
+}
+
+impl fmt::Display for DepositDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let deposit = self.deposit;
+        write!(f, "Deposit #{}: {} tokens", deposit.id, deposit.amount)?;
+        let tag = String::from_utf8_lossy(&deposit.tag);
+        let tag = tag.trim_end_matches('\0');
+        if !tag.is_empty() {
+            write!(f, " (tag: {})", tag)?;
+        }
+        if let Some(unlock_slot) = deposit.unlock_slot {
+            // Maturity depends on the slot, which isn't known here
+            let status = if let Some(terminal) = terminal_status(deposit) {
+                format!("{}, ", terminal)
+            } else if deposit.frozen {
+                "frozen, ".to_string()
+            } else {
+                String::new()
+            };
+            write!(
+                f,
+                ", {}unlocks at slot {}, created {}",
+                status,
+                unlock_slot,
+                format_timestamp(deposit.created_at),
+            )?;
+        } else {
+            let status = if let Some(terminal) = terminal_status(deposit) {
+                terminal
+            } else if deposit.frozen {
+                "frozen"
+            } else if deposit.unlock_time <= self.now {
+                "matured"
+            } else {
+                "locked"
+            };
+            write!(
+                f,
+                ", {}, unlocks {}, created {}",
+                status,
+                format_timestamp(deposit.unlock_time),
+                format_timestamp(deposit.created_at),
+            )?;
+        }
+        if let Some(withdrawn_at) = deposit.withdrawn_at {
+            write!(f, ", withdrawn at {}", format_timestamp(withdrawn_at))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Deposit {
+    /// Renders the deposit with its maturity judged by the system clock
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let now = Utc::now().timestamp();
+        self.display_at(now).fmt(f)
+    }
+}
@@ -0,0 +1,185 @@
+//! `wasm-bindgen` wrappers for browser clients (enabled with the `wasm` feature)
+//!
+//! Pubkeys cross the boundary as base58 strings. u64 values are returned as
+//! decimal strings because JavaScript numbers can't hold them exactly.
+
+use crate::{builder, Deposit, Vault};
+use borsh::BorshDeserialize;
+use js_sys::{Uint8Array, JSON};
+use serde_json::{json, Value};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// Build a `Deposit` instruction, returned as `{ programId, accounts, data }`
+/// where `accounts` holds `{ pubkey, isSigner, isWritable }` metas and `data`
+/// is a `Uint8Array`
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn js_create_deposit_ix(
+    program_id: &str,
+    depositor: &str,
+    vault: &str,
+    source: &str,
+    dest: &str,
+    amount: u64,
+    unlock_time: i64,
+    tag: &[u8],
+) -> Result<JsValue, JsError> {
+    let tag: [u8; 32] = tag.try_into()
+        .map_err(|_| JsError::new("tag must be exactly 32 bytes"))?;
+    let program_id = parse_pubkey(program_id)?;
+    if !crate::check_id(&program_id) {
+        return Err(JsError::new(&format!("program id {} is not the {} this build targets", program_id, crate::id())));
+    }
+    let instruction = builder::deposit(
+        &program_id,
+        &parse_pubkey(depositor)?,
+        &parse_pubkey(vault)?,
+        &parse_pubkey(source)?,
+        &parse_pubkey(dest)?,
+        amount,
+        unlock_time,
+        tag,
+    );
+    instruction_to_js(&instruction)
+}
+
+/// Decode vault account data into a plain JS object
+#[wasm_bindgen]
+pub fn js_decode_vault(bytes: &[u8]) -> Result<JsValue, JsError> {
+    let vault = Vault::deserialize(&mut &bytes[..])
+        .map_err(|e| JsError::new(&format!("invalid vault data: {}", e)))?;
+    to_js(&vault_to_json(&vault))
+}
+
+/// JSON view of a vault with base58 pubkeys and stringified u64s
+pub fn vault_to_json(vault: &Vault) -> Value {
+    json!({
+        "isInitialized": vault.is_initialized,
+        "owner": vault.owner.to_string(),
+        "depositCount": vault.deposit_count.to_string(),
+        "deposits": vault.deposits.iter().map(deposit_to_json).collect::<Vec<_>>(),
+        "reentrancyGuard": vault.reentrancy_guard,
+        "emergencyAuthority": vault.emergency_authority.map(|key| key.to_string()),
+        "freezeAuthority": vault.freeze_authority.map(|key| key.to_string()),
+        "positionNfts": vault.position_nfts,
+        "positionIndex": vault.position_index,
+        "allowedCallers": vault.allowed_callers.as_ref()
+            .map(|callers| callers.iter().map(|caller| caller.to_string()).collect::<Vec<_>>()),
+        "rejectTopLevel": vault.reject_top_level,
+        "gcDelay": vault.gc_delay.map(|delay| delay.to_string()),
+        "acceptingDeposits": vault.accepting_deposits(),
+        "ownerApprovedSplits": vault.owner_approved_splits,
+        "uniqueTags": vault.unique_tags,
+        "unlockGranularity": vault.unlock_granularity.to_string(),
+        "sweepAnnouncedAt": vault.sweep_announced_at.map(|at| at.to_string()),
+        "ownerSet": vault.owner_set.as_ref().map(|set| json!({
+            "members": set.members.iter().map(|member| member.to_string()).collect::<Vec<_>>(),
+            "threshold": set.threshold,
+        })),
+        "coOwners": vault.co_owners.iter().map(|(co_owner, roles)| json!({
+            "coOwner": co_owner.to_string(),
+            "roles": roles,
+        })).collect::<Vec<_>>(),
+        "rewardMint": vault.reward_mint.map(|mint| mint.to_string()),
+        "rewardWeightMint": vault.reward_weight_mint.map(|mint| mint.to_string()),
+        "rewardCarry": vault.reward_carry.to_string(),
+        "rewardDistribution": vault.reward_distribution.as_ref().map(|distribution| json!({
+            "total": distribution.total.to_string(),
+            "distributed": distribution.distributed.to_string(),
+            "nextId": distribution.next_id.to_string(),
+            "endId": distribution.end_id.to_string(),
+        })),
+        "lifetimeDepositsCreated": vault.lifetime_deposits_created.to_string(),
+        "lifetimeAmountDeposited": vault.lifetime_amount_deposited.to_string(),
+        "lifetimeAmountWithdrawn": vault.lifetime_amount_withdrawn.to_string(),
+        "lifetimeEmergencyWithdrawals": vault.lifetime_emergency_withdrawals.to_string(),
+        "tvlCap": vault.tvl_cap.map(|cap| cap.to_string()),
+        "totalLocked": vault.total_locked.to_string(),
+        "tagTotals": vault.tag_totals.iter().map(|entry| json!({
+            "tag": String::from_utf8_lossy(&entry.tag).trim_end_matches('\0'),
+            "total": entry.total.to_string(),
+        })).collect::<Vec<_>>(),
+        "otherTagsTotal": vault.other_tags_total.to_string(),
+        "renounced": vault.renounced,
+        "emergencyDestination": vault.emergency_destination.map(|key| key.to_string()),
+        "emergencyEvacuation": vault.emergency_evacuation.as_ref().map(|evacuation| json!({
+            "depositor": evacuation.depositor.to_string(),
+            "mint": evacuation.mint.to_string(),
+            "nextId": evacuation.next_id.to_string(),
+            "endId": evacuation.end_id.to_string(),
+        })),
+        "statsReconciled": vault.stats_reconciled,
+        "hookProgram": vault.hook_program.map(|key| key.to_string()),
+        "donationsDisabled": vault.donations_disabled,
+        "autoClose": vault.auto_close,
+    })
+}
+
+/// JSON view of a deposit with base58 pubkeys and stringified u64s
+pub fn deposit_to_json(deposit: &Deposit) -> Value {
+    json!({
+        "id": deposit.id.to_string(),
+        "depositor": deposit.depositor.to_string(),
+        "tokenMint": deposit.token_mint.to_string(),
+        "amount": deposit.amount.to_string(),
+        "unlockTime": deposit.unlock_time.to_string(),
+        "createdAt": deposit.created_at.to_string(),
+        "withdrawn": deposit.is_withdrawn(),
+        "status": deposit.status,
+        "withdrawnAt": deposit.withdrawn_at.map(|at| at.to_string()),
+        "stakeAccount": deposit.stake_account.map(|key| key.to_string()),
+        "ownerCanReleaseEarly": deposit.owner_can_release_early,
+        "pendingRewards": deposit.pending_rewards.to_string(),
+        "escrowCounterparty": deposit.escrow_counterparty.map(|key| key.to_string()),
+        "donationsReceived": deposit.donations_received.to_string(),
+        "frozen": deposit.frozen,
+        "tag": String::from_utf8_lossy(&deposit.tag).trim_end_matches('\0'),
+        "beneficiary": deposit.beneficiary.map(|key| key.to_string()),
+        "lockedDestination": deposit.locked_destination.map(|key| key.to_string()),
+        "positionMint": deposit.position_mint.map(|key| key.to_string()),
+        "linkedDeposit": deposit.linked_deposit.map(|id| id.to_string()),
+        "lockTier": deposit.lock_tier,
+        "unlockSlot": deposit.unlock_slot.map(|slot| slot.to_string()),
+        "priceCondition": deposit.price_condition.map(|condition| json!({
+            "feed": condition.feed.to_string(),
+            "threshold": condition.threshold.to_string(),
+            "expo": condition.expo,
+        })),
+        "accountCondition": deposit.account_condition.as_ref().map(|condition| json!({
+            "account": condition.account.to_string(),
+            "offset": condition.offset,
+            "expected": condition.expected,
+        })),
+    })
+}
+
+fn instruction_to_js(instruction: &Instruction) -> Result<JsValue, JsError> {
+    let accounts: Vec<Value> = instruction
+        .accounts
+        .iter()
+        .map(|meta| {
+            json!({
+                "pubkey": meta.pubkey.to_string(),
+                "isSigner": meta.is_signer,
+                "isWritable": meta.is_writable,
+            })
+        })
+        .collect();
+    let object = to_js(&json!({
+        "programId": instruction.program_id.to_string(),
+        "accounts": accounts,
+    }))?;
+    js_sys::Reflect::set(&object, &"data".into(), &Uint8Array::from(&instruction.data[..]))
+        .map_err(|_| JsError::new("failed to set instruction data"))?;
+    Ok(object)
+}
+
+fn parse_pubkey(key: &str) -> Result<Pubkey, JsError> {
+    Pubkey::from_str(key).map_err(|_| JsError::new(&format!("invalid pubkey: {}", key)))
+}
+
+fn to_js(value: &Value) -> Result<JsValue, JsError> {
+    JSON::parse(&value.to_string()).map_err(|_| JsError::new("failed to build JS object"))
+}
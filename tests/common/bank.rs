@@ -0,0 +1,79 @@
+// Shared fixtures for the tests running the vault in a solana-program-test bank
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{instruction::Instruction, program_option::COption, program_pack::Pack, pubkey::Pubkey};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use spl_token::state::{Account as TokenAccount, AccountState};
+use time_locked_vault::{process_instruction, Vault};
+
+// The vault program, for a test to add its own programs and accounts to
+pub fn vault_program_test() -> ProgramTest {
+    ProgramTest::new("time_locked_vault", time_locked_vault::id(), processor!(process_instruction))
+}
+
+// A vault keypair the program's payout signing seeds accept: `[vault, 0]` must
+// derive a program address, which fails for about half of all keys
+pub fn signing_vault() -> Keypair {
+    loop {
+        let vault = Keypair::new();
+        if Pubkey::create_program_address(&[&vault.pubkey().to_bytes(), &[0]], &time_locked_vault::id()).is_ok() {
+            return vault;
+        }
+    }
+}
+
+// A vault program account holding `vault`, padded to 4000 bytes
+pub fn vault_account(vault: &Vault) -> Account {
+    let mut data = vault.try_to_vec().unwrap();
+    data.resize(4000, 0);
+    Account { lamports: 1_000_000_000, data, owner: time_locked_vault::id(), ..Account::default() }
+}
+
+// An initialized SPL token account
+pub fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    let mut data = vec![0; TokenAccount::LEN];
+    TokenAccount {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        delegate: COption::None,
+        state: AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    Account { lamports: 1_000_000_000, data, owner: spl_token::id(), ..Account::default() }
+}
+
+// Send `instructions` in one transaction paid by the context's payer and
+// signed by `signers` too
+pub async fn send(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction =
+        Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(transaction).await.map_err(|error| error.unwrap())
+}
+
+// Decode the vault stored at `vault`, ignoring trailing padding
+pub async fn vault_state(context: &mut ProgramTestContext, vault: Pubkey) -> Vault {
+    let account = context.banks_client.get_account(vault).await.unwrap().unwrap();
+    Vault::deserialize(&mut &account.data[..]).unwrap()
+}
+
+// The balance of the SPL token account at `key`
+pub async fn token_balance(context: &mut ProgramTestContext, key: Pubkey) -> u64 {
+    let account = context.banks_client.get_account(key).await.unwrap().unwrap();
+    TokenAccount::unpack(&account.data).unwrap().amount
+}
@@ -0,0 +1,357 @@
+// Shared helpers for the handler-level tests
+#![allow(dead_code)]
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction,
+    program_option::COption,
+    program_pack::Pack,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+    sysvar::{
+        self,
+        instructions::{construct_instructions_data, store_current_index, BorrowedAccountMeta, BorrowedInstruction},
+    },
+};
+use spl_token::{
+    instruction::TokenInstruction,
+    state::{Account as TokenAccount, AccountState},
+};
+use std::{cell::RefCell, sync::Once};
+use time_locked_vault::{process_instruction, Vault};
+
+pub mod bank;
+
+thread_local! {
+    static TEST_CLOCK: RefCell<Clock> = RefCell::new(Clock::default());
+    static INVOCATIONS: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+    static RETURN_DATA: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    static LOGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static EVENTS: RefCell<Vec<Vec<Vec<u8>>>> = const { RefCell::new(Vec::new()) };
+}
+
+// Syscall stubs serving the thread-local test clock and default rent, and
+// recording logs, events and cross-program invocations instead of executing
+// them (system transfers also move the lamports, so fee balances can be checked)
+struct TestSyscallStubs;
+
+impl SyscallStubs for TestSyscallStubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        INVOCATIONS.with(|i| i.borrow_mut().push(instruction.clone()));
+        // SystemInstruction::Transfer: a u32 discriminant of 2, then the lamports
+        let is_transfer = instruction.data.len() == 12 && instruction.data[..4] == [2, 0, 0, 0];
+        if instruction.program_id == system_program::id() && is_transfer {
+            let lamports = u64::from_le_bytes(instruction.data[4..].try_into().unwrap());
+            let find = |key: &Pubkey| account_infos.iter().find(|info| info.key == key).unwrap();
+            **find(&instruction.accounts[0].pubkey).lamports.borrow_mut() -= lamports;
+            **find(&instruction.accounts[1].pubkey).lamports.borrow_mut() += lamports;
+        }
+        Ok(())
+    }
+    
+    fn sol_log(&self, message: &str) {
+        LOGS.with(|l| l.borrow_mut().push(message.to_string()));
+    }
+    
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        EVENTS.with(|e| e.borrow_mut().push(fields.iter().map(|field| field.to_vec()).collect()));
+    }
+    
+    fn sol_set_return_data(&self, data: &[u8]) {
+        RETURN_DATA.with(|r| *r.borrow_mut() = data.to_vec());
+    }
+    
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = TEST_CLOCK.with(|c| c.borrow().clone());
+        unsafe {
+            *(var_addr as *mut Clock) = clock;
+        }
+        SUCCESS
+    }
+    
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            *(var_addr as *mut Rent) = Rent::default();
+        }
+        SUCCESS
+    }
+}
+
+static INSTALL_STUBS: Once = Once::new();
+
+// Route the program's syscalls to the test stubs; a test must do this before
+// its first instruction, which creating an account or pinning the clock does
+fn install_stubs() {
+    INSTALL_STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(TestSyscallStubs));
+    });
+}
+
+// Pin the clock seen by `Clock::get()` for the current test thread
+pub fn set_clock(slot: u64, unix_timestamp: i64) {
+    install_stubs();
+    TEST_CLOCK.with(|c| {
+        *c.borrow_mut() = Clock {
+            slot,
+            unix_timestamp,
+            ..Clock::default()
+        }
+    });
+}
+
+// Drain the cross-program invocations recorded on this thread
+pub fn take_invocations() -> Vec<Instruction> {
+    INVOCATIONS.with(|i| std::mem::take(&mut *i.borrow_mut()))
+}
+
+// Drain the return data last set on this thread
+pub fn take_return_data() -> Vec<u8> {
+    RETURN_DATA.with(|r| std::mem::take(&mut *r.borrow_mut()))
+}
+
+// Drain the messages logged on this thread
+pub fn take_logs() -> Vec<String> {
+    LOGS.with(|l| std::mem::take(&mut *l.borrow_mut()))
+}
+
+// Drain the `sol_log_data` events emitted on this thread, one list of fields each
+pub fn take_events() -> Vec<Vec<Vec<u8>>> {
+    EVENTS.with(|e| std::mem::take(&mut *e.borrow_mut()))
+}
+
+// Destinations and amounts of the SPL token transfers among `invocations`
+pub fn token_transfers(invocations: &[Instruction]) -> Vec<(Pubkey, u64)> {
+    invocations
+        .iter()
+        .filter(|ix| ix.program_id == spl_token::id())
+        .filter_map(|ix| match TokenInstruction::unpack(&ix.data) {
+            Ok(TokenInstruction::Transfer { amount }) => Some((ix.accounts[1].pubkey, amount)),
+            _ => None,
+        })
+        .collect()
+}
+
+// Owned storage for a mock account
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl TestAccount {
+    pub fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        install_stubs();
+        Self {
+            key,
+            owner,
+            lamports: 0,
+            data,
+            is_signer: false,
+            is_writable: false,
+        }
+    }
+    
+    // A system-owned wallet with no data
+    pub fn wallet(key: Pubkey) -> Self {
+        Self::new(key, system_program::id(), vec![])
+    }
+    
+    pub fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+    
+    pub fn writable(mut self) -> Self {
+        self.is_writable = true;
+        self
+    }
+    
+    pub fn with_lamports(mut self, lamports: u64) -> Self {
+        self.lamports = lamports;
+        self
+    }
+    
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            self.is_writable,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            false,
+            0,
+        )
+    }
+}
+
+// A program-owned vault account holding `vault`, marked initialized and padded
+// to `size` bytes
+pub fn vault_account(program_id: &Pubkey, vault: &Vault, size: usize) -> TestAccount {
+    let mut data = vec![0; size];
+    Vault { is_initialized: true, ..vault.clone() }.serialize(&mut data.as_mut_slice()).unwrap();
+    TestAccount::new(Pubkey::new_unique(), *program_id, data).writable()
+}
+
+// Decode the vault stored in a mock account, ignoring trailing padding
+pub fn read_vault(account: &TestAccount) -> Vault {
+    Vault::deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+// An initialized SPL token account
+pub fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> TestAccount {
+    let mut data = vec![0; TokenAccount::LEN];
+    TokenAccount {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        delegate: COption::None,
+        state: AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    TestAccount::new(Pubkey::new_unique(), spl_token::id(), data).writable()
+}
+
+// Rewrite the SPL token account state stored in a mock account
+pub fn update_token_account(account: &mut TestAccount, update: impl FnOnce(&mut TokenAccount)) {
+    let mut state = TokenAccount::unpack(&account.data).unwrap();
+    update(&mut state);
+    state.pack_into_slice(&mut account.data);
+}
+
+// The clock sysvar account as `Clock::from_account_info` expects it
+pub fn clock_account(slot: u64, unix_timestamp: i64) -> TestAccount {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(&slot.to_le_bytes());
+    data.extend_from_slice(&unix_timestamp.to_le_bytes()); // epoch_start_timestamp
+    data.extend_from_slice(&0u64.to_le_bytes()); // epoch
+    data.extend_from_slice(&0u64.to_le_bytes()); // leader_schedule_epoch
+    data.extend_from_slice(&unix_timestamp.to_le_bytes());
+    set_clock(slot, unix_timestamp);
+    TestAccount::new(sysvar::clock::id(), sysvar::id(), data)
+}
+
+pub fn token_program() -> TestAccount {
+    TestAccount::new(spl_token::id(), Pubkey::default(), vec![])
+}
+
+pub fn system_program_account() -> TestAccount {
+    TestAccount::new(system_program::id(), Pubkey::default(), vec![])
+}
+
+// The instructions sysvar account for a transaction made of `instructions`,
+// positioned at index `current`
+pub fn instructions_sysvar(instructions: &[Instruction], current: u16) -> TestAccount {
+    let borrowed: Vec<BorrowedInstruction> = instructions
+        .iter()
+        .map(|ix| BorrowedInstruction {
+            program_id: &ix.program_id,
+            accounts: ix
+                .accounts
+                .iter()
+                .map(|meta| BorrowedAccountMeta {
+                    pubkey: &meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: &ix.data,
+        })
+        .collect();
+    let mut data = construct_instructions_data(&borrowed);
+    store_current_index(&mut data, current);
+    TestAccount::new(sysvar::instructions::id(), sysvar::id(), data)
+}
+
+// A vault account under test and the keys most handler-level tests share: the
+// vault's owner and a depositor of one mint. Tests add their instructions in
+// their own `impl Harness` blocks, or wrap it when they need more accounts
+pub struct Harness {
+    pub program_id: Pubkey,
+    pub owner: Pubkey,
+    pub depositor: Pubkey,
+    pub token_mint: Pubkey,
+    pub vault: TestAccount,
+}
+
+impl Harness {
+    // `vault` in a `size`-byte account, with a fresh depositor and mint
+    pub fn new(vault: &Vault, size: usize) -> Self {
+        Self::with_depositor(vault, size, Pubkey::new_unique(), Pubkey::new_unique())
+    }
+    
+    // `vault` in a `size`-byte account, for the depositor and mint its
+    // deposits already name
+    pub fn with_depositor(vault: &Vault, size: usize, depositor: Pubkey, token_mint: Pubkey) -> Self {
+        let program_id = time_locked_vault::id();
+        Self { program_id, owner: vault.owner, depositor, token_mint, vault: vault_account(&program_id, vault, size) }
+    }
+    
+    // The vault as currently stored
+    pub fn state(&self) -> Vault {
+        read_vault(&self.vault)
+    }
+    
+    // Run `data` signed by `signer`, followed by the vault and `accounts`
+    pub fn call(&mut self, signer: Pubkey, data: &[u8], accounts: &mut [&mut TestAccount]) -> ProgramResult {
+        let mut signer = TestAccount::wallet(signer).signer();
+        let mut infos = vec![signer.info(), self.vault.info()];
+        infos.extend(accounts.iter_mut().map(|account| account.info()));
+        process_instruction(&self.program_id, &infos, data)
+    }
+    
+    // Run `data` over the accounts of `Deposit` and then `extra`: the
+    // depositor signs for a token account holding `balance` of the mint, at
+    // the `(slot, unix_timestamp)` clock
+    pub fn deposit_with(
+        &mut self,
+        data: &[u8],
+        balance: u64,
+        clock: (u64, i64),
+        extra: &mut [&mut TestAccount],
+    ) -> ProgramResult {
+        let mut source = token_account(&self.token_mint, &self.depositor, balance);
+        let mut vault_token = token_account(&self.token_mint, &self.vault.key, 0);
+        let (mut token_program, mut system_program) = (token_program(), system_program_account());
+        let mut clock = clock_account(clock.0, clock.1);
+        let mut accounts = vec![&mut source, &mut vault_token, &mut token_program, &mut system_program, &mut clock];
+        accounts.extend(extra.iter_mut().map(|account| &mut **account));
+        self.call(self.depositor, data, &mut accounts)
+    }
+    
+    // Run `data` over the accounts of `Withdraw` and then `extra`:
+    // `withdrawer` signs for a payout to its token account from the vault's
+    // token account holding `balance`, at the `(slot, unix_timestamp)` clock
+    pub fn withdraw_with(
+        &mut self,
+        withdrawer: Pubkey,
+        data: &[u8],
+        balance: u64,
+        clock: (u64, i64),
+        extra: &mut [&mut TestAccount],
+    ) -> ProgramResult {
+        let mut destination = token_account(&self.token_mint, &withdrawer, 0);
+        let mut vault_token = token_account(&self.token_mint, &self.vault.key, balance);
+        let mut token_program = token_program();
+        let mut clock = clock_account(clock.0, clock.1);
+        let mut accounts = vec![&mut destination, &mut vault_token, &mut token_program, &mut clock];
+        accounts.extend(extra.iter_mut().map(|account| &mut **account));
+        self.call(withdrawer, data, &mut accounts)
+    }
+}
@@ -0,0 +1,65 @@
+// A sample withdraw hook: keeps a ledger of the withdrawals a vault reports,
+// as a protocol burning receipts or tracking positions off-vault would. The
+// first byte of the ledger picks what the hook does, so tests can make it
+// fail or try to reenter the vault
+#![allow(dead_code)]
+
+use borsh::BorshDeserialize;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+use time_locked_vault::{process_instruction, Vault, VaultInstruction, WithdrawHookData};
+
+// Count the withdrawal in the ledger
+pub const MODE_RECORD: u8 = 0;
+// Fail with `HOOK_FAILED`
+pub const MODE_FAIL: u8 = 1;
+// Withdraw the next deposit from the vault while the first is paid out
+pub const MODE_REENTER: u8 = 2;
+
+pub const HOOK_FAILED: u32 = 7;
+
+// The mode, the number of withdrawals and their total amount
+pub const LEDGER_LEN: usize = 1 + 8 + 8;
+
+// Accounts: the vault, the hook's ledger and, in `MODE_REENTER`, the hook
+// program followed by the accounts of the `Withdraw` to send the vault
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let hook_data = WithdrawHookData::unpack(data)?;
+    let (vault_info, ledger_info, reentry_accounts) = match accounts {
+        [vault_info, ledger_info, rest @ ..] => (vault_info, ledger_info, rest),
+        _ => return Err(ProgramError::NotEnoughAccountKeys),
+    };
+    if ledger_info.owner != program_id || hook_data.vault != *vault_info.key {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // The vault must still be guarded while the hook runs
+    let vault = Vault::deserialize(&mut &vault_info.data.borrow()[..])?;
+    if !vault.reentrancy_guard {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mode = ledger_info.data.borrow()[0];
+    match mode {
+        MODE_FAIL => Err(ProgramError::Custom(HOOK_FAILED)),
+        MODE_REENTER => {
+            // The runtime already refuses a CPI back into the vault, and the
+            // test validator panics on that refusal, so call the vault's
+            // processor directly to reach its own guard, with the hook's
+            // program account last as the `Withdraw`'s hook
+            let (hook_program_info, withdraw_accounts) =
+                reentry_accounts.split_first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let mut account_infos = withdraw_accounts.to_vec();
+            account_infos.push(hook_program_info.clone());
+            let data = VaultInstruction::Withdraw { deposit_id: hook_data.deposit_id + 1 }.pack();
+            process_instruction(&time_locked_vault::id(), &account_infos, &data)
+        },
+        _ => {
+            let mut ledger = ledger_info.data.borrow_mut();
+            let count = u64::from_le_bytes(ledger[1..9].try_into().unwrap()) + 1;
+            let total = u64::from_le_bytes(ledger[9..17].try_into().unwrap()) + hook_data.amount;
+            ledger[1..9].copy_from_slice(&count.to_le_bytes());
+            ledger[9..17].copy_from_slice(&total.to_le_bytes());
+            Ok(())
+        },
+    }
+}
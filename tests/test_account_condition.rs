@@ -0,0 +1,142 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+    use std::ops::{Deref, DerefMut};
+    use time_locked_vault::{AccountCondition, Deposit, Vault, VaultError, VaultInstruction, MAX_CONDITION_BYTES};
+
+    const UNLOCK_TIME: i64 = 2_000_000_000;
+    const NOW: i64 = 1_700_000_000;
+    const OFFSET: u32 = 8;
+    const EXECUTED: [u8; 2] = [3, 1];
+
+    // The shared harness, plus the account its deposit's condition reads
+    struct Condition {
+        base: Harness,
+        proposal: Pubkey,
+    }
+
+    impl Deref for Condition {
+        type Target = Harness;
+        
+        fn deref(&self) -> &Harness {
+            &self.base
+        }
+    }
+
+    impl DerefMut for Condition {
+        fn deref_mut(&mut self) -> &mut Harness {
+            &mut self.base
+        }
+    }
+
+    impl Condition {
+        fn new() -> Self {
+            let (depositor, token_mint, proposal) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+            let vault = Vault {
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    depositor,
+                    token_mint,
+                    amount: 100,
+                    unlock_time: UNLOCK_TIME,
+                    account_condition: Some(AccountCondition {
+                        account: proposal,
+                        offset: OFFSET,
+                        expected: EXECUTED.to_vec(),
+                    }),
+                    ..Deposit::default()
+                }],
+                ..Vault::default()
+            };
+            Self { base: Harness::with_depositor(&vault, 1000, depositor, token_mint), proposal }
+        }
+        
+        // A governance-owned account with `state` written at `OFFSET`
+        fn proposal_account(&self, state: &[u8], len: usize) -> TestAccount {
+            let mut data = vec![0; len];
+            data[OFFSET as usize..OFFSET as usize + state.len()].copy_from_slice(state);
+            TestAccount::new(self.proposal, Pubkey::new_unique(), data)
+        }
+        
+        fn withdraw_on_condition(&mut self, condition_account: Option<&mut TestAccount>) -> ProgramResult {
+            let mut extra: Vec<&mut TestAccount> = condition_account.into_iter().collect();
+            let data = VaultInstruction::WithdrawOnCondition { deposit_id: 0 }.pack();
+            let depositor = self.depositor;
+            self.withdraw_with(depositor, &data, 100, (1, NOW), &mut extra)
+        }
+    }
+
+    #[test]
+    fn test_matching_account_releases_early() {
+        let mut harness = Condition::new();
+        let mut proposal = harness.proposal_account(&EXECUTED, 64);
+        
+        harness.withdraw_on_condition(Some(&mut proposal)).unwrap();
+        
+        assert!(read_vault(&harness.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_mismatching_account_stays_locked() {
+        let mut harness = Condition::new();
+        let mut proposal = harness.proposal_account(&[3, 0], 64);
+        
+        let result = harness.withdraw_on_condition(Some(&mut proposal));
+        
+        assert_eq!(result, Err(VaultError::AccountConditionNotMet.into()));
+        assert!(!read_vault(&harness.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_missing_or_substituted_account_rejected() {
+        let mut harness = Condition::new();
+        
+        assert_eq!(harness.withdraw_on_condition(None), Err(ProgramError::NotEnoughAccountKeys));
+        
+        let mut impostor = harness.proposal_account(&EXECUTED, 64);
+        impostor.key = Pubkey::new_unique();
+        let result = harness.withdraw_on_condition(Some(&mut impostor));
+        assert_eq!(result, Err(VaultError::InvalidAccountCondition.into()));
+    }
+
+    #[test]
+    fn test_shrunk_account_fails_gracefully() {
+        let mut harness = Condition::new();
+        let mut truncated = harness.proposal_account(&EXECUTED[..1], OFFSET as usize + 1);
+        let mut closed = TestAccount::new(harness.proposal, Pubkey::default(), vec![]);
+        
+        assert_eq!(harness.withdraw_on_condition(Some(&mut truncated)), Err(VaultError::ConditionOutOfRange.into()));
+        assert_eq!(harness.withdraw_on_condition(Some(&mut closed)), Err(VaultError::ConditionOutOfRange.into()));
+    }
+
+    #[test]
+    fn test_expected_bytes_bounded_at_deposit() {
+        let mut harness = Harness::new(&Vault::default(), 1000);
+        
+        for (len, ok) in [(0, false), (MAX_CONDITION_BYTES, true), (MAX_CONDITION_BYTES + 1, false)] {
+            let data = VaultInstruction::DepositWithAccountCondition {
+                amount: 100,
+                unlock_time: UNLOCK_TIME,
+                tag: [0; 32],
+                account_condition: AccountCondition {
+                    account: Pubkey::new_unique(),
+                    offset: 0,
+                    expected: vec![1; len],
+                },
+            }
+            .pack();
+            
+            let result = harness.deposit_with(&data, 100, (0, NOW), &mut []);
+            
+            if ok {
+                result.unwrap();
+            } else {
+                assert_eq!(result, Err(VaultError::InvalidAccountCondition.into()), "{} bytes", len);
+            }
+        }
+        assert_eq!(harness.state().deposits.len(), 1);
+    }
+}
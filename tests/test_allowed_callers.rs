@@ -0,0 +1,192 @@
+mod common;
+
+use common::bank::{send, token_account, vault_account, vault_program_test, vault_state};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+    sysvar,
+};
+use solana_program_test::{processor, ProgramTestContext};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use time_locked_vault::{Vault, VaultError, VaultInstruction, MAX_ALLOWED_CALLERS};
+
+// A stand-in router: forwards its instruction data to the vault program with
+// the remaining accounts, keeping their signer flags
+fn router_process<'a>(_program_id: &Pubkey, accounts: &[AccountInfo<'a>], data: &[u8]) -> ProgramResult {
+    let (vault_program_info, forwarded) = accounts.split_first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let instruction = Instruction {
+        program_id: *vault_program_info.key,
+        accounts: forwarded
+            .iter()
+            .map(|info| AccountMeta {
+                pubkey: *info.key,
+                is_signer: info.is_signer,
+                is_writable: info.is_writable,
+            })
+            .collect(),
+        data: data.to_vec(),
+    };
+    let mut account_infos = forwarded.to_vec();
+    account_infos.push(vault_program_info.clone());
+    invoke(&instruction, &account_infos)
+}
+
+struct Harness {
+    context: ProgramTestContext,
+    vault_program: Pubkey,
+    router: Pubkey,
+    other_router: Pubkey,
+    owner: Keypair,
+    depositor: Keypair,
+    vault: Pubkey,
+    source: Pubkey,
+    vault_tokens: Pubkey,
+}
+
+impl Harness {
+    // An empty, unrestricted vault, a depositor holding 100 tokens and two
+    // router programs
+    async fn new() -> Self {
+        let vault_program = time_locked_vault::id();
+        let (router, other_router) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (owner, depositor) = (Keypair::new(), Keypair::new());
+        let (vault, source, vault_tokens) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mint = Pubkey::new_unique();
+
+        let mut program_test = vault_program_test();
+        program_test.add_program("router", router, processor!(router_process));
+        program_test.add_program("other_router", other_router, processor!(router_process));
+
+        let state = Vault { is_initialized: true, owner: owner.pubkey(), ..Vault::default() };
+        program_test.add_account(vault, vault_account(&state));
+        program_test.add_account(source, token_account(&mint, &depositor.pubkey(), 100));
+        program_test.add_account(vault_tokens, token_account(&mint, &vault, 0));
+
+        Self {
+            context: program_test.start_with_context().await,
+            vault_program,
+            router,
+            other_router,
+            owner,
+            depositor,
+            vault,
+            source,
+            vault_tokens,
+        }
+    }
+
+    async fn set_allowed_callers(
+        &mut self,
+        allowed_callers: Option<Vec<Pubkey>>,
+        reject_top_level: bool,
+    ) -> Result<(), TransactionError> {
+        let instruction = Instruction {
+            program_id: self.vault_program,
+            accounts: vec![
+                AccountMeta::new_readonly(self.owner.pubkey(), true),
+                AccountMeta::new(self.vault, false),
+            ],
+            data: VaultInstruction::SetAllowedCallers { allowed_callers, reject_top_level }.pack(),
+        };
+        send(&mut self.context, &[instruction], &[&self.owner]).await
+    }
+
+    // Deposit `amount` tokens, directly or through `router`, passing the
+    // instructions sysvar
+    async fn deposit(&mut self, amount: u64, router: Option<Pubkey>) -> Result<(), TransactionError> {
+        let clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        let mut instruction = Instruction {
+            program_id: self.vault_program,
+            accounts: vec![
+                AccountMeta::new_readonly(self.depositor.pubkey(), true),
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(self.source, false),
+                AccountMeta::new(self.vault_tokens, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(sysvar::instructions::id(), false),
+            ],
+            data: VaultInstruction::Deposit {
+                amount,
+                unlock_time: clock.unix_timestamp + 3_600,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .pack(),
+        };
+        if let Some(router) = router {
+            instruction.accounts.insert(0, AccountMeta::new_readonly(self.vault_program, false));
+            instruction.program_id = router;
+        }
+        send(&mut self.context, &[instruction], &[&self.depositor]).await
+    }
+
+    async fn vault_state(&mut self) -> Vault {
+        vault_state(&mut self.context, self.vault).await
+    }
+}
+
+fn caller_not_allowed() -> Result<(), TransactionError> {
+    Err(TransactionError::InstructionError(0, InstructionError::Custom(VaultError::CallerNotAllowed as u32)))
+}
+
+#[tokio::test]
+async fn test_only_listed_routers_deposit_through_cpi() {
+    let mut harness = Harness::new().await;
+    let router = harness.router;
+    harness.set_allowed_callers(Some(vec![router]), false).await.unwrap();
+
+    harness.deposit(10, Some(router)).await.unwrap();
+    let other_router = harness.other_router;
+    assert_eq!(harness.deposit(10, Some(other_router)).await, caller_not_allowed());
+
+    let vault = harness.vault_state().await;
+    assert_eq!(vault.deposits.len(), 1);
+    assert_eq!(vault.allowed_callers, Some(vec![router]));
+}
+
+#[tokio::test]
+async fn test_top_level_deposits_allowed_unless_rejected() {
+    let mut harness = Harness::new().await;
+    let router = harness.router;
+    harness.set_allowed_callers(Some(vec![router]), false).await.unwrap();
+
+    harness.deposit(10, None).await.unwrap();
+
+    harness.set_allowed_callers(Some(vec![router]), true).await.unwrap();
+    assert_eq!(harness.deposit(20, None).await, caller_not_allowed());
+    harness.deposit(20, Some(router)).await.unwrap();
+    assert_eq!(harness.vault_state().await.deposits.len(), 2);
+}
+
+#[tokio::test]
+async fn test_allowed_callers_validated() {
+    let mut harness = Harness::new().await;
+    let invalid_data = Err(TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(VaultError::InvalidInstructionData as u32),
+    ));
+
+    let too_many = vec![Pubkey::new_unique(); MAX_ALLOWED_CALLERS + 1];
+    assert_eq!(harness.set_allowed_callers(Some(too_many), false).await, invalid_data);
+    assert_eq!(harness.set_allowed_callers(None, true).await, invalid_data);
+
+    // Lifting the restriction lets any program deposit again
+    harness.set_allowed_callers(None, false).await.unwrap();
+    let other_router = harness.other_router;
+    harness.deposit(10, Some(other_router)).await.unwrap();
+}
@@ -0,0 +1,205 @@
+#![cfg(feature = "anchor")]
+
+mod common;
+
+use anchor_lang::{prelude::*, solana_program::sysvar, InstructionData, ToAccountMetas};
+use common::bank::{send, signing_vault, token_account, token_balance, vault_account, vault_program_test, vault_state};
+use solana_program::instruction::Instruction;
+use solana_program_test::{processor, ProgramTestContext};
+use solana_sdk::signature::{Keypair, Signer};
+use time_locked_vault::{
+    anchor::{
+        cpi::{self as vault_cpi, accounts as vault_accounts},
+        TimeLockedVault,
+    },
+    Vault,
+};
+
+declare_id!("AQghXVUueFV74riUwfsYXVxzB68fz6cmv1UBBfDiMWNQ");
+
+const LOCKER_SEED: &[u8] = b"locker";
+
+// An example Anchor program locking tokens held by its PDA in the vault and
+// unlocking them again, through the `anchor` CPI shim
+#[program]
+pub mod locker {
+    use super::*;
+
+    pub fn lock(ctx: Context<Lock>, amount: u64, unlock_time: i64) -> Result<()> {
+        let bump = [ctx.bumps.locker];
+        let signer_seeds: &[&[&[u8]]] = &[&[LOCKER_SEED, &bump]];
+        let accounts = vault_accounts::Deposit {
+            depositor: ctx.accounts.locker.to_account_info(),
+            vault: ctx.accounts.vault.to_account_info(),
+            source_token_account: ctx.accounts.source.to_account_info(),
+            vault_token_account: ctx.accounts.vault_tokens.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            clock: ctx.accounts.clock.to_account_info(),
+        };
+        let program = ctx.accounts.vault_program.to_account_info();
+        vault_cpi::deposit(CpiContext::new_with_signer(program, accounts, signer_seeds), amount, unlock_time, [0; 32])
+    }
+
+    pub fn unlock(ctx: Context<Unlock>, deposit_id: u64) -> Result<()> {
+        let bump = [ctx.bumps.locker];
+        let signer_seeds: &[&[&[u8]]] = &[&[LOCKER_SEED, &bump]];
+        let accounts = vault_accounts::Withdraw {
+            depositor: ctx.accounts.locker.to_account_info(),
+            vault: ctx.accounts.vault.to_account_info(),
+            destination_token_account: ctx.accounts.destination.to_account_info(),
+            vault_token_account: ctx.accounts.vault_tokens.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            clock: ctx.accounts.clock.to_account_info(),
+        };
+        let program = ctx.accounts.vault_program.to_account_info();
+        vault_cpi::withdraw(CpiContext::new_with_signer(program, accounts, signer_seeds), deposit_id)
+    }
+}
+
+#[derive(Accounts)]
+pub struct Lock<'info> {
+    /// CHECK: the locker's PDA, depositing its tokens
+    #[account(seeds = [LOCKER_SEED], bump)]
+    pub locker: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: checked by the vault program
+    #[account(mut)]
+    pub source: UncheckedAccount<'info>,
+    /// CHECK: checked by the vault program
+    #[account(mut)]
+    pub vault_tokens: UncheckedAccount<'info>,
+    /// CHECK: checked by the vault program
+    pub token_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub clock: Sysvar<'info, Clock>,
+    pub vault_program: Program<'info, TimeLockedVault>,
+}
+
+#[derive(Accounts)]
+pub struct Unlock<'info> {
+    /// CHECK: the locker's PDA, withdrawing its deposit
+    #[account(seeds = [LOCKER_SEED], bump)]
+    pub locker: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: checked by the vault program
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+    /// CHECK: checked by the vault program
+    #[account(mut)]
+    pub vault_tokens: UncheckedAccount<'info>,
+    /// CHECK: checked by the vault program
+    pub token_program: UncheckedAccount<'info>,
+    pub clock: Sysvar<'info, Clock>,
+    pub vault_program: Program<'info, TimeLockedVault>,
+}
+
+struct Harness {
+    context: ProgramTestContext,
+    locker: Pubkey,
+    vault: Keypair,
+    source: Pubkey,
+    vault_tokens: Pubkey,
+}
+
+impl Harness {
+    // An empty vault and 100 tokens held by the locker's PDA
+    async fn new() -> Self {
+        let (locker, _) = Pubkey::find_program_address(&[LOCKER_SEED], &id());
+        let vault = signing_vault();
+        let (source, vault_tokens, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        
+        let mut program_test = vault_program_test();
+        // `entry` wants the accounts to live as long as their data
+        program_test.add_program(
+            "locker",
+            id(),
+            processor!(|program_id, accounts, data| entry(program_id, Box::leak(Box::new(accounts.to_vec())), data)),
+        );
+        
+        let state = Vault { is_initialized: true, owner: Pubkey::new_unique(), ..Vault::default() };
+        program_test.add_account(vault.pubkey(), vault_account(&state));
+        program_test.add_account(source, token_account(&mint, &locker, 100));
+        program_test.add_account(vault_tokens, token_account(&mint, &vault.pubkey(), 0));
+        
+        Self { context: program_test.start_with_context().await, locker, vault, source, vault_tokens }
+    }
+
+    async fn send(&mut self, instruction: Instruction, signers: &[&Keypair]) {
+        send(&mut self.context, &[instruction], signers).await.unwrap();
+    }
+
+    async fn lock(&mut self, amount: u64, unlock_time: i64) {
+        let accounts = accounts::Lock {
+            locker: self.locker,
+            vault: self.vault.pubkey(),
+            source: self.source,
+            vault_tokens: self.vault_tokens,
+            token_program: spl_token::id(),
+            system_program: solana_program::system_program::id(),
+            clock: sysvar::clock::id(),
+            vault_program: time_locked_vault::id(),
+        };
+        let data = instruction::Lock { amount, unlock_time }.data();
+        self.send(Instruction { program_id: id(), accounts: accounts.to_account_metas(None), data }, &[]).await;
+    }
+
+    async fn unlock(&mut self, deposit_id: u64) {
+        let accounts = accounts::Unlock {
+            locker: self.locker,
+            vault: self.vault.pubkey(),
+            destination: self.source,
+            vault_tokens: self.vault_tokens,
+            token_program: spl_token::id(),
+            clock: sysvar::clock::id(),
+            vault_program: time_locked_vault::id(),
+        };
+        let mut metas = accounts.to_account_metas(None);
+        // The vault signs the payout from its token account
+        metas[1].is_signer = true;
+        let data = instruction::Unlock { deposit_id }.data();
+        let vault = self.vault.insecure_clone();
+        self.send(Instruction { program_id: id(), accounts: metas, data }, &[&vault]).await;
+    }
+
+    async fn vault_state(&mut self) -> Vault {
+        vault_state(&mut self.context, self.vault.pubkey()).await
+    }
+
+    async fn token_balance(&mut self, key: Pubkey) -> u64 {
+        token_balance(&mut self.context, key).await
+    }
+}
+
+#[tokio::test]
+async fn test_anchor_program_deposits_and_withdraws_through_the_shim() {
+    let mut harness = Harness::new().await;
+    let mut clock: Clock = harness.context.banks_client.get_sysvar().await.unwrap();
+    let unlock_time = clock.unix_timestamp + 3_600;
+
+    harness.lock(60, unlock_time).await;
+
+    let vault = harness.vault_state().await;
+    assert_eq!((vault.deposits[0].depositor, vault.deposits[0].amount), (harness.locker, 60));
+    assert_eq!(harness.token_balance(harness.vault_tokens).await, 60);
+
+    clock.unix_timestamp = unlock_time;
+    harness.context.set_sysvar(&clock);
+    harness.unlock(0).await;
+
+    assert!(harness.vault_state().await.deposits[0].is_withdrawn());
+    assert_eq!(harness.token_balance(harness.source).await, 100);
+    assert_eq!(harness.token_balance(harness.vault_tokens).await, 0);
+}
+
+#[test]
+fn test_vault_accounts_need_the_initialized_byte() {
+    let data = Vault { is_initialized: true, ..Vault::default() }.try_to_vec().unwrap();
+    assert!(Vault::try_deserialize(&mut &data[..]).is_ok());
+
+    let uninitialized = vec![0; data.len()];
+    assert!(Vault::try_deserialize(&mut &uninitialized[..]).is_err());
+    assert_eq!(<Vault as Owner>::owner(), time_locked_vault::id());
+}
@@ -0,0 +1,151 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{process_instruction, Deposit, Vault, VaultError, VaultInstruction};
+
+    const UNLOCK_TIME: i64 = 10_000;
+    const CHANGE_DELAY: i64 = 7 * 24 * 60 * 60;
+
+    struct BeneficiaryContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        depositor: Pubkey,
+        beneficiary: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl BeneficiaryContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let owner = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let beneficiary = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let vault = Vault {
+                owner,
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor,
+                    token_mint,
+                    amount: 1_000,
+                    unlock_time: UNLOCK_TIME,
+                    beneficiary: Some(beneficiary),
+                    ..Deposit::default()
+                }],
+                beneficiary_change_delay: CHANGE_DELAY,
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                owner,
+                depositor,
+                beneficiary,
+                token_mint,
+                vault: vault_account(&program_id, &vault, 1000),
+            }
+        }
+        
+        fn send(&mut self, signer: &Pubkey, instruction: VaultInstruction, now: i64) -> ProgramResult {
+            let mut signer = TestAccount::wallet(*signer).signer();
+            let mut clock = clock_account(0, now);
+            let accounts = vec![signer.info(), self.vault.info(), clock.info()];
+            process_instruction(&self.program_id, &accounts, &instruction.try_to_vec().unwrap())
+        }
+        
+        fn propose(&mut self, new_beneficiary: Pubkey, now: i64) -> ProgramResult {
+            let depositor = self.depositor;
+            self.send(&depositor, VaultInstruction::ProposeBeneficiary { deposit_id: 0, new_beneficiary }, now)
+        }
+        
+        fn commit(&mut self, now: i64) -> ProgramResult {
+            let depositor = self.depositor;
+            self.send(&depositor, VaultInstruction::CommitBeneficiary { deposit_id: 0 }, now)
+        }
+        
+        fn cancel(&mut self, signer: &Pubkey) -> ProgramResult {
+            self.send(signer, VaultInstruction::CancelBeneficiaryChange { deposit_id: 0 }, 0)
+        }
+        
+        fn withdraw(&mut self, signer: &Pubkey) -> ProgramResult {
+            let mut signer = TestAccount::wallet(*signer).signer();
+            let mut destination = token_account(&self.token_mint, &signer.key, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, UNLOCK_TIME);
+            let accounts = vec![
+                signer.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Withdraw { deposit_id: 0 }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_propose_then_commit() {
+        let mut ctx = BeneficiaryContext::new();
+        let heir = Pubkey::new_unique();
+        
+        ctx.propose(heir, 0).unwrap();
+        ctx.commit(CHANGE_DELAY).unwrap();
+        
+        let deposit = &read_vault(&ctx.vault).deposits[0];
+        assert_eq!(deposit.beneficiary, Some(heir));
+        assert_eq!(deposit.pending_beneficiary, None);
+        ctx.withdraw(&heir).unwrap();
+    }
+
+    #[test]
+    fn test_premature_commit() {
+        let mut ctx = BeneficiaryContext::new();
+        let heir = Pubkey::new_unique();
+        ctx.propose(heir, 0).unwrap();
+        
+        assert_eq!(ctx.commit(CHANGE_DELAY - 1), Err(VaultError::TimelockNotElapsed.into()));
+        assert_eq!(read_vault(&ctx.vault).deposits[0].beneficiary, Some(ctx.beneficiary));
+    }
+
+    #[test]
+    fn test_pending_window_honors_old_beneficiary() {
+        let mut ctx = BeneficiaryContext::new();
+        let heir = Pubkey::new_unique();
+        let beneficiary = ctx.beneficiary;
+        ctx.propose(heir, 0).unwrap();
+        
+        assert_eq!(ctx.withdraw(&heir), Err(VaultError::UnauthorizedWithdrawal.into()));
+        ctx.withdraw(&beneficiary).unwrap();
+    }
+
+    #[test]
+    fn test_beneficiary_and_owner_can_veto() {
+        let mut ctx = BeneficiaryContext::new();
+        let (beneficiary, owner) = (ctx.beneficiary, ctx.owner);
+        
+        for vetoer in [beneficiary, owner] {
+            ctx.propose(Pubkey::new_unique(), 0).unwrap();
+            ctx.cancel(&vetoer).unwrap();
+            assert_eq!(ctx.commit(CHANGE_DELAY), Err(VaultError::NoPendingBeneficiaryChange.into()));
+        }
+    }
+
+    #[test]
+    fn test_stranger_cannot_veto() {
+        let mut ctx = BeneficiaryContext::new();
+        ctx.propose(Pubkey::new_unique(), 0).unwrap();
+        
+        let result = ctx.cancel(&Pubkey::new_unique());
+        
+        assert_eq!(result, Err(VaultError::UnauthorizedWithdrawal.into()));
+        assert!(read_vault(&ctx.vault).deposits[0].pending_beneficiary.is_some());
+    }
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use common::bank::{token_account, vault_account, vault_program_test};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar,
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use spl_token::state::Account as TokenAccount;
+use time_locked_vault::{builder, Vault, VaultInstruction};
+
+struct Harness {
+    context: ProgramTestContext,
+    depositor: Keypair,
+    vault: Pubkey,
+    source: Pubkey,
+    vault_tokens: Pubkey,
+}
+
+impl Harness {
+    // An empty vault, a depositor holding 100 tokens and a vault token
+    // account whose close authority is `vault_close_authority`
+    async fn new(vault_close_authority: Option<Pubkey>) -> Self {
+        let depositor = Keypair::new();
+        let (vault, source, vault_tokens, mint) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut program_test = vault_program_test();
+
+        program_test.add_account(vault, vault_account(&Vault { is_initialized: true, ..Vault::default() }));
+        program_test.add_account(source, token_account(&mint, &depositor.pubkey(), 100));
+        program_test.add_account(vault_tokens, closable_token_account(&mint, &vault, 0, vault_close_authority));
+
+        Self {
+            context: program_test.start_with_context().await,
+            depositor,
+            vault,
+            source,
+            vault_tokens,
+        }
+    }
+
+    fn deposit(&self) -> Instruction {
+        let (program_id, depositor) = (time_locked_vault::id(), self.depositor.pubkey());
+        builder::deposit(&program_id, &depositor, &self.vault, &self.source, &self.vault_tokens, 10, i64::MAX, [0; 32])
+    }
+
+    // A deposit passing the rent sysvar in place of the clock
+    fn deposit_without_clock(&self) -> Instruction {
+        let mut deposit = self.deposit();
+        deposit.accounts[6] = AccountMeta::new_readonly(sysvar::rent::id(), false);
+        deposit
+    }
+
+    // An emergency withdrawal signed by the depositor, paying back to the source
+    fn emergency_withdraw(&self) -> Instruction {
+        Instruction {
+            program_id: time_locked_vault::id(),
+            accounts: vec![
+                AccountMeta::new_readonly(self.depositor.pubkey(), true),
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(self.source, false),
+                AccountMeta::new(self.vault_tokens, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(self.depositor.pubkey(), false),
+            ],
+            data: VaultInstruction::EmergencyWithdraw { deposit_id: 0 }.pack(),
+        }
+    }
+
+    // Send `instruction`, which must fail, and return the transaction's logs
+    async fn logs_of_failed(&mut self, instruction: Instruction) -> Vec<String> {
+        let blockhash = self.context.banks_client.get_latest_blockhash().await.unwrap();
+        let payer = &self.context.payer;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, &self.depositor],
+            blockhash,
+        );
+        let outcome = self.context.banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+        assert!(outcome.result.is_err());
+        outcome.metadata.unwrap().log_messages
+    }
+}
+
+// An SPL token account whose close authority is `close_authority`
+fn closable_token_account(mint: &Pubkey, owner: &Pubkey, amount: u64, close_authority: Option<Pubkey>) -> Account {
+    let mut account = token_account(mint, owner, amount);
+    let mut state = TokenAccount::unpack(&account.data).unwrap();
+    state.close_authority = close_authority.into();
+    state.pack_into_slice(&mut account.data);
+    account
+}
+
+#[cfg(feature = "verbose-logs")]
+fn assert_logged(logs: &[String], expected: &str) {
+    assert!(logs.iter().any(|log| log.contains(expected)), "{:?} not in {:#?}", expected, logs);
+}
+
+#[cfg(feature = "verbose-logs")]
+#[tokio::test]
+async fn test_failed_checks_name_the_account() {
+    let mut harness = Harness::new(Some(Pubkey::new_unique())).await;
+
+    // A vault token account someone else could close
+    let deposit = harness.deposit();
+    let logs = harness.logs_of_failed(deposit).await;
+    let expected = "vault error: InvalidVaultTokenAccount at account[3] (vault token account) expected close authority";
+    assert_logged(&logs, expected);
+
+    let deposit = harness.deposit_without_clock();
+    let logs = harness.logs_of_failed(deposit).await;
+    assert_logged(&logs, "at account[6] (clock sysvar) expected SysvarC1ock11111111111111111111111111111111");
+}
+
+#[cfg(feature = "verbose-logs")]
+#[tokio::test]
+async fn test_emergency_withdraw_names_the_authority() {
+    let mut harness = Harness::new(None).await;
+
+    // The vault has no emergency authority at all
+    let emergency_withdraw = harness.emergency_withdraw();
+    let logs = harness.logs_of_failed(emergency_withdraw).await;
+    assert_logged(&logs, "vault error: UnauthorizedWithdrawal at account[0] (emergency authority) expected None");
+}
+
+#[cfg(not(feature = "verbose-logs"))]
+#[tokio::test]
+async fn test_failed_checks_are_not_formatted_by_default() {
+    let mut harness = Harness::new(Some(Pubkey::new_unique())).await;
+
+    for instruction in [harness.deposit(), harness.deposit_without_clock(), harness.emergency_withdraw()] {
+        let logs = harness.logs_of_failed(instruction).await;
+        assert!(logs.iter().all(|log| !log.contains("vault error:")), "{:#?}", logs);
+    }
+}
@@ -0,0 +1,264 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey, system_program};
+    use std::ops::{Deref, DerefMut};
+    use time_locked_vault::{
+        builder, find_vault_address, process_instruction, Deposit, DepositPolicy, EmergencyEvacuation,
+        IdempotencyKey, MintEntry, OwnerSet, PriceOracleConfig, RateLimit, RewardDistribution, TagTotal, Vault,
+        VaultError, VaultInstruction, WithdrawalWindow, TAG_CLONE_VAULT,
+    };
+
+    const VAULT_SIZE: usize = 4000;
+
+    // A paused, renounced vault with every configuration field set and
+    // state a clone must not inherit
+    fn source_vault() -> Vault {
+        let capped_mint = MintEntry {
+            mint: Pubkey::new_unique(),
+            outstanding: 500,
+            max_outstanding: Some(1_000),
+            lifetime_deposited: 800,
+        };
+        let uncapped_mint = MintEntry { mint: Pubkey::new_unique(), outstanding: 40, ..MintEntry::default() };
+        Vault {
+            is_initialized: true,
+            owner: Pubkey::new_unique(),
+            deposit_count: 2,
+            deposits: vec![Deposit { amount: 500, ..Deposit::default() }, Deposit { amount: 40, ..Deposit::default() }],
+            reentrancy_guard: true,
+            emergency_authority: Some(Pubkey::new_unique()),
+            position_nfts: true,
+            position_nft_uri: "https://example.com/positions".to_string(),
+            emergency_cooldown: 3_600,
+            last_emergency_withdraw: 1_700_000_000,
+            rate_limit: Some(RateLimit { amount: 250, window_secs: 86_400 }),
+            withdrawal_windows: vec![WithdrawalWindow {
+                withdrawer: Pubkey::new_unique(),
+                window_start: 1_700_000_000,
+                withdrawn: 100,
+            }],
+            withdraw_delay: 600,
+            max_penalty_bps: 1_500,
+            treasury: Some(Pubkey::new_unique()),
+            post_unlock_grace: 7_200,
+            destination_change_delay: 900,
+            beneficiary_change_delay: 1_800,
+            crank_bounty_bps: 25,
+            deposit_policy: DepositPolicy::Allowlist,
+            allowed_depositors: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            blocked_depositors: vec![Pubkey::new_unique()],
+            mints: vec![capped_mint, uncapped_mint],
+            tvl_cap: Some(1_000_000),
+            total_locked: 540,
+            deposit_fee_lamports: 5_000,
+            deposit_fee_destination: Pubkey::new_unique(),
+            freeze_authority: Some(Pubkey::new_unique()),
+            arbitration_destination: Some(Pubkey::new_unique()),
+            min_lock_slots: 150,
+            price_oracle: Some(PriceOracleConfig {
+                program: Pubkey::new_unique(),
+                max_age_slots: 25,
+                max_conf_bps: 50,
+            }),
+            tag_totals: vec![TagTotal { tag: [7; 32], total: 500 }],
+            other_tags_total: 40,
+            renounced: true,
+            emergency_destination: Some(Pubkey::new_unique()),
+            insurance_bps: 30,
+            insurance_pool: Pubkey::new_unique(),
+            insurance_premiums: 12,
+            position_index: true,
+            allowed_callers: Some(vec![Pubkey::new_unique()]),
+            reject_top_level: true,
+            gc_delay: Some(86_400),
+            lifetime_deposits_created: 2,
+            lifetime_amount_deposited: 540,
+            lifetime_amount_withdrawn: 0,
+            lifetime_emergency_withdrawals: 1,
+            idempotency_keys: vec![IdempotencyKey { depositor: Pubkey::new_unique(), key: [3; 16] }],
+            idempotency_cursor: 1,
+            deposits_closed: true,
+            owner_approved_splits: true,
+            sweep_announced_at: Some(1_700_000_000),
+            owner_set: Some(OwnerSet { members: vec![Pubkey::new_unique(), Pubkey::new_unique()], threshold: 2 }),
+            co_owners: vec![(Pubkey::new_unique(), 60), (Pubkey::new_unique(), 40)],
+            reward_mint: Some(Pubkey::new_unique()),
+            reward_weight_mint: Some(Pubkey::new_unique()),
+            reward_carry: 3,
+            reward_distribution: Some(RewardDistribution { total: 90, next_id: 1, end_id: 2, ..Default::default() }),
+            unique_tags: true,
+            unlock_granularity: 86_400,
+            emergency_evacuation: Some(EmergencyEvacuation { next_id: 1, end_id: 2, ..Default::default() }),
+            stats_reconciled: true,
+            hook_program: Some(Pubkey::new_unique()),
+            donations_disabled: true,
+            auto_close: true,
+        }
+    }
+
+    // The shared harness over the source vault, plus the account the clone
+    // is created at
+    struct Cloning {
+        base: Harness,
+        clone: TestAccount,
+    }
+
+    impl Deref for Cloning {
+        type Target = Harness;
+        
+        fn deref(&self) -> &Harness {
+            &self.base
+        }
+    }
+
+    impl DerefMut for Cloning {
+        fn deref_mut(&mut self) -> &mut Harness {
+            &mut self.base
+        }
+    }
+
+    impl Cloning {
+        // Cloning `source` needn't be done by its owner: the harness's owner is
+        // a fresh key, whose first vault PDA the clone is created at
+        fn new(source: &Vault) -> Self {
+            let mut base = Harness::new(source, VAULT_SIZE);
+            base.owner = Pubkey::new_unique();
+            let clone_key = find_vault_address(&base.owner, 0, &base.program_id).0;
+            Self { base, clone: TestAccount::new(clone_key, system_program::id(), vec![0; VAULT_SIZE]).writable() }
+        }
+        
+        fn clone_vault(&mut self, seed_index: u64) -> ProgramResult {
+            let Cloning { base, clone } = self;
+            let mut owner = TestAccount::wallet(base.owner).signer().writable();
+            let mut system_program = system_program_account();
+            let instruction = builder::clone_vault(&base.program_id, &base.owner, &base.vault.key, seed_index);
+            let accounts = vec![owner.info(), base.vault.info(), clone.info(), system_program.info()];
+            process_instruction(&base.program_id, &accounts, &instruction.data)
+        }
+    }
+
+    #[test]
+    fn test_clone_copies_configuration_only() {
+        let source = source_vault();
+        let mut harness = Cloning::new(&source);
+        set_clock(1, 1_700_000_000);
+        take_invocations();
+        take_events();
+        
+        harness.clone_vault(0).unwrap();
+        
+        // The clone's PDA was created as large as the source
+        let invocations = take_invocations();
+        assert_eq!(invocations.len(), 1);
+        assert_eq!(invocations[0].program_id, system_program::id());
+        assert_eq!(invocations[0].accounts[1].pubkey, harness.clone.key);
+        
+        let clone = read_vault(&harness.clone);
+        assert!(clone.is_initialized);
+        assert_eq!(clone.owner, harness.owner);
+        
+        // Every configuration field matches the source
+        assert_eq!(clone.emergency_authority, source.emergency_authority);
+        assert_eq!(clone.position_nfts, source.position_nfts);
+        assert_eq!(clone.position_nft_uri, source.position_nft_uri);
+        assert_eq!(clone.emergency_cooldown, source.emergency_cooldown);
+        assert_eq!(clone.rate_limit, source.rate_limit);
+        assert_eq!(clone.withdraw_delay, source.withdraw_delay);
+        assert_eq!(clone.max_penalty_bps, source.max_penalty_bps);
+        assert_eq!(clone.treasury, source.treasury);
+        assert_eq!(clone.post_unlock_grace, source.post_unlock_grace);
+        assert_eq!(clone.destination_change_delay, source.destination_change_delay);
+        assert_eq!(clone.beneficiary_change_delay, source.beneficiary_change_delay);
+        assert_eq!(clone.crank_bounty_bps, source.crank_bounty_bps);
+        assert_eq!(clone.deposit_policy, source.deposit_policy);
+        assert_eq!(clone.allowed_depositors, source.allowed_depositors);
+        assert_eq!(clone.blocked_depositors, source.blocked_depositors);
+        assert_eq!(clone.tvl_cap, source.tvl_cap);
+        assert_eq!(clone.deposit_fee_lamports, source.deposit_fee_lamports);
+        assert_eq!(clone.deposit_fee_destination, source.deposit_fee_destination);
+        assert_eq!(clone.freeze_authority, source.freeze_authority);
+        assert_eq!(clone.arbitration_destination, source.arbitration_destination);
+        assert_eq!(clone.min_lock_slots, source.min_lock_slots);
+        assert_eq!(clone.price_oracle, source.price_oracle);
+        assert_eq!(clone.emergency_destination, source.emergency_destination);
+        assert_eq!(clone.insurance_bps, source.insurance_bps);
+        assert_eq!(clone.insurance_pool, source.insurance_pool);
+        assert_eq!(clone.position_index, source.position_index);
+        assert_eq!(clone.allowed_callers, source.allowed_callers);
+        assert_eq!(clone.reject_top_level, source.reject_top_level);
+        assert_eq!(clone.gc_delay, source.gc_delay);
+        assert_eq!(clone.owner_approved_splits, source.owner_approved_splits);
+        assert_eq!(clone.co_owners, source.co_owners);
+        assert_eq!(clone.reward_mint, source.reward_mint);
+        assert_eq!(clone.reward_weight_mint, source.reward_weight_mint);
+        assert_eq!(clone.unique_tags, source.unique_tags);
+        assert_eq!(clone.unlock_granularity, source.unlock_granularity);
+        assert_eq!(clone.hook_program, source.hook_program);
+        assert_eq!(clone.donations_disabled, source.donations_disabled);
+        assert_eq!(clone.auto_close, source.auto_close);
+        
+        // Per-mint caps carry over without the source's balances
+        let capped = MintEntry { mint: source.mints[0].mint, max_outstanding: Some(1_000), ..MintEntry::default() };
+        assert_eq!(clone.mints, vec![capped]);
+        
+        // Everything else starts fresh
+        let fresh = Vault::default();
+        assert_eq!(clone.deposit_count, 0);
+        assert!(clone.deposits.is_empty());
+        assert!(!clone.reentrancy_guard);
+        assert_eq!(clone.last_emergency_withdraw, fresh.last_emergency_withdraw);
+        assert!(clone.withdrawal_windows.is_empty());
+        assert_eq!(clone.total_locked, 0);
+        assert!(clone.tag_totals.is_empty());
+        assert_eq!(clone.other_tags_total, 0);
+        assert!(!clone.renounced);
+        assert_eq!(clone.insurance_premiums, 0);
+        assert_eq!(clone.lifetime_deposits_created, 0);
+        assert_eq!(clone.lifetime_amount_deposited, 0);
+        assert_eq!(clone.lifetime_amount_withdrawn, 0);
+        assert_eq!(clone.lifetime_emergency_withdrawals, 0);
+        assert!(clone.idempotency_keys.is_empty());
+        assert_eq!(clone.idempotency_cursor, 0);
+        assert!(!clone.deposits_closed);
+        assert_eq!(clone.sweep_announced_at, None);
+        assert_eq!(clone.owner_set, None);
+        assert_eq!(clone.reward_carry, 0);
+        assert_eq!(clone.reward_distribution, None);
+        assert_eq!(clone.emergency_evacuation, None);
+        assert!(!clone.stats_reconciled);
+        
+        // The source is untouched
+        assert_eq!(read_vault(&harness.vault).try_to_vec().unwrap(), source.try_to_vec().unwrap());
+        
+        let events = take_events();
+        let created = events.last().unwrap();
+        assert_eq!(created[0], b"vault_created");
+        assert_eq!(created[1], harness.clone.key.to_bytes());
+        assert_eq!(created[2], harness.owner.to_bytes());
+        assert_eq!(created[3], harness.vault.key.to_bytes());
+    }
+
+    #[test]
+    fn test_clone_must_be_the_owners_unused_pda() {
+        let mut harness = Cloning::new(&source_vault());
+        set_clock(1, 1_700_000_000);
+        
+        // Another index derives another address
+        assert_eq!(harness.clone_vault(1), Err(ProgramError::InvalidSeeds));
+        
+        // An existing vault at the address isn't overwritten
+        harness.clone.owner = harness.program_id;
+        assert_eq!(harness.clone_vault(0), Err(VaultError::AccountAlreadyInUse.into()));
+    }
+
+    #[test]
+    fn test_clone_vault_round_trips() {
+        let data = VaultInstruction::CloneVault { seed_index: 9 }.pack();
+        assert_eq!(data[0], TAG_CLONE_VAULT);
+        assert_eq!(VaultInstruction::unpack(&data).unwrap(), VaultInstruction::CloneVault { seed_index: 9 });
+    }
+}
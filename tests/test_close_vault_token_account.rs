@@ -0,0 +1,176 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, instruction::Instruction, pubkey::Pubkey};
+    use time_locked_vault::{builder, Deposit, DepositStatus, MintEntry, Vault, VaultError, VaultInstruction};
+
+    const UNLOCK_TIME: i64 = 1_700_086_400;
+
+    impl Harness {
+        // A vault holding `amounts.len()` unlocked deposits of one mint
+        fn with_deposits(amounts: &[u64]) -> Self {
+            let (depositor, token_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+            let deposits: Vec<Deposit> = amounts
+                .iter()
+                .enumerate()
+                .map(|(id, &amount)| Deposit {
+                    id: id as u64,
+                    depositor,
+                    token_mint,
+                    amount,
+                    unlock_time: UNLOCK_TIME,
+                    status: DepositStatus::Active as u8,
+                    ..Deposit::default()
+                })
+                .collect();
+            let total: u64 = amounts.iter().sum();
+            let vault = Vault {
+                is_initialized: true,
+                owner: Pubkey::new_unique(),
+                deposit_count: deposits.len() as u64,
+                deposits,
+                mints: vec![MintEntry { mint: token_mint, outstanding: total, ..MintEntry::default() }],
+                total_locked: total,
+                ..Vault::default()
+            };
+            Self::with_depositor(&vault, 2000, depositor, token_mint)
+        }
+        
+        // Withdraw a deposit, passing the owner to take the rent of an
+        // auto-closed account; the mocked transfer doesn't move tokens, so the
+        // vault's token account is passed with the balance it is left with
+        fn withdraw(&mut self, deposit_id: u64, balance_left: u64) -> ProgramResult {
+            let data = VaultInstruction::Withdraw { deposit_id }.pack();
+            let mut owner = TestAccount::wallet(self.owner).writable();
+            self.withdraw_with(self.depositor, &data, balance_left, (1, UNLOCK_TIME), &mut [&mut owner])
+        }
+        
+        // Close the vault's token account, which holds the vault's mint, naming
+        // `mint` and sending the rent to `rent_destination`
+        fn close(&mut self, mint: Pubkey, balance: u64, rent_destination: Pubkey) -> ProgramResult {
+            let mut vault_token = token_account(&self.token_mint, &self.vault.key, balance);
+            let mut token_program = token_program();
+            let mut destination = TestAccount::wallet(rent_destination).writable();
+            let (owner, vault) = (self.owner, self.vault.key);
+            let (vault_token_key, destination_key) = (vault_token.key, destination.key);
+            let instruction = builder::close_vault_token_account(
+                &self.program_id,
+                &owner,
+                &vault,
+                &vault_token_key,
+                &mint,
+                &destination_key,
+            );
+            self.call(owner, &instruction.data, &mut [&mut vault_token, &mut token_program, &mut destination])
+        }
+        
+        fn set_auto_close(&mut self, enabled: bool) -> ProgramResult {
+            self.call(self.owner, &VaultInstruction::SetAutoClose { enabled }.pack(), &mut [])
+        }
+    }
+
+    // The SPL token `CloseAccount` invocations, as (account, rent destination, authority)
+    fn token_account_closes(invocations: &[Instruction]) -> Vec<(Pubkey, Pubkey, Pubkey)> {
+        invocations
+            .iter()
+            .filter(|invocation| invocation.program_id == spl_token::id() && invocation.data == [9])
+            .map(|invocation| {
+                let keys: Vec<Pubkey> = invocation.accounts.iter().map(|meta| meta.pubkey).collect();
+                (keys[0], keys[1], keys[2])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_owner_closes_the_emptied_token_account() {
+        let mut harness = Harness::with_deposits(&[100]);
+        harness.withdraw(0, 0).unwrap();
+        take_invocations();
+        take_events();
+        
+        // The owner sends the rent to the fee payer rather than their own wallet
+        let payer = Pubkey::new_unique();
+        harness.close(harness.token_mint, 0, payer).unwrap();
+        
+        let closes = token_account_closes(&take_invocations());
+        assert_eq!(closes.len(), 1);
+        assert_eq!((closes[0].1, closes[0].2), (payer, harness.vault.key));
+        assert!(read_vault(&harness.vault).mints.is_empty());
+        let events = take_events();
+        assert_eq!(events.last().unwrap()[0], b"token_account_closed");
+        assert_eq!(events.last().unwrap()[2], harness.token_mint.to_bytes());
+    }
+
+    #[test]
+    fn test_close_guards() {
+        let mut harness = Harness::with_deposits(&[100]);
+        let (mint, owner) = (harness.token_mint, harness.owner);
+        take_invocations();
+        
+        // An open deposit keeps the mint active
+        assert_eq!(harness.close(mint, 0, owner), Err(VaultError::MintStillActive.into()));
+        
+        // So do tokens left in the account
+        harness.withdraw(0, 5).unwrap();
+        take_invocations();
+        assert_eq!(harness.close(mint, 5, owner), Err(VaultError::MintStillActive.into()));
+        
+        // And the token account must hold the mint named
+        assert_eq!(harness.close(Pubkey::new_unique(), 0, owner), Err(VaultError::TokenMintMismatch.into()));
+        assert!(token_account_closes(&take_invocations()).is_empty());
+        
+        // A mint limit outlives the closed account
+        let mut vault = read_vault(&harness.vault);
+        vault.mints = vec![MintEntry { mint, max_outstanding: Some(1_000), ..MintEntry::default() }];
+        harness.vault = vault_account(&harness.program_id, &vault, 2000);
+        harness.close(mint, 0, owner).unwrap();
+        assert_eq!(read_vault(&harness.vault).mints, vault.mints);
+    }
+
+    #[test]
+    fn test_last_withdrawal_auto_closes_the_token_account() {
+        let mut harness = Harness::with_deposits(&[60, 40]);
+        
+        // Without auto-close the emptied account stays open
+        harness.withdraw(0, 40).unwrap();
+        assert!(token_account_closes(&take_invocations()).is_empty());
+        
+        harness.set_auto_close(true).unwrap();
+        harness.withdraw(1, 0).unwrap();
+        
+        let closes = token_account_closes(&take_invocations());
+        assert_eq!(closes.len(), 1);
+        assert_eq!((closes[0].1, closes[0].2), (harness.owner, harness.vault.key));
+        let vault = read_vault(&harness.vault);
+        assert!(vault.mints.is_empty());
+        assert!(!vault.reentrancy_guard);
+    }
+
+    #[test]
+    fn test_auto_close_needs_the_owner_account() {
+        let mut harness = Harness::with_deposits(&[100]);
+        harness.set_auto_close(true).unwrap();
+        take_invocations();
+        
+        // Without the owner to take the rent the emptied account stays open
+        let data = VaultInstruction::Withdraw { deposit_id: 0 }.pack();
+        harness.withdraw_with(harness.depositor, &data, 0, (1, UNLOCK_TIME), &mut []).unwrap();
+        
+        assert!(token_account_closes(&take_invocations()).is_empty());
+    }
+
+    #[test]
+    fn test_auto_close_waits_for_the_mints_last_deposit() {
+        let mut harness = Harness::with_deposits(&[60, 40]);
+        harness.set_auto_close(true).unwrap();
+        take_invocations();
+        
+        // Another deposit of the mint is still open, even if the balance reads empty
+        harness.withdraw(0, 0).unwrap();
+        
+        assert!(token_account_closes(&take_invocations()).is_empty());
+        assert_eq!(read_vault(&harness.vault).mints[0].outstanding, 40);
+    }
+}
@@ -0,0 +1,95 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        process_instruction, Vault, VaultError, VaultInstruction, CAN_EDIT_WHITELIST, CAN_PAUSE, CAN_SET_LIMITS,
+        MAX_CO_OWNERS,
+    };
+
+    struct CoOwnerContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        manager: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl CoOwnerContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let owner = Pubkey::new_unique();
+            let vault = vault_account(&program_id, &Vault { owner, ..Vault::default() }, 2000);
+            Self { program_id, owner, manager: Pubkey::new_unique(), vault }
+        }
+        
+        fn send(&mut self, signer: Pubkey, instruction: VaultInstruction) -> ProgramResult {
+            let mut signer = TestAccount::wallet(signer).signer();
+            let accounts = vec![signer.info(), self.vault.info()];
+            process_instruction(&self.program_id, &accounts, &instruction.pack())
+        }
+        
+        fn set_co_owner(&mut self, co_owner: Pubkey, roles: u8) -> ProgramResult {
+            let owner = self.owner;
+            self.send(owner, VaultInstruction::SetCoOwner { co_owner, roles })
+        }
+    }
+
+    #[test]
+    fn test_manager_can_pause_but_not_change_authorities() {
+        let mut ctx = CoOwnerContext::new();
+        let manager = ctx.manager;
+        ctx.set_co_owner(manager, CAN_PAUSE | CAN_EDIT_WHITELIST).unwrap();
+        let missing_role = Err(VaultError::MissingCoOwnerRole.into());
+        
+        ctx.send(manager, VaultInstruction::CloseToNewDeposits).unwrap();
+        assert!(!read_vault(&ctx.vault).accepting_deposits());
+        ctx.send(manager, VaultInstruction::AddAllowedDepositor { depositor: Pubkey::new_unique() }).unwrap();
+        
+        let set_authority = VaultInstruction::SetEmergencyAuthority { authority: Some(manager) };
+        assert_eq!(ctx.send(manager, set_authority), missing_role);
+        assert_eq!(ctx.send(manager, VaultInstruction::SetTvlCap { cap: Some(1) }), missing_role);
+        // Co-owners cannot hand out roles either
+        let grant = VaultInstruction::SetCoOwner { co_owner: manager, roles: CAN_SET_LIMITS };
+        assert_eq!(ctx.send(manager, grant), missing_role);
+        assert_eq!(read_vault(&ctx.vault).emergency_authority, None);
+    }
+
+    #[test]
+    fn test_removed_co_owner_loses_its_roles() {
+        let mut ctx = CoOwnerContext::new();
+        let (owner, manager) = (ctx.owner, ctx.manager);
+        ctx.set_co_owner(manager, CAN_PAUSE).unwrap();
+        
+        ctx.send(owner, VaultInstruction::RemoveCoOwner { co_owner: manager }).unwrap();
+        
+        assert!(read_vault(&ctx.vault).co_owners.is_empty());
+        assert_eq!(ctx.send(manager, VaultInstruction::CloseToNewDeposits), Err(VaultError::NotVaultOwner.into()));
+    }
+
+    #[test]
+    fn test_co_owner_list_is_validated_and_capped() {
+        let mut ctx = CoOwnerContext::new();
+        let (owner, manager) = (ctx.owner, ctx.manager);
+        let invalid = Err(VaultError::InvalidCoOwner.into());
+        
+        assert_eq!(ctx.set_co_owner(manager, 0), invalid);
+        assert_eq!(ctx.set_co_owner(manager, 1 << 7), invalid);
+        assert_eq!(ctx.set_co_owner(owner, CAN_PAUSE), invalid);
+        assert_eq!(
+            ctx.send(manager, VaultInstruction::SetCoOwner { co_owner: manager, roles: CAN_PAUSE }),
+            Err(VaultError::NotVaultOwner.into()),
+        );
+        
+        for _ in 0..MAX_CO_OWNERS {
+            ctx.set_co_owner(Pubkey::new_unique(), CAN_PAUSE).unwrap();
+        }
+        assert_eq!(ctx.set_co_owner(manager, CAN_PAUSE), Err(VaultError::TooManyCoOwners.into()));
+        
+        // Updating an existing co-owner's roles does not need a free slot
+        let existing = read_vault(&ctx.vault).co_owners[0].0;
+        ctx.set_co_owner(existing, CAN_SET_LIMITS).unwrap();
+        assert_eq!(read_vault(&ctx.vault).co_owners[0], (existing, CAN_SET_LIMITS));
+    }
+}
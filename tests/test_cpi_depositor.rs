@@ -0,0 +1,148 @@
+mod common;
+
+use common::bank::{send, token_account, token_balance, vault_account, vault_program_test, vault_state};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+    sysvar,
+};
+use solana_program_test::{processor, ProgramTestContext};
+use solana_sdk::transaction::TransactionError;
+use time_locked_vault::{Vault, VaultInstruction};
+
+const LOCKER_SEED: &[u8] = b"locker";
+
+// A stand-in protocol holding tokens under its PDA: forwards the rest of its
+// instruction data to the vault program with the remaining accounts, signing
+// for the PDA when the first byte is 1
+fn locker_process<'a>(program_id: &Pubkey, accounts: &[AccountInfo<'a>], data: &[u8]) -> ProgramResult {
+    let (vault_program_info, forwarded) = accounts.split_first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let (pda, bump) = Pubkey::find_program_address(&[LOCKER_SEED], program_id);
+    let sign = data[0] == 1;
+
+    let instruction = Instruction {
+        program_id: *vault_program_info.key,
+        accounts: forwarded
+            .iter()
+            .map(|info| AccountMeta {
+                pubkey: *info.key,
+                is_signer: info.is_signer || (sign && *info.key == pda),
+                is_writable: info.is_writable,
+            })
+            .collect(),
+        data: data[1..].to_vec(),
+    };
+    let mut account_infos = forwarded.to_vec();
+    account_infos.push(vault_program_info.clone());
+    invoke_signed(&instruction, &account_infos, &[&[LOCKER_SEED, &[bump]]])
+}
+
+struct Harness {
+    context: ProgramTestContext,
+    vault_program: Pubkey,
+    locker: Pubkey,
+    pda: Pubkey,
+    vault: Pubkey,
+    source: Pubkey,
+    vault_tokens: Pubkey,
+}
+
+impl Harness {
+    // An empty vault and a token account of 100 tokens owned by the locker's PDA
+    async fn new() -> Self {
+        let vault_program = time_locked_vault::id();
+        let locker = Pubkey::new_unique();
+        let (pda, _) = Pubkey::find_program_address(&[LOCKER_SEED], &locker);
+        let (vault, source, vault_tokens) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mint = Pubkey::new_unique();
+
+        let mut program_test = vault_program_test();
+        program_test.add_program("locker", locker, processor!(locker_process));
+
+        let state = Vault { is_initialized: true, owner: Pubkey::new_unique(), ..Vault::default() };
+        program_test.add_account(vault, vault_account(&state));
+        program_test.add_account(source, token_account(&mint, &pda, 100));
+        program_test.add_account(vault_tokens, token_account(&mint, &vault, 0));
+
+        Self {
+            context: program_test.start_with_context().await,
+            vault_program,
+            locker,
+            pda,
+            vault,
+            source,
+            vault_tokens,
+        }
+    }
+
+    // Have the locker deposit `amount` of its PDA's tokens into the vault
+    async fn locker_deposit(&mut self, amount: u64, sign: bool) -> Result<(), TransactionError> {
+        let clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        let mut data = vec![sign as u8];
+        data.extend(
+            VaultInstruction::Deposit {
+                amount,
+                unlock_time: clock.unix_timestamp + 3_600,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .pack(),
+        );
+        let instruction = Instruction {
+            program_id: self.locker,
+            accounts: vec![
+                AccountMeta::new_readonly(self.vault_program, false),
+                AccountMeta::new_readonly(self.pda, false),
+                AccountMeta::new(self.vault, false),
+                AccountMeta::new(self.source, false),
+                AccountMeta::new(self.vault_tokens, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data,
+        };
+        send(&mut self.context, &[instruction], &[]).await
+    }
+
+    async fn vault_state(&mut self) -> Vault {
+        vault_state(&mut self.context, self.vault).await
+    }
+
+    async fn token_balance(&mut self, key: Pubkey) -> u64 {
+        token_balance(&mut self.context, key).await
+    }
+}
+
+#[tokio::test]
+async fn test_program_owned_depositor_locks_through_cpi() {
+    let mut harness = Harness::new().await;
+    assert!(!harness.pda.is_on_curve());
+
+    harness.locker_deposit(60, true).await.unwrap();
+
+    let vault = harness.vault_state().await;
+    assert_eq!((vault.deposits[0].depositor, vault.deposits[0].amount), (harness.pda, 60));
+    assert_eq!(harness.token_balance(harness.source).await, 40);
+    assert_eq!(harness.token_balance(harness.vault_tokens).await, 60);
+}
+
+#[tokio::test]
+async fn test_unsigned_program_owned_depositor_rejected() {
+    let mut harness = Harness::new().await;
+
+    let result = harness.locker_deposit(60, false).await;
+
+    assert_eq!(result, Err(TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)));
+    assert!(harness.vault_state().await.deposits.is_empty());
+}
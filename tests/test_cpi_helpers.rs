@@ -0,0 +1,217 @@
+mod common;
+
+use common::bank::{send, signing_vault, token_account, token_balance, vault_account, vault_program_test, vault_state};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+    sysvar,
+};
+use solana_program_test::{processor, ProgramTestBanksClientExt, ProgramTestContext};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use time_locked_vault::{
+    builder,
+    cpi::{self, DepositCpiAccounts, WithdrawCpiAccounts},
+    Vault, VaultError,
+};
+
+const INTEGRATOR_SEED: &[u8] = b"integrator";
+
+// A sample integrator program locking tokens held by its PDA through the
+// `cpi` helpers. Accounts: the vault program, the PDA, the vault, the PDA's
+// token account, the vault token account, the token program, the system
+// program and the clock. Data: 0 then the amount and unlock time to deposit,
+// or 1 then the deposit id to withdraw
+fn integrator_process<'a>(program_id: &Pubkey, accounts: &[AccountInfo<'a>], data: &[u8]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_program_info = next_account_info(account_info_iter)?;
+    let pda_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let token_account_info = next_account_info(account_info_iter)?;
+    let vault_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+
+    let (pda, bump) = Pubkey::find_program_address(&[INTEGRATOR_SEED], program_id);
+    if *pda_info.key != pda {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let bump = [bump];
+    let signers_seeds: &[&[&[u8]]] = &[&[INTEGRATOR_SEED, &bump]];
+
+    let argument = |index: usize| u64::from_le_bytes(data[1 + 8 * index..9 + 8 * index].try_into().unwrap());
+    match data[0] {
+        0 => {
+            let accounts = DepositCpiAccounts {
+                depositor: pda_info.clone(),
+                vault: vault_info.clone(),
+                source_token_account: token_account_info.clone(),
+                vault_token_account: vault_token_account_info.clone(),
+                token_program: token_program_info.clone(),
+                system_program: system_program_info.clone(),
+                clock: clock_info.clone(),
+            };
+            cpi::deposit(vault_program_info.key, accounts, signers_seeds, argument(0), argument(1) as i64, [0; 32])
+        },
+        1 => {
+            let accounts = WithdrawCpiAccounts {
+                depositor: pda_info.clone(),
+                vault: vault_info.clone(),
+                destination_token_account: token_account_info.clone(),
+                vault_token_account: vault_token_account_info.clone(),
+                token_program: token_program_info.clone(),
+                clock: clock_info.clone(),
+            };
+            cpi::withdraw(vault_program_info.key, accounts, signers_seeds, argument(0))
+        },
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+struct Harness {
+    context: ProgramTestContext,
+    integrator: Pubkey,
+    pda: Pubkey,
+    vault: Keypair,
+    source: Pubkey,
+    vault_tokens: Pubkey,
+}
+
+impl Harness {
+    // An empty vault and 100 tokens held by the integrator's PDA
+    async fn new() -> Self {
+        let integrator = Pubkey::new_unique();
+        let (pda, _) = Pubkey::find_program_address(&[INTEGRATOR_SEED], &integrator);
+        let vault = signing_vault();
+        let (source, vault_tokens, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        
+        let mut program_test = vault_program_test();
+        program_test.add_program("integrator", integrator, processor!(integrator_process));
+        
+        let state = Vault { is_initialized: true, owner: Pubkey::new_unique(), ..Vault::default() };
+        program_test.add_account(vault.pubkey(), vault_account(&state));
+        program_test.add_account(source, token_account(&mint, &pda, 100));
+        program_test.add_account(vault_tokens, token_account(&mint, &vault.pubkey(), 0));
+        
+        Self { context: program_test.start_with_context().await, integrator, pda, vault, source, vault_tokens }
+    }
+
+    // Send the integrator instruction `data`, with the vault keypair signing
+    // when `vault_signs` (the payout from its token account needs it)
+    async fn send(&mut self, data: Vec<u8>, vault_signs: bool) -> Result<(), TransactionError> {
+        let instruction = Instruction {
+            program_id: self.integrator,
+            accounts: vec![
+                AccountMeta::new_readonly(time_locked_vault::id(), false),
+                AccountMeta::new(self.pda, false),
+                AccountMeta::new(self.vault.pubkey(), vault_signs),
+                AccountMeta::new(self.source, false),
+                AccountMeta::new(self.vault_tokens, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data,
+        };
+        let signers = if vault_signs { vec![&self.vault] } else { vec![] };
+        send(&mut self.context, &[instruction], &signers).await
+    }
+
+    async fn deposit(&mut self, amount: u64, unlock_time: i64) -> Result<(), TransactionError> {
+        let mut data = vec![0];
+        data.extend(amount.to_le_bytes());
+        data.extend(unlock_time.to_le_bytes());
+        self.send(data, false).await
+    }
+
+    async fn withdraw(&mut self, deposit_id: u64) -> Result<(), TransactionError> {
+        let mut data = vec![1];
+        data.extend(deposit_id.to_le_bytes());
+        self.send(data, true).await
+    }
+
+    async fn vault_state(&mut self) -> Vault {
+        vault_state(&mut self.context, self.vault.pubkey()).await
+    }
+
+    async fn token_balance(&mut self, key: Pubkey) -> u64 {
+        token_balance(&mut self.context, key).await
+    }
+}
+
+#[tokio::test]
+async fn test_integrator_deposits_through_cpi_helper() {
+    let mut harness = Harness::new().await;
+    let clock: Clock = harness.context.banks_client.get_sysvar().await.unwrap();
+
+    harness.deposit(60, clock.unix_timestamp + 3_600).await.unwrap();
+
+    let vault = harness.vault_state().await;
+    assert_eq!((vault.deposits[0].depositor, vault.deposits[0].amount), (harness.pda, 60));
+    assert_eq!(harness.token_balance(harness.source).await, 40);
+    assert_eq!(harness.token_balance(harness.vault_tokens).await, 60);
+}
+
+#[tokio::test]
+async fn test_integrator_withdraws_through_cpi_helper() {
+    let mut harness = Harness::new().await;
+    let mut clock: Clock = harness.context.banks_client.get_sysvar().await.unwrap();
+    let unlock_time = clock.unix_timestamp + 3_600;
+    harness.deposit(60, unlock_time).await.unwrap();
+    // Withdrawals in the deposit's slot are refused
+    clock.slot += 1;
+    harness.context.set_sysvar(&clock);
+
+    let result = harness.withdraw(0).await;
+    let locked = InstructionError::Custom(VaultError::UnlockTimeNotReached as u32);
+    assert_eq!(result, Err(TransactionError::InstructionError(0, locked)));
+
+    // Wait out the refused withdrawal's blockhash so the retry isn't deduplicated against it
+    let refused_blockhash = harness.context.banks_client.get_latest_blockhash().await.unwrap();
+    harness.context.banks_client.get_new_latest_blockhash(&refused_blockhash).await.unwrap();
+    clock.unix_timestamp = unlock_time;
+    harness.context.set_sysvar(&clock);
+    harness.withdraw(0).await.unwrap();
+
+    assert!(harness.vault_state().await.deposits[0].is_withdrawn());
+    assert_eq!(harness.token_balance(harness.source).await, 100);
+    assert_eq!(harness.token_balance(harness.vault_tokens).await, 0);
+}
+
+#[test]
+fn test_cpi_account_metas_match_builder() {
+    let (depositor, vault, source, vault_tokens) =
+        (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let keys = [depositor, vault, source, vault_tokens, spl_token::id(), system_program::id(), sysvar::clock::id()];
+    let mut lamports = [0; 7];
+    let mut data: [Vec<u8>; 7] = Default::default();
+    let mut infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .map(|((key, lamports), data)| AccountInfo::new(key, false, false, lamports, data, key, false, 0))
+        .collect();
+    let mut next = || infos.remove(0);
+    let accounts = DepositCpiAccounts {
+        depositor: next(),
+        vault: next(),
+        source_token_account: next(),
+        vault_token_account: next(),
+        token_program: next(),
+        system_program: next(),
+        clock: next(),
+    };
+
+    let instruction =
+        builder::deposit(&time_locked_vault::id(), &depositor, &vault, &source, &vault_tokens, 10, 100, [0; 32]);
+
+    assert_eq!(accounts.to_account_metas(), instruction.accounts);
+}
@@ -0,0 +1,229 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use spl_associated_token_account::get_associated_token_address;
+    use time_locked_vault::{
+        crank_bounty, process_instruction, Deposit, Vault, VaultError, VaultInstruction,
+    };
+
+    const UNLOCK_TIME: i64 = 10_000;
+
+    struct CrankContext {
+        program_id: Pubkey,
+        cranker: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl CrankContext {
+        fn new(no_crank: bool) -> Self {
+            let program_id = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor,
+                    token_mint,
+                    amount: 1_000,
+                    unlock_time: UNLOCK_TIME,
+                    no_crank,
+                    ..Deposit::default()
+                }],
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                cranker: Pubkey::new_unique(),
+                depositor,
+                token_mint,
+                vault: vault_account(&program_id, &vault, 1000),
+            }
+        }
+        
+        // Reconfigure the vault to pay a crank bounty on a deposit of `amount`
+        fn with_bounty(mut self, crank_bounty_bps: u16, amount: u64) -> Self {
+            let mut vault = read_vault(&self.vault);
+            vault.crank_bounty_bps = crank_bounty_bps;
+            vault.deposits[0].amount = amount;
+            vault.serialize(&mut self.vault.data.as_mut_slice()).unwrap();
+            self
+        }
+        
+        // The depositor's associated token account, already initialized
+        fn depositor_ata(&self) -> TestAccount {
+            let mut ata = token_account(&self.token_mint, &self.depositor, 0);
+            ata.key = get_associated_token_address(&self.depositor, &self.token_mint);
+            ata
+        }
+        
+        fn crank(&mut self, destination: &mut TestAccount, now: i64) -> ProgramResult {
+            self.crank_with_bounty_accounts(destination, now, &mut [])
+        }
+        
+        fn crank_with_bounty_accounts(
+            &mut self,
+            destination: &mut TestAccount,
+            now: i64,
+            bounty_accounts: &mut [TestAccount],
+        ) -> ProgramResult {
+            let mut cranker = TestAccount::wallet(self.cranker).signer().writable();
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut depositor = TestAccount::wallet(self.depositor);
+            let mut mint = TestAccount::new(self.token_mint, spl_token::id(), vec![]);
+            let mut token_program = token_program();
+            let mut system_program = system_program_account();
+            let mut ata_program = TestAccount::new(spl_associated_token_account::id(), Pubkey::default(), vec![]);
+            let mut clock = clock_account(0, now);
+            let mut accounts = vec![
+                cranker.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                depositor.info(),
+                mint.info(),
+                token_program.info(),
+                system_program.info(),
+                ata_program.info(),
+                clock.info(),
+            ];
+            accounts.extend(bounty_accounts.iter_mut().map(|a| a.info()));
+            take_invocations();
+            let data = VaultInstruction::CrankWithdraw { deposit_id: 0 }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_cranker_finalizes_matured_deposit() {
+        let mut ctx = CrankContext::new(false);
+        let mut ata = ctx.depositor_ata();
+        
+        ctx.crank(&mut ata, UNLOCK_TIME).unwrap();
+        
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+        assert_eq!(ctx.crank(&mut ata, UNLOCK_TIME), Err(VaultError::AlreadyWithdrawn.into()));
+    }
+
+    #[test]
+    fn test_crank_creates_missing_ata() {
+        let mut ctx = CrankContext::new(false);
+        let ata_key = get_associated_token_address(&ctx.depositor, &ctx.token_mint);
+        let mut ata = TestAccount::wallet(ata_key).writable();
+        
+        ctx.crank(&mut ata, UNLOCK_TIME).unwrap();
+        
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_crank_pays_program_owned_depositor_ata() {
+        let mut ctx = CrankContext::new(false);
+        let (pda, _) = Pubkey::find_program_address(&[b"locker"], &Pubkey::new_unique());
+        let mut vault = read_vault(&ctx.vault);
+        vault.deposits[0].depositor = pda;
+        vault.serialize(&mut ctx.vault.data.as_mut_slice()).unwrap();
+        ctx.depositor = pda;
+        let mut ata = ctx.depositor_ata();
+        
+        ctx.crank(&mut ata, UNLOCK_TIME).unwrap();
+        
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_crank_unmatured_deposit_fails() {
+        let mut ctx = CrankContext::new(false);
+        let mut ata = ctx.depositor_ata();
+        
+        let result = ctx.crank(&mut ata, UNLOCK_TIME - 1);
+        
+        assert_eq!(result, Err(VaultError::UnlockTimeNotReached.into()));
+    }
+
+    #[test]
+    fn test_crank_must_pay_depositor_ata() {
+        let mut ctx = CrankContext::new(false);
+        let mut other = token_account(&ctx.token_mint, &ctx.depositor, 0);
+        
+        let result = ctx.crank(&mut other, UNLOCK_TIME);
+        
+        assert_eq!(result, Err(VaultError::InvalidDestinationAccount.into()));
+    }
+
+    #[test]
+    fn test_no_crank_deposit_rejects_crank() {
+        let mut ctx = CrankContext::new(true);
+        let mut ata = ctx.depositor_ata();
+        
+        let result = ctx.crank(&mut ata, UNLOCK_TIME);
+        
+        assert_eq!(result, Err(VaultError::CrankDisabled.into()));
+    }
+
+    #[test]
+    fn test_bounty_split_rounds_for_depositor() {
+        assert_eq!(crank_bounty(1_000, 100), 10);
+        assert_eq!(crank_bounty(1_999, 100), 19);
+        assert_eq!(crank_bounty(99, 100), 0);
+        assert_eq!(crank_bounty(u64::MAX, 100), u64::MAX / 100);
+    }
+
+    #[test]
+    fn test_bounty_paid_to_cranker() {
+        let mut ctx = CrankContext::new(false).with_bounty(100, 1_999);
+        let mut ata = ctx.depositor_ata();
+        let mut bounty_account = token_account(&ctx.token_mint, &ctx.cranker, 0);
+        let bounty_key = bounty_account.key;
+        
+        ctx.crank_with_bounty_accounts(&mut ata, UNLOCK_TIME, std::slice::from_mut(&mut bounty_account))
+            .unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers, vec![(bounty_key, 19), (ata.key, 1_980)]);
+    }
+
+    #[test]
+    fn test_extra_bounty_accounts_are_ignored() {
+        let mut ctx = CrankContext::new(false).with_bounty(100, 1_000);
+        let mut ata = ctx.depositor_ata();
+        let mut bounty_accounts: Vec<_> = (0..3)
+            .map(|_| token_account(&ctx.token_mint, &ctx.cranker, 0))
+            .collect();
+        let first_bounty_key = bounty_accounts[0].key;
+        
+        ctx.crank_with_bounty_accounts(&mut ata, UNLOCK_TIME, &mut bounty_accounts).unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers, vec![(first_bounty_key, 10), (ata.key, 990)]);
+    }
+
+    #[test]
+    fn test_bounty_account_must_belong_to_cranker() {
+        let mut ctx = CrankContext::new(false).with_bounty(100, 1_000);
+        let mut ata = ctx.depositor_ata();
+        let mut not_cranker = token_account(&ctx.token_mint, &Pubkey::new_unique(), 0);
+        
+        let result = ctx.crank_with_bounty_accounts(&mut ata, UNLOCK_TIME, std::slice::from_mut(&mut not_cranker));
+        
+        assert_eq!(result, Err(VaultError::InvalidBountyAccount.into()));
+    }
+
+    #[test]
+    fn test_zero_bounty_skips_transfer() {
+        let mut ctx = CrankContext::new(false);
+        let mut ata = ctx.depositor_ata();
+        
+        ctx.crank(&mut ata, UNLOCK_TIME).unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers, vec![(ata.key, 1_000)]);
+    }
+}
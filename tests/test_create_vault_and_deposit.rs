@@ -0,0 +1,122 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::bank::{send, token_account, token_balance, vault_program_test};
+use solana_program::{
+    clock::Clock,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{account::Account, signature::Signer, transaction::TransactionError};
+use time_locked_vault::{builder, find_vault_address, Vault, VaultConfig, VaultError, PDA_VAULT_SPACE};
+
+struct Harness {
+    context: ProgramTestContext,
+    mint: Pubkey,
+    source: Pubkey,
+    vault: Pubkey,
+    vault_tokens: Pubkey,
+    unlock_time: i64,
+}
+
+impl Harness {
+    // The payer holds 100 tokens and has no vault yet; the vault's token
+    // account is already set up
+    async fn new() -> Self {
+        let vault_program = time_locked_vault::id();
+        let program_test = vault_program_test();
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.pubkey();
+        let (mint, source) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let vault = find_vault_address(&payer, 0, &vault_program).0;
+        let vault_tokens = spl_associated_token_account::get_associated_token_address(&vault, &mint);
+        context.set_account(&source, &token_account(&mint, &payer, 100).into());
+        context.set_account(&vault_tokens, &token_account(&mint, &vault, 0).into());
+        let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+        Self { context, mint, source, vault, vault_tokens, unlock_time: clock.unix_timestamp + 3_600 }
+    }
+
+    fn instruction(&self, amount: u64, config: VaultConfig) -> Instruction {
+        builder::create_vault_and_deposit(
+            &time_locked_vault::id(),
+            &self.context.payer.pubkey(),
+            &self.source,
+            &self.mint,
+            amount,
+            self.unlock_time,
+            [0; 32],
+            config,
+        )
+    }
+
+    async fn send(&mut self, instruction: Instruction) -> Result<(), TransactionError> {
+        send(&mut self.context, &[instruction], &[]).await
+    }
+
+    async fn vault_account(&mut self) -> Option<Account> {
+        self.context.banks_client.get_account(self.vault).await.unwrap()
+    }
+
+    async fn token_balance(&mut self, key: Pubkey) -> u64 {
+        token_balance(&mut self.context, key).await
+    }
+}
+
+#[tokio::test]
+async fn test_creates_the_vault_with_its_first_deposit() {
+    let mut harness = Harness::new().await;
+    let config = VaultConfig { tvl_cap: Some(1_000), ..VaultConfig::default() };
+    
+    harness.send(harness.instruction(60, config)).await.unwrap();
+    
+    let account = harness.vault_account().await.unwrap();
+    assert_eq!(account.owner, time_locked_vault::id());
+    assert_eq!(account.data.len(), PDA_VAULT_SPACE);
+    let vault = Vault::deserialize(&mut &account.data[..]).unwrap();
+    assert_eq!(vault.owner, harness.context.payer.pubkey());
+    assert_eq!(vault.tvl_cap, Some(1_000));
+    assert_eq!(vault.deposit_count, 1);
+    assert_eq!(vault.deposits[0].id, 0);
+    assert_eq!(vault.deposits[0].depositor, harness.context.payer.pubkey());
+    assert_eq!(vault.deposits[0].amount, 60);
+    assert_eq!(vault.total_locked, 60);
+    assert_eq!(harness.token_balance(harness.source).await, 40);
+    assert_eq!(harness.token_balance(harness.vault_tokens).await, 60);
+}
+
+#[tokio::test]
+async fn test_retry_deposits_into_the_existing_vault() {
+    let mut harness = Harness::new().await;
+    harness.send(harness.instruction(60, VaultConfig::default())).await.unwrap();
+    
+    // The creation leg is skipped and the new config ignored
+    let config = VaultConfig { tvl_cap: Some(1_000), ..VaultConfig::default() };
+    harness.send(harness.instruction(30, config)).await.unwrap();
+    
+    let account = harness.vault_account().await.unwrap();
+    let vault = Vault::deserialize(&mut &account.data[..]).unwrap();
+    assert_eq!(vault.tvl_cap, None);
+    let deposits: Vec<(u64, u64)> = vault.deposits.iter().map(|deposit| (deposit.id, deposit.amount)).collect();
+    assert_eq!(deposits, [(0, 60), (1, 30)]);
+    assert_eq!(harness.token_balance(harness.vault_tokens).await, 90);
+}
+
+#[tokio::test]
+async fn test_failed_deposit_leaves_no_vault() {
+    let mut harness = Harness::new().await;
+    
+    let result = harness.send(harness.instruction(1_000, VaultConfig::default())).await;
+    
+    let insufficient = InstructionError::Custom(VaultError::InsufficientFunds as u32);
+    assert_eq!(result, Err(TransactionError::InstructionError(0, insufficient)));
+    assert!(harness.vault_account().await.is_none());
+    assert_eq!(harness.token_balance(harness.source).await, 100);
+    
+    // An invalid config fails before anything is created
+    let config = VaultConfig { tvl_cap: Some(0), ..VaultConfig::default() };
+    let result = harness.send(harness.instruction(60, config)).await;
+    let invalid = InstructionError::Custom(VaultError::InvalidVaultConfig as u32);
+    assert_eq!(result, Err(TransactionError::InstructionError(0, invalid)));
+    assert!(harness.vault_account().await.is_none());
+}
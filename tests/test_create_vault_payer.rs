@@ -0,0 +1,113 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshDeserialize;
+    use solana_program::{
+        entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction,
+        system_program,
+    };
+    use time_locked_vault::{
+        builder, find_registry_address, process_instruction, Vault, VaultConfig, VaultError, VaultInstruction,
+        VaultRegistry,
+    };
+
+    struct PayerContext {
+        program_id: Pubkey,
+        owner: TestAccount,
+        payer: TestAccount,
+        vault: TestAccount,
+        registry: TestAccount,
+    }
+
+    impl PayerContext {
+        // An unfunded, read-only owner and a service wallet paying the rent
+        fn new() -> Self {
+            let program_id = time_locked_vault::id();
+            let owner = TestAccount::wallet(Pubkey::new_unique()).signer();
+            let (registry_key, _) = find_registry_address(&owner.key, &program_id);
+            Self {
+                program_id,
+                payer: TestAccount::wallet(Pubkey::new_unique()).signer().writable().with_lamports(10_000_000),
+                vault: TestAccount::new(Pubkey::new_unique(), program_id, vec![0; 1000]).writable(),
+                registry: TestAccount::new(registry_key, system_program::id(), vec![0; VaultRegistry::LEN]).writable(),
+                owner,
+            }
+        }
+        
+        fn create(&mut self) -> ProgramResult {
+            let instruction = builder::create_vault_paid_by(
+                &self.program_id,
+                &self.payer.key,
+                &self.owner.key,
+                &self.vault.key,
+                false,
+                VaultConfig::default(),
+            );
+            let mut system_program = system_program_account();
+            let accounts = vec![
+                self.owner.info(),
+                self.vault.info(),
+                system_program.info(),
+                self.registry.info(),
+                self.payer.info(),
+            ];
+            take_invocations();
+            process_instruction(&self.program_id, &accounts, &instruction.data)
+        }
+    }
+
+    #[test]
+    fn test_payer_funds_the_registry_and_the_owner_owns_the_vault() {
+        let mut ctx = PayerContext::new();
+        
+        ctx.create().unwrap();
+        
+        let rent = Rent::default().minimum_balance(VaultRegistry::LEN);
+        assert_eq!(take_invocations(), vec![system_instruction::create_account(
+            &ctx.payer.key,
+            &ctx.registry.key,
+            rent,
+            VaultRegistry::LEN as u64,
+            &ctx.program_id,
+        )]);
+        assert_eq!(ctx.owner.lamports, 0);
+        let vault = Vault::deserialize(&mut ctx.vault.data.as_slice()).unwrap();
+        assert_eq!(vault.owner, ctx.owner.key);
+        let registry = VaultRegistry::deserialize(&mut ctx.registry.data.as_slice()).unwrap();
+        assert_eq!(registry.owner, ctx.owner.key);
+        
+        // Paying the rent gives no say over the vault
+        let mut stored = vault_account(&ctx.program_id, &vault, 1000);
+        let accounts = vec![ctx.payer.info(), stored.info()];
+        let data = VaultInstruction::SetTvlCap { cap: Some(1) }.pack();
+        assert_eq!(process_instruction(&ctx.program_id, &accounts, &data), Err(VaultError::NotVaultOwner.into()));
+    }
+
+    #[test]
+    fn test_payer_must_sign() {
+        let mut ctx = PayerContext::new();
+        ctx.payer.is_signer = false;
+        
+        assert_eq!(ctx.create(), Err(ProgramError::MissingRequiredSignature));
+        assert!(take_invocations().is_empty());
+    }
+
+    #[test]
+    fn test_builders_mark_the_payer() {
+        let program_id = time_locked_vault::id();
+        let (payer, owner, vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        
+        let simple = builder::create_vault(&program_id, &owner, &vault, false, VaultConfig::default());
+        assert_eq!(simple.accounts.len(), 4);
+        assert!(simple.accounts[0].is_signer && simple.accounts[0].is_writable);
+        
+        let split = builder::create_vault_paid_by(&program_id, &payer, &owner, &vault, false, VaultConfig::default());
+        assert_eq!(split.accounts.len(), 5);
+        assert!(split.accounts[0].is_signer && !split.accounts[0].is_writable);
+        assert_eq!(split.accounts[4].pubkey, payer);
+        assert!(split.accounts[4].is_signer && split.accounts[4].is_writable);
+        assert_eq!(split.data, simple.data);
+    }
+}
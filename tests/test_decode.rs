@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSerialize;
+    use solana_program::{instruction::Instruction, pubkey::Pubkey};
+    use time_locked_vault::{
+        builder::{self, account_roles},
+        decode::decode_instruction,
+        RateLimit, VaultError, VaultInstruction,
+    };
+
+    // 2025-01-01T00:00:00Z
+    const NEW_YEAR_2025: i64 = 1_735_689_600;
+
+    fn tag(text: &str) -> [u8; 32] {
+        let mut tag = [0; 32];
+        tag[..text.len()].copy_from_slice(text.as_bytes());
+        tag
+    }
+
+    fn decode(instruction: &Instruction) -> time_locked_vault::decode::DecodedInstruction {
+        let keys: Vec<Pubkey> = instruction.accounts.iter().map(|meta| meta.pubkey).collect();
+        decode_instruction(&instruction.data, &keys).unwrap()
+    }
+
+    fn summary(instruction: VaultInstruction) -> String {
+        decode_instruction(&instruction.try_to_vec().unwrap(), &[]).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_decode_deposit_builder() {
+        let depositor = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        let instruction = builder::deposit(
+            &time_locked_vault::id(),
+            &depositor,
+            &vault,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1000,
+            NEW_YEAR_2025,
+            tag("rent"),
+        );
+        
+        let decoded = decode(&instruction);
+        
+        assert_eq!(decoded.to_string(), "Deposit 1000 tokens until 2025-01-01 (tag: rent)");
+        assert_eq!(decoded.account("depositor"), Some(&depositor));
+        assert_eq!(decoded.account("vault"), Some(&vault));
+        assert_eq!(decoded.accounts.len(), account_roles(&decoded.instruction).len());
+        assert!(decoded.accounts.iter().all(|(role, _)| *role != "extra"));
+    }
+
+    #[test]
+    fn test_decode_deposit_for_builder() {
+        let funder = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let depositor = Pubkey::new_unique();
+        let instruction = builder::deposit_for(
+            &time_locked_vault::id(),
+            &funder,
+            &payer,
+            &depositor,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            5,
+            0,
+            [0; 32],
+        );
+        
+        let decoded = decode(&instruction);
+        
+        assert_eq!(decoded.to_string(), "Deposit 5 tokens until 1970-01-01");
+        assert_eq!(decoded.account("funder"), Some(&funder));
+        assert_eq!(decoded.account("payer"), Some(&payer));
+        assert_eq!(decoded.account("depositor"), Some(&depositor));
+        assert_eq!(decoded.accounts.len(), account_roles(&decoded.instruction).len());
+    }
+
+    #[test]
+    fn test_trailing_accounts_are_extra() {
+        let keys: Vec<Pubkey> = (0..8).map(|_| Pubkey::new_unique()).collect();
+        let data = VaultInstruction::Withdraw { deposit_id: 3 }.try_to_vec().unwrap();
+        
+        let decoded = decode_instruction(&data, &keys).unwrap();
+        
+        assert_eq!(decoded.accounts[5], ("clock sysvar", keys[5]));
+        assert_eq!(decoded.accounts[6], ("extra", keys[6]));
+        assert_eq!(decoded.accounts[7], ("extra", keys[7]));
+    }
+
+    #[test]
+    fn test_summaries() {
+        assert_eq!(summary(VaultInstruction::Withdraw { deposit_id: 3 }), "Withdraw deposit #3");
+        assert_eq!(summary(VaultInstruction::CloseVault), "Close vault");
+        assert_eq!(
+            summary(VaultInstruction::DepositWithDuration { amount: 10, lock_duration_secs: 60, tag: [0; 32] }),
+            "Deposit 10 tokens for 60s",
+        );
+        assert_eq!(
+            summary(VaultInstruction::SetRateLimit { rate_limit: Some(RateLimit { amount: 100, window_secs: 3600 }) }),
+            "Rate limit withdrawals to 100 per 3600s",
+        );
+        assert_eq!(summary(VaultInstruction::SetTvlCap { cap: None }), "Lift the vault TVL cap");
+        assert_eq!(
+            summary(VaultInstruction::WithdrawWithPermit { deposit_id: 1, expiry: 951_782_400 }),
+            "Withdraw deposit #1 with permit expiring 2000-02-29",
+        );
+    }
+
+    #[test]
+    fn test_invalid_data_rejected() {
+        assert!(matches!(decode_instruction(&[255], &[]), Err(VaultError::InvalidInstructionData)));
+        assert!(matches!(decode_instruction(&[], &[]), Err(VaultError::InvalidInstructionData)));
+    }
+}
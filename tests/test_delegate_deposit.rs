@@ -0,0 +1,122 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{
+        entrypoint::ProgramResult, program_error::ProgramError, program_option::COption, pubkey::Pubkey,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+    use time_locked_vault::{process_instruction, Vault, VaultError, VaultInstruction};
+
+    const UNLOCK_TIME: i64 = 10_000;
+
+    struct DelegateContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        delegate: Pubkey,
+        mint: Pubkey,
+        vault: TestAccount,
+        source: TestAccount,
+    }
+
+    impl DelegateContext {
+        // A source account of `owner` holding 1,000 tokens, 600 of them
+        // approved to `delegate`
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let (owner, delegate, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+            let mut source = token_account(&mint, &owner, 1_000);
+            update_token_account(&mut source, |state| {
+                state.delegate = COption::Some(delegate);
+                state.delegated_amount = 600;
+            });
+            let vault = vault_account(&program_id, &Vault { owner: Pubkey::new_unique(), ..Vault::default() }, 2000);
+            Self { program_id, owner, delegate, mint, vault, source }
+        }
+        
+        // `DepositFor` with the delegate as funder
+        fn deposit_for(&mut self, depositor: Pubkey, amount: u64, locked_destination: Option<Pubkey>) -> ProgramResult {
+            let mut delegate = TestAccount::wallet(self.delegate).signer();
+            let mut payer = TestAccount::wallet(Pubkey::new_unique()).signer().writable();
+            let mut depositor = TestAccount::wallet(depositor);
+            let mut vault_tokens = token_account(&self.mint, &self.vault.key, 0);
+            let mut token_program = token_program();
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(0, 0);
+            let accounts = vec![
+                delegate.info(),
+                payer.info(),
+                depositor.info(),
+                self.vault.info(),
+                self.source.info(),
+                vault_tokens.info(),
+                token_program.info(),
+                system_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::DepositFor {
+                amount,
+                unlock_time: UNLOCK_TIME,
+                tag: [0; 32],
+                locked_destination,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+            }
+            .pack();
+            take_invocations();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_delegate_deposits_for_the_owner() {
+        let mut ctx = DelegateContext::new();
+        let owner = ctx.owner;
+        
+        ctx.deposit_for(owner, 400, None).unwrap();
+        
+        // The delegate signs the transfer out of the owner's account
+        let invocations = take_invocations();
+        assert_eq!(token_transfers(&invocations).len(), 1);
+        assert_eq!(invocations[0].accounts[0].pubkey, ctx.source.key);
+        assert_eq!(invocations[0].accounts[2].pubkey, ctx.delegate);
+        let vault = read_vault(&ctx.vault);
+        assert_eq!(vault.deposits[0].depositor, owner);
+        assert_eq!(vault.deposits[0].amount, 400);
+    }
+
+    #[test]
+    fn test_delegate_cannot_exceed_its_approval() {
+        let mut ctx = DelegateContext::new();
+        let owner = ctx.owner;
+        
+        assert_eq!(ctx.deposit_for(owner, 601, None), Err(VaultError::InsufficientDelegation.into()));
+        
+        // Without an approval the delegate is just another wallet
+        ctx.delegate = Pubkey::new_unique();
+        assert_eq!(ctx.deposit_for(owner, 100, None), Err(ProgramError::IllegalOwner));
+        assert!(take_invocations().is_empty());
+        assert!(read_vault(&ctx.vault).deposits.is_empty());
+    }
+
+    #[test]
+    fn test_delegate_cannot_redirect_the_deposit() {
+        let mut ctx = DelegateContext::new();
+        let (owner, delegate, mint) = (ctx.owner, ctx.delegate, ctx.mint);
+        let not_allowed = Err(VaultError::DelegateTermsNotAllowed.into());
+        
+        // Recording the deposit for itself, or locking the payout to its own account
+        assert_eq!(ctx.deposit_for(delegate, 100, None), not_allowed);
+        assert_eq!(ctx.deposit_for(Pubkey::new_unique(), 100, None), not_allowed);
+        let delegate_account = get_associated_token_address(&delegate, &mint);
+        assert_eq!(ctx.deposit_for(owner, 100, Some(delegate_account)), not_allowed);
+        assert!(read_vault(&ctx.vault).deposits.is_empty());
+        
+        // The owner's associated token account is a fine locked destination
+        let owner_account = get_associated_token_address(&owner, &mint);
+        ctx.deposit_for(owner, 100, Some(owner_account)).unwrap();
+        assert_eq!(read_vault(&ctx.vault).deposits[0].locked_destination, Some(owner_account));
+    }
+}
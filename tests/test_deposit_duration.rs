@@ -0,0 +1,75 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{process_instruction, Vault, VaultError, VaultInstruction};
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn deposit_with_duration(vault: &mut TestAccount, program_id: &Pubkey, lock_duration_secs: i64) -> ProgramResult {
+        let depositor_key = Pubkey::new_unique();
+        let token_mint = Pubkey::new_unique();
+        let mut depositor = TestAccount::wallet(depositor_key).signer();
+        let mut source = token_account(&token_mint, &depositor_key, 1_000);
+        let mut vault_tokens = token_account(&token_mint, &vault.key, 0);
+        let mut token_program = token_program();
+        let mut system_program = system_program_account();
+        let mut clock = clock_account(0, NOW);
+        let accounts = vec![
+            depositor.info(),
+            vault.info(),
+            source.info(),
+            vault_tokens.info(),
+            token_program.info(),
+            system_program.info(),
+            clock.info(),
+        ];
+        let data = VaultInstruction::DepositWithDuration {
+            amount: 1_000,
+            lock_duration_secs,
+            tag: [0; 32],
+        }
+        .try_to_vec()
+        .unwrap();
+        process_instruction(program_id, &accounts, &data)
+    }
+
+    fn empty_vault(program_id: &Pubkey) -> TestAccount {
+        vault_account(program_id, &Vault::default(), 1000)
+    }
+
+    #[test]
+    fn test_unlock_is_clock_plus_duration() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = empty_vault(&program_id);
+        let duration = 90 * 24 * 60 * 60;
+        
+        deposit_with_duration(&mut vault, &program_id, duration).unwrap();
+        
+        assert_eq!(read_vault(&vault).deposits[0].unlock_time, NOW + duration);
+        assert_eq!(take_return_data(), (NOW + duration).to_le_bytes().to_vec());
+    }
+
+    #[test]
+    fn test_zero_duration_rejected() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = empty_vault(&program_id);
+        
+        let result = deposit_with_duration(&mut vault, &program_id, 0);
+        
+        assert_eq!(result, Err(VaultError::InvalidUnlockTime.into()));
+    }
+
+    #[test]
+    fn test_overflowing_duration_rejected() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = empty_vault(&program_id);
+        
+        let result = deposit_with_duration(&mut vault, &program_id, i64::MAX);
+        
+        assert_eq!(result, Err(VaultError::MathOverflow.into()));
+    }
+}
@@ -0,0 +1,124 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey, system_program};
+    use time_locked_vault::{process_instruction, Vault, VaultError, VaultInstruction};
+
+    const UNLOCK_TIME: i64 = 10_000;
+    const FEE: u64 = 5_000;
+
+    struct FeeContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        fee_destination: TestAccount,
+        vault: TestAccount,
+    }
+
+    impl FeeContext {
+        fn new(fee: u64) -> Self {
+            let program_id = Pubkey::new_unique();
+            let owner = Pubkey::new_unique();
+            let vault = Vault {
+                owner,
+                ..Vault::default()
+            };
+            let mut ctx = Self {
+                program_id,
+                owner,
+                fee_destination: TestAccount::wallet(Pubkey::new_unique()).writable(),
+                vault: vault_account(&program_id, &vault, 1000),
+            };
+            ctx.set_fee(fee).unwrap();
+            ctx
+        }
+        
+        fn set_fee(&mut self, lamports: u64) -> ProgramResult {
+            let mut owner = TestAccount::wallet(self.owner).signer();
+            let accounts = vec![owner.info(), self.vault.info()];
+            let data = VaultInstruction::SetDepositFee {
+                lamports,
+                destination: self.fee_destination.key,
+            }
+            .try_to_vec()
+            .unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn deposit(&mut self, depositor: &mut TestAccount) -> ProgramResult {
+            let token_mint = Pubkey::new_unique();
+            let mut source = token_account(&token_mint, &depositor.key, 1_000);
+            let mut vault_tokens = token_account(&token_mint, &self.vault.key, 0);
+            let mut token_program = token_program();
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(0, 0);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                source.info(),
+                vault_tokens.info(),
+                token_program.info(),
+                system_program.info(),
+                clock.info(),
+                self.fee_destination.info(),
+            ];
+            let data = VaultInstruction::Deposit {
+                amount: 1_000,
+                unlock_time: UNLOCK_TIME,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .try_to_vec()
+            .unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    fn depositor(lamports: u64) -> TestAccount {
+        TestAccount::wallet(Pubkey::new_unique()).signer().writable().with_lamports(lamports)
+    }
+
+    #[test]
+    fn test_fee_moves_to_destination() {
+        let mut ctx = FeeContext::new(FEE);
+        let mut depositor = depositor(FEE + 1);
+        
+        ctx.deposit(&mut depositor).unwrap();
+        
+        assert_eq!(depositor.lamports, 1);
+        assert_eq!(ctx.fee_destination.lamports, FEE);
+        assert_eq!(read_vault(&ctx.vault).deposits.len(), 1);
+    }
+
+    #[test]
+    fn test_insufficient_lamports_rejected_before_any_transfer() {
+        let mut ctx = FeeContext::new(FEE);
+        let mut depositor = depositor(FEE - 1);
+        take_invocations();
+        
+        let result = ctx.deposit(&mut depositor);
+        
+        assert_eq!(result, Err(VaultError::InsufficientFunds.into()));
+        assert!(take_invocations().is_empty());
+        assert_eq!(depositor.lamports, FEE - 1);
+    }
+
+    #[test]
+    fn test_zero_fee_skips_system_transfer() {
+        let mut ctx = FeeContext::new(0);
+        let mut depositor = depositor(FEE);
+        take_invocations();
+        
+        ctx.deposit(&mut depositor).unwrap();
+        
+        let invocations = take_invocations();
+        assert!(invocations.iter().all(|ix| ix.program_id != system_program::id()));
+        assert_eq!(depositor.lamports, FEE);
+    }
+}
@@ -0,0 +1,91 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{process_instruction, Vault, VaultError, VaultInstruction, MAX_BATCH_DEPOSITS};
+
+    const NOW: i64 = 1_000;
+
+    fn deposit_many(vault: &mut TestAccount, program_id: &Pubkey, items: Vec<(u64, i64, [u8; 32])>) -> ProgramResult {
+        let depositor_key = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let mut depositor = TestAccount::wallet(depositor_key).signer();
+        let mut source = token_account(&mint, &depositor_key, 1_000);
+        let mut vault_token = token_account(&mint, &vault.key, 0);
+        let mut token_program = token_program();
+        let mut system_program = system_program_account();
+        let mut clock = clock_account(0, NOW);
+        let accounts = vec![
+            depositor.info(),
+            vault.info(),
+            source.info(),
+            vault_token.info(),
+            token_program.info(),
+            system_program.info(),
+            clock.info(),
+        ];
+        let data = VaultInstruction::DepositMany { items }.pack();
+        process_instruction(program_id, &accounts, &data)
+    }
+
+    #[test]
+    fn test_batch_recorded_with_one_transfer() {
+        let program_id = Pubkey::new_unique();
+        let vault = Vault { deposit_count: 3, ..Vault::default() };
+        let mut vault = vault_account(&program_id, &vault, 4000);
+        let items = vec![(100, NOW + 10, [1; 32]), (200, NOW + 20, [2; 32]), (300, NOW + 30, [1; 32])];
+        take_invocations();
+        
+        deposit_many(&mut vault, &program_id, items).unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers.iter().map(|(_, amount)| *amount).collect::<Vec<_>>(), vec![600]);
+        let return_data = take_return_data();
+        assert_eq!(&return_data[..8], &3u64.to_le_bytes());
+        assert_eq!(&return_data[8..], &5u64.to_le_bytes());
+        
+        let vault = read_vault(&vault);
+        assert_eq!(vault.deposit_count, 6);
+        assert_eq!(vault.total_locked, 600);
+        let recorded: Vec<_> = vault.deposits.iter().map(|d| (d.id, d.amount, d.unlock_time)).collect();
+        assert_eq!(recorded, vec![(3, 100, NOW + 10), (4, 200, NOW + 20), (5, 300, NOW + 30)]);
+        assert_eq!(vault.total_for_tag(&[1; 32]), Some(400));
+    }
+
+    #[test]
+    fn test_invalid_last_item_records_nothing() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault::default(), 4000);
+        
+        let invalid_last_items = [
+            ((51, NOW + 10, [0; 32]), VaultError::InsufficientFunds),
+            ((0, NOW + 10, [0; 32]), VaultError::InvalidAmount),
+            ((1, NOW, [0; 32]), VaultError::InvalidUnlockTime),
+        ];
+        for (last, error) in invalid_last_items {
+            take_invocations();
+            let items = vec![(500, NOW + 10, [0; 32]), (450, NOW + 20, [0; 32]), last];
+        
+            assert_eq!(deposit_many(&mut vault, &program_id, items), Err(error.into()));
+            assert!(take_invocations().is_empty());
+            let stored = read_vault(&vault);
+            assert!(stored.deposits.is_empty());
+            assert_eq!((stored.deposit_count, stored.total_locked), (0, 0));
+        }
+    }
+
+    #[test]
+    fn test_batch_size_capped() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault::default(), 20_000);
+        
+        assert_eq!(deposit_many(&mut vault, &program_id, vec![]), Err(VaultError::InvalidInstructionData.into()));
+        let too_many = vec![(1, NOW + 10, [0; 32]); MAX_BATCH_DEPOSITS + 1];
+        assert_eq!(deposit_many(&mut vault, &program_id, too_many), Err(VaultError::InvalidInstructionData.into()));
+        
+        deposit_many(&mut vault, &program_id, vec![(1, NOW + 10, [0; 32]); MAX_BATCH_DEPOSITS]).unwrap();
+        assert_eq!(read_vault(&vault).deposits.len(), MAX_BATCH_DEPOSITS);
+    }
+}
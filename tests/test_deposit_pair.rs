@@ -0,0 +1,89 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{process_instruction, Vault, VaultError, VaultInstruction};
+
+    const UNLOCK_TIME: i64 = 1_000;
+
+    fn deposit_pair(vault: &mut TestAccount, program_id: &Pubkey, balance_b: u64) -> ProgramResult {
+        let depositor_key = Pubkey::new_unique();
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut depositor = TestAccount::wallet(depositor_key).signer();
+        let mut source_a = token_account(&mint_a, &depositor_key, 100);
+        let mut vault_a = token_account(&mint_a, &vault.key, 0);
+        let mut source_b = token_account(&mint_b, &depositor_key, balance_b);
+        let mut vault_b = token_account(&mint_b, &vault.key, 0);
+        let mut token_program = token_program();
+        let mut system_program = system_program_account();
+        let mut clock = clock_account(0, 0);
+        let accounts = vec![
+            depositor.info(),
+            vault.info(),
+            source_a.info(),
+            vault_a.info(),
+            source_b.info(),
+            vault_b.info(),
+            token_program.info(),
+            system_program.info(),
+            clock.info(),
+        ];
+        let data = VaultInstruction::DepositPair {
+            amount_a: 100,
+            amount_b: 2_000,
+            unlock_time: UNLOCK_TIME,
+            tag: [7; 32],
+        }
+        .pack();
+        process_instruction(program_id, &accounts, &data)
+    }
+
+    #[test]
+    fn test_pair_recorded_and_linked() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault::default(), 1000);
+        take_invocations();
+        
+        deposit_pair(&mut vault, &program_id, 2_000).unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers.iter().map(|(_, amount)| *amount).collect::<Vec<_>>(), vec![100, 2_000]);
+        let vault = read_vault(&vault);
+        assert_eq!(vault.deposit_count, 2);
+        let (a, b) = (&vault.deposits[0], &vault.deposits[1]);
+        assert_eq!((a.linked_deposit, b.linked_deposit), (Some(1), Some(0)));
+        assert_eq!((a.unlock_time, b.unlock_time), (UNLOCK_TIME, UNLOCK_TIME));
+        assert_eq!(a.tag, b.tag);
+        assert_ne!(a.token_mint, b.token_mint);
+    }
+
+    #[test]
+    fn test_second_leg_shortfall_records_nothing() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault::default(), 1000);
+        take_invocations();
+        
+        let result = deposit_pair(&mut vault, &program_id, 1_999);
+        
+        assert_eq!(result, Err(VaultError::InsufficientFunds.into()));
+        assert!(take_invocations().is_empty());
+        let vault = read_vault(&vault);
+        assert!(vault.deposits.is_empty());
+        assert_eq!(vault.total_locked, 0);
+    }
+
+    #[test]
+    fn test_pair_over_tvl_cap_records_nothing() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault { tvl_cap: Some(2_099), ..Vault::default() }, 1000);
+        take_invocations();
+        
+        let result = deposit_pair(&mut vault, &program_id, 2_000);
+        
+        assert_eq!(result, Err(VaultError::VaultCapReached.into()));
+        assert!(take_invocations().is_empty());
+        assert!(read_vault(&vault).deposits.is_empty());
+    }
+}
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use solana_program::pubkey::Pubkey;
+    use std::time::Duration;
+    use time_locked_vault::{
+        builder::{self, DepositParams},
+        VaultError, VaultInstruction,
+    };
+
+    const NOW: i64 = 1_000;
+
+    fn err(result: Result<DepositParams, VaultError>) -> VaultError {
+        result.unwrap_err()
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        assert!(matches!(
+            err(DepositParams::builder().unlock_at(NOW + 1).build(NOW)),
+            VaultError::MissingDepositAmount
+        ));
+        assert!(matches!(err(DepositParams::builder().amount(1).build(NOW)), VaultError::MissingUnlockTime));
+    }
+
+    #[test]
+    fn test_invalid_terms() {
+        let valid = DepositParams::builder().amount(1).unlock_at(NOW + 1);
+        
+        assert!(matches!(err(valid.clone().amount(0).build(NOW)), VaultError::InvalidAmount));
+        assert!(matches!(err(valid.clone().unlock_at(NOW).build(NOW)), VaultError::InvalidUnlockTime));
+        assert!(matches!(err(valid.clone().lock_for(Duration::ZERO).build(NOW)), VaultError::InvalidUnlockTime));
+        assert!(matches!(err(valid.clone().lock_for(Duration::MAX).build(NOW)), VaultError::MathOverflow));
+        assert!(matches!(
+            err(valid.clone().lock_for(Duration::from_secs(i64::MAX as u64)).build(NOW)),
+            VaultError::MathOverflow
+        ));
+        assert!(matches!(err(valid.tag(&"x".repeat(33)).build(NOW)), VaultError::TagTooLong));
+    }
+
+    #[test]
+    fn test_build_terms() {
+        let destination = Pubkey::new_unique();
+        
+        let params = DepositParams::builder()
+            .amount(50)
+            .lock_for(Duration::from_secs(60))
+            .tag(&"r".repeat(32))
+            .locked_destination(destination)
+            .no_crank(true)
+            .build(NOW)
+            .unwrap();
+        
+        assert_eq!(params.unlock_time, NOW + 60);
+        assert_eq!(params.tag, [b'r'; 32]);
+        assert_eq!(params.locked_destination, Some(destination));
+        assert!(params.no_crank);
+    }
+
+    #[test]
+    fn test_instruction_matches_raw_builder() {
+        let mut keys: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        keys[0] = time_locked_vault::id();
+        let mut tag = [0; 32];
+        tag[..4].copy_from_slice(b"rent");
+        
+        let built = DepositParams::builder()
+            .amount(1_000)
+            .unlock_at(NOW + 500)
+            .tag("rent")
+            .build(NOW)
+            .unwrap()
+            .into_instruction(&keys[0], &keys[1], &keys[2], &keys[3], &keys[4]);
+        let raw = builder::deposit(&keys[0], &keys[1], &keys[2], &keys[3], &keys[4], 1_000, NOW + 500, tag);
+        
+        assert_eq!(built, raw);
+        assert_eq!(
+            VaultInstruction::unpack(&built.data).unwrap(),
+            VaultInstruction::Deposit {
+                amount: 1_000,
+                unlock_time: NOW + 500,
+                tag,
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            },
+        );
+    }
+}
@@ -0,0 +1,169 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        process_instruction, DepositPolicy, Vault, VaultError, VaultInstruction,
+    };
+
+    const UNLOCK_TIME: i64 = 10_000;
+
+    struct PolicyContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl PolicyContext {
+        fn new(vault: Vault) -> Self {
+            let program_id = Pubkey::new_unique();
+            Self {
+                program_id,
+                owner: vault.owner,
+                token_mint: Pubkey::new_unique(),
+                vault: vault_account(&program_id, &vault, 4000),
+            }
+        }
+        
+        fn owner_update(&mut self, instruction: VaultInstruction) -> ProgramResult {
+            let mut owner = TestAccount::wallet(self.owner).signer();
+            let accounts = vec![owner.info(), self.vault.info()];
+            process_instruction(&self.program_id, &accounts, &instruction.try_to_vec().unwrap())
+        }
+        
+        fn deposit(&mut self, depositor: &Pubkey) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(*depositor).signer();
+            let mut source = token_account(&self.token_mint, &depositor.key, 1_000);
+            let mut vault_tokens = token_account(&self.token_mint, &self.vault.key, 0);
+            let mut token_program = token_program();
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(0, 0);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                source.info(),
+                vault_tokens.info(),
+                token_program.info(),
+                system_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Deposit {
+                amount: 1_000,
+                unlock_time: UNLOCK_TIME,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .try_to_vec()
+            .unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn withdraw(&mut self, depositor: &Pubkey, deposit_id: u64) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(*depositor).signer();
+            let mut destination = token_account(&self.token_mint, &depositor.key, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, UNLOCK_TIME);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Withdraw { deposit_id }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    fn allowlist_vault() -> PolicyContext {
+        let mut ctx = PolicyContext::new(Vault {
+            owner: Pubkey::new_unique(),
+            ..Vault::default()
+        });
+        ctx.owner_update(VaultInstruction::SetDepositPolicy { policy: DepositPolicy::Allowlist })
+            .unwrap();
+        ctx
+    }
+
+    #[test]
+    fn test_allowed_deposit_succeeds() {
+        let mut ctx = allowlist_vault();
+        let employee = Pubkey::new_unique();
+        ctx.owner_update(VaultInstruction::AddAllowedDepositor { depositor: employee }).unwrap();
+        
+        ctx.deposit(&employee).unwrap();
+        
+        assert_eq!(
+            ctx.deposit(&Pubkey::new_unique()),
+            Err(VaultError::DepositorNotAllowed.into())
+        );
+        assert_eq!(read_vault(&ctx.vault).deposits.len(), 1);
+    }
+
+    #[test]
+    fn test_removed_depositor_keeps_existing_deposits() {
+        let mut ctx = allowlist_vault();
+        let employee = Pubkey::new_unique();
+        ctx.owner_update(VaultInstruction::AddAllowedDepositor { depositor: employee }).unwrap();
+        ctx.deposit(&employee).unwrap();
+        
+        ctx.owner_update(VaultInstruction::RemoveAllowedDepositor { depositor: employee }).unwrap();
+        
+        assert_eq!(ctx.deposit(&employee), Err(VaultError::DepositorNotAllowed.into()));
+        ctx.withdraw(&employee, 0).unwrap();
+    }
+
+    #[test]
+    fn test_non_owner_cannot_edit_allowlist() {
+        let mut ctx = allowlist_vault();
+        ctx.owner = Pubkey::new_unique();
+        
+        let result = ctx.owner_update(VaultInstruction::AddAllowedDepositor { depositor: ctx.owner });
+        
+        assert_eq!(result, Err(VaultError::NotVaultOwner.into()));
+    }
+
+    #[test]
+    fn test_blocked_wallet_can_still_withdraw() {
+        let mut ctx = PolicyContext::new(Vault {
+            owner: Pubkey::new_unique(),
+            ..Vault::default()
+        });
+        let spammer = Pubkey::new_unique();
+        ctx.deposit(&spammer).unwrap();
+        
+        ctx.owner_update(VaultInstruction::BlockDepositor { depositor: spammer }).unwrap();
+        
+        assert_eq!(ctx.deposit(&spammer), Err(VaultError::DepositorBlocked.into()));
+        ctx.deposit(&Pubkey::new_unique()).unwrap();
+        ctx.withdraw(&spammer, 0).unwrap();
+    }
+
+    #[test]
+    fn test_blocklist_stays_sorted() {
+        let mut ctx = PolicyContext::new(Vault {
+            owner: Pubkey::new_unique(),
+            ..Vault::default()
+        });
+        let wallets: Vec<_> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        for wallet in wallets.iter().rev() {
+            ctx.owner_update(VaultInstruction::BlockDepositor { depositor: *wallet }).unwrap();
+        }
+        ctx.owner_update(VaultInstruction::UnblockDepositor { depositor: wallets[2] }).unwrap();
+        
+        let blocked = read_vault(&ctx.vault).blocked_depositors;
+        let mut expected = vec![wallets[0], wallets[1], wallets[3], wallets[4]];
+        expected.sort();
+        assert_eq!(blocked, expected);
+    }
+}
@@ -0,0 +1,132 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{builder, process_instruction, Vault, VaultError, VaultInstruction};
+
+    const UNLOCK_TIME: i64 = 10_000;
+
+    struct RolesContext {
+        program_id: Pubkey,
+        funder: Pubkey,
+        payer: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl RolesContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                funder: Pubkey::new_unique(),
+                payer: Pubkey::new_unique(),
+                depositor: Pubkey::new_unique(),
+                token_mint: Pubkey::new_unique(),
+                vault: vault_account(&program_id, &vault, 1000),
+            }
+        }
+        
+        fn deposit_for(&mut self) -> ProgramResult {
+            let mut funder = TestAccount::wallet(self.funder).signer();
+            let mut payer = TestAccount::wallet(self.payer).signer().writable();
+            let mut depositor = TestAccount::wallet(self.depositor);
+            let mut source = token_account(&self.token_mint, &self.funder, 1_000);
+            let mut vault_tokens = token_account(&self.token_mint, &self.vault.key, 0);
+            let mut token_program = token_program();
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(0, 0);
+            let accounts = vec![
+                funder.info(),
+                payer.info(),
+                depositor.info(),
+                self.vault.info(),
+                source.info(),
+                vault_tokens.info(),
+                token_program.info(),
+                system_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::DepositFor {
+                amount: 1_000,
+                unlock_time: UNLOCK_TIME,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+            }
+            .try_to_vec()
+            .unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn withdraw(&mut self, signer: &Pubkey) -> ProgramResult {
+            let mut signer = TestAccount::wallet(*signer).signer();
+            let mut destination = token_account(&self.token_mint, &signer.key, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, UNLOCK_TIME);
+            let accounts = vec![
+                signer.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Withdraw { deposit_id: 0 }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_deposit_with_three_distinct_roles() {
+        let mut ctx = RolesContext::new();
+        
+        ctx.deposit_for().unwrap();
+        
+        let vault = read_vault(&ctx.vault);
+        assert_eq!(vault.deposits.len(), 1);
+        assert_eq!(vault.deposits[0].depositor, ctx.depositor);
+        assert_eq!(vault.deposits[0].token_mint, ctx.token_mint);
+    }
+
+    #[test]
+    fn test_only_depositor_can_withdraw() {
+        let mut ctx = RolesContext::new();
+        ctx.deposit_for().unwrap();
+        let (funder, payer, depositor) = (ctx.funder, ctx.payer, ctx.depositor);
+        
+        assert_eq!(ctx.withdraw(&funder), Err(VaultError::UnauthorizedWithdrawal.into()));
+        assert_eq!(ctx.withdraw(&payer), Err(VaultError::UnauthorizedWithdrawal.into()));
+        ctx.withdraw(&depositor).unwrap();
+    }
+
+    #[test]
+    fn test_builders_mark_signers() {
+        let program_id = time_locked_vault::id();
+        let (funder, payer, depositor) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (vault, source, vault_tokens) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        
+        let simple = builder::deposit(&program_id, &depositor, &vault, &source, &vault_tokens, 1, UNLOCK_TIME, [0; 32]);
+        assert_eq!(simple.accounts.len(), 7);
+        assert!(simple.accounts[0].is_signer);
+        
+        let split = builder::deposit_for(
+            &program_id, &funder, &payer, &depositor, &vault, &source, &vault_tokens, 1, UNLOCK_TIME, [0; 32],
+        );
+        assert_eq!(split.accounts.len(), 9);
+        let signers: Vec<_> = split.accounts.iter().filter(|a| a.is_signer).map(|a| a.pubkey).collect();
+        assert_eq!(signers, vec![funder, payer]);
+        assert_eq!(split.accounts[3].pubkey, vault);
+    }
+}
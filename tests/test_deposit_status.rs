@@ -0,0 +1,196 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        process_instruction, Deposit, DepositStatus, ResolutionDestination, Vault, VaultInstruction,
+    };
+
+    const UNLOCK_TIME: i64 = 1_000;
+    const TERMINAL: [DepositStatus; 4] = [
+        DepositStatus::Withdrawn,
+        DepositStatus::EmergencyWithdrawn,
+        DepositStatus::Cancelled,
+        DepositStatus::Swept,
+    ];
+
+    struct StatusContext {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        authority: Pubkey,
+        treasury: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl StatusContext {
+        // One deposit of 1,000 tokens unlocking at `UNLOCK_TIME`, in a vault
+        // with an emergency authority and an early withdrawal penalty
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let authority = Pubkey::new_unique();
+            let treasury = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor,
+                    token_mint,
+                    amount: 1_000,
+                    unlock_time: UNLOCK_TIME,
+                    ..Deposit::default()
+                }],
+                emergency_authority: Some(authority),
+                max_penalty_bps: 1_000,
+                treasury: Some(treasury),
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                depositor,
+                token_mint,
+                authority,
+                treasury,
+                vault: vault_account(&program_id, &vault, 1000),
+            }
+        }
+        
+        // The same deposit, already ended with `status`
+        fn ended(status: DepositStatus) -> Self {
+            let mut ctx = Self::new();
+            let mut vault = read_vault(&ctx.vault);
+            vault.deposits[0].status = status as u8;
+            vault.deposits[0].frozen = status == DepositStatus::Swept;
+            vault.serialize(&mut ctx.vault.data.as_mut_slice()).unwrap();
+            ctx
+        }
+        
+        fn status(&self) -> Option<DepositStatus> {
+            read_vault(&self.vault).deposits[0].status()
+        }
+        
+        // Withdraw, EarlyWithdraw or EmergencyWithdraw, signed by whoever the
+        // instruction expects
+        fn withdraw(&mut self, instruction: VaultInstruction, now: i64) -> ProgramResult {
+            let signer = match instruction {
+                VaultInstruction::EmergencyWithdraw { .. } => self.authority,
+                _ => self.depositor,
+            };
+            let mut signer = TestAccount::wallet(signer).signer();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, now);
+            let mut treasury = token_account(&self.token_mint, &Pubkey::new_unique(), 0);
+            treasury.key = self.treasury;
+            let mut depositor = TestAccount::wallet(self.depositor);
+            let mut accounts = vec![
+                signer.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+            ];
+            match instruction {
+                VaultInstruction::EmergencyWithdraw { .. } => accounts.push(depositor.info()),
+                VaultInstruction::EarlyWithdraw { .. } => {
+                    accounts.push(clock.info());
+                    accounts.push(treasury.info());
+                },
+                _ => accounts.push(clock.info()),
+            }
+            take_events();
+            process_instruction(&self.program_id, &accounts, &instruction.try_to_vec().unwrap())
+        }
+        
+        fn freeze(&mut self) -> ProgramResult {
+            let mut authority = TestAccount::wallet(self.authority).signer();
+            let mut clock = clock_account(0, 0);
+            let accounts = vec![authority.info(), self.vault.info(), clock.info()];
+            let data = VaultInstruction::FreezeDeposit { deposit_id: 0 }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn resolve(&mut self) -> ProgramResult {
+            set_clock(0, 0);
+            let mut authority = TestAccount::wallet(self.authority).signer();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let accounts = vec![
+                authority.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+            ];
+            let destination_kind = ResolutionDestination::ReturnToDepositor;
+            let data = VaultInstruction::ResolveFrozenDeposit { deposit_id: 0, destination_kind }
+                .try_to_vec()
+                .unwrap();
+            take_events();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    // The status byte that ends the payout event
+    fn event_status() -> u8 {
+        let events = take_events();
+        *events.last().unwrap().last().unwrap().last().unwrap()
+    }
+
+    #[test]
+    fn test_each_path_records_its_status() {
+        let mut ctx = StatusContext::new();
+        assert_eq!(ctx.status(), Some(DepositStatus::Active));
+        ctx.withdraw(VaultInstruction::Withdraw { deposit_id: 0 }, UNLOCK_TIME).unwrap();
+        assert_eq!((ctx.status(), event_status()), (Some(DepositStatus::Withdrawn), 1));
+        
+        let mut ctx = StatusContext::new();
+        ctx.withdraw(VaultInstruction::EarlyWithdraw { deposit_id: 0 }, UNLOCK_TIME / 2).unwrap();
+        assert_eq!((ctx.status(), event_status()), (Some(DepositStatus::Cancelled), 3));
+        
+        let mut ctx = StatusContext::new();
+        ctx.withdraw(VaultInstruction::EmergencyWithdraw { deposit_id: 0 }, 0).unwrap();
+        assert_eq!((ctx.status(), event_status()), (Some(DepositStatus::EmergencyWithdrawn), 2));
+        
+        let mut ctx = StatusContext::new();
+        ctx.freeze().unwrap();
+        ctx.resolve().unwrap();
+        assert_eq!((ctx.status(), event_status()), (Some(DepositStatus::Swept), 4));
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_terminal_statuses_are_final() {
+        for status in TERMINAL {
+            let mut ctx = StatusContext::ended(status);
+            
+            assert!(ctx.withdraw(VaultInstruction::Withdraw { deposit_id: 0 }, UNLOCK_TIME).is_err());
+            assert!(ctx.withdraw(VaultInstruction::EarlyWithdraw { deposit_id: 0 }, UNLOCK_TIME / 2).is_err());
+            assert!(ctx.withdraw(VaultInstruction::EmergencyWithdraw { deposit_id: 0 }, 0).is_err());
+            assert!(ctx.resolve().is_err());
+            
+            assert_eq!(ctx.status(), Some(status), "{:?}", status);
+        }
+    }
+
+    #[test]
+    fn test_legacy_withdrawn_flag_reads_as_withdrawn() {
+        // Records written with `withdrawn: bool` hold 0 or 1 in the status byte
+        assert_eq!(false.try_to_vec().unwrap(), vec![DepositStatus::Active as u8]);
+        assert_eq!(true.try_to_vec().unwrap(), vec![DepositStatus::Withdrawn as u8]);
+        
+        let legacy = Deposit { status: 1, ..Deposit::default() };
+        assert_eq!(legacy.status(), Some(DepositStatus::Withdrawn));
+        assert!(legacy.is_withdrawn());
+        assert!(!Deposit::default().is_withdrawn());
+        assert_eq!(DepositStatus::from_u8(DepositStatus::ReleasedEarly as u8 + 1), None);
+    }
+}
@@ -0,0 +1,106 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        entrypoint::ProgramResult,
+        program_option::COption,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_token::state::Mint;
+    use time_locked_vault::{process_instruction, ui_amount_to_raw, Vault, VaultError, VaultInstruction};
+
+    fn mint_account(key: Pubkey, decimals: u8) -> TestAccount {
+        let mut data = vec![0; Mint::LEN];
+        Mint {
+            mint_authority: COption::None,
+            supply: u64::MAX,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        TestAccount::new(key, spl_token::id(), data)
+    }
+
+    fn deposit_ui_amount(
+        vault: &mut TestAccount,
+        program_id: &Pubkey,
+        decimals: u8,
+        ui_amount: u64,
+        decimals_exponent: u8,
+    ) -> ProgramResult {
+        let depositor_key = Pubkey::new_unique();
+        let token_mint = Pubkey::new_unique();
+        let mut mint = mint_account(token_mint, decimals);
+        let mut depositor = TestAccount::wallet(depositor_key).signer();
+        let mut source = token_account(&token_mint, &depositor_key, u64::MAX);
+        let mut vault_tokens = token_account(&token_mint, &vault.key, 0);
+        let mut token_program = token_program();
+        let mut system_program = system_program_account();
+        let mut clock = clock_account(0, 0);
+        let accounts = vec![
+            mint.info(),
+            depositor.info(),
+            vault.info(),
+            source.info(),
+            vault_tokens.info(),
+            token_program.info(),
+            system_program.info(),
+            clock.info(),
+        ];
+        let data = VaultInstruction::DepositUiAmount {
+            ui_amount,
+            decimals_exponent,
+            unlock_time: 1_000,
+            tag: [0; 32],
+        }
+        .try_to_vec()
+        .unwrap();
+        process_instruction(program_id, &accounts, &data)
+    }
+
+    #[test]
+    fn test_ui_amount_conversion() {
+        assert_eq!(ui_amount_to_raw(10, 0), Some(10));
+        assert_eq!(ui_amount_to_raw(10, 9), Some(10_000_000_000));
+        assert_eq!(ui_amount_to_raw(u64::MAX, 0), Some(u64::MAX));
+        assert_eq!(ui_amount_to_raw(18_446_744_074, 9), None);
+        assert_eq!(ui_amount_to_raw(1, 20), None);
+        assert_eq!(ui_amount_to_raw(1, u8::MAX), None);
+    }
+
+    #[test]
+    fn test_deposit_stores_raw_amount() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault::default(), 1000);
+        
+        deposit_ui_amount(&mut vault, &program_id, 6, 10, 6).unwrap();
+        
+        assert_eq!(read_vault(&vault).deposits[0].amount, 10_000_000);
+    }
+
+    #[test]
+    fn test_decimals_mismatch_rejected() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault::default(), 1000);
+        
+        let result = deposit_ui_amount(&mut vault, &program_id, 6, 10, 0);
+        
+        assert_eq!(result, Err(VaultError::DecimalsMismatch.into()));
+    }
+
+    #[test]
+    fn test_overflowing_amount_rejected() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault::default(), 1000);
+        
+        let result = deposit_ui_amount(&mut vault, &program_id, 9, u64::MAX, 9);
+        
+        assert_eq!(result, Err(VaultError::MathOverflow.into()));
+        assert!(read_vault(&vault).deposits.is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, instruction::Instruction, pubkey::Pubkey};
+    use spl_associated_token_account::get_associated_token_address;
+    use time_locked_vault::{process_instruction, Deposit, Vault, VaultError, VaultInstruction};
+
+    const UNLOCK_TIME: i64 = 10_000;
+
+    struct CreationContext {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        payer: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl CreationContext {
+        // A vault holding one matured deposit of 1,000 tokens
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                emergency_authority: Some(Pubkey::new_unique()),
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor,
+                    token_mint,
+                    amount: 1_000,
+                    unlock_time: UNLOCK_TIME,
+                    ..Deposit::default()
+                }],
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                depositor,
+                payer: Pubkey::new_unique(),
+                token_mint,
+                vault: vault_account(&program_id, &vault, 1000),
+            }
+        }
+        
+        // The depositor's associated token account, not created yet
+        fn missing_ata(&self) -> TestAccount {
+            TestAccount::wallet(get_associated_token_address(&self.depositor, &self.token_mint)).writable()
+        }
+        
+        // The rent payer, mint, system program and associated token program
+        fn creation_accounts(&self) -> Vec<TestAccount> {
+            vec![
+                TestAccount::wallet(self.payer).signer().writable(),
+                TestAccount::new(self.token_mint, spl_token::id(), vec![]),
+                system_program_account(),
+                TestAccount::new(spl_associated_token_account::id(), Pubkey::default(), vec![]),
+            ]
+        }
+        
+        fn withdraw(&mut self, destination: &mut TestAccount, creation: &mut [TestAccount]) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(1, UNLOCK_TIME);
+            let mut accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            accounts.extend(creation.iter_mut().map(|account| account.info()));
+            take_invocations();
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::Withdraw { deposit_id: 0 }.pack())
+        }
+        
+        fn emergency_withdraw(&mut self, destination: &mut TestAccount, creation: &mut [TestAccount]) -> ProgramResult {
+            let authority = read_vault(&self.vault).emergency_authority.unwrap();
+            let mut authority = TestAccount::wallet(authority).signer();
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut depositor = TestAccount::wallet(self.depositor);
+            let mut accounts = vec![
+                authority.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                depositor.info(),
+            ];
+            accounts.extend(creation.iter_mut().map(|account| account.info()));
+            set_clock(1, 0);
+            take_invocations();
+            let data = VaultInstruction::EmergencyWithdraw { deposit_id: 0 }.pack();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    // Whether `invocations` create `ata` through the associated token program,
+    // paid for by `payer`
+    fn creates(invocations: &[Instruction], ata: &Pubkey, payer: &Pubkey) -> bool {
+        invocations.iter().any(|ix| {
+            ix.program_id == spl_associated_token_account::id()
+                && ix.accounts[0].pubkey == *payer
+                && ix.accounts[1].pubkey == *ata
+        })
+    }
+
+    #[test]
+    fn test_withdraw_creates_missing_ata() {
+        let mut ctx = CreationContext::new();
+        let mut ata = ctx.missing_ata();
+        let mut creation = ctx.creation_accounts();
+        
+        ctx.withdraw(&mut ata, &mut creation).unwrap();
+        
+        let invocations = take_invocations();
+        assert!(creates(&invocations, &ata.key, &ctx.payer));
+        assert_eq!(token_transfers(&invocations), vec![(ata.key, 1_000)]);
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_withdraw_to_missing_ata_fails_without_creation_accounts() {
+        let mut ctx = CreationContext::new();
+        let mut ata = ctx.missing_ata();
+        
+        assert!(ctx.withdraw(&mut ata, &mut []).is_err());
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_existing_ata_is_not_recreated() {
+        let mut ctx = CreationContext::new();
+        let mut ata = token_account(&ctx.token_mint, &ctx.depositor, 0);
+        ata.key = get_associated_token_address(&ctx.depositor, &ctx.token_mint);
+        let mut creation = ctx.creation_accounts();
+        
+        ctx.withdraw(&mut ata, &mut creation).unwrap();
+        
+        let invocations = take_invocations();
+        assert!(!creates(&invocations, &ata.key, &ctx.payer));
+        assert_eq!(token_transfers(&invocations), vec![(ata.key, 1_000)]);
+    }
+
+    #[test]
+    fn test_creation_needs_the_depositor_ata() {
+        let mut ctx = CreationContext::new();
+        let invalid = Err(VaultError::InvalidDestinationAccount.into());
+        
+        // Another wallet's associated token account, or one for another mint
+        let other_wallet = get_associated_token_address(&Pubkey::new_unique(), &ctx.token_mint);
+        let mut creation = ctx.creation_accounts();
+        assert_eq!(ctx.withdraw(&mut TestAccount::wallet(other_wallet).writable(), &mut creation), invalid);
+        let mut ata = ctx.missing_ata();
+        let mut creation = ctx.creation_accounts();
+        creation[1].key = Pubkey::new_unique();
+        assert_eq!(ctx.withdraw(&mut ata, &mut creation), invalid);
+        
+        // The rent payer must sign
+        let mut creation = ctx.creation_accounts();
+        creation[0].is_signer = false;
+        assert!(ctx.withdraw(&mut ata, &mut creation).is_err());
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_emergency_withdraw_creates_missing_ata() {
+        let mut ctx = CreationContext::new();
+        let mut ata = ctx.missing_ata();
+        let mut creation = ctx.creation_accounts();
+        
+        ctx.emergency_withdraw(&mut ata, &mut creation).unwrap();
+        
+        let invocations = take_invocations();
+        assert!(creates(&invocations, &ata.key, &ctx.payer));
+        assert_eq!(token_transfers(&invocations), vec![(ata.key, 1_000)]);
+    }
+
+    #[test]
+    fn test_emergency_destination_is_not_created() {
+        let mut ctx = CreationContext::new();
+        let insurance_fund = Pubkey::new_unique();
+        let mut vault = read_vault(&ctx.vault);
+        vault.emergency_destination = Some(insurance_fund);
+        vault.serialize(&mut ctx.vault.data.as_mut_slice()).unwrap();
+        let ata_key = get_associated_token_address(&insurance_fund, &ctx.token_mint);
+        let mut creation = ctx.creation_accounts();
+        
+        let result = ctx.emergency_withdraw(&mut TestAccount::wallet(ata_key).writable(), &mut creation);
+        
+        assert_eq!(result, Err(VaultError::InvalidDestinationAccount.into()));
+    }
+}
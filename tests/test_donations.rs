@@ -0,0 +1,108 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{builder, Vault, VaultError, VaultInstruction};
+
+    const UNLOCK_TIME: i64 = 1_700_086_400;
+    const NOW: i64 = 1_700_000_000;
+
+    impl Harness {
+        // A vault holding one deposit of 100 tokens
+        fn with_deposit() -> Self {
+            let vault = Vault { is_initialized: true, owner: Pubkey::new_unique(), ..Vault::default() };
+            let mut harness = Self::new(&vault, 1000);
+            let instruction = builder::deposit(
+                &harness.program_id,
+                &harness.depositor,
+                &harness.vault.key,
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                100,
+                UNLOCK_TIME,
+                [0; 32],
+            );
+            harness.deposit_with(&instruction.data, 100, (0, NOW), &mut []).unwrap();
+            harness
+        }
+        
+        fn donate(&mut self, donor: Pubkey, mint: Pubkey, amount: u64) -> ProgramResult {
+            let mut source = token_account(&mint, &donor, 1_000);
+            let mut vault_token = token_account(&self.token_mint, &self.vault.key, 100);
+            let mut token_program = token_program();
+            set_clock(1, NOW);
+            let data = VaultInstruction::DonateToDeposit { deposit_id: 0, amount }.pack();
+            self.call(donor, &data, &mut [&mut source, &mut vault_token, &mut token_program])
+        }
+        
+        fn set_donations_disabled(&mut self, disabled: bool) -> ProgramResult {
+            self.call(self.owner, &VaultInstruction::SetDonationsDisabled { disabled }.pack(), &mut [])
+        }
+    }
+
+    #[test]
+    fn test_third_party_donation_is_credited_to_the_depositor() {
+        let mut harness = Harness::with_deposit();
+        let donor = Pubkey::new_unique();
+        take_invocations();
+        take_events();
+        
+        harness.donate(donor, harness.token_mint, 50).unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers.iter().map(|(_, amount)| *amount).collect::<Vec<_>>(), vec![50]);
+        let vault = read_vault(&harness.vault);
+        let deposit = &vault.deposits[0];
+        assert_eq!(deposit.depositor, harness.depositor);
+        assert_eq!(deposit.unlock_time, UNLOCK_TIME);
+        assert_eq!(deposit.amount, 150);
+        assert_eq!(deposit.donations_received, 50);
+        assert_eq!(vault.total_locked, 150);
+        assert_eq!(vault.lifetime_deposits_created, 1);
+        assert!(!vault.reentrancy_guard);
+        
+        let events = take_events();
+        let donation = events.last().unwrap();
+        assert_eq!(donation[0], b"donation_received");
+        assert_eq!(donation[1], 0u64.to_le_bytes());
+        assert_eq!(donation[2], donor.to_bytes());
+        assert_eq!(donation[3], harness.depositor.to_bytes());
+        assert_eq!(donation[4], 50u64.to_le_bytes());
+        
+        // The depositor withdraws the whole deposit at its unlock time
+        let data = VaultInstruction::Withdraw { deposit_id: 0 }.pack();
+        harness.withdraw_with(harness.depositor, &data, 150, (2, UNLOCK_TIME), &mut []).unwrap();
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers.iter().map(|(_, amount)| *amount).collect::<Vec<_>>(), vec![150]);
+    }
+
+    #[test]
+    fn test_disabled_donations_are_rejected() {
+        let mut harness = Harness::with_deposit();
+        let donor = Pubkey::new_unique();
+        harness.set_donations_disabled(true).unwrap();
+        
+        assert_eq!(harness.donate(donor, harness.token_mint, 50), Err(VaultError::DonationsDisabled.into()));
+        assert_eq!(read_vault(&harness.vault).deposits[0].amount, 100);
+        
+        harness.set_donations_disabled(false).unwrap();
+        harness.donate(donor, harness.token_mint, 50).unwrap();
+        assert_eq!(read_vault(&harness.vault).deposits[0].amount, 150);
+    }
+
+    #[test]
+    fn test_donation_in_another_mint_is_rejected() {
+        let mut harness = Harness::with_deposit();
+        take_invocations();
+        
+        let result = harness.donate(Pubkey::new_unique(), Pubkey::new_unique(), 50);
+        
+        assert_eq!(result, Err(VaultError::TokenMintMismatch.into()));
+        assert!(take_invocations().is_empty());
+        let vault = read_vault(&harness.vault);
+        assert_eq!(vault.deposits[0].amount, 100);
+        assert_eq!(vault.deposits[0].donations_received, 0);
+    }
+}
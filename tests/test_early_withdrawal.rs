@@ -0,0 +1,127 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        early_withdrawal_penalty_bps, process_instruction, Deposit, Vault, VaultError,
+        VaultInstruction,
+    };
+
+    const CREATED_AT: i64 = 1_000;
+    const LOCK: i64 = 10_000;
+    const UNLOCK_TIME: i64 = CREATED_AT + LOCK;
+    const MAX_PENALTY_BPS: u16 = 2_000;
+
+    #[test]
+    fn test_penalty_decays_linearly() {
+        // (share of the lock period elapsed in percent, expected bps)
+        let cases = [(0, 2_000), (25, 1_500), (50, 1_000), (99, 20)];
+        for (elapsed_percent, expected) in cases {
+            let now = CREATED_AT + LOCK * elapsed_percent / 100;
+            assert_eq!(
+                early_withdrawal_penalty_bps(MAX_PENALTY_BPS, CREATED_AT, UNLOCK_TIME, now),
+                expected,
+                "{}% elapsed",
+                elapsed_percent,
+            );
+        }
+    }
+
+    #[test]
+    fn test_penalty_degenerate_cases() {
+        // Exactly at and after the unlock time
+        assert_eq!(early_withdrawal_penalty_bps(MAX_PENALTY_BPS, CREATED_AT, UNLOCK_TIME, UNLOCK_TIME), 0);
+        assert_eq!(early_withdrawal_penalty_bps(MAX_PENALTY_BPS, CREATED_AT, UNLOCK_TIME, i64::MAX), 0);
+        // A one-second lock and a clock behind the deposit time
+        assert_eq!(early_withdrawal_penalty_bps(MAX_PENALTY_BPS, 0, 1, 0), MAX_PENALTY_BPS);
+        assert_eq!(early_withdrawal_penalty_bps(MAX_PENALTY_BPS, CREATED_AT, UNLOCK_TIME, 0), MAX_PENALTY_BPS);
+        // Extreme timestamps don't overflow
+        assert_eq!(early_withdrawal_penalty_bps(MAX_PENALTY_BPS, i64::MIN, i64::MAX, 0), 999);
+    }
+
+    struct EarlyContext {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        treasury: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl EarlyContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let treasury = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor,
+                    token_mint,
+                    amount: 1_000,
+                    unlock_time: UNLOCK_TIME,
+                    created_at: CREATED_AT,
+                    ..Deposit::default()
+                }],
+                max_penalty_bps: MAX_PENALTY_BPS,
+                treasury: Some(treasury),
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                depositor,
+                token_mint,
+                treasury,
+                vault: vault_account(&program_id, &vault, 1000),
+            }
+        }
+        
+        fn early_withdraw(&mut self, treasury: &mut TestAccount, now: i64) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, now);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+                treasury.info(),
+            ];
+            let data = VaultInstruction::EarlyWithdraw { deposit_id: 0 }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_early_withdraw_before_unlock() {
+        let mut ctx = EarlyContext::new();
+        let mut treasury = token_account(&ctx.token_mint, &Pubkey::new_unique(), 0);
+        treasury.key = ctx.treasury;
+        
+        assert_eq!(read_vault(&ctx.vault).deposits[0].withdrawn_at, None);
+        ctx.early_withdraw(&mut treasury, CREATED_AT + LOCK / 2).unwrap();
+        
+        let deposit = &read_vault(&ctx.vault).deposits[0];
+        assert!(deposit.is_withdrawn());
+        assert_eq!(deposit.withdrawn_at, Some(CREATED_AT + LOCK / 2));
+    }
+
+    #[test]
+    fn test_penalty_must_go_to_treasury() {
+        let mut ctx = EarlyContext::new();
+        let mut other = token_account(&ctx.token_mint, &ctx.depositor, 0);
+        
+        let result = ctx.early_withdraw(&mut other, CREATED_AT);
+        
+        assert_eq!(result, Err(VaultError::InvalidTreasuryAccount.into()));
+    }
+}
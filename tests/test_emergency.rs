@@ -0,0 +1,224 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use spl_associated_token_account::get_associated_token_address;
+    use time_locked_vault::{process_instruction, Deposit, Vault, VaultError, VaultInstruction};
+
+    const COOLDOWN: i64 = 3_600;
+    const FIRST_WITHDRAW: i64 = 10_000;
+    const UNLOCK_TIME: i64 = 50_000;
+    const GRACE: i64 = 7 * 24 * 60 * 60;
+
+    struct EmergencyContext {
+        program_id: Pubkey,
+        authority: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl EmergencyContext {
+        fn new(emergency_cooldown: i64) -> Self {
+            let program_id = Pubkey::new_unique();
+            let authority = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: 2,
+                deposits: (0..2)
+                    .map(|id| Deposit {
+                        id,
+                        depositor,
+                        token_mint,
+                        amount: 500,
+                        unlock_time: i64::MAX,
+                        ..Deposit::default()
+                    })
+                    .collect(),
+                emergency_authority: Some(authority),
+                emergency_cooldown,
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                authority,
+                depositor,
+                token_mint,
+                vault: vault_account(&program_id, &vault, 1000),
+            }
+        }
+        
+        // Deposits matured at `UNLOCK_TIME` under a post-unlock grace period
+        fn matured(post_unlock_grace: i64) -> Self {
+            let mut ctx = Self::new(0);
+            let mut vault = read_vault(&ctx.vault);
+            vault.post_unlock_grace = post_unlock_grace;
+            for deposit in &mut vault.deposits {
+                deposit.unlock_time = UNLOCK_TIME;
+            }
+            vault.serialize(&mut ctx.vault.data.as_mut_slice()).unwrap();
+            ctx
+        }
+        
+        // Deposit 0 opted into requiring the depositor's co-signature
+        fn dual_control() -> Self {
+            let mut ctx = Self::new(0);
+            let mut vault = read_vault(&ctx.vault);
+            vault.deposits[0].emergency_requires_depositor = true;
+            vault.serialize(&mut ctx.vault.data.as_mut_slice()).unwrap();
+            ctx
+        }
+        
+        // Emergency withdrawals pay out to `wallet` instead of the depositor
+        fn insured(wallet: Pubkey) -> Self {
+            let mut ctx = Self::new(0);
+            let mut vault = read_vault(&ctx.vault);
+            vault.emergency_destination = Some(wallet);
+            vault.serialize(&mut ctx.vault.data.as_mut_slice()).unwrap();
+            ctx
+        }
+        
+        fn emergency_withdraw(&mut self, deposit_id: u64, now: i64) -> ProgramResult {
+            self.emergency_withdraw_cosigned(deposit_id, now, false)
+        }
+        
+        fn emergency_withdraw_cosigned(&mut self, deposit_id: u64, now: i64, depositor_signs: bool) -> ProgramResult {
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            self.emergency_withdraw_to(deposit_id, now, depositor_signs, &mut destination)
+        }
+        
+        fn emergency_withdraw_to(
+            &mut self,
+            deposit_id: u64,
+            now: i64,
+            depositor_signs: bool,
+            destination: &mut TestAccount,
+        ) -> ProgramResult {
+            set_clock(0, now);
+            let mut authority = TestAccount::wallet(self.authority).signer();
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut depositor = TestAccount::wallet(self.depositor);
+            if depositor_signs {
+                depositor = depositor.signer();
+            }
+            let accounts = vec![
+                authority.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                depositor.info(),
+            ];
+            let data = VaultInstruction::EmergencyWithdraw { deposit_id }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_cooldown_applies_across_deposits() {
+        let mut ctx = EmergencyContext::new(COOLDOWN);
+        ctx.emergency_withdraw(0, FIRST_WITHDRAW).unwrap();
+        
+        let result = ctx.emergency_withdraw(1, FIRST_WITHDRAW + COOLDOWN - 1);
+        
+        assert_eq!(result, Err(VaultError::EmergencyCooldownActive.into()));
+        let deposit = &read_vault(&ctx.vault).deposits[1];
+        assert!(!deposit.is_withdrawn());
+        assert_eq!(deposit.withdrawn_at, None);
+    }
+
+    #[test]
+    fn test_cooldown_elapses_at_boundary() {
+        let mut ctx = EmergencyContext::new(COOLDOWN);
+        ctx.emergency_withdraw(0, FIRST_WITHDRAW).unwrap();
+        
+        ctx.emergency_withdraw(1, FIRST_WITHDRAW + COOLDOWN).unwrap();
+        
+        let vault = read_vault(&ctx.vault);
+        assert!(vault.deposits[1].is_withdrawn());
+        assert_eq!(vault.last_emergency_withdraw, FIRST_WITHDRAW + COOLDOWN);
+        let withdrawn_at: Vec<_> = vault.deposits.iter().map(|d| d.withdrawn_at).collect();
+        assert_eq!(withdrawn_at, vec![Some(FIRST_WITHDRAW), Some(FIRST_WITHDRAW + COOLDOWN)]);
+    }
+
+    #[test]
+    fn test_zero_cooldown_disables_limit() {
+        let mut ctx = EmergencyContext::new(0);
+        ctx.emergency_withdraw(0, FIRST_WITHDRAW).unwrap();
+        
+        ctx.emergency_withdraw(1, FIRST_WITHDRAW).unwrap();
+    }
+
+    #[test]
+    fn test_grace_period_blocks_emergency_withdraw() {
+        let mut ctx = EmergencyContext::matured(GRACE);
+        
+        assert_eq!(ctx.emergency_withdraw(0, UNLOCK_TIME), Err(VaultError::GracePeriodActive.into()));
+        assert_eq!(ctx.emergency_withdraw(0, UNLOCK_TIME + GRACE), Err(VaultError::GracePeriodActive.into()));
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_emergency_withdraw_outside_grace_period() {
+        let mut ctx = EmergencyContext::matured(GRACE);
+        
+        // Before maturity the emergency path is unchanged
+        ctx.emergency_withdraw(0, UNLOCK_TIME - 1).unwrap();
+        ctx.emergency_withdraw(1, UNLOCK_TIME + GRACE + 1).unwrap();
+        
+        let vault = read_vault(&ctx.vault);
+        assert!(vault.deposits.iter().all(|d| d.is_withdrawn()));
+    }
+
+    #[test]
+    fn test_depositor_paid_without_emergency_destination() {
+        let mut ctx = EmergencyContext::new(0);
+        let mut destination = token_account(&ctx.token_mint, &ctx.depositor, 0);
+        
+        ctx.emergency_withdraw_to(0, FIRST_WITHDRAW, false, &mut destination).unwrap();
+        
+        assert_eq!(read_vault(&ctx.vault).deposits[0].emergency_paid_to, Some(destination.key));
+    }
+
+    #[test]
+    fn test_emergency_destination_overrides_depositor() {
+        let insurance_fund = Pubkey::new_unique();
+        let mut ctx = EmergencyContext::insured(insurance_fund);
+        
+        assert_eq!(ctx.emergency_withdraw(0, FIRST_WITHDRAW), Err(VaultError::InvalidDestinationAccount.into()));
+        
+        let mut destination = token_account(&ctx.token_mint, &insurance_fund, 0);
+        destination.key = get_associated_token_address(&insurance_fund, &ctx.token_mint);
+        ctx.emergency_withdraw_to(0, FIRST_WITHDRAW, false, &mut destination).unwrap();
+        
+        let deposit = &read_vault(&ctx.vault).deposits[0];
+        assert!(deposit.is_withdrawn());
+        assert_eq!(deposit.emergency_paid_to, Some(destination.key));
+    }
+
+    #[test]
+    fn test_flagged_deposit_rejects_authority_alone() {
+        let mut ctx = EmergencyContext::dual_control();
+        
+        assert_eq!(ctx.emergency_withdraw(0, FIRST_WITHDRAW), Err(VaultError::UnauthorizedWithdrawal.into()));
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+        
+        // Deposits without the flag keep the authority-only path
+        ctx.emergency_withdraw(1, FIRST_WITHDRAW).unwrap();
+    }
+
+    #[test]
+    fn test_flagged_deposit_withdrawn_when_cosigned() {
+        let mut ctx = EmergencyContext::dual_control();
+        
+        ctx.emergency_withdraw_cosigned(0, FIRST_WITHDRAW, true).unwrap();
+        
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+}
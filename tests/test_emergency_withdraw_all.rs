@@ -0,0 +1,176 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshDeserialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        process_instruction, Deposit, DepositStatus, EmergencyWithdrawAllResult, Vault, VaultError,
+        VaultInstruction, MAX_EVACUATION_DEPOSITS_PER_CALL,
+    };
+
+    const NOW: i64 = 1_700_000_000;
+
+    struct EvacuationContext {
+        program_id: Pubkey,
+        authority: Pubkey,
+        mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl EvacuationContext {
+        // A vault holding `deposits`, with an emergency authority and a day
+        // of cooldown between emergency withdrawals
+        fn new(mint: Pubkey, deposits: Vec<Deposit>) -> Self {
+            let program_id = Pubkey::new_unique();
+            let authority = Pubkey::new_unique();
+            let total_locked = deposits.iter().map(|deposit| deposit.amount).sum();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                emergency_authority: Some(authority),
+                emergency_cooldown: 86_400,
+                deposit_count: deposits.len() as u64,
+                total_locked,
+                deposits,
+                ..Vault::default()
+            };
+            Self { program_id, authority, mint, vault: vault_account(&program_id, &vault, 20_000) }
+        }
+        
+        fn withdraw_all(&mut self, depositor: Pubkey, depositor_signs: bool) -> ProgramResult {
+            let mut authority = TestAccount::wallet(self.authority).signer();
+            let mut destination = token_account(&self.mint, &depositor, 0);
+            let mut source = token_account(&self.mint, &self.vault.key, u64::MAX);
+            let mut token_program = token_program();
+            let depositor_account = TestAccount::wallet(depositor);
+            let mut depositor_account = if depositor_signs { depositor_account.signer() } else { depositor_account };
+            let accounts = vec![
+                authority.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                depositor_account.info(),
+            ];
+            set_clock(1, NOW);
+            take_invocations();
+            take_return_data();
+            let data = VaultInstruction::EmergencyWithdrawAll { depositor, mint: self.mint }.pack();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn result(&self) -> EmergencyWithdrawAllResult {
+            EmergencyWithdrawAllResult::try_from_slice(&take_return_data()).unwrap()
+        }
+    }
+
+    fn deposit(id: u64, depositor: Pubkey, mint: Pubkey, amount: u64) -> Deposit {
+        Deposit { id, depositor, token_mint: mint, amount, unlock_time: NOW + 1_000, ..Deposit::default() }
+    }
+
+    #[test]
+    fn test_deposits_are_withdrawn_in_one_transfer() {
+        let (depositor, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let deposits = vec![
+            deposit(0, depositor, mint, 100),
+            deposit(1, Pubkey::new_unique(), mint, 200),
+            deposit(2, depositor, Pubkey::new_unique(), 300),
+            deposit(3, depositor, mint, 400),
+            Deposit { status: DepositStatus::Withdrawn as u8, ..deposit(4, depositor, mint, 500) },
+        ];
+        let mut ctx = EvacuationContext::new(mint, deposits);
+        
+        ctx.withdraw_all(depositor, false).unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].1, 500);
+        let expected = EmergencyWithdrawAllResult { withdrawn: vec![0, 3], skipped: vec![], finished: true };
+        assert_eq!(ctx.result(), expected);
+        let vault = read_vault(&ctx.vault);
+        let statuses: Vec<u8> = vault.deposits.iter().map(|deposit| deposit.status).collect();
+        let (active, emergency) = (DepositStatus::Active as u8, DepositStatus::EmergencyWithdrawn as u8);
+        assert_eq!(statuses, vec![emergency, active, active, emergency, DepositStatus::Withdrawn as u8]);
+        assert_eq!(vault.deposits[0].emergency_paid_to, Some(transfers[0].0));
+        assert_eq!(vault.lifetime_emergency_withdrawals, 1);
+        assert_eq!(vault.emergency_evacuation, None);
+        
+        // The cooldown applies to the next evacuation
+        assert_eq!(ctx.withdraw_all(depositor, false), Err(VaultError::EmergencyCooldownActive.into()));
+    }
+
+    #[test]
+    fn test_refused_deposits_are_skipped_and_reported() {
+        let (depositor, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let deposits = vec![
+            deposit(0, depositor, mint, 100),
+            Deposit { emergency_requires_depositor: true, ..deposit(1, depositor, mint, 200) },
+            Deposit { frozen: true, ..deposit(2, depositor, mint, 300) },
+            Deposit { locked_destination: Some(Pubkey::new_unique()), ..deposit(3, depositor, mint, 400) },
+        ];
+        let mut ctx = EvacuationContext::new(mint, deposits);
+        
+        ctx.withdraw_all(depositor, false).unwrap();
+        
+        assert_eq!(token_transfers(&take_invocations())[0].1, 100);
+        let expected = EmergencyWithdrawAllResult { withdrawn: vec![0], skipped: vec![1, 2, 3], finished: true };
+        assert_eq!(ctx.result(), expected);
+        let skipped_events = take_events().into_iter().filter(|event| event[0] == b"emergency_skipped").count();
+        assert_eq!(skipped_events, 3);
+        let vault = read_vault(&ctx.vault);
+        assert!(vault.deposits[1..].iter().all(|deposit| !deposit.is_withdrawn()));
+    }
+
+    #[test]
+    fn test_co_signed_deposits_need_the_depositor() {
+        let (depositor, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let deposits = vec![Deposit { emergency_requires_depositor: true, ..deposit(0, depositor, mint, 100) }];
+        let mut ctx = EvacuationContext::new(mint, deposits);
+        
+        ctx.withdraw_all(depositor, true).unwrap();
+        
+        assert_eq!(ctx.result().withdrawn, vec![0]);
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_evacuation_continues_from_the_cursor() {
+        let (depositor, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let count = MAX_EVACUATION_DEPOSITS_PER_CALL as u64 + 4;
+        let deposits = (0..count).map(|id| deposit(id, depositor, mint, 10)).collect();
+        let mut ctx = EvacuationContext::new(mint, deposits);
+        
+        ctx.withdraw_all(depositor, false).unwrap();
+        
+        let first = ctx.result();
+        assert_eq!(first.withdrawn, (0..MAX_EVACUATION_DEPOSITS_PER_CALL as u64).collect::<Vec<_>>());
+        assert!(!first.finished);
+        assert_eq!(token_transfers(&take_invocations())[0].1, 10 * MAX_EVACUATION_DEPOSITS_PER_CALL as u64);
+        let evacuation = read_vault(&ctx.vault).emergency_evacuation.unwrap();
+        assert_eq!((evacuation.next_id, evacuation.end_id), (MAX_EVACUATION_DEPOSITS_PER_CALL as u64, count));
+        
+        // Another depositor has to wait; the same one continues despite the cooldown
+        assert_eq!(ctx.withdraw_all(Pubkey::new_unique(), false), Err(VaultError::EvacuationInProgress.into()));
+        ctx.withdraw_all(depositor, false).unwrap();
+        
+        let second = ctx.result();
+        assert_eq!(second.withdrawn, (MAX_EVACUATION_DEPOSITS_PER_CALL as u64..count).collect::<Vec<_>>());
+        assert!(second.finished);
+        assert_eq!(token_transfers(&take_invocations())[0].1, 40);
+        let vault = read_vault(&ctx.vault);
+        assert!(vault.deposits.iter().all(|deposit| deposit.is_withdrawn()));
+        assert_eq!(vault.emergency_evacuation, None);
+        assert_eq!(vault.lifetime_emergency_withdrawals, 1);
+    }
+
+    #[test]
+    fn test_only_the_emergency_authority_may_evacuate() {
+        let (depositor, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut ctx = EvacuationContext::new(mint, vec![deposit(0, depositor, mint, 100)]);
+        ctx.authority = Pubkey::new_unique();
+        
+        assert_eq!(ctx.withdraw_all(depositor, false), Err(VaultError::UnauthorizedWithdrawal.into()));
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+}
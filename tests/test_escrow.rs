@@ -0,0 +1,138 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+    use std::ops::{Deref, DerefMut};
+    use time_locked_vault::{process_instruction, Vault, VaultError, VaultInstruction};
+
+    const UNLOCK_TIME: i64 = 1_700_086_400;
+    const NOW: i64 = 1_700_000_000;
+
+    // The shared harness, plus the counterparty of its escrow deposit
+    struct Escrow {
+        base: Harness,
+        counterparty: Pubkey,
+    }
+
+    impl Deref for Escrow {
+        type Target = Harness;
+        
+        fn deref(&self) -> &Harness {
+            &self.base
+        }
+    }
+
+    impl DerefMut for Escrow {
+        fn deref_mut(&mut self) -> &mut Harness {
+            &mut self.base
+        }
+    }
+
+    impl Escrow {
+        // A vault holding one escrow deposit of 100 tokens
+        fn new() -> Self {
+            let mut harness = Self { base: Harness::new(&Vault::default(), 1000), counterparty: Pubkey::new_unique() };
+            let counterparty = harness.counterparty;
+            harness.deposit(counterparty).unwrap();
+            harness
+        }
+        
+        fn deposit(&mut self, counterparty: Pubkey) -> ProgramResult {
+            let data =
+                VaultInstruction::DepositEscrow { amount: 100, unlock_time: UNLOCK_TIME, tag: [0; 32], counterparty }
+                    .pack();
+            self.deposit_with(&data, 100, (0, NOW), &mut [])
+        }
+        
+        fn release(&mut self, depositor_signs: bool, counterparty_signs: bool) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor);
+            depositor.is_signer = depositor_signs;
+            let mut counterparty = TestAccount::wallet(self.counterparty);
+            counterparty.is_signer = counterparty_signs;
+            let mut destination = token_account(&self.token_mint, &self.counterparty, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 100);
+            let mut token_program = token_program();
+            let accounts = vec![
+                depositor.info(),
+                counterparty.info(),
+                self.base.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+            ];
+            set_clock(1, NOW);
+            let data = VaultInstruction::ReleaseEscrow { deposit_id: 0 }.pack();
+            process_instruction(&self.base.program_id, &accounts, &data)
+        }
+        
+        fn withdraw(&mut self, withdrawer: Pubkey, now: i64) -> ProgramResult {
+            let data = VaultInstruction::Withdraw { deposit_id: 0 }.pack();
+            self.withdraw_with(withdrawer, &data, 100, (1, now), &mut [])
+        }
+    }
+
+    #[test]
+    fn test_mutual_release_pays_counterparty_before_deadline() {
+        let mut harness = Escrow::new();
+        take_invocations();
+        
+        harness.release(true, true).unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers.iter().map(|(_, amount)| *amount).collect::<Vec<_>>(), vec![100]);
+        assert!(read_vault(&harness.vault).deposits[0].is_withdrawn());
+        
+        // A released deposit can't be refunded
+        let depositor = harness.depositor;
+        assert_eq!(harness.withdraw(depositor, UNLOCK_TIME), Err(VaultError::AlreadyWithdrawn.into()));
+    }
+
+    #[test]
+    fn test_neither_party_releases_alone() {
+        let mut harness = Escrow::new();
+        
+        assert_eq!(harness.release(true, false), Err(ProgramError::MissingRequiredSignature));
+        assert_eq!(harness.release(false, true), Err(ProgramError::MissingRequiredSignature));
+        
+        // Nor can either withdraw before the deadline
+        let (depositor, counterparty) = (harness.depositor, harness.counterparty);
+        assert_eq!(harness.withdraw(depositor, UNLOCK_TIME - 1), Err(VaultError::UnlockTimeNotReached.into()));
+        assert_eq!(harness.withdraw(counterparty, UNLOCK_TIME - 1), Err(VaultError::UnauthorizedWithdrawal.into()));
+        assert!(!read_vault(&harness.vault).deposits[0].is_withdrawn());
+        
+        // Two other signers aren't the escrow's parties
+        harness.counterparty = Pubkey::new_unique();
+        assert_eq!(harness.release(true, true), Err(VaultError::NotEscrowDeposit.into()));
+    }
+
+    #[test]
+    fn test_depositor_refunded_after_deadline() {
+        let mut harness = Escrow::new();
+        let (depositor, counterparty) = (harness.depositor, harness.counterparty);
+        
+        assert_eq!(harness.withdraw(counterparty, UNLOCK_TIME), Err(VaultError::UnauthorizedWithdrawal.into()));
+        
+        harness.withdraw(depositor, UNLOCK_TIME).unwrap();
+        
+        assert!(read_vault(&harness.vault).deposits[0].is_withdrawn());
+        assert_eq!(harness.release(true, true), Err(VaultError::AlreadyWithdrawn.into()));
+    }
+
+    #[test]
+    fn test_escrow_deposits_are_exempt_from_unilateral_emergency_withdrawal() {
+        let harness = Escrow::new();
+        let vault = read_vault(&harness.vault);
+        let deposit = &vault.deposits[0];
+        assert_eq!(deposit.escrow_counterparty, Some(harness.counterparty));
+        assert!(deposit.emergency_requires_depositor);
+    }
+
+    #[test]
+    fn test_counterparty_must_differ_from_depositor() {
+        let mut harness = Escrow::new();
+        let depositor = harness.depositor;
+        assert_eq!(harness.deposit(depositor), Err(VaultError::InvalidInstructionData.into()));
+    }
+}
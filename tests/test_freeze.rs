@@ -0,0 +1,238 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        process_instruction, Deposit, ResolutionDestination, Vault, VaultError, VaultInstruction,
+    };
+
+    const UNLOCK_TIME: i64 = 1_000;
+
+    struct FreezeContext {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        emergency_authority: Pubkey,
+        freeze_authority: Pubkey,
+        treasury: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl FreezeContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let emergency_authority = Pubkey::new_unique();
+            let freeze_authority = Pubkey::new_unique();
+            let treasury = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor,
+                    token_mint,
+                    amount: 1_000,
+                    unlock_time: UNLOCK_TIME,
+                    ..Deposit::default()
+                }],
+                emergency_authority: Some(emergency_authority),
+                freeze_authority: Some(freeze_authority),
+                max_penalty_bps: 1_000,
+                treasury: Some(treasury),
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                depositor,
+                token_mint,
+                emergency_authority,
+                freeze_authority,
+                treasury,
+                vault: vault_account(&program_id, &vault, 1000),
+            }
+        }
+        
+        fn send(&mut self, signer: &Pubkey, instruction: VaultInstruction, now: i64) -> ProgramResult {
+            let mut signer = TestAccount::wallet(*signer).signer();
+            let mut clock = clock_account(0, now);
+            let accounts = vec![signer.info(), self.vault.info(), clock.info()];
+            process_instruction(&self.program_id, &accounts, &instruction.try_to_vec().unwrap())
+        }
+        
+        fn freeze(&mut self, signer: &Pubkey) -> ProgramResult {
+            self.send(signer, VaultInstruction::FreezeDeposit { deposit_id: 0 }, 0)
+        }
+        
+        fn thaw(&mut self, signer: &Pubkey) -> ProgramResult {
+            self.send(signer, VaultInstruction::ThawDeposit { deposit_id: 0 }, 0)
+        }
+        
+        fn withdraw(&mut self, signer: &Pubkey, instruction: VaultInstruction, now: i64) -> ProgramResult {
+            let mut signer = TestAccount::wallet(*signer).signer();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, now);
+            let mut treasury = token_account(&self.token_mint, &Pubkey::new_unique(), 0);
+            treasury.key = self.treasury;
+            let mut depositor = TestAccount::wallet(self.depositor);
+            let mut accounts = vec![
+                signer.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+            ];
+            match instruction {
+                VaultInstruction::EmergencyWithdraw { .. } => accounts.push(depositor.info()),
+                VaultInstruction::EarlyWithdraw { .. } => {
+                    accounts.push(clock.info());
+                    accounts.push(treasury.info());
+                },
+                _ => accounts.push(clock.info()),
+            }
+            process_instruction(&self.program_id, &accounts, &instruction.try_to_vec().unwrap())
+        }
+        
+        fn resolve(&mut self, destination: &mut TestAccount, destination_kind: ResolutionDestination) -> ProgramResult {
+            let mut authority = TestAccount::wallet(self.emergency_authority).signer();
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let accounts = vec![
+                authority.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+            ];
+            let data = VaultInstruction::ResolveFrozenDeposit { deposit_id: 0, destination_kind }
+                .try_to_vec()
+                .unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn set_arbitration_destination(&mut self, arbitration_destination: Option<Pubkey>) {
+            let mut vault = read_vault(&self.vault);
+            vault.arbitration_destination = arbitration_destination;
+            self.vault = vault_account(&self.program_id, &vault, 1000);
+        }
+    }
+
+    #[test]
+    fn test_freeze_blocks_every_path() {
+        let mut ctx = FreezeContext::new();
+        let (depositor, emergency_authority, freeze_authority) =
+            (ctx.depositor, ctx.emergency_authority, ctx.freeze_authority);
+        ctx.freeze(&freeze_authority).unwrap();
+        assert!(read_vault(&ctx.vault).deposits[0].frozen);
+        
+        let frozen = Err(VaultError::DepositFrozen.into());
+        assert_eq!(ctx.withdraw(&depositor, VaultInstruction::Withdraw { deposit_id: 0 }, UNLOCK_TIME), frozen);
+        assert_eq!(ctx.withdraw(&depositor, VaultInstruction::EarlyWithdraw { deposit_id: 0 }, 0), frozen);
+        assert_eq!(
+            ctx.withdraw(&emergency_authority, VaultInstruction::EmergencyWithdraw { deposit_id: 0 }, 0),
+            frozen,
+        );
+        let propose = VaultInstruction::ProposeBeneficiary { deposit_id: 0, new_beneficiary: Pubkey::new_unique() };
+        assert_eq!(ctx.send(&depositor, propose, 0), frozen);
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_thaw_restores_withdrawals() {
+        let mut ctx = FreezeContext::new();
+        let (depositor, freeze_authority) = (ctx.depositor, ctx.freeze_authority);
+        ctx.freeze(&freeze_authority).unwrap();
+        
+        ctx.thaw(&freeze_authority).unwrap();
+        
+        ctx.withdraw(&depositor, VaultInstruction::Withdraw { deposit_id: 0 }, UNLOCK_TIME).unwrap();
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_unauthorized_freezer_rejected() {
+        let mut ctx = FreezeContext::new();
+        let (depositor, emergency_authority) = (ctx.depositor, ctx.emergency_authority);
+        
+        // The emergency authority only freezes when no freeze authority is set
+        assert_eq!(ctx.freeze(&depositor), Err(VaultError::UnauthorizedWithdrawal.into()));
+        assert_eq!(ctx.freeze(&emergency_authority), Err(VaultError::UnauthorizedWithdrawal.into()));
+        assert!(!read_vault(&ctx.vault).deposits[0].frozen);
+    }
+
+    #[test]
+    fn test_emergency_authority_freezes_without_freeze_authority() {
+        let mut ctx = FreezeContext::new();
+        let mut vault = read_vault(&ctx.vault);
+        vault.freeze_authority = None;
+        ctx.vault = vault_account(&ctx.program_id, &vault, 1000);
+        let emergency_authority = ctx.emergency_authority;
+        
+        ctx.freeze(&emergency_authority).unwrap();
+        
+        assert!(read_vault(&ctx.vault).deposits[0].frozen);
+    }
+
+    #[test]
+    fn test_resolve_returns_to_depositor() {
+        let mut ctx = FreezeContext::new();
+        let freeze_authority = ctx.freeze_authority;
+        ctx.freeze(&freeze_authority).unwrap();
+        let mut destination = token_account(&ctx.token_mint, &ctx.depositor, 0);
+        take_invocations();
+        
+        ctx.resolve(&mut destination, ResolutionDestination::ReturnToDepositor).unwrap();
+        
+        assert_eq!(token_transfers(&take_invocations()), vec![(destination.key, 1_000)]);
+        let deposit = &read_vault(&ctx.vault).deposits[0];
+        assert_eq!(deposit.resolution, Some(ResolutionDestination::ReturnToDepositor));
+        assert!(deposit.is_withdrawn());
+    }
+
+    #[test]
+    fn test_resolve_to_arbitration() {
+        let mut ctx = FreezeContext::new();
+        let mut escrow = token_account(&ctx.token_mint, &Pubkey::new_unique(), 0);
+        ctx.set_arbitration_destination(Some(escrow.key));
+        let freeze_authority = ctx.freeze_authority;
+        ctx.freeze(&freeze_authority).unwrap();
+        let mut other = token_account(&ctx.token_mint, &Pubkey::new_unique(), 0);
+        
+        let wrong = ctx.resolve(&mut other, ResolutionDestination::Arbitration);
+        assert_eq!(wrong, Err(VaultError::InvalidDestinationAccount.into()));
+        
+        take_invocations();
+        ctx.resolve(&mut escrow, ResolutionDestination::Arbitration).unwrap();
+        assert_eq!(token_transfers(&take_invocations()), vec![(escrow.key, 1_000)]);
+        assert_eq!(read_vault(&ctx.vault).deposits[0].resolution, Some(ResolutionDestination::Arbitration));
+    }
+
+    #[test]
+    fn test_resolve_without_arbitration_destination() {
+        let mut ctx = FreezeContext::new();
+        let freeze_authority = ctx.freeze_authority;
+        ctx.freeze(&freeze_authority).unwrap();
+        let mut escrow = token_account(&ctx.token_mint, &Pubkey::new_unique(), 0);
+        
+        let result = ctx.resolve(&mut escrow, ResolutionDestination::Arbitration);
+        
+        assert_eq!(result, Err(VaultError::ArbitrationNotConfigured.into()));
+    }
+
+    #[test]
+    fn test_resolve_rejects_unfrozen_deposit() {
+        let mut ctx = FreezeContext::new();
+        let mut destination = token_account(&ctx.token_mint, &ctx.depositor, 0);
+        
+        let result = ctx.resolve(&mut destination, ResolutionDestination::ReturnToDepositor);
+        
+        assert_eq!(result, Err(VaultError::DepositNotFrozen.into()));
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+}
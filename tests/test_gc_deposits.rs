@@ -0,0 +1,152 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey, rent::Rent};
+    use time_locked_vault::{
+        gc_bounty, process_instruction, Deposit, DepositStatus, Vault, VaultError, VaultInstruction, GC_BOUNTY_LAMPORTS,
+    };
+
+    const VAULT_SIZE: usize = 2000;
+    const GC_DELAY: i64 = 100;
+
+    // Deposits 0 and 2 withdrawn at 1,000 and 1,050, deposit 1 still open and
+    // deposit 3 withdrawn before withdrawal times were recorded
+    fn gc_vault(program_id: &Pubkey, gc_delay: Option<i64>, excess_lamports: u64) -> TestAccount {
+        let deposit = |id, withdrawn_at: Option<i64>| Deposit {
+            id,
+            amount: 10,
+            status: if id == 1 { DepositStatus::Active as u8 } else { DepositStatus::Withdrawn as u8 },
+            withdrawn_at,
+            ..Deposit::default()
+        };
+        let vault = Vault {
+            deposit_count: 4,
+            deposits: vec![deposit(0, Some(1_000)), deposit(1, None), deposit(2, Some(1_050)), deposit(3, None)],
+            gc_delay,
+            ..Vault::default()
+        };
+        let lamports = Rent::default().minimum_balance(VAULT_SIZE) + excess_lamports;
+        vault_account(program_id, &vault, VAULT_SIZE).with_lamports(lamports)
+    }
+
+    fn gc(
+        program_id: &Pubkey,
+        vault: &mut TestAccount,
+        caller: &mut TestAccount,
+        max: u8,
+        now: i64,
+    ) -> ProgramResult {
+        let mut clock = clock_account(0, now);
+        let accounts = vec![caller.info(), vault.info(), clock.info()];
+        process_instruction(program_id, &accounts, &VaultInstruction::GcDeposits { max }.pack())
+    }
+
+    fn remaining_ids(vault: &TestAccount) -> Vec<u64> {
+        read_vault(vault).deposits.iter().map(|deposit| deposit.id).collect()
+    }
+
+    #[test]
+    fn test_records_kept_until_gc_delay_passes() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = gc_vault(&program_id, Some(GC_DELAY), 1_000_000);
+        let mut caller = TestAccount::wallet(Pubkey::new_unique()).signer().writable();
+
+        gc(&program_id, &mut vault, &mut caller, 10, 1_099).unwrap();
+        assert_eq!(remaining_ids(&vault), vec![0, 1, 2, 3]);
+
+        gc(&program_id, &mut vault, &mut caller, 10, 1_100).unwrap();
+        assert_eq!(remaining_ids(&vault), vec![1, 2, 3]);
+
+        gc(&program_id, &mut vault, &mut caller, 10, i64::MAX).unwrap();
+        assert_eq!(remaining_ids(&vault), vec![1, 3]);
+        assert_eq!(read_vault(&vault).deposit_count, 4);
+    }
+
+    #[test]
+    fn test_gc_respects_max_and_pays_bounty() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = gc_vault(&program_id, Some(GC_DELAY), 1_000_000);
+        let mut caller = TestAccount::wallet(Pubkey::new_unique()).signer().writable();
+        let vault_lamports = vault.lamports;
+
+        gc(&program_id, &mut vault, &mut caller, 1, 2_000).unwrap();
+        assert_eq!(remaining_ids(&vault), vec![1, 2, 3]);
+        assert_eq!(caller.lamports, GC_BOUNTY_LAMPORTS);
+        assert_eq!(vault.lamports, vault_lamports - GC_BOUNTY_LAMPORTS);
+    }
+
+    #[test]
+    fn test_bounty_capped_at_lamports_above_rent() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = gc_vault(&program_id, Some(GC_DELAY), 7_000);
+        let mut caller = TestAccount::wallet(Pubkey::new_unique()).signer().writable();
+
+        gc(&program_id, &mut vault, &mut caller, 10, 2_000).unwrap();
+
+        assert_eq!(caller.lamports, 7_000);
+        assert_eq!(vault.lamports, Rent::default().minimum_balance(VAULT_SIZE));
+        assert_eq!(gc_bounty(3, 1_000_000), 3 * GC_BOUNTY_LAMPORTS);
+        assert_eq!(gc_bounty(3, 0), 0);
+    }
+
+    #[test]
+    fn test_nothing_to_collect_is_a_no_op() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = gc_vault(&program_id, Some(GC_DELAY), 1_000_000);
+        let mut caller = TestAccount::wallet(Pubkey::new_unique()).signer().writable();
+        let (data, lamports) = (vault.data.clone(), vault.lamports);
+
+        gc(&program_id, &mut vault, &mut caller, 10, 1_000).unwrap();
+        gc(&program_id, &mut vault, &mut caller, 0, 2_000).unwrap();
+
+        assert_eq!((vault.data.clone(), vault.lamports, caller.lamports), (data, lamports, 0));
+    }
+
+    #[test]
+    fn test_gc_needs_a_configured_delay() {
+        let program_id = Pubkey::new_unique();
+        let mut vault = gc_vault(&program_id, None, 1_000_000);
+        let mut caller = TestAccount::wallet(Pubkey::new_unique()).signer().writable();
+
+        assert_eq!(gc(&program_id, &mut vault, &mut caller, 10, 2_000), Err(VaultError::GcDisabled.into()));
+    }
+
+    #[test]
+    fn test_withdraw_records_withdrawal_time() {
+        let program_id = Pubkey::new_unique();
+        let depositor_key = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let vault = Vault {
+            deposit_count: 1,
+            deposits: vec![Deposit {
+                depositor: depositor_key,
+                token_mint: mint,
+                amount: 10,
+                unlock_time: 500,
+                ..Deposit::default()
+            }],
+            ..Vault::default()
+        };
+        let mut vault = vault_account(&program_id, &vault, VAULT_SIZE);
+        let mut depositor = TestAccount::wallet(depositor_key).signer();
+        let mut destination = token_account(&mint, &depositor_key, 0);
+        let mut vault_token = token_account(&mint, &vault.key, 10);
+        let mut token_program = token_program();
+        let mut clock = clock_account(1, 600);
+        let accounts = vec![
+            depositor.info(),
+            vault.info(),
+            destination.info(),
+            vault_token.info(),
+            token_program.info(),
+            clock.info(),
+        ];
+        let data = VaultInstruction::Withdraw { deposit_id: 0 }.pack();
+        process_instruction(&program_id, &accounts, &data).unwrap();
+        drop(accounts);
+
+        assert_eq!(read_vault(&vault).deposits[0].withdrawn_at, Some(600));
+    }
+}
@@ -0,0 +1,185 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        find_global_address, process_instruction, Deposit, GlobalState, Vault, VaultError, VaultInstruction,
+    };
+
+    const NOW: i64 = 1_000;
+
+    struct HaltContext {
+        program_id: Pubkey,
+        authority: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+        global: TestAccount,
+    }
+
+    impl HaltContext {
+        // A vault with one matured deposit and an initialized, un-halted
+        // global state
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let authority = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor,
+                    token_mint,
+                    amount: 100,
+                    unlock_time: NOW - 1,
+                    ..Deposit::default()
+                }],
+                total_locked: 100,
+                ..Vault::default()
+            };
+            let global = GlobalState { authority, ..GlobalState::default() };
+            let mut data = vec![0; GlobalState::LEN];
+            global.serialize(&mut data.as_mut_slice()).unwrap();
+            Self {
+                program_id,
+                authority,
+                depositor,
+                token_mint,
+                vault: vault_account(&program_id, &vault, 2000),
+                global: TestAccount::new(find_global_address(&program_id).0, program_id, data).writable(),
+            }
+        }
+        
+        fn read_global(&self) -> GlobalState {
+            GlobalState::deserialize(&mut self.global.data.as_slice()).unwrap()
+        }
+        
+        fn send(&mut self, signer: Pubkey, instruction: VaultInstruction) -> ProgramResult {
+            let mut signer = TestAccount::wallet(signer).signer();
+            let accounts = vec![signer.info(), self.global.info()];
+            take_events();
+            process_instruction(&self.program_id, &accounts, &instruction.pack())
+        }
+        
+        fn deposit(&mut self, with_global: bool) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut source = token_account(&self.token_mint, &self.depositor, 1_000);
+            let mut vault_token = token_account(&self.token_mint, &self.vault.key, 100);
+            let mut token_program = token_program();
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(0, NOW);
+            let mut accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                source.info(),
+                vault_token.info(),
+                token_program.info(),
+                system_program.info(),
+                clock.info(),
+            ];
+            if with_global {
+                accounts.push(self.global.info());
+            }
+            let data = VaultInstruction::Deposit {
+                amount: 10,
+                unlock_time: NOW + 10,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .pack();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn withdraw(&mut self) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 100);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, NOW);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::Withdraw { deposit_id: 0 }.pack())
+        }
+    }
+
+    #[test]
+    fn test_halt_and_unhalt() {
+        let mut ctx = HaltContext::new();
+        let authority = ctx.authority;
+        
+        ctx.send(authority, VaultInstruction::SetGlobalHalt { halted: true }).unwrap();
+        assert_eq!(take_events(), vec![vec![b"global_halt".to_vec(), vec![1]]]);
+        assert!(ctx.read_global().deposits_halted);
+        assert_eq!(ctx.deposit(true), Err(VaultError::DepositsHalted.into()));
+        assert_eq!(read_vault(&ctx.vault).deposits.len(), 1);
+        
+        ctx.send(authority, VaultInstruction::SetGlobalHalt { halted: false }).unwrap();
+        assert_eq!(take_events(), vec![vec![b"global_halt".to_vec(), vec![0]]]);
+        ctx.deposit(true).unwrap();
+        assert_eq!(read_vault(&ctx.vault).deposits.len(), 2);
+    }
+
+    #[test]
+    fn test_withdrawals_ignore_halt() {
+        let mut ctx = HaltContext::new();
+        let authority = ctx.authority;
+        ctx.send(authority, VaultInstruction::SetGlobalHalt { halted: true }).unwrap();
+        
+        ctx.withdraw().unwrap();
+        
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_omitted_or_uninitialized_global_reads_unhalted() {
+        let mut ctx = HaltContext::new();
+        let authority = ctx.authority;
+        ctx.send(authority, VaultInstruction::SetGlobalHalt { halted: true }).unwrap();
+        
+        // Clients from before the flag day don't pass the global state
+        ctx.deposit(false).unwrap();
+        
+        let mut ctx = HaltContext::new();
+        ctx.global.owner = solana_program::system_program::id();
+        ctx.deposit(true).unwrap();
+        assert_eq!(read_vault(&ctx.vault).deposits.len(), 2);
+    }
+
+    #[test]
+    fn test_only_authority_halts_and_rotation_is_two_step() {
+        let mut ctx = HaltContext::new();
+        let (authority, successor) = (ctx.authority, Pubkey::new_unique());
+        
+        let halt = VaultInstruction::SetGlobalHalt { halted: true };
+        assert_eq!(ctx.send(successor, halt.clone()), Err(VaultError::NotGlobalAuthority.into()));
+        assert_eq!(
+            ctx.send(successor, VaultInstruction::AcceptGlobalAuthority),
+            Err(VaultError::NoPendingAuthorityChange.into()),
+        );
+        
+        let propose = VaultInstruction::ProposeGlobalAuthority { new_authority: Some(successor) };
+        ctx.send(authority, propose).unwrap();
+        // The proposal alone hands over nothing
+        assert_eq!(ctx.send(successor, halt.clone()), Err(VaultError::NotGlobalAuthority.into()));
+        ctx.send(successor, VaultInstruction::AcceptGlobalAuthority).unwrap();
+        
+        assert_eq!(ctx.read_global(), GlobalState { authority: successor, ..GlobalState::default() });
+        assert_eq!(ctx.send(authority, halt.clone()), Err(VaultError::NotGlobalAuthority.into()));
+        ctx.send(successor, halt).unwrap();
+    }
+}
@@ -0,0 +1,105 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, hash::hash, pubkey::Pubkey};
+    use std::ops::{Deref, DerefMut};
+    use time_locked_vault::{process_instruction, Vault, VaultError, VaultInstruction};
+
+    const UNLOCK_TIME: i64 = 1_700_086_400;
+    const NOW: i64 = 1_700_000_000;
+    const SECRET: [u8; 32] = [42; 32];
+
+    // The shared harness, plus the beneficiary of its hashlocked deposit
+    struct Hashlock {
+        base: Harness,
+        beneficiary: Pubkey,
+    }
+
+    impl Deref for Hashlock {
+        type Target = Harness;
+        
+        fn deref(&self) -> &Harness {
+            &self.base
+        }
+    }
+
+    impl DerefMut for Hashlock {
+        fn deref_mut(&mut self) -> &mut Harness {
+            &mut self.base
+        }
+    }
+
+    impl Hashlock {
+        // A vault holding one hashlocked deposit of 100 tokens
+        fn new() -> Self {
+            let mut harness = Self { base: Harness::new(&Vault::default(), 1000), beneficiary: Pubkey::new_unique() };
+            let data = VaultInstruction::DepositWithHashlock {
+                amount: 100,
+                unlock_time: UNLOCK_TIME,
+                tag: [0; 32],
+                hashlock: hash(&SECRET).to_bytes(),
+                beneficiary: harness.beneficiary,
+            }
+            .pack();
+            harness.deposit_with(&data, 100, (0, NOW), &mut []).unwrap();
+            harness
+        }
+        
+        fn claim(&mut self, preimage: [u8; 32]) -> ProgramResult {
+            let mut destination = token_account(&self.token_mint, &self.beneficiary, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 100);
+            let mut token_program = token_program();
+            let accounts = vec![self.base.vault.info(), destination.info(), source.info(), token_program.info()];
+            let data = VaultInstruction::ClaimWithPreimage { deposit_id: 0, preimage }.pack();
+            process_instruction(&self.base.program_id, &accounts, &data)
+        }
+        
+        fn withdraw(&mut self, withdrawer: Pubkey, now: i64) -> ProgramResult {
+            let data = VaultInstruction::Withdraw { deposit_id: 0 }.pack();
+            self.withdraw_with(withdrawer, &data, 100, (1, now), &mut [])
+        }
+    }
+
+    #[test]
+    fn test_preimage_pays_beneficiary_before_unlock() {
+        let mut harness = Hashlock::new();
+        take_invocations();
+        
+        harness.claim(SECRET).unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers.iter().map(|(_, amount)| *amount).collect::<Vec<_>>(), vec![100]);
+        assert!(read_vault(&harness.vault).deposits[0].is_withdrawn());
+        
+        // A claimed deposit can't be refunded
+        let depositor = harness.depositor;
+        assert_eq!(harness.withdraw(depositor, UNLOCK_TIME), Err(VaultError::AlreadyWithdrawn.into()));
+    }
+
+    #[test]
+    fn test_depositor_refunded_after_timeout() {
+        let mut harness = Hashlock::new();
+        let (depositor, beneficiary) = (harness.depositor, harness.beneficiary);
+        
+        assert_eq!(harness.withdraw(depositor, UNLOCK_TIME - 1), Err(VaultError::UnlockTimeNotReached.into()));
+        assert_eq!(harness.withdraw(beneficiary, UNLOCK_TIME), Err(VaultError::UnauthorizedWithdrawal.into()));
+        
+        harness.withdraw(depositor, UNLOCK_TIME).unwrap();
+        
+        assert!(read_vault(&harness.vault).deposits[0].is_withdrawn());
+        assert_eq!(harness.claim(SECRET), Err(VaultError::AlreadyWithdrawn.into()));
+    }
+
+    #[test]
+    fn test_wrong_preimage_rejected() {
+        let mut harness = Hashlock::new();
+        let mut almost = SECRET;
+        almost[31] ^= 1;
+        
+        assert_eq!(harness.claim(almost), Err(VaultError::InvalidPreimage.into()));
+        assert_eq!(harness.claim(hash(&SECRET).to_bytes()), Err(VaultError::InvalidPreimage.into()));
+        assert!(!read_vault(&harness.vault).deposits[0].is_withdrawn());
+    }
+}
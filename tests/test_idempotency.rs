@@ -0,0 +1,99 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        IdempotencyKey, Vault, VaultError, VaultInstruction, IDEMPOTENCY_KEYS_SPACE,
+        MAX_IDEMPOTENCY_KEYS,
+    };
+
+    const NOW: i64 = 1_700_000_000;
+
+    impl Harness {
+        fn empty() -> Self {
+            Self::new(&Vault::default(), 20_000)
+        }
+        
+        fn deposit(&mut self, depositor: Pubkey, idempotency_key: Option<[u8; 16]>) -> ProgramResult {
+            let data = VaultInstruction::Deposit {
+                amount: 100,
+                unlock_time: NOW + 10,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key,
+            }
+            .pack();
+            self.depositor = depositor;
+            self.deposit_with(&data, 100, (1, NOW), &mut [])
+        }
+        
+        fn deposit_count(&self) -> usize {
+            read_vault(&self.vault).deposits.len()
+        }
+    }
+
+    #[test]
+    fn test_replayed_deposit_lands_once() {
+        let mut harness = Harness::empty();
+        let depositor = Pubkey::new_unique();
+        
+        harness.deposit(depositor, Some([7; 16])).unwrap();
+        assert_eq!(harness.deposit(depositor, Some([7; 16])), Err(VaultError::DuplicateDeposit.into()));
+        
+        assert_eq!(harness.deposit_count(), 1);
+    }
+
+    #[test]
+    fn test_keys_are_scoped_and_optional() {
+        let mut harness = Harness::empty();
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        
+        // Another depositor's key never blocks a deposit
+        harness.deposit(alice, Some([1; 16])).unwrap();
+        harness.deposit(bob, Some([1; 16])).unwrap();
+        // Without a key there is no check
+        harness.deposit(alice, None).unwrap();
+        harness.deposit(alice, None).unwrap();
+        
+        assert_eq!(harness.deposit_count(), 4);
+        assert_eq!(read_vault(&harness.vault).idempotency_keys.len(), 2);
+    }
+
+    #[test]
+    fn test_oldest_key_forgotten_when_buffer_full() {
+        let mut harness = Harness::empty();
+        let depositor = Pubkey::new_unique();
+        let key = |i: usize| [i as u8; 16];
+        
+        for i in 0..=MAX_IDEMPOTENCY_KEYS {
+            harness.deposit(depositor, Some(key(i))).unwrap();
+        }
+        
+        let vault = read_vault(&harness.vault);
+        assert_eq!(vault.idempotency_keys.len(), MAX_IDEMPOTENCY_KEYS);
+        assert_eq!(vault.idempotency_cursor, 1);
+        // The first key was overwritten; reusing it evicts the second, not the third
+        harness.deposit(depositor, Some(key(0))).unwrap();
+        assert_eq!(harness.deposit(depositor, Some(key(2))), Err(VaultError::DuplicateDeposit.into()));
+        assert_eq!(harness.deposit_count(), MAX_IDEMPOTENCY_KEYS + 2);
+    }
+
+    #[test]
+    fn test_full_buffer_fits_its_space_budget() {
+        let empty = Vault::default();
+        let full = Vault {
+            idempotency_keys: vec![IdempotencyKey::default(); MAX_IDEMPOTENCY_KEYS],
+            ..Vault::default()
+        };
+        
+        // The empty vector's length prefix is already part of every vault
+        let growth = full.try_to_vec().unwrap().len() - empty.try_to_vec().unwrap().len();
+        assert_eq!(growth + 4, IDEMPOTENCY_KEYS_SPACE);
+    }
+}
@@ -0,0 +1,291 @@
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSerialize;
+    use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+    use time_locked_vault::{
+        process_instruction, OwnerSet, ResolutionDestination, VaultConfig, VaultError, VaultInstruction,
+        INSTRUCTION_VERSION, TAG_CREATE_VAULT, TAG_DEPOSIT, TAG_DEPOSIT_FOR, TAG_DEPOSIT_PAIR, TAG_DEPOSIT_UI_AMOUNT,
+        TAG_WITHDRAW, VERSIONED_TAGS, VERSIONED_TAG_FLAG,
+    };
+
+    fn samples() -> Vec<VaultInstruction> {
+        vec![
+            VaultInstruction::CreateVault {
+                skip_registry: true,
+                arbitration_destination: Some(Pubkey::new_unique()),
+                emergency_destination: None,
+                owner_set: None,
+                config: VaultConfig::default(),
+            },
+            VaultInstruction::Deposit {
+                amount: 7,
+                unlock_time: 1_000,
+                tag: [3; 32],
+                locked_destination: None,
+                no_crank: true,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            },
+            VaultInstruction::Withdraw { deposit_id: 9 },
+            VaultInstruction::CloseVault,
+            VaultInstruction::DepositWithDuration { amount: 1, lock_duration_secs: 60, tag: [0; 32] },
+            VaultInstruction::AcceptConfigAdmin,
+            VaultInstruction::ResolveFrozenDeposit {
+                deposit_id: 2,
+                destination_kind: ResolutionDestination::Arbitration,
+            },
+            VaultInstruction::DepositUiAmount { ui_amount: 5, decimals_exponent: 6, unlock_time: 10, tag: [0; 32] },
+        ]
+    }
+
+    // `fields` encoded as `version` of the instruction tagged `tag`
+    fn versioned(tag: u8, version: u8, fields: impl BorshSerialize) -> Vec<u8> {
+        let mut data = vec![tag | VERSIONED_TAG_FLAG, version];
+        data.extend(fields.try_to_vec().unwrap());
+        data
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for instruction in samples() {
+            assert_eq!(VaultInstruction::unpack(&instruction.pack()).unwrap(), instruction);
+        }
+    }
+
+    #[test]
+    fn test_tags_match_legacy_encoding() {
+        for instruction in samples() {
+            let legacy = instruction.try_to_vec().unwrap();
+            let packed = instruction.pack();
+            assert_eq!(legacy[0], instruction.tag());
+            if VERSIONED_TAGS.contains(&instruction.tag()) {
+                assert_eq!(packed[0], instruction.tag() | VERSIONED_TAG_FLAG);
+                assert_eq!(packed[1], INSTRUCTION_VERSION);
+                assert_eq!(&packed[2..], &legacy[1..]);
+            } else {
+                assert_eq!(packed, legacy);
+            }
+        }
+        assert_eq!(TAG_CREATE_VAULT, 0);
+        assert_eq!(TAG_DEPOSIT, 1);
+        assert_eq!(TAG_WITHDRAW, 2);
+        assert_eq!(TAG_DEPOSIT_UI_AMOUNT, 45);
+        assert_eq!(TAG_DEPOSIT_PAIR, 46);
+    }
+
+    #[test]
+    fn test_legacy_encoding_still_decodes() {
+        for instruction in samples() {
+            let legacy = instruction.try_to_vec().unwrap();
+            assert_eq!(VaultInstruction::unpack(&legacy).unwrap(), instruction);
+        }
+    }
+
+    // Encodings clients already send, byte for byte: the baseline untagged
+    // instructions and the first tagged version. Never edit these; a change
+    // that breaks one breaks those clients, so new versions get new fixtures
+    const AMOUNT_AND_UNLOCK_TIME: [u8; 16] = [7, 0, 0, 0, 0, 0, 0, 0, 0xe8, 3, 0, 0, 0, 0, 0, 0];
+    
+    fn frozen_fixtures() -> Vec<(Vec<u8>, VaultInstruction)> {
+        let deposit = |locked_destination, no_crank| VaultInstruction::Deposit {
+            amount: 7,
+            unlock_time: 1_000,
+            tag: [3; 32],
+            locked_destination,
+            no_crank,
+            emergency_requires_depositor: false,
+            owner_can_release_early: false,
+            idempotency_key: None,
+        };
+        let create_vault = |skip_registry, arbitration_destination| VaultInstruction::CreateVault {
+            skip_registry,
+            arbitration_destination,
+            emergency_destination: None,
+            owner_set: None,
+            config: VaultConfig::default(),
+        };
+        let destination = Pubkey::new_from_array([6; 32]);
+        
+        vec![
+            // Baseline
+            (vec![0], create_vault(true, None)),
+            ([&[1][..], &AMOUNT_AND_UNLOCK_TIME, &[3; 32]].concat(), deposit(None, false)),
+            (vec![2, 9, 0, 0, 0, 0, 0, 0, 0], VaultInstruction::Withdraw { deposit_id: 9 }),
+            (vec![3, 9, 0, 0, 0, 0, 0, 0, 0], VaultInstruction::EmergencyWithdraw { deposit_id: 9 }),
+            // Version 1
+            ([&[0x80, 1, 0, 1][..], &[6; 32]].concat(), create_vault(false, Some(destination))),
+            (
+                [&[0x81, 1][..], &AMOUNT_AND_UNLOCK_TIME, &[3; 32], &[1], &[6; 32], &[1]].concat(),
+                deposit(Some(destination), true),
+            ),
+            (
+                [&[0x97, 1][..], &AMOUNT_AND_UNLOCK_TIME, &[3; 32], &[0, 0]].concat(),
+                VaultInstruction::DepositFor {
+                    amount: 7,
+                    unlock_time: 1_000,
+                    tag: [3; 32],
+                    locked_destination: None,
+                    no_crank: false,
+                    emergency_requires_depositor: false,
+                    owner_can_release_early: false,
+                },
+            ),
+            (
+                [&[0x9a, 1][..], &AMOUNT_AND_UNLOCK_TIME, &[3; 32]].concat(),
+                VaultInstruction::DepositWithDuration { amount: 7, lock_duration_secs: 1_000, tag: [3; 32] },
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_frozen_fixtures_decode() {
+        for (data, instruction) in frozen_fixtures() {
+            assert_eq!(VaultInstruction::unpack(&data).unwrap(), instruction, "{data:?}");
+        }
+    }
+
+    #[test]
+    fn test_untagged_encodings_from_before_versioning_decode() {
+        let unpack = |tag, fields: &[u8]| VaultInstruction::unpack(&[&[tag][..], fields].concat()).unwrap();
+        let destination = Some(Pubkey::new_unique());
+        let base = (7u64, 1_000i64, [3u8; 32]);
+        let deposit = |locked_destination, no_crank| VaultInstruction::Deposit {
+            amount: 7,
+            unlock_time: 1_000,
+            tag: [3; 32],
+            locked_destination,
+            no_crank,
+            emergency_requires_depositor: false,
+            owner_can_release_early: false,
+            idempotency_key: None,
+        };
+        
+        let three = base.try_to_vec().unwrap();
+        assert_eq!(unpack(TAG_DEPOSIT, &three), deposit(None, false));
+        let four = (base, destination).try_to_vec().unwrap();
+        assert_eq!(unpack(TAG_DEPOSIT, &four), deposit(destination, false));
+        let five = (base, destination, true).try_to_vec().unwrap();
+        assert_eq!(unpack(TAG_DEPOSIT, &five), deposit(destination, true));
+        assert_eq!(
+            unpack(TAG_DEPOSIT_FOR, &four),
+            VaultInstruction::DepositFor {
+                amount: 7,
+                unlock_time: 1_000,
+                tag: [3; 32],
+                locked_destination: destination,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+            },
+        );
+        
+        let create_vault = |skip_registry, arbitration_destination| VaultInstruction::CreateVault {
+            skip_registry,
+            arbitration_destination,
+            emergency_destination: None,
+            owner_set: None,
+            config: VaultConfig::default(),
+        };
+        assert_eq!(unpack(TAG_CREATE_VAULT, &[]), create_vault(true, None));
+        assert_eq!(unpack(TAG_CREATE_VAULT, &[0]), create_vault(false, None));
+        let two = (false, destination).try_to_vec().unwrap();
+        assert_eq!(unpack(TAG_CREATE_VAULT, &two), create_vault(false, destination));
+    }
+
+    #[test]
+    fn test_older_create_vault_versions_decode() {
+        let arbitration_destination = Some(Pubkey::new_unique());
+        let create = |emergency_destination, owner_set| VaultInstruction::CreateVault {
+            skip_registry: true,
+            arbitration_destination,
+            emergency_destination,
+            owner_set,
+            config: VaultConfig::default(),
+        };
+        let fields = (true, arbitration_destination);
+        
+        let v2 = versioned(TAG_CREATE_VAULT, 2, fields);
+        assert_eq!(VaultInstruction::unpack(&v2).unwrap(), create(None, None));
+        let emergency_destination = Some(Pubkey::new_unique());
+        let v4 = versioned(TAG_CREATE_VAULT, 4, (fields, emergency_destination));
+        assert_eq!(VaultInstruction::unpack(&v4).unwrap(), create(emergency_destination, None));
+        let owner_set = Some(OwnerSet { members: vec![Pubkey::new_unique(), Pubkey::new_unique()], threshold: 2 });
+        let v6 = versioned(TAG_CREATE_VAULT, 6, (fields, emergency_destination, owner_set.clone()));
+        assert_eq!(VaultInstruction::unpack(&v6).unwrap(), create(emergency_destination, owner_set));
+    }
+
+    #[test]
+    fn test_older_deposit_versions_decode() {
+        let deposit = |emergency_requires_depositor, idempotency_key, owner_can_release_early| {
+            VaultInstruction::Deposit {
+                amount: 7,
+                unlock_time: 1_000,
+                tag: [3; 32],
+                locked_destination: None,
+                no_crank: true,
+                emergency_requires_depositor,
+                owner_can_release_early,
+                idempotency_key,
+            }
+        };
+        let fields = (7u64, 1_000i64, [3u8; 32], None::<Pubkey>, true);
+        
+        let v1 = versioned(TAG_DEPOSIT, 1, fields);
+        assert_eq!(VaultInstruction::unpack(&v1).unwrap(), deposit(false, None, false));
+        let v3 = versioned(TAG_DEPOSIT, 3, (fields, true));
+        assert_eq!(VaultInstruction::unpack(&v3).unwrap(), deposit(true, None, false));
+        let key = Some([1u8; 16]);
+        let v5 = versioned(TAG_DEPOSIT, 5, (fields, true, key));
+        assert_eq!(VaultInstruction::unpack(&v5).unwrap(), deposit(true, key, false));
+        
+        let destination = Pubkey::new_unique();
+        let deposit_for = |emergency_requires_depositor| VaultInstruction::DepositFor {
+            amount: 7,
+            unlock_time: 1_000,
+            tag: [3; 32],
+            locked_destination: Some(destination),
+            no_crank: false,
+            emergency_requires_depositor,
+            owner_can_release_early: false,
+        };
+        let fields = (7u64, 1_000i64, [3u8; 32], Some(destination), false);
+        
+        let v1 = versioned(TAG_DEPOSIT_FOR, 1, fields);
+        assert_eq!(VaultInstruction::unpack(&v1).unwrap(), deposit_for(false));
+        let v5 = versioned(TAG_DEPOSIT_FOR, 5, (fields, true));
+        assert_eq!(VaultInstruction::unpack(&v5).unwrap(), deposit_for(true));
+    }
+
+    #[test]
+    fn test_unknown_tags_and_versions_rejected() {
+        let invalid = |data: &[u8]| matches!(VaultInstruction::unpack(data), Err(VaultError::InvalidInstructionData));
+        
+        assert!(invalid(&[]));
+        assert!(invalid(&[VERSIONED_TAG_FLAG - 1]));
+        assert!(invalid(&[u8::MAX]));
+        
+        let mut future_version = VaultInstruction::CloseVault.pack();
+        future_version[0] |= VERSIONED_TAG_FLAG;
+        future_version.push(INSTRUCTION_VERSION);
+        assert!(invalid(&future_version), "unversioned tags can't carry a version");
+        
+        let mut deposit = samples()[1].pack();
+        deposit[1] = INSTRUCTION_VERSION + 1;
+        assert!(invalid(&deposit));
+        deposit[1] = 0;
+        assert!(invalid(&deposit));
+        assert!(invalid(&deposit[..1]));
+        
+        let mut trailing = samples()[2].pack();
+        trailing.push(0);
+        assert!(invalid(&trailing));
+    }
+
+    #[test]
+    fn test_unknown_tag_fails_without_panicking() {
+        let result = process_instruction(&Pubkey::new_unique(), &[], &[VERSIONED_TAG_FLAG - 1, 0, 0]);
+        
+        assert_eq!(result, Err(ProgramError::Custom(VaultError::InvalidInstructionData as u32)));
+    }
+}
@@ -0,0 +1,126 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use std::ops::{Deref, DerefMut};
+    use time_locked_vault::{Vault, VaultError, VaultInstruction};
+
+    const NOW: i64 = 1_700_000_000;
+    const UNLOCK_TIME: i64 = NOW + 100;
+
+    // The shared harness, plus the vault's insurance pool token account
+    struct Insured {
+        base: Harness,
+        pool: TestAccount,
+    }
+
+    impl Deref for Insured {
+        type Target = Harness;
+        
+        fn deref(&self) -> &Harness {
+            &self.base
+        }
+    }
+
+    impl DerefMut for Insured {
+        fn deref_mut(&mut self) -> &mut Harness {
+            &mut self.base
+        }
+    }
+
+    impl Insured {
+        fn new(insurance_bps: u16) -> Self {
+            let base = Harness::new(&Vault { owner: Pubkey::new_unique(), ..Vault::default() }, 2000);
+            let pool = token_account(&base.token_mint, &Pubkey::new_unique(), 0);
+            let mut harness = Self { base, pool };
+            let data = VaultInstruction::SetInsurance { insurance_bps, insurance_pool: harness.pool.key }.pack();
+            let Insured { base, pool } = &mut harness;
+            base.call(base.owner, &data, &mut [pool]).unwrap();
+            harness
+        }
+        
+        // Deposit `amount` of the harness's mint, passing the pool once insurance is on
+        fn deposit(&mut self, amount: u64) -> ProgramResult {
+            let data = VaultInstruction::Deposit {
+                amount,
+                unlock_time: UNLOCK_TIME,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .pack();
+            let Insured { base, pool } = self;
+            if base.state().insurance_bps > 0 {
+                base.deposit_with(&data, amount, (1, NOW), &mut [pool])
+            } else {
+                base.deposit_with(&data, amount, (1, NOW), &mut [])
+            }
+        }
+        
+        fn withdraw(&mut self, deposit_id: u64) -> ProgramResult {
+            let data = VaultInstruction::Withdraw { deposit_id }.pack();
+            let depositor = self.depositor;
+            self.withdraw_with(depositor, &data, 1_000_000, (2, UNLOCK_TIME), &mut [])
+        }
+    }
+
+    #[test]
+    fn test_premium_rounds_up_for_odd_amounts() {
+        let mut harness = Insured::new(250);
+        let pool = harness.pool.key;
+        
+        for (amount, premium) in [(1_001, 26), (3, 1), (40_000, 1_000)] {
+            take_invocations();
+            harness.deposit(amount).unwrap();
+        
+            let transfers = token_transfers(&take_invocations());
+            assert_eq!(transfers[0], (pool, premium));
+            assert_eq!(transfers[1].1, amount - premium);
+        
+            let deposit = read_vault(&harness.vault).deposits.pop().unwrap();
+            assert_eq!((deposit.amount, deposit.premium_paid), (amount - premium, premium));
+        }
+        
+        let vault = read_vault(&harness.vault);
+        assert_eq!(vault.insurance_premiums, 26 + 1 + 1_000);
+        assert_eq!(vault.total_locked, 975 + 2 + 39_000);
+    }
+
+    #[test]
+    fn test_net_amount_withdrawn() {
+        let mut harness = Insured::new(250);
+        harness.deposit(1_001).unwrap();
+        take_invocations();
+        
+        harness.withdraw(0).unwrap();
+        
+        let amounts: Vec<u64> = token_transfers(&take_invocations()).iter().map(|(_, amount)| *amount).collect();
+        assert_eq!(amounts, vec![975]);
+    }
+
+    #[test]
+    fn test_zero_bps_skips_insurance() {
+        let mut harness = Insured::new(0);
+        take_invocations();
+        
+        harness.deposit(1_001).unwrap();
+        
+        assert_eq!(token_transfers(&take_invocations()).len(), 1);
+        let vault = read_vault(&harness.vault);
+        assert_eq!((vault.deposits[0].amount, vault.deposits[0].premium_paid), (1_001, 0));
+        assert_eq!(vault.insurance_premiums, 0);
+    }
+
+    #[test]
+    fn test_pool_must_hold_deposit_mint() {
+        let mut harness = Insured::new(250);
+        harness.token_mint = Pubkey::new_unique();
+        
+        assert_eq!(harness.deposit(1_000), Err(VaultError::TokenMintMismatch.into()));
+    }
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "layout")]
+
+use borsh::BorshSerialize;
+use solana_program::pubkey::Pubkey;
+use time_locked_vault::{
+    layout::{account_layouts, field_offset, FieldSpec, DEPOSIT_HEADER, VAULT_HEADER},
+    Deposit, DepositStatus, Vault,
+};
+
+fn sample_vault() -> Vault {
+    Vault {
+        is_initialized: true,
+        owner: Pubkey::new_unique(),
+        deposit_count: 0x0102_0304_0506_0708,
+        deposits: vec![
+            Deposit {
+                id: 7,
+                depositor: Pubkey::new_unique(),
+                token_mint: Pubkey::new_unique(),
+                amount: 1_000,
+                unlock_time: 2_000,
+                status: DepositStatus::Withdrawn as u8,
+                tag: [9; 32],
+                created_at: 3_000,
+                ..Deposit::default()
+            },
+            Deposit::default(),
+        ],
+        ..Vault::default()
+    }
+}
+
+fn probe(data: &[u8], fields: &[FieldSpec], name: &str) -> Vec<u8> {
+    let offset = field_offset(fields, name).unwrap();
+    let size = fields.iter().find(|(field, _, _)| *field == name).unwrap().2;
+    data[offset..offset + size].to_vec()
+}
+
+#[test]
+fn test_vault_header_offsets_match_serialization() {
+    let vault = sample_vault();
+    let data = vault.try_to_vec().unwrap();
+    
+    assert_eq!(probe(&data, VAULT_HEADER, "owner"), vault.owner.to_bytes().to_vec());
+    assert_eq!(probe(&data, VAULT_HEADER, "deposit_count"), vault.deposit_count.to_le_bytes().to_vec());
+    assert_eq!(probe(&data, VAULT_HEADER, "deposits_len"), 2u32.to_le_bytes().to_vec());
+}
+
+#[test]
+fn test_deposit_header_offsets_match_serialization() {
+    let vault = sample_vault();
+    let deposit = &vault.deposits[0];
+    let data = vault.try_to_vec().unwrap();
+    let start: usize = VAULT_HEADER.iter().map(|(_, _, size)| size).sum();
+    let data = &data[start..];
+    
+    assert_eq!(probe(data, DEPOSIT_HEADER, "id"), deposit.id.to_le_bytes().to_vec());
+    assert_eq!(probe(data, DEPOSIT_HEADER, "depositor"), deposit.depositor.to_bytes().to_vec());
+    assert_eq!(probe(data, DEPOSIT_HEADER, "token_mint"), deposit.token_mint.to_bytes().to_vec());
+    assert_eq!(probe(data, DEPOSIT_HEADER, "amount"), deposit.amount.to_le_bytes().to_vec());
+    assert_eq!(probe(data, DEPOSIT_HEADER, "unlock_time"), deposit.unlock_time.to_le_bytes().to_vec());
+    assert_eq!(probe(data, DEPOSIT_HEADER, "status"), vec![1]);
+    assert_eq!(probe(data, DEPOSIT_HEADER, "tag"), deposit.tag.to_vec());
+    assert_eq!(probe(data, DEPOSIT_HEADER, "created_at"), deposit.created_at.to_le_bytes().to_vec());
+}
+
+#[test]
+fn test_write_layouts_json() {
+    let layouts = account_layouts();
+    assert_eq!(layouts["Vault"]["fixedHeader"][1]["offset"], 32);
+    assert_eq!(layouts["Vault"]["deposits"]["offset"], 44);
+    
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("layouts.json");
+    std::fs::write(path, serde_json::to_string_pretty(&layouts).unwrap()).unwrap();
+}
@@ -0,0 +1,125 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use solana_program::pubkey::Pubkey;
+    use time_locked_vault::{Vault, VaultInstruction};
+
+    const NOW: i64 = 1_700_000_000;
+
+    impl Harness {
+        // A vault with an emergency authority that lets anyone collect
+        // withdrawn records right away
+        fn collecting() -> Self {
+            let vault = Vault {
+                emergency_authority: Some(Pubkey::new_unique()),
+                gc_delay: Some(0),
+                ..Vault::default()
+            };
+            Self::new(&vault, 100_000)
+        }
+
+        fn deposit(&mut self, amount: u64) {
+            let data = VaultInstruction::Deposit {
+                amount,
+                unlock_time: NOW + 10,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .pack();
+            self.deposit_with(&data, amount, (1, NOW), &mut []).unwrap();
+        }
+
+        fn withdraw(&mut self, deposit_id: u64) {
+            let data = VaultInstruction::Withdraw { deposit_id }.pack();
+            self.withdraw_with(self.depositor, &data, u64::MAX, (2, NOW + 100), &mut []).unwrap();
+        }
+
+        fn emergency_withdraw(&mut self, deposit_id: u64) {
+            set_clock(2, NOW + 100);
+            let authority = self.state().emergency_authority.unwrap();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, u64::MAX);
+            let mut token_program = token_program();
+            let mut depositor = TestAccount::wallet(self.depositor);
+            let data = VaultInstruction::EmergencyWithdraw { deposit_id }.pack();
+            let mut accounts = [&mut destination, &mut source, &mut token_program, &mut depositor];
+            self.call(authority, &data, &mut accounts).unwrap();
+        }
+
+        fn gc(&mut self) {
+            let mut clock = clock_account(3, NOW + 200);
+            let data = VaultInstruction::GcDeposits { max: 8 }.pack();
+            self.call(Pubkey::new_unique(), &data, &mut [&mut clock]).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_lifetime_totals_survive_gc() {
+        let mut harness = Harness::collecting();
+        harness.deposit(100);
+        harness.deposit(40);
+        harness.withdraw(0);
+        harness.emergency_withdraw(1);
+        harness.gc();
+
+        let vault = read_vault(&harness.vault);
+        assert!(vault.deposits.is_empty());
+        assert_eq!(vault.lifetime_deposits_created, 2);
+        assert_eq!(vault.lifetime_amount_deposited, 140);
+        assert_eq!(vault.lifetime_amount_withdrawn, 140);
+        assert_eq!(vault.lifetime_emergency_withdrawals, 1);
+        let summary = vault.to_string();
+        assert!(summary.ends_with("lifetime 2 deposits, 140 deposited, 140 withdrawn, 1 emergency withdrawals"));
+    }
+
+    #[test]
+    fn test_random_operations_match_lifetime_totals() {
+        for seed in 0..8 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut harness = Harness::collecting();
+            let (mut created, mut deposited, mut withdrawn, mut emergencies) = (0u64, 0u128, 0u128, 0u64);
+
+            for _ in 0..60 {
+                let vault = read_vault(&harness.vault);
+                let open: Vec<_> =
+                    vault.deposits.iter().filter(|d| !d.is_withdrawn()).map(|d| (d.id, d.amount)).collect();
+                match rng.gen_range(0, 10) {
+                    0..=4 => {
+                        let amount = rng.gen_range(1, u64::MAX / 64);
+                        harness.deposit(amount);
+                        created += 1;
+                        deposited += amount as u128;
+                    },
+                    5 | 6 if !open.is_empty() => {
+                        let (id, amount) = open[rng.gen_range(0, open.len())];
+                        harness.withdraw(id);
+                        withdrawn += amount as u128;
+                    },
+                    7 if !open.is_empty() => {
+                        let (id, amount) = open[rng.gen_range(0, open.len())];
+                        harness.emergency_withdraw(id);
+                        withdrawn += amount as u128;
+                        emergencies += 1;
+                    },
+                    _ => harness.gc(),
+                }
+
+                let vault = read_vault(&harness.vault);
+                assert_eq!(vault.lifetime_deposits_created, created);
+                assert_eq!(vault.lifetime_amount_deposited, deposited);
+                assert_eq!(vault.lifetime_amount_withdrawn, withdrawn);
+                assert_eq!(vault.lifetime_emergency_withdrawals, emergencies);
+                let open_amount: u128 =
+                    vault.deposits.iter().filter(|d| !d.is_withdrawn()).map(|d| d.amount as u128).sum();
+                assert_eq!(vault.lifetime_amount_deposited - vault.lifetime_amount_withdrawn, open_amount);
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        process_instruction, LockTier, Vault, VaultError, VaultInstruction, LOCK_FOUR_YEARS_SECS,
+        LOCK_ONE_MONTH_SECS, LOCK_ONE_YEAR_SECS, LOCK_SIX_MONTHS_SECS, LOCK_THREE_MONTHS_SECS,
+    };
+
+    const NOW: i64 = 1_700_000_000;
+
+    const TIERS: [(LockTier, i64); 5] = [
+        (LockTier::OneMonth, LOCK_ONE_MONTH_SECS),
+        (LockTier::ThreeMonths, LOCK_THREE_MONTHS_SECS),
+        (LockTier::SixMonths, LOCK_SIX_MONTHS_SECS),
+        (LockTier::OneYear, LOCK_ONE_YEAR_SECS),
+        (LockTier::FourYears, LOCK_FOUR_YEARS_SECS),
+    ];
+
+    fn deposit_tiered(
+        vault: &mut TestAccount,
+        program_id: &Pubkey,
+        depositor_key: Pubkey,
+        tier: LockTier,
+    ) -> ProgramResult {
+        let mint = Pubkey::new_unique();
+        let mut depositor = TestAccount::wallet(depositor_key).signer();
+        let mut source = token_account(&mint, &depositor_key, 100);
+        let mut vault_token = token_account(&mint, &vault.key, 0);
+        let mut token_program = token_program();
+        let mut system_program = system_program_account();
+        let mut clock = clock_account(0, NOW);
+        let accounts = vec![
+            depositor.info(),
+            vault.info(),
+            source.info(),
+            vault_token.info(),
+            token_program.info(),
+            system_program.info(),
+            clock.info(),
+        ];
+        let data = VaultInstruction::DepositTiered { amount: 100, tier, tag: [0; 32] }.pack();
+        process_instruction(program_id, &accounts, &data)
+    }
+
+    fn extend_lock(
+        vault: &mut TestAccount,
+        program_id: &Pubkey,
+        depositor_key: Pubkey,
+        new_tier: LockTier,
+        now: i64,
+    ) -> ProgramResult {
+        let mut depositor = TestAccount::wallet(depositor_key).signer();
+        let mut clock = clock_account(1, now);
+        let accounts = vec![depositor.info(), vault.info(), clock.info()];
+        let data = VaultInstruction::ExtendLock { deposit_id: 0, new_tier }.pack();
+        process_instruction(program_id, &accounts, &data)
+    }
+
+    #[test]
+    fn test_each_tier_unlocks_after_its_duration() {
+        for (tier, secs) in TIERS {
+            let program_id = Pubkey::new_unique();
+            let mut vault = vault_account(&program_id, &Vault::default(), 1000);
+        
+            deposit_tiered(&mut vault, &program_id, Pubkey::new_unique(), tier).unwrap();
+        
+            let unlock_time = NOW + secs;
+            assert_eq!(take_return_data(), unlock_time.to_le_bytes());
+            let deposit = &read_vault(&vault).deposits[0];
+            assert_eq!(deposit.unlock_time, unlock_time);
+            assert_eq!(deposit.lock_tier.and_then(LockTier::from_u8), Some(tier));
+        }
+    }
+
+    #[test]
+    fn test_extend_to_higher_tier_relocks() {
+        let program_id = Pubkey::new_unique();
+        let depositor = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault::default(), 1000);
+        deposit_tiered(&mut vault, &program_id, depositor, LockTier::ThreeMonths).unwrap();
+        
+        let later = NOW + LOCK_ONE_MONTH_SECS;
+        extend_lock(&mut vault, &program_id, depositor, LockTier::OneYear, later).unwrap();
+        
+        let deposit = &read_vault(&vault).deposits[0];
+        assert_eq!(deposit.unlock_time, later + LOCK_ONE_YEAR_SECS);
+        assert_eq!(deposit.lock_tier, Some(LockTier::OneYear as u8));
+    }
+
+    #[test]
+    fn test_extend_requires_strictly_higher_tier() {
+        let program_id = Pubkey::new_unique();
+        let depositor = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault::default(), 1000);
+        deposit_tiered(&mut vault, &program_id, depositor, LockTier::SixMonths).unwrap();
+        
+        for tier in [LockTier::OneMonth, LockTier::ThreeMonths, LockTier::SixMonths] {
+            let result = extend_lock(&mut vault, &program_id, depositor, tier, NOW);
+            assert_eq!(result, Err(VaultError::InvalidLockTier.into()));
+        }
+        assert_eq!(read_vault(&vault).deposits[0].unlock_time, NOW + LOCK_SIX_MONTHS_SECS);
+    }
+
+    #[test]
+    fn test_extend_rejects_untiered_and_other_signers() {
+        let program_id = Pubkey::new_unique();
+        let depositor = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault::default(), 1000);
+        deposit_tiered(&mut vault, &program_id, depositor, LockTier::OneMonth).unwrap();
+        
+        let result = extend_lock(&mut vault, &program_id, Pubkey::new_unique(), LockTier::FourYears, NOW);
+        assert_eq!(result, Err(VaultError::UnauthorizedWithdrawal.into()));
+        
+        let mut untiered = read_vault(&vault);
+        untiered.deposits[0].lock_tier = None;
+        let mut vault = vault_account(&program_id, &untiered, 1000);
+        let result = extend_lock(&mut vault, &program_id, depositor, LockTier::FourYears, NOW);
+        assert_eq!(result, Err(VaultError::InvalidLockTier.into()));
+    }
+}
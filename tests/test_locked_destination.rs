@@ -0,0 +1,135 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{process_instruction, Deposit, Vault, VaultError, VaultInstruction};
+
+    const UNLOCK_TIME: i64 = 10_000;
+    const CHANGE_DELAY: i64 = 2 * 24 * 60 * 60;
+
+    struct DestinationContext {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        locked: TestAccount,
+        vault: TestAccount,
+    }
+
+    impl DestinationContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let locked = token_account(&token_mint, &depositor, 0);
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor,
+                    token_mint,
+                    amount: 1_000,
+                    unlock_time: UNLOCK_TIME,
+                    locked_destination: Some(locked.key),
+                    ..Deposit::default()
+                }],
+                destination_change_delay: CHANGE_DELAY,
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                depositor,
+                token_mint,
+                locked,
+                vault: vault_account(&program_id, &vault, 1000),
+            }
+        }
+        
+        fn withdraw(&mut self, destination: &mut TestAccount) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, UNLOCK_TIME);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Withdraw { deposit_id: 0 }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn change(&mut self, new_destination: Pubkey, now: i64) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut clock = clock_account(0, now);
+            let accounts = vec![depositor.info(), self.vault.info(), clock.info()];
+            let data = VaultInstruction::ChangeLockedDestination { deposit_id: 0, new_destination }
+                .try_to_vec()
+                .unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_redirect_attempt_is_rejected() {
+        let mut ctx = DestinationContext::new();
+        // An account the (compromised) depositor key controls, but not the locked one
+        let mut attacker = token_account(&ctx.token_mint, &ctx.depositor, 0);
+        
+        let result = ctx.withdraw(&mut attacker);
+        
+        assert_eq!(result, Err(VaultError::InvalidDestinationAccount.into()));
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_withdraw_to_locked_destination() {
+        let mut ctx = DestinationContext::new();
+        let mut locked = token_account(&ctx.token_mint, &ctx.depositor, 0);
+        locked.key = ctx.locked.key;
+        
+        ctx.withdraw(&mut locked).unwrap();
+        
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_timelocked_destination_change() {
+        let mut ctx = DestinationContext::new();
+        let mut new_destination = token_account(&ctx.token_mint, &ctx.depositor, 0);
+        
+        ctx.change(new_destination.key, 0).unwrap();
+        assert_eq!(ctx.change(new_destination.key, CHANGE_DELAY - 1), Err(VaultError::TimelockNotElapsed.into()));
+        // Still locked to the old destination while the change is pending
+        assert_eq!(ctx.withdraw(&mut new_destination), Err(VaultError::InvalidDestinationAccount.into()));
+        
+        ctx.change(new_destination.key, CHANGE_DELAY).unwrap();
+        
+        let deposit = &read_vault(&ctx.vault).deposits[0];
+        assert_eq!(deposit.locked_destination, Some(new_destination.key));
+        assert_eq!(deposit.pending_destination, None);
+        ctx.withdraw(&mut new_destination).unwrap();
+    }
+
+    #[test]
+    fn test_proposing_current_destination_cancels_change() {
+        let mut ctx = DestinationContext::new();
+        let new_destination = Pubkey::new_unique();
+        let locked = ctx.locked.key;
+        ctx.change(new_destination, 0).unwrap();
+        
+        ctx.change(locked, 1).unwrap();
+        // Proposing again restarts the timelock
+        ctx.change(new_destination, CHANGE_DELAY).unwrap();
+        
+        let deposit = &read_vault(&ctx.vault).deposits[0];
+        assert_eq!(deposit.locked_destination, Some(locked));
+        assert_eq!(deposit.pending_destination, Some((new_destination, CHANGE_DELAY)));
+    }
+}
@@ -0,0 +1,105 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::pubkey::Pubkey;
+    use time_locked_vault::{process_instruction, DepositStatus, Vault, VaultInstruction};
+
+    const NOW: i64 = 1_700_000_000;
+    const UNLOCK_TIME: i64 = NOW + 100;
+
+    // Deposit 100 tokens and withdraw them once unlocked, returning the logs
+    // and events of both instructions
+    fn deposit_and_withdraw() -> (Vec<String>, Vec<Vec<Vec<u8>>>) {
+        let program_id = Pubkey::new_unique();
+        let depositor_key = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault::default(), 2000);
+        let mut depositor = TestAccount::wallet(depositor_key).signer();
+        let mut source = token_account(&mint, &depositor_key, 100);
+        let mut vault_token = token_account(&mint, &vault.key, 100);
+        let mut token_program = token_program();
+        let mut system_program = system_program_account();
+        let mut clock = clock_account(0, NOW);
+        take_logs();
+        take_events();
+        
+        let accounts = vec![
+            depositor.info(),
+            vault.info(),
+            source.info(),
+            vault_token.info(),
+            token_program.info(),
+            system_program.info(),
+            clock.info(),
+        ];
+        let data = VaultInstruction::Deposit {
+            amount: 100,
+            unlock_time: UNLOCK_TIME,
+            tag: [0; 32],
+            locked_destination: None,
+            no_crank: false,
+            emergency_requires_depositor: false,
+            owner_can_release_early: false,
+            idempotency_key: None,
+        }
+        .pack();
+        process_instruction(&program_id, &accounts, &data).unwrap();
+        drop(accounts);
+        
+        let mut destination = token_account(&mint, &depositor_key, 0);
+        let mut clock = clock_account(1, UNLOCK_TIME);
+        let accounts = vec![
+            depositor.info(),
+            vault.info(),
+            destination.info(),
+            vault_token.info(),
+            token_program.info(),
+            clock.info(),
+        ];
+        let data = VaultInstruction::Withdraw { deposit_id: 0 }.pack();
+        process_instruction(&program_id, &accounts, &data).unwrap();
+        
+        (take_logs(), take_events())
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw_emit_events() {
+        let (_, events) = deposit_and_withdraw();
+        
+        let deposit = vec![
+            b"deposit".to_vec(),
+            0u64.to_le_bytes().to_vec(),
+            100u64.to_le_bytes().to_vec(),
+            UNLOCK_TIME.to_le_bytes().to_vec(),
+        ];
+        let withdraw = vec![
+            b"withdraw".to_vec(),
+            0u64.to_le_bytes().to_vec(),
+            100u64.to_le_bytes().to_vec(),
+            UNLOCK_TIME.to_le_bytes().to_vec(),
+            vec![DepositStatus::Withdrawn as u8],
+        ];
+        assert_eq!(events, vec![deposit, withdraw]);
+    }
+
+    #[cfg(not(feature = "verbose-logs"))]
+    #[test]
+    fn test_compact_logs_skip_formatting() {
+        let (logs, _) = deposit_and_withdraw();
+        
+        assert!(logs.iter().all(|log| !log.contains("successful")), "{:?}", logs);
+        println!("log bytes without verbose-logs: {}", logs.iter().map(String::len).sum::<usize>());
+    }
+
+    #[cfg(feature = "verbose-logs")]
+    #[test]
+    fn test_verbose_logs_keep_messages() {
+        let (logs, _) = deposit_and_withdraw();
+        
+        assert!(logs.contains(&format!("Deposit successful: 100 tokens locked until timestamp {}", UNLOCK_TIME)));
+        assert!(logs.contains(&"Withdrawal successful: 100 tokens from deposit 0 (0 protocol fee)".to_string()));
+        println!("log bytes with verbose-logs: {}", logs.iter().map(String::len).sum::<usize>());
+    }
+}
@@ -0,0 +1,169 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{process_instruction, Vault, VaultError, VaultInstruction};
+
+    const UNLOCK_TIME: i64 = 10_000;
+    const LIMIT: u64 = 1_000;
+
+    struct LimitContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl LimitContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let owner = Pubkey::new_unique();
+            let vault = Vault {
+                owner,
+                ..Vault::default()
+            };
+            let mut ctx = Self {
+                program_id,
+                owner,
+                depositor: Pubkey::new_unique(),
+                token_mint: Pubkey::new_unique(),
+                vault: vault_account(&program_id, &vault, 1000),
+            };
+            ctx.set_limit(Some(LIMIT)).unwrap();
+            ctx
+        }
+        
+        fn set_limit(&mut self, limit: Option<u64>) -> ProgramResult {
+            let mut owner = TestAccount::wallet(self.owner).signer();
+            let accounts = vec![owner.info(), self.vault.info()];
+            let data = VaultInstruction::SetMintLimit { mint: self.token_mint, limit }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn set_tvl_cap(&mut self, cap: Option<u64>) -> ProgramResult {
+            let mut owner = TestAccount::wallet(self.owner).signer();
+            let accounts = vec![owner.info(), self.vault.info()];
+            let data = VaultInstruction::SetTvlCap { cap }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn deposit(&mut self, amount: u64) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut source = token_account(&self.token_mint, &self.depositor, amount);
+            let mut vault_tokens = token_account(&self.token_mint, &self.vault.key, 0);
+            let mut token_program = token_program();
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(0, 0);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                source.info(),
+                vault_tokens.info(),
+                token_program.info(),
+                system_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Deposit {
+                amount,
+                unlock_time: UNLOCK_TIME,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .try_to_vec()
+            .unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn withdraw(&mut self, deposit_id: u64) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, LIMIT);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, UNLOCK_TIME);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Withdraw { deposit_id }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn outstanding(&self) -> u64 {
+            read_vault(&self.vault).mints.iter()
+                .find(|entry| entry.mint == self.token_mint)
+                .map_or(0, |entry| entry.outstanding)
+        }
+    }
+
+    #[test]
+    fn test_deposit_up_to_limit() {
+        let mut ctx = LimitContext::new();
+        ctx.deposit(400).unwrap();
+        
+        ctx.deposit(600).unwrap();
+        
+        assert_eq!(ctx.outstanding(), LIMIT);
+    }
+
+    #[test]
+    fn test_exceeding_limit_by_one() {
+        let mut ctx = LimitContext::new();
+        ctx.deposit(400).unwrap();
+        
+        assert_eq!(ctx.deposit(601), Err(VaultError::MintLimitExceeded.into()));
+        assert_eq!(ctx.outstanding(), 400);
+    }
+
+    #[test]
+    fn test_withdrawal_restores_headroom() {
+        let mut ctx = LimitContext::new();
+        ctx.deposit(LIMIT).unwrap();
+        assert_eq!(ctx.deposit(1), Err(VaultError::MintLimitExceeded.into()));
+        
+        ctx.withdraw(0).unwrap();
+        
+        assert_eq!(ctx.outstanding(), 0);
+        ctx.deposit(LIMIT).unwrap();
+    }
+
+    #[test]
+    fn test_limit_below_outstanding_only_blocks_new_deposits() {
+        let mut ctx = LimitContext::new();
+        ctx.deposit(800).unwrap();
+        
+        ctx.set_limit(Some(500)).unwrap();
+        
+        assert_eq!(ctx.deposit(1), Err(VaultError::MintLimitExceeded.into()));
+        ctx.withdraw(0).unwrap();
+        ctx.deposit(500).unwrap();
+    }
+
+    #[test]
+    fn test_tvl_cap_blocks_until_withdrawal() {
+        let mut ctx = LimitContext::new();
+        ctx.set_limit(None).unwrap();
+        ctx.set_tvl_cap(Some(LIMIT)).unwrap();
+        ctx.deposit(LIMIT).unwrap();
+        take_invocations();
+        
+        assert_eq!(ctx.deposit(1), Err(VaultError::VaultCapReached.into()));
+        // Rejected before the transfer CPI
+        assert!(take_invocations().is_empty());
+        
+        ctx.withdraw(0).unwrap();
+        assert_eq!(read_vault(&ctx.vault).total_locked, 0);
+        ctx.deposit(LIMIT).unwrap();
+    }
+}
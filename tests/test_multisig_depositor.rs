@@ -0,0 +1,200 @@
+mod common;
+
+use common::bank::{send, signing_vault, token_account, token_balance, vault_account, vault_program_test, vault_state};
+use solana_program::{
+    clock::Clock,
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use spl_token::state::Multisig;
+use time_locked_vault::{builder, Vault};
+
+struct Harness {
+    context: ProgramTestContext,
+    vault: Keypair,
+    multisig: Keypair,
+    members: Vec<Keypair>,
+    source: Pubkey,
+    vault_tokens: Pubkey,
+    unlock_time: i64,
+}
+
+impl Harness {
+    // An empty vault and a 2-of-3 SPL token multisig holding 100 tokens
+    async fn new() -> Self {
+        let vault = signing_vault();
+        let (source, vault_tokens, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        
+        let mut program_test = vault_program_test();
+        let state = Vault { is_initialized: true, owner: Pubkey::new_unique(), ..Vault::default() };
+        program_test.add_account(vault.pubkey(), vault_account(&state));
+        let context = program_test.start_with_context().await;
+        let mut harness = Self {
+            context,
+            vault,
+            multisig: Keypair::new(),
+            members: (0..3).map(|_| Keypair::new()).collect(),
+            source,
+            vault_tokens,
+            unlock_time: 0,
+        };
+        
+        // Initialize the multisig through the token program
+        let payer = harness.context.payer.pubkey();
+        let multisig = harness.multisig.pubkey();
+        let members: Vec<Pubkey> = harness.members.iter().map(|member| member.pubkey()).collect();
+        let create = system_instruction::create_account(
+            &payer,
+            &multisig,
+            Rent::default().minimum_balance(Multisig::LEN),
+            Multisig::LEN as u64,
+            &spl_token::id(),
+        );
+        let member_keys: Vec<&Pubkey> = members.iter().collect();
+        let initialize =
+            spl_token::instruction::initialize_multisig(&spl_token::id(), &multisig, &member_keys, 2).unwrap();
+        let multisig_keypair = harness.multisig.insecure_clone();
+        harness.send(&[create, initialize], &[&multisig_keypair]).await.unwrap();
+        
+        harness.context.set_account(&source, &token_account(&mint, &multisig, 100).into());
+        harness.context.set_account(&vault_tokens, &token_account(&mint, &harness.vault.pubkey(), 0).into());
+        let clock: Clock = harness.context.banks_client.get_sysvar().await.unwrap();
+        harness.unlock_time = clock.unix_timestamp + 3_600;
+        harness
+    }
+
+    async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), TransactionError> {
+        send(&mut self.context, instructions, signers).await
+    }
+
+    // Deposit 60 of the multisig's tokens, signed by the members at `signers`
+    async fn deposit(&mut self, signers: &[usize]) -> Result<(), TransactionError> {
+        let instruction = builder::deposit(
+            &time_locked_vault::id(),
+            &self.multisig.pubkey(),
+            &self.vault.pubkey(),
+            &self.source,
+            &self.vault_tokens,
+            60,
+            self.unlock_time,
+            [0; 32],
+        );
+        let members: Vec<Keypair> = signers.iter().map(|&index| self.members[index].insecure_clone()).collect();
+        let keys: Vec<Pubkey> = members.iter().map(|member| member.pubkey()).collect();
+        let instruction = builder::with_multisig_signers(instruction, &keys);
+        self.send(&[instruction], &members.iter().collect::<Vec<_>>()).await
+    }
+
+    // Withdraw deposit 0 back to the multisig's token account once it has
+    // unlocked, signed by `members`
+    async fn withdraw(&mut self, members: &[Keypair]) -> Result<(), TransactionError> {
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        if clock.unix_timestamp < self.unlock_time {
+            clock.slot += 10;
+            clock.unix_timestamp = self.unlock_time;
+            self.context.set_sysvar(&clock);
+        }
+        let mut instruction = builder::withdraw(
+            &time_locked_vault::id(),
+            &self.multisig.pubkey(),
+            &self.vault.pubkey(),
+            &self.source,
+            &self.vault_tokens,
+            0,
+        );
+        // The vault signs the payout from its token account
+        instruction.accounts[1].is_signer = true;
+        let keys: Vec<Pubkey> = members.iter().map(|member| member.pubkey()).collect();
+        let instruction = builder::with_multisig_signers(instruction, &keys);
+        let mut signers = vec![self.vault.insecure_clone()];
+        signers.extend(members.iter().map(|member| member.insecure_clone()));
+        self.send(&[instruction], &signers.iter().collect::<Vec<_>>()).await
+    }
+
+    async fn vault_state(&mut self) -> Vault {
+        vault_state(&mut self.context, self.vault.pubkey()).await
+    }
+
+    async fn token_balance(&mut self, key: Pubkey) -> u64 {
+        token_balance(&mut self.context, key).await
+    }
+
+    fn members(&self, indexes: &[usize]) -> Vec<Keypair> {
+        indexes.iter().map(|&index| self.members[index].insecure_clone()).collect()
+    }
+}
+
+fn missing_signature() -> Result<(), TransactionError> {
+    Err(TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature))
+}
+
+#[tokio::test]
+async fn test_multisig_deposits_and_withdraws() {
+    let mut harness = Harness::new().await;
+    
+    harness.deposit(&[0, 1]).await.unwrap();
+    
+    let vault = harness.vault_state().await;
+    assert_eq!(vault.deposits[0].depositor, harness.multisig.pubkey());
+    assert_eq!(harness.token_balance(harness.source).await, 40);
+    assert_eq!(harness.token_balance(harness.vault_tokens).await, 60);
+    
+    // Any two members may withdraw
+    let members = harness.members(&[1, 2]);
+    harness.withdraw(&members).await.unwrap();
+    
+    assert!(harness.vault_state().await.deposits[0].is_withdrawn());
+    assert_eq!(harness.token_balance(harness.source).await, 100);
+}
+
+#[tokio::test]
+async fn test_multisig_threshold_not_met() {
+    let mut harness = Harness::new().await;
+    
+    assert_eq!(harness.deposit(&[0]).await, missing_signature());
+    assert!(harness.vault_state().await.deposits.is_empty());
+    
+    harness.deposit(&[0, 2]).await.unwrap();
+    let members = harness.members(&[2]);
+    assert_eq!(harness.withdraw(&members).await, missing_signature());
+    
+    // Signers outside the multisig don't count
+    let outsiders = vec![harness.members[0].insecure_clone(), Keypair::new()];
+    assert_eq!(harness.withdraw(&outsiders).await, missing_signature());
+    assert!(!harness.vault_state().await.deposits[0].is_withdrawn());
+    assert_eq!(harness.token_balance(harness.vault_tokens).await, 60);
+}
+
+#[tokio::test]
+async fn test_rotated_multisig_members_withdraw() {
+    let mut harness = Harness::new().await;
+    harness.deposit(&[0, 1]).await.unwrap();
+    
+    // Rotate the members in place: the multisig's address, not its members,
+    // is the depositor
+    let multisig = harness.multisig.pubkey();
+    let mut account = harness.context.banks_client.get_account(multisig).await.unwrap().unwrap();
+    let mut state = Multisig::unpack(&account.data).unwrap();
+    let rotated: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+    for (slot, member) in state.signers.iter_mut().zip(&rotated) {
+        *slot = member.pubkey();
+    }
+    Multisig::pack(state, &mut account.data).unwrap();
+    harness.context.set_account(&multisig, &account.into());
+    
+    let retired = harness.members(&[0, 1]);
+    assert_eq!(harness.withdraw(&retired).await, missing_signature());
+    
+    harness.withdraw(&rotated[1..]).await.unwrap();
+    
+    assert!(harness.vault_state().await.deposits[0].is_withdrawn());
+    assert_eq!(harness.token_balance(harness.source).await, 100);
+}
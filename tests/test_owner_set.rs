@@ -0,0 +1,128 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshDeserialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        process_instruction, OwnerSet, Vault, VaultConfig, VaultError, VaultInstruction, MAX_OWNER_SET_MEMBERS,
+    };
+
+    struct OwnerSetContext {
+        program_id: Pubkey,
+        members: Vec<Pubkey>,
+        vault: TestAccount,
+    }
+
+    impl OwnerSetContext {
+        // A vault owned 2-of-3 by `members`
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let members = vec![Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+            let vault = Vault {
+                owner: members[0],
+                owner_set: Some(OwnerSet { members: members.clone(), threshold: 2 }),
+                ..Vault::default()
+            };
+            Self { program_id, members, vault: vault_account(&program_id, &vault, 1000) }
+        }
+        
+        // Send `instruction` with `owner` in the owner position and `cosigners`
+        // appended after the vault, each `(key, signed)`
+        fn send(
+            &mut self,
+            instruction: VaultInstruction,
+            owner: Pubkey,
+            cosigners: &[(Pubkey, bool)],
+        ) -> ProgramResult {
+            let mut owner = TestAccount::wallet(owner).signer();
+            let mut cosigners: Vec<TestAccount> = cosigners
+                .iter()
+                .map(|(key, signed)| {
+                    let account = TestAccount::wallet(*key);
+                    if *signed { account.signer() } else { account }
+                })
+                .collect();
+            let mut accounts = vec![owner.info(), self.vault.info()];
+            accounts.extend(cosigners.iter_mut().map(|cosigner| cosigner.info()));
+            process_instruction(&self.program_id, &accounts, &instruction.pack())
+        }
+    }
+
+    fn create(owner_set: OwnerSet) -> (ProgramResult, Vault) {
+        let program_id = Pubkey::new_unique();
+        let mut owner = TestAccount::wallet(Pubkey::new_unique()).signer();
+        let mut vault = TestAccount::new(Pubkey::new_unique(), program_id, vec![0; 1000]).writable();
+        let mut system_program = system_program_account();
+        let accounts = vec![owner.info(), vault.info(), system_program.info()];
+        let data = VaultInstruction::CreateVault {
+            skip_registry: true,
+            arbitration_destination: None,
+            emergency_destination: None,
+            owner_set: Some(owner_set),
+            config: VaultConfig::default(),
+        }
+        .pack();
+        let result = process_instruction(&program_id, &accounts, &data);
+        let state = Vault::deserialize(&mut accounts[1].data.borrow().as_ref()).unwrap_or_default();
+        (result, state)
+    }
+
+    #[test]
+    fn test_create_validates_the_owner_set() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let invalid = Err(VaultError::InvalidOwnerSet.into());
+        
+        assert_eq!(create(OwnerSet { members: vec![a, b], threshold: 0 }).0, invalid);
+        assert_eq!(create(OwnerSet { members: vec![a, b], threshold: 3 }).0, invalid);
+        assert_eq!(create(OwnerSet { members: vec![a, b, a], threshold: 2 }).0, invalid);
+        assert_eq!(create(OwnerSet { members: vec![], threshold: 0 }).0, invalid);
+        let too_many: Vec<Pubkey> = (0..=MAX_OWNER_SET_MEMBERS).map(|_| Pubkey::new_unique()).collect();
+        assert_eq!(create(OwnerSet { members: too_many, threshold: 1 }).0, invalid);
+        
+        let owner_set = OwnerSet { members: vec![a, b], threshold: 2 };
+        let (result, vault) = create(owner_set.clone());
+        result.unwrap();
+        assert_eq!(vault.owner_set, Some(owner_set));
+    }
+
+    #[test]
+    fn test_owner_updates_need_the_threshold() {
+        let mut ctx = OwnerSetContext::new();
+        let (m0, m1, m2) = (ctx.members[0], ctx.members[1], ctx.members[2]);
+        let set_cap = VaultInstruction::SetTvlCap { cap: Some(500) };
+        let not_met = Err(VaultError::OwnerThresholdNotMet.into());
+        
+        // One member alone, or with a second member that did not sign or is repeated
+        assert_eq!(ctx.send(set_cap.clone(), m1, &[]), not_met);
+        assert_eq!(ctx.send(set_cap.clone(), m1, &[(m2, false)]), not_met);
+        assert_eq!(ctx.send(set_cap.clone(), m1, &[(m1, true)]), not_met);
+        // A non-member cannot take the owner position or count towards the threshold
+        assert_eq!(
+            ctx.send(set_cap.clone(), Pubkey::new_unique(), &[(m1, true), (m2, true)]),
+            Err(VaultError::NotVaultOwner.into()),
+        );
+        assert_eq!(ctx.send(set_cap.clone(), m1, &[(Pubkey::new_unique(), true)]), not_met);
+        assert_eq!(read_vault(&ctx.vault).tvl_cap, None);
+        
+        // Any two members will do, whichever is in the owner position
+        ctx.send(set_cap, m2, &[(m0, true)]).unwrap();
+        assert_eq!(read_vault(&ctx.vault).tvl_cap, Some(500));
+    }
+
+    #[test]
+    fn test_closing_deposits_needs_the_threshold() {
+        let mut ctx = OwnerSetContext::new();
+        let (m0, m1, m2) = (ctx.members[0], ctx.members[1], ctx.members[2]);
+        
+        assert_eq!(
+            ctx.send(VaultInstruction::CloseToNewDeposits, m0, &[]),
+            Err(VaultError::OwnerThresholdNotMet.into()),
+        );
+        assert!(read_vault(&ctx.vault).accepting_deposits());
+        
+        ctx.send(VaultInstruction::CloseToNewDeposits, m0, &[(m1, true), (m2, true)]).unwrap();
+        assert!(!read_vault(&ctx.vault).accepting_deposits());
+    }
+}
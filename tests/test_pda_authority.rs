@@ -0,0 +1,144 @@
+mod common;
+
+use common::bank::{send, vault_account, vault_program_test, vault_state};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program::invoke_signed,
+    pubkey::Pubkey,
+};
+use solana_program_test::{processor, ProgramTestContext};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use time_locked_vault::{Deposit, Vault, VaultInstruction};
+
+const AUTHORITY_SEED: &[u8] = b"emergency";
+
+// A stand-in governance program: forwards the rest of its instruction data to
+// the vault program with its PDA as the authority account, signing for the PDA
+// when the first byte is 1
+fn governance_process<'a>(program_id: &Pubkey, accounts: &[AccountInfo<'a>], data: &[u8]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_program_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let vault_account_info = next_account_info(account_info_iter)?;
+    
+    let (_, bump) = Pubkey::find_program_address(&[AUTHORITY_SEED], program_id);
+    let instruction = Instruction {
+        program_id: *vault_program_info.key,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority_info.key, data[0] == 1),
+            AccountMeta::new(*vault_account_info.key, false),
+        ],
+        data: data[1..].to_vec(),
+    };
+    invoke_signed(
+        &instruction,
+        &[authority_info.clone(), vault_account_info.clone(), vault_program_info.clone()],
+        &[&[AUTHORITY_SEED, &[bump]]],
+    )
+}
+
+struct Harness {
+    context: ProgramTestContext,
+    vault_program: Pubkey,
+    governance: Pubkey,
+    authority: Pubkey,
+    owner: Keypair,
+    vault: Pubkey,
+}
+
+impl Harness {
+    // A vault holding one deposit, with the governance PDA not yet its
+    // emergency authority
+    async fn new() -> Self {
+        let vault_program = time_locked_vault::id();
+        let governance = Pubkey::new_unique();
+        let (authority, _) = Pubkey::find_program_address(&[AUTHORITY_SEED], &governance);
+        let owner = Keypair::new();
+        let vault = Pubkey::new_unique();
+    
+        let mut program_test = vault_program_test();
+        program_test.add_program("governance", governance, processor!(governance_process));
+    
+        let state = Vault {
+            is_initialized: true,
+            owner: owner.pubkey(),
+            deposit_count: 1,
+            deposits: vec![Deposit {
+                depositor: Pubkey::new_unique(),
+                token_mint: Pubkey::new_unique(),
+                amount: 100,
+                unlock_time: i64::MAX,
+                ..Deposit::default()
+            }],
+            ..Vault::default()
+        };
+        program_test.add_account(vault, vault_account(&state));
+    
+        Self {
+            context: program_test.start_with_context().await,
+            vault_program,
+            governance,
+            authority,
+            owner,
+            vault,
+        }
+    }
+    
+    async fn set_emergency_authority(&mut self) {
+        let instruction = Instruction {
+            program_id: self.vault_program,
+            accounts: vec![AccountMeta::new_readonly(self.owner.pubkey(), true), AccountMeta::new(self.vault, false)],
+            data: VaultInstruction::SetEmergencyAuthority { authority: Some(self.authority) }.pack(),
+        };
+        send(&mut self.context, &[instruction], &[&self.owner]).await.unwrap();
+    }
+    
+    // Have the governance program call the vault as the emergency authority
+    async fn governance_call(&mut self, instruction: VaultInstruction, sign: bool) -> Result<(), TransactionError> {
+        let mut data = vec![sign as u8];
+        data.extend(instruction.pack());
+        let instruction = Instruction {
+            program_id: self.governance,
+            accounts: vec![
+                AccountMeta::new_readonly(self.vault_program, false),
+                AccountMeta::new_readonly(self.authority, false),
+                AccountMeta::new(self.vault, false),
+            ],
+            data,
+        };
+        send(&mut self.context, &[instruction], &[]).await
+    }
+    
+    async fn vault_state(&mut self) -> Vault {
+        vault_state(&mut self.context, self.vault).await
+    }
+}
+
+#[tokio::test]
+async fn test_governance_pda_acts_through_cpi() {
+    let mut harness = Harness::new().await;
+    assert!(!harness.authority.is_on_curve());
+    
+    harness.set_emergency_authority().await;
+    assert_eq!(harness.vault_state().await.emergency_authority, Some(harness.authority));
+    
+    // Freezing falls back to the emergency authority
+    harness.governance_call(VaultInstruction::FreezeDeposit { deposit_id: 0 }, true).await.unwrap();
+    assert!(harness.vault_state().await.deposits[0].frozen);
+}
+
+#[tokio::test]
+async fn test_unsigned_pda_rejected() {
+    let mut harness = Harness::new().await;
+    harness.set_emergency_authority().await;
+    
+    let result = harness.governance_call(VaultInstruction::FreezeDeposit { deposit_id: 0 }, false).await;
+    
+    assert_eq!(result, Err(TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)));
+    assert!(!harness.vault_state().await.deposits[0].frozen);
+}
@@ -0,0 +1,203 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use ed25519_dalek::Keypair;
+    use solana_program::{
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_sdk::ed25519_instruction::new_ed25519_instruction;
+    use time_locked_vault::{
+        find_config_address, permit_message, process_instruction, Deposit, ProtocolConfig, Vault, VaultError,
+        VaultInstruction,
+    };
+
+    const UNLOCK_TIME: i64 = 1_000;
+    const EXPIRY: i64 = 2_000;
+
+    fn keypair() -> Keypair {
+        Keypair::generate(&mut rand::thread_rng())
+    }
+
+    struct PermitContext {
+        program_id: Pubkey,
+        depositor: Keypair,
+        token_mint: Pubkey,
+        vault: TestAccount,
+        destination: TestAccount,
+        protocol_fee: Option<(TestAccount, TestAccount)>,
+    }
+
+    impl PermitContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let depositor = keypair();
+            let depositor_key = Pubkey::new_from_array(depositor.public.to_bytes());
+            let token_mint = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor: depositor_key,
+                    token_mint,
+                    amount: 1_000,
+                    unlock_time: UNLOCK_TIME,
+                    ..Deposit::default()
+                }],
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                depositor,
+                token_mint,
+                vault: vault_account(&program_id, &vault, 1000),
+                destination: token_account(&token_mint, &depositor_key, 0),
+                protocol_fee: None,
+            }
+        }
+        
+        // Pass the protocol config, charging `protocol_fee_bps`, and its fee
+        // destination to every withdrawal
+        fn with_protocol_fee(protocol_fee_bps: u16) -> Self {
+            let mut ctx = Self::new();
+            let fee_destination = TestAccount::new(Pubkey::new_unique(), spl_token::id(), vec![]).writable();
+            let config = ProtocolConfig {
+                admin: Pubkey::new_unique(),
+                protocol_fee_bps,
+                fee_destination: fee_destination.key,
+                pending_admin: None,
+            };
+            let mut data = vec![0; ProtocolConfig::LEN];
+            config.serialize(&mut data.as_mut_slice()).unwrap();
+            let config = TestAccount::new(find_config_address(&ctx.program_id).0, ctx.program_id, data);
+            ctx.protocol_fee = Some((config, fee_destination));
+            ctx
+        }
+        
+        fn depositor_key(&self) -> Pubkey {
+            Pubkey::new_from_array(self.depositor.public.to_bytes())
+        }
+        
+        fn push_protocol_fee<'a>(
+            protocol_fee: &'a mut Option<(TestAccount, TestAccount)>,
+            accounts: &mut Vec<AccountInfo<'a>>,
+        ) {
+            if let Some((config, fee_destination)) = protocol_fee {
+                accounts.push(config.info());
+                accounts.push(fee_destination.info());
+            }
+        }
+        
+        fn message(&self, nonce: u64) -> Vec<u8> {
+            permit_message(&self.program_id, &self.vault.key, 0, &self.destination.key, EXPIRY, nonce)
+        }
+        
+        fn withdraw(&mut self, signature_ix: &Instruction, now: i64) -> ProgramResult {
+            let permit_ix = Instruction {
+                program_id: self.program_id,
+                accounts: vec![AccountMeta::new(self.vault.key, false)],
+                data: VaultInstruction::WithdrawWithPermit { deposit_id: 0, expiry: EXPIRY }
+                    .try_to_vec()
+                    .unwrap(),
+            };
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, now);
+            let mut instructions = instructions_sysvar(&[signature_ix.clone(), permit_ix.clone()], 1);
+            let mut accounts = vec![
+                self.vault.info(),
+                self.destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+                instructions.info(),
+            ];
+            Self::push_protocol_fee(&mut self.protocol_fee, &mut accounts);
+            process_instruction(&self.program_id, &accounts, &permit_ix.data)
+        }
+        
+        // Withdraw the deposit with the depositor signing the transaction
+        fn withdraw_directly(&mut self, now: i64) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor_key()).signer();
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, now);
+            let mut accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                self.destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            Self::push_protocol_fee(&mut self.protocol_fee, &mut accounts);
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::Withdraw { deposit_id: 0 }.pack())
+        }
+    }
+
+    #[test]
+    fn test_withdraw_with_valid_permit() {
+        let mut ctx = PermitContext::new();
+        let signature_ix = new_ed25519_instruction(&ctx.depositor, &ctx.message(0));
+        
+        ctx.withdraw(&signature_ix, UNLOCK_TIME).unwrap();
+        
+        let vault = read_vault(&ctx.vault);
+        assert!(vault.deposits[0].is_withdrawn());
+        assert_eq!(vault.deposits[0].permit_nonce, 1);
+    }
+
+    #[test]
+    fn test_permit_signed_by_wrong_key_is_rejected() {
+        let mut ctx = PermitContext::new();
+        let signature_ix = new_ed25519_instruction(&keypair(), &ctx.message(0));
+        
+        let result = ctx.withdraw(&signature_ix, UNLOCK_TIME);
+        
+        assert_eq!(result, Err(VaultError::InvalidPermit.into()));
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_permit_with_stale_nonce_is_rejected() {
+        let mut ctx = PermitContext::new();
+        let signature_ix = new_ed25519_instruction(&ctx.depositor, &ctx.message(1));
+        
+        let result = ctx.withdraw(&signature_ix, UNLOCK_TIME);
+        
+        assert_eq!(result, Err(VaultError::InvalidPermit.into()));
+    }
+
+    #[test]
+    fn test_expired_permit_is_rejected() {
+        let mut ctx = PermitContext::new();
+        let signature_ix = new_ed25519_instruction(&ctx.depositor, &ctx.message(0));
+        
+        let result = ctx.withdraw(&signature_ix, EXPIRY + 1);
+        
+        assert_eq!(result, Err(VaultError::PermitExpired.into()));
+    }
+
+    #[test]
+    fn test_permit_withdrawal_charges_the_protocol_fee_of_a_withdraw() {
+        let mut direct = PermitContext::with_protocol_fee(250);
+        take_invocations();
+        direct.withdraw_directly(UNLOCK_TIME).unwrap();
+        let direct_fee = direct.protocol_fee.as_ref().unwrap().1.key;
+        assert_eq!(token_transfers(&take_invocations()), vec![(direct_fee, 25), (direct.destination.key, 975)]);
+        
+        let mut ctx = PermitContext::with_protocol_fee(250);
+        let signature_ix = new_ed25519_instruction(&ctx.depositor, &ctx.message(0));
+        ctx.withdraw(&signature_ix, UNLOCK_TIME).unwrap();
+        
+        let fee_destination = ctx.protocol_fee.as_ref().unwrap().1.key;
+        assert_eq!(token_transfers(&take_invocations()), vec![(fee_destination, 25), (ctx.destination.key, 975)]);
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+}
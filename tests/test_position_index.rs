@@ -0,0 +1,298 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey, system_program};
+    use std::ops::{Deref, DerefMut};
+    use time_locked_vault::{
+        find_config_address, find_position_index_address, verify_position_index, Deposit, DepositStatus,
+        PendingRecovery, PositionIndex, Vault, VaultError, VaultInstruction, RECOVERY_DELAY,
+    };
+
+    const NOW: i64 = 1_700_000_000;
+    const UNLOCK_TIME: i64 = NOW + 100;
+
+    // The shared harness, plus each depositor's index account and the slot
+    // the last instruction ran in
+    struct Indexes {
+        base: Harness,
+        indexes: Vec<(Pubkey, TestAccount)>,
+        slot: u64,
+    }
+
+    impl Deref for Indexes {
+        type Target = Harness;
+        
+        fn deref(&self) -> &Harness {
+            &self.base
+        }
+    }
+
+    impl DerefMut for Indexes {
+        fn deref_mut(&mut self) -> &mut Harness {
+            &mut self.base
+        }
+    }
+
+    impl Indexes {
+        fn new(vault: Vault) -> Self {
+            Self { base: Harness::new(&vault, 100_000), indexes: Vec::new(), slot: 0 }
+        }
+        
+        // An empty vault with position indexes enabled by its owner
+        fn indexed() -> Self {
+            let mut harness = Self::new(Vault { owner: Pubkey::new_unique(), ..Vault::default() });
+            harness.set_position_index(true).unwrap();
+            harness
+        }
+        
+        fn set_position_index(&mut self, enabled: bool) -> ProgramResult {
+            let owner = self.owner;
+            self.call(owner, &VaultInstruction::SetPositionIndex { enabled }.pack(), &mut [])
+        }
+        
+        // The depositor's index account, not yet created until its first deposit
+        fn index(&mut self, depositor: Pubkey) -> &mut TestAccount {
+            if !self.indexes.iter().any(|(key, _)| *key == depositor) {
+                let (key, _) = find_position_index_address(&self.vault.key, &depositor, &self.program_id);
+                let account = TestAccount::new(key, system_program::id(), vec![0; PositionIndex::LEN]).writable();
+                self.indexes.push((depositor, account));
+            }
+            &mut self.indexes.iter_mut().find(|(key, _)| *key == depositor).unwrap().1
+        }
+        
+        fn read_index(&mut self, depositor: Pubkey) -> PositionIndex {
+            PositionIndex::deserialize(&mut self.index(depositor).data.as_slice()).unwrap()
+        }
+        
+        fn deposit(&mut self, depositor: Pubkey) -> ProgramResult {
+            self.slot += 1;
+            let mut index = std::mem::replace(self.index(depositor), TestAccount::wallet(depositor));
+            let data = VaultInstruction::Deposit {
+                amount: 100,
+                unlock_time: UNLOCK_TIME,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .pack();
+            self.base.depositor = depositor;
+            let result = self.base.deposit_with(&data, 100, (self.slot, NOW), &mut [&mut index]);
+            
+            // The runtime assigns the created index to the program
+            if result.is_ok() {
+                index.owner = self.program_id;
+            }
+            *self.index(depositor) = index;
+            result
+        }
+        
+        fn withdraw(&mut self, withdrawer: Pubkey, deposit_id: u64, index_of: Pubkey) -> ProgramResult {
+            self.slot += 1;
+            let mut index = std::mem::replace(self.index(index_of), TestAccount::wallet(index_of));
+            let data = VaultInstruction::Withdraw { deposit_id }.pack();
+            let result = self.base.withdraw_with(withdrawer, &data, 1000, (self.slot, UNLOCK_TIME), &mut [&mut index]);
+            *self.index(index_of) = index;
+            result
+        }
+        
+        fn verify(&mut self, depositor: Pubkey) {
+            let index = self.read_index(depositor);
+            assert!(verify_position_index(&read_vault(&self.vault), &index).is_ok());
+        }
+    }
+
+    // A program-owned index account listing `deposit_ids`
+    fn index_account(program_id: &Pubkey, vault: &Pubkey, depositor: &Pubkey, deposit_ids: Vec<u64>) -> TestAccount {
+        let (key, _) = find_position_index_address(vault, depositor, program_id);
+        let mut data = vec![0; PositionIndex::LEN];
+        PositionIndex { vault: *vault, depositor: *depositor, deposit_ids }
+            .serialize(&mut data.as_mut_slice())
+            .unwrap();
+        TestAccount::new(key, *program_id, data).writable()
+    }
+
+    #[test]
+    fn test_enabling_requires_no_open_deposits() {
+        let vault = Vault {
+            owner: Pubkey::new_unique(),
+            deposit_count: 1,
+            deposits: vec![Deposit { id: 0, amount: 5, ..Deposit::default() }],
+            ..Vault::default()
+        };
+        let mut harness = Indexes::new(vault);
+        
+        assert_eq!(harness.set_position_index(true), Err(VaultError::VaultNotEmpty.into()));
+        assert!(!read_vault(&harness.vault).position_index);
+    }
+
+    #[test]
+    fn test_first_deposit_creates_index() {
+        let mut harness = Indexes::indexed();
+        let alice = Pubkey::new_unique();
+        take_invocations();
+        
+        harness.deposit(alice).unwrap();
+        
+        let create = &take_invocations()[0];
+        assert_eq!(create.program_id, system_program::id());
+        assert_eq!(create.accounts[1].pubkey, harness.index(alice).key);
+        let index = harness.read_index(alice);
+        assert_eq!((index.vault, index.depositor, index.deposit_ids), (harness.vault.key, alice, vec![0]));
+        
+        // Later deposits reuse it
+        harness.deposit(alice).unwrap();
+        assert!(take_invocations().iter().all(|ix| ix.program_id != system_program::id()));
+        assert_eq!(harness.read_index(alice).deposit_ids, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_withdraw_updates_the_depositors_index() {
+        let mut harness = Indexes::indexed();
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        harness.deposit(alice).unwrap();
+        harness.deposit(bob).unwrap();
+        harness.deposit(alice).unwrap();
+        
+        assert_eq!(harness.withdraw(alice, 0, bob), Err(ProgramError::InvalidSeeds));
+        harness.withdraw(alice, 0, alice).unwrap();
+        
+        assert_eq!(harness.read_index(alice).deposit_ids, vec![2]);
+        assert_eq!(harness.read_index(bob).deposit_ids, vec![1]);
+        harness.verify(alice);
+        harness.verify(bob);
+    }
+
+    #[test]
+    fn test_withdraw_by_tag_visits_only_indexed_deposits() {
+        let (alice, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let deposit = |id, depositor| Deposit {
+            id,
+            depositor,
+            token_mint: mint,
+            amount: 10,
+            unlock_time: NOW - 1,
+            ..Deposit::default()
+        };
+        let mut beneficiary_of = deposit(1, Pubkey::new_unique());
+        beneficiary_of.beneficiary = Some(alice);
+        let vault = Vault {
+            position_index: true,
+            deposit_count: 3,
+            deposits: vec![deposit(0, alice), beneficiary_of, deposit(2, alice)],
+            ..Vault::default()
+        };
+        let mut harness = Harness::with_depositor(&vault, 4000, alice, mint);
+        
+        let mut token_program = token_program();
+        let mut clock = clock_account(1, NOW);
+        let mut index = index_account(&harness.program_id, &harness.vault.key, &alice, vec![0, 2]);
+        let mut config = TestAccount::new(find_config_address(&harness.program_id).0, system_program::id(), vec![]);
+        let mut source = token_account(&mint, &harness.vault.key, 1000);
+        let mut destination = token_account(&mint, &alice, 0);
+        let mut accounts =
+            [&mut token_program, &mut clock, &mut index, &mut config, &mut source, &mut destination];
+        let data = VaultInstruction::WithdrawByTag { tag: [0; 32] }.pack();
+        take_invocations();
+        harness.call(alice, &data, &mut accounts).unwrap();
+        
+        assert_eq!(token_transfers(&take_invocations()).iter().map(|(_, amount)| *amount).sum::<u64>(), 20);
+        let vault = harness.state();
+        assert_eq!(vault.deposits.iter().map(|d| d.is_withdrawn()).collect::<Vec<_>>(), vec![true, false, true]);
+        let index = PositionIndex::deserialize(&mut index.data.as_slice()).unwrap();
+        assert!(index.deposit_ids.is_empty());
+    }
+
+    #[test]
+    fn test_recovery_moves_deposit_between_indexes() {
+        let (alice, heir) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let vault = Vault {
+            position_index: true,
+            deposit_count: 2,
+            deposits: vec![
+                Deposit { id: 0, depositor: alice, amount: 10, ..Deposit::default() },
+                Deposit {
+                    id: 1,
+                    depositor: alice,
+                    amount: 10,
+                    pending_recovery: Some(PendingRecovery {
+                        new_depositor: heir,
+                        approvals: vec![Pubkey::new_unique()],
+                        approved_at: Some(NOW - RECOVERY_DELAY),
+                    }),
+                    ..Deposit::default()
+                },
+            ],
+            ..Vault::default()
+        };
+        let mut harness = Harness::new(&vault, 4000);
+        let (program_id, vault_key) = (harness.program_id, harness.vault.key);
+        
+        let mut clock = clock_account(1, NOW);
+        let mut old_index = index_account(&program_id, &vault_key, &alice, vec![0, 1]);
+        let (new_key, _) = find_position_index_address(&vault_key, &heir, &program_id);
+        let mut new_index = TestAccount::new(new_key, system_program::id(), vec![0; PositionIndex::LEN]).writable();
+        let mut system_program = system_program_account();
+        let mut accounts = [&mut clock, &mut old_index, &mut new_index, &mut system_program];
+        let data = VaultInstruction::ExecuteRecovery { deposit_id: 1 }.pack();
+        harness.call(Pubkey::new_unique(), &data, &mut accounts).unwrap();
+        
+        let vault = harness.state();
+        let old_index = PositionIndex::deserialize(&mut old_index.data.as_slice()).unwrap();
+        let new_index = PositionIndex::deserialize(&mut new_index.data.as_slice()).unwrap();
+        assert_eq!((old_index.deposit_ids.clone(), new_index.deposit_ids.clone()), (vec![0], vec![1]));
+        assert!(verify_position_index(&vault, &old_index).is_ok());
+        assert!(verify_position_index(&vault, &new_index).is_ok());
+    }
+
+    #[test]
+    fn test_stale_index_detected() {
+        let alice = Pubkey::new_unique();
+        let vault = Vault {
+            deposits: vec![
+                Deposit { id: 0, depositor: alice, ..Deposit::default() },
+                Deposit { id: 1, depositor: alice, status: DepositStatus::Withdrawn as u8, ..Deposit::default() },
+            ],
+            ..Vault::default()
+        };
+        let index = |deposit_ids| PositionIndex { vault: Pubkey::new_unique(), depositor: alice, deposit_ids };
+        
+        assert!(verify_position_index(&vault, &index(vec![0])).is_ok());
+        let mismatch = |result: Result<(), VaultError>| matches!(result, Err(VaultError::PositionIndexMismatch));
+        assert!(mismatch(verify_position_index(&vault, &index(vec![0, 1]))));
+        assert!(mismatch(verify_position_index(&vault, &index(vec![]))));
+    }
+
+    #[test]
+    fn test_random_operations_keep_indexes_consistent() {
+        for seed in 0..8 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut harness = Indexes::indexed();
+            let depositors: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        
+            for _ in 0..40 {
+                let deposits = read_vault(&harness.vault).deposits;
+                let open: Vec<Deposit> = deposits.into_iter().filter(|d| !d.is_withdrawn()).collect();
+                if open.is_empty() || rng.gen_bool(0.6) {
+                    let depositor = depositors[rng.gen_range(0, depositors.len())];
+                    harness.deposit(depositor).unwrap();
+                } else {
+                    let deposit = &open[rng.gen_range(0, open.len())];
+                    harness.withdraw(deposit.depositor, deposit.id, deposit.depositor).unwrap();
+                }
+                let program_id = harness.program_id;
+                for depositor in &depositors {
+                    if harness.index(*depositor).owner == program_id {
+                        harness.verify(*depositor);
+                    }
+                }
+            }
+        }
+    }
+}
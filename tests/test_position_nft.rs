@@ -0,0 +1,139 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        entrypoint::ProgramResult,
+        program_option::COption,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_token::state::Mint;
+    use time_locked_vault::{
+        position_nft_name, process_instruction, Deposit, Vault, VaultError, VaultInstruction,
+        POSITION_BURN_FALLBACK_DELAY,
+    };
+
+    const UNLOCK_TIME: i64 = 1_000;
+
+    struct PositionContext {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        position_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl PositionContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let position_mint = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor,
+                    token_mint,
+                    amount: 1_000,
+                    unlock_time: UNLOCK_TIME,
+                    position_mint: Some(position_mint),
+                    ..Deposit::default()
+                }],
+                position_nfts: true,
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                depositor,
+                token_mint,
+                position_mint,
+                vault: vault_account(&program_id, &vault, 1000),
+            }
+        }
+        
+        fn mint_account(&self, supply: u64) -> TestAccount {
+            let mut data = vec![0; Mint::LEN];
+            Mint {
+                mint_authority: COption::None,
+                supply,
+                decimals: 0,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            }
+            .pack_into_slice(&mut data);
+            TestAccount::new(self.position_mint, spl_token::id(), data).writable()
+        }
+        
+        fn withdraw(&mut self, signer: &Pubkey, holding: &mut TestAccount, supply: u64, now: i64) -> ProgramResult {
+            let mut signer = TestAccount::wallet(*signer).signer();
+            let mut destination = token_account(&self.token_mint, &signer.key, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, now);
+            let mut mint = self.mint_account(supply);
+            let accounts = vec![
+                signer.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+                holding.info(),
+                mint.info(),
+            ];
+            let data = VaultInstruction::Withdraw { deposit_id: 0 }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_position_nft_name() {
+        // 2026-01-01T00:00:00Z
+        assert_eq!(position_nft_name(1_000, 1_767_225_600), "Locked 1,000 until 2026-01-01");
+        assert_eq!(position_nft_name(5, 0), "Locked 5 until 1970-01-01");
+        // Long names are cut to the Metaplex limit
+        assert_eq!(position_nft_name(u64::MAX, 1_767_225_600).len(), 32);
+    }
+
+    #[test]
+    fn test_new_holder_withdraws_after_transfer() {
+        let mut ctx = PositionContext::new();
+        let new_holder = Pubkey::new_unique();
+        let mut holding = token_account(&ctx.position_mint, &new_holder, 1);
+        
+        ctx.withdraw(&new_holder, &mut holding, 1, UNLOCK_TIME).unwrap();
+        
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_original_depositor_without_nft_is_rejected() {
+        let mut ctx = PositionContext::new();
+        let depositor = ctx.depositor;
+        let new_holder = Pubkey::new_unique();
+        let mut holding = token_account(&ctx.position_mint, &new_holder, 1);
+        
+        let result = ctx.withdraw(&depositor, &mut holding, 1, UNLOCK_TIME);
+        
+        assert_eq!(result, Err(VaultError::UnauthorizedWithdrawal.into()));
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_burned_nft_falls_back_to_depositor_after_delay() {
+        let mut ctx = PositionContext::new();
+        let depositor = ctx.depositor;
+        let mut holding = token_account(&ctx.position_mint, &depositor, 0);
+        
+        let early = ctx.withdraw(&depositor, &mut holding, 0, UNLOCK_TIME + POSITION_BURN_FALLBACK_DELAY - 1);
+        assert_eq!(early, Err(VaultError::UnlockTimeNotReached.into()));
+        
+        ctx.withdraw(&depositor, &mut holding, 0, UNLOCK_TIME + POSITION_BURN_FALLBACK_DELAY).unwrap();
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+}
@@ -0,0 +1,122 @@
+#![cfg(feature = "client")]
+
+use borsh::BorshSerialize;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::account::Account;
+use time_locked_vault::{
+    client::{self, PositionScanConfig, PositionSummary},
+    process_instruction, Deposit, DepositStatus, Vault, VaultRegistry,
+};
+
+// A program-owned account holding `data`, padded like a real vault account
+fn program_account(mut data: Vec<u8>) -> Account {
+    data.resize(data.len() + 500, 0);
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: time_locked_vault::id(),
+        ..Account::default()
+    }
+}
+
+fn deposit(id: u64, depositor: Pubkey, amount: u64, status: DepositStatus) -> Deposit {
+    Deposit {
+        id,
+        depositor,
+        token_mint: Pubkey::new_unique(),
+        amount,
+        unlock_time: 1_000 + id as i64,
+        status: status as u8,
+        ..Deposit::default()
+    }
+}
+
+// What `getProgramAccounts` would return for the program, read through the
+// banks client
+async fn program_accounts(banks_client: &mut BanksClient, keys: &[Pubkey]) -> Vec<(Pubkey, Vec<u8>)> {
+    let mut accounts = Vec::new();
+    for key in keys {
+        let account = banks_client.get_account(*key).await.unwrap().unwrap();
+        accounts.push((*key, account.data));
+    }
+    accounts
+}
+
+#[tokio::test]
+async fn test_positions_are_found_across_vaults() {
+    let (alice, bob, carol) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let first = Vault {
+        is_initialized: true,
+        owner: Pubkey::new_unique(),
+        deposit_count: 3,
+        deposits: vec![
+            deposit(0, alice, 100, DepositStatus::Active),
+            deposit(1, bob, 200, DepositStatus::Active),
+            deposit(2, alice, 300, DepositStatus::Withdrawn),
+        ],
+        ..Vault::default()
+    };
+    let second = Vault {
+        is_initialized: true,
+        owner: Pubkey::new_unique(),
+        deposit_count: 2,
+        deposits: vec![
+            deposit(0, carol, 400, DepositStatus::Active),
+            Deposit { beneficiary: Some(alice), ..deposit(1, carol, 500, DepositStatus::Active) },
+        ],
+        ..Vault::default()
+    };
+    // The scan also returns the program's other accounts, such as
+    // registries, which must not trip it up
+    let registry = VaultRegistry { owner: Pubkey::new_from_array([1; 32]), vaults: vec![Pubkey::new_unique()] };
+
+    let (first_key, second_key, registry_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut program_test =
+        ProgramTest::new("time_locked_vault", time_locked_vault::id(), processor!(process_instruction));
+    program_test.add_account(first_key, program_account(first.try_to_vec().unwrap()));
+    program_test.add_account(second_key, program_account(second.try_to_vec().unwrap()));
+    program_test.add_account(registry_key, program_account(registry.try_to_vec().unwrap()));
+    let (mut banks_client, _, _) = program_test.start().await;
+    let accounts = program_accounts(&mut banks_client, &[first_key, second_key, registry_key]).await;
+    let positions = |wallet: &Pubkey, config: &PositionScanConfig| {
+        client::positions_for(accounts.iter().map(|(key, data)| (key, data.as_slice())), wallet, config)
+    };
+
+    // Alice's active deposit in the first vault and the one she is the
+    // beneficiary of in the second
+    let found = positions(&alice, &PositionScanConfig::default());
+    let ids: Vec<(Pubkey, u64)> = found.iter().map(|position| (position.vault, position.deposit_id)).collect();
+    let mut expected = vec![(first_key, 0), (second_key, 1)];
+    expected.sort();
+    assert_eq!(ids, expected);
+    let first_position = found.iter().find(|position| position.vault == first_key).unwrap();
+    assert_eq!(first_position, &PositionSummary {
+        vault: first_key,
+        deposit_id: 0,
+        mint: first.deposits[0].token_mint,
+        amount: 100,
+        unlock_time: 1_000,
+        status: Some(DepositStatus::Active),
+    });
+
+    // Closed deposits on request, and paging through the sorted results
+    let with_closed = PositionScanConfig { include_closed: true, ..PositionScanConfig::default() };
+    assert_eq!(positions(&alice, &with_closed).len(), 3);
+    let second_page = PositionScanConfig { offset: 1, limit: Some(1), ..with_closed.clone() };
+    assert_eq!(positions(&alice, &second_page), positions(&alice, &with_closed)[1..2].to_vec());
+
+    assert_eq!(positions(&bob, &PositionScanConfig::default()).len(), 1);
+    assert_eq!(positions(&carol, &PositionScanConfig::default()).len(), 2);
+    assert!(positions(&Pubkey::new_unique(), &with_closed).is_empty());
+}
+
+#[test]
+fn test_owner_filter_matches_the_vault_layout() {
+    let owner = Pubkey::new_unique();
+    let vault = Vault { is_initialized: true, owner, ..Vault::default() }.try_to_vec().unwrap();
+
+    let RpcFilterType::Memcmp(by_owner) = client::vault_owner_filter(&owner) else { panic!() };
+    assert_eq!(by_owner, Memcmp::new_base58_encoded(client::VAULT_OWNER_OFFSET, &vault[..32]));
+}
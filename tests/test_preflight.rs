@@ -0,0 +1,195 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use spl_token::state::{Account as TokenAccount, AccountState};
+    use time_locked_vault::{
+        preflight::{preflight_deposit, preflight_withdraw, TokenAccountInfoLite},
+        process_instruction, Deposit, DepositPolicy, DepositStatus, RateLimit, Vault, VaultInstruction,
+    };
+
+    const UNLOCK_TIME: i64 = 1_000;
+    const AMOUNT: u64 = 500;
+
+    struct Scenario {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        signer: Pubkey,
+        token_mint: Pubkey,
+        now: i64,
+        vault: Vault,
+        token_account: TestAccount,
+    }
+
+    impl Scenario {
+        fn withdrawal() -> Self {
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            Self {
+                program_id: Pubkey::new_unique(),
+                depositor,
+                signer: depositor,
+                token_mint,
+                now: UNLOCK_TIME,
+                vault: Vault {
+                    deposit_count: 1,
+                    deposits: vec![Deposit {
+                        id: 0,
+                        depositor,
+                        token_mint,
+                        amount: AMOUNT,
+                        unlock_time: UNLOCK_TIME,
+                        ..Deposit::default()
+                    }],
+                    ..Vault::default()
+                },
+                token_account: token_account(&token_mint, &depositor, 0),
+            }
+        }
+        
+        fn deposit() -> Self {
+            let mut scenario = Self::withdrawal();
+            scenario.vault.deposits.clear();
+            scenario.vault.deposit_count = 0;
+            scenario.now = 0;
+            scenario.token_account = token_account(&scenario.token_mint, &scenario.depositor, AMOUNT);
+            scenario
+        }
+        
+        fn lite(&self) -> TokenAccountInfoLite {
+            let token_account = TokenAccount::unpack_unchecked(&self.token_account.data).unwrap();
+            TokenAccountInfoLite::new(self.token_account.key, &token_account)
+        }
+        
+        fn assert_withdraw_agrees(mut self) {
+            let predicted = preflight_withdraw(&self.vault, 0, &self.signer, self.now, Some(&self.lite()));
+        
+            let mut vault = vault_account(&self.program_id, &self.vault, 2_000);
+            let mut signer = TestAccount::wallet(self.signer).signer();
+            let mut source = token_account(&self.token_mint, &vault.key, AMOUNT);
+            let mut token_program = token_program();
+            let mut clock = clock_account(1, self.now);
+            let accounts = vec![
+                signer.info(),
+                vault.info(),
+                self.token_account.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Withdraw { deposit_id: 0 }.try_to_vec().unwrap();
+            let actual = process_instruction(&self.program_id, &accounts, &data);
+        
+            assert_eq!(predicted.map_err(ProgramError::from), actual);
+        }
+        
+        fn assert_deposit_agrees(mut self, amount: u64, unlock_time: i64) {
+            let lite = self.lite();
+            let predicted = preflight_deposit(&self.vault, &self.signer, amount, unlock_time, self.now, Some(&lite));
+        
+            let mut vault = vault_account(&self.program_id, &self.vault, 2_000);
+            let mut signer = TestAccount::wallet(self.signer).signer();
+            let mut vault_tokens = token_account(&self.token_mint, &vault.key, 0);
+            let mut token_program = token_program();
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(1, self.now);
+            let accounts = vec![
+                signer.info(),
+                vault.info(),
+                self.token_account.info(),
+                vault_tokens.info(),
+                token_program.info(),
+                system_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Deposit {
+                amount,
+                unlock_time,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .try_to_vec()
+            .unwrap();
+            let actual: ProgramResult = process_instruction(&self.program_id, &accounts, &data);
+        
+            assert_eq!(predicted.map_err(ProgramError::from), actual);
+        }
+    }
+
+    #[test]
+    fn test_withdraw_matrix() {
+        Scenario::withdrawal().assert_withdraw_agrees();
+        
+        let mut early = Scenario::withdrawal();
+        early.now = UNLOCK_TIME - 1;
+        early.assert_withdraw_agrees();
+        
+        let mut stranger = Scenario::withdrawal();
+        stranger.signer = Pubkey::new_unique();
+        stranger.assert_withdraw_agrees();
+        
+        let mut withdrawn = Scenario::withdrawal();
+        withdrawn.vault.deposits[0].status = DepositStatus::Withdrawn as u8;
+        withdrawn.assert_withdraw_agrees();
+        
+        let mut frozen = Scenario::withdrawal();
+        frozen.vault.deposits[0].frozen = true;
+        frozen.assert_withdraw_agrees();
+        
+        let mut frozen_account = Scenario::withdrawal();
+        update_token_account(&mut frozen_account.token_account, |state| state.state = AccountState::Frozen);
+        frozen_account.assert_withdraw_agrees();
+        
+        let mut wrong_mint = Scenario::withdrawal();
+        update_token_account(&mut wrong_mint.token_account, |state| state.mint = Pubkey::new_unique());
+        wrong_mint.assert_withdraw_agrees();
+        
+        let mut locked = Scenario::withdrawal();
+        locked.vault.deposits[0].locked_destination = Some(Pubkey::new_unique());
+        locked.assert_withdraw_agrees();
+        
+        let mut queued = Scenario::withdrawal();
+        queued.vault.deposits[0].queued_withdrawals = true;
+        queued.assert_withdraw_agrees();
+        
+        let mut rate_limited = Scenario::withdrawal();
+        rate_limited.vault.rate_limit = Some(RateLimit { amount: AMOUNT - 1, window_secs: 60 });
+        rate_limited.assert_withdraw_agrees();
+    }
+
+    #[test]
+    fn test_deposit_matrix() {
+        Scenario::deposit().assert_deposit_agrees(AMOUNT, UNLOCK_TIME);
+        Scenario::deposit().assert_deposit_agrees(0, UNLOCK_TIME);
+        Scenario::deposit().assert_deposit_agrees(AMOUNT, 0);
+        Scenario::deposit().assert_deposit_agrees(AMOUNT + 1, UNLOCK_TIME);
+        
+        let mut not_allowed = Scenario::deposit();
+        not_allowed.vault.deposit_policy = DepositPolicy::Allowlist;
+        not_allowed.assert_deposit_agrees(AMOUNT, UNLOCK_TIME);
+        
+        let mut blocked = Scenario::deposit();
+        blocked.vault.blocked_depositors = vec![blocked.depositor];
+        blocked.assert_deposit_agrees(AMOUNT, UNLOCK_TIME);
+        
+        let mut capped = Scenario::deposit();
+        capped.vault.tvl_cap = Some(AMOUNT - 1);
+        capped.assert_deposit_agrees(AMOUNT, UNLOCK_TIME);
+        
+        let mut frozen_source = Scenario::deposit();
+        update_token_account(&mut frozen_source.token_account, |state| state.state = AccountState::Frozen);
+        frozen_source.assert_deposit_agrees(AMOUNT, UNLOCK_TIME);
+    }
+}
@@ -0,0 +1,148 @@
+#![cfg(feature = "pyth")]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use pyth_sdk_solana::state::{AccountType, PriceAccount, PriceInfo, PriceStatus, MAGIC, VERSION_2};
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use std::ops::{Deref, DerefMut};
+    use time_locked_vault::{Deposit, PriceCondition, PriceOracleConfig, Vault, VaultError, VaultInstruction};
+
+    const SLOT: u64 = 1_000;
+    const MAX_AGE_SLOTS: u64 = 25;
+    const UNLOCK_TIME: i64 = 2_000_000_000;
+    const NOW: i64 = 1_700_000_000;
+
+    // A Pyth price account publishing `price` × 10^-8 at `publish_slot`
+    fn price_account(oracle_program: &Pubkey, feed: Pubkey, price: i64, conf: u64, publish_slot: u64) -> TestAccount {
+        let account = PriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            expo: -8,
+            agg: PriceInfo {
+                price,
+                conf,
+                status: PriceStatus::Trading,
+                pub_slot: publish_slot,
+                ..PriceInfo::default()
+            },
+            ..PriceAccount::default()
+        };
+        TestAccount::new(feed, *oracle_program, bytemuck::bytes_of(&account).to_vec())
+    }
+
+    // The shared harness, plus the oracle program and the feed its deposit's
+    // price condition reads
+    struct PriceUnlock {
+        base: Harness,
+        oracle_program: Pubkey,
+        feed: Pubkey,
+    }
+
+    impl Deref for PriceUnlock {
+        type Target = Harness;
+        
+        fn deref(&self) -> &Harness {
+            &self.base
+        }
+    }
+
+    impl DerefMut for PriceUnlock {
+        fn deref_mut(&mut self) -> &mut Harness {
+            &mut self.base
+        }
+    }
+
+    impl PriceUnlock {
+        // A vault holding one deposit that unlocks early at $1.00 (100 × 10^-2)
+        fn new() -> Self {
+            let (oracle_program, feed) = (Pubkey::new_unique(), Pubkey::new_unique());
+            let (depositor, token_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+            let vault = Vault {
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    depositor,
+                    token_mint,
+                    amount: 100,
+                    unlock_time: UNLOCK_TIME,
+                    price_condition: Some(PriceCondition { feed, threshold: 100, expo: -2 }),
+                    ..Deposit::default()
+                }],
+                price_oracle: Some(PriceOracleConfig {
+                    program: oracle_program,
+                    max_age_slots: MAX_AGE_SLOTS,
+                    max_conf_bps: 100,
+                }),
+                ..Vault::default()
+            };
+            Self { base: Harness::with_depositor(&vault, 1000, depositor, token_mint), oracle_program, feed }
+        }
+        
+        fn withdraw_on_price(&mut self, price: &mut TestAccount) -> ProgramResult {
+            let data = VaultInstruction::WithdrawOnPrice { deposit_id: 0 }.pack();
+            let depositor = self.depositor;
+            self.withdraw_with(depositor, &data, 100, (SLOT, NOW), &mut [price])
+        }
+        
+        fn price(&self, price: i64, publish_slot: u64) -> TestAccount {
+            price_account(&self.oracle_program, self.feed, price, 10_000, publish_slot)
+        }
+    }
+
+    #[test]
+    fn test_price_above_threshold_releases_early() {
+        let mut harness = PriceUnlock::new();
+        let mut price = harness.price(100_000_000, SLOT - MAX_AGE_SLOTS);
+        take_invocations();
+        
+        harness.withdraw_on_price(&mut price).unwrap();
+        
+        assert_eq!(token_transfers(&take_invocations()).len(), 1);
+        assert!(read_vault(&harness.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_price_below_threshold_stays_locked() {
+        let mut harness = PriceUnlock::new();
+        let mut price = harness.price(99_999_999, SLOT);
+        
+        let result = harness.withdraw_on_price(&mut price);
+        
+        assert_eq!(result, Err(VaultError::PriceThresholdNotMet.into()));
+        assert!(!read_vault(&harness.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_stale_price_rejected() {
+        let mut harness = PriceUnlock::new();
+        let mut price = harness.price(200_000_000, SLOT - MAX_AGE_SLOTS - 1);
+        
+        let result = harness.withdraw_on_price(&mut price);
+        
+        assert_eq!(result, Err(VaultError::StalePrice.into()));
+    }
+
+    #[test]
+    fn test_wide_confidence_rejected() {
+        let mut harness = PriceUnlock::new();
+        // 1% of the price is allowed, this is 1.5%
+        let mut price = price_account(&harness.oracle_program, harness.feed, 200_000_000, 3_000_000, SLOT);
+        
+        let result = harness.withdraw_on_price(&mut price);
+        
+        assert_eq!(result, Err(VaultError::PriceConfidenceTooWide.into()));
+    }
+
+    #[test]
+    fn test_feed_must_match_condition_and_oracle() {
+        let mut harness = PriceUnlock::new();
+        let mut other_feed = price_account(&harness.oracle_program, Pubkey::new_unique(), 200_000_000, 0, SLOT);
+        let mut other_owner = price_account(&Pubkey::new_unique(), harness.feed, 200_000_000, 0, SLOT);
+        
+        assert_eq!(harness.withdraw_on_price(&mut other_feed), Err(VaultError::InvalidPriceFeed.into()));
+        assert_eq!(harness.withdraw_on_price(&mut other_owner), Err(VaultError::InvalidPriceFeed.into()));
+    }
+}
@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod tests {
+    use solana_program::pubkey::Pubkey;
+    use time_locked_vault::{
+        builder, check_id, id, parse_program_id, DEVNET_PROGRAM_ID, LOCALNET_PROGRAM_ID, MAINNET_PROGRAM_ID,
+    };
+
+    #[test]
+    fn test_tests_run_against_the_localnet_id() {
+        assert_eq!(id(), LOCALNET_PROGRAM_ID);
+        assert!(check_id(&LOCALNET_PROGRAM_ID));
+        assert!(!check_id(&DEVNET_PROGRAM_ID) && !check_id(&MAINNET_PROGRAM_ID));
+        
+        // The committed localnet keypair deploys to that id
+        let keypair: Vec<u8> = include_str!("../deploy/localnet-keypair.json")
+            .trim_matches(|c| c == '[' || c == ']' || c == '\n')
+            .split(',')
+            .map(|byte| byte.trim().parse().unwrap())
+            .collect();
+        assert_eq!(keypair.len(), 64);
+        assert_eq!(&keypair[32..], LOCALNET_PROGRAM_ID.as_ref());
+    }
+
+    #[test]
+    fn test_parse_program_id_matches_base58() {
+        for program_id in [MAINNET_PROGRAM_ID, DEVNET_PROGRAM_ID, LOCALNET_PROGRAM_ID, Pubkey::default()] {
+            assert_eq!(parse_program_id(&program_id.to_string()), program_id);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "check the cluster feature")]
+    fn test_builders_reject_another_clusters_id() {
+        builder::get_version(&DEVNET_PROGRAM_ID);
+    }
+}
@@ -0,0 +1,290 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey, rent::Rent, system_instruction, system_program};
+    use time_locked_vault::{
+        builder, find_config_address, find_stats_address, process_instruction, ProgramStats, Vault, VaultConfig,
+        VaultError, VaultInstruction, MAX_STATS_MINTS,
+    };
+
+    const NOW: i64 = 1_700_000_000;
+
+    struct StatsContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        mint: Pubkey,
+        vault: TestAccount,
+        stats: TestAccount,
+    }
+
+    impl StatsContext {
+        // An uninitialized vault account and existing, empty program stats
+        fn new() -> Self {
+            let program_id = time_locked_vault::id();
+            let mut data = ProgramStats::default().try_to_vec().unwrap();
+            data.resize(ProgramStats::LEN, 0);
+            Self {
+                program_id,
+                owner: Pubkey::new_unique(),
+                mint: Pubkey::new_unique(),
+                vault: TestAccount::new(Pubkey::new_unique(), program_id, vec![0; 2000]).writable(),
+                stats: TestAccount::new(find_stats_address(&program_id).0, program_id, data).writable(),
+            }
+        }
+        
+        fn stats(&self) -> ProgramStats {
+            ProgramStats::deserialize(&mut self.stats.data.as_slice()).unwrap()
+        }
+        
+        fn create_vault(&mut self, with_stats: bool) -> ProgramResult {
+            let config = VaultConfig::default();
+            let instruction = builder::create_vault(&self.program_id, &self.owner, &self.vault.key, true, config);
+            let mut owner = TestAccount::wallet(self.owner).signer();
+            let mut system_program = system_program_account();
+            let mut accounts = vec![owner.info(), self.vault.info(), system_program.info()];
+            if with_stats {
+                accounts.push(self.stats.info());
+            }
+            set_clock(7, NOW);
+            process_instruction(&self.program_id, &accounts, &instruction.data)
+        }
+        
+        fn deposit(&mut self, amount: u64, with_stats: bool) -> ProgramResult {
+            let depositor = Pubkey::new_unique();
+            let mut depositor_account = TestAccount::wallet(depositor).signer();
+            let mut source = token_account(&self.mint, &depositor, amount);
+            let mut vault_token = token_account(&self.mint, &self.vault.key, 0);
+            let mut token_program = token_program();
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(8, NOW);
+            let mut accounts = vec![
+                depositor_account.info(),
+                self.vault.info(),
+                source.info(),
+                vault_token.info(),
+                token_program.info(),
+                system_program.info(),
+                clock.info(),
+            ];
+            if with_stats {
+                accounts.push(self.stats.info());
+            }
+            let data = VaultInstruction::DepositWithDuration { amount, lock_duration_secs: 60, tag: [0; 32] }.pack();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn withdraw(&mut self, deposit_id: u64) -> ProgramResult {
+            let deposit = read_vault(&self.vault).deposits[deposit_id as usize].clone();
+            let mut depositor = TestAccount::wallet(deposit.depositor).signer();
+            let mut destination = token_account(&self.mint, &deposit.depositor, 0);
+            let mut source = token_account(&self.mint, &self.vault.key, 1_000_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(9, deposit.unlock_time);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+                self.stats.info(),
+            ];
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::Withdraw { deposit_id }.pack())
+        }
+        
+        fn withdraw_split(&mut self, deposit_id: u64, splits: &[u64]) -> ProgramResult {
+            let deposit = read_vault(&self.vault).deposits[deposit_id as usize].clone();
+            let mut depositor = TestAccount::wallet(deposit.depositor).signer();
+            let mut source = token_account(&self.mint, &self.vault.key, 1_000_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(9, deposit.unlock_time);
+            let mut config = TestAccount::new(find_config_address(&self.program_id).0, system_program::id(), vec![]);
+            let mut destinations: Vec<_> =
+                splits.iter().map(|_| token_account(&self.mint, &deposit.depositor, 0)).collect();
+            let mut accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+                config.info(),
+            ];
+            accounts.extend(destinations.iter_mut().map(|destination| destination.info()));
+            accounts.push(self.stats.info());
+            set_clock(9, deposit.unlock_time);
+            let data = VaultInstruction::WithdrawSplit { deposit_id, splits: splits.to_vec() }.pack();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn emergency_withdraw(&mut self, deposit_id: u64) -> ProgramResult {
+            let authority = Pubkey::new_unique();
+            let mut vault = read_vault(&self.vault);
+            vault.emergency_authority = Some(authority);
+            vault.serialize(&mut self.vault.data.as_mut_slice()).unwrap();
+            let depositor = vault.deposits[deposit_id as usize].depositor;
+            let mut authority = TestAccount::wallet(authority).signer();
+            let mut destination = token_account(&self.mint, &depositor, 0);
+            let mut source = token_account(&self.mint, &self.vault.key, 1_000_000);
+            let mut token_program = token_program();
+            let mut depositor = TestAccount::wallet(depositor);
+            let accounts = vec![
+                authority.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                depositor.info(),
+                self.stats.info(),
+            ];
+            set_clock(11, NOW);
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::EmergencyWithdraw { deposit_id }.pack())
+        }
+        
+        fn reconcile(&mut self) -> ProgramResult {
+            let mut caller = TestAccount::wallet(Pubkey::new_unique()).signer().writable();
+            let mut system_program = system_program_account();
+            let accounts = vec![caller.info(), self.vault.info(), self.stats.info(), system_program.info()];
+            set_clock(10, NOW);
+            take_invocations();
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::ReconcileStats.pack())
+        }
+    }
+
+    #[test]
+    fn test_counted_vault_updates_stats_incrementally() {
+        let mut ctx = StatsContext::new();
+        
+        ctx.create_vault(true).unwrap();
+        assert!(read_vault(&ctx.vault).stats_reconciled);
+        assert_eq!((ctx.stats().vaults_counted, ctx.stats().last_updated_slot), (1, 7));
+        
+        ctx.deposit(300, true).unwrap();
+        ctx.deposit(200, true).unwrap();
+        let stats = ctx.stats();
+        assert_eq!((stats.deposits_created, stats.amount_deposited), (2, 500));
+        assert_eq!(stats.locked(&ctx.mint), Some(500));
+        assert_eq!(stats.last_updated_slot, 8);
+        
+        ctx.withdraw(0).unwrap();
+        let stats = ctx.stats();
+        assert_eq!(stats.amount_withdrawn, 300);
+        assert_eq!(stats.locked(&ctx.mint), Some(200));
+        assert_eq!(stats.last_updated_slot, 9);
+    }
+
+    #[test]
+    fn test_split_and_emergency_withdrawals_keep_stats_reconciled() {
+        let mut ctx = StatsContext::new();
+        ctx.create_vault(true).unwrap();
+        ctx.deposit(300, true).unwrap();
+        ctx.deposit(200, true).unwrap();
+        ctx.deposit(100, true).unwrap();
+        
+        ctx.withdraw_split(0, &[250, 50]).unwrap();
+        ctx.emergency_withdraw(1).unwrap();
+        
+        let stats = ctx.stats();
+        assert_eq!((stats.deposits_created, stats.amount_deposited, stats.amount_withdrawn), (3, 600, 500));
+        assert_eq!(stats.locked(&ctx.mint), Some(100));
+        assert_eq!(stats.last_updated_slot, 11);
+        
+        // Folding the same vault into empty stats gives the same totals
+        let mut vault = read_vault(&ctx.vault);
+        vault.stats_reconciled = false;
+        let mut fresh = StatsContext::new();
+        fresh.vault = vault_account(&fresh.program_id, &vault, 2000);
+        fresh.reconcile().unwrap();
+        assert_eq!(fresh.stats(), ProgramStats { last_updated_slot: 10, ..stats });
+    }
+
+    #[test]
+    fn test_stats_account_is_optional() {
+        let mut ctx = StatsContext::new();
+        ctx.create_vault(true).unwrap();
+        
+        // Deposits without the stats account still go through, uncounted
+        ctx.deposit(300, false).unwrap();
+        assert_eq!(read_vault(&ctx.vault).deposits.len(), 1);
+        assert_eq!(ctx.stats().deposits_created, 0);
+        
+        // A vault created without it isn't counted until reconciled, even
+        // when later instructions pass the stats account
+        let mut ctx = StatsContext::new();
+        ctx.create_vault(false).unwrap();
+        ctx.deposit(300, true).unwrap();
+        assert!(!read_vault(&ctx.vault).stats_reconciled);
+        assert_eq!(ctx.stats(), ProgramStats::default());
+    }
+
+    #[test]
+    fn test_reconcile_folds_a_vault_in_once() {
+        let mut ctx = StatsContext::new();
+        ctx.create_vault(false).unwrap();
+        ctx.deposit(300, false).unwrap();
+        ctx.deposit(200, false).unwrap();
+        ctx.withdraw(0).unwrap();
+        
+        ctx.reconcile().unwrap();
+        
+        let stats = ctx.stats();
+        assert_eq!((stats.vaults_counted, stats.deposits_created), (1, 2));
+        assert_eq!((stats.amount_deposited, stats.amount_withdrawn), (500, 300));
+        assert_eq!(stats.locked(&ctx.mint), Some(200));
+        assert_eq!(stats.last_updated_slot, 10);
+        assert!(read_vault(&ctx.vault).stats_reconciled);
+        
+        // Folding the same vault again would double count it
+        assert_eq!(ctx.reconcile(), Err(VaultError::StatsAlreadyReconciled.into()));
+        assert_eq!(ctx.stats(), stats);
+        
+        // From now on the vault updates the stats directly
+        ctx.deposit(50, true).unwrap();
+        assert_eq!(ctx.stats().locked(&ctx.mint), Some(250));
+    }
+
+    #[test]
+    fn test_reconcile_creates_the_stats_on_first_use() {
+        let mut ctx = StatsContext::new();
+        ctx.stats = TestAccount::new(ctx.stats.key, system_program::id(), vec![0; ProgramStats::LEN]).writable();
+        let vault = Vault { is_initialized: true, lifetime_deposits_created: 3, ..Vault::default() };
+        ctx.vault = vault_account(&ctx.program_id, &vault, 2000);
+        
+        ctx.reconcile().unwrap();
+        
+        let invocations = take_invocations();
+        assert_eq!(invocations.len(), 1);
+        assert_eq!(
+            invocations[0].data,
+            system_instruction::create_account(
+                &invocations[0].accounts[0].pubkey,
+                &ctx.stats.key,
+                Rent::default().minimum_balance(ProgramStats::LEN),
+                ProgramStats::LEN as u64,
+                &ctx.program_id,
+            )
+            .data
+        );
+        assert_eq!(ctx.stats().deposits_created, 3);
+    }
+
+    #[test]
+    fn test_mints_past_the_limit_share_a_bucket() {
+        let mut stats = ProgramStats::default();
+        let mints: Vec<Pubkey> = (0..=MAX_STATS_MINTS).map(|_| Pubkey::new_unique()).collect();
+        for mint in &mints {
+            stats.add_locked(mint, 10);
+        }
+        let overflow = mints.last().unwrap();
+        assert_eq!(stats.mint_locked.len(), MAX_STATS_MINTS);
+        assert_eq!((stats.locked(overflow), stats.other_mints_locked), (None, 10));
+        
+        // Removals saturate rather than fail
+        stats.remove_locked(overflow, 25);
+        stats.remove_locked(&mints[0], u128::MAX);
+        assert_eq!((stats.locked(&mints[0]), stats.other_mints_locked), (Some(0), 0));
+        assert!(stats.try_to_vec().unwrap().len() <= ProgramStats::LEN);
+    }
+}
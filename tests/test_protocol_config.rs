@@ -0,0 +1,175 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        find_config_address, process_instruction, protocol_fee, Deposit, ProtocolConfig, Vault,
+        VaultError, VaultInstruction, MAX_PROTOCOL_FEE_BPS,
+    };
+
+    const UNLOCK_TIME: i64 = 1_000;
+    const AMOUNT: u64 = 10_000;
+
+    struct ConfigContext {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        fee_destination: Pubkey,
+        vault: TestAccount,
+        config: TestAccount,
+    }
+
+    impl ConfigContext {
+        fn new(protocol_fee_bps: u16) -> Self {
+            let program_id = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let fee_destination = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor,
+                    token_mint,
+                    amount: AMOUNT,
+                    unlock_time: UNLOCK_TIME,
+                    ..Deposit::default()
+                }],
+                ..Vault::default()
+            };
+            let config = ProtocolConfig {
+                admin: Pubkey::new_unique(),
+                protocol_fee_bps,
+                fee_destination,
+                pending_admin: None,
+            };
+            let mut data = vec![0; ProtocolConfig::LEN];
+            config.serialize(&mut data.as_mut_slice()).unwrap();
+            Self {
+                program_id,
+                depositor,
+                token_mint,
+                fee_destination,
+                vault: vault_account(&program_id, &vault, 1000),
+                config: TestAccount::new(find_config_address(&program_id).0, program_id, data),
+            }
+        }
+        
+        fn read_config(&self) -> ProtocolConfig {
+            ProtocolConfig::deserialize(&mut self.config.data.as_slice()).unwrap()
+        }
+        
+        fn send(&mut self, signer: &Pubkey, instruction: VaultInstruction) -> ProgramResult {
+            let mut signer = TestAccount::wallet(*signer).signer();
+            self.config.is_writable = true;
+            let accounts = vec![signer.info(), self.config.info()];
+            process_instruction(&self.program_id, &accounts, &instruction.try_to_vec().unwrap())
+        }
+        
+        fn withdraw(&mut self, with_config: bool) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, AMOUNT);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, UNLOCK_TIME);
+            let mut fee_destination = TestAccount::new(self.fee_destination, spl_token::id(), vec![]).writable();
+            let mut accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            if with_config {
+                accounts.push(self.config.info());
+                accounts.push(fee_destination.info());
+            }
+            let data = VaultInstruction::Withdraw { deposit_id: 0 }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_protocol_fee_math() {
+        assert_eq!(protocol_fee(10_000, 30), 30);
+        assert_eq!(protocol_fee(333, 30), 0);
+        assert_eq!(protocol_fee(u64::MAX, 10_000), protocol_fee(u64::MAX, MAX_PROTOCOL_FEE_BPS));
+        assert_eq!(protocol_fee(u64::MAX, MAX_PROTOCOL_FEE_BPS), u64::MAX / 20);
+    }
+
+    #[test]
+    fn test_withdraw_skims_fee() {
+        let mut ctx = ConfigContext::new(250);
+        take_invocations();
+        
+        ctx.withdraw(true).unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers[0], (ctx.fee_destination, 250));
+        assert_eq!(transfers[1].1, AMOUNT - 250);
+    }
+
+    #[test]
+    fn test_withdraw_without_config_pays_in_full() {
+        let mut ctx = ConfigContext::new(250);
+        take_invocations();
+        
+        ctx.withdraw(false).unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].1, AMOUNT);
+    }
+
+    #[test]
+    fn test_uninitialized_config_pays_in_full() {
+        let mut ctx = ConfigContext::new(250);
+        ctx.config.owner = solana_program::system_program::id();
+        take_invocations();
+        
+        ctx.withdraw(true).unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].1, AMOUNT);
+    }
+
+    #[test]
+    fn test_fee_above_ceiling_rejected() {
+        let mut ctx = ConfigContext::new(0);
+        let admin = ctx.read_config().admin;
+        
+        let result = ctx.send(&admin, VaultInstruction::UpdateConfig {
+            protocol_fee_bps: MAX_PROTOCOL_FEE_BPS + 1,
+            fee_destination: ctx.fee_destination,
+        });
+        
+        assert_eq!(result, Err(VaultError::InvalidInstructionData.into()));
+    }
+
+    #[test]
+    fn test_two_step_admin_rotation() {
+        let mut ctx = ConfigContext::new(0);
+        let admin = ctx.read_config().admin;
+        let new_admin = Pubkey::new_unique();
+        
+        let early = ctx.send(&new_admin, VaultInstruction::AcceptConfigAdmin);
+        assert_eq!(early, Err(VaultError::NoPendingAdminChange.into()));
+        
+        ctx.send(&admin, VaultInstruction::ProposeConfigAdmin { new_admin: Some(new_admin) }).unwrap();
+        assert_eq!(ctx.read_config().admin, admin);
+        
+        let stranger = ctx.send(&Pubkey::new_unique(), VaultInstruction::AcceptConfigAdmin);
+        assert_eq!(stranger, Err(VaultError::NotConfigAdmin.into()));
+        
+        ctx.send(&new_admin, VaultInstruction::AcceptConfigAdmin).unwrap();
+        let config = ctx.read_config();
+        assert_eq!(config.admin, new_admin);
+        assert_eq!(config.pending_admin, None);
+    }
+}
@@ -0,0 +1,99 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        process_instruction, Deposit, RateLimit, Vault, VaultError, VaultInstruction,
+    };
+
+    const WINDOW: i64 = 24 * 60 * 60;
+    const START: i64 = 100_000;
+
+    struct RateLimitContext {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl RateLimitContext {
+        // Three matured deposits of 400 tokens under a 1,000 per day limit
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: 3,
+                deposits: (0..3)
+                    .map(|id| Deposit {
+                        id,
+                        depositor,
+                        token_mint,
+                        amount: 400,
+                        ..Deposit::default()
+                    })
+                    .collect(),
+                rate_limit: Some(RateLimit { amount: 1_000, window_secs: WINDOW }),
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                depositor,
+                token_mint,
+                vault: vault_account(&program_id, &vault, 2000),
+            }
+        }
+        
+        fn withdraw(&mut self, deposit_id: u64, now: i64) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_200);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, now);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Withdraw { deposit_id }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_multiple_withdrawals_within_window() {
+        let mut ctx = RateLimitContext::new();
+        ctx.withdraw(0, START).unwrap();
+        ctx.withdraw(1, START + 60).unwrap();
+        
+        let result = ctx.withdraw(2, START + 120);
+        
+        assert_eq!(result, Err(VaultError::RateLimited.into()));
+        let vault = read_vault(&ctx.vault);
+        assert!(!vault.deposits[2].is_withdrawn());
+        assert_eq!(vault.withdrawal_windows[0].withdrawn, 800);
+    }
+
+    #[test]
+    fn test_window_rollover() {
+        let mut ctx = RateLimitContext::new();
+        ctx.withdraw(0, START).unwrap();
+        ctx.withdraw(1, START).unwrap();
+        
+        let early = ctx.withdraw(2, START + WINDOW - 1);
+        assert_eq!(early, Err(VaultError::RateLimited.into()));
+        
+        ctx.withdraw(2, START + WINDOW).unwrap();
+        let vault = read_vault(&ctx.vault);
+        assert_eq!(vault.withdrawal_windows.len(), 1);
+        assert_eq!(vault.withdrawal_windows[0].window_start, START + WINDOW);
+        assert_eq!(vault.withdrawal_windows[0].withdrawn, 400);
+    }
+}
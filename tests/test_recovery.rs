@@ -0,0 +1,164 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use std::ops::{Deref, DerefMut};
+    use time_locked_vault::{Deposit, Vault, VaultError, VaultInstruction, MAX_GUARDIANS, RECOVERY_DELAY};
+
+    const NOW: i64 = 1_700_000_000;
+
+    // The shared harness, plus the guardians of its deposit
+    struct Recovery {
+        base: Harness,
+        guardians: Vec<Pubkey>,
+    }
+
+    impl Deref for Recovery {
+        type Target = Harness;
+        
+        fn deref(&self) -> &Harness {
+            &self.base
+        }
+    }
+
+    impl DerefMut for Recovery {
+        fn deref_mut(&mut self) -> &mut Harness {
+            &mut self.base
+        }
+    }
+
+    impl Recovery {
+        // A deposit with three guardians, two of which must approve
+        fn new() -> Self {
+            let depositor = Pubkey::new_unique();
+            let guardians = vec![Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+            let vault = Vault {
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    depositor,
+                    amount: 100,
+                    unlock_time: NOW + 1_000_000,
+                    locked_destination: Some(Pubkey::new_unique()),
+                    guardians: guardians.clone(),
+                    guardian_threshold: 2,
+                    ..Deposit::default()
+                }],
+                ..Vault::default()
+            };
+            Self { base: Harness::with_depositor(&vault, 1000, depositor, Pubkey::new_unique()), guardians }
+        }
+        
+        fn call(&mut self, signer: Pubkey, now: i64, instruction: VaultInstruction) -> ProgramResult {
+            let mut clock = clock_account(0, now);
+            self.base.call(signer, &instruction.pack(), &mut [&mut clock])
+        }
+        
+        fn deposit(&self) -> Deposit {
+            read_vault(&self.vault).deposits[0].clone()
+        }
+    }
+
+    #[test]
+    fn test_recovery_lifecycle() {
+        let mut harness = Recovery::new();
+        let (g0, g1) = (harness.guardians[0], harness.guardians[1]);
+        let new_depositor = Pubkey::new_unique();
+        
+        harness.call(g0, NOW, VaultInstruction::ProposeRecovery { deposit_id: 0, new_depositor }).unwrap();
+        let result = harness.call(g0, NOW, VaultInstruction::ExecuteRecovery { deposit_id: 0 });
+        assert_eq!(result, Err(VaultError::RecoveryNotApproved.into()));
+        
+        let approved_at = NOW + 60;
+        harness.call(g1, approved_at, VaultInstruction::ApproveRecovery { deposit_id: 0 }).unwrap();
+        assert_eq!(harness.deposit().pending_recovery.unwrap().approved_at, Some(approved_at));
+        
+        let (caller, execute) = (Pubkey::new_unique(), VaultInstruction::ExecuteRecovery { deposit_id: 0 });
+        let early = harness.call(caller, approved_at + RECOVERY_DELAY - 1, execute.clone());
+        assert_eq!(early, Err(VaultError::TimelockNotElapsed.into()));
+        
+        harness.call(caller, approved_at + RECOVERY_DELAY, execute).unwrap();
+        
+        let deposit = harness.deposit();
+        assert_eq!(deposit.depositor, new_depositor);
+        assert_eq!(deposit.pending_recovery, None);
+        assert_eq!(deposit.locked_destination, None);
+    }
+
+    #[test]
+    fn test_depositor_veto_cancels_recovery() {
+        let mut harness = Recovery::new();
+        let (g0, g1, depositor) = (harness.guardians[0], harness.guardians[1], harness.depositor);
+        let new_depositor = Pubkey::new_unique();
+        harness.call(g0, NOW, VaultInstruction::ProposeRecovery { deposit_id: 0, new_depositor }).unwrap();
+        harness.call(g1, NOW, VaultInstruction::ApproveRecovery { deposit_id: 0 }).unwrap();
+        
+        let result = harness.call(g0, NOW, VaultInstruction::VetoRecovery { deposit_id: 0 });
+        assert_eq!(result, Err(VaultError::UnauthorizedWithdrawal.into()));
+        harness.call(depositor, NOW + 1, VaultInstruction::VetoRecovery { deposit_id: 0 }).unwrap();
+        
+        let result = harness.call(g0, NOW + RECOVERY_DELAY, VaultInstruction::ExecuteRecovery { deposit_id: 0 });
+        assert_eq!(result, Err(VaultError::NoPendingRecovery.into()));
+        assert_eq!(harness.deposit().depositor, depositor);
+    }
+
+    #[test]
+    fn test_duplicate_and_outside_approvals_rejected() {
+        let mut harness = Recovery::new();
+        let g0 = harness.guardians[0];
+        let new_depositor = Pubkey::new_unique();
+        
+        let result = harness.call(g0, NOW, VaultInstruction::ApproveRecovery { deposit_id: 0 });
+        assert_eq!(result, Err(VaultError::NoPendingRecovery.into()));
+        
+        harness.call(g0, NOW, VaultInstruction::ProposeRecovery { deposit_id: 0, new_depositor }).unwrap();
+        
+        let result = harness.call(g0, NOW, VaultInstruction::ApproveRecovery { deposit_id: 0 });
+        assert_eq!(result, Err(VaultError::DuplicateApproval.into()));
+        let result = harness.call(Pubkey::new_unique(), NOW, VaultInstruction::ApproveRecovery { deposit_id: 0 });
+        assert_eq!(result, Err(VaultError::NotGuardian.into()));
+        let result = harness.call(g0, NOW, VaultInstruction::ProposeRecovery { deposit_id: 0, new_depositor: g0 });
+        assert_eq!(result, Err(VaultError::RecoveryAlreadyPending.into()));
+        
+        let recovery = harness.deposit().pending_recovery.unwrap();
+        assert_eq!((recovery.approvals, recovery.approved_at), (vec![g0], None));
+    }
+
+    #[test]
+    fn test_guardian_sets_validated_at_deposit() {
+        let depositor_key = Pubkey::new_unique();
+        let guardian = Pubkey::new_unique();
+        let cases = [
+            (vec![], 0, true),
+            (vec![guardian], 1, true),
+            (vec![guardian], 0, false),
+            (vec![guardian], 2, false),
+            (vec![guardian, guardian], 1, false),
+            (vec![depositor_key], 1, false),
+            ((0..=MAX_GUARDIANS).map(|_| Pubkey::new_unique()).collect(), 1, false),
+        ];
+        
+        for (guardians, guardian_threshold, ok) in cases {
+            let mut harness = Harness::with_depositor(&Vault::default(), 1000, depositor_key, Pubkey::new_unique());
+            let data = VaultInstruction::DepositWithGuardians {
+                amount: 100,
+                unlock_time: NOW + 1,
+                tag: [0; 32],
+                guardians: guardians.clone(),
+                guardian_threshold,
+            }
+            .pack();
+            
+            let result = harness.deposit_with(&data, 100, (0, NOW), &mut []);
+            
+            if ok {
+                result.unwrap();
+                assert_eq!(harness.state().deposits[0].guardians, guardians);
+            } else {
+                let invalid = Err(VaultError::InvalidGuardians.into());
+                assert_eq!(result, invalid, "{:?} / {}", guardians, guardian_threshold);
+            }
+        }
+    }
+}
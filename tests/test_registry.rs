@@ -0,0 +1,132 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+    use time_locked_vault::{
+        find_registry_address, process_instruction, Vault, VaultConfig, VaultError, VaultInstruction,
+        VaultRegistry, MAX_REGISTRY_VAULTS,
+    };
+
+    fn registry_account(program_id: &Pubkey, owner: &Pubkey, registry: Option<&VaultRegistry>) -> TestAccount {
+        let (key, _) = find_registry_address(owner, program_id);
+        let mut data = vec![0; VaultRegistry::LEN];
+        match registry {
+            Some(registry) => {
+                registry.serialize(&mut data.as_mut_slice()).unwrap();
+                TestAccount::new(key, *program_id, data).writable()
+            },
+            // Not yet created: still owned by the system program
+            None => TestAccount::new(key, solana_program::system_program::id(), data).writable(),
+        }
+    }
+
+    fn create_vault(
+        program_id: &Pubkey,
+        owner: &mut TestAccount,
+        vault: &mut TestAccount,
+        registry: &mut TestAccount,
+    ) -> Result<(), ProgramError> {
+        let mut system_program = system_program_account();
+        let accounts = vec![owner.info(), vault.info(), system_program.info(), registry.info()];
+        let data = VaultInstruction::CreateVault {
+            skip_registry: false,
+            arbitration_destination: None,
+            emergency_destination: None,
+            owner_set: None,
+            config: VaultConfig::default(),
+        }
+        .try_to_vec()
+        .unwrap();
+        process_instruction(program_id, &accounts, &data)
+    }
+
+    #[test]
+    fn test_create_vault_lazily_creates_registry() {
+        let program_id = Pubkey::new_unique();
+        let owner_key = Pubkey::new_unique();
+        let mut owner = TestAccount::wallet(owner_key).signer().writable();
+        let mut vault = TestAccount::new(Pubkey::new_unique(), program_id, vec![0; 1000]).writable();
+        let mut registry = registry_account(&program_id, &owner_key, None);
+        set_clock(0, 0);
+        
+        create_vault(&program_id, &mut owner, &mut vault, &mut registry).unwrap();
+        
+        let stored = VaultRegistry::deserialize(&mut registry.data.as_slice()).unwrap();
+        assert_eq!(stored.owner, owner_key);
+        assert_eq!(stored.vaults, vec![vault.key]);
+    }
+
+    #[test]
+    fn test_create_vault_appends_to_registry() {
+        let program_id = Pubkey::new_unique();
+        let owner_key = Pubkey::new_unique();
+        let existing = Pubkey::new_unique();
+        let mut owner = TestAccount::wallet(owner_key).signer().writable();
+        let mut vault = TestAccount::new(Pubkey::new_unique(), program_id, vec![0; 1000]).writable();
+        let mut registry = registry_account(
+            &program_id,
+            &owner_key,
+            Some(&VaultRegistry { owner: owner_key, vaults: vec![existing] }),
+        );
+        
+        create_vault(&program_id, &mut owner, &mut vault, &mut registry).unwrap();
+        
+        let stored = VaultRegistry::deserialize(&mut registry.data.as_slice()).unwrap();
+        assert_eq!(stored.vaults, vec![existing, vault.key]);
+    }
+
+    #[test]
+    fn test_registry_cap() {
+        let program_id = Pubkey::new_unique();
+        let owner_key = Pubkey::new_unique();
+        let full = VaultRegistry {
+            owner: owner_key,
+            vaults: (0..MAX_REGISTRY_VAULTS).map(|_| Pubkey::new_unique()).collect(),
+        };
+        let mut owner = TestAccount::wallet(owner_key).signer().writable();
+        let mut vault = TestAccount::new(Pubkey::new_unique(), program_id, vec![0; 1000]).writable();
+        let mut registry = registry_account(&program_id, &owner_key, Some(&full));
+        
+        let result = create_vault(&program_id, &mut owner, &mut vault, &mut registry);
+        assert_eq!(result, Err(VaultError::RegistryFull.into()));
+    }
+
+    #[test]
+    fn test_close_vault_prunes_registry() {
+        let program_id = Pubkey::new_unique();
+        let owner_key = Pubkey::new_unique();
+        let mut vault = vault_account(
+            &program_id,
+            &Vault {
+                owner: owner_key,
+                deposit_count: 0,
+                deposits: Vec::new(),
+                reentrancy_guard: false,
+                emergency_authority: None,
+                ..Vault::default()
+            },
+            1000,
+        )
+        .with_lamports(5_000);
+        let other = Pubkey::new_unique();
+        let mut registry = registry_account(
+            &program_id,
+            &owner_key,
+            Some(&VaultRegistry { owner: owner_key, vaults: vec![other, vault.key] }),
+        );
+        let mut owner = TestAccount::wallet(owner_key).signer().writable();
+        
+        let accounts = vec![owner.info(), vault.info(), registry.info()];
+        let data = VaultInstruction::CloseVault.try_to_vec().unwrap();
+        process_instruction(&program_id, &accounts, &data).unwrap();
+        drop(accounts);
+        
+        let stored = VaultRegistry::deserialize(&mut registry.data.as_slice()).unwrap();
+        assert_eq!(stored.vaults, vec![other]);
+        assert_eq!(owner.lamports, 5_000);
+        assert_eq!(vault.lamports, 0);
+    }
+}
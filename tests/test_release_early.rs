@@ -0,0 +1,120 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{process_instruction, Deposit, DepositStatus, Vault, VaultError, VaultInstruction};
+
+    const NOW: i64 = 1_000;
+
+    struct ReleaseContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        depositor: Pubkey,
+        mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl ReleaseContext {
+        // Locked deposits 0 (the owner may release it early) and 1 (it may not)
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let owner = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let mint = Pubkey::new_unique();
+            let deposit = |id, owner_can_release_early| Deposit {
+                id,
+                depositor,
+                token_mint: mint,
+                amount: 100,
+                unlock_time: NOW + 1_000,
+                owner_can_release_early,
+                ..Deposit::default()
+            };
+            let vault = Vault {
+                owner,
+                deposit_count: 2,
+                deposits: vec![deposit(0, true), deposit(1, false)],
+                total_locked: 200,
+                ..Vault::default()
+            };
+            Self { program_id, owner, depositor, mint, vault: vault_account(&program_id, &vault, 2000) }
+        }
+        
+        fn release(&mut self, signer: Pubkey, deposit_id: u64, mut destination: TestAccount) -> ProgramResult {
+            set_clock(0, NOW);
+            let mut signer = TestAccount::wallet(signer).signer();
+            let mut source = token_account(&self.mint, &self.vault.key, 200);
+            let mut token_program = token_program();
+            let accounts = vec![
+                signer.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+            ];
+            take_invocations();
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::ReleaseEarly { deposit_id }.pack())
+        }
+        
+        fn depositor_account(&self) -> TestAccount {
+            token_account(&self.mint, &self.depositor, 0)
+        }
+    }
+
+    #[test]
+    fn test_owner_releases_consenting_deposit() {
+        let mut ctx = ReleaseContext::new();
+        let (owner, destination) = (ctx.owner, ctx.depositor_account());
+        let destination_key = destination.key;
+        
+        ctx.release(owner, 0, destination).unwrap();
+        
+        assert_eq!(token_transfers(&take_invocations()), vec![(destination_key, 100)]);
+        let vault = read_vault(&ctx.vault);
+        assert_eq!(vault.deposits[0].status(), Some(DepositStatus::ReleasedEarly));
+        assert_eq!(vault.deposits[0].withdrawn_at, Some(NOW));
+        assert_eq!(vault.total_locked, 100);
+        let destination = ctx.depositor_account();
+        assert_eq!(ctx.release(owner, 0, destination), Err(VaultError::AlreadyWithdrawn.into()));
+    }
+
+    #[test]
+    fn test_release_needs_the_depositors_consent_and_the_owner() {
+        let mut ctx = ReleaseContext::new();
+        let (owner, depositor) = (ctx.owner, ctx.depositor);
+        
+        let destination = ctx.depositor_account();
+        assert_eq!(ctx.release(owner, 1, destination), Err(VaultError::EarlyReleaseNotAllowed.into()));
+        let destination = ctx.depositor_account();
+        assert_eq!(ctx.release(depositor, 0, destination), Err(VaultError::NotVaultOwner.into()));
+        
+        assert!(take_invocations().is_empty());
+        assert_eq!(read_vault(&ctx.vault).deposits[0].status(), Some(DepositStatus::Active));
+    }
+
+    #[test]
+    fn test_owner_cannot_redirect_the_release() {
+        let mut ctx = ReleaseContext::new();
+        let (owner, mint) = (ctx.owner, ctx.mint);
+        let invalid = Err(VaultError::InvalidDestinationAccount.into());
+        
+        assert_eq!(ctx.release(owner, 0, token_account(&mint, &owner, 0)), invalid);
+        let other_mint = token_account(&Pubkey::new_unique(), &ctx.depositor, 0);
+        assert_eq!(ctx.release(owner, 0, other_mint), Err(VaultError::TokenMintMismatch.into()));
+        let mut not_a_token_account = ctx.depositor_account();
+        not_a_token_account.owner = Pubkey::new_unique();
+        assert_eq!(ctx.release(owner, 0, not_a_token_account), invalid);
+        
+        // A locked destination is the only account a release may pay
+        let locked = ctx.depositor_account();
+        let mut vault = read_vault(&ctx.vault);
+        vault.deposits[0].locked_destination = Some(locked.key);
+        ctx.vault = vault_account(&ctx.program_id, &vault, 2000);
+        let unlocked_account = ctx.depositor_account();
+        assert_eq!(ctx.release(owner, 0, unlocked_account), invalid);
+        assert!(take_invocations().is_empty());
+        ctx.release(owner, 0, locked).unwrap();
+    }
+}
@@ -0,0 +1,119 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{AuthorityOnRenounce, DepositPolicy, Vault, VaultError, VaultInstruction};
+
+    const NOW: i64 = 1_700_000_000;
+
+    impl Harness {
+        fn with_emergency_authority(emergency_authority: Option<Pubkey>) -> Self {
+            let vault = Vault {
+                is_initialized: true,
+                owner: Pubkey::new_unique(),
+                emergency_authority,
+                ..Vault::default()
+            };
+            Self::new(&vault, 2000)
+        }
+        
+        // Run an owner-signed instruction, passing the vault token accounts the
+        // token-account fixes expect
+        fn as_owner(&mut self, instruction: VaultInstruction) -> ProgramResult {
+            let mut vault_token = token_account(&Pubkey::new_unique(), &self.vault.key, 0);
+            let mut token_program = token_program();
+            self.call(self.owner, &instruction.pack(), &mut [&mut vault_token, &mut token_program])
+        }
+        
+        fn renounce(&mut self, emergency_authority: Option<AuthorityOnRenounce>) -> ProgramResult {
+            self.as_owner(VaultInstruction::RenounceOwnership { emergency_authority })
+        }
+    }
+
+    fn owner_gated() -> Vec<VaultInstruction> {
+        let key = Pubkey::new_unique();
+        vec![
+            VaultInstruction::CloseVault,
+            VaultInstruction::SetPositionNfts { enabled: true, uri: String::new() },
+            VaultInstruction::SetEmergencyCooldown { cooldown: 60 },
+            VaultInstruction::SetRateLimit { rate_limit: None },
+            VaultInstruction::SetWithdrawDelay { delay: 60 },
+            VaultInstruction::SetEarlyWithdrawal { max_penalty_bps: 100, treasury: Some(key) },
+            VaultInstruction::SetPostUnlockGrace { grace: 60 },
+            VaultInstruction::SetDestinationChangeDelay { delay: 60 },
+            VaultInstruction::SetBeneficiaryChangeDelay { delay: 60 },
+            VaultInstruction::SetCrankBounty { bounty_bps: 10 },
+            VaultInstruction::SetDepositPolicy { policy: DepositPolicy::Allowlist },
+            VaultInstruction::AddAllowedDepositor { depositor: key },
+            VaultInstruction::RemoveAllowedDepositor { depositor: key },
+            VaultInstruction::BlockDepositor { depositor: key },
+            VaultInstruction::UnblockDepositor { depositor: key },
+            VaultInstruction::SetMintLimit { mint: key, limit: Some(1) },
+            VaultInstruction::SetTvlCap { cap: Some(1) },
+            VaultInstruction::SetDepositFee { lamports: 1, destination: key },
+            VaultInstruction::SetFreezeAuthority { authority: Some(key) },
+            VaultInstruction::RevokeVaultDelegate,
+            VaultInstruction::SetVaultTokenCloseAuthority,
+            VaultInstruction::SetMinLockSlots { min_slots: 10 },
+            VaultInstruction::SetPriceOracle { price_oracle: None },
+            VaultInstruction::RenounceOwnership { emergency_authority: None },
+            VaultInstruction::SetEmergencyAuthority { authority: Some(key) },
+        ]
+    }
+
+    #[test]
+    fn test_every_owner_instruction_rejected_after_renounce() {
+        let mut harness = Harness::with_emergency_authority(None);
+        harness.renounce(None).unwrap();
+        
+        assert!(read_vault(&harness.vault).renounced);
+        for instruction in owner_gated() {
+            let name = format!("{:?}", instruction);
+            assert_eq!(harness.as_owner(instruction), Err(VaultError::OwnershipRenounced.into()), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_emergency_authority_choice_required() {
+        let authority = Pubkey::new_unique();
+        let mut harness = Harness::with_emergency_authority(Some(authority));
+        
+        assert_eq!(harness.renounce(None), Err(VaultError::EmergencyAuthorityChoiceRequired.into()));
+        assert!(!read_vault(&harness.vault).renounced);
+        
+        harness.renounce(Some(AuthorityOnRenounce::Keep)).unwrap();
+        assert_eq!(read_vault(&harness.vault).emergency_authority, Some(authority));
+        
+        let mut harness = Harness::with_emergency_authority(Some(authority));
+        harness.renounce(Some(AuthorityOnRenounce::Clear)).unwrap();
+        let vault = read_vault(&harness.vault);
+        assert!(vault.renounced);
+        assert_eq!(vault.emergency_authority, None);
+    }
+
+    #[test]
+    fn test_depositors_unaffected() {
+        let mut harness = Harness::with_emergency_authority(None);
+        harness.renounce(None).unwrap();
+        
+        let data = VaultInstruction::Deposit {
+            amount: 100,
+            unlock_time: NOW + 10,
+            tag: [0; 32],
+            locked_destination: None,
+            no_crank: false,
+            emergency_requires_depositor: false,
+            owner_can_release_early: false,
+            idempotency_key: None,
+        }
+        .pack();
+        harness.deposit_with(&data, 100, (1, NOW), &mut []).unwrap();
+        
+        let data = VaultInstruction::Withdraw { deposit_id: 0 }.pack();
+        harness.withdraw_with(harness.depositor, &data, 100, (2, NOW + 10), &mut []).unwrap();
+        
+        assert!(read_vault(&harness.vault).deposits[0].is_withdrawn());
+    }
+}
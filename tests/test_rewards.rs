@@ -0,0 +1,216 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use rand::Rng;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        process_instruction, Deposit, DepositStatus, Vault, VaultError, VaultInstruction,
+        MAX_REWARD_DEPOSITS_PER_CALL,
+    };
+
+    struct RewardContext {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        reward_mint: Pubkey,
+        weight_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl RewardContext {
+        // One active weight mint deposit per amount, plus a deposit of
+        // another mint that never shares in rewards
+        fn new(amounts: &[u64]) -> Self {
+            let program_id = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let reward_mint = Pubkey::new_unique();
+            let weight_mint = Pubkey::new_unique();
+            let mut deposits: Vec<Deposit> = amounts
+                .iter()
+                .enumerate()
+                .map(|(id, &amount)| Deposit {
+                    id: id as u64,
+                    depositor,
+                    token_mint: weight_mint,
+                    amount,
+                    unlock_time: i64::MAX,
+                    ..Deposit::default()
+                })
+                .collect();
+            deposits.push(Deposit {
+                id: amounts.len() as u64,
+                depositor,
+                token_mint: Pubkey::new_unique(),
+                amount: 1_000,
+                unlock_time: i64::MAX,
+                ..Deposit::default()
+            });
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: deposits.len() as u64,
+                deposits,
+                reward_mint: Some(reward_mint),
+                reward_weight_mint: Some(weight_mint),
+                ..Vault::default()
+            };
+            let size = vault.try_to_vec().unwrap().len() + 1000;
+            Self { program_id, depositor, reward_mint, weight_mint, vault: vault_account(&program_id, &vault, size) }
+        }
+        
+        // Start a distribution of `total`, or continue one with zero
+        fn distribute(&mut self, total: u64) -> ProgramResult {
+            let caller_key = Pubkey::new_unique();
+            let mut caller = TestAccount::wallet(caller_key).signer();
+            let mut source = token_account(&self.reward_mint, &caller_key, total);
+            let mut vault_token = token_account(&self.reward_mint, &self.vault.key, 0);
+            let mut token_program = token_program();
+            let mut accounts = vec![caller.info(), self.vault.info()];
+            if total > 0 {
+                accounts.extend([source.info(), vault_token.info(), token_program.info()]);
+            }
+            let data = VaultInstruction::DistributeRewards { mint: self.reward_mint, total }.pack();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        // Distribute `total` to completion
+        fn distribute_all(&mut self, total: u64) {
+            self.distribute(total).unwrap();
+            while read_vault(&self.vault).reward_distribution.is_some() {
+                self.distribute(0).unwrap();
+            }
+        }
+        
+        fn claim(&mut self, claimant: Pubkey, deposit_id: u64) -> ProgramResult {
+            let mut claimant = TestAccount::wallet(claimant).signer();
+            let mut destination = token_account(&self.reward_mint, &self.depositor, 0);
+            let mut source = token_account(&self.reward_mint, &self.vault.key, u64::MAX);
+            let mut token_program = token_program();
+            let accounts = vec![
+                claimant.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+            ];
+            take_invocations();
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::ClaimRewards { deposit_id }.pack())
+        }
+        
+        fn update_vault(&mut self, update: impl FnOnce(&mut Vault)) {
+            let mut vault = read_vault(&self.vault);
+            update(&mut vault);
+            self.vault = vault_account(&self.program_id, &vault, self.vault.data.len());
+        }
+        
+        fn pending(&self) -> Vec<u64> {
+            read_vault(&self.vault).deposits.iter().map(|deposit| deposit.pending_rewards).collect()
+        }
+        
+        // Rewards allocated to deposits plus the carried dust
+        fn accounted(&self) -> u128 {
+            let vault = read_vault(&self.vault);
+            self.pending().iter().map(|&pending| pending as u128).sum::<u128>() + vault.reward_carry as u128
+        }
+    }
+
+    #[test]
+    fn test_rewards_are_pro_rata_with_dust_carried() {
+        let mut ctx = RewardContext::new(&[1, 2, 3]);
+        
+        ctx.distribute_all(100);
+        
+        // 100 * 1/6, 100 * 2/6 and 100 * 3/6, rounded down; the other mint gets nothing
+        assert_eq!(ctx.pending(), vec![16, 33, 50, 0]);
+        assert_eq!(read_vault(&ctx.vault).reward_carry, 1);
+        
+        // The dust joins the next distribution, which then divides evenly
+        ctx.distribute_all(5);
+        assert_eq!(ctx.pending(), vec![16 + 1, 33 + 2, 50 + 3, 0]);
+        assert_eq!(read_vault(&ctx.vault).reward_carry, 0);
+        assert_eq!(ctx.accounted(), 105);
+    }
+
+    #[test]
+    fn test_distribution_is_exact_for_adversarial_amounts() {
+        let cases: Vec<(Vec<u64>, u64)> = vec![
+            (vec![1, u64::MAX / 2], u64::MAX),
+            (vec![u64::MAX, u64::MAX, 1], u64::MAX - 1),
+            (vec![3; 7], 1),
+            (vec![1, 1, 1], 2),
+            (vec![999_999_937, 3, 5, 7, 11], 1_000_000_007),
+            (vec![1], 1),
+        ];
+        for (amounts, total) in cases {
+            let mut ctx = RewardContext::new(&amounts);
+            ctx.distribute_all(total);
+            assert_eq!(ctx.accounted(), total as u128, "amounts {:?}, total {}", amounts, total);
+        }
+        
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let count = rng.gen_range(1, 200);
+            let amounts: Vec<u64> = (0..count).map(|_| rng.gen_range(1, u64::MAX / 256)).collect();
+            let total = rng.gen_range(1, u64::MAX);
+            let mut ctx = RewardContext::new(&amounts);
+            ctx.distribute_all(total);
+            assert_eq!(ctx.accounted(), total as u128, "amounts {:?}, total {}", amounts, total);
+        }
+    }
+
+    #[test]
+    fn test_large_vaults_distribute_over_several_calls() {
+        const COUNT: usize = 2 * MAX_REWARD_DEPOSITS_PER_CALL + 10;
+        let mut ctx = RewardContext::new(&[10; COUNT]);
+        let weight_mint = ctx.weight_mint;
+        
+        ctx.distribute(COUNT as u64 * 1_000).unwrap();
+        assert_eq!(ctx.distribute(5), Err(VaultError::RewardDistributionInProgress.into()));
+        let distribution = read_vault(&ctx.vault).reward_distribution.unwrap();
+        assert_eq!(distribution.next_id, MAX_REWARD_DEPOSITS_PER_CALL as u64);
+        assert_eq!(distribution.distributed, MAX_REWARD_DEPOSITS_PER_CALL as u64 * 1_000);
+        
+        // A deposit withdrawn mid-way forfeits its share to the carry, and
+        // one made after the distribution started shares nothing
+        ctx.update_vault(|vault| {
+            vault.deposits[COUNT - 1].status = DepositStatus::Withdrawn as u8;
+            vault.deposits.push(Deposit {
+                id: vault.deposit_count,
+                token_mint: weight_mint,
+                amount: 10,
+                ..Deposit::default()
+            });
+            vault.deposit_count += 1;
+        });
+        ctx.distribute(0).unwrap();
+        ctx.distribute(0).unwrap();
+        assert_eq!(ctx.distribute(0), Err(VaultError::NoRewardDistribution.into()));
+        
+        let pending = ctx.pending();
+        assert!(pending[..COUNT - 1].iter().all(|&pending| pending == 1_000));
+        assert_eq!(pending[COUNT - 1..], [0, 0, 0]);
+        assert_eq!(read_vault(&ctx.vault).reward_carry, 1_000);
+        assert_eq!(ctx.accounted(), COUNT as u128 * 1_000);
+    }
+
+    #[test]
+    fn test_claim_pays_pending_rewards_once() {
+        let mut ctx = RewardContext::new(&[1, 3]);
+        let depositor = ctx.depositor;
+        assert_eq!(ctx.claim(depositor, 0), Err(VaultError::NoPendingRewards.into()));
+        ctx.distribute_all(400);
+        
+        assert_eq!(ctx.claim(Pubkey::new_unique(), 1), Err(VaultError::UnauthorizedWithdrawal.into()));
+        ctx.claim(depositor, 1).unwrap();
+        
+        assert_eq!(token_transfers(&take_invocations())[0].1, 300);
+        assert_eq!(ctx.pending(), vec![100, 0, 0]);
+        assert_eq!(ctx.claim(depositor, 1), Err(VaultError::NoPendingRewards.into()));
+        
+        // Rewards stay claimable once the deposit itself has been withdrawn
+        ctx.update_vault(|vault| vault.deposits[0].status = DepositStatus::Withdrawn as u8);
+        ctx.claim(depositor, 0).unwrap();
+        assert_eq!(token_transfers(&take_invocations())[0].1, 100);
+    }
+}
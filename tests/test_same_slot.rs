@@ -0,0 +1,139 @@
+mod common;
+
+use common::bank::{send, signing_vault, token_account, vault_account, vault_program_test, vault_state};
+use solana_program::{clock::Clock, instruction::InstructionError, pubkey::Pubkey};
+use solana_sdk::{signature::Signer, transaction::TransactionError};
+use time_locked_vault::{builder, Vault, VaultError};
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{process_instruction, Vault, VaultError, VaultInstruction};
+
+    const DEPOSIT_SLOT: u64 = 500;
+    const UNLOCK_TIME: i64 = 10;
+
+    struct SlotContext {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl SlotContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            Self {
+                program_id,
+                depositor: Pubkey::new_unique(),
+                token_mint: Pubkey::new_unique(),
+                vault: vault_account(&program_id, &Vault::default(), 1000),
+            }
+        }
+        
+        fn deposit(&mut self) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut source = token_account(&self.token_mint, &self.depositor, 1_000);
+            let mut vault_tokens = token_account(&self.token_mint, &self.vault.key, 0);
+            let mut token_program = token_program();
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(DEPOSIT_SLOT, 0);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                source.info(),
+                vault_tokens.info(),
+                token_program.info(),
+                system_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Deposit {
+                amount: 1_000,
+                unlock_time: UNLOCK_TIME,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .try_to_vec()
+            .unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn withdraw(&mut self, slot: u64) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut vault_tokens = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(slot, UNLOCK_TIME);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                vault_tokens.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Withdraw { deposit_id: 0 }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_withdraw_in_deposit_slot_rejected() {
+        let mut ctx = SlotContext::new();
+        ctx.deposit().unwrap();
+        assert_eq!(read_vault(&ctx.vault).deposits[0].last_action_slot, DEPOSIT_SLOT);
+        
+        let result = ctx.withdraw(DEPOSIT_SLOT);
+        
+        assert_eq!(result, Err(VaultError::SameSlotAction.into()));
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_withdraw_in_later_slot_succeeds() {
+        let mut ctx = SlotContext::new();
+        ctx.deposit().unwrap();
+        
+        ctx.withdraw(DEPOSIT_SLOT + 1).unwrap();
+        
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+}
+
+// A deposit and a withdraw of it sent in one transaction run in the same slot
+#[tokio::test]
+async fn test_deposit_and_withdraw_in_one_transaction_rejected() {
+    let vault = signing_vault();
+    let (mint, source, vault_tokens) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    let mut program_test = vault_program_test();
+    let state = Vault { is_initialized: true, owner: Pubkey::new_unique(), ..Vault::default() };
+    program_test.add_account(vault.pubkey(), vault_account(&state));
+    program_test.add_account(vault_tokens, token_account(&mint, &vault.pubkey(), 0));
+    let mut context = program_test.start_with_context().await;
+    context.warp_to_slot(10).unwrap();
+    let payer = context.payer.pubkey();
+    context.set_account(&source, &token_account(&mint, &payer, 100).into());
+
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let program_id = time_locked_vault::id();
+    let unlock_time = clock.unix_timestamp + 1;
+    let deposit =
+        builder::deposit(&program_id, &payer, &vault.pubkey(), &source, &vault_tokens, 100, unlock_time, [0; 32]);
+    let mut withdraw = builder::withdraw(&program_id, &payer, &vault.pubkey(), &source, &vault_tokens, 0);
+    withdraw.accounts[1].is_signer = true;
+
+    let result = send(&mut context, &[deposit, withdraw], &[&vault]).await;
+
+    assert_eq!(
+        result,
+        Err(TransactionError::InstructionError(1, InstructionError::Custom(VaultError::SameSlotAction as u32)))
+    );
+    assert!(vault_state(&mut context, vault.pubkey()).await.deposits.is_empty());
+}
@@ -0,0 +1,66 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::entrypoint::ProgramResult;
+    use time_locked_vault::{Vault, VaultError, VaultInstruction};
+
+    const DEPOSIT_SLOT: u64 = 100;
+    const UNLOCK_SLOT: u64 = 200;
+    const NOW: i64 = 1_700_000_000;
+
+    impl Harness {
+        fn with_min_lock_slots(min_lock_slots: u64) -> Self {
+            Self::new(&Vault { min_lock_slots, ..Vault::default() }, 1000)
+        }
+        
+        fn deposit(&mut self, unlock_slot: u64) -> ProgramResult {
+            let data = VaultInstruction::DepositUntilSlot { amount: 100, unlock_slot, tag: [0; 32] }.pack();
+            self.deposit_with(&data, 100, (DEPOSIT_SLOT, NOW), &mut [])
+        }
+        
+        fn withdraw_at(&mut self, slot: u64) -> ProgramResult {
+            // Timestamps don't hold slot-locked deposits: keep the clock at the deposit time
+            let data = VaultInstruction::Withdraw { deposit_id: 0 }.pack();
+            self.withdraw_with(self.depositor, &data, 100, (slot, NOW), &mut [])
+        }
+    }
+
+    #[test]
+    fn test_slot_lock_releases_at_unlock_slot() {
+        let mut harness = Harness::with_min_lock_slots(0);
+        harness.deposit(UNLOCK_SLOT).unwrap();
+        let deposit = &read_vault(&harness.vault).deposits[0];
+        assert_eq!((deposit.unlock_slot, deposit.unlock_time), (Some(UNLOCK_SLOT), NOW));
+        
+        let result = harness.withdraw_at(UNLOCK_SLOT - 1);
+        assert_eq!(result, Err(VaultError::UnlockTimeNotReached.into()));
+        assert!(!read_vault(&harness.vault).deposits[0].is_withdrawn());
+        
+        harness.withdraw_at(UNLOCK_SLOT).unwrap();
+        assert!(read_vault(&harness.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_unlock_slot_must_clear_minimum() {
+        let min_lock_slots = UNLOCK_SLOT - DEPOSIT_SLOT;
+        
+        for unlock_slot in [0, DEPOSIT_SLOT, UNLOCK_SLOT - 1] {
+            let mut harness = Harness::with_min_lock_slots(min_lock_slots);
+            let result = harness.deposit(unlock_slot);
+            assert_eq!(result, Err(VaultError::InvalidUnlockTime.into()), "unlock slot {}", unlock_slot);
+        }
+        
+        let mut harness = Harness::with_min_lock_slots(min_lock_slots);
+        harness.deposit(UNLOCK_SLOT).unwrap();
+    }
+
+    #[test]
+    fn test_unlock_slot_in_future_without_minimum() {
+        let mut harness = Harness::with_min_lock_slots(0);
+        assert_eq!(harness.deposit(DEPOSIT_SLOT), Err(VaultError::InvalidUnlockTime.into()));
+        
+        harness.deposit(DEPOSIT_SLOT + 1).unwrap();
+    }
+}
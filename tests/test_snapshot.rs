@@ -0,0 +1,165 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey, system_program};
+    use time_locked_vault::{
+        find_snapshot_address, process_instruction, Deposit, DepositStatus, MintEntry, Vault, VaultError,
+        VaultInstruction, VaultSnapshot, MAX_SNAPSHOT_SCAN, MAX_VAULT_MINTS,
+    };
+
+    const SLOT: u64 = 42;
+    const NOW: i64 = 1_700_000_000;
+
+    struct SnapshotContext {
+        program_id: Pubkey,
+        vault: TestAccount,
+        snapshot: TestAccount,
+    }
+
+    impl SnapshotContext {
+        fn new(vault: &Vault) -> Self {
+            let program_id = Pubkey::new_unique();
+            let vault = vault_account(&program_id, vault, vault.try_to_vec().unwrap().len());
+            let snapshot_key = find_snapshot_address(&vault.key, &program_id).0;
+            let snapshot = TestAccount::new(snapshot_key, system_program::id(), vec![0; VaultSnapshot::LEN]).writable();
+            Self { program_id, vault, snapshot }
+        }
+        
+        fn write(&mut self) -> ProgramResult {
+            set_clock(SLOT, NOW);
+            let mut caller = TestAccount::wallet(Pubkey::new_unique()).signer().writable();
+            let mut system_program = system_program_account();
+            let accounts = vec![caller.info(), self.vault.info(), self.snapshot.info(), system_program.info()];
+            take_invocations();
+            let result = process_instruction(&self.program_id, &accounts, &VaultInstruction::WriteSnapshot.pack());
+            // The stubbed system program doesn't run, so assign the account
+            // the way `create_account` would have
+            if result.is_ok() {
+                self.snapshot.owner = self.program_id;
+            }
+            result
+        }
+        
+        fn read(&self) -> VaultSnapshot {
+            VaultSnapshot::deserialize(&mut self.snapshot.data.as_slice()).unwrap()
+        }
+    }
+
+    // A vault of random deposits, some withdrawn and some slot-locked, with
+    // counters filled in as the handlers would have
+    fn random_vault(rng: &mut StdRng) -> Vault {
+        let mints: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let mut vault = Vault::default();
+        for id in 0..rng.gen_range(0, 40) {
+            let deposit = Deposit {
+                id,
+                token_mint: mints[rng.gen_range(0, mints.len())],
+                amount: rng.gen_range(1, 1_000),
+                unlock_time: NOW + rng.gen_range(-1_000, 1_000),
+                unlock_slot: if rng.gen_bool(0.2) { Some(SLOT + 100) } else { None },
+                status: if rng.gen_bool(0.3) { DepositStatus::Withdrawn as u8 } else { DepositStatus::Active as u8 },
+                ..Deposit::default()
+            };
+            vault.lifetime_deposits_created += 1;
+            vault.lifetime_amount_deposited += deposit.amount as u128;
+            if deposit.is_withdrawn() {
+                vault.lifetime_amount_withdrawn += deposit.amount as u128;
+            } else {
+                vault.total_locked += deposit.amount;
+                match vault.mints.iter_mut().find(|entry| entry.mint == deposit.token_mint) {
+                    Some(entry) => entry.outstanding += deposit.amount,
+                    None => vault.mints.push(MintEntry {
+                        mint: deposit.token_mint,
+                        outstanding: deposit.amount,
+                        ..MintEntry::default()
+                    }),
+                }
+            }
+            vault.deposits.push(deposit);
+        }
+        vault.deposit_count = vault.deposits.len() as u64;
+        vault
+    }
+
+    #[test]
+    fn test_snapshot_matches_vault() {
+        for seed in 0..8 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let vault = random_vault(&mut rng);
+            let mut ctx = SnapshotContext::new(&vault);
+            
+            ctx.write().unwrap();
+            
+            let active: Vec<&Deposit> = vault.deposits.iter().filter(|d| !d.is_withdrawn()).collect();
+            let unlocks: Vec<i64> = active.iter().filter(|d| d.unlock_slot.is_none()).map(|d| d.unlock_time).collect();
+            let mut outstanding: Vec<(Pubkey, u64)> = Vec::new();
+            for deposit in &active {
+                match outstanding.iter_mut().find(|(mint, _)| *mint == deposit.token_mint) {
+                    Some((_, amount)) => *amount += deposit.amount,
+                    None => outstanding.push((deposit.token_mint, deposit.amount)),
+                }
+            }
+            let snapshot = ctx.read();
+            assert_eq!(snapshot.vault, ctx.vault.key);
+            assert_eq!((snapshot.slot, snapshot.unix_timestamp), (SLOT, NOW));
+            assert_eq!(snapshot.active_deposits, active.len() as u64);
+            assert_eq!(snapshot.earliest_unlock, unlocks.iter().copied().min());
+            assert_eq!(snapshot.latest_unlock, unlocks.iter().copied().max());
+            assert_eq!(snapshot.total_locked, active.iter().map(|d| d.amount).sum::<u64>());
+            assert_eq!(snapshot.outstanding, outstanding);
+            assert_eq!(snapshot.lifetime_deposits_created, vault.deposits.len() as u64);
+            assert_eq!(
+                snapshot.lifetime_amount_deposited - snapshot.lifetime_amount_withdrawn,
+                snapshot.total_locked as u128,
+            );
+        }
+    }
+
+    #[test]
+    fn test_snapshot_created_once_then_overwritten() {
+        let mut ctx = SnapshotContext::new(&random_vault(&mut StdRng::seed_from_u64(1)));
+        
+        ctx.write().unwrap();
+        assert_eq!(take_invocations().len(), 1);
+        
+        // An empty vault overwrites every aggregate without creating anything
+        let empty = Vault { is_initialized: true, ..Vault::default() };
+        empty.serialize(&mut ctx.vault.data.as_mut_slice()).unwrap();
+        ctx.write().unwrap();
+        assert!(take_invocations().is_empty());
+        assert_eq!(ctx.read(), VaultSnapshot {
+            vault: ctx.vault.key,
+            slot: SLOT,
+            unix_timestamp: NOW,
+            ..VaultSnapshot::default()
+        });
+    }
+
+    #[test]
+    fn test_full_snapshot_fits_and_oversized_vault_fails() {
+        let full = VaultSnapshot {
+            earliest_unlock: Some(0),
+            latest_unlock: Some(0),
+            outstanding: vec![(Pubkey::default(), 0); MAX_VAULT_MINTS],
+            ..VaultSnapshot::default()
+        };
+        assert_eq!(full.try_to_vec().unwrap().len(), VaultSnapshot::LEN);
+        
+        let vault = Vault { deposits: vec![Deposit::default(); MAX_SNAPSHOT_SCAN + 1], ..Vault::default() };
+        let mut ctx = SnapshotContext::new(&vault);
+        assert_eq!(ctx.write(), Err(VaultError::VaultTooLargeToScan.into()));
+        assert!(take_invocations().is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_address_is_checked() {
+        let mut ctx = SnapshotContext::new(&Vault::default());
+        ctx.snapshot.key = Pubkey::new_unique();
+        
+        assert_eq!(ctx.write(), Err(ProgramError::InvalidSeeds));
+    }
+}
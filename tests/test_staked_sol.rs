@@ -0,0 +1,206 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        rent::Rent,
+        stake::{
+            self,
+            instruction as stake_instruction,
+            state::{Authorized, Lockup, StakeState},
+        },
+        system_instruction, sysvar, vote,
+    };
+    use time_locked_vault::{
+        find_stake_account_address, find_stake_authority_address, process_instruction, Vault, VaultError,
+        VaultInstruction,
+    };
+
+    const NOW: i64 = 1_000;
+    const UNLOCK: i64 = NOW + 100;
+    const AMOUNT: u64 = 5_000_000_000;
+
+    struct StakeContext {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        validator_vote: Pubkey,
+        vault: TestAccount,
+        stake_account: TestAccount,
+    }
+
+    impl StakeContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let vault = vault_account(&program_id, &Vault { owner: Pubkey::new_unique(), ..Vault::default() }, 2000);
+            let stake_key = find_stake_account_address(&vault.key, 0, &program_id);
+            Self {
+                program_id,
+                depositor: Pubkey::new_unique(),
+                validator_vote: Pubkey::new_unique(),
+                vault,
+                stake_account: TestAccount::new(stake_key, stake::program::id(), vec![]).writable(),
+            }
+        }
+        
+        fn authority(&self) -> Pubkey {
+            find_stake_authority_address(&self.vault.key, &self.program_id).0
+        }
+        
+        fn deposit(&mut self, vote_owner: Pubkey) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer().writable().with_lamports(2 * AMOUNT);
+            let mut authority = TestAccount::wallet(self.authority());
+            let mut vote_account = TestAccount::new(self.validator_vote, vote_owner, vec![]);
+            let mut stake_program = TestAccount::new(stake::program::id(), Pubkey::default(), vec![]);
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(1, NOW);
+            let mut rent = TestAccount::new(sysvar::rent::id(), sysvar::id(), vec![]);
+            let mut stake_history = TestAccount::new(sysvar::stake_history::id(), sysvar::id(), vec![]);
+            let mut stake_config = TestAccount::new(Pubkey::new_unique(), Pubkey::default(), vec![]);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                self.stake_account.info(),
+                authority.info(),
+                vote_account.info(),
+                stake_program.info(),
+                system_program.info(),
+                clock.info(),
+                rent.info(),
+                stake_history.info(),
+                stake_config.info(),
+            ];
+            take_invocations();
+            let data = VaultInstruction::DepositStakedSol {
+                amount: AMOUNT,
+                unlock_time: UNLOCK,
+                validator_vote: self.validator_vote,
+                tag: [0; 32],
+            }
+            .pack();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        // Send `DeactivateStake` or `WithdrawStake` for deposit 0 at `now`
+        fn send(&mut self, signer: Pubkey, instruction: VaultInstruction, now: i64) -> ProgramResult {
+            let mut signer = TestAccount::wallet(signer).signer().writable();
+            let mut authority = TestAccount::wallet(self.authority());
+            let mut stake_program = TestAccount::new(stake::program::id(), Pubkey::default(), vec![]);
+            let mut clock = clock_account(2, now);
+            let mut stake_history = TestAccount::new(sysvar::stake_history::id(), sysvar::id(), vec![]);
+            let mut accounts = vec![
+                signer.info(),
+                self.vault.info(),
+                self.stake_account.info(),
+                authority.info(),
+                stake_program.info(),
+                clock.info(),
+            ];
+            if let VaultInstruction::WithdrawStake { .. } = instruction {
+                accounts.push(stake_history.info());
+            }
+            take_invocations();
+            process_instruction(&self.program_id, &accounts, &instruction.pack())
+        }
+    }
+
+    #[test]
+    fn test_deposit_creates_and_delegates_stake() {
+        let mut ctx = StakeContext::new();
+        
+        ctx.deposit(vote::program::id()).unwrap();
+        
+        let (stake_key, authority) = (ctx.stake_account.key, ctx.authority());
+        let lamports = Rent::default().minimum_balance(StakeState::size_of()) + AMOUNT;
+        let authorized = Authorized { staker: authority, withdrawer: authority };
+        assert_eq!(take_invocations(), vec![
+            system_instruction::create_account_with_seed(
+                &ctx.depositor,
+                &stake_key,
+                &authority,
+                "deposit-0",
+                lamports,
+                StakeState::size_of() as u64,
+                &stake::program::id(),
+            ),
+            stake_instruction::initialize(&stake_key, &authorized, &Lockup::default()),
+            stake_instruction::delegate_stake(&stake_key, &authority, &ctx.validator_vote),
+        ]);
+        let vault = read_vault(&ctx.vault);
+        let deposit = &vault.deposits[0];
+        assert_eq!(deposit.stake_account, Some(stake_key));
+        assert_eq!((deposit.token_mint, deposit.amount, deposit.unlock_time), (stake::program::id(), AMOUNT, UNLOCK));
+        assert_eq!(vault.total_locked, AMOUNT);
+    }
+
+    #[test]
+    fn test_deposit_checks_vote_and_stake_accounts() {
+        let mut ctx = StakeContext::new();
+        assert_eq!(ctx.deposit(Pubkey::new_unique()), Err(ProgramError::InvalidAccountData));
+        
+        ctx.stake_account.key = find_stake_account_address(&ctx.vault.key, 1, &ctx.program_id);
+        assert_eq!(ctx.deposit(vote::program::id()), Err(VaultError::InvalidStakeAccount.into()));
+        
+        assert!(take_invocations().is_empty());
+        assert!(read_vault(&ctx.vault).deposits.is_empty());
+    }
+
+    #[test]
+    fn test_deactivate_then_withdraw_after_unlock() {
+        let mut ctx = StakeContext::new();
+        ctx.deposit(vote::program::id()).unwrap();
+        let (depositor, stake_key, authority) = (ctx.depositor, ctx.stake_account.key, ctx.authority());
+        let deactivate = VaultInstruction::DeactivateStake { deposit_id: 0 };
+        let withdraw = VaultInstruction::WithdrawStake { deposit_id: 0 };
+        let locked = Err(VaultError::UnlockTimeNotReached.into());
+        
+        assert_eq!(ctx.send(depositor, deactivate.clone(), UNLOCK - 1), locked);
+        assert_eq!(ctx.send(depositor, withdraw.clone(), UNLOCK - 1), locked);
+        assert_eq!(
+            ctx.send(Pubkey::new_unique(), deactivate.clone(), UNLOCK),
+            Err(VaultError::UnauthorizedWithdrawal.into()),
+        );
+        
+        ctx.send(depositor, deactivate, UNLOCK).unwrap();
+        assert_eq!(take_invocations(), vec![stake_instruction::deactivate_stake(&stake_key, &authority)]);
+        
+        // Rewards accrued on top of the principal go to the depositor too
+        ctx.stake_account.lamports = AMOUNT + 123_456;
+        ctx.send(depositor, withdraw.clone(), UNLOCK + 10).unwrap();
+        assert_eq!(
+            take_invocations(),
+            vec![stake_instruction::withdraw(&stake_key, &authority, &depositor, AMOUNT + 123_456, None)],
+        );
+        let vault = read_vault(&ctx.vault);
+        assert!(vault.deposits[0].is_withdrawn());
+        assert_eq!(vault.total_locked, 0);
+        assert_eq!(ctx.send(depositor, withdraw, UNLOCK + 10), Err(VaultError::AlreadyWithdrawn.into()));
+    }
+
+    #[test]
+    fn test_token_withdrawals_reject_staked_deposits() {
+        let mut ctx = StakeContext::new();
+        ctx.deposit(vote::program::id()).unwrap();
+        let mint = Pubkey::new_unique();
+        
+        let mut depositor = TestAccount::wallet(ctx.depositor).signer();
+        let mut destination = token_account(&mint, &ctx.depositor, 0);
+        let mut source = token_account(&mint, &ctx.vault.key, AMOUNT);
+        let mut token_program = token_program();
+        let mut clock = clock_account(2, UNLOCK);
+        let accounts = vec![
+            depositor.info(),
+            ctx.vault.info(),
+            destination.info(),
+            source.info(),
+            token_program.info(),
+            clock.info(),
+        ];
+        let data = VaultInstruction::Withdraw { deposit_id: 0 }.pack();
+        
+        assert_eq!(process_instruction(&ctx.program_id, &accounts, &data), Err(VaultError::StakedDeposit.into()));
+    }
+}
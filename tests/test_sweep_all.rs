@@ -0,0 +1,204 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use spl_associated_token_account::get_associated_token_address;
+    use time_locked_vault::{
+        process_instruction, Deposit, DepositStatus, Vault, VaultError, VaultInstruction, SWEEP_ALL_DELAY,
+    };
+
+    const NOW: i64 = 1_000_000;
+
+    struct SweepContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        authority: Pubkey,
+        depositor: Pubkey,
+        emergency_destination: Pubkey,
+        mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl SweepContext {
+        // Deposits 0 (matured) and 1 of `mint`, and deposit 2 of another mint
+        fn new() -> Self {
+            Self::with_grace(0)
+        }
+        
+        fn with_grace(post_unlock_grace: i64) -> Self {
+            let program_id = Pubkey::new_unique();
+            let owner = Pubkey::new_unique();
+            let authority = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let emergency_destination = Pubkey::new_unique();
+            let mint = Pubkey::new_unique();
+            let deposit = |id, token_mint, unlock_time| Deposit {
+                id,
+                depositor,
+                token_mint,
+                amount: 100,
+                unlock_time,
+                ..Deposit::default()
+            };
+            let vault = Vault {
+                owner,
+                emergency_authority: Some(authority),
+                emergency_destination: Some(emergency_destination),
+                deposit_count: 3,
+                deposits: vec![
+                    deposit(0, mint, NOW - 1),
+                    deposit(1, mint, i64::MAX),
+                    deposit(2, Pubkey::new_unique(), i64::MAX),
+                ],
+                total_locked: 300,
+                post_unlock_grace,
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                owner,
+                authority,
+                depositor,
+                emergency_destination,
+                mint,
+                vault: vault_account(&program_id, &vault, 4000),
+            }
+        }
+        
+        fn announce(&mut self, authority: Pubkey, owner: Pubkey) -> ProgramResult {
+            set_clock(0, NOW);
+            let mut authority = TestAccount::wallet(authority).signer();
+            let mut owner = TestAccount::wallet(owner).signer();
+            let accounts = vec![authority.info(), owner.info(), self.vault.info()];
+            take_events();
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::AnnounceSweepAll.pack())
+        }
+        
+        // Sweep the mint at `now` from a vault token account holding `balance`
+        fn execute(&mut self, now: i64, balance: u64) -> ProgramResult {
+            set_clock(0, now);
+            let destination_key = get_associated_token_address(&self.emergency_destination, &self.mint);
+            let mut authority = TestAccount::wallet(self.authority).signer();
+            let mut source = token_account(&self.mint, &self.vault.key, balance);
+            let mut destination = token_account(&self.mint, &self.emergency_destination, 0);
+            destination.key = destination_key;
+            let mut token_program = token_program();
+            let accounts = vec![
+                authority.info(),
+                self.vault.info(),
+                source.info(),
+                destination.info(),
+                token_program.info(),
+            ];
+            take_invocations();
+            let data = VaultInstruction::ExecuteSweepAll { mint: self.mint }.pack();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn withdraw(&mut self, deposit_id: u64) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut destination = token_account(&self.mint, &self.depositor, 0);
+            let mut source = token_account(&self.mint, &self.vault.key, 200);
+            let mut token_program = token_program();
+            let mut clock = clock_account(1, NOW + 1);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::Withdraw { deposit_id }.pack())
+        }
+        
+        fn statuses(&self) -> Vec<u8> {
+            read_vault(&self.vault).deposits.iter().map(|deposit| deposit.status).collect()
+        }
+    }
+
+    #[test]
+    fn test_sweep_waits_for_the_announcement_delay() {
+        let mut ctx = SweepContext::new();
+        let (authority, owner) = (ctx.authority, ctx.owner);
+        
+        assert_eq!(ctx.execute(NOW, 200), Err(VaultError::SweepNotAnnounced.into()));
+        // Neither the emergency authority nor the owner can announce alone
+        assert_eq!(ctx.announce(authority, Pubkey::new_unique()), Err(VaultError::NotVaultOwner.into()));
+        assert_eq!(ctx.announce(owner, owner), Err(VaultError::UnauthorizedWithdrawal.into()));
+        
+        ctx.announce(authority, owner).unwrap();
+        let events = take_events();
+        assert_eq!(events[0][0], b"sweep_all_announced".to_vec());
+        assert_eq!(events[0][2], (NOW + SWEEP_ALL_DELAY).to_le_bytes().to_vec());
+        let vault = read_vault(&ctx.vault);
+        assert_eq!(vault.sweep_announced_at, Some(NOW));
+        assert!(!vault.accepting_deposits());
+        assert_eq!(ctx.announce(authority, owner), Err(VaultError::SweepAlreadyAnnounced.into()));
+        
+        assert_eq!(ctx.execute(NOW + SWEEP_ALL_DELAY - 1, 200), Err(VaultError::SweepDelayNotElapsed.into()));
+        assert!(take_invocations().is_empty());
+        ctx.execute(NOW + SWEEP_ALL_DELAY, 200).unwrap();
+        assert_eq!(token_transfers(&take_invocations())[0].1, 200);
+    }
+
+    #[test]
+    fn test_withdrawal_during_window_is_paid_first() {
+        let mut ctx = SweepContext::new();
+        let (authority, owner) = (ctx.authority, ctx.owner);
+        ctx.announce(authority, owner).unwrap();
+        
+        ctx.withdraw(0).unwrap();
+        // The sweep takes only the 100 tokens left behind by the withdrawal
+        ctx.execute(NOW + SWEEP_ALL_DELAY, 100).unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers, vec![(get_associated_token_address(&ctx.emergency_destination, &ctx.mint), 100)]);
+        assert_eq!(ctx.statuses(), vec![
+            DepositStatus::Withdrawn as u8,
+            DepositStatus::Swept as u8,
+            DepositStatus::Active as u8,
+        ]);
+        let vault = read_vault(&ctx.vault);
+        assert_eq!(vault.deposits[1].withdrawn_at, Some(NOW + SWEEP_ALL_DELAY));
+        assert_eq!(vault.total_locked, 100);
+        assert_eq!(ctx.withdraw(1), Err(VaultError::AlreadyWithdrawn.into()));
+    }
+
+    #[test]
+    fn test_sweep_pays_only_the_emergency_destination() {
+        let mut ctx = SweepContext::new();
+        let (authority, owner) = (ctx.authority, ctx.owner);
+        ctx.announce(authority, owner).unwrap();
+        ctx.emergency_destination = Pubkey::new_unique();
+        
+        assert_eq!(ctx.execute(NOW + SWEEP_ALL_DELAY, 200), Err(VaultError::InvalidDestinationAccount.into()));
+        
+        ctx.authority = Pubkey::new_unique();
+        assert_eq!(ctx.execute(NOW + SWEEP_ALL_DELAY, 200), Err(VaultError::UnauthorizedWithdrawal.into()));
+        assert_eq!(ctx.statuses(), vec![DepositStatus::Active as u8; 3]);
+    }
+
+    #[test]
+    fn test_sweep_leaves_deposits_in_their_grace_period() {
+        let mut ctx = SweepContext::with_grace(2 * SWEEP_ALL_DELAY);
+        let (authority, owner) = (ctx.authority, ctx.owner);
+        ctx.announce(authority, owner).unwrap();
+        
+        // Deposit 0 matured just before the announcement, so its grace period
+        // outlasts the delay and only deposit 1's 100 tokens are swept
+        ctx.execute(NOW + SWEEP_ALL_DELAY, 200).unwrap();
+        
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers, vec![(get_associated_token_address(&ctx.emergency_destination, &ctx.mint), 100)]);
+        assert_eq!(ctx.statuses(), vec![
+            DepositStatus::Active as u8,
+            DepositStatus::Swept as u8,
+            DepositStatus::Active as u8,
+        ]);
+        assert_eq!(read_vault(&ctx.vault).total_locked, 200);
+        ctx.withdraw(0).unwrap();
+    }
+}
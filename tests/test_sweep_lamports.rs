@@ -0,0 +1,60 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey, rent::Rent};
+    use time_locked_vault::{process_instruction, Vault, VaultError, VaultInstruction};
+
+    const VAULT_SIZE: usize = 1000;
+
+    fn sweep(
+        program_id: &Pubkey,
+        signer: Pubkey,
+        vault: &mut TestAccount,
+        destination: &mut TestAccount,
+    ) -> ProgramResult {
+        set_clock(0, 0);
+        let mut owner = TestAccount::wallet(signer).signer();
+        let accounts = vec![owner.info(), vault.info(), destination.info()];
+        process_instruction(program_id, &accounts, &VaultInstruction::SweepLamports.pack())
+    }
+
+    #[test]
+    fn test_excess_swept_down_to_rent_minimum() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let rent_minimum = Rent::default().minimum_balance(VAULT_SIZE);
+        let mut vault = vault_account(&program_id, &Vault { owner, ..Vault::default() }, VAULT_SIZE)
+            .with_lamports(rent_minimum + 12_345);
+        let mut destination = TestAccount::wallet(Pubkey::new_unique()).writable().with_lamports(5);
+
+        sweep(&program_id, owner, &mut vault, &mut destination).unwrap();
+        assert_eq!((vault.lamports, destination.lamports), (rent_minimum, 12_350));
+
+        // Nothing left above the floor: a no-op
+        sweep(&program_id, owner, &mut vault, &mut destination).unwrap();
+        assert_eq!((vault.lamports, destination.lamports), (rent_minimum, 12_350));
+    }
+
+    #[test]
+    fn test_sweep_rejects_non_owner_and_vault_destination() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let lamports = Rent::default().minimum_balance(VAULT_SIZE) + 100;
+        let mut vault = vault_account(&program_id, &Vault { owner, ..Vault::default() }, VAULT_SIZE)
+            .with_lamports(lamports);
+        let mut destination = TestAccount::wallet(Pubkey::new_unique()).writable();
+
+        assert_eq!(
+            sweep(&program_id, Pubkey::new_unique(), &mut vault, &mut destination),
+            Err(VaultError::NotVaultOwner.into())
+        );
+        let mut vault_as_destination = TestAccount::new(vault.key, program_id, vec![]).writable();
+        assert_eq!(
+            sweep(&program_id, owner, &mut vault, &mut vault_as_destination),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(vault.lamports, lamports);
+    }
+}
@@ -0,0 +1,101 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        process_instruction, Deposit, DepositStatus, MintEntry, Vault, VaultError, VaultInstruction,
+    };
+
+    struct SweepContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        mint: Pubkey,
+        vault: TestAccount,
+        vault_tokens: TestAccount,
+    }
+
+    impl SweepContext {
+        // A vault holding 500 tokens of `mint` for active deposits, 200 of
+        // them in a deposit made before per-mint accounting, plus a withdrawn
+        // deposit and an active deposit of another mint
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let owner = Pubkey::new_unique();
+            let mint = Pubkey::new_unique();
+            let deposit = |id, token_mint, amount, withdrawn| Deposit {
+                id,
+                token_mint,
+                amount,
+                status: if withdrawn { DepositStatus::Withdrawn as u8 } else { DepositStatus::Active as u8 },
+                ..Deposit::default()
+            };
+            let vault = Vault {
+                owner,
+                deposit_count: 4,
+                deposits: vec![
+                    deposit(0, mint, 200, false),
+                    deposit(1, mint, 300, false),
+                    deposit(2, mint, 100, true),
+                    deposit(3, Pubkey::new_unique(), 900, false),
+                ],
+                mints: vec![MintEntry { mint, outstanding: 300, ..MintEntry::default() }],
+                ..Vault::default()
+            };
+            let vault = vault_account(&program_id, &vault, 2000);
+            let vault_tokens = token_account(&mint, &vault.key, 500);
+            Self { program_id, owner, mint, vault, vault_tokens }
+        }
+
+        fn sweep(&mut self, signer: Pubkey, mint: Pubkey) -> ProgramResult {
+            let mut owner = TestAccount::wallet(signer).signer();
+            let mut destination = token_account(&self.mint, &signer, 0);
+            let mut token_program = token_program();
+            let accounts = vec![
+                owner.info(),
+                self.vault.info(),
+                self.vault_tokens.info(),
+                destination.info(),
+                token_program.info(),
+            ];
+            take_invocations();
+            take_events();
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::SweepSurplus { mint }.pack())
+        }
+    }
+
+    #[test]
+    fn test_direct_transfer_swept_exactly() {
+        let mut ctx = SweepContext::new();
+        // A direct SPL transfer of 70 tokens into the vault's token account
+        update_token_account(&mut ctx.vault_tokens, |account| account.amount += 70);
+
+        ctx.sweep(ctx.owner, ctx.mint).unwrap();
+
+        let transfers = token_transfers(&take_invocations());
+        assert_eq!(transfers.iter().map(|(_, amount)| *amount).collect::<Vec<_>>(), vec![70]);
+        let sweep = vec![b"sweep".to_vec(), ctx.mint.to_bytes().to_vec(), 70u64.to_le_bytes().to_vec()];
+        assert_eq!(take_events(), vec![sweep]);
+    }
+
+    #[test]
+    fn test_deposits_never_swept() {
+        let mut ctx = SweepContext::new();
+        assert_eq!(ctx.sweep(ctx.owner, ctx.mint), Err(VaultError::NoSurplus.into()));
+
+        update_token_account(&mut ctx.vault_tokens, |account| account.amount = 400);
+        assert_eq!(ctx.sweep(ctx.owner, ctx.mint), Err(VaultError::NoSurplus.into()));
+        assert!(take_invocations().is_empty());
+    }
+
+    #[test]
+    fn test_sweep_checks_owner_and_mint() {
+        let mut ctx = SweepContext::new();
+        update_token_account(&mut ctx.vault_tokens, |account| account.amount += 70);
+
+        assert_eq!(ctx.sweep(Pubkey::new_unique(), ctx.mint), Err(VaultError::NotVaultOwner.into()));
+        assert_eq!(ctx.sweep(ctx.owner, Pubkey::new_unique()), Err(VaultError::TokenMintMismatch.into()));
+        assert!(take_invocations().is_empty());
+    }
+}
@@ -0,0 +1,23 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use time_locked_vault::{
+    test_utils::{synthetic_vault, SYNTHETIC_VAULT_SIZES},
+    Vault,
+};
+
+#[test]
+fn test_synthetic_vaults_round_trip() {
+    for &size in SYNTHETIC_VAULT_SIZES {
+        let vault = synthetic_vault(size);
+        let data = vault.try_to_vec().unwrap();
+        assert_eq!(Vault::try_from_slice(&data).unwrap().try_to_vec().unwrap(), data);
+        assert_eq!(vault.deposits.len(), size);
+    }
+}
+
+#[test]
+fn test_synthetic_deposits_are_in_id_order() {
+    let vault = synthetic_vault(1_000);
+    assert!(vault.deposits.windows(2).all(|pair| pair[0].id < pair[1].id));
+    assert_eq!(vault.deposits.binary_search_by_key(&999, |deposit| deposit.id), Ok(999));
+    assert_eq!(synthetic_vault(1_000).try_to_vec().unwrap(), vault.try_to_vec().unwrap());
+}
@@ -0,0 +1,78 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+    use time_locked_vault::{process_instruction, Vault, VaultConfig, VaultInstruction};
+
+    fn bogus_program() -> TestAccount {
+        TestAccount::new(Pubkey::new_unique(), Pubkey::default(), vec![])
+    }
+
+    #[test]
+    fn test_create_vault_rejects_bogus_system_program() {
+        let program_id = Pubkey::new_unique();
+        let mut owner = TestAccount::wallet(Pubkey::new_unique()).signer().writable();
+        let mut vault = TestAccount::new(Pubkey::new_unique(), program_id, vec![0; 1000]).writable();
+        let mut system_program = bogus_program();
+        let accounts = vec![owner.info(), vault.info(), system_program.info()];
+        let data = VaultInstruction::CreateVault {
+            skip_registry: true,
+            arbitration_destination: None,
+            emergency_destination: None,
+            owner_set: None,
+            config: VaultConfig::default(),
+        }
+        .try_to_vec()
+        .unwrap();
+        
+        let result = process_instruction(&program_id, &accounts, &data);
+        
+        assert_eq!(result, Err(ProgramError::IncorrectProgramId));
+        drop(accounts);
+        assert!(vault.data.iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_deposit_rejects_bogus_system_program() {
+        let program_id = Pubkey::new_unique();
+        let depositor_key = Pubkey::new_unique();
+        let token_mint = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault::default(), 1000);
+        let mut depositor = TestAccount::wallet(depositor_key).signer();
+        let mut source = token_account(&token_mint, &depositor_key, 1_000);
+        let mut vault_tokens = token_account(&token_mint, &vault.key, 0);
+        let mut token_program = token_program();
+        let mut system_program = bogus_program();
+        let mut clock = clock_account(0, 0);
+        let accounts = vec![
+            depositor.info(),
+            vault.info(),
+            source.info(),
+            vault_tokens.info(),
+            token_program.info(),
+            system_program.info(),
+            clock.info(),
+        ];
+        let data = VaultInstruction::Deposit {
+            amount: 1_000,
+            unlock_time: 1_000,
+            tag: [0; 32],
+            locked_destination: None,
+            no_crank: false,
+            emergency_requires_depositor: false,
+            owner_can_release_early: false,
+            idempotency_key: None,
+        }
+        .try_to_vec()
+        .unwrap();
+        
+        let result = process_instruction(&program_id, &accounts, &data);
+        
+        assert_eq!(result, Err(ProgramError::IncorrectProgramId));
+        drop(accounts);
+        assert!(read_vault(&vault).deposits.is_empty());
+    }
+}
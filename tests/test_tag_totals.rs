@@ -0,0 +1,95 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::collections::HashMap;
+    use time_locked_vault::{Vault, VaultInstruction, MAX_TRACKED_TAGS};
+
+    const NOW: i64 = 1_700_000_000;
+
+    impl Harness {
+        fn empty() -> Self {
+            Self::new(&Vault::default(), 100_000)
+        }
+        
+        fn deposit(&mut self, amount: u64, tag: [u8; 32]) {
+            let data = VaultInstruction::Deposit {
+                amount,
+                unlock_time: NOW + 10,
+                tag,
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .pack();
+            self.deposit_with(&data, amount, (1, NOW), &mut []).unwrap();
+        }
+        
+        fn withdraw(&mut self, deposit_id: u64) {
+            let data = VaultInstruction::Withdraw { deposit_id }.pack();
+            self.withdraw_with(self.depositor, &data, u64::MAX, (2, NOW + 100), &mut []).unwrap();
+        }
+    }
+
+    // Recompute the per-tag totals from the deposits and compare them with the
+    // vault's counters
+    fn assert_reconciles(vault: &Vault) {
+        let mut expected: HashMap<[u8; 32], u64> = HashMap::new();
+        for deposit in vault.deposits.iter().filter(|d| !d.is_withdrawn() && d.tag != [0; 32]) {
+            *expected.entry(deposit.tag).or_default() += deposit.amount;
+        }
+        
+        assert!(vault.tag_totals.len() <= MAX_TRACKED_TAGS);
+        let mut other = 0;
+        for (tag, total) in &expected {
+            match vault.total_for_tag(tag) {
+                Some(tracked) => assert_eq!(tracked, *total),
+                None => other += total,
+            }
+        }
+        assert_eq!(vault.other_tags_total, other);
+        assert!(vault.tag_totals.iter().all(|entry| expected.get(&entry.tag) == Some(&entry.total)));
+    }
+
+    #[test]
+    fn test_totals_track_deposits_and_withdrawals() {
+        let mut harness = Harness::empty();
+        harness.deposit(100, [1; 32]);
+        harness.deposit(50, [1; 32]);
+        harness.deposit(30, [0; 32]);
+        
+        let vault = read_vault(&harness.vault);
+        assert_eq!(vault.total_for_tag(&[1; 32]), Some(150));
+        assert_eq!(vault.total_for_tag(&[0; 32]), None);
+        
+        harness.withdraw(0);
+        harness.withdraw(1);
+        
+        assert!(read_vault(&harness.vault).tag_totals.is_empty());
+    }
+
+    #[test]
+    fn test_random_operations_reconcile() {
+        for seed in 0..8 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut harness = Harness::empty();
+        
+            for _ in 0..80 {
+                let vault = read_vault(&harness.vault);
+                let open: Vec<u64> = vault.deposits.iter().filter(|d| !d.is_withdrawn()).map(|d| d.id).collect();
+                if open.is_empty() || rng.gen_bool(0.6) {
+                    // More tags than can be tracked, plus untagged deposits
+                    let tag = [rng.gen_range(0, MAX_TRACKED_TAGS as u8 + 6); 32];
+                    harness.deposit(rng.gen_range(1, 1_000), tag);
+                } else {
+                    harness.withdraw(open[rng.gen_range(0, open.len())]);
+                }
+                assert_reconciles(&read_vault(&harness.vault));
+            }
+        }
+    }
+}
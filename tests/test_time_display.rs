@@ -0,0 +1,90 @@
+#![cfg(feature = "time-display")]
+
+use time_locked_vault::{
+    time_display::{format_timestamp, parse_unlock_time, ParseUnlockTimeError},
+    Deposit, DepositStatus,
+};
+
+// 2025-01-01T00:00:00Z
+const NEW_YEAR_2025: i64 = 1_735_689_600;
+
+#[test]
+fn test_parse_absolute_forms() {
+    assert_eq!(parse_unlock_time("2025-01-01T00:00:00Z", 0), Ok(NEW_YEAR_2025));
+    assert_eq!(parse_unlock_time("2025-01-01T01:00:00+01:00", 0), Ok(NEW_YEAR_2025));
+    assert_eq!(parse_unlock_time("1735689600", 0), Ok(NEW_YEAR_2025));
+    assert_eq!(parse_unlock_time("1969-12-31T23:59:59Z", 0), Ok(-1));
+}
+
+#[test]
+fn test_parse_relative_forms() {
+    assert_eq!(parse_unlock_time("+30d", NEW_YEAR_2025), Ok(NEW_YEAR_2025 + 30 * 86_400));
+    assert_eq!(parse_unlock_time("+2w", 0), Ok(14 * 86_400));
+    assert_eq!(parse_unlock_time("+12h", 0), Ok(12 * 3_600));
+    assert_eq!(parse_unlock_time("+15m", 0), Ok(900));
+    assert_eq!(parse_unlock_time(" +45s ", 0), Ok(45));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(parse_unlock_time("+3y", 0), Err(ParseUnlockTimeError::UnknownUnit('y')));
+    assert_eq!(parse_unlock_time("+30", 0), Err(ParseUnlockTimeError::Unrecognized("+30".to_string())));
+    assert_eq!(parse_unlock_time("+d", 0), Err(ParseUnlockTimeError::Unrecognized("+d".to_string())));
+    assert_eq!(parse_unlock_time("next week", 0), Err(ParseUnlockTimeError::Unrecognized("next week".to_string())));
+    assert_eq!(parse_unlock_time("+1w", i64::MAX), Err(ParseUnlockTimeError::OutOfRange));
+}
+
+#[test]
+fn test_format_round_trip() {
+    for timestamp in [0, -86_400, NEW_YEAR_2025, 253_402_300_799] {
+        assert_eq!(parse_unlock_time(&format_timestamp(timestamp), 0), Ok(timestamp));
+    }
+    assert_eq!(format_timestamp(NEW_YEAR_2025), "2025-01-01T00:00:00Z");
+    assert_eq!(format_timestamp(i64::MAX), format!("@{}", i64::MAX));
+    assert_eq!(format_timestamp(i64::MIN), format!("@{}", i64::MIN));
+}
+
+#[test]
+fn test_deposit_display() {
+    let mut tag = [0; 32];
+    tag[..4].copy_from_slice(b"rent");
+    let mut deposit = Deposit {
+        id: 3,
+        amount: 1_000,
+        unlock_time: NEW_YEAR_2025,
+        created_at: -86_400,
+        tag,
+        ..Deposit::default()
+    };
+    
+    assert_eq!(
+        deposit.display_at(0).to_string(),
+        "Deposit #3: 1000 tokens (tag: rent), locked, unlocks 2025-01-01T00:00:00Z, created 1969-12-31T00:00:00Z",
+    );
+    assert!(deposit.display_at(NEW_YEAR_2025).to_string().contains(", matured,"));
+    assert_eq!(deposit.unlock_datetime_utc().map(|datetime| datetime.timestamp()), Some(NEW_YEAR_2025));
+    
+    deposit.unlock_time = i64::MAX;
+    assert_eq!(deposit.unlock_datetime_utc(), None);
+    assert!(deposit.to_string().contains(&format!("unlocks @{}", i64::MAX)));
+}
+
+#[test]
+fn test_withdrawn_deposit_display() {
+    let deposit = Deposit {
+        id: 4,
+        amount: 50,
+        unlock_time: NEW_YEAR_2025,
+        status: DepositStatus::Withdrawn as u8,
+        withdrawn_at: Some(NEW_YEAR_2025 + 3_600),
+        ..Deposit::default()
+    };
+    
+    assert_eq!(
+        deposit.display_at(0).to_string(),
+        "Deposit #4: 50 tokens, withdrawn, unlocks 2025-01-01T00:00:00Z, created 1970-01-01T00:00:00Z, \
+         withdrawn at 2025-01-01T01:00:00Z",
+    );
+    assert_eq!(deposit.withdrawn_datetime_utc().map(|datetime| datetime.timestamp()), Some(NEW_YEAR_2025 + 3_600));
+    assert_eq!(Deposit::default().withdrawn_datetime_utc(), None);
+}
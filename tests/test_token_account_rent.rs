@@ -0,0 +1,113 @@
+mod common;
+
+use common::bank::{send, signing_vault, token_account, vault_account, vault_program_test, vault_state};
+use solana_program::{clock::Clock, instruction::Instruction, pubkey::Pubkey};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::signature::{Keypair, Signer};
+use time_locked_vault::{builder, Vault};
+
+struct Harness {
+    context: ProgramTestContext,
+    owner: Keypair,
+    vault: Keypair,
+    mint: Pubkey,
+    source: Pubkey,
+    vault_tokens: Pubkey,
+}
+
+impl Harness {
+    // A vault, auto-closing its token accounts if `auto_close`, holding an
+    // unlocked deposit of 100 of the payer's tokens
+    async fn new(auto_close: bool) -> Self {
+        let (owner, vault) = (Keypair::new(), signing_vault());
+        let (mint, source, vault_tokens) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        
+        let mut program_test = vault_program_test();
+        let state = Vault { is_initialized: true, owner: owner.pubkey(), auto_close, ..Vault::default() };
+        program_test.add_account(vault.pubkey(), vault_account(&state));
+        let context = program_test.start_with_context().await;
+        let payer = context.payer.pubkey();
+        let mut harness = Self { context, owner, vault, mint, source, vault_tokens };
+        harness.context.set_account(&vault_tokens, &token_account(&mint, &harness.vault.pubkey(), 0).into());
+        harness.context.set_account(&source, &token_account(&mint, &payer, 100).into());
+        
+        let mut clock: Clock = harness.context.banks_client.get_sysvar().await.unwrap();
+        let unlock_time = clock.unix_timestamp + 3_600;
+        let instruction = builder::deposit(
+            &time_locked_vault::id(),
+            &payer,
+            &harness.vault.pubkey(),
+            &source,
+            &vault_tokens,
+            100,
+            unlock_time,
+            [0; 32],
+        );
+        send(&mut harness.context, &[instruction], &[]).await.unwrap();
+        
+        clock.slot += 10;
+        clock.unix_timestamp = unlock_time;
+        harness.context.set_sysvar(&clock);
+        harness
+    }
+
+    // A `Withdraw` of the deposit back to the payer, signed by the vault for
+    // the payout from its token account
+    fn withdraw_instruction(&self) -> Instruction {
+        let mut instruction = builder::withdraw(
+            &time_locked_vault::id(),
+            &self.context.payer.pubkey(),
+            &self.vault.pubkey(),
+            &self.source,
+            &self.vault_tokens,
+            0,
+        );
+        instruction.accounts[1].is_signer = true;
+        instruction
+    }
+
+    async fn lamports(&mut self, key: Pubkey) -> u64 {
+        self.context.banks_client.get_balance(key).await.unwrap()
+    }
+}
+
+#[tokio::test]
+async fn test_owner_sends_the_rent_where_they_choose() {
+    let mut harness = Harness::new(false).await;
+    let (vault, withdraw) = (harness.vault.insecure_clone(), harness.withdraw_instruction());
+    send(&mut harness.context, &[withdraw], &[&vault]).await.unwrap();
+    let rent = harness.lamports(harness.vault_tokens).await;
+    let destination = Pubkey::new_unique();
+
+    let mut instruction = builder::close_vault_token_account(
+        &time_locked_vault::id(),
+        &harness.owner.pubkey(),
+        &vault.pubkey(),
+        &harness.vault_tokens,
+        &harness.mint,
+        &destination,
+    );
+    instruction.accounts[1].is_signer = true;
+    let owner = harness.owner.insecure_clone();
+    send(&mut harness.context, &[instruction], &[&owner, &vault]).await.unwrap();
+
+    assert_eq!(harness.lamports(destination).await, rent);
+    assert_eq!(harness.lamports(harness.owner.pubkey()).await, 0);
+    assert!(harness.context.banks_client.get_account(harness.vault_tokens).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_auto_close_credits_the_vault_owner() {
+    let mut harness = Harness::new(true).await;
+    let rent = harness.lamports(harness.vault_tokens).await;
+    let vault_lamports = harness.lamports(harness.vault.pubkey()).await;
+
+    let instruction = builder::with_auto_close_owner(harness.withdraw_instruction(), &harness.owner.pubkey());
+    let vault = harness.vault.insecure_clone();
+    send(&mut harness.context, &[instruction], &[&vault]).await.unwrap();
+
+    assert_eq!(harness.lamports(harness.owner.pubkey()).await, rent);
+    assert_eq!(harness.lamports(vault.pubkey()).await, vault_lamports);
+    assert!(harness.context.banks_client.get_account(harness.vault_tokens).await.unwrap().is_none());
+    assert!(vault_state(&mut harness.context, vault.pubkey()).await.mints.is_empty());
+}
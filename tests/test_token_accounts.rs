@@ -0,0 +1,456 @@
+mod common;
+
+use common::bank::{send, signing_vault, token_account, token_balance, vault_account, vault_program_test};
+use solana_program::{
+    clock::Clock, instruction::InstructionError, program_option::COption, program_pack::Pack, pubkey::Pubkey,
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use spl_token::state::Mint;
+use time_locked_vault::{builder, Vault, VaultError};
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        entrypoint::ProgramResult, program_error::ProgramError, program_option::COption, pubkey::Pubkey,
+    };
+    use spl_token::{
+        instruction::{AuthorityType, TokenInstruction},
+        state::AccountState,
+    };
+    use time_locked_vault::{process_instruction, Vault, VaultError, VaultInstruction};
+
+    struct TokenContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl TokenContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let owner = Pubkey::new_unique();
+            let vault = Vault {
+                owner,
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                owner,
+                depositor: Pubkey::new_unique(),
+                token_mint: Pubkey::new_unique(),
+                vault: vault_account(&program_id, &vault, 1000),
+            }
+        }
+        
+        fn vault_tokens(&self) -> TestAccount {
+            token_account(&self.token_mint, &self.vault.key, 0)
+        }
+        
+        fn deposit(&mut self, source: &mut TestAccount, vault_tokens: &mut TestAccount) -> ProgramResult {
+            self.deposit_through(source, vault_tokens, &mut token_program())
+        }
+        
+        fn deposit_through(
+            &mut self,
+            source: &mut TestAccount,
+            vault_tokens: &mut TestAccount,
+            token_program: &mut TestAccount,
+        ) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(0, 0);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                source.info(),
+                vault_tokens.info(),
+                token_program.info(),
+                system_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Deposit {
+                amount: 1_000,
+                unlock_time: 1_000,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .try_to_vec()
+            .unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        // Withdraw deposit 0, unlocked at 1_000, back to `destination`
+        fn withdraw_through(
+            &mut self,
+            destination: &mut TestAccount,
+            vault_tokens: &mut TestAccount,
+            token_program: &mut TestAccount,
+        ) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut clock = clock_account(0, 1_000);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                vault_tokens.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Withdraw { deposit_id: 0 }.try_to_vec().unwrap();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn send(
+            &mut self,
+            signer: &Pubkey,
+            instruction: VaultInstruction,
+            vault_tokens: &mut TestAccount,
+        ) -> ProgramResult {
+            let mut signer = TestAccount::wallet(*signer).signer();
+            let mut token_program = token_program();
+            let accounts = vec![signer.info(), self.vault.info(), vault_tokens.info(), token_program.info()];
+            process_instruction(&self.program_id, &accounts, &instruction.try_to_vec().unwrap())
+        }
+    }
+
+    #[test]
+    fn test_delegated_vault_token_account_rejected() {
+        let mut ctx = TokenContext::new();
+        let mut source = token_account(&ctx.token_mint, &ctx.depositor, 1_000);
+        let mut vault_tokens = ctx.vault_tokens();
+        update_token_account(&mut vault_tokens, |state| {
+            state.delegate = COption::Some(Pubkey::new_unique());
+            state.delegated_amount = u64::MAX;
+        });
+        
+        let result = ctx.deposit(&mut source, &mut vault_tokens);
+        
+        assert_eq!(result, Err(VaultError::DelegatedAccountNotAllowed.into()));
+        assert!(read_vault(&ctx.vault).deposits.is_empty());
+    }
+
+    #[test]
+    fn test_delegated_source_account_allowed() {
+        let mut ctx = TokenContext::new();
+        let mut source = token_account(&ctx.token_mint, &ctx.depositor, 1_000);
+        update_token_account(&mut source, |state| {
+            state.delegate = COption::Some(Pubkey::new_unique());
+            state.delegated_amount = 1;
+        });
+        let mut vault_tokens = ctx.vault_tokens();
+        
+        ctx.deposit(&mut source, &mut vault_tokens).unwrap();
+        
+        assert_eq!(read_vault(&ctx.vault).deposits.len(), 1);
+    }
+
+    #[test]
+    fn test_owner_revokes_vault_delegate() {
+        let mut ctx = TokenContext::new();
+        let (owner, depositor) = (ctx.owner, ctx.depositor);
+        let mut vault_tokens = ctx.vault_tokens();
+        update_token_account(&mut vault_tokens, |state| {
+            state.delegate = COption::Some(Pubkey::new_unique());
+            state.delegated_amount = u64::MAX;
+        });
+        
+        let stranger = ctx.send(&depositor, VaultInstruction::RevokeVaultDelegate, &mut vault_tokens);
+        assert_eq!(stranger, Err(VaultError::NotVaultOwner.into()));
+        
+        take_invocations();
+        ctx.send(&owner, VaultInstruction::RevokeVaultDelegate, &mut vault_tokens).unwrap();
+        let invocations = take_invocations();
+        assert_eq!(invocations.len(), 1);
+        assert_eq!(TokenInstruction::unpack(&invocations[0].data), Ok(TokenInstruction::Revoke));
+        assert_eq!(invocations[0].accounts[0].pubkey, vault_tokens.key);
+        assert_eq!(invocations[0].accounts[1].pubkey, ctx.vault.key);
+    }
+
+    #[test]
+    fn test_foreign_close_authority_rejected_on_deposit() {
+        let mut ctx = TokenContext::new();
+        let mut source = token_account(&ctx.token_mint, &ctx.depositor, 1_000);
+        let mut vault_tokens = ctx.vault_tokens();
+        update_token_account(&mut vault_tokens, |state| {
+            state.close_authority = COption::Some(Pubkey::new_unique());
+        });
+        
+        let result = ctx.deposit(&mut source, &mut vault_tokens);
+        
+        assert_eq!(result, Err(VaultError::InvalidVaultTokenAccount.into()));
+    }
+
+    #[test]
+    fn test_vault_close_authority_allowed_on_deposit() {
+        let mut ctx = TokenContext::new();
+        let mut source = token_account(&ctx.token_mint, &ctx.depositor, 1_000);
+        let mut vault_tokens = ctx.vault_tokens();
+        let vault_key = ctx.vault.key;
+        update_token_account(&mut vault_tokens, |state| {
+            state.close_authority = COption::Some(vault_key);
+        });
+        
+        ctx.deposit(&mut source, &mut vault_tokens).unwrap();
+    }
+
+    #[test]
+    fn test_foreign_close_authority_rejected_on_withdraw() {
+        let mut ctx = TokenContext::new();
+        let mut source = token_account(&ctx.token_mint, &ctx.depositor, 1_000);
+        let mut vault_tokens = ctx.vault_tokens();
+        ctx.deposit(&mut source, &mut vault_tokens).unwrap();
+        update_token_account(&mut vault_tokens, |state| {
+            state.amount = 1_000;
+            state.close_authority = COption::Some(Pubkey::new_unique());
+        });
+        
+        let result = ctx.withdraw_through(&mut source, &mut vault_tokens, &mut token_program());
+        
+        assert_eq!(result, Err(VaultError::InvalidVaultTokenAccount.into()));
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_vault_tokens_of_another_owner_rejected_on_deposit() {
+        let mut ctx = TokenContext::new();
+        let mut source = token_account(&ctx.token_mint, &ctx.depositor, 1_000);
+        let mut vault_tokens = token_account(&ctx.token_mint, &Pubkey::new_unique(), 0);
+        
+        let result = ctx.deposit(&mut source, &mut vault_tokens);
+        
+        assert_eq!(result, Err(VaultError::InvalidVaultTokenAccount.into()));
+        assert!(read_vault(&ctx.vault).deposits.is_empty());
+    }
+
+    #[test]
+    fn test_vault_tokens_of_another_owner_rejected_on_withdraw() {
+        let mut ctx = TokenContext::new();
+        let mut source = token_account(&ctx.token_mint, &ctx.depositor, 1_000);
+        let mut vault_tokens = ctx.vault_tokens();
+        ctx.deposit(&mut source, &mut vault_tokens).unwrap();
+        update_token_account(&mut vault_tokens, |state| {
+            state.amount = 1_000;
+            state.owner = Pubkey::new_unique();
+        });
+        
+        let result = ctx.withdraw_through(&mut source, &mut vault_tokens, &mut token_program());
+        
+        assert_eq!(result, Err(VaultError::InvalidVaultTokenAccount.into()));
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_fake_token_program_rejected_on_deposit() {
+        let mut ctx = TokenContext::new();
+        let mut source = token_account(&ctx.token_mint, &ctx.depositor, 1_000);
+        let mut vault_tokens = ctx.vault_tokens();
+        let mut fake_token_program = TestAccount::new(Pubkey::new_unique(), Pubkey::default(), vec![]);
+        
+        let result = ctx.deposit_through(&mut source, &mut vault_tokens, &mut fake_token_program);
+        
+        assert_eq!(result, Err(ProgramError::IncorrectProgramId));
+        assert!(read_vault(&ctx.vault).deposits.is_empty());
+    }
+
+    #[test]
+    fn test_fake_token_program_rejected_on_withdraw() {
+        let mut ctx = TokenContext::new();
+        let mut source = token_account(&ctx.token_mint, &ctx.depositor, 1_000);
+        let mut vault_tokens = ctx.vault_tokens();
+        ctx.deposit(&mut source, &mut vault_tokens).unwrap();
+        update_token_account(&mut vault_tokens, |state| state.amount = 1_000);
+        let mut fake_token_program = TestAccount::new(Pubkey::new_unique(), Pubkey::default(), vec![]);
+        
+        let result = ctx.withdraw_through(&mut source, &mut vault_tokens, &mut fake_token_program);
+        
+        assert_eq!(result, Err(ProgramError::IncorrectProgramId));
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_owner_clears_close_authority() {
+        let mut ctx = TokenContext::new();
+        let owner = ctx.owner;
+        let mut vault_tokens = ctx.vault_tokens();
+        take_invocations();
+        
+        ctx.send(&owner, VaultInstruction::SetVaultTokenCloseAuthority, &mut vault_tokens).unwrap();
+        
+        let invocations = take_invocations();
+        assert_eq!(invocations.len(), 1);
+        assert_eq!(
+            TokenInstruction::unpack(&invocations[0].data),
+            Ok(TokenInstruction::SetAuthority {
+                authority_type: AuthorityType::CloseAccount,
+                new_authority: COption::None,
+            }),
+        );
+        assert_eq!(invocations[0].accounts[1].pubkey, ctx.vault.key);
+    }
+
+    #[test]
+    fn test_frozen_source_rejected_on_deposit() {
+        let mut ctx = TokenContext::new();
+        let mut source = token_account(&ctx.token_mint, &ctx.depositor, 1_000);
+        update_token_account(&mut source, |state| state.state = AccountState::Frozen);
+        let mut vault_tokens = ctx.vault_tokens();
+        let before = ctx.vault.data.clone();
+        
+        let result = ctx.deposit(&mut source, &mut vault_tokens);
+        
+        assert_eq!(result, Err(VaultError::TokenAccountFrozen.into()));
+        assert_eq!(ctx.vault.data, before);
+    }
+
+    #[test]
+    fn test_frozen_accounts_rejected_on_withdraw() {
+        for frozen_vault_side in [false, true] {
+            let mut ctx = TokenContext::new();
+            let mut destination = token_account(&ctx.token_mint, &ctx.depositor, 1_000);
+            let mut vault_tokens = ctx.vault_tokens();
+            ctx.deposit(&mut destination, &mut vault_tokens).unwrap();
+            let frozen = if frozen_vault_side { &mut vault_tokens } else { &mut destination };
+            update_token_account(frozen, |state| state.state = AccountState::Frozen);
+            let before = ctx.vault.data.clone();
+        
+            let mut depositor = TestAccount::wallet(ctx.depositor).signer();
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, 1_000);
+            let accounts = vec![
+                depositor.info(),
+                ctx.vault.info(),
+                destination.info(),
+                vault_tokens.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Withdraw { deposit_id: 0 }.try_to_vec().unwrap();
+            let result = process_instruction(&ctx.program_id, &accounts, &data);
+            drop(accounts);
+        
+            assert_eq!(result, Err(VaultError::TokenAccountFrozen.into()));
+            assert_eq!(ctx.vault.data, before);
+        }
+    }
+}
+
+// A vault and 100 of the payer's tokens of a mint whose freeze authority can
+// freeze them for real through the token program
+struct FreezeContext {
+    context: ProgramTestContext,
+    freeze_authority: Keypair,
+    vault: Keypair,
+    mint: Pubkey,
+    source: Pubkey,
+    vault_tokens: Pubkey,
+}
+
+impl FreezeContext {
+    async fn new() -> Self {
+        let (freeze_authority, vault) = (Keypair::new(), signing_vault());
+        let (mint, source, vault_tokens) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        
+        let mut program_test = vault_program_test();
+        let state = Vault { is_initialized: true, owner: Pubkey::new_unique(), ..Vault::default() };
+        program_test.add_account(vault.pubkey(), vault_account(&state));
+        let mut data = vec![0; Mint::LEN];
+        Mint {
+            mint_authority: COption::None,
+            supply: 100,
+            decimals: 0,
+            is_initialized: true,
+            freeze_authority: COption::Some(freeze_authority.pubkey()),
+        }
+        .pack_into_slice(&mut data);
+        let mint_account = Account { lamports: 1_000_000_000, data, owner: spl_token::id(), ..Account::default() };
+        program_test.add_account(mint, mint_account);
+        program_test.add_account(vault_tokens, token_account(&mint, &vault.pubkey(), 0));
+        let context = program_test.start_with_context().await;
+        let payer = context.payer.pubkey();
+        let mut ctx = Self { context, freeze_authority, vault, mint, source, vault_tokens };
+        ctx.context.set_account(&source, &token_account(&mint, &payer, 100).into());
+        ctx
+    }
+
+    async fn freeze(&mut self, account: Pubkey) {
+        let authority = self.freeze_authority.insecure_clone();
+        let instruction =
+            spl_token::instruction::freeze_account(&spl_token::id(), &account, &self.mint, &authority.pubkey(), &[])
+                .unwrap();
+        send(&mut self.context, &[instruction], &[&authority]).await.unwrap();
+    }
+
+    async fn deposit(&mut self, unlock_time: i64) -> Result<(), TransactionError> {
+        let instruction = builder::deposit(
+            &time_locked_vault::id(),
+            &self.context.payer.pubkey(),
+            &self.vault.pubkey(),
+            &self.source,
+            &self.vault_tokens,
+            100,
+            unlock_time,
+            [0; 32],
+        );
+        send(&mut self.context, &[instruction], &[]).await
+    }
+
+    async fn withdraw(&mut self) -> Result<(), TransactionError> {
+        let mut instruction = builder::withdraw(
+            &time_locked_vault::id(),
+            &self.context.payer.pubkey(),
+            &self.vault.pubkey(),
+            &self.source,
+            &self.vault_tokens,
+            0,
+        );
+        instruction.accounts[1].is_signer = true;
+        let vault = self.vault.insecure_clone();
+        send(&mut self.context, &[instruction], &[&vault]).await
+    }
+}
+
+fn frozen_error() -> Result<(), TransactionError> {
+    Err(TransactionError::InstructionError(0, InstructionError::Custom(VaultError::TokenAccountFrozen as u32)))
+}
+
+#[tokio::test]
+async fn test_source_frozen_by_the_mint_rejected_on_deposit() {
+    let mut ctx = FreezeContext::new().await;
+    ctx.freeze(ctx.source).await;
+    let clock: Clock = ctx.context.banks_client.get_sysvar().await.unwrap();
+
+    let result = ctx.deposit(clock.unix_timestamp + 3_600).await;
+
+    assert_eq!(result, frozen_error());
+    assert_eq!(token_balance(&mut ctx.context, ctx.source).await, 100);
+}
+
+#[tokio::test]
+async fn test_vault_tokens_frozen_by_the_mint_rejected_on_withdraw() {
+    let mut ctx = FreezeContext::new().await;
+    let mut clock: Clock = ctx.context.banks_client.get_sysvar().await.unwrap();
+    let unlock_time = clock.unix_timestamp + 3_600;
+    ctx.deposit(unlock_time).await.unwrap();
+    ctx.freeze(ctx.vault_tokens).await;
+    clock.slot += 10;
+    clock.unix_timestamp = unlock_time;
+    ctx.context.set_sysvar(&clock);
+
+    let result = ctx.withdraw().await;
+
+    assert_eq!(result, frozen_error());
+    assert_eq!(token_balance(&mut ctx.context, ctx.vault_tokens).await, 100);
+}
@@ -0,0 +1,147 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{process_instruction, Vault, VaultError, VaultInstruction, MAX_TRACKED_TAGS};
+
+    const NOW: i64 = 1_700_000_000;
+    const RENT: [u8; 32] = *b"rent-2025-01\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0";
+
+    struct TagContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        depositor: Pubkey,
+        mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl TagContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let owner = Pubkey::new_unique();
+            let vault = vault_account(&program_id, &Vault { owner, ..Vault::default() }, 100_000);
+            Self { program_id, owner, depositor: Pubkey::new_unique(), mint: Pubkey::new_unique(), vault }
+        }
+        
+        fn set_unique_tags(&mut self, enabled: bool) -> ProgramResult {
+            let mut owner = TestAccount::wallet(self.owner).signer();
+            let accounts = vec![owner.info(), self.vault.info()];
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::SetUniqueTags { enabled }.pack())
+        }
+        
+        fn deposit(&mut self, depositor: Pubkey, tag: [u8; 32]) -> ProgramResult {
+            let mut depositor_account = TestAccount::wallet(depositor).signer();
+            let mut source = token_account(&self.mint, &depositor, 100);
+            let mut vault_token = token_account(&self.mint, &self.vault.key, 0);
+            let mut token_program = token_program();
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(1, NOW);
+            let accounts = vec![
+                depositor_account.info(),
+                self.vault.info(),
+                source.info(),
+                vault_token.info(),
+                token_program.info(),
+                system_program.info(),
+                clock.info(),
+            ];
+            let data = VaultInstruction::Deposit {
+                amount: 100,
+                unlock_time: NOW + 10,
+                tag,
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            }
+            .pack();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn withdraw(&mut self, deposit_id: u64) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut destination = token_account(&self.mint, &self.depositor, 0);
+            let mut source = token_account(&self.mint, &self.vault.key, u64::MAX);
+            let mut token_program = token_program();
+            let mut clock = clock_account(2, NOW + 100);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::Withdraw { deposit_id }.pack())
+        }
+    }
+
+    #[test]
+    fn test_tags_may_repeat_by_default() {
+        let mut ctx = TagContext::new();
+        let depositor = ctx.depositor;
+        
+        ctx.deposit(depositor, RENT).unwrap();
+        ctx.deposit(depositor, RENT).unwrap();
+        
+        let vault = read_vault(&ctx.vault);
+        assert!(!vault.unique_tags);
+        assert_eq!(vault.total_for_tag(&RENT), Some(200));
+    }
+
+    #[test]
+    fn test_duplicate_tag_is_rejected_per_depositor() {
+        let mut ctx = TagContext::new();
+        let depositor = ctx.depositor;
+        ctx.set_unique_tags(true).unwrap();
+        ctx.deposit(depositor, RENT).unwrap();
+        
+        assert_eq!(ctx.deposit(depositor, RENT), Err(VaultError::DuplicateTag.into()));
+        assert_eq!(read_vault(&ctx.vault).deposits.len(), 1);
+        
+        // Other depositors, other tags and untagged deposits are unaffected
+        ctx.deposit(Pubkey::new_unique(), RENT).unwrap();
+        ctx.deposit(depositor, [7; 32]).unwrap();
+        ctx.deposit(depositor, [0; 32]).unwrap();
+        ctx.deposit(depositor, [0; 32]).unwrap();
+        assert_eq!(read_vault(&ctx.vault).deposits.len(), 5);
+        
+        // Only the owner may lift the requirement
+        ctx.owner = depositor;
+        assert_eq!(ctx.set_unique_tags(false), Err(VaultError::NotVaultOwner.into()));
+    }
+
+    #[test]
+    fn test_withdrawal_frees_the_tag() {
+        let mut ctx = TagContext::new();
+        let depositor = ctx.depositor;
+        ctx.set_unique_tags(true).unwrap();
+        ctx.deposit(depositor, RENT).unwrap();
+        
+        ctx.withdraw(0).unwrap();
+        
+        ctx.deposit(depositor, RENT).unwrap();
+        assert_eq!(ctx.deposit(depositor, RENT), Err(VaultError::DuplicateTag.into()));
+    }
+
+    #[test]
+    fn test_untracked_tags_are_still_unique() {
+        let mut ctx = TagContext::new();
+        let depositor = ctx.depositor;
+        ctx.set_unique_tags(true).unwrap();
+        // Fill every tracked tag entry, so the next tag is counted in the other bucket
+        for tag in 1..=MAX_TRACKED_TAGS {
+            ctx.deposit(Pubkey::new_unique(), [tag as u8; 32]).unwrap();
+        }
+        
+        ctx.deposit(depositor, RENT).unwrap();
+        
+        let vault = read_vault(&ctx.vault);
+        assert_eq!(vault.total_for_tag(&RENT), None);
+        assert_eq!(vault.other_tags_total, 100);
+        assert_eq!(ctx.deposit(depositor, RENT), Err(VaultError::DuplicateTag.into()));
+    }
+}
@@ -0,0 +1,176 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        process_instruction, round_unlock_time, Deposit, LockTier, Vault, VaultError, VaultInstruction,
+        LOCK_ONE_MONTH_SECS, LOCK_THREE_MONTHS_SECS, MAX_UNLOCK_GRANULARITY,
+    };
+
+    const DAY: i64 = 24 * 60 * 60;
+    // Mid-way through a day
+    const NOW: i64 = 20_000 * DAY + 12 * 60 * 60;
+
+    struct GranularityContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        depositor: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl GranularityContext {
+        // A vault rounding unlock times to whole days
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let owner = Pubkey::new_unique();
+            let vault = Vault { owner, unlock_granularity: DAY, ..Vault::default() };
+            Self {
+                program_id,
+                owner,
+                depositor: Pubkey::new_unique(),
+                vault: vault_account(&program_id, &vault, 10_000),
+            }
+        }
+        
+        fn set_granularity(&mut self, granularity: i64) -> ProgramResult {
+            let mut owner = TestAccount::wallet(self.owner).signer();
+            let accounts = vec![owner.info(), self.vault.info()];
+            let data = VaultInstruction::SetUnlockGranularity { granularity }.pack();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn send_deposit(&mut self, instruction: VaultInstruction) -> ProgramResult {
+            let mint = Pubkey::new_unique();
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut source = token_account(&mint, &self.depositor, 100);
+            let mut vault_token = token_account(&mint, &self.vault.key, 0);
+            let mut token_program = token_program();
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(1, NOW);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                source.info(),
+                vault_token.info(),
+                token_program.info(),
+                system_program.info(),
+                clock.info(),
+            ];
+            take_return_data();
+            take_events();
+            process_instruction(&self.program_id, &accounts, &instruction.pack())
+        }
+        
+        fn deposit(&mut self, unlock_time: i64) -> ProgramResult {
+            self.send_deposit(VaultInstruction::Deposit {
+                amount: 100,
+                unlock_time,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            })
+        }
+        
+        // The unlock time of the last deposit, which must match the return
+        // data and the `deposit` event
+        fn stored_unlock_time(&self) -> i64 {
+            let unlock_time = read_vault(&self.vault).deposits.last().unwrap().unlock_time;
+            assert_eq!(take_return_data(), unlock_time.to_le_bytes());
+            let events = take_events();
+            let deposit_event = events.iter().find(|event| event[0] == b"deposit").unwrap();
+            assert_eq!(deposit_event[3], unlock_time.to_le_bytes());
+            unlock_time
+        }
+    }
+
+    #[test]
+    fn test_rounding_lands_on_the_next_boundary() {
+        // Exact boundaries are kept, anything past one moves to the next
+        assert_eq!(round_unlock_time(20_001 * DAY, DAY, NOW).ok(), Some(20_001 * DAY));
+        assert_eq!(round_unlock_time(20_001 * DAY + 1, DAY, NOW).ok(), Some(20_002 * DAY));
+        assert_eq!(round_unlock_time(20_002 * DAY - 1, DAY, NOW).ok(), Some(20_002 * DAY));
+        assert_eq!(round_unlock_time(NOW + 1, DAY, NOW).ok(), Some(20_001 * DAY));
+        
+        // Never at or before now: a boundary that is now moves on a whole interval
+        assert_eq!(round_unlock_time(20_000 * DAY, DAY, 20_000 * DAY).ok(), Some(20_001 * DAY));
+        assert_eq!(round_unlock_time(NOW - DAY, DAY, NOW).ok(), Some(20_001 * DAY));
+        
+        // Zero granularity leaves the time alone; overflow is an error
+        assert_eq!(round_unlock_time(NOW + 1, 0, NOW).ok(), Some(NOW + 1));
+        assert!(matches!(round_unlock_time(i64::MAX, DAY, NOW), Err(VaultError::MathOverflow)));
+    }
+
+    #[test]
+    fn test_deposit_unlock_is_rounded() {
+        let mut ctx = GranularityContext::new();
+        
+        // Mid-interval, just past a boundary and exactly on one
+        ctx.deposit(NOW + 60).unwrap();
+        assert_eq!(ctx.stored_unlock_time(), 20_001 * DAY);
+        ctx.deposit(20_001 * DAY + 1).unwrap();
+        assert_eq!(ctx.stored_unlock_time(), 20_002 * DAY);
+        ctx.deposit(20_003 * DAY).unwrap();
+        assert_eq!(ctx.stored_unlock_time(), 20_003 * DAY);
+        
+        // The requested time must still be in the future
+        assert_eq!(ctx.deposit(NOW), Err(VaultError::InvalidUnlockTime.into()));
+    }
+
+    #[test]
+    fn test_duration_and_tier_unlocks_are_rounded() {
+        let mut ctx = GranularityContext::new();
+        
+        ctx.send_deposit(VaultInstruction::DepositWithDuration { amount: 100, lock_duration_secs: 60, tag: [0; 32] })
+            .unwrap();
+        assert_eq!(ctx.stored_unlock_time(), 20_001 * DAY);
+        
+        let tiered = VaultInstruction::DepositTiered { amount: 100, tier: LockTier::OneMonth, tag: [0; 32] };
+        ctx.send_deposit(tiered).unwrap();
+        assert_eq!(ctx.stored_unlock_time(), 20_000 * DAY + LOCK_ONE_MONTH_SECS + DAY);
+    }
+
+    #[test]
+    fn test_extend_lock_keeps_the_rounding() {
+        let mut ctx = GranularityContext::new();
+        let mut vault = read_vault(&ctx.vault);
+        vault.deposit_count = 1;
+        vault.deposits.push(Deposit {
+            depositor: ctx.depositor,
+            amount: 100,
+            unlock_time: 20_001 * DAY,
+            lock_tier: Some(LockTier::OneMonth as u8),
+            ..Deposit::default()
+        });
+        ctx.vault = vault_account(&ctx.program_id, &vault, 10_000);
+        
+        let mut depositor = TestAccount::wallet(ctx.depositor).signer();
+        let mut clock = clock_account(1, NOW);
+        let accounts = vec![depositor.info(), ctx.vault.info(), clock.info()];
+        let data = VaultInstruction::ExtendLock { deposit_id: 0, new_tier: LockTier::ThreeMonths }.pack();
+        process_instruction(&ctx.program_id, &accounts, &data).unwrap();
+        
+        assert_eq!(read_vault(&ctx.vault).deposits[0].unlock_time, 20_000 * DAY + LOCK_THREE_MONTHS_SECS + DAY);
+    }
+
+    #[test]
+    fn test_granularity_is_validated() {
+        let mut ctx = GranularityContext::new();
+        let invalid = Err(VaultError::InvalidUnlockGranularity.into());
+        
+        assert_eq!(ctx.set_granularity(-1), invalid);
+        assert_eq!(ctx.set_granularity(MAX_UNLOCK_GRANULARITY + 1), invalid);
+        
+        // Turning rounding off stores requested times as they are
+        ctx.set_granularity(0).unwrap();
+        ctx.deposit(NOW + 60).unwrap();
+        assert_eq!(read_vault(&ctx.vault).deposits[0].unlock_time, NOW + 60);
+        
+        ctx.owner = ctx.depositor;
+        assert_eq!(ctx.set_granularity(DAY), Err(VaultError::NotVaultOwner.into()));
+    }
+}
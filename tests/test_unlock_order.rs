@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod tests {
+    use time_locked_vault::{Deposit, DepositStatus, Vault};
+
+    const NOW: i64 = 1_000;
+
+    fn deposit(id: u64, unlock_time: i64) -> Deposit {
+        Deposit { id, unlock_time, amount: 1, ..Deposit::default() }
+    }
+
+    // Deposits stored out of unlock order, three of them sharing an unlock
+    // time, with one withdrawn and one slot-locked deposit mixed in
+    fn vault() -> Vault {
+        let mut withdrawn = deposit(3, NOW + 5);
+        withdrawn.status = DepositStatus::Withdrawn as u8;
+        let mut slot_locked = deposit(6, NOW - 500);
+        slot_locked.unlock_slot = Some(99);
+        Vault {
+            deposits: vec![
+                deposit(5, NOW + 20),
+                deposit(4, NOW + 10),
+                withdrawn,
+                deposit(2, NOW + 10),
+                deposit(0, NOW - 10),
+                slot_locked,
+                deposit(1, NOW + 10),
+            ],
+            deposit_count: 7,
+            ..Vault::default()
+        }
+    }
+
+    fn ids(deposits: Vec<&Deposit>) -> Vec<u64> {
+        deposits.iter().map(|deposit| deposit.id).collect()
+    }
+
+    #[test]
+    fn test_unlock_order_breaks_ties_by_id() {
+        let vault = vault();
+        
+        assert_eq!(ids(vault.unlock_order()), vec![0, 1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn test_next_unlock() {
+        let mut vault = vault();
+        
+        // Deposit 3 is withdrawn, so the shared NOW + 10 comes first, lowest ID winning
+        assert_eq!(vault.next_unlock(NOW), Some((1, NOW + 10)));
+        // Deposits that already unlocked are not "next"
+        assert_eq!(vault.next_unlock(NOW + 10), Some((5, NOW + 20)));
+        assert_eq!(vault.next_unlock(NOW + 20), None);
+        
+        // Withdrawing the head of a tie hands over to the next ID
+        vault.deposits[6].status = DepositStatus::Withdrawn as u8;
+        assert_eq!(vault.next_unlock(NOW), Some((2, NOW + 10)));
+        assert_eq!(Vault::default().next_unlock(NOW), None);
+    }
+
+    #[test]
+    fn test_unlocking_between_is_half_open() {
+        let mut vault = vault();
+        
+        assert_eq!(ids(vault.unlocking_between(NOW, NOW + 20)), vec![1, 2, 4]);
+        assert_eq!(ids(vault.unlocking_between(NOW + 10, NOW + 11)), vec![1, 2, 4]);
+        assert_eq!(ids(vault.unlocking_between(NOW - 10, NOW + 21)), vec![0, 1, 2, 4, 5]);
+        assert!(vault.unlocking_between(NOW + 11, NOW + 20).is_empty());
+        
+        // New deposits show up without any index to maintain
+        vault.deposits.push(deposit(7, NOW + 10));
+        assert_eq!(ids(vault.unlocking_between(NOW + 10, NOW + 11)), vec![1, 2, 4, 7]);
+    }
+}
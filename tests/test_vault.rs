@@ -3,22 +3,23 @@ mod tests {
     use solana_program::{
         account_info::AccountInfo,
         clock::Clock,
-        entrypoint::ProgramResult,
         program_error::ProgramError,
         pubkey::Pubkey,
-        sysvar::Sysvar,
     };
-    use solana_program_test::*;
-    use std::mem::size_of;
     use borsh::{BorshDeserialize, BorshSerialize};
     use time_locked_vault::{
         process_instruction,
         VaultInstruction,
         Vault,
+        VaultConfig,
         Deposit,
+        DepositStatus,
         VaultError,
     };
 
+    // Owner of the mock wallet and program accounts
+    const DEFAULT_OWNER: Pubkey = Pubkey::new_from_array([0; 32]);
+
     // Mock accounts and data for testing
     struct TestContext {
         program_id: Pubkey,
@@ -30,7 +31,6 @@ mod tests {
         token_program: Pubkey,
         system_program: Pubkey,
         clock_sysvar: Pubkey,
-        emergency_authority: Pubkey,
     }
 
     impl TestContext {
@@ -42,10 +42,9 @@ mod tests {
                 vault_account: Pubkey::new_unique(),
                 source_token_account: Pubkey::new_unique(),
                 destination_token_account: Pubkey::new_unique(),
-                token_program: Pubkey::new_unique(),
-                system_program: Pubkey::new_unique(),
-                clock_sysvar: Pubkey::new_unique(),
-                emergency_authority: Pubkey::new_unique(),
+                token_program: spl_token::id(),
+                system_program: solana_program::system_program::id(),
+                clock_sysvar: solana_program::sysvar::clock::id(),
             }
         }
     }
@@ -53,14 +52,26 @@ mod tests {
     // Helper function to create a mock vault
     fn create_mock_vault(owner: &Pubkey) -> Vault {
         Vault {
+            is_initialized: true,
             owner: *owner,
             deposit_count: 0,
             deposits: Vec::new(),
             reentrancy_guard: false,
             emergency_authority: None,
+            ..Vault::default()
         }
     }
 
+    // The clock sysvar's account data: its fields in order, little-endian
+    fn sysvar_data(clock: &Clock) -> Vec<u8> {
+        let mut data = clock.slot.to_le_bytes().to_vec();
+        data.extend(clock.epoch_start_timestamp.to_le_bytes());
+        data.extend(clock.epoch.to_le_bytes());
+        data.extend(clock.leader_schedule_epoch.to_le_bytes());
+        data.extend(clock.unix_timestamp.to_le_bytes());
+        data
+    }
+
     // Helper function to create a mock deposit
     fn create_mock_deposit(
         id: u64,
@@ -75,9 +86,10 @@ mod tests {
             token_mint: *token_mint,
             amount,
             unlock_time,
-            withdrawn: false,
+            status: DepositStatus::Active as u8,
             tag: [0; 32],
             created_at: 0,
+            ..Deposit::default()
         }
     }
 
@@ -108,7 +120,20 @@ mod tests {
             false,
             &mut owner_lamports,
             &mut owner_data,
-            &Pubkey::default(),
+            &DEFAULT_OWNER,
+            false,
+            0,
+        );
+        
+        let mut system_program_lamports = 0;
+        let mut system_program_data = vec![];
+        let system_program_info = AccountInfo::new(
+            &ctx.system_program,
+            false,
+            false,
+            &mut system_program_lamports,
+            &mut system_program_data,
+            &DEFAULT_OWNER,
             false,
             0,
         );
@@ -116,10 +141,17 @@ mod tests {
         let accounts = vec![
             owner_account_info,
             vault_account_info,
+            system_program_info,
         ];
         
         // Create instruction data
-        let instruction = VaultInstruction::CreateVault;
+        let instruction = VaultInstruction::CreateVault {
+            skip_registry: true,
+            arbitration_destination: None,
+            emergency_destination: None,
+            owner_set: None,
+            config: VaultConfig::default(),
+        };
         let instruction_data = instruction.try_to_vec().unwrap();
         
         // Process instruction
@@ -133,11 +165,11 @@ mod tests {
         assert!(result.is_ok());
         
         // Verify vault data
-        let vault = Vault::try_from_slice(&vault_account_data).unwrap();
+        let vault = Vault::deserialize(&mut &vault_account_data[..]).unwrap();
         assert_eq!(vault.owner, ctx.owner);
         assert_eq!(vault.deposit_count, 0);
         assert_eq!(vault.deposits.len(), 0);
-        assert_eq!(vault.reentrancy_guard, false);
+        assert!(!vault.reentrancy_guard);
         assert_eq!(vault.emergency_authority, None);
     }
 
@@ -170,7 +202,7 @@ mod tests {
             false,
             &mut depositor_lamports,
             &mut depositor_data,
-            &Pubkey::default(),
+            &DEFAULT_OWNER,
             false,
             0,
         );
@@ -210,7 +242,7 @@ mod tests {
             false,
             &mut token_program_lamports,
             &mut token_program_data,
-            &Pubkey::default(),
+            &DEFAULT_OWNER,
             false,
             0,
         );
@@ -223,13 +255,12 @@ mod tests {
             false,
             &mut system_program_lamports,
             &mut system_program_data,
-            &Pubkey::default(),
+            &DEFAULT_OWNER,
             false,
             0,
         );
         
         // Mock clock sysvar
-        let mut clock_data = vec![0; size_of::<Clock>()];
         let clock = Clock {
             slot: 0,
             epoch_start_timestamp: 0,
@@ -237,6 +268,7 @@ mod tests {
             leader_schedule_epoch: 0,
             unix_timestamp: 100, // Current time
         };
+        let mut clock_data = sysvar_data(&clock);
         let mut clock_lamports = 0;
         let clock_account_info = AccountInfo::new(
             &ctx.clock_sysvar,
@@ -267,6 +299,11 @@ mod tests {
             amount,
             unlock_time,
             tag,
+            locked_destination: None,
+            no_crank: false,
+            emergency_requires_depositor: false,
+            owner_can_release_early: false,
+            idempotency_key: None,
         };
         let instruction_data = instruction.try_to_vec().unwrap();
         
@@ -329,7 +366,7 @@ mod tests {
             false,
             &mut depositor_lamports,
             &mut depositor_data,
-            &Pubkey::default(),
+            &DEFAULT_OWNER,
             false,
             0,
         );
@@ -369,13 +406,12 @@ mod tests {
             false,
             &mut token_program_lamports,
             &mut token_program_data,
-            &Pubkey::default(),
+            &DEFAULT_OWNER,
             false,
             0,
         );
         
         // Mock clock sysvar with current time < unlock time
-        let mut clock_data = vec![0; size_of::<Clock>()];
         let clock = Clock {
             slot: 0,
             epoch_start_timestamp: 0,
@@ -383,6 +419,7 @@ mod tests {
             leader_schedule_epoch: 0,
             unix_timestamp: current_time, // Current time is before unlock time
         };
+        let mut clock_data = sysvar_data(&clock);
         let mut clock_lamports = 0;
         let clock_account_info = AccountInfo::new(
             &ctx.clock_sysvar,
@@ -424,7 +461,7 @@ mod tests {
             Err(ProgramError::Custom(error_code)) => {
                 assert_eq!(error_code, VaultError::UnlockTimeNotReached as u32);
             },
-            _ => panic!("Expected UnlockTimeNotReached error"),
+            other => panic!("Expected UnlockTimeNotReached error, got {:?}", other),
         }
     }
 
@@ -474,7 +511,7 @@ mod tests {
             false,
             &mut depositor_lamports,
             &mut depositor_data,
-            &Pubkey::default(),
+            &DEFAULT_OWNER,
             false,
             0,
         );
@@ -514,13 +551,12 @@ mod tests {
             false,
             &mut token_program_lamports,
             &mut token_program_data,
-            &Pubkey::default(),
+            &DEFAULT_OWNER,
             false,
             0,
         );
         
         // Mock clock sysvar with current time > unlock time
-        let mut clock_data = vec![0; size_of::<Clock>()];
         let clock = Clock {
             slot: 0,
             epoch_start_timestamp: 0,
@@ -528,6 +564,7 @@ mod tests {
             leader_schedule_epoch: 0,
             unix_timestamp: current_time, // Current time is after unlock time
         };
+        let mut clock_data = sysvar_data(&clock);
         let mut clock_lamports = 0;
         let clock_account_info = AccountInfo::new(
             &ctx.clock_sysvar,
@@ -616,7 +653,7 @@ mod tests {
             false,
             &mut unauthorized_lamports,
             &mut unauthorized_data,
-            &Pubkey::default(),
+            &DEFAULT_OWNER,
             false,
             0,
         );
@@ -656,13 +693,12 @@ mod tests {
             false,
             &mut token_program_lamports,
             &mut token_program_data,
-            &Pubkey::default(),
+            &DEFAULT_OWNER,
             false,
             0,
         );
         
         // Mock clock sysvar with current time > unlock time
-        let mut clock_data = vec![0; size_of::<Clock>()];
         let clock = Clock {
             slot: 0,
             epoch_start_timestamp: 0,
@@ -670,6 +706,7 @@ mod tests {
             leader_schedule_epoch: 0,
             unix_timestamp: current_time, // Current time is after unlock time
         };
+        let mut clock_data = sysvar_data(&clock);
         let mut clock_lamports = 0;
         let clock_account_info = AccountInfo::new(
             &ctx.clock_sysvar,
@@ -733,7 +770,7 @@ mod tests {
             amount,
             unlock_time,
         );
-        deposit.withdrawn = true; // Already withdrawn
+        deposit.status = DepositStatus::Withdrawn as u8; // Already withdrawn
         
         vault.deposits.push(deposit);
         vault.deposit_count = 1;
@@ -762,7 +799,7 @@ mod tests {
             false,
             &mut depositor_lamports,
             &mut depositor_data,
-            &Pubkey::default(),
+            &DEFAULT_OWNER,
             false,
             0,
         );
@@ -802,13 +839,12 @@ mod tests {
             false,
             &mut token_program_lamports,
             &mut token_program_data,
-            &Pubkey::default(),
+            &DEFAULT_OWNER,
             false,
             0,
         );
         
         // Mock clock sysvar with current time > unlock time
-        let mut clock_data = vec![0; size_of::<Clock>()];
         let clock = Clock {
             slot: 0,
             epoch_start_timestamp: 0,
@@ -816,6 +852,7 @@ mod tests {
             leader_schedule_epoch: 0,
             unix_timestamp: current_time, // Current time is after unlock time
         };
+        let mut clock_data = sysvar_data(&clock);
         let mut clock_lamports = 0;
         let clock_account_info = AccountInfo::new(
             &ctx.clock_sysvar,
@@ -0,0 +1,101 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        builder, process_instruction, DepositPolicy, Vault, VaultConfig, VaultError, VaultInstruction,
+        TAG_CREATE_VAULT, VERSIONED_TAG_FLAG,
+    };
+
+    fn create(config: VaultConfig) -> (ProgramResult, Vault) {
+        let program_id = time_locked_vault::id();
+        let mut owner = TestAccount::wallet(Pubkey::new_unique()).signer();
+        let mut vault = TestAccount::new(Pubkey::new_unique(), program_id, vec![0; 1000]).writable();
+        let mut system_program = system_program_account();
+        let instruction = builder::create_vault(&program_id, &owner.key, &vault.key, true, config);
+        let accounts = vec![owner.info(), vault.info(), system_program.info()];
+        let result = process_instruction(&program_id, &accounts, &instruction.data);
+        let state = Vault::deserialize(&mut accounts[1].data.borrow().as_ref()).unwrap_or_default();
+        (result, state)
+    }
+
+    #[test]
+    fn test_create_applies_the_config() {
+        let (authority, fee_destination) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (result, vault) = create(VaultConfig {
+            emergency_authority: Some(authority),
+            min_lock_slots: Some(50),
+            deposit_policy: Some(DepositPolicy::Allowlist),
+            tvl_cap: Some(1_000),
+            deposit_fee_lamports: Some(5_000),
+            deposit_fee_destination: Some(fee_destination),
+        });
+        result.unwrap();
+        
+        assert_eq!(vault.emergency_authority, Some(authority));
+        assert_eq!(vault.min_lock_slots, 50);
+        assert_eq!(vault.deposit_policy, DepositPolicy::Allowlist);
+        assert_eq!(vault.tvl_cap, Some(1_000));
+        assert_eq!(vault.deposit_fee_lamports, 5_000);
+        assert_eq!(vault.deposit_fee_destination, fee_destination);
+    }
+
+    #[test]
+    fn test_default_config_matches_an_unconfigured_vault() {
+        let (result, vault) = create(VaultConfig::default());
+        result.unwrap();
+        
+        let unconfigured = Vault { is_initialized: true, owner: vault.owner, ..Vault::default() };
+        assert_eq!(vault.try_to_vec().unwrap(), unconfigured.try_to_vec().unwrap());
+    }
+
+    #[test]
+    fn test_invalid_config_is_rejected_at_creation_and_update() {
+        let invalid = Err(VaultError::InvalidVaultConfig.into());
+        assert_eq!(create(VaultConfig { tvl_cap: Some(0), ..VaultConfig::default() }).0, invalid);
+        let default_authority = VaultConfig { emergency_authority: Some(Pubkey::default()), ..VaultConfig::default() };
+        assert_eq!(create(default_authority).0, invalid);
+        let fee_without_destination = VaultConfig { deposit_fee_lamports: Some(1), ..VaultConfig::default() };
+        assert_eq!(create(fee_without_destination).0, Err(VaultError::InvalidFeeDestination.into()));
+        
+        // The update instructions share the same checks
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut vault = vault_account(&program_id, &Vault { owner, ..Vault::default() }, 1000);
+        let mut signer = TestAccount::wallet(owner).signer();
+        let accounts = vec![signer.info(), vault.info()];
+        let set_cap = VaultInstruction::SetTvlCap { cap: Some(0) }.pack();
+        assert_eq!(process_instruction(&program_id, &accounts, &set_cap), invalid);
+        let set_fee = VaultInstruction::SetDepositFee { lamports: 1, destination: Pubkey::default() }.pack();
+        let no_destination = Err(VaultError::InvalidFeeDestination.into());
+        assert_eq!(process_instruction(&program_id, &accounts, &set_fee), no_destination);
+    }
+
+    #[test]
+    fn test_legacy_create_vault_encodings_still_decode() {
+        let default_create = |skip_registry, emergency_destination| VaultInstruction::CreateVault {
+            skip_registry,
+            arbitration_destination: None,
+            emergency_destination,
+            owner_set: None,
+            config: VaultConfig::default(),
+        };
+        
+        // The original zero-argument form never used the registry
+        assert_eq!(VaultInstruction::unpack(&[TAG_CREATE_VAULT]).unwrap(), default_create(true, None));
+        
+        // The fields from before `config`, unflagged and as version 6
+        let destination = Pubkey::new_unique();
+        let mut legacy = vec![TAG_CREATE_VAULT];
+        legacy.extend((false, None::<Pubkey>, Some(destination), None::<u8>).try_to_vec().unwrap());
+        assert_eq!(VaultInstruction::unpack(&legacy).unwrap(), default_create(false, Some(destination)));
+        let old_versioned = [&[TAG_CREATE_VAULT | VERSIONED_TAG_FLAG, 6], &legacy[1..]].concat();
+        assert_eq!(VaultInstruction::unpack(&old_versioned).unwrap(), default_create(false, Some(destination)));
+        
+        legacy.push(0);
+        assert!(matches!(VaultInstruction::unpack(&legacy), Err(VaultError::InvalidInstructionData)));
+    }
+}
@@ -0,0 +1,140 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{process_instruction, Deposit, Vault, VaultConfig, VaultError, VaultInstruction};
+
+    // Run `instruction` against a vault account holding `data`, signed by a
+    // random key and padded out with throwaway accounts
+    fn run(data: Vec<u8>, instruction: &VaultInstruction) -> ProgramResult {
+        let program_id = Pubkey::new_unique();
+        let token_mint = Pubkey::new_unique();
+        let mut signer = TestAccount::wallet(Pubkey::new_unique()).signer().writable();
+        let mut vault = TestAccount::new(Pubkey::new_unique(), program_id, data).writable();
+        let mut first_tokens = token_account(&token_mint, &signer.key, 1_000);
+        let mut second_tokens = token_account(&token_mint, &vault.key, 1_000);
+        let mut token_program = token_program();
+        let mut system_program = system_program_account();
+        let mut clock = clock_account(0, 0);
+        let accounts = vec![
+            signer.info(),
+            vault.info(),
+            first_tokens.info(),
+            second_tokens.info(),
+            token_program.info(),
+            system_program.info(),
+            clock.info(),
+        ];
+        process_instruction(&program_id, &accounts, &instruction.try_to_vec().unwrap())
+    }
+
+    fn instructions() -> Vec<VaultInstruction> {
+        vec![
+            VaultInstruction::Deposit {
+                amount: 1,
+                unlock_time: 1_000,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            },
+            VaultInstruction::Withdraw { deposit_id: 0 },
+            VaultInstruction::EmergencyWithdraw { deposit_id: 0 },
+            VaultInstruction::CloseVault,
+            VaultInstruction::SetTvlCap { cap: None },
+            VaultInstruction::FreezeDeposit { deposit_id: 0 },
+        ]
+    }
+
+    fn initialized_vault_data() -> Vec<u8> {
+        let vault = Vault {
+            is_initialized: true,
+            deposit_count: 1,
+            deposits: vec![Deposit::default()],
+            ..Vault::default()
+        };
+        vault.try_to_vec().unwrap()
+    }
+
+    #[test]
+    fn test_zeroed_vault_is_uninitialized() {
+        for instruction in instructions() {
+            let result = run(vec![0; 1000], &instruction);
+            assert_eq!(result, Err(VaultError::UninitializedVault.into()), "{:?}", instruction);
+        }
+    }
+
+    #[test]
+    fn test_empty_vault_is_uninitialized() {
+        for instruction in instructions() {
+            let result = run(Vec::new(), &instruction);
+            assert_eq!(result, Err(VaultError::UninitializedVault.into()), "{:?}", instruction);
+        }
+    }
+
+    #[test]
+    fn test_truncated_vault_is_corrupt() {
+        let mut data = initialized_vault_data();
+        data.truncate(data.len() - 1);
+        for instruction in instructions() {
+            let result = run(data.clone(), &instruction);
+            assert_eq!(result, Err(VaultError::CorruptVaultData.into()), "{:?}", instruction);
+        }
+    }
+
+    #[test]
+    fn test_garbage_vault_is_corrupt() {
+        let garbage: Vec<u8> = (0..1000).map(|i| (i * 7 + 3) as u8).collect();
+        for instruction in instructions() {
+            let result = run(garbage.clone(), &instruction);
+            assert_eq!(result, Err(VaultError::CorruptVaultData.into()), "{:?}", instruction);
+        }
+    }
+
+    #[test]
+    fn test_vault_from_before_the_flag_still_loads() {
+        // Vaults created before `is_initialized` was appended read it from
+        // their zero padding
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut data = vec![0; 1000];
+        let vault = Vault { owner, deposit_count: 1, deposits: vec![Deposit::default()], ..Vault::default() };
+        vault.serialize(&mut data.as_mut_slice()).unwrap();
+        assert!(!Vault::from_account_data(&data).unwrap().is_initialized);
+        
+        let mut signer = TestAccount::wallet(owner).signer();
+        let mut vault = TestAccount::new(Pubkey::new_unique(), program_id, data).writable();
+        let accounts = vec![signer.info(), vault.info()];
+        let data = VaultInstruction::SetTvlCap { cap: Some(5) }.pack();
+        process_instruction(&program_id, &accounts, &data).unwrap();
+        
+        assert_eq!(read_vault(&vault).tvl_cap, Some(5));
+    }
+
+    #[test]
+    fn test_create_vault_rejects_initialized_account() {
+        let program_id = Pubkey::new_unique();
+        let mut owner = TestAccount::wallet(Pubkey::new_unique()).signer().writable();
+        let mut vault = vault_account(&program_id, &Vault::default(), 1000);
+        let mut system_program = system_program_account();
+        let accounts = vec![owner.info(), vault.info(), system_program.info()];
+        let data = VaultInstruction::CreateVault {
+            skip_registry: true,
+            arbitration_destination: None,
+            emergency_destination: None,
+            owner_set: None,
+            config: VaultConfig::default(),
+        }
+        .try_to_vec()
+        .unwrap();
+        
+        let result = process_instruction(&program_id, &accounts, &data);
+        
+        assert_eq!(result, Err(VaultError::AccountAlreadyInUse.into()));
+    }
+}
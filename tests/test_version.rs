@@ -0,0 +1,41 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use time_locked_vault::{
+        builder, process_instruction, ProgramVersion, FEATURE_VIEW_DEPOSIT, PROGRAM_VERSION, SUPPORTED_FEATURES,
+    };
+
+    #[test]
+    fn test_get_version_returns_the_crate_version() {
+        let program_id = time_locked_vault::id();
+        let instruction = builder::get_version(&program_id);
+        assert!(instruction.accounts.is_empty());
+        set_clock(0, 0);
+        
+        process_instruction(&program_id, &[], &instruction.data).unwrap();
+        
+        let returned = ProgramVersion::from_return_data(&take_return_data()).unwrap();
+        let crate_version = format!("{}.{}.{}", returned.version.0, returned.version.1, returned.version.2);
+        assert_eq!(crate_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(returned.version, PROGRAM_VERSION);
+        assert_eq!(returned.features, SUPPORTED_FEATURES);
+        assert!(returned.supports(FEATURE_VIEW_DEPOSIT));
+    }
+
+    #[test]
+    fn test_decoder_tolerates_future_versions() {
+        // A later program with a feature bit this client doesn't know and an
+        // extra field after the feature bits
+        let future = ProgramVersion { version: (9, 0, 1), features: FEATURE_VIEW_DEPOSIT | 1 << 63 };
+        let mut data = future.to_return_data();
+        data.extend_from_slice(&[7; 4]);
+        
+        let decoded = ProgramVersion::from_return_data(&data).unwrap();
+        assert_eq!(decoded, future);
+        assert!(decoded.supports(FEATURE_VIEW_DEPOSIT));
+        assert!(!decoded.supports(1 << 62));
+        assert_eq!(ProgramVersion::from_return_data(&data[..10]), None);
+    }
+}
@@ -0,0 +1,64 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+    use time_locked_vault::{builder, process_instruction, Deposit, Vault, VaultError, VaultInstruction};
+
+    fn vault_with_deposit(program_id: &Pubkey) -> (Deposit, TestAccount) {
+        let deposit = Deposit {
+            id: 7,
+            depositor: Pubkey::new_unique(),
+            token_mint: Pubkey::new_unique(),
+            amount: 250,
+            unlock_time: 5_000,
+            guardians: vec![Pubkey::new_unique()],
+            ..Deposit::default()
+        };
+        let vault = Vault { deposit_count: 8, deposits: vec![deposit.clone()], ..Vault::default() };
+        (deposit, vault_account(program_id, &vault, 2000))
+    }
+
+    #[test]
+    fn test_view_returns_the_deposit_without_writing() {
+        let program_id = time_locked_vault::id();
+        let (deposit, mut vault) = vault_with_deposit(&program_id);
+        vault.is_writable = false;
+        let before = vault.data.clone();
+        let instruction = builder::view_deposit(&program_id, &vault.key, 7);
+        assert!(instruction.accounts.iter().all(|meta| !meta.is_signer && !meta.is_writable));
+        take_logs();
+        
+        process_instruction(&program_id, &[vault.info()], &instruction.data).unwrap();
+        
+        let returned = take_return_data();
+        assert_eq!(returned, deposit.try_to_vec().unwrap());
+        let decoded = Deposit::try_from_slice(&returned).unwrap();
+        assert_eq!((decoded.id, decoded.amount, decoded.guardians), (7, 250, deposit.guardians));
+        let logs = take_logs();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].starts_with("Deposit 7: 250 of mint"), "{}", logs[0]);
+        assert_eq!(vault.data, before);
+    }
+
+    #[test]
+    fn test_view_fails_for_missing_deposits_and_foreign_accounts() {
+        let program_id = time_locked_vault::id();
+        let (_, mut vault) = vault_with_deposit(&program_id);
+        
+        let missing = builder::view_deposit(&program_id, &vault.key, 8);
+        assert_eq!(
+            process_instruction(&program_id, &[vault.info()], &missing.data),
+            Err(VaultError::DepositNotFound.into()),
+        );
+        let other_program = Pubkey::new_unique();
+        let foreign = VaultInstruction::ViewDeposit { deposit_id: 7 }.pack();
+        assert_eq!(
+            process_instruction(&other_program, &[vault.info()], &foreign),
+            Err(ProgramError::IncorrectProgramId),
+        );
+        assert!(take_return_data().is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        builder, process_instruction, Deposit, DepositStatus, Vault, VaultInstruction, LOCK_ONE_YEAR_SECS,
+        TAG_LOG_VOTING_POWER, VOTING_POWER_MAX_LOCK,
+    };
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn deposit(depositor: Pubkey, amount: u64, unlock_time: i64) -> Deposit {
+        Deposit { depositor, amount, unlock_time, status: DepositStatus::Active as u8, ..Deposit::default() }
+    }
+
+    fn log_voting_power(vault: &Vault, depositor: &Pubkey, now: i64) -> ProgramResult {
+        let program_id = time_locked_vault::id();
+        let mut vault_info = vault_account(&program_id, vault, 2000);
+        let mut clock = clock_account(1, now);
+        let instruction = builder::log_voting_power(&program_id, &vault_info.key, depositor);
+        process_instruction(&program_id, &[vault_info.info(), clock.info()], &instruction.data)
+    }
+
+    #[test]
+    fn test_voting_power_decays_linearly_to_the_unlock_time() {
+        let max_lock = 4 * LOCK_ONE_YEAR_SECS;
+        let deposit = deposit(Pubkey::new_unique(), 1_000, NOW + max_lock);
+        
+        assert_eq!(deposit.voting_power(NOW, max_lock), 1_000);
+        assert_eq!(deposit.voting_power(NOW + max_lock / 4, max_lock), 750);
+        assert_eq!(deposit.voting_power(NOW + max_lock / 2, max_lock), 500);
+        assert_eq!(deposit.voting_power(NOW + max_lock - 1, max_lock), 0);
+        assert_eq!(deposit.voting_power(NOW + max_lock, max_lock), 0);
+        assert_eq!(deposit.voting_power(NOW + 2 * max_lock, max_lock), 0);
+    }
+
+    #[test]
+    fn test_voting_power_clamps_at_the_max_lock() {
+        let deposit = deposit(Pubkey::new_unique(), u64::MAX, NOW + 10 * LOCK_ONE_YEAR_SECS);
+        
+        // Locking past the max lock earns nothing extra, and no amount overflows
+        assert_eq!(deposit.voting_power(NOW, LOCK_ONE_YEAR_SECS), u64::MAX as u128);
+        assert_eq!(deposit.voting_power(i64::MIN, LOCK_ONE_YEAR_SECS), u64::MAX as u128);
+        assert_eq!(deposit.voting_power(NOW, 0), 0);
+        assert_eq!(deposit.voting_power(NOW, -1), 0);
+    }
+
+    #[test]
+    fn test_withdrawn_deposits_have_no_voting_power() {
+        let mut deposit = deposit(Pubkey::new_unique(), 1_000, NOW + LOCK_ONE_YEAR_SECS);
+        for status in [DepositStatus::Withdrawn, DepositStatus::EmergencyWithdrawn] {
+            deposit.status = status as u8;
+            assert_eq!(deposit.voting_power(NOW, LOCK_ONE_YEAR_SECS), 0);
+        }
+    }
+
+    #[test]
+    fn test_log_voting_power_returns_the_depositors_total() {
+        let (depositor, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut withdrawn = deposit(depositor, 5_000, NOW + VOTING_POWER_MAX_LOCK);
+        withdrawn.status = DepositStatus::Withdrawn as u8;
+        let vault = Vault {
+            is_initialized: true,
+            deposits: vec![
+                deposit(depositor, 1_000, NOW + VOTING_POWER_MAX_LOCK),
+                deposit(depositor, 1_000, NOW + VOTING_POWER_MAX_LOCK / 2),
+                deposit(other, 7_000, NOW + VOTING_POWER_MAX_LOCK),
+                withdrawn,
+            ],
+            ..Vault::default()
+        };
+        assert_eq!(vault.total_voting_power(&depositor, NOW, VOTING_POWER_MAX_LOCK), 1_500);
+        
+        take_events();
+        log_voting_power(&vault, &depositor, NOW).unwrap();
+        assert_eq!(take_return_data(), 1_500u128.to_le_bytes());
+        let events = take_events();
+        assert_eq!(events.last().unwrap()[0], b"voting_power");
+        assert_eq!(events.last().unwrap()[2], 1_500u128.to_le_bytes());
+        
+        // A depositor without deposits has no power
+        log_voting_power(&vault, &Pubkey::new_unique(), NOW).unwrap();
+        assert_eq!(take_return_data(), 0u128.to_le_bytes());
+    }
+
+    #[test]
+    fn test_log_voting_power_round_trips() {
+        let depositor = Pubkey::new_unique();
+        let data = VaultInstruction::LogVotingPower { depositor }.pack();
+        assert_eq!(data[0], TAG_LOG_VOTING_POWER);
+        assert_eq!(VaultInstruction::unpack(&data).unwrap(), VaultInstruction::LogVotingPower { depositor });
+    }
+}
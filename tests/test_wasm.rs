@@ -0,0 +1,55 @@
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use borsh::BorshSerialize;
+use js_sys::{Array, Reflect, Uint8Array};
+use solana_program::pubkey::Pubkey;
+use time_locked_vault::{
+    wasm::{js_create_deposit_ix, js_decode_vault},
+    Deposit, Vault,
+};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+fn get(object: &JsValue, key: &str) -> JsValue {
+    Reflect::get(object, &key.into()).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn test_decode_vault_round_trip() {
+    let owner = Pubkey::new_unique();
+    let vault = Vault {
+        is_initialized: true,
+        owner,
+        deposit_count: 1,
+        deposits: vec![Deposit { amount: u64::MAX, ..Deposit::default() }],
+        ..Vault::default()
+    };
+    
+    let decoded = js_decode_vault(&vault.try_to_vec().unwrap()).unwrap();
+    
+    assert_eq!(get(&decoded, "owner").as_string(), Some(owner.to_string()));
+    let deposits = Array::from(&get(&decoded, "deposits"));
+    assert_eq!(get(&deposits.get(0), "amount").as_string(), Some(u64::MAX.to_string()));
+}
+
+#[wasm_bindgen_test]
+fn test_decode_vault_rejects_garbage() {
+    assert!(js_decode_vault(&[1, 2, 3]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_create_deposit_ix() {
+    let mut keys: Vec<String> = (0..5).map(|_| Pubkey::new_unique().to_string()).collect();
+    keys[0] = time_locked_vault::id().to_string();
+    
+    let ix = js_create_deposit_ix(&keys[0], &keys[1], &keys[2], &keys[3], &keys[4], 100, 1_000, &[0; 32]).unwrap();
+    
+    assert_eq!(get(&ix, "programId").as_string(), Some(keys[0].clone()));
+    let depositor = Array::from(&get(&ix, "accounts")).get(0);
+    assert_eq!(get(&depositor, "pubkey").as_string(), Some(keys[1].clone()));
+    assert_eq!(get(&depositor, "isSigner").as_bool(), Some(true));
+    assert!(Uint8Array::new(&get(&ix, "data")).length() > 0);
+    assert!(js_create_deposit_ix(&keys[0], "bad", &keys[2], &keys[3], &keys[4], 100, 1_000, &[0; 32]).is_err());
+    // A program id of another cluster is an error rather than a panic
+    assert!(js_create_deposit_ix(&keys[1], &keys[1], &keys[2], &keys[3], &keys[4], 100, 1_000, &[0; 32]).is_err());
+}
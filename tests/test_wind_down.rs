@@ -0,0 +1,190 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{
+        preflight::preflight_deposit, process_instruction, Deposit, Vault, VaultError, VaultInstruction,
+    };
+
+    const NOW: i64 = 1_000;
+
+    struct WindDown {
+        program_id: Pubkey,
+        owner: Pubkey,
+        authority: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl WindDown {
+        // A vault with one matured and one locked deposit
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let owner = Pubkey::new_unique();
+            let authority = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let deposit = |id, unlock_time| Deposit {
+                id,
+                depositor,
+                token_mint,
+                amount: 100,
+                unlock_time,
+                ..Deposit::default()
+            };
+            let vault = Vault {
+                owner,
+                emergency_authority: Some(authority),
+                deposit_count: 2,
+                deposits: vec![deposit(0, NOW - 1), deposit(1, i64::MAX)],
+                total_locked: 200,
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                owner,
+                authority,
+                depositor,
+                token_mint,
+                vault: vault_account(&program_id, &vault, 4000),
+            }
+        }
+        
+        fn send_owner(&mut self, signer: Pubkey, instruction: VaultInstruction) -> ProgramResult {
+            let mut owner = TestAccount::wallet(signer).signer();
+            let accounts = vec![owner.info(), self.vault.info()];
+            take_events();
+            process_instruction(&self.program_id, &accounts, &instruction.pack())
+        }
+        
+        fn deposit_accounts(&mut self, instruction: VaultInstruction) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut source = token_account(&self.token_mint, &self.depositor, 1_000);
+            let mut vault_token = token_account(&self.token_mint, &self.vault.key, 200);
+            let mut source_b = token_account(&self.token_mint, &self.depositor, 1_000);
+            let mut vault_token_b = token_account(&self.token_mint, &self.vault.key, 200);
+            let mut token_program = token_program();
+            let mut system_program = system_program_account();
+            let mut clock = clock_account(0, NOW);
+            let mut accounts = vec![depositor.info(), self.vault.info(), source.info(), vault_token.info()];
+            if let VaultInstruction::DepositPair { .. } = instruction {
+                accounts.push(source_b.info());
+                accounts.push(vault_token_b.info());
+            }
+            accounts.extend([token_program.info(), system_program.info(), clock.info()]);
+            process_instruction(&self.program_id, &accounts, &instruction.pack())
+        }
+        
+        fn deposit(&mut self) -> ProgramResult {
+            self.deposit_accounts(VaultInstruction::Deposit {
+                amount: 10,
+                unlock_time: NOW + 10,
+                tag: [0; 32],
+                locked_destination: None,
+                no_crank: false,
+                emergency_requires_depositor: false,
+                owner_can_release_early: false,
+                idempotency_key: None,
+            })
+        }
+        
+        fn withdraw(&mut self, deposit_id: u64) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 200);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, NOW);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            process_instruction(&self.program_id, &accounts, &VaultInstruction::Withdraw { deposit_id }.pack())
+        }
+        
+        fn emergency_withdraw(&mut self, deposit_id: u64) -> ProgramResult {
+            set_clock(0, NOW);
+            let mut authority = TestAccount::wallet(self.authority).signer();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 200);
+            let mut token_program = token_program();
+            let mut depositor = TestAccount::wallet(self.depositor);
+            let accounts = vec![
+                authority.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                depositor.info(),
+            ];
+            let data = VaultInstruction::EmergencyWithdraw { deposit_id }.pack();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_closed_vault_pays_out_but_takes_nothing_new() {
+        let mut ctx = WindDown::new();
+        let owner = ctx.owner;
+        
+        ctx.send_owner(owner, VaultInstruction::CloseToNewDeposits).unwrap();
+        assert_eq!(take_events(), vec![vec![b"deposits_open".to_vec(), vec![0]]]);
+        let vault = read_vault(&ctx.vault);
+        assert!(!vault.accepting_deposits());
+        assert!(vault.to_string().contains("tokens locked, closed to new deposits;"));
+        
+        let closed = Err(VaultError::DepositsClosed.into());
+        assert_eq!(ctx.deposit(), closed);
+        let pair = VaultInstruction::DepositPair {
+            amount_a: 10,
+            amount_b: 10,
+            unlock_time: NOW + 10,
+            tag: [0; 32],
+        };
+        assert_eq!(ctx.deposit_accounts(pair), closed);
+        let many = VaultInstruction::DepositMany { items: vec![(10, NOW + 10, [0; 32])] };
+        assert_eq!(ctx.deposit_accounts(many), closed);
+        assert!(matches!(
+            preflight_deposit(&read_vault(&ctx.vault), &ctx.depositor, 10, NOW + 10, NOW, None),
+            Err(VaultError::DepositsClosed),
+        ));
+        
+        // Everyone still gets their money out
+        ctx.withdraw(0).unwrap();
+        ctx.emergency_withdraw(1).unwrap();
+        let vault = read_vault(&ctx.vault);
+        assert_eq!(vault.deposits.len(), 2);
+        assert!(vault.deposits.iter().all(|deposit| deposit.is_withdrawn()));
+        assert_eq!(vault.total_locked, 0);
+    }
+
+    #[test]
+    fn test_reopened_vault_accepts_deposits() {
+        let mut ctx = WindDown::new();
+        let owner = ctx.owner;
+        ctx.send_owner(owner, VaultInstruction::CloseToNewDeposits).unwrap();
+        
+        ctx.send_owner(owner, VaultInstruction::ReopenDeposits).unwrap();
+        
+        assert_eq!(take_events(), vec![vec![b"deposits_open".to_vec(), vec![1]]]);
+        ctx.deposit().unwrap();
+        assert_eq!(read_vault(&ctx.vault).deposits.len(), 3);
+    }
+
+    #[test]
+    fn test_only_owner_closes_deposits() {
+        let mut ctx = WindDown::new();
+        
+        let result = ctx.send_owner(Pubkey::new_unique(), VaultInstruction::CloseToNewDeposits);
+        
+        assert_eq!(result, Err(VaultError::NotVaultOwner.into()));
+        assert!(read_vault(&ctx.vault).accepting_deposits());
+        ctx.deposit().unwrap();
+    }
+}
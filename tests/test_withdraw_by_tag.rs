@@ -0,0 +1,132 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey, system_program};
+    use std::ops::{Deref, DerefMut};
+    use time_locked_vault::{find_config_address, Deposit, Vault, VaultInstruction};
+
+    const NOW: i64 = 1_700_000_000;
+    const PAYDAY: [u8; 32] = [1; 32];
+    const SAVINGS: [u8; 32] = [2; 32];
+
+    // The shared harness, whose mint stands for USDC, plus a second mint
+    struct ByTag {
+        base: Harness,
+        bonk: Pubkey,
+    }
+
+    impl Deref for ByTag {
+        type Target = Harness;
+        
+        fn deref(&self) -> &Harness {
+            &self.base
+        }
+    }
+
+    impl DerefMut for ByTag {
+        fn deref_mut(&mut self) -> &mut Harness {
+            &mut self.base
+        }
+    }
+
+    impl ByTag {
+        // Matured and locked deposits under two tags and two mints, plus one
+        // of another depositor
+        fn new() -> Self {
+            let depositor = Pubkey::new_unique();
+            let (usdc, bonk) = (Pubkey::new_unique(), Pubkey::new_unique());
+            let deposit = |id, token_mint, amount, unlock_time, tag| Deposit {
+                id,
+                depositor,
+                token_mint,
+                amount,
+                unlock_time,
+                tag,
+                ..Deposit::default()
+            };
+            let mut stranger = deposit(5, usdc, 40, NOW - 1, PAYDAY);
+            stranger.depositor = Pubkey::new_unique();
+            let vault = Vault {
+                deposit_count: 6,
+                deposits: vec![
+                    deposit(0, usdc, 100, NOW - 10, PAYDAY),
+                    deposit(1, usdc, 50, NOW, PAYDAY),
+                    deposit(2, usdc, 30, NOW - 10, SAVINGS),
+                    deposit(3, usdc, 70, NOW + 10, PAYDAY),
+                    deposit(4, bonk, 20, NOW - 10, PAYDAY),
+                    stranger,
+                ],
+                ..Vault::default()
+            };
+            Self { base: Harness::with_depositor(&vault, 2000, depositor, usdc), bonk }
+        }
+        
+        fn withdraw_by_tag(&mut self, tag: [u8; 32], mints: &[Pubkey]) -> ProgramResult {
+            let (vault, depositor) = (self.vault.key, self.depositor);
+            let mut token_program = token_program();
+            let mut clock = clock_account(1, NOW);
+            let mut config = TestAccount::new(find_config_address(&self.program_id).0, system_program::id(), vec![]);
+            let mut pairs: Vec<_> = mints
+                .iter()
+                .map(|mint| (token_account(mint, &vault, 1000), token_account(mint, &depositor, 0)))
+                .collect();
+            let mut accounts = vec![&mut token_program, &mut clock, &mut config];
+            for (source, destination) in pairs.iter_mut() {
+                accounts.push(source);
+                accounts.push(destination);
+            }
+            let data = VaultInstruction::WithdrawByTag { tag }.pack();
+            self.base.call(depositor, &data, &mut accounts)
+        }
+        
+        fn withdrawn_ids(&self) -> Vec<u64> {
+            read_vault(&self.vault).deposits.iter().filter(|d| d.is_withdrawn()).map(|d| d.id).collect()
+        }
+    }
+
+    #[test]
+    fn test_one_transfer_per_mint() {
+        let mut harness = ByTag::new();
+        let (usdc, bonk) = (harness.token_mint, harness.bonk);
+        take_invocations();
+        
+        harness.withdraw_by_tag(PAYDAY, &[usdc, bonk]).unwrap();
+        
+        let amounts: Vec<u64> = token_transfers(&take_invocations()).iter().map(|(_, amount)| *amount).collect();
+        assert_eq!(amounts, vec![150, 20]);
+        assert_eq!(harness.withdrawn_ids(), vec![0, 1, 4]);
+        assert!(take_return_data().is_empty());
+    }
+
+    #[test]
+    fn test_missing_mint_accounts_skipped() {
+        let mut harness = ByTag::new();
+        let usdc = harness.token_mint;
+        take_invocations();
+        
+        harness.withdraw_by_tag(PAYDAY, &[usdc]).unwrap();
+        
+        let amounts: Vec<u64> = token_transfers(&take_invocations()).iter().map(|(_, amount)| *amount).collect();
+        assert_eq!(amounts, vec![150]);
+        assert_eq!(harness.withdrawn_ids(), vec![0, 1]);
+        assert_eq!(take_return_data(), 4u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_no_match_is_a_no_op() {
+        let mut harness = ByTag::new();
+        let (usdc, bonk) = (harness.token_mint, harness.bonk);
+        take_invocations();
+        
+        harness.withdraw_by_tag([3; 32], &[usdc, bonk]).unwrap();
+        
+        assert!(token_transfers(&take_invocations()).is_empty());
+        assert!(harness.withdrawn_ids().is_empty());
+        
+        // The other tag only releases its own deposit
+        harness.withdraw_by_tag(SAVINGS, &[usdc]).unwrap();
+        assert_eq!(harness.withdrawn_ids(), vec![2]);
+    }
+}
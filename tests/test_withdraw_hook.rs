@@ -0,0 +1,240 @@
+mod common;
+mod hook_program;
+
+use common::bank::{send, signing_vault, token_account, token_balance, vault_account, vault_program_test, vault_state};
+use hook_program::{HOOK_FAILED, LEDGER_LEN, MODE_FAIL, MODE_RECORD, MODE_REENTER};
+use solana_program::{
+    clock::Clock,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    sysvar,
+};
+use solana_program_test::{processor, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use time_locked_vault::{builder, find_config_address, Vault, VaultError, VaultInstruction, WithdrawHookData};
+
+struct Harness {
+    context: ProgramTestContext,
+    hook: Pubkey,
+    ledger: Pubkey,
+    vault: Keypair,
+    source: Pubkey,
+    vault_tokens: Pubkey,
+}
+
+impl Harness {
+    // A vault calling the sample hook, holding deposits of 60 and 40 of the
+    // payer's tokens that have unlocked; the hook's ledger starts in `mode`
+    async fn new(mode: u8) -> Self {
+        let vault_program = time_locked_vault::id();
+        let hook = Pubkey::new_unique();
+        let vault = signing_vault();
+        let (ledger, source, vault_tokens, mint) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        
+        let mut program_test = vault_program_test();
+        program_test.add_program("withdraw_hook", hook, processor!(hook_program::process));
+        
+        let state = Vault {
+            is_initialized: true,
+            owner: Pubkey::new_unique(),
+            hook_program: Some(hook),
+            ..Vault::default()
+        };
+        program_test.add_account(vault.pubkey(), vault_account(&state));
+        let mut ledger_data = vec![0; LEDGER_LEN];
+        ledger_data[0] = mode;
+        program_test.add_account(
+            ledger,
+            Account { lamports: 1_000_000_000, data: ledger_data, owner: hook, ..Account::default() },
+        );
+        let context = program_test.start_with_context().await;
+        let payer = context.payer.pubkey();
+        let mut harness = Self { context, hook, ledger, vault, source, vault_tokens };
+        harness.context.set_account(&source, &token_account(&mint, &payer, 100).into());
+        harness.context.set_account(&vault_tokens, &token_account(&mint, &harness.vault.pubkey(), 0).into());
+        
+        let mut clock: Clock = harness.context.banks_client.get_sysvar().await.unwrap();
+        let unlock_time = clock.unix_timestamp + 3_600;
+        for amount in [60, 40] {
+            let instruction = builder::deposit(
+                &vault_program,
+                &payer,
+                &harness.vault.pubkey(),
+                &source,
+                &vault_tokens,
+                amount,
+                unlock_time,
+                [0; 32],
+            );
+            harness.send(instruction, &[]).await.unwrap();
+        }
+        
+        clock.slot += 10;
+        clock.unix_timestamp = unlock_time;
+        harness.context.set_sysvar(&clock);
+        harness
+    }
+
+    async fn send(&mut self, instruction: Instruction, signers: &[&Keypair]) -> Result<(), TransactionError> {
+        send(&mut self.context, &[instruction], signers).await
+    }
+
+    // A `Withdraw` of deposit 0 back to the payer, without the hook accounts
+    fn withdraw_instruction(&self) -> Instruction {
+        let mut instruction = builder::withdraw(
+            &time_locked_vault::id(),
+            &self.context.payer.pubkey(),
+            &self.vault.pubkey(),
+            &self.source,
+            &self.vault_tokens,
+            0,
+        );
+        // The vault signs the payout from its token account
+        instruction.accounts[1].is_signer = true;
+        instruction
+    }
+
+    // A `WithdrawByTag` of both (untagged) deposits back to the payer, without
+    // the hook accounts
+    fn withdraw_by_tag_instruction(&self) -> Instruction {
+        let program_id = time_locked_vault::id();
+        Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(self.context.payer.pubkey(), true),
+                AccountMeta::new(self.vault.pubkey(), true),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(find_config_address(&program_id).0, false),
+                AccountMeta::new(self.vault_tokens, false),
+                AccountMeta::new(self.source, false),
+            ],
+            data: VaultInstruction::WithdrawByTag { tag: [0; 32] }.pack(),
+        }
+    }
+
+    async fn withdraw(&mut self, hook_accounts: &[AccountMeta]) -> Result<(), TransactionError> {
+        let instruction = builder::with_withdraw_hook(self.withdraw_instruction(), &self.hook, hook_accounts);
+        let vault = self.vault.insecure_clone();
+        self.send(instruction, &[&vault]).await
+    }
+
+    async fn vault_state(&mut self) -> Vault {
+        vault_state(&mut self.context, self.vault.pubkey()).await
+    }
+
+    async fn token_balance(&mut self, key: Pubkey) -> u64 {
+        token_balance(&mut self.context, key).await
+    }
+
+    // The number of withdrawals the hook recorded and their total amount
+    async fn ledger(&mut self) -> (u64, u64) {
+        let account = self.context.banks_client.get_account(self.ledger).await.unwrap().unwrap();
+        let count = u64::from_le_bytes(account.data[1..9].try_into().unwrap());
+        let total = u64::from_le_bytes(account.data[9..17].try_into().unwrap());
+        (count, total)
+    }
+
+    // Neither deposit has moved
+    async fn assert_untouched(&mut self) {
+        let vault = self.vault_state().await;
+        assert!(vault.deposits.iter().all(|deposit| !deposit.is_withdrawn()));
+        assert!(!vault.reentrancy_guard);
+        assert_eq!(self.token_balance(self.vault_tokens).await, 100);
+        assert_eq!(self.ledger().await, (0, 0));
+    }
+}
+
+#[tokio::test]
+async fn test_hook_records_the_withdrawal() {
+    let mut harness = Harness::new(MODE_RECORD).await;
+    let ledger = AccountMeta::new(harness.ledger, false);
+
+    harness.withdraw(&[ledger]).await.unwrap();
+
+    let vault = harness.vault_state().await;
+    assert!(vault.deposits[0].is_withdrawn());
+    assert!(!vault.reentrancy_guard);
+    assert_eq!(harness.token_balance(harness.source).await, 60);
+    assert_eq!(harness.ledger().await, (1, 60));
+}
+
+#[tokio::test]
+async fn test_hook_failure_fails_the_withdrawal() {
+    let mut harness = Harness::new(MODE_FAIL).await;
+    let ledger = AccountMeta::new(harness.ledger, false);
+
+    let result = harness.withdraw(&[ledger]).await;
+
+    assert_eq!(result, Err(TransactionError::InstructionError(0, InstructionError::Custom(HOOK_FAILED))));
+    harness.assert_untouched().await;
+}
+
+#[tokio::test]
+async fn test_hook_cannot_reenter_the_vault() {
+    let mut harness = Harness::new(MODE_REENTER).await;
+    let mut hook_accounts = vec![
+        AccountMeta::new(harness.ledger, false),
+        AccountMeta::new_readonly(harness.hook, false),
+    ];
+    hook_accounts.extend(harness.withdraw_instruction().accounts);
+
+    let result = harness.withdraw(&hook_accounts).await;
+
+    let reentered = InstructionError::Custom(VaultError::ReentrancyDetected as u32);
+    assert_eq!(result, Err(TransactionError::InstructionError(0, reentered)));
+    harness.assert_untouched().await;
+}
+
+#[tokio::test]
+async fn test_withdrawal_needs_the_hook_program() {
+    let mut harness = Harness::new(MODE_RECORD).await;
+    let instruction = harness.withdraw_instruction();
+    let vault = harness.vault.insecure_clone();
+
+    let result = harness.send(instruction, &[&vault]).await;
+
+    let missing = InstructionError::Custom(VaultError::WithdrawHookMissing as u32);
+    assert_eq!(result, Err(TransactionError::InstructionError(0, missing)));
+    harness.assert_untouched().await;
+}
+
+#[tokio::test]
+async fn test_withdraw_by_tag_calls_the_hook_for_each_deposit() {
+    let mut harness = Harness::new(MODE_RECORD).await;
+    let vault = harness.vault.insecure_clone();
+
+    // Leaving the hook out fails like it does for `Withdraw`
+    let result = harness.send(harness.withdraw_by_tag_instruction(), &[&vault]).await;
+    let missing = InstructionError::Custom(VaultError::WithdrawHookMissing as u32);
+    assert_eq!(result, Err(TransactionError::InstructionError(0, missing)));
+    harness.assert_untouched().await;
+
+    let ledger = AccountMeta::new(harness.ledger, false);
+    let instruction = builder::with_withdraw_hook(harness.withdraw_by_tag_instruction(), &harness.hook, &[ledger]);
+    harness.send(instruction, &[&vault]).await.unwrap();
+
+    assert!(harness.vault_state().await.deposits.iter().all(|deposit| deposit.is_withdrawn()));
+    assert_eq!(harness.token_balance(harness.source).await, 100);
+    assert_eq!(harness.ledger().await, (2, 100));
+}
+
+#[test]
+fn test_hook_data_round_trips() {
+    let data = WithdrawHookData {
+        vault: Pubkey::new_unique(),
+        deposit_id: 3,
+        depositor: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        amount: 60,
+    };
+    let packed = data.pack();
+    assert_eq!(packed[..8], time_locked_vault::WITHDRAW_HOOK_DISCRIMINATOR);
+    assert_eq!(WithdrawHookData::unpack(&packed).unwrap(), data);
+    assert!(WithdrawHookData::unpack(&packed[8..]).is_err());
+}
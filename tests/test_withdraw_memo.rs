@@ -0,0 +1,92 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{process_instruction, Deposit, Vault, VaultError, VaultInstruction, MAX_MEMO_LEN};
+
+    const NOW: i64 = 1_000;
+
+    struct MemoContext {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl MemoContext {
+        // A vault with one matured deposit
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor,
+                    token_mint,
+                    amount: 100,
+                    unlock_time: NOW - 1,
+                    ..Deposit::default()
+                }],
+                total_locked: 100,
+                ..Vault::default()
+            };
+            Self { program_id, depositor, token_mint, vault: vault_account(&program_id, &vault, 2000) }
+        }
+        
+        fn withdraw(&mut self, memo: &[u8], memo_program: Pubkey) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 100);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, NOW);
+            let mut memo_program = TestAccount::new(memo_program, Pubkey::default(), vec![]);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+                memo_program.info(),
+            ];
+            take_invocations();
+            let data = VaultInstruction::WithdrawWithMemo { deposit_id: 0, memo: memo.to_vec() }.pack();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_memo_follows_the_payout() {
+        let mut ctx = MemoContext::new();
+        
+        ctx.withdraw("invoice #42 ✓".as_bytes(), spl_memo::id()).unwrap();
+        
+        let invocations = take_invocations();
+        assert_eq!(invocations.len(), 2);
+        assert_eq!(token_transfers(&invocations[..1])[0].1, 100);
+        assert_eq!(invocations[1].program_id, spl_memo::id());
+        assert_eq!(invocations[1].data, "invoice #42 ✓".as_bytes());
+        assert!(invocations[1].accounts.is_empty());
+        assert!(read_vault(&ctx.vault).deposits[0].is_withdrawn());
+    }
+
+    #[test]
+    fn test_invalid_memos_are_rejected_before_paying() {
+        let mut ctx = MemoContext::new();
+        let invalid = Err(VaultError::InvalidMemo.into());
+        
+        assert_eq!(ctx.withdraw(&[b'a'; MAX_MEMO_LEN + 1], spl_memo::id()), invalid);
+        assert_eq!(ctx.withdraw(&[0xff, 0xfe], spl_memo::id()), invalid);
+        assert_eq!(ctx.withdraw(b"", spl_memo::id()), invalid);
+        assert_eq!(ctx.withdraw(b"payroll", Pubkey::new_unique()), Err(VaultError::InvalidMemoProgram.into()));
+        
+        assert!(take_invocations().is_empty());
+        assert!(!read_vault(&ctx.vault).deposits[0].is_withdrawn());
+        ctx.withdraw(&[b'a'; MAX_MEMO_LEN], spl_memo::id()).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+    use time_locked_vault::{process_instruction, Deposit, Vault, VaultError, VaultInstruction};
+
+    const UNLOCK_TIME: i64 = 10_000;
+    const DELAY: i64 = 3_600;
+
+    struct QueueContext {
+        program_id: Pubkey,
+        depositor: Pubkey,
+        emergency_authority: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl QueueContext {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let emergency_authority = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let vault = Vault {
+                owner: Pubkey::new_unique(),
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor,
+                    token_mint,
+                    amount: 1_000,
+                    unlock_time: UNLOCK_TIME,
+                    ..Deposit::default()
+                }],
+                emergency_authority: Some(emergency_authority),
+                withdraw_delay: DELAY,
+                ..Vault::default()
+            };
+            let mut ctx = Self {
+                program_id,
+                depositor,
+                emergency_authority,
+                token_mint,
+                vault: vault_account(&program_id, &vault, 1000),
+            };
+            let depositor = ctx.depositor;
+            ctx.send(&depositor, VaultInstruction::EnableQueuedWithdrawals { deposit_id: 0 }, None)
+                .unwrap();
+            ctx
+        }
+        
+        // Send a two-account deposit instruction, appending the clock when given
+        fn send(&mut self, signer: &Pubkey, instruction: VaultInstruction, now: Option<i64>) -> ProgramResult {
+            let mut signer = TestAccount::wallet(*signer).signer();
+            let mut clock = clock_account(0, now.unwrap_or_default());
+            let mut accounts = vec![signer.info(), self.vault.info()];
+            if now.is_some() {
+                accounts.push(clock.info());
+            }
+            process_instruction(&self.program_id, &accounts, &instruction.try_to_vec().unwrap())
+        }
+        
+        fn request(&mut self, now: i64) -> ProgramResult {
+            let depositor = self.depositor;
+            self.send(&depositor, VaultInstruction::RequestWithdraw { deposit_id: 0 }, Some(now))
+        }
+        
+        fn cancel(&mut self, signer: &Pubkey) -> ProgramResult {
+            self.send(signer, VaultInstruction::CancelWithdrawRequest { deposit_id: 0 }, None)
+        }
+        
+        fn withdraw(&mut self, instruction: VaultInstruction, now: i64) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut destination = token_account(&self.token_mint, &self.depositor, 0);
+            let mut source = token_account(&self.token_mint, &self.vault.key, 1_000);
+            let mut token_program = token_program();
+            let mut clock = clock_account(0, now);
+            let accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                destination.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+            ];
+            process_instruction(&self.program_id, &accounts, &instruction.try_to_vec().unwrap())
+        }
+        
+        fn claim(&mut self, now: i64) -> ProgramResult {
+            self.withdraw(VaultInstruction::ClaimWithdraw { deposit_id: 0 }, now)
+        }
+    }
+
+    #[test]
+    fn test_direct_withdraw_is_replaced() {
+        let mut ctx = QueueContext::new();
+        
+        let result = ctx.withdraw(VaultInstruction::Withdraw { deposit_id: 0 }, UNLOCK_TIME);
+        
+        assert_eq!(result, Err(VaultError::WithdrawalQueueRequired.into()));
+    }
+
+    #[test]
+    fn test_claim_without_request() {
+        let mut ctx = QueueContext::new();
+        
+        assert_eq!(ctx.claim(UNLOCK_TIME), Err(VaultError::NoPendingWithdrawal.into()));
+    }
+
+    #[test]
+    fn test_request_then_claim_after_delay() {
+        let mut ctx = QueueContext::new();
+        ctx.request(UNLOCK_TIME).unwrap();
+        assert_eq!(ctx.request(UNLOCK_TIME + 1), Err(VaultError::WithdrawalAlreadyRequested.into()));
+        
+        assert_eq!(ctx.claim(UNLOCK_TIME + DELAY - 1), Err(VaultError::WithdrawalDelayActive.into()));
+        ctx.claim(UNLOCK_TIME + DELAY).unwrap();
+        
+        let deposit = &read_vault(&ctx.vault).deposits[0];
+        assert!(deposit.is_withdrawn());
+        assert_eq!(deposit.withdraw_requested_at, None);
+    }
+
+    #[test]
+    fn test_claim_waits_for_unlock_time() {
+        let mut ctx = QueueContext::new();
+        ctx.request(0).unwrap();
+        
+        // The delay has long elapsed, but the deposit is still locked
+        assert_eq!(ctx.claim(UNLOCK_TIME - 1), Err(VaultError::UnlockTimeNotReached.into()));
+        ctx.claim(UNLOCK_TIME).unwrap();
+    }
+
+    #[test]
+    fn test_depositor_cancels_request() {
+        let mut ctx = QueueContext::new();
+        let depositor = ctx.depositor;
+        ctx.request(UNLOCK_TIME).unwrap();
+        
+        ctx.cancel(&depositor).unwrap();
+        
+        assert_eq!(ctx.claim(UNLOCK_TIME + DELAY), Err(VaultError::NoPendingWithdrawal.into()));
+        assert_eq!(ctx.cancel(&depositor), Err(VaultError::NoPendingWithdrawal.into()));
+    }
+
+    #[test]
+    fn test_emergency_authority_cancels_request() {
+        let mut ctx = QueueContext::new();
+        let emergency_authority = ctx.emergency_authority;
+        ctx.request(UNLOCK_TIME).unwrap();
+        
+        ctx.cancel(&emergency_authority).unwrap();
+        
+        // A fresh request restarts the delay
+        ctx.request(UNLOCK_TIME + DELAY).unwrap();
+        assert_eq!(ctx.claim(UNLOCK_TIME + DELAY), Err(VaultError::WithdrawalDelayActive.into()));
+        ctx.claim(UNLOCK_TIME + 2 * DELAY).unwrap();
+    }
+
+    #[test]
+    fn test_stranger_cannot_cancel_request() {
+        let mut ctx = QueueContext::new();
+        ctx.request(UNLOCK_TIME).unwrap();
+        
+        let result = ctx.cancel(&Pubkey::new_unique());
+        
+        assert_eq!(result, Err(VaultError::UnauthorizedWithdrawal.into()));
+        assert!(read_vault(&ctx.vault).deposits[0].withdraw_requested_at.is_some());
+    }
+}
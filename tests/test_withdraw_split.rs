@@ -0,0 +1,159 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::*;
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey, system_program};
+    use time_locked_vault::{
+        find_config_address, process_instruction, Deposit, Vault, VaultError, VaultInstruction, MAX_WITHDRAW_SPLITS,
+    };
+
+    const NOW: i64 = 1_700_000_000;
+    const AMOUNT: u64 = 1_000;
+
+    struct SplitContext {
+        program_id: Pubkey,
+        owner: Pubkey,
+        depositor: Pubkey,
+        token_mint: Pubkey,
+        vault: TestAccount,
+    }
+
+    impl SplitContext {
+        // One matured deposit of `AMOUNT` tokens
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let owner = Pubkey::new_unique();
+            let depositor = Pubkey::new_unique();
+            let token_mint = Pubkey::new_unique();
+            let vault = Vault {
+                owner,
+                deposit_count: 1,
+                deposits: vec![Deposit {
+                    id: 0,
+                    depositor,
+                    token_mint,
+                    amount: AMOUNT,
+                    unlock_time: NOW - 1,
+                    ..Deposit::default()
+                }],
+                total_locked: AMOUNT,
+                ..Vault::default()
+            };
+            Self {
+                program_id,
+                owner,
+                depositor,
+                token_mint,
+                vault: vault_account(&program_id, &vault, 2000),
+            }
+        }
+        
+        fn allow_owner_approval(&mut self) {
+            let mut owner = TestAccount::wallet(self.owner).signer();
+            let accounts = vec![owner.info(), self.vault.info()];
+            let data = VaultInstruction::SetOwnerApprovedSplits { enabled: true }.pack();
+            process_instruction(&self.program_id, &accounts, &data).unwrap();
+        }
+        
+        // Withdraw with one `(destination owner, mint)` account per split
+        fn withdraw(
+            &mut self,
+            splits: &[u64],
+            destinations: &[(Pubkey, Pubkey)],
+            cosigner: Option<Pubkey>,
+        ) -> ProgramResult {
+            let mut depositor = TestAccount::wallet(self.depositor).signer();
+            let mut source = token_account(&self.token_mint, &self.vault.key, AMOUNT);
+            let mut token_program = token_program();
+            let mut clock = clock_account(1, NOW);
+            let mut config = TestAccount::new(find_config_address(&self.program_id).0, system_program::id(), vec![]);
+            let mut destinations: Vec<_> =
+                destinations.iter().map(|(owner, mint)| token_account(mint, owner, 0)).collect();
+            let mut cosigner = cosigner.map(|key| TestAccount::wallet(key).signer());
+            let mut accounts = vec![
+                depositor.info(),
+                self.vault.info(),
+                source.info(),
+                token_program.info(),
+                clock.info(),
+                config.info(),
+            ];
+            accounts.extend(destinations.iter_mut().map(|destination| destination.info()));
+            accounts.extend(cosigner.as_mut().map(|cosigner| cosigner.info()));
+            take_invocations();
+            let data = VaultInstruction::WithdrawSplit { deposit_id: 0, splits: splits.to_vec() }.pack();
+            process_instruction(&self.program_id, &accounts, &data)
+        }
+        
+        fn is_withdrawn(&self) -> bool {
+            read_vault(&self.vault).deposits[0].is_withdrawn()
+        }
+    }
+
+    #[test]
+    fn test_split_pays_each_destination() {
+        let mut ctx = SplitContext::new();
+        let (depositor, mint, contributor) = (ctx.depositor, ctx.token_mint, Pubkey::new_unique());
+        
+        ctx.withdraw(&[700, 300], &[(depositor, mint), (contributor, mint)], None).unwrap();
+        
+        let amounts: Vec<u64> = token_transfers(&take_invocations()).iter().map(|(_, amount)| *amount).collect();
+        assert_eq!(amounts, vec![700, 300]);
+        assert!(ctx.is_withdrawn());
+        assert_eq!(read_vault(&ctx.vault).total_locked, 0);
+    }
+
+    #[test]
+    fn test_splits_must_sum_exactly() {
+        let mut ctx = SplitContext::new();
+        let (depositor, mint) = (ctx.depositor, ctx.token_mint);
+        let two = [(depositor, mint), (depositor, mint)];
+        let invalid = Err(VaultError::InvalidSplits.into());
+        
+        assert_eq!(ctx.withdraw(&[700, 299], &two, None), invalid);
+        assert_eq!(ctx.withdraw(&[700, 301], &two, None), invalid);
+        assert_eq!(ctx.withdraw(&[AMOUNT, 0], &two, None), invalid);
+        assert_eq!(ctx.withdraw(&[u64::MAX, 1001], &two, None), invalid);
+        assert_eq!(ctx.withdraw(&[], &[], None), invalid);
+        let six = vec![(depositor, mint); MAX_WITHDRAW_SPLITS + 1];
+        assert_eq!(ctx.withdraw(&[100, 100, 100, 100, 100, 500], &six, None), invalid);
+        
+        assert!(take_invocations().is_empty());
+        assert!(!ctx.is_withdrawn());
+        ctx.withdraw(&[200; MAX_WITHDRAW_SPLITS], &six[..MAX_WITHDRAW_SPLITS], None).unwrap();
+    }
+
+    #[test]
+    fn test_every_destination_must_hold_the_mint() {
+        let mut ctx = SplitContext::new();
+        let (depositor, mint) = (ctx.depositor, ctx.token_mint);
+        
+        let result = ctx.withdraw(&[700, 300], &[(depositor, mint), (depositor, Pubkey::new_unique())], None);
+        
+        assert_eq!(result, Err(VaultError::TokenMintMismatch.into()));
+        assert!(!ctx.is_withdrawn());
+    }
+
+    #[test]
+    fn test_third_party_only_splits_need_the_flag_and_owner() {
+        let mut ctx = SplitContext::new();
+        let (owner, mint) = (ctx.owner, ctx.token_mint);
+        let strangers = [(Pubkey::new_unique(), mint), (Pubkey::new_unique(), mint)];
+        let needs_depositor = Err(VaultError::SplitNeedsDepositorDestination.into());
+        
+        // Neither the owner's signature alone nor the flag alone is enough
+        assert_eq!(ctx.withdraw(&[500, 500], &strangers, None), needs_depositor);
+        assert_eq!(ctx.withdraw(&[500, 500], &strangers, Some(owner)), needs_depositor);
+        ctx.allow_owner_approval();
+        assert_eq!(ctx.withdraw(&[500, 500], &strangers, None), needs_depositor);
+        assert_eq!(
+            ctx.withdraw(&[500, 500], &strangers, Some(Pubkey::new_unique())),
+            Err(VaultError::NotVaultOwner.into()),
+        );
+        assert!(!ctx.is_withdrawn());
+        
+        ctx.withdraw(&[500, 500], &strangers, Some(owner)).unwrap();
+        assert!(ctx.is_withdrawn());
+    }
+}